@@ -1,11 +1,96 @@
+use chrono::Utc;
 use eframe::egui;
-use std::path::PathBuf;
-use std::sync::mpsc;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
 
-use crate::clipboard::copy_to_clipboard;
-use crate::hotkeys::HotkeyEvent;
-use crate::storage::{FileStorage, SearchIndex, Snippet};
-use crate::ui::{AddWindowState, GetWindowState};
+use crate::clipboard::{clear_clipboard_after, copy_to_clipboard};
+use crate::config::{Config, ConfigProblem, FolderVault};
+use crate::diagnostics;
+use crate::keymap::Keymap;
+use crate::logging;
+#[cfg(all(target_os = "macos", feature = "tray"))]
+use crate::menu_bar::{MenuBar, MenuBarAction};
+use crate::platform::active_window::active_app;
+#[cfg(feature = "hotkeys")]
+use crate::platform::selection::{capture_selection, Captured};
+use crate::platform::Tray;
+#[cfg(any(windows, target_os = "macos"))]
+use crate::platform::window_effects;
+#[cfg(windows)]
+use crate::platform::windows::{set_jump_list, PINNED_TASKS};
+#[cfg(feature = "vault")]
+use crate::crypto::{Vault, VaultError};
+#[cfg(feature = "vault")]
+use age::secrecy::SecretString;
+#[cfg(feature = "ocr")]
+use crate::ocr;
+#[cfg(feature = "spellcheck")]
+use crate::spellcheck::{self, SpellcheckError, Spellchecker};
+#[cfg(feature = "ocr")]
+use crate::ui::capture_window::{CaptureAction, CaptureWindowState};
+use crate::hotkeys::{HotkeyEvent, ListenerHealth, ListenerStatus};
+#[cfg(feature = "hotkeys")]
+use crate::hotkeys::GET_HINT;
+use crate::storage::backup::{self, spawn_backups, BackupSettings};
+use crate::storage::janitor::spawn_janitor;
+use crate::page_title::fetch_page_title;
+use crate::platform_auth::request_verification;
+#[cfg(any(target_os = "macos", feature = "hotkeys"))]
+use crate::redaction::CaptureFilter;
+use crate::redaction::Redactor;
+use crate::session::{spawn_session_watcher, SessionEvent};
+#[cfg(feature = "sync")]
+use crate::sync::{self, Conflict, Snapshot};
+#[cfg(feature = "sync")]
+use crate::sync::lan::{DeviceKey, LanEvent, LanSync};
+#[cfg(feature = "sync")]
+use crate::sync::companion;
+#[cfg(feature = "sync")]
+use crate::sync::meta::ClockStore;
+#[cfg(feature = "s3")]
+use crate::sync::s3::{self, S3Sync};
+#[cfg(feature = "vault")]
+use crate::storage::archive::{export_archive, ARCHIVE_EXTENSION};
+use crate::storage::audit::{export_csv, AccessKind, AuditLog, AUDIT_FILE};
+use crate::storage::checklist;
+use crate::storage::markdown::export_markdown;
+#[cfg(feature = "vault")]
+use crate::storage::folders::FOLDER_KEY_FILE;
+use crate::storage::folders::normalize_folder;
+use crate::storage::title::link_url;
+use crate::storage::search_history::SEARCH_HISTORY_FILE;
+use crate::storage::review::stale_snippets;
+use crate::storage::search_provider;
+use crate::storage::starters::{STARTER_FOLDER, STARTER_SETS};
+use crate::storage::metrics::METRICS_FILE;
+use crate::storage::usage::{self, USAGE_FILE};
+use crate::undo::{UndoChange, UndoEntry, UndoStack};
+use crate::events::{EventBus, SnippetEvent};
+use crate::storage::{next_pin_order, sort_snippets, FileStorage, Metrics, SearchHistory, SearchProvider, Snippet, SnippetMeta, SnippetSource, StorageError, StorageWorker, UsageLog};
+use crate::ui::bulk_bar::BulkOp;
+use crate::ui::title_bar::{show_title_bar, TitleBarAction};
+use crate::ui::duplicates_window::DuplicatePlan;
+use crate::ui::fonts;
+#[cfg(feature = "vault")]
+use crate::ui::{ArchiveAction, ArchiveWindowState};
+#[cfg(feature = "sync")]
+use crate::ui::{ConflictAction, ConflictWindowState, PairingAction, PairingWindowState};
+#[cfg(all(feature = "sync", feature = "tray"))]
+use crate::ui::sync_status;
+use crate::ui::{show_config_problems, ConfigProblemsAction};
+use crate::ui::{AddWindowState, AuditAction, AuditWindowState, DuplicatesAction, DuplicatesWindowState, FolderImportAction, FolderImportWindowState, GetWindowAction, GetWindowState, HistoryAction, HistoryWindowState, ImportAction, ImportWindowState, ListDensity, LogAction, LogWindowState, NewSnippet, BackupEntry, RestoreAction, RestoreWindowState, ReviewAction, ReviewWindowState, SettingsAction, SettingsWindowState, StatsAction, StatsWindowState, TagsAction, TagsWindowState, Toasts, TrashAction, TrashWindowState};
+
+const JANITOR_INTERVAL: Duration = Duration::from_secs(60);
+/// The least time between two suggestions to review unused snippets.
+const REVIEW_REMINDER_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Used by ☰ → Review unused… while suggestions are off.
+const DEFAULT_REVIEW_MONTHS: u32 = 6;
+#[cfg(feature = "tray")]
+pub const TRAY_TOOLTIP: &str = "Trinket - Text Snippets";
 
 #[derive(Default)]
 pub enum AppMode {
@@ -13,92 +98,3343 @@ pub enum AppMode {
     Hidden,
     AddingSnippet,
     GettingSnippet,
+    Statistics,
+    History,
+    Duplicates,
+    Review,
+    Settings,
+    Tags,
+    Trash,
+    Audit,
+    Restore,
+    Import,
+    FolderImport,
+    Log,
+    #[cfg(feature = "vault")]
+    Archive,
+    #[cfg(feature = "sync")]
+    Pairing,
+    #[cfg(feature = "sync")]
+    Conflicts,
+    #[cfg(feature = "ocr")]
+    Capturing,
+}
+
+/// Applied to the app on the UI thread once a storage job is done.
+type StorageReply = Box<dyn FnOnce(&mut TrinketApp) + Send>;
+
+/// The library as read in one go, after keys changed or more was written
+/// than events describe.
+struct Library {
+    snippets: Vec<Snippet>,
+    folders: Vec<String>,
+    locked_folders: Vec<String>,
+}
+
+/// What a secret snippet was wanted for while the OS confirms the user.
+enum SecretUse {
+    Reveal(String),
+    /// Snippet ids and the text to copy.
+    Copy(Vec<String>, String),
+    Edit(String),
+    Duplicate(String),
+    History(String),
 }
 
 pub struct TrinketApp {
     mode: AppMode,
     add_window: AddWindowState,
     get_window: GetWindowState,
+    stats_window: StatsWindowState,
+    history_window: Option<HistoryWindowState>,
+    duplicates_window: Option<DuplicatesWindowState>,
+    review_window: Option<ReviewWindowState>,
+    /// When opening the get window last suggested a review.
+    review_reminded: Option<Instant>,
+    /// Until when the hotkeys and tray clicks are ignored and notifications
+    /// muted.
+    snoozed_until: Option<Instant>,
+    settings_window: Option<SettingsWindowState>,
+    /// What was wrong in the config file at startup, until dismissed.
+    config_problems: Vec<ConfigProblem>,
+    trash_window: Option<TrashWindowState>,
+    restore_window: Option<RestoreWindowState>,
+    import_window: Option<ImportWindowState>,
+    folder_import_window: Option<FolderImportWindowState>,
+    log_window: Option<LogWindowState>,
+    audit_window: Option<AuditWindowState>,
+    #[cfg(feature = "vault")]
+    archive_window: Option<ArchiveWindowState>,
+    #[cfg(feature = "sync")]
+    pairing_window: Option<PairingWindowState>,
+    #[cfg(feature = "sync")]
+    conflict_window: Option<ConflictWindowState>,
+    tags_window: TagsWindowState,
+    toasts: Toasts,
+    undo: UndoStack,
     
     snippets: Vec<Snippet>,
-    search_index: SearchIndex,
+    folders: Vec<String>,
+    search: Box<dyn SearchProvider>,
+    usage: UsageLog,
+    /// Changes to the library are published here rather than made to
+    /// `snippets` directly; `snippet_events` is the app's own subscription.
+    events: EventBus,
+    snippet_events: mpsc::Receiver<SnippetEvent>,
+    /// `None` unless `local_metrics` is on.
+    metrics: Option<Metrics>,
+    /// When the get window last opened, to time the copy made from it.
+    get_opened: Option<Instant>,
+    audit: AuditLog,
+    search_history: SearchHistory,
     
     hotkey_receiver: mpsc::Receiver<HotkeyEvent>,
-    storage: FileStorage,
+    hotkey_health: Arc<ListenerHealth>,
+    /// Kept for as long as the app runs; its tooltip shows the sync state
+    /// and any snooze, and on macOS it carries the menu bar dropdown.
+    /// `None` where the desktop shows no tray.
+    tray: Option<Tray>,
+    #[cfg(all(target_os = "macos", feature = "tray"))]
+    menu_bar: MenuBar,
+    /// Whether a hotkey or the tray icon can bring the app back once hidden.
+    /// If not, it is a plain window: hiding goes back to the get window and
+    /// closing quits.
+    resident: bool,
+    /// Pinned snippets last put in the taskbar jump list, as ids and titles.
+    #[cfg(windows)]
+    jump_list: Option<Vec<(String, String)>>,
+    /// Backdrop setting and dark mode last applied to the window, so they
+    /// are only applied again when one changes.
+    #[cfg(any(windows, target_os = "macos"))]
+    window_effects: Option<(bool, bool)>,
+    /// Whether the blurred backdrop is showing, so egui leaves it visible.
+    #[cfg(any(windows, target_os = "macos"))]
+    backdrop: bool,
+    #[cfg(feature = "tray")]
+    tray_tooltip: String,
+    expired_receiver: mpsc::Receiver<String>,
+    /// Shared with the backup thread so a settings change applies right away.
+    backup_settings: Arc<Mutex<BackupSettings>>,
+    backup_errors: mpsc::Receiver<String>,
+    session_receiver: mpsc::Receiver<SessionEvent>,
+    /// Shared with the janitor so a settings change applies right away.
+    trash_retention_days: Arc<AtomicU32>,
+    title_sender: mpsc::Sender<(String, String)>,
+    title_receiver: mpsc::Receiver<(String, String)>,
+    auth_sender: mpsc::Sender<Result<bool, String>>,
+    auth_receiver: mpsc::Receiver<Result<bool, String>>,
+    /// Text copied from the focused app by the selection hotkey.
+    #[cfg(feature = "hotkeys")]
+    selection_sender: mpsc::Sender<Result<Captured, String>>,
+    #[cfg(feature = "hotkeys")]
+    selection_receiver: mpsc::Receiver<Result<Captured, String>>,
+    /// What the selection hotkey and the Services menu don't save.
+    #[cfg(any(target_os = "macos", feature = "hotkeys"))]
+    capture_filter: CaptureFilter,
+    /// Waiting for the OS prompt to confirm the user.
+    pending_secret: Option<SecretUse>,
+    /// The snippet new snippets and captures are appended to, if any.
+    append_target: Option<String>,
+    /// The user confirmed it's them since the window was last shown.
+    secrets_unlocked: bool,
+    /// Running once LAN sync has been turned on.
+    #[cfg(feature = "sync")]
+    lan: Option<LanSync>,
+    #[cfg(feature = "s3")]
+    s3: Option<S3Sync>,
+    /// Snippets edited here and on a peer, kept until the user settles
+    /// them. A restart forgets them, but the next sync finds them again.
+    #[cfg(feature = "sync")]
+    sync_conflicts: Vec<Conflict>,
+    #[cfg(feature = "ocr")]
+    capture_window: Option<CaptureWindowState>,
+    #[cfg(feature = "ocr")]
+    ocr_sender: mpsc::Sender<Result<String, String>>,
+    #[cfg(feature = "ocr")]
+    ocr_receiver: mpsc::Receiver<Result<String, String>>,
+    /// The app focused when the screen was grabbed, for the recognized text.
+    #[cfg(feature = "ocr")]
+    capture_source: Option<SnippetSource>,
+    /// The dictionary being loaded for the add window.
+    #[cfg(feature = "spellcheck")]
+    dictionary_receiver: mpsc::Receiver<Result<Spellchecker, SpellcheckError>>,
+    /// Every read and write of the library goes through here, so none of
+    /// them holds up a frame.
+    storage: StorageWorker<StorageReply>,
+    /// The library folder, for the syncs that keep their own handle on it.
+    #[cfg_attr(not(feature = "sync"), allow(dead_code))]
+    storage_path: PathBuf,
+    config: Config,
 }
 
 impl TrinketApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>, hotkey_rx: mpsc::Receiver<HotkeyEvent>) -> Self {
-        let storage_path = dirs::data_local_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("trinket")
-            .join("snippets");
+    pub fn new(
+        ctx: &egui::Context,
+        hotkey_rx: mpsc::Receiver<HotkeyEvent>,
+        hotkey_health: Arc<ListenerHealth>,
+        // `None` when built without the tray.
+        tray: Option<Result<Tray, String>>,
+    ) -> Self {
+        let storage_path = trinket_core::data_dir().join("snippets");
         
-        let storage = FileStorage::new(storage_path).expect("Failed to create storage");
-        let snippets = storage.load_all_snippets().unwrap_or_default();
+        let config_path = Config::default_path();
+        let (config, config_problems) = Config::load_checked(&config_path);
+        
+        // The snippets show up once the storage thread has read them.
+        let storage = StorageWorker::spawn(
+            {
+                let (storage_path, config) = (storage_path.clone(), config.clone());
+                move || {
+                    if !config_path.exists() {
+                        if let Err(e) = config.save(&config_path) {
+                            log::warn!("Failed to write default config: {}", e);
+                        }
+                    }
+                    open_library(storage_path, &config)
+                }
+            },
+            {
+                let ctx = ctx.clone();
+                move || ctx.request_repaint()
+            },
+        );
+        let usage = open_usage(&storage_path);
+        let audit = AuditLog::new(storage_path.join(AUDIT_FILE));
+        let history_path = storage_path.join(SEARCH_HISTORY_FILE);
+        let search_history = SearchHistory::open(history_path.clone()).unwrap_or_else(|e| {
+            log::error!("Failed to read search history: {}", e);
+            SearchHistory::empty(history_path)
+        });
+        let (title_sender, title_receiver) = mpsc::channel();
+        let (auth_sender, auth_receiver) = mpsc::channel();
+        #[cfg(feature = "hotkeys")]
+        let (selection_sender, selection_receiver) = mpsc::channel();
+        #[cfg(feature = "ocr")]
+        let (ocr_sender, ocr_receiver) = mpsc::channel();
+        let trash_retention_days = Arc::new(AtomicU32::new(config.trash_retention_days));
+        let expired_receiver = spawn_janitor(storage_path.clone(), JANITOR_INTERVAL, trash_retention_days.clone());
+        let backup_settings = Arc::new(Mutex::new(config.backup_settings()));
+        let backup_errors = spawn_backups(storage_path.clone(), backup_settings.clone());
+        let (session_sender, session_receiver) = mpsc::channel();
+        spawn_session_watcher(session_sender);
+        let keymap = Keymap::new(config.keymap_profile, &config.keybindings);
+        ctx.set_zoom_factor(config.ui_scale);
+        ctx.send_viewport_cmd(window_level(&config));
+        fonts::add_rtl_fallback(ctx);
+        
+        #[cfg_attr(not(any(feature = "hotkeys", feature = "sync")), allow(unused_mut))]
+        let mut toasts = Toasts::default();
+        let tray = tray.and_then(|tray| tray.inspect_err(|e| {
+            log::warn!("No tray icon: {}", e);
+            #[cfg(feature = "hotkeys")]
+            toasts.info(format!("No tray icon: {}. Press {} to open trinket.", e, GET_HINT));
+        }).ok());
+        let resident = cfg!(feature = "hotkeys") || tray.is_some();
+        #[cfg(feature = "sync")]
+        let lan = config.lan_sync.then(|| start_lan_sync(&storage_path, &config, &mut toasts)).flatten();
+        #[cfg(feature = "s3")]
+        let s3 = start_s3_sync(&storage_path, &config, &mut toasts);
+        
+        let mut add_window = AddWindowState::new(keymap.clone());
+        add_window.set_redaction(config.redaction, Redactor::new(&config.redaction_patterns));
+        add_window.set_wrap(config.word_wrap);
+        let mut get_window = GetWindowState::new(keymap, list_density(&config), config.list_layout);
+        get_window.set_wrap(config.word_wrap);
+        get_window.set_table_layout(config.table_layout.clone());
+        let events = EventBus::new();
+        let snippet_events = events.subscribe();
         
         Self {
-            mode: AppMode::Hidden,
-            add_window: AddWindowState::new(),
-            get_window: GetWindowState::new(),
-            snippets,
-            search_index: SearchIndex::new(),
+            // Problems in the config are shown right away.
+            mode: if config_problems.is_empty() { AppMode::Hidden } else { AppMode::GettingSnippet },
+            add_window,
+            get_window,
+            stats_window: StatsWindowState,
+            history_window: None,
+            duplicates_window: None,
+            review_window: None,
+            review_reminded: None,
+            snoozed_until: None,
+            settings_window: None,
+            config_problems,
+            trash_window: None,
+            restore_window: None,
+            import_window: None,
+            folder_import_window: None,
+            log_window: None,
+            audit_window: None,
+            #[cfg(feature = "vault")]
+            archive_window: None,
+            #[cfg(feature = "sync")]
+            pairing_window: None,
+            #[cfg(feature = "sync")]
+            conflict_window: None,
+            tags_window: TagsWindowState::default(),
+            toasts,
+            undo: UndoStack::default(),
+            snippets: Vec::new(),
+            folders: Vec::new(),
+            search: search_provider::build(&config.search_providers),
+            usage,
+            events,
+            snippet_events,
+            metrics: config.local_metrics.then(open_metrics),
+            get_opened: None,
+            audit,
+            search_history,
             hotkey_receiver: hotkey_rx,
+            hotkey_health,
+            tray,
+            #[cfg(all(target_os = "macos", feature = "tray"))]
+            menu_bar: MenuBar::default(),
+            resident,
+            #[cfg(windows)]
+            jump_list: None,
+            #[cfg(any(windows, target_os = "macos"))]
+            window_effects: None,
+            #[cfg(any(windows, target_os = "macos"))]
+            backdrop: false,
+            #[cfg(feature = "tray")]
+            tray_tooltip: TRAY_TOOLTIP.to_string(),
+            expired_receiver,
+            backup_settings,
+            backup_errors,
+            session_receiver,
+            trash_retention_days,
+            title_sender,
+            title_receiver,
+            auth_sender,
+            auth_receiver,
+            #[cfg(feature = "hotkeys")]
+            selection_sender,
+            #[cfg(feature = "hotkeys")]
+            selection_receiver,
+            #[cfg(any(target_os = "macos", feature = "hotkeys"))]
+            capture_filter: CaptureFilter::new(&config.capture_excluded_apps, &config.capture_excluded_patterns),
+            pending_secret: None,
+            append_target: None,
+            secrets_unlocked: false,
+            #[cfg(feature = "sync")]
+            lan,
+            #[cfg(feature = "s3")]
+            s3,
+            #[cfg(feature = "sync")]
+            sync_conflicts: Vec::new(),
+            #[cfg(feature = "ocr")]
+            capture_window: None,
+            #[cfg(feature = "ocr")]
+            ocr_sender,
+            #[cfg(feature = "ocr")]
+            ocr_receiver,
+            #[cfg(feature = "ocr")]
+            capture_source: None,
+            #[cfg(feature = "spellcheck")]
+            dictionary_receiver: spellcheck::load_in_background(config.spellcheck_language.clone()),
             storage,
+            storage_path,
+            config,
         }
     }
 }
 
-impl eframe::App for TrinketApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if let Ok(event) = self.hotkey_receiver.try_recv() {
+impl TrinketApp {
+    /// Runs `job` on the storage thread, then `then` here with what it
+    /// returned, in a later frame.
+    fn with_storage<T: Send + 'static>(
+        &self,
+        job: impl FnOnce(&mut FileStorage) -> T + Send + 'static,
+        then: impl FnOnce(&mut TrinketApp, T) + Send + 'static,
+    ) {
+        self.storage.run(move |storage| {
+            let result = job(storage);
+            Box::new(move |app: &mut TrinketApp| then(app, result)) as StorageReply
+        });
+    }
+    
+    /// Applies the results of the storage jobs finished since the last
+    /// frame, in the order they were sent.
+    fn handle_storage_replies(&mut self) {
+        while let Some(reply) = self.storage.try_reply() {
+            reply(self);
+        }
+    }
+    
+    fn set_library(&mut self, library: Library) {
+        self.snippets = library.snippets;
+        self.folders = library.folders;
+        self.get_window.set_locked_folders(library.locked_folders);
+        self.get_window.invalidate();
+    }
+    
+    /// Reads the folder list again, after a snippet may have added one or
+    /// left one empty.
+    fn refresh_folders(&self) {
+        self.with_storage(
+            |storage| storage.list_folders().unwrap_or_default(),
+            |app, folders| app.folders = folders,
+        );
+    }
+    
+    /// Brings the app's view of the library up to date with the events
+    /// published since the last call: the snippet list and folders, the
+    /// usage counts and page titles to look up.
+    fn handle_snippet_events(&mut self) {
+        let mut changed = false;
+        while let Ok(event) = self.snippet_events.try_recv() {
             match event {
-                HotkeyEvent::Add => {
-                    self.mode = AppMode::AddingSnippet;
+                SnippetEvent::Created(snippet) => {
+                    if !snippet.folder.is_empty() && !self.folders.contains(&snippet.folder) {
+                        self.refresh_folders();
+                    }
+                    // Restored snippets still have the title looked up before.
+                    if snippet.meta.page_title.is_none() {
+                        self.fetch_title(&snippet);
+                    }
+                    self.snippets.push(snippet);
+                    changed = true;
                 }
-                HotkeyEvent::Get => {
-                    self.mode = AppMode::GettingSnippet;
-                    self.get_window.reset();
+                SnippetEvent::Updated(snippet) => match self.snippets.iter_mut().find(|s| s.id == snippet.id) {
+                    Some(existing) => {
+                        *existing = snippet;
+                        changed = true;
+                    }
+                    None => log::warn!("Updated snippet {} is not loaded", snippet.id),
+                },
+                SnippetEvent::Deleted { id } => {
+                    self.snippets.retain(|snippet| snippet.id != id);
+                    changed = true;
+                }
+                SnippetEvent::Copied { id } => {
+                    let path = self.usage.path().to_path_buf();
+                    self.with_storage(
+                        move |_| usage::write_copy(&path, &id).map(|at| (id, at)),
+                        |app, result| match result {
+                            Ok((id, at)) => app.usage.counted(id, at),
+                            Err(e) => log::warn!("Failed to record usage: {}", e),
+                        },
+                    );
+                }
+            }
+        }
+        if changed {
+            sort_snippets(&mut self.snippets);
+            self.get_window.invalidate();
+            if let Some(review_window) = &mut self.review_window {
+                review_window.refresh(&self.snippets, &self.usage);
+            }
+        }
+    }
+    
+    /// Counts something in today's metrics when they are kept. A failed
+    /// write only loses a count, so it is logged and otherwise ignored.
+    fn record_metric(&mut self, record: impl FnOnce(&mut Metrics) -> Result<(), std::io::Error>) {
+        if let Some(metrics) = &mut self.metrics {
+            if let Err(e) = record(metrics) {
+                log::warn!("Failed to record metrics: {}", e);
+            }
+        }
+    }
+    
+    /// Reopens the add window on a snippet the library refused as too
+    /// large, asking whether to save it anyway.
+    fn confirm_oversize(&mut self, new_snippet: NewSnippet, size: usize, limit: usize) {
+        self.add_window.confirm_oversize(new_snippet, size, limit);
+        self.mode = AppMode::AddingSnippet;
+    }
+    
+    /// Applies `change` to the metadata of the snippet at `index` as it is
+    /// on disk, then shows the change.
+    fn save_meta(&mut self, index: usize, change: impl FnOnce(&mut SnippetMeta) + Send + 'static) {
+        let mut snippet = self.snippets[index].clone();
+        self.with_storage(
+            move |storage| storage.update_meta(&mut snippet, change).map(|()| snippet),
+            |app, result| match result {
+                Ok(snippet) => app.events.publish(SnippetEvent::Updated(snippet)),
+                Err(e) => {
+                    log::error!("Failed to update snippet metadata: {}", e);
+                    app.toasts.error(format!("Could not update snippet: {}", e));
+                }
+            },
+        );
+    }
+    
+    fn save_new(&mut self, new_snippet: NewSnippet) {
+        if let Some(id) = self.append_target.clone() {
+            match self.snippets.iter().find(|s| s.id == id) {
+                // A secret stays a snippet of its own rather than landing in
+                // one that isn't masked.
+                Some(target) if new_snippet.meta.secret && !target.meta.secret => {}
+                Some(target) => {
+                    self.append(target.clone(), new_snippet.content);
+                    return;
+                }
+                None => {
+                    self.set_append_target(None);
+                    self.toasts.info("The snippet being appended to is gone; saved a new one");
                 }
             }
         }
+        self.with_storage(
+            move |storage| {
+                let meta = new_snippet.meta.clone();
+                let result = if new_snippet.over_limit {
+                    storage.save_snippet_anyway(&new_snippet.content, &new_snippet.folder, meta)
+                } else {
+                    storage.save_snippet(&new_snippet.content, &new_snippet.folder, meta)
+                };
+                result.map_err(|e| (e, new_snippet))
+            },
+            |app, result| match result {
+                Ok(snippet) => {
+                    app.events.publish(SnippetEvent::Created(snippet));
+                    app.record_metric(Metrics::record_capture);
+                    log::info!("Snippet saved successfully");
+                }
+                Err((StorageError::TooLarge { size, limit }, new_snippet)) => app.confirm_oversize(new_snippet, size, limit),
+                Err((e, _)) => {
+                    log::error!("Failed to save snippet: {}", e);
+                    app.toasts.error(format!("Could not save snippet: {}", e));
+                }
+            },
+        );
+    }
+    
+    /// Saves the starter set at `index`, skipping snippets already imported.
+    fn import_starters(&mut self, index: usize) {
+        self.with_storage(
+            move |storage| {
+                let set = &STARTER_SETS[index];
+                storage.import_starters(set).map(|saved| (saved, set.name, set.folder))
+            },
+            |app, result| match result {
+                Ok((saved, _, _)) if saved.is_empty() => app.toasts.info("All of these starter snippets are already in the library"),
+                Ok((saved, name, folder)) => {
+                    app.toasts.info(format!("Added {} {} snippets to {}/{}", saved.len(), name, STARTER_FOLDER, folder));
+                    for snippet in saved {
+                        app.events.publish(SnippetEvent::Created(snippet));
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to import starter snippets: {}", e);
+                    app.toasts.error(format!("Could not import the starter snippets: {}", e));
+                }
+            },
+        );
+    }
+    
+    /// Adds `content` to the end of `target`, in place of a new snippet.
+    fn append(&mut self, mut target: Snippet, content: String) {
+        self.with_storage(
+            move |storage| storage.append_to_snippet(&mut target, &content).map(|()| target),
+            |app, result| match result {
+                Ok(snippet) => {
+                    app.toasts.info(format!("Appended to “{}”", snippet.safe_title()));
+                    app.events.publish(SnippetEvent::Updated(snippet));
+                    app.record_metric(Metrics::record_capture);
+                }
+                Err(e) => {
+                    log::error!("Failed to append to snippet: {}", e);
+                    app.toasts.error(format!("Could not append to the snippet: {}", e));
+                }
+            },
+        );
+    }
+    
+    /// Where new snippets and captures go: the end of the snippet with `id`,
+    /// or new snippets of their own when `None`.
+    fn set_append_target(&mut self, id: Option<String>) {
+        let title = id.as_ref()
+            .and_then(|id| self.snippets.iter().find(|s| &s.id == id))
+            .map(|snippet| snippet.safe_title().to_string());
+        self.add_window.set_append_target(title);
+        self.get_window.set_append_target(id.clone());
+        self.append_target = id;
+    }
+    
+    /// Applies edited content plus the editor's title, notes and flags; other
+    /// metadata such as creation time and lock state is kept.
+    fn apply_edit(&mut self, id: &str, edit: NewSnippet) {
+        let Some(mut snippet) = self.snippets.iter().find(|s| s.id == id).cloned() else {
+            log::warn!("Edited snippet {} no longer exists", id);
+            return;
+        };
+        let meta = &edit.meta;
+        snippet.meta.secret = meta.secret;
+        snippet.meta.notes = meta.notes.clone();
+        snippet.meta.apps = meta.apps.clone();
+        snippet.meta.checklist = meta.checklist;
+        snippet.meta.spellcheck = meta.spellcheck;
+        snippet.meta.title = meta.title.clone();
+        let link_changed = link_url(&snippet.content) != link_url(&edit.content);
+        if link_changed {
+            snippet.meta.page_title = None;
+        }
+        self.with_storage(
+            move |storage| {
+                let result = if edit.over_limit {
+                    storage.update_snippet_anyway(&mut snippet, &edit.content)
+                } else {
+                    storage.update_snippet(&mut snippet, &edit.content)
+                };
+                result.map(|()| snippet).map_err(|e| (e, edit))
+            },
+            move |app, result| match result {
+                Ok(snippet) => {
+                    if link_changed {
+                        app.fetch_title(&snippet);
+                    }
+                    app.events.publish(SnippetEvent::Updated(snippet));
+                    log::info!("Snippet updated");
+                }
+                Err((StorageError::TooLarge { size, limit }, edit)) => app.confirm_oversize(edit, size, limit),
+                Err((e, _)) => {
+                    log::error!("Failed to update snippet: {}", e);
+                    app.toasts.error(format!("Could not update snippet: {}", e));
+                }
+            },
+        );
+    }
+    
+    /// Puts `text`, built from the snippets at `indices`, on the clipboard
+    /// and hides the window, unless it's kept open for copying more.
+    fn copy(&mut self, indices: &[usize], mut text: String) {
+        let ids: Vec<String> = indices.iter().map(|&i| self.snippets[i].id.clone()).collect();
+        if indices.iter().any(|&i| self.needs_confirmation(i)) {
+            self.confirm_secret(SecretUse::Copy(ids, text));
+            return;
+        }
+        let secrets: Vec<String> = indices.iter()
+            .filter(|&&i| self.snippets[i].meta.secret)
+            .map(|&i| self.snippets[i].id.clone())
+            .collect();
+        if secrets.is_empty() {
+            self.copy_logged(indices, text);
+            return;
+        }
+        // Secrets reach the clipboard only once the access log has them.
+        let audit = self.audit.clone();
+        self.with_storage(
+            move |_| secrets.iter().try_for_each(|id| audit.record(id, AccessKind::Copy)),
+            move |app, result| match result {
+                Ok(()) => {
+                    let indices: Vec<usize> = ids.iter()
+                        .filter_map(|id| app.snippets.iter().position(|snippet| &snippet.id == id))
+                        .collect();
+                    app.copy_logged(&indices, text);
+                }
+                Err(e) => {
+                    text.zeroize();
+                    log::error!("Failed to write access log: {}", e);
+                    app.toasts.error(format!("Could not write the access log: {}", e));
+                }
+            },
+        );
+    }
+    
+    /// Does the copy once any secrets in it are in the access log.
+    fn copy_logged(&mut self, indices: &[usize], mut text: String) {
+        if let Err(e) = copy_to_clipboard(&text) {
+            // Stay open so the user can simply try again.
+            log::error!("Failed to copy to clipboard: {}", e);
+            self.toasts.error(format!("Could not copy to clipboard: {}", e));
+            return;
+        }
         
-        match self.mode {
-            AppMode::Hidden => {
-                // Window is controlled by hotkey events
+        log::info!("Snippet copied to clipboard");
+        let has_secret = indices.iter().any(|&i| self.snippets[i].meta.secret);
+        if has_secret && self.config.secret_clipboard_clear_secs > 0 {
+            clear_clipboard_after(text, Duration::from_secs(self.config.secret_clipboard_clear_secs));
+        } else if has_secret {
+            text.zeroize();
+        }
+        for &i in indices {
+            self.events.publish(SnippetEvent::Copied { id: self.snippets[i].id.clone() });
+        }
+        // A search that led to a copy is worth recalling later.
+        if self.search_history.record(self.get_window.search_query()) {
+            let history = self.search_history.clone();
+            self.with_storage(
+                move |_| history.save(),
+                |_, result| {
+                    if let Err(e) = result {
+                        log::warn!("Failed to record search: {}", e);
+                    }
+                },
+            );
+        }
+        let latency = self.get_opened.take()
+            .filter(|_| matches!(self.mode, AppMode::GettingSnippet))
+            .map(|opened| opened.elapsed());
+        self.record_metric(|metrics| metrics.record_copy(latency));
+        if !self.get_window.search_query().trim().is_empty() {
+            self.record_metric(Metrics::record_search);
+        }
+        if self.get_window.is_sticky() && matches!(self.mode, AppMode::GettingSnippet) {
+            for &i in indices {
+                self.get_window.mark_copied(self.snippets[i].id.clone());
             }
-            AppMode::AddingSnippet => {
-                if let Some(content) = self.add_window.show(ctx) {
-                    if !content.is_empty() {
-                        match self.storage.save_snippet(&content) {
-                            Ok(snippet) => {
-                                self.snippets.insert(0, snippet);
-                                log::info!("Snippet saved successfully");
-                            }
+        } else {
+            self.mode = AppMode::Hidden;
+        }
+    }
+    
+    /// True when the snippet at `index` is a secret the user hasn't
+    /// confirmed access to yet.
+    fn needs_confirmation(&self, index: usize) -> bool {
+        self.config.confirm_secrets && !self.secrets_unlocked && self.snippets[index].meta.secret
+    }
+    
+    /// Shows the OS verification prompt; `wanted` goes ahead once it is
+    /// confirmed.
+    fn confirm_secret(&mut self, wanted: SecretUse) {
+        if self.pending_secret.is_none() {
+            request_verification("unlock secret snippets".to_string(), self.auth_sender.clone());
+        }
+        self.pending_secret = Some(wanted);
+    }
+    
+    fn set_secrets_unlocked(&mut self, unlocked: bool) {
+        self.secrets_unlocked = unlocked;
+        if !unlocked {
+            self.get_window.hide_revealed();
+        }
+    }
+    
+    /// Shows a secret snippet's content once the access is logged.
+    fn reveal_secret(&mut self, id: String) {
+        let audit = self.audit.clone();
+        self.with_storage(
+            move |_| audit.record(&id, AccessKind::Reveal).map(|()| id),
+            |app, result| match result {
+                Ok(id) => app.get_window.reveal(id),
+                Err(e) => {
+                    log::error!("Failed to write access log: {}", e);
+                    app.toasts.error(format!("Could not write the access log: {}", e));
+                }
+            },
+        );
+    }
+    
+    fn use_secret(&mut self, wanted: SecretUse) {
+        let position = |id: &str| self.snippets.iter().position(|snippet| snippet.id == id);
+        match wanted {
+            SecretUse::Reveal(id) => self.reveal_secret(id),
+            SecretUse::Copy(ids, text) => {
+                let indices: Vec<usize> = ids.iter().filter_map(|id| position(id)).collect();
+                self.copy(&indices, text);
+            }
+            SecretUse::Edit(id) => {
+                if let Some(index) = position(&id) {
+                    self.add_window.edit(&self.snippets[index]);
+                    self.mode = AppMode::AddingSnippet;
+                }
+            }
+            SecretUse::Duplicate(id) => {
+                if let Some(index) = position(&id) {
+                    self.duplicate(index);
+                }
+            }
+            SecretUse::History(id) => {
+                if let Some(index) = position(&id) {
+                    self.open_history(index);
+                }
+            }
+        }
+    }
+    
+    /// Saves a copy of a snippet under a new id, titled as a copy, then
+    /// opens the copy in the editor. What describes the content carries
+    /// over; the dates, pin, lock and where it was captured from don't.
+    fn duplicate(&mut self, index: usize) {
+        let original = &self.snippets[index];
+        let content = original.content.clone();
+        let folder = original.folder.clone();
+        let meta = SnippetMeta {
+            title: Some(format!("{} (copy)", original.title)),
+            language: original.meta.language,
+            secret: original.meta.secret,
+            notes: original.meta.notes.clone(),
+            checklist: original.meta.checklist,
+            spellcheck: original.meta.spellcheck,
+            page_title: original.meta.page_title.clone(),
+            tags: original.meta.tags.clone(),
+            apps: original.meta.apps.clone(),
+            ..SnippetMeta::default()
+        };
+        self.with_storage(
+            // The original already got past the size limit.
+            move |storage| storage.save_snippet_anyway(&content, &folder, meta),
+            |app, result| match result {
+                Ok(snippet) => {
+                    app.add_window.edit(&snippet);
+                    app.mode = AppMode::AddingSnippet;
+                    app.events.publish(SnippetEvent::Created(snippet));
+                }
+                Err(e) => {
+                    log::error!("Failed to duplicate snippet: {}", e);
+                    app.toasts.error(format!("Could not duplicate the snippet: {}", e));
+                }
+            },
+        );
+    }
+    
+    fn update_checklist(&mut self, index: usize, content: String) {
+        let mut snippet = self.snippets[index].clone();
+        self.with_storage(
+            move |storage| storage.update_checklist(&mut snippet, &content).map(|()| snippet),
+            |app, result| match result {
+                Ok(snippet) => app.events.publish(SnippetEvent::Updated(snippet)),
+                Err(e) => {
+                    log::error!("Failed to save checklist: {}", e);
+                    app.toasts.error(format!("Could not save checklist: {}", e));
+                }
+            },
+        );
+    }
+    
+    /// Starts a page title lookup for link snippets without a title override.
+    fn fetch_title(&self, snippet: &Snippet) {
+        if !self.config.fetch_link_titles || snippet.meta.secret || snippet.meta.title.is_some() {
+            return;
+        }
+        if let Some(url) = link_url(&snippet.content) {
+            fetch_page_title(snippet.id.clone(), url.to_string(), self.title_sender.clone());
+        }
+    }
+    
+    fn apply_page_title(&mut self, id: &str, title: String) {
+        let Some(mut snippet) = self.snippets.iter().find(|s| s.id == id).cloned() else {
+            return;
+        };
+        self.with_storage(
+            move |storage| storage.update_meta(&mut snippet, |meta| meta.page_title = Some(title)).map(|()| snippet),
+            |app, result| match result {
+                Ok(snippet) => app.events.publish(SnippetEvent::Updated(snippet)),
+                Err(e) => log::warn!("Failed to save page title: {}", e),
+            },
+        );
+    }
+    
+    fn open_history(&mut self, index: usize) {
+        let id = self.snippets[index].id.clone();
+        self.with_storage(
+            {
+                let id = id.clone();
+                move |storage| storage.versions(&id).map(|versions| {
+                    versions.into_iter()
+                        .filter_map(|version| match storage.read_version(&version) {
+                            Ok(content) => Some((version, content)),
                             Err(e) => {
-                                log::error!("Failed to save snippet: {}", e);
+                                log::warn!("Skipping unreadable version {}: {}", version.path.display(), e);
+                                None
+                            }
+                        })
+                        .collect()
+                })
+            },
+            move |app, result| match result {
+                Ok(versions) => {
+                    let Some(snippet) = app.snippets.iter().find(|snippet| snippet.id == id) else {
+                        return;
+                    };
+                    app.history_window = Some(HistoryWindowState::new(snippet, versions));
+                    app.mode = AppMode::History;
+                }
+                Err(e) => {
+                    log::error!("Failed to read snippet history: {}", e);
+                    app.toasts.error(format!("Could not read history: {}", e));
+                }
+            },
+        );
+    }
+    
+    fn update_history(&mut self, ctx: &egui::Context) {
+        let Some(history_window) = &mut self.history_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match history_window.show(ctx) {
+            Some(HistoryAction::Restore(content)) => {
+                let id = history_window.snippet_id().to_string();
+                let Some(index) = self.snippets.iter().position(|s| s.id == id) else {
+                    self.mode = AppMode::GettingSnippet;
+                    return;
+                };
+                let mut snippet = self.snippets[index].clone();
+                self.with_storage(
+                    move |storage| storage.update_snippet(&mut snippet, &content).map(|()| snippet),
+                    move |app, result| match result {
+                        // Reopen so the restored content shows as current
+                        // and the replaced one appears as a version.
+                        Ok(snippet) => {
+                            app.events.publish(SnippetEvent::Updated(snippet));
+                            app.handle_snippet_events();
+                            if let Some(index) = app.snippets.iter().position(|s| s.id == id) {
+                                app.open_history(index);
                             }
                         }
+                        Err(e) => {
+                            log::error!("Failed to restore version: {}", e);
+                            app.toasts.error(format!("Could not restore version: {}", e));
+                        }
+                    },
+                );
+            }
+            Some(HistoryAction::Back) => {
+                self.history_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    fn update_tags(&mut self, ctx: &egui::Context) {
+        match self.tags_window.show(ctx, &self.snippets) {
+            Some(TagsAction::Rename { from, to }) => {
+                let label = format!("renaming #{} to #{}", from, to);
+                self.retag(label, &[from], Some(&to));
+            }
+            Some(TagsAction::Merge { from, into }) => {
+                let label = format!("merging {} tags into #{}", from.len(), into);
+                self.retag(label, &from, Some(&into));
+            }
+            Some(TagsAction::Delete(tag)) => {
+                let label = format!("deleting #{}", tag);
+                self.retag(label, &[tag], None);
+            }
+            Some(TagsAction::Back) => self.mode = AppMode::GettingSnippet,
+            None => {}
+        }
+    }
+    
+    /// Rewrites tags across the library as one undoable change.
+    fn retag(&mut self, label: String, from: &[String], to: Option<&str>) {
+        let mut tagged: Vec<Snippet> = self.snippets.iter()
+            .filter(|snippet| snippet.meta.tags.iter().any(|tag| from.contains(tag)))
+            .cloned()
+            .collect();
+        let (from, to) = (from.to_vec(), to.map(str::to_string));
+        self.with_storage(
+            move |storage| storage.rewrite_tags(&mut tagged, &from, to.as_deref()).map(|written| (tagged, written)),
+            |app, result| match result {
+                Ok((tagged, written)) => {
+                    for snippet in tagged {
+                        app.events.publish(SnippetEvent::Updated(snippet));
                     }
-                    
-                    self.mode = AppMode::Hidden;
+                    let changes = written.into_iter()
+                        .map(|(id, before)| UndoChange::Meta { id, before: Box::new(before) })
+                        .collect();
+                    app.undo.push(UndoEntry { label, changes });
+                }
+                Err(e) => {
+                    log::error!("Failed to rewrite tags: {}", e);
+                    app.toasts.error(format!("Could not update tags, nothing was changed: {}", e));
+                }
+            },
+        );
+    }
+    
+    fn open_trash(&mut self) {
+        self.with_storage(
+            |storage| storage.trashed_snippets(),
+            |app, result| match result {
+                Ok(snippets) => {
+                    app.trash_window = Some(TrashWindowState::new(snippets, app.config.trash_retention_days));
+                    app.mode = AppMode::Trash;
+                }
+                Err(e) => {
+                    log::error!("Failed to read trash: {}", e);
+                    app.toasts.error(format!("Could not read trash: {}", e));
                 }
+            },
+        );
+    }
+    
+    fn open_audit(&mut self) {
+        let audit = self.audit.clone();
+        self.with_storage(
+            move |_| audit.records(),
+            |app, result| match result {
+                Ok(records) => {
+                    app.audit_window = Some(AuditWindowState::new(records));
+                    app.mode = AppMode::Audit;
+                }
+                Err(e) => {
+                    log::error!("Failed to read access log: {}", e);
+                    app.toasts.error(format!("Could not read the access log: {}", e));
+                }
+            },
+        );
+    }
+    
+    fn update_audit(&mut self, ctx: &egui::Context) {
+        let Some(audit_window) = &mut self.audit_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match audit_window.show(ctx, &self.snippets) {
+            Some(AuditAction::Export) => {
+                let path = dirs::download_dir()
+                    .or_else(dirs::home_dir)
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(format!("trinket-access-log-{}.csv", chrono::Local::now().format("%Y-%m-%d")));
+                let records = audit_window.records().to_vec();
+                self.with_storage(
+                    move |_| export_csv(&records, &path).map(|()| path),
+                    |app, result| match result {
+                        Ok(path) => app.toasts.info(format!("Exported to {}", path.display())),
+                        Err(e) => {
+                            log::error!("Failed to export access log: {}", e);
+                            app.toasts.error(format!("Could not export the access log: {}", e));
+                        }
+                    },
+                );
             }
-            AppMode::GettingSnippet => {
-                if let Some(content) = self.get_window.show(ctx, &self.snippets) {
-                    if !content.is_empty() {
-                        if let Err(e) = copy_to_clipboard(&content) {
-                            log::error!("Failed to copy to clipboard: {}", e);
-                        } else {
-                            log::info!("Snippet copied to clipboard");
+            Some(AuditAction::Back) => {
+                self.audit_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    /// Writes the listed snippets to a Markdown file in Downloads.
+    fn export_results(&mut self, indices: &[usize]) {
+        let path = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(format!("trinket-snippets-{}.md", chrono::Local::now().format("%Y-%m-%d")));
+        let ids: Vec<String> = indices.iter().map(|&i| self.snippets[i].id.clone()).collect();
+        self.with_storage(
+            move |storage| {
+                let mut loaded: HashMap<String, Snippet> = storage.load_all_snippets()?
+                    .into_iter()
+                    .map(|snippet| (snippet.id.clone(), snippet))
+                    .collect();
+                // In the order they were listed.
+                let snippets: Vec<Snippet> = ids.iter().filter_map(|id| loaded.remove(id)).collect();
+                export_markdown(&snippets, &path).map(|count| (count, ids.len(), path))
+            },
+            |app, result| match result {
+                Ok((count, listed, path)) if count < listed => app.toasts.info(format!(
+                    "Exported {} snippets to {}; secrets were left out",
+                    count,
+                    path.display(),
+                )),
+                Ok((count, _, path)) => app.toasts.info(format!("Exported {} snippets to {}", count, path.display())),
+                Err(e) => {
+                    log::error!("Failed to export snippets: {}", e);
+                    app.toasts.error(format!("Could not export the snippets: {}", e));
+                }
+            },
+        );
+    }
+    
+    fn open_log(&mut self) {
+        self.with_storage(
+            |_| logging::recent_lines(),
+            |app, result| match result {
+                Ok(lines) => {
+                    app.log_window = Some(LogWindowState::new(lines));
+                    app.mode = AppMode::Log;
+                }
+                Err(e) => {
+                    log::error!("Failed to read the log: {}", e);
+                    app.toasts.error(format!("Could not read the log: {}", e));
+                }
+            },
+        );
+    }
+    
+    fn update_log(&mut self, ctx: &egui::Context) {
+        let Some(log_window) = &mut self.log_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match log_window.show(ctx) {
+            Some(LogAction::Refresh) => self.with_storage(
+                |_| logging::recent_lines(),
+                |app, result| match result {
+                    Ok(lines) => {
+                        if let Some(log_window) = &mut app.log_window {
+                            log_window.set_lines(lines);
                         }
                     }
-                    
-                    self.mode = AppMode::Hidden;
+                    Err(e) => {
+                        log::error!("Failed to read the log: {}", e);
+                        app.toasts.error(format!("Could not read the log: {}", e));
+                    }
+                },
+            ),
+            Some(LogAction::Copy(text)) => match copy_to_clipboard(&text) {
+                Ok(()) => self.toasts.info("Copied the log lines"),
+                Err(e) => {
+                    log::error!("Failed to copy to clipboard: {}", e);
+                    self.toasts.error(format!("Could not copy to clipboard: {}", e));
                 }
+            },
+            Some(LogAction::CollectDiagnostics) => self.collect_diagnostics(),
+            Some(LogAction::Back) => {
+                self.log_window = None;
+                self.mode = AppMode::GettingSnippet;
             }
+            None => {}
         }
-        
-        ctx.request_repaint();
     }
-}
\ No newline at end of file
+    
+    /// Writes a diagnostics bundle to Downloads on the storage thread.
+    fn collect_diagnostics(&mut self) {
+        let path = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(format!("trinket-diagnostics-{}.zip", chrono::Local::now().format("%Y-%m-%d-%H%M%S")));
+        let hotkeys = match self.hotkey_health.status() {
+            ListenerStatus::Running => "running".to_string(),
+            ListenerStatus::Restarted { count, last_error } => format!("restarted {} times, last after: {}", count, last_error),
+            ListenerStatus::Stopped { last_error } => format!("stopped after: {}", last_error),
+        };
+        let state = vec![
+            format!("Hotkey listener: {}", hotkeys),
+            format!("Tray icon: {}", if self.tray.is_some() { "shown" } else { "none" }),
+            format!("Snoozed: {}", if self.snoozed_until.is_some() { "yes" } else { "no" }),
+        ];
+        let config = self.config.clone();
+        self.with_storage(
+            move |storage| diagnostics::write_bundle(&path, &config, storage.statistics(), &state).map(|()| path),
+            |app, result| match result {
+                Ok(path) => app.toasts.info(format!("Saved {}; look it over before attaching it to a report", path.display())),
+                Err(e) => {
+                    log::error!("Failed to collect diagnostics: {}", e);
+                    app.toasts.error(format!("Could not collect diagnostics: {}", e));
+                }
+            },
+        );
+    }
+    
+    #[cfg(feature = "vault")]
+    fn update_archive(&mut self, ctx: &egui::Context) {
+        let Some(archive_window) = &mut self.archive_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        let secrets = self.snippets.iter().filter(|snippet| snippet.meta.secret).count();
+        match archive_window.show(ctx, self.snippets.len(), secrets) {
+            Some(ArchiveAction::Export(path, passphrase, include_secrets)) => self.export_archive(&path, passphrase, include_secrets),
+            Some(ArchiveAction::Import(path, passphrase)) => self.import_archive(&path, passphrase),
+            Some(ArchiveAction::Back) => {
+                self.archive_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    #[cfg(feature = "vault")]
+    fn export_archive(&mut self, path: &Path, passphrase: String, include_secrets: bool) {
+        let ids: HashSet<String> = self.snippets.iter().map(|snippet| snippet.id.clone()).collect();
+        let audit = self.audit.clone();
+        let path = path.to_path_buf();
+        self.with_storage(
+            move |storage| {
+                let mut snippets = storage.load_all_snippets()?;
+                snippets.retain(|snippet| ids.contains(&snippet.id));
+                // As with copies, secrets aren't exported without a record of it.
+                if include_secrets {
+                    for snippet in snippets.iter().filter(|snippet| snippet.meta.secret) {
+                        audit.record(&snippet.id, AccessKind::Export)?;
+                    }
+                }
+                export_archive(&snippets, &path, SecretString::from(passphrase), include_secrets).map(|written| (written.len(), path))
+            },
+            |app, result| match result {
+                Ok((count, path)) => {
+                    app.toasts.info(format!("Exported {} snippets to {}", count, path.display()));
+                    app.archive_window = None;
+                    app.mode = AppMode::GettingSnippet;
+                }
+                Err(e) => {
+                    log::error!("Failed to export archive: {}", e);
+                    app.toasts.error(format!("Could not export the archive: {}", e));
+                }
+            },
+        );
+    }
+    
+    #[cfg(feature = "vault")]
+    fn import_archive(&mut self, path: &Path, passphrase: String) {
+        let path = path.to_path_buf();
+        self.with_storage(
+            move |storage| {
+                let existing = storage.load_all_snippets().unwrap_or_default();
+                let result = storage.import_archive(&path, SecretString::from(passphrase), &existing);
+                // Snippets saved before a failure are kept, so reload either way.
+                (result, read_library(storage))
+            },
+            |app, (result, library)| {
+                app.set_library(library);
+                match result {
+                    Ok((imported, skipped)) => {
+                        let message = match skipped {
+                            0 => format!("Imported {} snippets", imported),
+                            _ => format!("Imported {} snippets, skipped {} already in the library", imported, skipped),
+                        };
+                        app.toasts.info(message);
+                        app.archive_window = None;
+                        app.mode = AppMode::GettingSnippet;
+                    }
+                    Err(e) => {
+                        log::error!("Failed to import archive: {}", e);
+                        app.toasts.error(format!("Could not import the archive: {}", e));
+                    }
+                }
+            },
+        );
+    }
+    
+    fn update_import(&mut self, ctx: &egui::Context) {
+        let Some(import_window) = &mut self.import_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match import_window.show(ctx) {
+            Some(ImportAction::Import(source, path)) => {
+                import_window.set_importing(true);
+                self.with_storage(
+                    move |storage| {
+                        let result = storage.import_from(source, &path);
+                        // Snippets saved before a failure are kept; reload to show them.
+                        let library = result.is_err().then(|| read_library(storage));
+                        (result, library)
+                    },
+                    move |app, (result, library)| {
+                        if let Some(import_window) = &mut app.import_window {
+                            import_window.set_importing(false);
+                        }
+                        if let Some(library) = library {
+                            app.set_library(library);
+                        }
+                        match result {
+                            Ok(saved) if saved.is_empty() => {
+                                app.toasts.info(format!("Nothing new to import from {}", source.label()));
+                            }
+                            Ok(saved) => {
+                                app.toasts.info(format!("Imported {} snippets from {} into {}", saved.len(), source.label(), source.folder()));
+                                for snippet in saved {
+                                    app.events.publish(SnippetEvent::Created(snippet));
+                                }
+                                app.import_window = None;
+                                app.mode = AppMode::GettingSnippet;
+                            }
+                            Err(e) => {
+                                log::error!("Failed to import from {}: {}", source.label(), e);
+                                app.toasts.error(format!("Could not import from {}: {}", source.label(), e));
+                            }
+                        }
+                    },
+                );
+            }
+            Some(ImportAction::Back) => {
+                self.import_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    fn update_folder_import(&mut self, ctx: &egui::Context) {
+        let Some(folder_import_window) = &mut self.folder_import_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match folder_import_window.show(ctx) {
+            Some(FolderImportAction::Preview(root, subfolders)) => {
+                folder_import_window.set_busy(true);
+                let max_bytes = self.config.max_snippet_bytes;
+                self.with_storage(
+                    move |storage| storage.plan_folder_import(&root, subfolders, max_bytes),
+                    |app, result| {
+                        let Some(folder_import_window) = &mut app.folder_import_window else {
+                            return;
+                        };
+                        match result {
+                            Ok(plan) => folder_import_window.set_plan(Some(plan)),
+                            Err(e) => {
+                                folder_import_window.set_plan(None);
+                                log::error!("Failed to read the folder to import: {}", e);
+                                app.toasts.error(format!("Could not read the folder: {}", e));
+                            }
+                        }
+                    },
+                );
+            }
+            Some(FolderImportAction::Import(plan)) => {
+                self.with_storage(
+                    move |storage| {
+                        let folder = plan.folder.clone();
+                        let result = storage.import_folder(plan);
+                        // Snippets saved before a failure are kept; reload to show them.
+                        let library = result.is_err().then(|| read_library(storage));
+                        (result, library, folder)
+                    },
+                    |app, (result, library, folder)| {
+                        if let Some(library) = library {
+                            app.set_library(library);
+                        }
+                        match result {
+                            Ok(saved) => {
+                                app.toasts.info(format!("Imported {} snippets into {}", saved.len(), folder));
+                                for snippet in saved {
+                                    app.events.publish(SnippetEvent::Created(snippet));
+                                }
+                                app.folder_import_window = None;
+                                app.mode = AppMode::GettingSnippet;
+                            }
+                            Err(e) => {
+                                if let Some(folder_import_window) = &mut app.folder_import_window {
+                                    folder_import_window.set_busy(false);
+                                }
+                                log::error!("Failed to import folder: {}", e);
+                                app.toasts.error(format!("Could not import the folder: {}", e));
+                            }
+                        }
+                    },
+                );
+            }
+            Some(FolderImportAction::Back) => {
+                self.folder_import_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    fn update_trash(&mut self, ctx: &egui::Context) {
+        let Some(trash_window) = &mut self.trash_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match trash_window.show(ctx) {
+            Some(TrashAction::Restore(id)) => self.with_storage(
+                move |storage| storage.restore_from_trash(&id).map(|snippet| (id, snippet)),
+                |app, result| match result {
+                    Ok((id, snippet)) => {
+                        if let Some(trash_window) = &mut app.trash_window {
+                            trash_window.remove(&id);
+                        }
+                        app.events.publish(SnippetEvent::Created(snippet));
+                    }
+                    Err(e) => {
+                        log::error!("Failed to restore snippet: {}", e);
+                        app.toasts.error(format!("Could not restore snippet: {}", e));
+                    }
+                },
+            ),
+            Some(TrashAction::Delete(id)) => self.with_storage(
+                move |storage| storage.delete_from_trash(&id).map(|()| id),
+                |app, result| match result {
+                    Ok(id) => {
+                        if let Some(trash_window) = &mut app.trash_window {
+                            trash_window.remove(&id);
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Failed to delete snippet from trash: {}", e);
+                        app.toasts.error(format!("Could not delete snippet: {}", e));
+                    }
+                },
+            ),
+            Some(TrashAction::Empty) => {
+                self.with_storage(
+                    |storage| storage.purge_trash(None),
+                    |app, result| {
+                        if let Err(e) = result {
+                            log::error!("Failed to empty trash: {}", e);
+                            app.toasts.error(format!("Could not empty trash: {}", e));
+                        }
+                    },
+                );
+                // Reload, since a failure may have left part of the trash behind.
+                self.open_trash();
+            }
+            Some(TrashAction::Back) => {
+                self.trash_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    fn update_duplicates(&mut self, ctx: &egui::Context) {
+        let Some(duplicates_window) = &mut self.duplicates_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match duplicates_window.show(ctx, &self.snippets, &self.usage) {
+            Some(DuplicatesAction::Merge(plans)) => self.resolve_duplicates(plans, true),
+            Some(DuplicatesAction::Delete(plans)) => self.resolve_duplicates(plans, false),
+            Some(DuplicatesAction::Back) => {
+                self.duplicates_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    /// Switches line wrapping in the editor and preview together and keeps
+    /// the choice for next time.
+    fn set_word_wrap(&mut self, wrap: bool) {
+        self.config.word_wrap = wrap;
+        self.add_window.set_wrap(wrap);
+        self.get_window.set_wrap(wrap);
+        self.save_config();
+    }
+    
+    /// Saves the config as it is now on the storage thread. A failure is
+    /// only logged; the setting still holds until the app quits.
+    fn save_config(&self) {
+        let config = self.config.clone();
+        self.with_storage(
+            move |_| config.save(&Config::default_path()),
+            |_, result| {
+                if let Err(e) = result {
+                    log::warn!("Failed to save config: {}", e);
+                }
+            },
+        );
+    }
+    
+    /// Saves `config` on the storage thread, then applies it everywhere it
+    /// is used and runs `then`. Shows a toast instead when it could not be
+    /// saved.
+    fn apply_config(&self, ctx: &egui::Context, config: Config, then: impl FnOnce(&mut TrinketApp) + Send + 'static) {
+        let ctx = ctx.clone();
+        self.with_storage(
+            move |_| config.save(&Config::default_path()).map(|()| config),
+            move |app, result| match result {
+                Ok(config) => {
+                    app.use_config(&ctx, config);
+                    then(app);
+                }
+                Err(e) => {
+                    log::error!("Failed to save config: {}", e);
+                    app.toasts.error(format!("Could not save settings: {}", e));
+                }
+            },
+        );
+    }
+    
+    /// Applies a saved `config` everywhere it is used.
+    fn use_config(&mut self, ctx: &egui::Context, config: Config) {
+        let keymap = Keymap::new(config.keymap_profile, &config.keybindings);
+        self.add_window.configure(keymap.clone());
+        self.add_window.set_redaction(config.redaction, Redactor::new(&config.redaction_patterns));
+        #[cfg(any(target_os = "macos", feature = "hotkeys"))]
+        {
+            self.capture_filter = CaptureFilter::new(&config.capture_excluded_apps, &config.capture_excluded_patterns);
+        }
+        self.get_window.set_keymap(keymap);
+        self.get_window.set_density(list_density(&config));
+        self.get_window.set_layout(config.list_layout);
+        self.get_window.set_table_layout(config.table_layout.clone());
+        self.add_window.set_wrap(config.word_wrap);
+        self.get_window.set_wrap(config.word_wrap);
+        #[cfg(feature = "spellcheck")]
+        if config.spellcheck_language != self.config.spellcheck_language {
+            self.add_window.set_spellchecker(None);
+            self.dictionary_receiver = spellcheck::load_in_background(config.spellcheck_language.clone());
+        }
+        if config.local_metrics != self.config.local_metrics {
+            self.metrics = config.local_metrics.then(open_metrics);
+        }
+        if config.search_providers != self.config.search_providers {
+            self.search = search_provider::build(&config.search_providers);
+            self.get_window.invalidate();
+        }
+        ctx.set_zoom_factor(config.ui_scale);
+        ctx.send_viewport_cmd(window_level(&config));
+        self.trash_retention_days.store(config.trash_retention_days, Ordering::Relaxed);
+        let secure_delete = config.secure_delete;
+        let max_snippet_bytes = config.max_snippet_bytes;
+        self.with_storage(
+            move |storage| {
+                storage.set_secure_delete(secure_delete);
+                storage.set_max_snippet_bytes(max_snippet_bytes);
+            },
+            |_, ()| {},
+        );
+        *self.backup_settings.lock().unwrap_or_else(PoisonError::into_inner) = config.backup_settings();
+        #[cfg(feature = "s3")]
+        let s3_changed = self.config.s3 != config.s3;
+        self.config = config;
+        #[cfg(feature = "sync")]
+        self.apply_lan_sync();
+        #[cfg(feature = "sync")]
+        if let Some(lan) = &self.lan {
+            lan.set_folder_sync(self.config.folder_sync.clone());
+        }
+        #[cfg(feature = "s3")]
+        if s3_changed {
+            self.restart_s3_sync();
+        } else if let Some(s3) = &self.s3 {
+            s3.set_folder_sync(self.config.folder_sync.clone());
+        }
+    }
+    
+    fn update_settings(&mut self, ctx: &egui::Context) {
+        let Some(settings_window) = &mut self.settings_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match settings_window.show(ctx, &self.snippets) {
+            Some(SettingsAction::Save(config)) => self.apply_config(ctx, *config, |app| {
+                app.settings_window = None;
+                app.mode = AppMode::GettingSnippet;
+            }),
+            #[cfg(feature = "s3")]
+            Some(SettingsAction::SaveS3Credentials(credentials)) => match credentials.store() {
+                Ok(()) => {
+                    self.toasts.info("Saved the bucket's credentials in the keychain");
+                    self.restart_s3_sync();
+                }
+                Err(e) => {
+                    log::error!("Failed to store the S3 credentials: {}", e);
+                    self.toasts.error(format!("Could not save the credentials: {}", e));
+                }
+            },
+            #[cfg(feature = "s3")]
+            Some(SettingsAction::SetS3Key(mut key)) => {
+                let result = s3::store_key(&key);
+                key.zeroize();
+                match result {
+                    Ok(()) => {
+                        self.toasts.info("Saved the sync key in the keychain");
+                        self.restart_s3_sync();
+                    }
+                    Err(e) => {
+                        log::error!("Failed to store the S3 sync key: {}", e);
+                        self.toasts.error(format!("Could not use the sync key: {}", e));
+                    }
+                }
+            }
+            #[cfg(feature = "s3")]
+            Some(SettingsAction::CopyS3Key) => self.copy_s3_key(),
+            Some(SettingsAction::BackUpNow) => self.back_up_now(),
+            Some(SettingsAction::OpenRestore) => {
+                self.settings_window = None;
+                self.open_restore();
+            }
+            Some(SettingsAction::ImportStarters(index)) => self.import_starters(index),
+            Some(SettingsAction::Back) => {
+                self.settings_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    /// Lists the backups on the storage thread, then hands them to `then`.
+    /// Failing to list them is shown and leaves an empty list.
+    fn with_backups(&self, then: impl FnOnce(&mut TrinketApp, Vec<BackupEntry>) + Send + 'static) {
+        let dir = self.config.backup_dir();
+        self.with_storage(
+            move |_| backup_entries(&dir),
+            |app, result| {
+                let backups = result.unwrap_or_else(|e| {
+                    log::error!("Failed to list backups: {}", e);
+                    app.toasts.error(format!("Could not list the backups: {}", e));
+                    Vec::new()
+                });
+                then(app, backups);
+            },
+        );
+    }
+    
+    fn open_restore(&mut self) {
+        self.with_backups(|app, backups| {
+            app.restore_window = Some(RestoreWindowState::new(backups));
+            app.mode = AppMode::Restore;
+        });
+    }
+    
+    fn update_restore(&mut self, ctx: &egui::Context) {
+        let Some(restore_window) = &mut self.restore_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match restore_window.show(ctx) {
+            Some(RestoreAction::Preview(backup)) => self.with_storage(
+                move |storage| storage.backup_snippets(&backup.path).map_err(|e| (backup.path.clone(), e)).map(|snippets| (backup, snippets)),
+                |app, result| match result {
+                    Ok((backup, snippets)) => {
+                        if let Some(restore_window) = &mut app.restore_window {
+                            restore_window.set_preview(backup, backup::diff_backup(&app.snippets, snippets));
+                        }
+                    }
+                    Err((path, e)) => {
+                        log::error!("Failed to read backup {}: {}", path.display(), e);
+                        app.toasts.error(format!("Could not read the backup: {}", e));
+                    }
+                },
+            ),
+            Some(RestoreAction::Restore(path)) => self.restore_backup(&path),
+            Some(RestoreAction::BackUpNow) => self.back_up_now(),
+            Some(RestoreAction::Back) => {
+                self.restore_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    fn back_up_now(&mut self) {
+        let dir = self.config.backup_dir();
+        let kept = self.config.backups_kept;
+        self.with_storage(
+            move |storage| backup::write_backup(&storage.base_path, &dir)
+                .and_then(|written| backup::prune_backups(&dir, kept).map(|_| written)),
+            |app, result| match result {
+                Ok(written) => app.toasts.info(format!("Backed up the snippets to {}", written.path.display())),
+                Err(e) => {
+                    log::error!("Failed to back up the snippets: {}", e);
+                    app.toasts.error(format!("Could not back up the snippets: {}", e));
+                }
+            },
+        );
+        if self.restore_window.is_some() {
+            self.with_backups(|app, backups| {
+                if let Some(restore_window) = &mut app.restore_window {
+                    restore_window.set_backups(backups);
+                }
+            });
+        }
+    }
+    
+    /// Replaces the library with a backup. What it held before is backed up
+    /// first, so a restore can itself be undone.
+    fn restore_backup(&mut self, path: &Path) {
+        let path = path.to_path_buf();
+        let backup_dir = self.config.backup_dir();
+        self.with_storage(
+            move |storage| {
+                let result = storage.restore_backup(&path, &backup_dir).map_err(|e| (path, e));
+                // A failed restore may have got partway, so reload either way.
+                (result, read_library(storage), open_usage(&storage.base_path))
+            },
+            |app, (result, library, usage)| {
+                app.set_library(library);
+                app.usage = usage;
+                match result {
+                    Ok(before) => {
+                        app.toasts.info(format!("Restored {} snippets; the library as it was is in {}", app.snippets.len(), before.path.display()));
+                        app.restore_window = None;
+                        app.mode = AppMode::GettingSnippet;
+                    }
+                    Err((path, e)) => {
+                        log::error!("Failed to restore {}: {}", path.display(), e);
+                        app.toasts.error(format!("Could not restore the backup: {}", e));
+                    }
+                }
+            },
+        );
+    }
+    
+    /// Reconnects to the configured bucket, or stops syncing with it.
+    #[cfg(feature = "s3")]
+    fn restart_s3_sync(&mut self) {
+        self.s3 = None;
+        self.s3 = start_s3_sync(&self.storage_path, &self.config, &mut self.toasts);
+    }
+    
+    /// Copies the bucket's sync key to paste on another device. It is
+    /// cleared from the clipboard like a secret snippet.
+    #[cfg(feature = "s3")]
+    fn copy_s3_key(&mut self) {
+        let key = match s3::load_key() {
+            Ok(Some(key)) => key,
+            Ok(None) => {
+                self.toasts.error("There is no sync key yet; it is created once a bucket is set up");
+                return;
+            }
+            Err(e) => {
+                log::error!("Failed to read the S3 sync key: {}", e);
+                self.toasts.error(format!("Could not read the sync key: {}", e));
+                return;
+            }
+        };
+        if let Err(e) = copy_to_clipboard(&key) {
+            log::error!("Failed to copy to clipboard: {}", e);
+            self.toasts.error(format!("Could not copy to clipboard: {}", e));
+            return;
+        }
+        self.toasts.info("Copied the sync key; paste it in the settings of your other devices");
+        if self.config.secret_clipboard_clear_secs > 0 {
+            clear_clipboard_after(key, Duration::from_secs(self.config.secret_clipboard_clear_secs));
+        }
+    }
+    
+    /// Removes the extra copies of each group. When merging, their usage,
+    /// tags and notes move to the kept snippet first. Locked copies are left alone.
+    fn resolve_duplicates(&mut self, plans: Vec<DuplicatePlan>, merge: bool) {
+        let groups: Vec<(Snippet, Vec<Snippet>)> = plans.into_iter()
+            .map(|plan| (self.snippets[plan.keep].clone(), plan.remove.iter().map(|&i| self.snippets[i].clone()).collect()))
+            .collect();
+        let usage_path = self.usage.path().to_path_buf();
+        self.with_storage(
+            move |storage| {
+                // Each removed snippet's id with the id of the one kept.
+                let mut removed = Vec::new();
+                let mut merged = Vec::new();
+                let mut merged_usage = Vec::new();
+                let mut failures = 0;
+                for (mut kept, remove) in groups {
+                    let before = (kept.meta.notes.len(), kept.meta.tags.len());
+                    for snippet in remove {
+                        if let Err(e) = storage.delete_snippet(&snippet) {
+                            log::warn!("Failed to remove duplicate {}: {}", snippet.id, e);
+                            failures += 1;
+                            continue;
+                        }
+                        removed.push((snippet.id.clone(), kept.id.clone()));
+                        if !merge {
+                            continue;
+                        }
+                        match usage::write_merge(&usage_path, &snippet.id, &kept.id) {
+                            Ok(()) => merged_usage.push((snippet.id.clone(), kept.id.clone())),
+                            Err(e) => log::warn!("Failed to merge usage of {}: {}", snippet.id, e),
+                        }
+                        
+                        let notes = &snippet.meta.notes;
+                        if !notes.is_empty() && !kept.meta.notes.contains(notes.as_str()) {
+                            if !kept.meta.notes.is_empty() {
+                                kept.meta.notes.push('\n');
+                            }
+                            kept.meta.notes.push_str(notes);
+                        }
+                        for tag in &snippet.meta.tags {
+                            if !kept.meta.tags.contains(tag) {
+                                kept.meta.tags.push(tag.clone());
+                            }
+                        }
+                    }
+                    if (kept.meta.notes.len(), kept.meta.tags.len()) != before {
+                        match storage.save_meta(&kept) {
+                            Ok(()) => merged.push(kept),
+                            Err(e) => log::warn!("Failed to save merged metadata: {}", e),
+                        }
+                    }
+                }
+                (removed, merged, merged_usage, failures)
+            },
+            move |app, (removed, merged, merged_usage, failures)| {
+                for (id, kept) in merged_usage {
+                    app.usage.merged(&id, kept);
+                }
+                for (id, _) in removed {
+                    app.events.publish(SnippetEvent::Deleted { id });
+                }
+                for kept in merged {
+                    app.events.publish(SnippetEvent::Updated(kept));
+                }
+                app.handle_snippet_events();
+                app.duplicates_window = Some(DuplicatesWindowState::new(&app.snippets, &app.usage));
+                if failures > 0 {
+                    app.toasts.error(format!("{} duplicate(s) could not be removed; unlock them first", failures));
+                }
+            },
+        );
+    }
+    
+    /// Applies `op` to every snippet in `indices` and records a single undo
+    /// entry for all of them. Failures are counted rather than aborting, so
+    /// one locked snippet doesn't block the rest.
+    fn apply_bulk(&mut self, indices: &[usize], op: BulkOp) {
+        let label = match &op {
+            BulkOp::AddTag(tag) => format!("tagging {} snippet(s) #{}", indices.len(), tag),
+            BulkOp::RemoveTag(tag) => format!("untagging {} snippet(s) #{}", indices.len(), tag),
+            BulkOp::Move(folder) if folder.is_empty() => format!("moving {} snippet(s) to the root", indices.len()),
+            BulkOp::Move(folder) => format!("moving {} snippet(s) to {}", indices.len(), folder),
+            BulkOp::SetPinned(true) => format!("pinning {} snippet(s)", indices.len()),
+            BulkOp::SetPinned(false) => format!("unpinning {} snippet(s)", indices.len()),
+            BulkOp::Delete => format!("deleting {} snippet(s)", indices.len()),
+        };
+        let snippets: Vec<Snippet> = indices.iter().map(|&index| self.snippets[index].clone()).collect();
+        let mut pin_order = next_pin_order(&self.snippets);
+        let moved = matches!(op, BulkOp::Move(_));
+        
+        self.with_storage(
+            move |storage| {
+                let mut applied = Vec::new();
+                let mut failures = Vec::new();
+                // Erased secrets can't be brought back by undo.
+                let mut erased = HashSet::new();
+                for mut snippet in snippets {
+                    let id = snippet.id.clone();
+                    let before = snippet.meta.clone();
+                    let result = match &op {
+                        BulkOp::AddTag(tag) => {
+                            if !snippet.meta.tags.contains(tag) {
+                                snippet.meta.tags.push(tag.clone());
+                            }
+                            storage.save_meta(&snippet).map(|()| UndoChange::Meta { id, before: Box::new(before) })
+                        }
+                        BulkOp::RemoveTag(tag) => {
+                            snippet.meta.tags.retain(|t| t != tag);
+                            storage.save_meta(&snippet).map(|()| UndoChange::Meta { id, before: Box::new(before) })
+                        }
+                        BulkOp::SetPinned(pinned) => {
+                            if *pinned && !snippet.meta.pinned {
+                                snippet.meta.pin_order = pin_order;
+                                pin_order += 1;
+                            }
+                            snippet.meta.pinned = *pinned;
+                            storage.save_meta(&snippet).map(|()| UndoChange::Meta { id, before: Box::new(before) })
+                        }
+                        BulkOp::Move(folder) => {
+                            let from = snippet.folder.clone();
+                            storage.move_snippet(&mut snippet, folder).map(|()| UndoChange::Moved { id, from })
+                        }
+                        BulkOp::Delete => {
+                            let result = storage.delete_snippet(&snippet);
+                            if let Ok(false) = result {
+                                erased.insert(id.clone());
+                            }
+                            result.map(|_| UndoChange::Trashed { id })
+                        }
+                    };
+                    match result {
+                        Ok(change) => {
+                            let event = if matches!(op, BulkOp::Delete) {
+                                SnippetEvent::Deleted { id: snippet.id.clone() }
+                            } else {
+                                SnippetEvent::Updated(snippet)
+                            };
+                            applied.push((event, change));
+                        }
+                        Err(e) => {
+                            log::warn!("Bulk operation failed for {}: {}", snippet.id, e);
+                            failures.push(e);
+                        }
+                    }
+                }
+                (applied, erased, failures)
+            },
+            move |app, (applied, erased, failures)| {
+                let mut changes = Vec::new();
+                for (event, change) in applied {
+                    app.events.publish(event);
+                    changes.push(change);
+                }
+                changes.retain(|change| !matches!(change, UndoChange::Trashed { id } if erased.contains(id)));
+                if moved {
+                    app.refresh_folders();
+                }
+                app.undo.push(UndoEntry { label, changes });
+                if let Some(e) = failures.first() {
+                    app.toasts.error(format!("{} snippet(s) were skipped: {}", failures.len(), e));
+                }
+            },
+        );
+    }
+    
+    /// Swaps a pinned snippet with its pinned neighbour and renumbers the
+    /// pinned snippets so their order survives a restart.
+    fn move_pinned(&mut self, index: usize, up: bool) {
+        // Sorted, so the pinned snippets come first and in order.
+        let mut pinned: Vec<usize> = (0..self.snippets.len())
+            .take_while(|&i| self.snippets[i].meta.pinned)
+            .collect();
+        let Some(position) = pinned.iter().position(|&i| i == index) else {
+            return;
+        };
+        let target = if up { position.checked_sub(1) } else { Some(position + 1) };
+        let Some(target) = target.filter(|&target| target < pinned.len()) else {
+            return;
+        };
+        pinned.swap(position, target);
+        let renumbered: Vec<Snippet> = pinned.iter()
+            .enumerate()
+            .filter(|&(order, &i)| self.snippets[i].meta.pin_order != order as u32)
+            .map(|(order, &i)| {
+                let mut snippet = self.snippets[i].clone();
+                snippet.meta.pin_order = order as u32;
+                snippet
+            })
+            .collect();
+        
+        let id = self.snippets[index].id.clone();
+        self.with_storage(
+            |storage| {
+                let mut saved = Vec::new();
+                for snippet in renumbered {
+                    if let Err(e) = storage.save_meta(&snippet) {
+                        return (saved, Some(e));
+                    }
+                    saved.push(snippet);
+                }
+                (saved, None)
+            },
+            move |app, (saved, error)| {
+                if let Some(e) = error {
+                    log::error!("Failed to save pinned order: {}", e);
+                    app.toasts.error(format!("Could not reorder pinned snippets: {}", e));
+                }
+                for snippet in saved {
+                    app.events.publish(SnippetEvent::Updated(snippet));
+                }
+                app.handle_snippet_events();
+                if let Some(moved) = app.snippets.iter().position(|snippet| snippet.id == id) {
+                    app.get_window.select_snippet(moved);
+                }
+            },
+        );
+    }
+    
+    fn undo(&mut self) {
+        let Some(entry) = self.undo.pop() else {
+            return;
+        };
+        // Changes are reverted newest first, each with the snippet as it is
+        // now when there is one.
+        let changes: Vec<(UndoChange, Option<Snippet>)> = entry.changes.into_iter()
+            .rev()
+            .map(|change| {
+                let snippet = match &change {
+                    UndoChange::Meta { id, .. } | UndoChange::Moved { id, .. } => self.snippets.iter().find(|s| &s.id == id).cloned(),
+                    UndoChange::Trashed { .. } => None,
+                };
+                (change, snippet)
+            })
+            .collect();
+        
+        self.with_storage(
+            |storage| {
+                let mut events = Vec::new();
+                let mut failures = 0;
+                for (change, snippet) in changes {
+                    let result = match (change, snippet) {
+                        (UndoChange::Meta { before, .. }, Some(mut snippet)) => {
+                            snippet.meta = *before;
+                            snippet.refresh_title();
+                            storage.save_meta(&snippet).map(|()| events.push(SnippetEvent::Updated(snippet)))
+                        }
+                        (UndoChange::Moved { from, .. }, Some(mut snippet)) => storage.move_snippet(&mut snippet, &from)
+                            .map(|()| events.push(SnippetEvent::Updated(snippet))),
+                        (UndoChange::Trashed { id }, _) => storage.restore_from_trash(&id)
+                            .map(|snippet| events.push(SnippetEvent::Created(snippet))),
+                        (_, None) => Ok(()),
+                    };
+                    if let Err(e) = result {
+                        log::warn!("Failed to undo change: {}", e);
+                        failures += 1;
+                    }
+                }
+                (events, failures, storage.list_folders().unwrap_or_default())
+            },
+            move |app, (events, failures, folders)| {
+                for event in events {
+                    app.events.publish(event);
+                }
+                app.folders = folders;
+                if failures > 0 {
+                    app.toasts.error(format!("Undo was incomplete: {} change(s) could not be reverted", failures));
+                } else {
+                    app.toasts.info(format!("Undid {}", entry.label));
+                }
+            },
+        );
+    }
+    
+    fn delete(&mut self, index: usize) {
+        let snippet = self.snippets[index].clone();
+        self.with_storage(
+            move |storage| storage.delete_snippet(&snippet).map(|_| snippet.id.clone()),
+            |app, result| match result {
+                Ok(id) => app.events.publish(SnippetEvent::Deleted { id }),
+                Err(e) => {
+                    log::error!("Failed to delete snippet: {}", e);
+                    app.toasts.error(format!("Could not delete snippet: {}", e));
+                }
+            },
+        );
+    }
+    
+    /// Hides every window when the OS session locks, and locks the vault.
+    fn session_locked(&mut self, ctx: &egui::Context) {
+        log::info!("Session locked");
+        #[cfg(feature = "ocr")]
+        if self.capture_window.take().is_some() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+        }
+        #[cfg(not(feature = "ocr"))]
+        let _ = ctx;
+        self.mode = AppMode::Hidden;
+        #[cfg(feature = "vault")]
+        self.lock_vault();
+    }
+    
+    /// Drops the vault's keys, those of encrypted folders and every
+    /// decrypted snippet.
+    #[cfg(feature = "vault")]
+    fn lock_vault(&mut self) {
+        if !self.uses_vaults() {
+            return;
+        }
+        self.forget_snippets();
+        self.with_storage(
+            |storage| {
+                storage.lock_vault();
+                storage.locked_folders()
+            },
+            |app, locked_folders| {
+                // A reload sent before the lock may have brought some back.
+                app.forget_snippets();
+                app.get_window.set_locked_folders(locked_folders);
+            },
+        );
+    }
+    
+    /// Wipes every decrypted snippet held here and closes the windows
+    /// showing any.
+    #[cfg(feature = "vault")]
+    fn forget_snippets(&mut self) {
+        for snippet in &mut self.snippets {
+            snippet.content.zeroize();
+        }
+        self.snippets.clear();
+        self.history_window = None;
+        self.duplicates_window = None;
+        self.trash_window = None;
+        self.restore_window = None;
+        self.get_window.invalidate();
+    }
+    
+    /// Reopens a locked vault once the session is unlocked; the identity
+    /// comes from its file or the keychain, so there is nothing to ask.
+    /// Passphrase folders stay locked until they are opened again.
+    #[cfg(feature = "vault")]
+    fn unlock_vault(&mut self) {
+        if !self.uses_vaults() {
+            return;
+        }
+        let config = self.config.clone();
+        self.with_storage(
+            move |storage| {
+                if storage.is_vault_locked() {
+                    storage.unlock_vault(load_vault(&config)?);
+                }
+                let folder_errors = open_folder_vaults(storage, &config);
+                Ok((read_library(storage), folder_errors))
+            },
+            |app, result: Result<_, VaultError>| match result {
+                Ok((library, folder_errors)) => {
+                    app.set_library(library);
+                    for e in folder_errors {
+                        app.toasts.error(format!("Could not open an encrypted folder: {}", e));
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to unlock vault: {}", e);
+                    app.toasts.error(format!("Could not unlock the vault: {}", e));
+                }
+            },
+        );
+    }
+    
+    /// Opens an encrypted folder's key with the passphrase the user typed;
+    /// the first passphrase given for a folder creates its key. Folders
+    /// keyed by an identity file just retry opening it.
+    fn unlock_folder(&mut self, folder: &str, passphrase: String) {
+        #[cfg(feature = "vault")]
+        {
+            let Some((folder, folder_vault)) = folder_vaults(&self.config).find(|(name, _)| name == folder) else {
+                return;
+            };
+            let folder_vault = folder_vault.clone();
+            self.with_storage(
+                move |storage| {
+                    let key_file = storage.folder_path(&folder).join(FOLDER_KEY_FILE);
+                    let created = folder_vault.identity_file.is_none() && !key_file.exists();
+                    let recipients_file = folder_vault.recipients_file.as_deref();
+                    let result = match folder_vault.identity_file.as_deref() {
+                        Some(identity_file) => Vault::open(identity_file, recipients_file),
+                        None => Vault::open_with_passphrase(&key_file, SecretString::from(passphrase), recipients_file),
+                    };
+                    let result = result.map(|vault| {
+                        storage.unlock_folder(&folder, vault);
+                        (read_library(storage), created)
+                    });
+                    (folder, result)
+                },
+                |app, (folder, result)| match result {
+                    Ok((library, created)) => {
+                        app.set_library(library);
+                        if created {
+                            app.toasts.info(format!("Passphrase set for {}", folder));
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Failed to unlock folder {}: {}", folder, e);
+                        app.toasts.error(format!("Could not unlock {}: {}", folder, e));
+                    }
+                },
+            );
+        }
+        #[cfg(not(feature = "vault"))]
+        {
+            let _ = passphrase;
+            self.toasts.error(format!("Could not unlock {}: this build has no vault support", folder));
+        }
+    }
+    
+    #[cfg(feature = "vault")]
+    fn uses_vaults(&self) -> bool {
+        self.config.vault_identity_file.is_some() || self.config.vault_identity_in_keychain || !self.config.folder_vaults.is_empty()
+    }
+    
+    /// Starts or pauses LAN sync to match the config. A changed port takes
+    /// effect on the next start.
+    #[cfg(feature = "sync")]
+    fn apply_lan_sync(&mut self) {
+        match &self.lan {
+            Some(lan) => lan.set_enabled(self.config.lan_sync),
+            None if self.config.lan_sync => self.lan = start_lan_sync(&self.storage_path, &self.config, &mut self.toasts),
+            None => {}
+        }
+    }
+    
+    /// Opens the get window fresh, noting which app had the focus so
+    /// snippets meant for other apps stay out of the list.
+    fn open_get_window(&mut self) {
+        self.get_opened = Some(Instant::now());
+        self.get_window.reset();
+        self.get_window.set_active_app(active_app());
+        self.mode = AppMode::GettingSnippet;
+        self.suggest_review();
+    }
+    
+    /// Mentions snippets unused for the configured months, at most once per
+    /// `REVIEW_REMINDER_INTERVAL`.
+    fn suggest_review(&mut self) {
+        let months = self.config.review_after_months;
+        if months == 0 || self.review_reminded.is_some_and(|at| at.elapsed() < REVIEW_REMINDER_INTERVAL) {
+            return;
+        }
+        self.review_reminded = Some(Instant::now());
+        let stale = stale_snippets(&self.snippets, &self.usage, months, Utc::now()).len();
+        if stale > 0 {
+            self.toasts.info(format!("{} snippets unused for {} months; see ☰ → Review unused…", stale, months));
+        }
+    }
+    
+    fn open_review(&mut self) {
+        let months = match self.config.review_after_months {
+            0 => DEFAULT_REVIEW_MONTHS,
+            months => months,
+        };
+        self.review_window = Some(ReviewWindowState::new(&self.snippets, &self.usage, months));
+        self.mode = AppMode::Review;
+    }
+    
+    fn update_review(&mut self, ctx: &egui::Context) {
+        let Some(review_window) = &mut self.review_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match review_window.show(ctx, &self.snippets, &self.usage) {
+            Some(ReviewAction::Keep(index)) => {
+                self.save_meta(index, |meta| meta.reviewed = Some(Utc::now()));
+            }
+            Some(ReviewAction::Edit(index)) if self.needs_confirmation(index) => {
+                self.confirm_secret(SecretUse::Edit(self.snippets[index].id.clone()));
+            }
+            Some(ReviewAction::Edit(index)) => {
+                self.add_window.edit(&self.snippets[index]);
+                self.mode = AppMode::AddingSnippet;
+            }
+            Some(ReviewAction::Archive(index)) => {
+                self.save_meta(index, |meta| meta.archived = true);
+            }
+            Some(ReviewAction::Delete(index)) => self.delete(index),
+            Some(ReviewAction::Back) => {
+                self.review_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    /// Copies a snippet picked outside the app windows, from the menu bar
+    /// or the jump list.
+    #[cfg(any(all(target_os = "macos", feature = "tray"), windows))]
+    fn copy_by_id(&mut self, id: &str) {
+        if let Some(index) = self.snippets.iter().position(|snippet| snippet.id == id) {
+            let text = self.snippets[index].content.clone();
+            self.copy(&[index], text);
+        }
+    }
+    
+    /// Saves text sent from the Services menu or copied by the selection
+    /// hotkey as it is, without opening the add window, crediting the app it
+    /// came from.
+    #[cfg(any(target_os = "macos", feature = "hotkeys"))]
+    fn save_selection(&mut self, mut content: String) {
+        let source = capture_source();
+        let excluded_app = source.as_ref().is_some_and(|source| self.capture_filter.excludes_app(&source.app));
+        if excluded_app || self.capture_filter.excludes_text(&content) {
+            log::info!("Not saving a selection that a capture exclusion matches");
+            content.zeroize();
+            self.toasts.info("Not saved: the capture exclusions leave this out");
+            return;
+        }
+        let meta = SnippetMeta {
+            source,
+            ..SnippetMeta::default()
+        };
+        self.save_new(NewSnippet { editing: None, content, folder: String::new(), meta, over_limit: false });
+    }
+    
+    /// Puts the pinned snippets in the taskbar jump list when they changed.
+    #[cfg(windows)]
+    fn update_jump_list(&mut self) {
+        let mut pinned: Vec<&Snippet> = self.snippets.iter().filter(|snippet| snippet.meta.pinned).collect();
+        pinned.sort_by_key(|snippet| snippet.meta.pin_order);
+        let pinned: Vec<(String, String)> = pinned.into_iter()
+            .take(PINNED_TASKS)
+            .map(|snippet| (snippet.id.clone(), snippet.safe_title().to_string()))
+            .collect();
+        if self.jump_list.as_ref() != Some(&pinned) {
+            set_jump_list(pinned.clone());
+            self.jump_list = Some(pinned);
+        }
+    }
+    
+    /// Applies the blurred backdrop and a title bar matching the theme when
+    /// either setting changed, and lets the backdrop show through the panels.
+    #[cfg(any(windows, target_os = "macos"))]
+    fn update_window_effects(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let wanted = (self.config.window_effects, ctx.style().visuals.dark_mode);
+        if self.window_effects == Some(wanted) {
+            return;
+        }
+        self.window_effects = Some(wanted);
+        self.backdrop = match window_effects::apply(frame, wanted.0, wanted.1) {
+            Ok(()) => wanted.0,
+            Err(e) => {
+                log::warn!("Could not set the window backdrop: {}", e);
+                false
+            }
+        };
+        let backdrop = self.backdrop;
+        ctx.all_styles_mut(|style| {
+            let base = if style.visuals.dark_mode { egui::Visuals::dark() } else { egui::Visuals::light() };
+            let (panel_fill, window_fill) = if backdrop {
+                (base.panel_fill.gamma_multiply(0.55), base.window_fill.gamma_multiply(0.8))
+            } else {
+                (base.panel_fill, base.window_fill)
+            };
+            style.visuals.panel_fill = panel_fill;
+            style.visuals.window_fill = window_fill;
+        });
+    }
+
+    /// Keeps the recent snippets in the menu bar dropdown current and acts on
+    /// what the user picked from it.
+    #[cfg(all(target_os = "macos", feature = "tray"))]
+    fn handle_menu_bar(&mut self, ctx: &egui::Context) {
+        if let Some(tray) = &self.tray {
+            if let Err(e) = self.menu_bar.refresh(tray, &self.snippets, &self.usage, self.snoozed_until.is_some()) {
+                log::warn!("Could not update the menu bar menu: {}", e);
+            }
+        }
+        while let Some(action) = self.menu_bar.poll() {
+            match action {
+                MenuBarAction::Add => self.mode = AppMode::AddingSnippet,
+                MenuBarAction::Get => self.open_get_window(),
+                MenuBarAction::Copy(id) => self.copy_by_id(&id),
+                MenuBarAction::ToggleSnooze => self.toggle_snooze(),
+                MenuBarAction::Settings => {
+                    self.settings_window = Some(SettingsWindowState::new(&self.config));
+                    self.mode = AppMode::Settings;
+                }
+                MenuBarAction::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            }
+        }
+    }
+    
+    /// Shows how syncing is going in the status bar.
+    #[cfg(feature = "sync")]
+    fn show_sync_status(&mut self) {
+        let status = self.lan.as_ref().filter(|_| self.config.lan_sync).map(LanSync::status);
+        self.get_window.set_sync_status(status);
+    }
+
+    /// Ignores the hotkeys and tray clicks and mutes notifications for
+    /// `minutes`; they come back on their own after. With the selection
+    /// hotkey ignored nothing captures, as there is no clipboard monitor;
+    /// the Services menu, picked by hand, still saves.
+    fn snooze(&mut self, minutes: u32) {
+        log::info!("Snoozed for {} minutes", minutes);
+        self.snoozed_until = Some(Instant::now() + Duration::from_secs(u64::from(minutes.max(1)) * 60));
+        self.toasts.set_muted(true);
+    }
+
+    fn resume(&mut self) {
+        log::info!("Snooze over");
+        self.snoozed_until = None;
+        self.toasts.set_muted(false);
+    }
+
+    /// Snoozes for the configured minutes, or resumes early.
+    fn toggle_snooze(&mut self) {
+        if self.snoozed_until.is_some() {
+            self.resume();
+        } else {
+            self.snooze(self.config.snooze_minutes);
+        }
+    }
+
+    /// Keeps the tray tooltip on how long a snooze has left and how
+    /// syncing is going.
+    #[cfg(feature = "tray")]
+    fn update_tray_tooltip(&mut self) {
+        let mut tooltip = TRAY_TOOLTIP.to_string();
+        if let Some(until) = self.snoozed_until {
+            tooltip = format!("{}\n{}", tooltip, snooze_summary(until.saturating_duration_since(Instant::now())));
+        }
+        #[cfg(feature = "sync")]
+        if let Some(status) = self.lan.as_ref().filter(|_| self.config.lan_sync).map(LanSync::status) {
+            tooltip = format!("{}\n{}", tooltip, sync_status::summary(&status));
+        }
+        if tooltip != self.tray_tooltip {
+            if let Some(Err(e)) = self.tray.as_ref().map(|tray| tray.set_tooltip(Some(&tooltip))) {
+                log::debug!("Could not update the tray tooltip: {}", e);
+            }
+            self.tray_tooltip = tooltip;
+        }
+    }
+
+    /// Handles what LAN sync and S3 sync report.
+    #[cfg(feature = "sync")]
+    fn handle_lan_events(&mut self) {
+        #[cfg_attr(not(feature = "s3"), allow(unused_mut))]
+        let mut events: Vec<LanEvent> = self.lan.iter()
+            .flat_map(|lan| std::iter::from_fn(|| lan.try_event()))
+            .collect();
+        let device = self.lan.as_ref().map(|lan| lan.id().to_string());
+        #[cfg(feature = "s3")]
+        events.extend(self.s3.iter().flat_map(|s3| std::iter::from_fn(|| s3.try_event())));
+        #[cfg(feature = "s3")]
+        let device = device.or_else(|| self.s3.as_ref().map(|s3| s3.device().to_string()));
+        let Some(device) = device else {
+            return;
+        };
+        for event in events {
+            match event {
+                LanEvent::SnapshotNeeded(reply) => {
+                    let snippets = self.snippets.clone();
+                    let local_uses = self.usage.local_uses().clone();
+                    let device = device.clone();
+                    // The sync thread waits for the reply, not the app, and
+                    // leaves out the folders that don't sync with its peer.
+                    self.with_storage(
+                        move |storage| match Snapshot::new(storage, &snippets, &local_uses, &device) {
+                            Ok(snapshot) => {
+                                let _ = reply.send(snapshot);
+                            }
+                            Err(e) => log::error!("Failed to read the library for LAN sync: {}", e),
+                        },
+                        |_, ()| {},
+                    );
+                }
+                LanEvent::Received { peer, changes, done } => self.with_storage(
+                    move |storage| {
+                        let mut snippets = storage.load_all_snippets().unwrap_or_default();
+                        let result = sync::apply(storage, &mut snippets, &changes);
+                        let _ = done.send(result.is_ok());
+                        // Changes saved before a failure are kept, so reload either way.
+                        let synced = ClockStore::load(&storage.base_path).synced_uses();
+                        (result.map(|()| changes.len()), peer, read_library(storage), synced)
+                    },
+                    |app, (result, peer, library, synced)| {
+                        app.set_library(library);
+                        app.usage.set_synced(synced);
+                        match result {
+                            Ok(count) => app.toasts.info(format!("Synced {} changes from {}", count, peer)),
+                            Err(e) => {
+                                log::error!("Failed to save changes from {}: {}", peer, e);
+                                app.toasts.error(format!("Could not save changes from {}: {}", peer, e));
+                            }
+                        }
+                    },
+                ),
+                LanEvent::Conflicts(conflicts) => {
+                    let peer = conflicts[0].peer_name.clone();
+                    self.toasts.info(format!(
+                        "{} snippets were edited here and on {}; pick which to keep from the ☰ menu",
+                        conflicts.len(),
+                        peer
+                    ));
+                    for conflict in conflicts {
+                        self.sync_conflicts.retain(|known| known.peer_id != conflict.peer_id || known.id() != conflict.id());
+                        self.sync_conflicts.push(conflict);
+                    }
+                    self.get_window.set_sync_conflicts(self.sync_conflicts.len());
+                }
+                LanEvent::Paired(peer) => {
+                    self.toasts.info(format!("Paired with {}", peer.name));
+                    self.config.sync_peers.retain(|known| known.id != peer.id);
+                    self.config.sync_peers.push(peer);
+                    self.save_sync_config();
+                }
+                LanEvent::CompanionPaired(companion) => {
+                    self.toasts.info(format!("Paired with {}", companion.name));
+                    self.config.companions.retain(|known| known.id != companion.id);
+                    self.config.companions.push(companion);
+                    self.save_sync_config();
+                }
+                LanEvent::Pushed { from, text, done } => self.save_pushed(from, text, done),
+                LanEvent::SearchNeeded { query, limit, reply } => {
+                    let _ = reply.send(companion::search(self.search.as_ref(), &self.snippets, &query, limit));
+                }
+                LanEvent::Failed(message) => self.toasts.error(message),
+            }
+        }
+    }
+    
+    /// Saves text a companion phone sent as a new snippet in the root
+    /// folder, then tells the phone whether that worked.
+    #[cfg(feature = "sync")]
+    fn save_pushed(&mut self, from: String, text: String, done: mpsc::Sender<Result<(), String>>) {
+        if text.trim().is_empty() {
+            let _ = done.send(Err("There is nothing to save".to_string()));
+            return;
+        }
+        self.with_storage(
+            move |storage| storage.save_snippet(&text, "", SnippetMeta::default()),
+            move |app, result| match result {
+                Ok(snippet) => {
+                    app.events.publish(SnippetEvent::Created(snippet));
+                    app.record_metric(Metrics::record_capture);
+                    app.toasts.info(format!("Saved a snippet from {}", from));
+                    let _ = done.send(Ok(()));
+                }
+                Err(e) => {
+                    log::error!("Failed to save text from {}: {}", from, e);
+                    app.toasts.error(format!("Could not save text from {}: {}", from, e));
+                    let _ = done.send(Err(e.to_string()));
+                }
+            },
+        );
+    }
+
+    #[cfg(feature = "sync")]
+    fn save_sync_config(&mut self) {
+        if let Some(lan) = &self.lan {
+            lan.set_trusted(self.config.sync_peers.clone());
+            lan.set_companions(self.config.companions.clone());
+            lan.set_folder_sync(self.config.folder_sync.clone());
+        }
+        #[cfg(feature = "s3")]
+        if let Some(s3) = &self.s3 {
+            s3.set_folder_sync(self.config.folder_sync.clone());
+        }
+        let config = self.config.clone();
+        self.with_storage(
+            move |_| config.save(&Config::default_path()),
+            |app, result| {
+                if let Err(e) = result {
+                    log::error!("Failed to save config: {}", e);
+                    app.toasts.error(format!("Could not save the sync settings: {}", e));
+                }
+            },
+        );
+    }
+    
+    #[cfg(feature = "sync")]
+    fn update_conflicts(&mut self, ctx: &egui::Context) {
+        let Some(conflict_window) = &mut self.conflict_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        match conflict_window.show(ctx, &self.sync_conflicts, &self.snippets) {
+            Some(ConflictAction::Resolve(index, resolution)) => self.resolve_conflict(index, resolution),
+            Some(ConflictAction::Back) => {
+                self.conflict_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    #[cfg(feature = "sync")]
+    fn resolve_conflict(&mut self, index: usize, resolution: sync::Resolution) {
+        let conflict = self.sync_conflicts.remove(index);
+        self.get_window.set_sync_conflicts(self.sync_conflicts.len());
+        self.with_storage(
+            move |storage| {
+                let mut snippets = storage.load_all_snippets().unwrap_or_default();
+                let result = sync::resolve(storage, &mut snippets, &conflict, resolution);
+                (result, conflict, read_library(storage))
+            },
+            move |app, (result, conflict, library)| {
+                app.set_library(library);
+                match result {
+                    Ok(()) => app.settle(&conflict),
+                    Err(e) => {
+                        log::error!("Failed to resolve sync conflict for {}: {}", conflict.id(), e);
+                        app.toasts.error(format!("Could not save the chosen version: {}", e));
+                        app.sync_conflicts.insert(index.min(app.sync_conflicts.len()), conflict);
+                    }
+                }
+                app.get_window.set_sync_conflicts(app.sync_conflicts.len());
+                if app.sync_conflicts.is_empty() {
+                    app.conflict_window = None;
+                    app.mode = AppMode::GettingSnippet;
+                }
+            },
+        );
+    }
+    
+    /// Tells whichever sync the conflict came from that it is settled.
+    #[cfg(feature = "sync")]
+    fn settle(&self, conflict: &Conflict) {
+        #[cfg(feature = "s3")]
+        if let Some(s3) = self.s3.as_ref().filter(|s3| s3.peer_id() == conflict.peer_id) {
+            s3.settle(conflict.id(), conflict.theirs.hashes());
+            return;
+        }
+        if let Some(lan) = &self.lan {
+            lan.settle(&conflict.peer_id, conflict.id(), conflict.theirs.hashes());
+        }
+    }
+    
+    #[cfg(feature = "sync")]
+    fn update_pairing(&mut self, ctx: &egui::Context) {
+        let Some(pairing_window) = &mut self.pairing_window else {
+            self.mode = AppMode::GettingSnippet;
+            return;
+        };
+        let config = &self.config;
+        match pairing_window.show(ctx, self.lan.as_ref(), &config.sync_peers, &config.companions, &self.folders, &config.folder_sync, config.s3.is_some()) {
+            Some(PairingAction::StartPairing) => {
+                if let Some(lan) = &self.lan {
+                    pairing_window.show_code(lan.start_pairing());
+                }
+            }
+            Some(PairingAction::StopPairing) => {
+                if let Some(lan) = &self.lan {
+                    lan.stop_pairing();
+                }
+            }
+            Some(PairingAction::Pair(id, code)) => {
+                if let Some(lan) = &self.lan {
+                    lan.pair_with(&id, &code);
+                }
+            }
+            Some(PairingAction::SyncNow) => {
+                if let Some(lan) = &self.lan {
+                    lan.sync_now();
+                }
+            }
+            Some(PairingAction::Forget(id)) => {
+                self.config.sync_peers.retain(|peer| peer.id != id);
+                self.save_sync_config();
+            }
+            Some(PairingAction::StartCompanionPairing) => {
+                if let Some(lan) = &self.lan {
+                    match lan.start_companion_pairing() {
+                        Ok(invite) => pairing_window.show_invite(ctx, invite),
+                        Err(e) => {
+                            log::error!("Failed to start pairing a phone: {}", e);
+                            self.toasts.error(format!("Could not start pairing a phone: {}", e));
+                        }
+                    }
+                }
+            }
+            Some(PairingAction::StopCompanionPairing) => {
+                if let Some(lan) = &self.lan {
+                    lan.stop_companion_pairing();
+                }
+            }
+            Some(PairingAction::ForgetCompanion(id)) => {
+                self.config.companions.retain(|companion| companion.id != id);
+                self.save_sync_config();
+            }
+            Some(PairingAction::SetFolderSync(folder, folder_sync)) => {
+                self.config.folder_sync.insert(folder, folder_sync);
+                self.save_sync_config();
+            }
+            Some(PairingAction::Back) => {
+                if let Some(lan) = &self.lan {
+                    lan.stop_pairing();
+                    lan.stop_companion_pairing();
+                }
+                self.pairing_window = None;
+                self.mode = AppMode::GettingSnippet;
+            }
+            None => {}
+        }
+    }
+    
+    #[cfg(feature = "ocr")]
+    fn start_capture(&mut self, ctx: &egui::Context) {
+        // Grab the screen before our own window covers it.
+        match ocr::capture_screen() {
+            Ok(screenshot) => {
+                self.capture_source = capture_source();
+                self.capture_window = Some(CaptureWindowState::new(screenshot));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+                self.mode = AppMode::Capturing;
+            }
+            Err(e) => {
+                log::error!("Failed to capture screen: {}", e);
+                self.toasts.error(format!("Could not capture the screen: {}", e));
+            }
+        }
+    }
+    
+    #[cfg(feature = "ocr")]
+    fn update_capture(&mut self, ctx: &egui::Context) {
+        let Some(capture_window) = &mut self.capture_window else {
+            self.mode = AppMode::Hidden;
+            return;
+        };
+        let Some(action) = capture_window.show(ctx) else {
+            return;
+        };
+        
+        if let CaptureAction::Selected(region) = action {
+            ocr::recognize_in_background(region, self.ocr_sender.clone());
+        }
+        self.capture_window = None;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+        self.mode = AppMode::Hidden;
+    }
+    
+    /// Opens the add window with recognized text so OCR mistakes can be fixed
+    /// before saving.
+    #[cfg(feature = "ocr")]
+    fn receive_ocr_results(&mut self) {
+        while let Ok(result) = self.ocr_receiver.try_recv() {
+            match result {
+                Ok(text) if text.is_empty() => self.toasts.error("No text found in the selected region"),
+                Ok(text) => {
+                    self.add_window.prefill(text);
+                    self.add_window.set_source(self.capture_source.take());
+                    self.mode = AppMode::AddingSnippet;
+                }
+                Err(e) => {
+                    log::error!("OCR failed: {}", e);
+                    self.toasts.error(format!("Text recognition failed: {}", e));
+                }
+            }
+        }
+    }
+    
+    /// Title bar text for the current mode, `None` where no bar is drawn.
+    fn window_title(&self) -> Option<&'static str> {
+        match self.mode {
+            AppMode::Hidden => None,
+            AppMode::AddingSnippet if self.add_window.is_editing() => Some("Edit snippet"),
+            AppMode::AddingSnippet => Some("Add snippet"),
+            AppMode::GettingSnippet => Some("Trinket"),
+            AppMode::Statistics => Some("Statistics"),
+            AppMode::History => Some("History"),
+            AppMode::Duplicates => Some("Duplicates"),
+            AppMode::Review => Some("Review"),
+            AppMode::Settings => Some("Settings"),
+            AppMode::Tags => Some("Tags"),
+            AppMode::Trash => Some("Trash"),
+            AppMode::Audit => Some("Access log"),
+            AppMode::Restore => Some("Restore"),
+            AppMode::Import => Some("Import"),
+            AppMode::FolderImport => Some("Import folder"),
+            AppMode::Log => Some("Log"),
+            #[cfg(feature = "vault")]
+            AppMode::Archive => Some(self.archive_window.as_ref().map_or("Archive", ArchiveWindowState::title)),
+            #[cfg(feature = "sync")]
+            AppMode::Pairing => Some("Pair devices"),
+            #[cfg(feature = "sync")]
+            AppMode::Conflicts => Some("Sync conflicts"),
+            // The capture overlay covers the whole screen.
+            #[cfg(feature = "ocr")]
+            AppMode::Capturing => None,
+        }
+    }
+    
+    /// Lists what was wrong in the config file at startup, over whichever
+    /// window is open, until the user carries on or resets it.
+    fn update_config_problems(&mut self, ctx: &egui::Context) {
+        if self.config_problems.is_empty() {
+            return;
+        }
+        let path = Config::default_path();
+        match show_config_problems(ctx, &path, &self.config_problems) {
+            Some(ConfigProblemsAction::ResetToDefaults) => {
+                let kept = path.with_extension("json.bak");
+                let ctx = ctx.clone();
+                self.with_storage(
+                    move |_| std::fs::copy(&path, &kept).map(|_| kept),
+                    move |app, result| match result {
+                        Ok(kept) => app.apply_config(&ctx, Config::default(), move |app| {
+                            app.toasts.info(format!("Settings reset to defaults; the old file is {}", kept.display()));
+                            app.config_problems.clear();
+                        }),
+                        Err(e) => {
+                            log::error!("Failed to keep a copy of the config: {}", e);
+                            app.toasts.error(format!("Could not keep a copy of the config, so it was not reset: {}", e));
+                        }
+                    },
+                );
+            }
+            Some(ConfigProblemsAction::Dismiss) => self.config_problems.clear(),
+            None => {}
+        }
+    }
+    
+    fn show_hotkey_health(&self, ctx: &egui::Context) {
+        let (message, color) = match self.hotkey_health.status() {
+            ListenerStatus::Running => return,
+            ListenerStatus::Restarted { count, last_error } => (
+                format!("Hotkey listener restarted {} time(s) after an error: {}", count, last_error),
+                ctx.style().visuals.warn_fg_color,
+            ),
+            ListenerStatus::Stopped { last_error } => (
+                format!("Global hotkeys stopped working ({}). Restart Trinket to recover.", last_error),
+                ctx.style().visuals.error_fg_color,
+            ),
+        };
+        
+        egui::TopBottomPanel::bottom("hotkey_health").show(ctx, |ui| {
+            ui.colored_label(color, message);
+        });
+    }
+}
+
+impl eframe::App for TrinketApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        #[cfg(any(windows, target_os = "macos"))]
+        self.update_window_effects(ctx, _frame);
+        self.ui(ctx);
+    }
+
+    /// Fully transparent while the backdrop shows, so it isn't dimmed.
+    #[cfg(any(windows, target_os = "macos"))]
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        if self.backdrop {
+            [0.0; 4]
+        } else {
+            egui::Color32::from_rgba_unmultiplied(12, 12, 12, 180).to_normalized_gamma_f32()
+        }
+    }
+}
+
+impl TrinketApp {
+    /// False while the app is hidden, so a runner that owns its surface can
+    /// take it down.
+    #[cfg(all(target_os = "linux", feature = "layer-shell"))]
+    pub fn wants_window(&self) -> bool {
+        !matches!(self.mode, AppMode::Hidden)
+    }
+    
+    /// Runs one frame of the app, whichever runner draws it.
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        if self.snoozed_until.is_some_and(|until| until <= Instant::now()) {
+            self.resume();
+        }
+        if let Ok(event) = self.hotkey_receiver.try_recv() {
+            match event {
+                HotkeyEvent::ToggleSnooze => self.toggle_snooze(),
+                // Picked from the jump list or the Services menu, not pressed.
+                #[cfg(windows)]
+                HotkeyEvent::CopySnippet(id) => self.copy_by_id(&id),
+                #[cfg(target_os = "macos")]
+                HotkeyEvent::SaveSelection(text) => self.save_selection(text),
+                _ if self.snoozed_until.is_some() => log::debug!("Ignored {:?} while snoozed", event),
+                HotkeyEvent::Add => {
+                    self.add_window.set_source(capture_source());
+                    self.mode = AppMode::AddingSnippet;
+                }
+                HotkeyEvent::Get => self.open_get_window(),
+                #[cfg(feature = "ocr")]
+                HotkeyEvent::Capture => self.start_capture(ctx),
+                // Nothing is copied from an excluded app in the first place.
+                #[cfg(feature = "hotkeys")]
+                HotkeyEvent::CaptureSelection if active_app().is_some_and(|app| self.capture_filter.excludes_app(&app.name)) => {
+                    self.toasts.info("Not captured: the capture exclusions leave this app out");
+                }
+                #[cfg(feature = "hotkeys")]
+                HotkeyEvent::CaptureSelection => {
+                    let sender = self.selection_sender.clone();
+                    std::thread::spawn(move || {
+                        let _ = sender.send(capture_selection());
+                    });
+                }
+            }
+        }
+        
+        #[cfg(all(target_os = "macos", feature = "tray"))]
+        self.handle_menu_bar(ctx);
+        #[cfg(feature = "tray")]
+        self.update_tray_tooltip();
+        #[cfg(windows)]
+        self.update_jump_list();
+        
+        #[cfg(feature = "ocr")]
+        self.receive_ocr_results();
+        
+        #[cfg(feature = "hotkeys")]
+        while let Ok(selection) = self.selection_receiver.try_recv() {
+            match selection {
+                Ok(Captured::Text(text)) => self.save_selection(text),
+                Ok(Captured::Concealed) => {
+                    log::info!("Not saving a selection its app marked as private");
+                    self.toasts.info("Not saved: the app marked this copy as private");
+                }
+                Err(e) => {
+                    log::error!("Failed to capture the selection: {}", e);
+                    self.toasts.error(format!("Could not capture the selection: {}", e));
+                }
+            }
+        }
+        
+        self.handle_storage_replies();
+        
+        while let Ok(id) = self.expired_receiver.try_recv() {
+            self.events.publish(SnippetEvent::Deleted { id });
+        }
+        
+        while let Ok((id, title)) = self.title_receiver.try_recv() {
+            self.apply_page_title(&id, title);
+        }
+        
+        while let Ok(e) = self.backup_errors.try_recv() {
+            self.toasts.error(format!("Could not back up the snippets: {}", e));
+        }
+        
+        #[cfg(feature = "spellcheck")]
+        while let Ok(result) = self.dictionary_receiver.try_recv() {
+            match result {
+                Ok(speller) => self.add_window.set_spellchecker(Some(speller)),
+                // Most systems have no dictionary until one is installed.
+                Err(e @ SpellcheckError::NotFound(_)) => log::info!("Spellchecking is off: {}", e),
+                Err(e) => {
+                    log::error!("Failed to load the spelling dictionary: {}", e);
+                    self.toasts.error(format!("Could not load the spelling dictionary: {}", e));
+                }
+            }
+        }
+        
+        #[cfg(feature = "sync")]
+        {
+            self.handle_lan_events();
+            self.show_sync_status();
+        }
+        
+        while let Ok(event) = self.session_receiver.try_recv() {
+            match event {
+                SessionEvent::Locked => self.session_locked(ctx),
+                #[cfg(feature = "vault")]
+                SessionEvent::Unlocked => self.unlock_vault(),
+                #[cfg(not(feature = "vault"))]
+                SessionEvent::Unlocked => {}
+            }
+        }
+        
+        // Confirmation lasts until the window hides.
+        if matches!(self.mode, AppMode::Hidden) && (self.secrets_unlocked || self.pending_secret.is_some()) {
+            self.pending_secret = None;
+            self.set_secrets_unlocked(false);
+        }
+        while let Ok(result) = self.auth_receiver.try_recv() {
+            let Some(wanted) = self.pending_secret.take() else {
+                continue;
+            };
+            match result {
+                Ok(true) => {
+                    self.set_secrets_unlocked(true);
+                    self.use_secret(wanted);
+                }
+                Ok(false) => log::info!("Access to secret snippets was not confirmed"),
+                Err(e) => {
+                    log::error!("Failed to confirm the user: {}", e);
+                    self.toasts.error(format!("Could not confirm it's you: {}", e));
+                }
+            }
+        }
+        
+        if let Some(title) = self.window_title() {
+            match show_title_bar(ctx, title, self.config.always_on_top) {
+                Some(TitleBarAction::ToggleAlwaysOnTop) => {
+                    self.config.always_on_top = !self.config.always_on_top;
+                    ctx.send_viewport_cmd(window_level(&self.config));
+                    self.save_config();
+                }
+                Some(TitleBarAction::Minimize) => ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true)),
+                Some(TitleBarAction::Close) if self.resident => self.mode = AppMode::Hidden,
+                Some(TitleBarAction::Close) => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+                None => {}
+            }
+        }
+        if !matches!(self.mode, AppMode::Hidden) {
+            self.show_hotkey_health(ctx);
+            self.update_config_problems(ctx);
+        }
+        
+        match self.mode {
+            AppMode::Hidden if !self.resident => {
+                // Nothing could show the window again, so stay on the get
+                // window instead.
+                self.open_get_window();
+                ctx.request_repaint();
+            }
+            AppMode::Hidden => {
+                // Window is controlled by hotkey events
+            }
+            AppMode::AddingSnippet => {
+                let new_snippet = self.add_window.show(ctx, &self.folders);
+                if self.add_window.wraps() != self.config.word_wrap {
+                    self.set_word_wrap(self.add_window.wraps());
+                }
+                if let Some(new_snippet) = new_snippet {
+                    if new_snippet.content.is_empty() {
+                        // Nothing to save.
+                    } else if let Some(id) = new_snippet.editing.clone() {
+                        self.apply_edit(&id, new_snippet);
+                    } else {
+                        self.save_new(new_snippet);
+                    }
+                    
+                    self.mode = AppMode::Hidden;
+                }
+            }
+            AppMode::GettingSnippet => {
+                let undo_label = self.undo.peek_label();
+                let action = self.get_window.show(ctx, &self.snippets, self.search.as_mut(), &self.folders, undo_label, self.search_history.queries(), &self.usage);
+                if self.get_window.table_layout() != &self.config.table_layout {
+                    self.config.table_layout = self.get_window.table_layout().clone();
+                    self.save_config();
+                }
+                match action {
+                    Some(GetWindowAction::Copy(index)) => {
+                        let text = self.snippets[index].content.clone();
+                        self.copy(&[index], text);
+                    }
+                    Some(GetWindowAction::CopyPlain(index)) => {
+                        let text = checklist::to_plain(&self.snippets[index].content);
+                        self.copy(&[index], text);
+                    }
+                    Some(GetWindowAction::CopyTransformed(index, pipeline)) => {
+                        match pipeline.apply(&self.snippets[index].content) {
+                            Ok(text) => self.copy(&[index], text),
+                            Err(e) => self.toasts.error(format!("Could not transform snippet: {}", e)),
+                        }
+                    }
+                    Some(GetWindowAction::CopyCombined(indices, text)) => self.copy(&indices, text),
+                    Some(GetWindowAction::ToggleChecklistItem(index, line)) => {
+                        let content = checklist::toggle_item(&self.snippets[index].content, line);
+                        self.update_checklist(index, content);
+                    }
+                    Some(GetWindowAction::ResetChecklist(index)) => {
+                        let content = checklist::reset(&self.snippets[index].content);
+                        self.update_checklist(index, content);
+                    }
+                    Some(GetWindowAction::Edit(index)) if self.needs_confirmation(index) => {
+                        self.confirm_secret(SecretUse::Edit(self.snippets[index].id.clone()));
+                    }
+                    Some(GetWindowAction::Edit(index)) => {
+                        self.add_window.edit(&self.snippets[index]);
+                        self.mode = AppMode::AddingSnippet;
+                    }
+                    Some(GetWindowAction::Duplicate(index)) if self.needs_confirmation(index) => {
+                        self.confirm_secret(SecretUse::Duplicate(self.snippets[index].id.clone()));
+                    }
+                    Some(GetWindowAction::Duplicate(index)) => self.duplicate(index),
+                    Some(GetWindowAction::Delete(index)) => self.delete(index),
+                    Some(GetWindowAction::ShowHistory(index)) if self.needs_confirmation(index) => {
+                        self.confirm_secret(SecretUse::History(self.snippets[index].id.clone()));
+                    }
+                    Some(GetWindowAction::ShowHistory(index)) => self.open_history(index),
+                    Some(GetWindowAction::RevealSecret(id)) => {
+                        match self.snippets.iter().position(|snippet| snippet.id == id) {
+                            Some(index) if self.needs_confirmation(index) => self.confirm_secret(SecretUse::Reveal(id)),
+                            _ => self.reveal_secret(id),
+                        }
+                    }
+                    Some(GetWindowAction::ToggleLock(index)) => {
+                        self.save_meta(index, |meta| meta.locked = !meta.locked);
+                    }
+                    Some(GetWindowAction::ToggleArchived(index)) => {
+                        self.save_meta(index, |meta| meta.archived = !meta.archived);
+                    }
+                    Some(GetWindowAction::ToggleSecret(index)) => {
+                        self.save_meta(index, |meta| meta.secret = !meta.secret);
+                    }
+                    Some(GetWindowAction::SetWordWrap(wrap)) => self.set_word_wrap(wrap),
+                    Some(GetWindowAction::Bulk(indices, op)) => self.apply_bulk(&indices, op),
+                    Some(GetWindowAction::Undo) => self.undo(),
+                    Some(GetWindowAction::OpenReview) => self.open_review(),
+                    Some(GetWindowAction::ExportMarkdown(indices)) => self.export_results(&indices),
+                    Some(GetWindowAction::OpenDuplicates) => {
+                        self.duplicates_window = Some(DuplicatesWindowState::new(&self.snippets, &self.usage));
+                        self.mode = AppMode::Duplicates;
+                    }
+                    Some(GetWindowAction::CreateFromQuery(text)) => {
+                        self.add_window.prefill(text);
+                        self.mode = AppMode::AddingSnippet;
+                    }
+                    Some(GetWindowAction::SetAppendTarget(index)) => {
+                        let id = index.map(|index| self.snippets[index].id.clone());
+                        self.set_append_target(id);
+                    }
+                    Some(GetWindowAction::MovePinned(index, up)) => self.move_pinned(index, up),
+                    Some(GetWindowAction::OpenTags) => self.mode = AppMode::Tags,
+                    Some(GetWindowAction::OpenTrash) => self.open_trash(),
+                    Some(GetWindowAction::OpenAudit) => self.open_audit(),
+                    Some(GetWindowAction::OpenRestore) => self.open_restore(),
+                    Some(GetWindowAction::OpenImport) => {
+                        self.import_window = Some(ImportWindowState::new());
+                        self.mode = AppMode::Import;
+                    }
+                    Some(GetWindowAction::OpenFolderImport) => {
+                        self.folder_import_window = Some(FolderImportWindowState::new());
+                        self.mode = AppMode::FolderImport;
+                    }
+                    Some(GetWindowAction::OpenLog) => self.open_log(),
+                    #[cfg(not(feature = "hotkeys"))]
+                    Some(GetWindowAction::NewSnippet) => {
+                        self.add_window.set_source(None);
+                        self.mode = AppMode::AddingSnippet;
+                    }
+                    #[cfg(feature = "vault")]
+                    Some(GetWindowAction::ExportArchive) => {
+                        let path = dirs::download_dir()
+                            .or_else(dirs::home_dir)
+                            .unwrap_or_else(|| PathBuf::from("."))
+                            .join(format!("trinket-{}.{}", chrono::Local::now().format("%Y-%m-%d"), ARCHIVE_EXTENSION));
+                        self.archive_window = Some(ArchiveWindowState::export(path));
+                        self.mode = AppMode::Archive;
+                    }
+                    #[cfg(feature = "vault")]
+                    Some(GetWindowAction::ImportArchive) => {
+                        self.archive_window = Some(ArchiveWindowState::import());
+                        self.mode = AppMode::Archive;
+                    }
+                    #[cfg(feature = "sync")]
+                    Some(GetWindowAction::OpenPairing) => {
+                        self.pairing_window = Some(PairingWindowState::new());
+                        self.mode = AppMode::Pairing;
+                    }
+                    #[cfg(feature = "sync")]
+                    Some(GetWindowAction::OpenConflicts) => {
+                        self.conflict_window = Some(ConflictWindowState::new());
+                        self.mode = AppMode::Conflicts;
+                    }
+                    #[cfg(feature = "sync")]
+                    Some(GetWindowAction::SyncNow) => {
+                        if let Some(lan) = &self.lan {
+                            lan.sync_now();
+                        }
+                        #[cfg(feature = "s3")]
+                        if let Some(s3) = &self.s3 {
+                            s3.sync_now();
+                        }
+                    }
+                    #[cfg(feature = "sync")]
+                    Some(GetWindowAction::DismissSyncError) => {
+                        if let Some(lan) = &self.lan {
+                            lan.dismiss_error();
+                        }
+                    }
+                    Some(GetWindowAction::UnlockFolder(folder, passphrase)) => self.unlock_folder(&folder, passphrase),
+                    Some(GetWindowAction::OpenSettings) => {
+                        self.settings_window = Some(SettingsWindowState::new(&self.config));
+                        self.mode = AppMode::Settings;
+                    }
+                    #[cfg(any(feature = "hotkeys", feature = "tray"))]
+                    Some(GetWindowAction::Snooze(minutes)) => {
+                        self.snooze(minutes);
+                        self.mode = AppMode::Hidden;
+                    }
+                    Some(GetWindowAction::OpenStatistics) => {
+                        self.mode = AppMode::Statistics;
+                    }
+                    Some(GetWindowAction::Close) => {
+                        self.mode = AppMode::Hidden;
+                    }
+                    None => {}
+                }
+            }
+            AppMode::Statistics => {
+                match self.stats_window.show(ctx, &self.snippets, &self.usage, self.metrics.as_ref()) {
+                    Some(StatsAction::ClearMetrics) => {
+                        if let Some(Err(e)) = self.metrics.as_mut().map(Metrics::clear) {
+                            log::error!("Failed to clear metrics: {}", e);
+                            self.toasts.error(format!("Could not clear the metrics: {}", e));
+                        }
+                    }
+                    Some(StatsAction::Back) => self.mode = AppMode::GettingSnippet,
+                    Some(StatsAction::Close) => self.mode = AppMode::Hidden,
+                    None => {}
+                }
+            }
+            AppMode::History => self.update_history(ctx),
+            AppMode::Duplicates => self.update_duplicates(ctx),
+            AppMode::Review => self.update_review(ctx),
+            AppMode::Settings => self.update_settings(ctx),
+            AppMode::Tags => self.update_tags(ctx),
+            AppMode::Trash => self.update_trash(ctx),
+            AppMode::Audit => self.update_audit(ctx),
+            AppMode::Restore => self.update_restore(ctx),
+            AppMode::Import => self.update_import(ctx),
+            AppMode::FolderImport => self.update_folder_import(ctx),
+            AppMode::Log => self.update_log(ctx),
+            #[cfg(feature = "vault")]
+            AppMode::Archive => self.update_archive(ctx),
+            #[cfg(feature = "sync")]
+            AppMode::Pairing => self.update_pairing(ctx),
+            #[cfg(feature = "sync")]
+            AppMode::Conflicts => self.update_conflicts(ctx),
+            #[cfg(feature = "ocr")]
+            AppMode::Capturing => self.update_capture(ctx),
+        }
+        
+        self.handle_snippet_events();
+        self.toasts.show(ctx);
+        
+        ctx.request_repaint();
+    }
+}
+/// The focused app, recorded with a snippet captured from it.
+fn capture_source() -> Option<SnippetSource> {
+    active_app().map(|app| SnippetSource { app: app.name, window: app.title })
+}
+
+/// Opens the library with its keys, then recovers unsaved captures,
+/// upgrades the layout and reads the snippets. Runs on the storage thread;
+/// the reply shows the library and whatever went wrong on the way.
+fn open_library(storage_path: PathBuf, config: &Config) -> (FileStorage, StorageReply) {
+    let mut problems = Vec::new();
+    // A library that can't be written is still worth reading from.
+    let storage = match FileStorage::new(storage_path.clone()) {
+        Ok(storage) => storage,
+        Err(e) => {
+            log::error!("Opening the library read-only: {}", e);
+            problems.push(format!("The library is read-only, so nothing can be saved: {}", e));
+            FileStorage::read_only(storage_path)
+        }
+    };
+    let (mut storage, vault_error) = open_vault(storage, config);
+    if let Some(e) = vault_error {
+        problems.push(format!("Could not open the vault; saving is disabled: {}", e));
+    }
+    for (folder, _) in folder_vaults(config) {
+        storage.add_encrypted_folder(folder);
+    }
+    #[cfg(feature = "vault")]
+    let folder_errors = open_folder_vaults(&mut storage, config);
+    #[cfg(not(feature = "vault"))]
+    let folder_errors: Vec<String> = storage.locked_folders().into_iter()
+        .map(|folder| format!("{}: this build has no vault support", folder))
+        .collect();
+    problems.extend(folder_errors.into_iter().map(|e| format!("Could not open an encrypted folder: {}", e)));
+    storage.set_secure_delete(config.secure_delete);
+    storage.set_max_snippet_bytes(config.max_snippet_bytes);
+    match storage.replay_journal() {
+        Ok(0) => {}
+        Ok(count) => log::info!("Recovered {} unsaved capture(s) from journal", count),
+        Err(e) => log::error!("Failed to replay capture journal: {}", e),
+    }
+    if let Err(e) = storage.migrate(&config.backup_dir()) {
+        log::error!("Failed to migrate the library: {}", e);
+        problems.push(format!("Could not upgrade the library's storage format: {}", e));
+    }
+    
+    let library = read_library(&storage);
+    let reply: StorageReply = Box::new(move |app| {
+        app.set_library(library);
+        for problem in problems {
+            app.toasts.error(problem);
+        }
+    });
+    (storage, reply)
+}
+
+/// The copy counts kept with the library, with those made on synced
+/// devices.
+fn open_usage(storage_path: &Path) -> UsageLog {
+    let usage_path = storage_path.join(USAGE_FILE);
+    #[cfg_attr(not(feature = "sync"), allow(unused_mut))]
+    let mut usage = UsageLog::open(usage_path.clone()).unwrap_or_else(|e| {
+        log::error!("Failed to read usage log: {}", e);
+        UsageLog::empty(usage_path)
+    });
+    #[cfg(feature = "sync")]
+    usage.set_synced(ClockStore::load(storage_path).synced_uses());
+    usage
+}
+
+/// Backups in `dir`, newest first, with their snippet counts.
+fn backup_entries(dir: &Path) -> Result<Vec<BackupEntry>, std::io::Error> {
+    Ok(backup::list_backups(dir)?
+        .into_iter()
+        .map(|backup| {
+            let snippets = backup::count_snippets(&backup.path)
+                .inspect_err(|e| log::warn!("Failed to read backup {}: {}", backup.path.display(), e))
+                .ok();
+            BackupEntry { backup, snippets }
+        })
+        .collect())
+}
+
+fn read_library(storage: &FileStorage) -> Library {
+    Library {
+        snippets: storage.load_all_snippets().unwrap_or_default(),
+        folders: storage.list_folders().unwrap_or_default(),
+        locked_folders: storage.locked_folders(),
+    }
+}
+
+/// Attaches the vault named in the config, if any. When it can't be opened
+/// the storage refuses to write content rather than save it unencrypted.
+fn open_vault(storage: FileStorage, config: &Config) -> (FileStorage, Option<String>) {
+    if config.vault_identity_file.is_none() && !config.vault_identity_in_keychain {
+        return (storage, None);
+    }
+    #[cfg(feature = "vault")]
+    let result = load_vault(config);
+    #[cfg(not(feature = "vault"))]
+    let result: Result<(), String> = Err("a vault is configured but this build has no vault support".to_string());
+    match result {
+        #[cfg(feature = "vault")]
+        Ok(vault) => (storage.with_vault(vault), None),
+        #[cfg(not(feature = "vault"))]
+        Ok(()) => (storage, None),
+        Err(e) => {
+            log::error!("Failed to open vault: {}", e);
+            (storage.require_vault(), Some(e.to_string()))
+        }
+    }
+}
+
+/// Folders with keys of their own, by normalized folder path.
+fn folder_vaults(config: &Config) -> impl Iterator<Item = (String, &FolderVault)> {
+    config.folder_vaults.iter().filter_map(|(folder, folder_vault)| match normalize_folder(folder) {
+        Ok(normalized) if !normalized.is_empty() => Some((normalized, folder_vault)),
+        _ => {
+            log::warn!("Ignoring folder vault for '{}': not a folder", folder);
+            None
+        }
+    })
+}
+
+/// Opens the keys of encrypted folders that have an identity file and
+/// aren't unlocked yet. Returns why any of them couldn't be opened.
+#[cfg(feature = "vault")]
+fn open_folder_vaults(storage: &mut FileStorage, config: &Config) -> Vec<String> {
+    let mut errors = Vec::new();
+    let locked = storage.locked_folders();
+    for (folder, folder_vault) in folder_vaults(config).filter(|(folder, _)| locked.contains(folder)) {
+        let Some(identity_file) = folder_vault.identity_file.as_deref() else {
+            continue;
+        };
+        match Vault::open(identity_file, folder_vault.recipients_file.as_deref()) {
+            Ok(vault) => storage.unlock_folder(&folder, vault),
+            Err(e) => {
+                log::error!("Failed to open vault for folder {}: {}", folder, e);
+                errors.push(format!("{}: {}", folder, e));
+            }
+        }
+    }
+    errors
+}
+
+/// Opens the configured vault from its identity file or the keychain.
+#[cfg(feature = "vault")]
+fn load_vault(config: &Config) -> Result<Vault, VaultError> {
+    match config.vault_identity_file.as_deref() {
+        Some(identity_file) if !config.vault_identity_in_keychain => {
+            Vault::open(identity_file, config.vault_recipients_file.as_deref())
+        }
+        identity_file => open_keychain_vault(identity_file, config),
+    }
+}
+
+#[cfg(feature = "vault")]
+fn open_keychain_vault(identity_file: Option<&std::path::Path>, config: &Config) -> Result<Vault, VaultError> {
+    #[cfg(feature = "keychain")]
+    return Vault::open_keychain(identity_file, config.vault_recipients_file.as_deref());
+    #[cfg(not(feature = "keychain"))]
+    {
+        let _ = (identity_file, config);
+        Err(std::io::Error::other("the vault identity is set to live in the keychain but this build has no keychain support").into())
+    }
+}
+
+/// Starts syncing through the configured bucket, if there is one,
+/// reporting failures as a toast.
+#[cfg(feature = "s3")]
+fn start_s3_sync(storage_path: &Path, config: &Config, toasts: &mut Toasts) -> Option<S3Sync> {
+    let target = config.s3.as_ref()?;
+    let result = DeviceKey::load_or_create(&DeviceKey::default_path())
+        .and_then(|key| S3Sync::start(target, key.id(), storage_path.to_path_buf(), config.folder_sync.clone()));
+    match result {
+        Ok(s3) => Some(s3),
+        Err(e) => {
+            log::error!("Failed to start S3 sync: {}", e);
+            toasts.error(format!("Could not start S3 sync: {}", e));
+            None
+        }
+    }
+}
+
+/// Starts LAN sync with this device's key, reporting failures as a toast.
+#[cfg(feature = "sync")]
+fn start_lan_sync(storage_path: &Path, config: &Config, toasts: &mut Toasts) -> Option<LanSync> {
+    let result = DeviceKey::load_or_create(&DeviceKey::default_path())
+        .and_then(|key| LanSync::start(key, config.lan_sync_port, config.sync_peers.clone(), config.companions.clone(), storage_path.to_path_buf()));
+    match result {
+        Ok(lan) => {
+            lan.set_folder_sync(config.folder_sync.clone());
+            Some(lan)
+        }
+        Err(e) => {
+            log::error!("Failed to start LAN sync: {}", e);
+            toasts.error(format!("Could not start LAN sync: {}", e));
+            None
+        }
+    }
+}
+
+fn open_metrics() -> Metrics {
+    Metrics::open(trinket_core::data_dir().join(METRICS_FILE))
+}
+
+/// The tray tooltip's line for a snooze with `left` to go, rounded up to
+/// the minute.
+#[cfg(feature = "tray")]
+fn snooze_summary(left: Duration) -> String {
+    let minutes = left.as_secs().div_ceil(60);
+    if minutes < 60 {
+        format!("Snoozed, back in {} min", minutes)
+    } else if minutes.is_multiple_of(60) {
+        format!("Snoozed, back in {} h", minutes / 60)
+    } else {
+        format!("Snoozed, back in {} h {} min", minutes / 60, minutes % 60)
+    }
+}
+
+fn list_density(config: &Config) -> ListDensity {
+    ListDensity {
+        row_height: config.row_height,
+        preview_chars: config.preview_chars,
+    }
+}
+
+fn window_level(config: &Config) -> egui::ViewportCommand {
+    egui::ViewportCommand::WindowLevel(if config.always_on_top {
+        egui::WindowLevel::AlwaysOnTop
+    } else {
+        egui::WindowLevel::Normal
+    })
+}
@@ -1,7 +1,56 @@
 use arboard::Clipboard;
+use std::time::Duration;
+use zeroize::Zeroizing;
 
-pub fn copy_to_clipboard(text: &str) -> Result<(), Box<dyn std::error::Error>> {
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Copies `text`, retrying with exponential backoff while the clipboard is
+/// transiently unavailable (held by another process on Windows, no owner yet
+/// on X11). Gives up after `MAX_ATTEMPTS` and returns the last error.
+pub fn copy_to_clipboard(text: &str) -> Result<(), arboard::Error> {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 1;
+    
+    loop {
+        match try_copy(text) {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < MAX_ATTEMPTS && is_transient(&e) => {
+                log::warn!("Clipboard copy attempt {} failed, retrying: {}", attempt, e);
+                std::thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn try_copy(text: &str) -> Result<(), arboard::Error> {
     let mut clipboard = Clipboard::new()?;
     clipboard.set_text(text)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+fn is_transient(error: &arboard::Error) -> bool {
+    !matches!(error, arboard::Error::ClipboardNotSupported | arboard::Error::ConversionFailure)
+}
+
+/// Clears the clipboard after `delay`, unless something else was copied in
+/// the meantime. Used so copied secrets don't linger; our copy of the text
+/// is scrubbed from memory afterwards.
+pub fn clear_clipboard_after(text: String, delay: Duration) {
+    let text = Zeroizing::new(text);
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let Ok(mut clipboard) = Clipboard::new() else {
+            return;
+        };
+        if clipboard.get_text().map(Zeroizing::new).is_ok_and(|current| current == text) {
+            match clipboard.clear() {
+                Ok(()) => log::info!("Cleared secret from clipboard"),
+                Err(e) => log::warn!("Failed to clear clipboard: {}", e),
+            }
+        }
+    });
+}
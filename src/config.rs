@@ -0,0 +1,435 @@
+use serde::{Deserialize, Serialize};
+
+use crate::keymap::{parse_sequence, Command, Profile};
+use crate::redaction::RedactionMode;
+use crate::storage::backup::BackupSettings;
+use crate::storage::{SearchProviderKind, DEFAULT_MAX_SNIPPET_BYTES};
+use crate::ui::{ListLayout, TableLayout};
+pub use trinket_core::library_settings::FolderVault;
+pub use trinket_core::sync_settings::{FolderSync, S3Target, SyncPeer};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_SECRET_CLEAR_SECS: u64 = 30;
+const DEFAULT_ROW_HEIGHT: f32 = 25.0;
+const DEFAULT_PREVIEW_CHARS: usize = 80;
+const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+const DEFAULT_LAN_SYNC_PORT: u16 = 47478;
+const DEFAULT_BACKUP_INTERVAL_HOURS: u32 = 24;
+const DEFAULT_BACKUPS_KEPT: usize = 14;
+const DEFAULT_SNOOZE_MINUTES: u32 = 60;
+/// Stands in for what [`Config::redacted`] leaves out.
+const REDACTED: &str = "<redacted>";
+
+/// Limits the settings window offers; values outside them in the file are
+/// reported and replaced by the default.
+pub const ROW_HEIGHT_RANGE: RangeInclusive<f32> = 16.0..=64.0;
+pub const UI_SCALE_RANGE: RangeInclusive<f32> = 0.75..=2.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Snippets larger than this aren't saved without an explicit "save
+    /// anyway" in the add window.
+    pub max_snippet_bytes: usize,
+    /// Seconds before a copied secret is wiped from the clipboard; 0 keeps it.
+    pub secret_clipboard_clear_secs: u64,
+    /// Ask Windows Hello, Touch ID or polkit before a secret snippet is
+    /// revealed, copied or edited; once is enough until the window hides.
+    pub confirm_secrets: bool,
+    /// Erase deleted secret snippets right away instead of trashing them.
+    /// Their files are overwritten before removal either way.
+    pub secure_delete: bool,
+    /// What to do when a snippet being saved looks like it holds a card
+    /// number, cloud key or token. Secret snippets are never touched.
+    pub redaction: RedactionMode,
+    /// Extra patterns to look for, as `"name": "regex"`. Only the first
+    /// capture group is masked when the regex has one.
+    pub redaction_patterns: BTreeMap<String, String>,
+    /// Apps the selection hotkey and the Services menu never save from,
+    /// such as password managers, by the app name a snippet's source shows;
+    /// case doesn't matter.
+    pub capture_excluded_apps: Vec<String>,
+    /// Regexes; a captured selection matching any of them isn't saved.
+    pub capture_excluded_patterns: Vec<String>,
+    /// Look up the page title when a snippet is a single link. Off means
+    /// no network requests at all.
+    pub fetch_link_titles: bool,
+    /// Count captures, searches and time to copy per day, for the
+    /// statistics view. Kept on this device and never sent anywhere.
+    pub local_metrics: bool,
+    /// Days a deleted snippet stays in the trash before it is removed for
+    /// good; 0 keeps it until the trash is emptied.
+    pub trash_retention_days: u32,
+    /// Months a snippet may go uncopied and unchanged before opening the
+    /// get window suggests reviewing it; 0 never suggests.
+    pub review_after_months: u32,
+    /// Hours between automatic backups of the snippet folder; 0 turns them
+    /// off.
+    pub backup_interval_hours: u32,
+    /// Where backups are written; unset keeps them in `backups` next to the
+    /// config file.
+    pub backup_dir: Option<PathBuf>,
+    /// Newest backups to keep; older ones are deleted. 0 keeps them all.
+    pub backups_kept: usize,
+    /// How long the snooze hotkey and tray icon put the hotkeys and
+    /// notifications to sleep, in minutes.
+    pub snooze_minutes: u32,
+    /// How the get window matches the search text: `substring`, `fuzzy`
+    /// or both, ranking a snippet by the better of the two.
+    pub search_providers: Vec<SearchProviderKind>,
+    /// Table with columns and side panels, or the compact launcher.
+    pub list_layout: ListLayout,
+    /// Column widths, hidden columns and sort order of the table, as last left.
+    pub table_layout: TableLayout,
+    /// Height of a row in the snippet list, in points.
+    pub row_height: f32,
+    /// Characters of each snippet's title shown in the list.
+    pub preview_chars: usize,
+    /// Zoom applied to every window, text included; 1.0 is the system size.
+    pub ui_scale: f32,
+    /// Keep windows above all others.
+    pub always_on_top: bool,
+    /// Wrap long lines in the editor and preview; off, they scroll sideways.
+    pub word_wrap: bool,
+    /// Hunspell dictionary the add window checks spelling with, such as
+    /// `en_US`. Needs the `spellcheck` feature.
+    pub spellcheck_language: String,
+    /// On wlroots-based Wayland compositors, show the windows as an overlay
+    /// layer, above fullscreen apps and centred by the compositor, since
+    /// Wayland ignores `always_on_top`. Read at startup. Needs the
+    /// `layer-shell` feature.
+    pub layer_shell: bool,
+    /// Blur the desktop behind the window: acrylic on Windows 11, a
+    /// vibrant panel on macOS. Ignored elsewhere.
+    pub window_effects: bool,
+    /// Default key bindings, before `keybindings` overrides.
+    pub keymap_profile: Profile,
+    /// Shortcut overrides such as `"undo": "Ctrl+Shift+Z"`; unlisted commands
+    /// keep their default keys.
+    pub keybindings: BTreeMap<Command, String>,
+    /// age identity file that unlocks the encrypted vault, created on first
+    /// use. Unset keeps snippets in plain text. Needs the `vault` feature.
+    pub vault_identity_file: Option<PathBuf>,
+    /// Keep the vault identity in the OS keychain. An existing
+    /// `vault_identity_file` is copied there on first use, after which the
+    /// file can be deleted. Needs the `keychain` feature.
+    pub vault_identity_in_keychain: bool,
+    /// Further age recipients who can read the vault, one per line: team
+    /// members' keys, SSH keys or hardware keys such as `age1yubikey1…`.
+    pub vault_recipients_file: Option<PathBuf>,
+    /// Folders encrypted with a key of their own, by folder path. Their
+    /// subfolders share the key; other folders stay in plain text unless the
+    /// vault above is set. Needs the `vault` feature.
+    pub folder_vaults: BTreeMap<String, FolderVault>,
+    /// Sync with paired devices on the local network. Needs the `sync`
+    /// feature.
+    pub lan_sync: bool,
+    /// TCP port paired devices connect to.
+    pub lan_sync_port: u16,
+    /// Devices paired for LAN sync, added from the pairing window.
+    pub sync_peers: Vec<SyncPeer>,
+    /// Phones paired as companions, which can push text and search
+    /// snippets but don't sync the library.
+    pub companions: Vec<SyncPeer>,
+    /// Which paired devices each folder syncs with, by folder path, with
+    /// `""` for the root. Subfolders follow the nearest folder above them
+    /// that has an entry; without one a folder syncs with every device.
+    pub folder_sync: BTreeMap<String, FolderSync>,
+    /// An S3-compatible bucket to sync through and keep backups in. Its
+    /// credentials live in the keychain. Needs the `s3` feature.
+    pub s3: Option<S3Target>,
+}
+
+/// Something wrong in the config file, found as it was loaded. The setting
+/// named is left at its default unless the message says otherwise.
+#[derive(Debug, Clone)]
+pub struct ConfigProblem {
+    /// The setting, like `ui_scale`; `None` when the whole file is unusable.
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    fn new(key: &str, message: impl Into<String>) -> Self {
+        Self { key: Some(key.to_string()), message: message.into() }
+    }
+
+    fn file(message: impl Into<String>) -> Self {
+        Self { key: None, message: message.into() }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_snippet_bytes: DEFAULT_MAX_SNIPPET_BYTES,
+            secret_clipboard_clear_secs: DEFAULT_SECRET_CLEAR_SECS,
+            confirm_secrets: false,
+            secure_delete: false,
+            redaction: RedactionMode::default(),
+            redaction_patterns: BTreeMap::new(),
+            capture_excluded_apps: Vec::new(),
+            capture_excluded_patterns: Vec::new(),
+            fetch_link_titles: true,
+            local_metrics: false,
+            trash_retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+            review_after_months: 0,
+            backup_interval_hours: DEFAULT_BACKUP_INTERVAL_HOURS,
+            backup_dir: None,
+            backups_kept: DEFAULT_BACKUPS_KEPT,
+            snooze_minutes: DEFAULT_SNOOZE_MINUTES,
+            search_providers: vec![SearchProviderKind::Substring],
+            list_layout: ListLayout::default(),
+            table_layout: TableLayout::default(),
+            row_height: DEFAULT_ROW_HEIGHT,
+            preview_chars: DEFAULT_PREVIEW_CHARS,
+            ui_scale: 1.0,
+            always_on_top: true,
+            word_wrap: true,
+            spellcheck_language: "en_US".to_string(),
+            layer_shell: false,
+            window_effects: true,
+            keymap_profile: Profile::default(),
+            keybindings: BTreeMap::new(),
+            vault_identity_file: None,
+            vault_identity_in_keychain: false,
+            vault_recipients_file: None,
+            folder_vaults: BTreeMap::new(),
+            lan_sync: false,
+            lan_sync_port: DEFAULT_LAN_SYNC_PORT,
+            sync_peers: Vec::new(),
+            companions: Vec::new(),
+            folder_sync: BTreeMap::new(),
+            s3: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn default_path() -> PathBuf {
+        trinket_core::config_path()
+    }
+
+    pub fn backup_dir(&self) -> PathBuf {
+        self.backup_dir.clone().unwrap_or_else(|| Self::default_path().with_file_name("backups"))
+    }
+
+    pub fn backup_settings(&self) -> BackupSettings {
+        BackupSettings {
+            dir: self.backup_dir(),
+            interval_hours: self.backup_interval_hours,
+            kept: self.backups_kept,
+        }
+    }
+
+    /// Loads the config, keeping every setting that is valid and listing
+    /// what was wrong with the rest, so one typo doesn't reset everything.
+    /// A missing file is the defaults, with nothing to report.
+    pub fn load_checked(path: &Path) -> (Self, Vec<ConfigProblem>) {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (Self::default(), Vec::new()),
+            Err(e) => return (Self::default(), vec![ConfigProblem::file(format!("Could not read it: {}", e))]),
+        };
+        let (config, problems) = match serde_json::from_str(&text) {
+            Ok(Value::Object(entries)) => Self::from_entries(entries),
+            Ok(_) => (Self::default(), vec![ConfigProblem::file("It is not a JSON object")]),
+            Err(e) => (Self::default(), vec![ConfigProblem::file(format!("It is not valid JSON: {}", e))]),
+        };
+        for problem in &problems {
+            log::warn!("Config {}: {}: {}", path.display(), problem.key.as_deref().unwrap_or("file"), problem.message);
+        }
+        (config, problems)
+    }
+
+    /// Takes the settings one at a time over the defaults, dropping the
+    /// unknown ones and those that don't fit their type.
+    fn from_entries(entries: Map<String, Value>) -> (Self, Vec<ConfigProblem>) {
+        let Ok(Value::Object(defaults)) = serde_json::to_value(Self::default()) else {
+            unreachable!("the config serializes to an object");
+        };
+        let mut problems = Vec::new();
+        let mut merged = defaults.clone();
+        for (key, value) in entries {
+            if !defaults.contains_key(&key) {
+                problems.push(ConfigProblem::new(&key, "Not a known setting; it is ignored"));
+                continue;
+            }
+            let mut trial = merged.clone();
+            trial.insert(key.clone(), value);
+            match serde_json::from_value::<Self>(Value::Object(trial.clone())) {
+                Ok(_) => merged = trial,
+                Err(e) => problems.push(ConfigProblem::new(&key, e.to_string())),
+            }
+        }
+        let mut config: Self = serde_json::from_value(Value::Object(merged)).unwrap_or_default();
+        config.check(&mut problems);
+        (config, problems)
+    }
+
+    /// Checks what the types alone don't: key combinations, patterns,
+    /// ranges and paths.
+    fn check(&mut self, problems: &mut Vec<ConfigProblem>) {
+        self.keybindings.retain(|command, keys| {
+            let valid = parse_sequence(keys).is_some();
+            if !valid {
+                problems.push(ConfigProblem::new(
+                    "keybindings",
+                    format!("\"{}\" for {} is not a key combination like Ctrl+Shift+Z", keys, command.label()),
+                ));
+            }
+            valid
+        });
+        self.redaction_patterns.retain(|name, pattern| match Regex::new(pattern) {
+            Ok(_) => true,
+            Err(e) => {
+                problems.push(ConfigProblem::new("redaction_patterns", format!("{}: {}", name, e)));
+                false
+            }
+        });
+        self.capture_excluded_patterns.retain(|pattern| match Regex::new(pattern) {
+            Ok(_) => true,
+            Err(e) => {
+                problems.push(ConfigProblem::new("capture_excluded_patterns", format!("{}: {}", pattern, e)));
+                false
+            }
+        });
+        let defaults = Self::default();
+        if !ROW_HEIGHT_RANGE.contains(&self.row_height) {
+            problems.push(ConfigProblem::new("row_height", format!(
+                "{} is outside {} to {}", self.row_height, ROW_HEIGHT_RANGE.start(), ROW_HEIGHT_RANGE.end(),
+            )));
+            self.row_height = defaults.row_height;
+        }
+        if !UI_SCALE_RANGE.contains(&self.ui_scale) {
+            problems.push(ConfigProblem::new("ui_scale", format!(
+                "{} is outside {} to {}", self.ui_scale, UI_SCALE_RANGE.start(), UI_SCALE_RANGE.end(),
+            )));
+            self.ui_scale = defaults.ui_scale;
+        }
+        if self.lan_sync_port == 0 {
+            problems.push(ConfigProblem::new("lan_sync_port", "0 is not a port devices can connect to"));
+            self.lan_sync_port = defaults.lan_sync_port;
+        }
+        if let Some(dir) = self.backup_dir.take() {
+            if dir.exists() && !dir.is_dir() {
+                problems.push(ConfigProblem::new("backup_dir", format!("{} is a file, not a folder", dir.display())));
+            } else {
+                self.backup_dir = Some(dir);
+            }
+        }
+        // The vault paths are only reported: dropping them would save
+        // snippets in plain text.
+        let mut vault_files = vec![
+            ("vault_identity_file", self.vault_identity_file.as_ref(), false),
+            ("vault_recipients_file", self.vault_recipients_file.as_ref(), true),
+        ];
+        for folder in self.folder_vaults.values() {
+            vault_files.push(("folder_vaults", folder.identity_file.as_ref(), false));
+            vault_files.push(("folder_vaults", folder.recipients_file.as_ref(), true));
+        }
+        for (key, path, must_exist) in vault_files {
+            let Some(path) = path else {
+                continue;
+            };
+            if must_exist && !path.is_file() {
+                problems.push(ConfigProblem::new(key, format!(
+                    "{} does not exist; the vault can't open until it does", path.display(),
+                )));
+            } else if !must_exist && path.parent().is_some_and(|dir| !dir.as_os_str().is_empty() && !dir.is_dir()) {
+                // Identities are created on first use, but not their folder.
+                problems.push(ConfigProblem::new(key, format!(
+                    "The folder of {} does not exist; the vault can't open until it does", path.display(),
+                )));
+            }
+        }
+    }
+
+    /// A copy fit for a bug report: keys, the bucket and file paths are
+    /// replaced by a placeholder, as are the redaction and capture
+    /// exclusion patterns, which spell out what they're meant to catch.
+    /// Credentials are in the keychain and never in the file to begin with.
+    pub fn redacted(&self) -> Config {
+        let hidden = || PathBuf::from(REDACTED);
+        let mut config = self.clone();
+        config.backup_dir = config.backup_dir.map(|_| hidden());
+        config.vault_identity_file = config.vault_identity_file.map(|_| hidden());
+        config.vault_recipients_file = config.vault_recipients_file.map(|_| hidden());
+        for vault in config.folder_vaults.values_mut() {
+            vault.identity_file = vault.identity_file.take().map(|_| hidden());
+            vault.recipients_file = vault.recipients_file.take().map(|_| hidden());
+        }
+        for pattern in config.redaction_patterns.values_mut().chain(config.capture_excluded_patterns.iter_mut()) {
+            *pattern = REDACTED.to_string();
+        }
+        for peer in config.sync_peers.iter_mut().chain(config.companions.iter_mut()) {
+            peer.public_key = REDACTED.to_string();
+        }
+        if let Some(s3) = &mut config.s3 {
+            s3.endpoint = REDACTED.to_string();
+            s3.bucket = REDACTED.to_string();
+        }
+        config
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(json: &str) -> (Config, Vec<ConfigProblem>) {
+        let Ok(Value::Object(entries)) = serde_json::from_str(json) else {
+            panic!("test config is not an object");
+        };
+        Config::from_entries(entries)
+    }
+
+    fn keys(problems: &[ConfigProblem]) -> Vec<&str> {
+        problems.iter().filter_map(|problem| problem.key.as_deref()).collect()
+    }
+
+    #[test]
+    fn bad_settings_fall_back_alone() {
+        let (config, problems) = load(r#"{"ui_scal": 1.5, "lan_sync_port": "many", "preview_chars": 40, "ui_scale": 9.0}"#);
+        assert_eq!(keys(&problems), ["lan_sync_port", "ui_scal", "ui_scale"]);
+        assert_eq!(config.preview_chars, 40);
+        assert_eq!(config.lan_sync_port, DEFAULT_LAN_SYNC_PORT);
+        assert_eq!(config.ui_scale, 1.0);
+    }
+
+    #[test]
+    fn bad_shortcuts_keep_their_defaults() {
+        let (config, problems) = load(r#"{"keybindings": {"undo": "Ctrl+Shift+Z", "copy": "Hyper+Q"}}"#);
+        assert_eq!(keys(&problems), ["keybindings"]);
+        assert_eq!(config.keybindings.len(), 1);
+        assert!(config.keybindings.contains_key(&Command::Undo));
+    }
+
+    #[test]
+    fn redacted_config_leaves_out_keys_and_paths() {
+        let (config, _) = load(r#"{
+            "vault_identity_file": "/home/ana/.config/trinket/vault.key",
+            "redaction_patterns": {"badge": "ACME-\\d{6}"},
+            "sync_peers": [{"id": "p1", "name": "Laptop", "public_key": "8f3a"}],
+            "preview_chars": 40
+        }"#);
+        let json = serde_json::to_string(&config.redacted()).unwrap();
+        for secret in ["/home/ana", "ACME", "8f3a"] {
+            assert!(!json.contains(secret), "{} is in {}", secret, json);
+        }
+        assert!(json.contains("Laptop") && json.contains(r#""preview_chars":40"#));
+    }
+}
@@ -0,0 +1,131 @@
+//! The zip "Collect diagnostics" writes for a bug report: the log files,
+//! the config with its secrets left out, counts from the library and what
+//! trinket runs on.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use thiserror::Error;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::config::Config;
+use crate::logging::log_dir;
+use crate::storage::{StorageError, StorageStats};
+
+/// Features this build was made with, as named in Cargo.toml.
+const FEATURES: &[(&str, bool)] = &[
+    ("tray", cfg!(feature = "tray")),
+    ("hotkeys", cfg!(feature = "hotkeys")),
+    ("ocr", cfg!(feature = "ocr")),
+    ("spellcheck", cfg!(feature = "spellcheck")),
+    ("vault", cfg!(feature = "vault")),
+    ("keychain", cfg!(feature = "keychain")),
+    ("sync", cfg!(feature = "sync")),
+    ("s3", cfg!(feature = "s3")),
+    ("ditto", cfg!(feature = "ditto")),
+    ("layer-shell", cfg!(feature = "layer-shell")),
+];
+
+/// Environment variables that say which desktop and display server the
+/// hotkeys and tray icon are dealing with.
+#[cfg(target_os = "linux")]
+const SESSION_VARS: &[&str] = &["XDG_SESSION_TYPE", "XDG_CURRENT_DESKTOP", "DESKTOP_SESSION", "WAYLAND_DISPLAY", "DISPLAY"];
+
+#[derive(Debug, Error)]
+pub enum DiagnosticsError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Writes the bundle to `path`. `state` lists what the app knows of itself,
+/// such as whether the hotkeys are listening, one line each. A library that
+/// couldn't be counted or a log file that couldn't be read is noted in the
+/// bundle instead of failing it.
+pub fn write_bundle(
+    path: &Path,
+    config: &Config,
+    stats: Result<StorageStats, StorageError>,
+    state: &[String],
+) -> Result<(), DiagnosticsError> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("platform.txt", options)?;
+    zip.write_all(platform_info(state).as_bytes())?;
+
+    zip.start_file("config.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, &config.redacted())?;
+
+    match stats {
+        Ok(stats) => {
+            zip.start_file("storage.json", options)?;
+            serde_json::to_writer_pretty(&mut zip, &stats)?;
+        }
+        Err(e) => {
+            zip.start_file("storage.txt", options)?;
+            writeln!(zip, "Could not count the library: {}", e)?;
+        }
+    }
+
+    let mut logs: Vec<_> = fs::read_dir(log_dir())
+        .map(|entries| entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect())
+        .unwrap_or_default();
+    logs.sort();
+    for log in logs.iter().filter(|path| path.is_file()) {
+        let Some(name) = log.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        zip.start_file(format!("logs/{}", name), options)?;
+        if let Err(e) = File::open(log).and_then(|mut file| std::io::copy(&mut file, &mut zip)) {
+            writeln!(zip, "\n[could not read {}: {}]", name, e)?;
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// The version, system and build, then the lines in `state`.
+fn platform_info(state: &[String]) -> String {
+    let features: Vec<&str> = FEATURES.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+    let mut lines = vec![
+        format!("trinket {}", env!("CARGO_PKG_VERSION")),
+        format!("System: {} {} ({})", std::env::consts::OS, os_version().unwrap_or_default(), std::env::consts::ARCH),
+        format!("Features: {}", features.join(", ")),
+    ];
+    #[cfg(target_os = "linux")]
+    for var in SESSION_VARS {
+        lines.push(format!("{}: {}", var, std::env::var(var).unwrap_or_default()));
+    }
+    lines.extend(state.iter().cloned());
+    lines.join("\n") + "\n"
+}
+
+/// The distribution and kernel, such as `Fedora Linux 42, kernel 6.14.4`.
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let release = fs::read_to_string("/etc/os-release").ok()?;
+    let name = release.lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())?;
+    Some(match fs::read_to_string("/proc/sys/kernel/osrelease") {
+        Ok(kernel) => format!("{}, kernel {}", name, kernel.trim()),
+        Err(_) => name,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    let output = std::process::Command::new("sw_vers").arg("-productVersion").output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn os_version() -> Option<String> {
+    None
+}
@@ -1,5 +1,173 @@
-#[derive(Debug, Clone, Copy)]
+#[cfg(feature = "hotkeys")]
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
+#[cfg(feature = "hotkeys")]
+use std::sync::{mpsc, Arc};
+#[cfg(feature = "hotkeys")]
+use std::time::Duration;
+
+/// Restarts allowed before the supervisor gives up and reports the listener dead.
+#[cfg(feature = "hotkeys")]
+const MAX_RESTARTS: u32 = 5;
+#[cfg(feature = "hotkeys")]
+const RESTART_DELAY: Duration = Duration::from_millis(500);
+#[cfg(feature = "hotkeys")]
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Keys of the global hotkeys, all pressed with Super and Ctrl, which on a
+/// Mac are Cmd and Control. Mac keyboards mostly lack PageUp, PageDown,
+/// PrintScreen and End, so there the arrows add and get, O captures, S saves
+/// the selection and Z snoozes.
+#[cfg(all(feature = "hotkeys", not(target_os = "macos")))]
+mod keys {
+    use global_hotkey::hotkey::Code;
+    
+    pub const ADD: Code = Code::PageUp;
+    pub const GET: Code = Code::PageDown;
+    #[cfg(feature = "ocr")]
+    pub const CAPTURE: Code = Code::PrintScreen;
+    /// Ctrl+Insert is the older copy shortcut.
+    pub const SELECTION: Code = Code::Insert;
+    pub const SNOOZE: Code = Code::End;
+    /// The add and get hotkeys as written in hints.
+    pub const ADD_HINT: &str = "Ctrl+Super+PageUp";
+    pub const GET_HINT: &str = "Ctrl+Super+PageDown";
+    pub const SNOOZE_HINT: &str = "Ctrl+Super+End";
+}
+
+#[cfg(all(feature = "hotkeys", target_os = "macos"))]
+mod keys {
+    use global_hotkey::hotkey::Code;
+    
+    pub const ADD: Code = Code::ArrowUp;
+    pub const GET: Code = Code::ArrowDown;
+    #[cfg(feature = "ocr")]
+    pub const CAPTURE: Code = Code::KeyO;
+    pub const SELECTION: Code = Code::KeyS;
+    pub const SNOOZE: Code = Code::KeyZ;
+    pub const ADD_HINT: &str = "⌃⌘↑";
+    pub const GET_HINT: &str = "⌃⌘↓";
+    pub const SNOOZE_HINT: &str = "⌃⌘Z";
+}
+
+#[cfg(feature = "hotkeys")]
+pub use keys::*;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(not(any(feature = "hotkeys", feature = "tray")), allow(dead_code))]
 pub enum HotkeyEvent {
     Add,
     Get,
-}
\ No newline at end of file
+    /// Snooze the hotkeys for the configured minutes, or resume them early.
+    ToggleSnooze,
+    /// Drag-select a screen region and OCR it into a new snippet.
+    #[cfg(feature = "ocr")]
+    Capture,
+    /// Copy the selection in the focused app and save it as a new snippet.
+    #[cfg(feature = "hotkeys")]
+    CaptureSelection,
+    /// Copy the snippet with this id, picked from the taskbar jump list.
+    #[cfg(windows)]
+    CopySnippet(String),
+    /// Save this text as a new snippet, sent from the Services menu.
+    #[cfg(target_os = "macos")]
+    SaveSelection(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenerStatus {
+    Running,
+    Restarted { count: u32, last_error: String },
+    Stopped { last_error: String },
+}
+
+/// Shared between the supervisor thread and the UI so a dead listener is
+/// visible instead of hotkeys silently doing nothing. Built without hotkeys
+/// it stays `Running`.
+#[derive(Default)]
+pub struct ListenerHealth {
+    stopped: AtomicBool,
+    restarts: AtomicU32,
+    last_error: Mutex<Option<String>>,
+}
+
+impl ListenerHealth {
+    pub fn status(&self) -> ListenerStatus {
+        let last_error = self.last_error.lock()
+            .map(|e| e.clone().unwrap_or_default())
+            .unwrap_or_default();
+        if self.stopped.load(Ordering::Relaxed) {
+            ListenerStatus::Stopped { last_error }
+        } else {
+            match self.restarts.load(Ordering::Relaxed) {
+                0 => ListenerStatus::Running,
+                count => ListenerStatus::Restarted { count, last_error },
+            }
+        }
+    }
+    
+    #[cfg(feature = "hotkeys")]
+    fn record_panic(&self, message: String) {
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = Some(message);
+        }
+    }
+}
+
+/// Spawns the hotkey listener under a supervisor that catches panics, logs
+/// them and restarts the loop up to `MAX_RESTARTS` times.
+#[cfg(feature = "hotkeys")]
+pub fn spawn_listener(
+    bindings: Vec<(u32, HotkeyEvent)>,
+    sender: mpsc::Sender<HotkeyEvent>,
+) -> Arc<ListenerHealth> {
+    let health = Arc::new(ListenerHealth::default());
+    let supervisor_health = Arc::clone(&health);
+    
+    std::thread::spawn(move || {
+        loop {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| listen(&bindings, &sender)));
+            let message = match result {
+                // The app dropped its receiver, so there is nobody left to notify.
+                Ok(()) => return,
+                Err(payload) => panic_message(payload.as_ref()),
+            };
+            
+            log::error!("Hotkey listener panicked: {}", message);
+            supervisor_health.record_panic(message);
+            
+            let restarts = supervisor_health.restarts.fetch_add(1, Ordering::Relaxed) + 1;
+            if restarts > MAX_RESTARTS {
+                log::error!("Hotkey listener failed {} times, giving up", restarts);
+                supervisor_health.stopped.store(true, Ordering::Relaxed);
+                return;
+            }
+            std::thread::sleep(RESTART_DELAY);
+        }
+    });
+    
+    health
+}
+
+#[cfg(feature = "hotkeys")]
+fn listen(bindings: &[(u32, HotkeyEvent)], sender: &mpsc::Sender<HotkeyEvent>) {
+    loop {
+        if let Ok(event) = global_hotkey::GlobalHotKeyEvent::receiver().try_recv() {
+            if let Some((_, hotkey_event)) = bindings.iter().find(|(id, _)| *id == event.id) {
+                if sender.send(hotkey_event.clone()).is_err() {
+                    return;
+                }
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(feature = "hotkeys")]
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload.downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
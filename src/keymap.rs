@@ -0,0 +1,347 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Which window a command applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Get,
+    Add,
+}
+
+/// A set of default bindings to start from before the user's overrides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    #[default]
+    Standard,
+    /// `j`/`k` to move, `/` to search, `gg`/`G` to jump, `dd` to delete,
+    /// `y` to copy, `n`/`N` to step through matches. The search box isn't focused until `/` is pressed.
+    Vim,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 2] = [Profile::Standard, Profile::Vim];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Profile::Standard => "Standard",
+            Profile::Vim => "Vim",
+        }
+    }
+
+    /// Bindings as `(command, key sequence)`; a command may have several.
+    fn defaults(self) -> &'static [(Command, &'static str)] {
+        match self {
+            Profile::Standard => &[
+                (Command::SelectPrevious, "ArrowUp"),
+                (Command::SelectNext, "ArrowDown"),
+                (Command::JumpFirst, "Ctrl+Home"),
+                (Command::JumpLast, "Ctrl+End"),
+                (Command::MovePinnedUp, "Alt+ArrowUp"),
+                (Command::MovePinnedDown, "Alt+ArrowDown"),
+                (Command::NextMatch, "Ctrl+G"),
+                (Command::PreviousMatch, "Ctrl+Shift+G"),
+                (Command::FocusSearch, "Ctrl+F"),
+                (Command::Copy, "Enter"),
+                (Command::ShowDetails, "F3"),
+                (Command::AddToBuild, "Ctrl+B"),
+                (Command::Undo, "Ctrl+Z"),
+                (Command::Close, "Escape"),
+                (Command::Save, "Ctrl+Enter"),
+                (Command::Cancel, "Escape"),
+                (Command::ShowShortcuts, "F1"),
+                (Command::ShowShortcuts, "?"),
+            ],
+            Profile::Vim => &[
+                (Command::SelectPrevious, "k"),
+                (Command::SelectPrevious, "ArrowUp"),
+                (Command::SelectNext, "j"),
+                (Command::SelectNext, "ArrowDown"),
+                (Command::JumpFirst, "g g"),
+                (Command::JumpLast, "Shift+G"),
+                (Command::MovePinnedUp, "Shift+K"),
+                (Command::MovePinnedUp, "Alt+ArrowUp"),
+                (Command::MovePinnedDown, "Shift+J"),
+                (Command::MovePinnedDown, "Alt+ArrowDown"),
+                (Command::NextMatch, "n"),
+                (Command::PreviousMatch, "Shift+N"),
+                (Command::FocusSearch, "/"),
+                (Command::Copy, "y"),
+                (Command::Copy, "Enter"),
+                (Command::ShowDetails, "F3"),
+                (Command::AddToBuild, "Ctrl+B"),
+                (Command::Delete, "d d"),
+                (Command::Undo, "u"),
+                (Command::Undo, "Ctrl+Z"),
+                (Command::Close, "Escape"),
+                (Command::Save, "Ctrl+Enter"),
+                (Command::Cancel, "Escape"),
+                (Command::ShowShortcuts, "F1"),
+                (Command::ShowShortcuts, "?"),
+            ],
+        }
+    }
+}
+
+/// Everything that can be bound to a key. Config files name these in
+/// snake_case, e.g. `"add_to_build": "Ctrl+B"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    SelectPrevious,
+    SelectNext,
+    JumpFirst,
+    JumpLast,
+    MovePinnedUp,
+    MovePinnedDown,
+    NextMatch,
+    PreviousMatch,
+    FocusSearch,
+    Copy,
+    ShowDetails,
+    AddToBuild,
+    Delete,
+    Undo,
+    Close,
+    Save,
+    Cancel,
+    ShowShortcuts,
+}
+
+impl Command {
+    pub fn label(self) -> &'static str {
+        match self {
+            Command::SelectPrevious => "Previous result",
+            Command::SelectNext => "Next result",
+            Command::JumpFirst => "First result",
+            Command::JumpLast => "Last result",
+            Command::MovePinnedUp => "Move pinned snippet up",
+            Command::MovePinnedDown => "Move pinned snippet down",
+            Command::NextMatch => "Next match in preview",
+            Command::PreviousMatch => "Previous match in preview",
+            Command::FocusSearch => "Search",
+            Command::Copy => "Copy selected snippet",
+            Command::ShowDetails => "Show snippet details",
+            Command::AddToBuild => "Add to build list",
+            Command::Delete => "Delete selected snippet",
+            Command::Undo => "Undo last bulk action",
+            Command::Close => "Close window",
+            Command::Save => "Save snippet",
+            Command::Cancel => "Cancel",
+            Command::ShowShortcuts => "Show shortcuts",
+        }
+    }
+
+    pub fn applies_to(self, scope: Scope) -> bool {
+        match self {
+            Command::Save | Command::Cancel => scope == Scope::Add,
+            Command::ShowShortcuts => true,
+            _ => scope == Scope::Get,
+        }
+    }
+}
+
+/// Mouse gestures that aren't rebindable but belong in the shortcut list.
+pub const MOUSE_GESTURES: [(&str, &str); 5] = [
+    ("Click", "Copy snippet"),
+    ("Ctrl+Click", "Add to or remove from selection"),
+    ("Shift+Click", "Select range"),
+    ("Double-click", "Show details of a secret snippet"),
+    ("Right-click", "Snippet actions"),
+];
+
+/// A command and the keys that trigger it, pressed one after another.
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub command: Command,
+    pub keys: Vec<KeyboardShortcut>,
+}
+
+impl Binding {
+    /// Bindings that start with a plain character would swallow typing, so
+    /// they only apply while no text field has focus.
+    fn types_text(&self) -> bool {
+        self.keys.first().is_some_and(|first| {
+            !first.modifiers.command && !first.modifiers.ctrl && !first.modifiers.alt
+                && first.logical_key.symbol_or_name().len() == 1
+        })
+    }
+
+    fn modifier_count(&self) -> usize {
+        self.keys.iter()
+            .map(|key| {
+                let m = key.modifiers;
+                [m.alt, m.ctrl, m.shift, m.mac_cmd, m.command].into_iter().filter(|&set| set).count()
+            })
+            .sum()
+    }
+
+    fn matches_prefix(&self, pressed: &[(Key, Modifiers)]) -> bool {
+        pressed.len() <= self.keys.len()
+            && pressed.iter().zip(&self.keys).all(|(&(key, modifiers), shortcut)| {
+                key == shortcut.logical_key
+                    && modifiers.matches_logically(shortcut.modifiers)
+                    // Shift picks the letter: `G` is not `g`.
+                    && (!is_letter(key) || modifiers.shift == shortcut.modifiers.shift)
+            })
+    }
+}
+
+/// The single source of truth for keyboard shortcuts: a profile's defaults
+/// plus the user's overrides from the config file.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    profile: Profile,
+    bindings: Vec<Binding>,
+    /// Keys typed so far towards a multi-key binding such as `gg`.
+    pending: Vec<(Key, Modifiers)>,
+    /// An input method is composing text, so keys such as Enter and Escape
+    /// go to it rather than to bindings.
+    composing: bool,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(Profile::default(), &BTreeMap::new())
+    }
+}
+
+impl Keymap {
+    /// An override replaces every default binding of its command.
+    /// Unparseable overrides are logged and the defaults kept.
+    pub fn new(profile: Profile, overrides: &BTreeMap<Command, String>) -> Self {
+        let overrides: BTreeMap<Command, Vec<KeyboardShortcut>> = overrides.iter()
+            .filter_map(|(&command, text)| match parse_sequence(text) {
+                Some(keys) => Some((command, keys)),
+                None => {
+                    log::warn!("Ignoring invalid shortcut '{}' for {:?}", text, command);
+                    None
+                }
+            })
+            .collect();
+        let mut bindings: Vec<Binding> = profile.defaults().iter()
+            .filter(|(command, _)| !overrides.contains_key(command))
+            .filter_map(|&(command, keys)| parse_sequence(keys).map(|keys| Binding { command, keys }))
+            .collect();
+        bindings.extend(overrides.into_iter().map(|(command, keys)| Binding { command, keys }));
+        // Stable, so each command keeps its bindings in profile order.
+        bindings.sort_by_key(|binding| binding.command);
+        Self { profile, bindings, pending: Vec::new(), composing: false }
+    }
+
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    /// Feeds this frame's key presses through the bindings of `scope` and
+    /// returns the commands they completed. `typing` disables bindings that
+    /// start with a plain character. Keys an input method is composing
+    /// with, including the Enter that confirms the composition, trigger
+    /// nothing.
+    pub fn triggered(&mut self, input: &egui::InputState, scope: Scope, typing: bool) -> Vec<Command> {
+        let mut commands = Vec::new();
+        // Some input methods send the confirming Enter after the commit.
+        let mut committed = false;
+        for event in &input.events {
+            let (key, modifiers) = match event {
+                egui::Event::Ime(ime) => {
+                    self.composing = matches!(ime, egui::ImeEvent::Preedit(text) if !text.is_empty());
+                    committed |= matches!(ime, egui::ImeEvent::Commit(_));
+                    self.pending.clear();
+                    continue;
+                }
+                egui::Event::Key { key, pressed: true, modifiers, .. } if !self.composing && !committed => (*key, *modifiers),
+                _ => continue,
+            };
+            self.pending.push((key, modifiers));
+            loop {
+                let candidates: Vec<&Binding> = self.bindings.iter()
+                    .filter(|b| b.command.applies_to(scope) && !(typing && b.types_text()))
+                    .filter(|b| b.matches_prefix(&self.pending))
+                    .collect();
+                // Extra modifiers still match a plain binding, so `Alt+Up`
+                // prefers its own binding over `Up`.
+                let done = candidates.iter()
+                    .filter(|b| b.keys.len() == self.pending.len())
+                    .min_by_key(|b| std::cmp::Reverse(b.modifier_count()));
+                if let Some(done) = done {
+                    commands.push(done.command);
+                    self.pending.clear();
+                    break;
+                }
+                if !candidates.is_empty() {
+                    break;
+                }
+                // A dead end, but the last key may start a new sequence.
+                if self.pending.len() > 1 {
+                    self.pending.drain(..self.pending.len() - 1);
+                } else {
+                    self.pending.clear();
+                    break;
+                }
+            }
+        }
+        commands
+    }
+
+    /// Forgets a half-typed sequence.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.composing = false;
+    }
+
+    /// The first binding of `command`, for labels such as "Save (Ctrl+Enter)".
+    pub fn keys(&self, command: Command) -> Option<&[KeyboardShortcut]> {
+        self.bindings.iter()
+            .find(|binding| binding.command == command)
+            .map(|binding| binding.keys.as_slice())
+    }
+
+    pub fn bindings(&self, scope: Scope) -> impl Iterator<Item = &Binding> {
+        self.bindings.iter().filter(move |binding| binding.command.applies_to(scope))
+    }
+}
+
+/// Formats a key sequence for display, with plain letters in lower case so
+/// `g g` and `G` look different.
+pub fn format_keys(ctx: &egui::Context, keys: &[KeyboardShortcut]) -> String {
+    keys.iter()
+        .map(|shortcut| {
+            let text = ctx.format_shortcut(shortcut);
+            if shortcut.modifiers.is_none() && is_letter(shortcut.logical_key) {
+                text.to_lowercase()
+            } else {
+                text
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses a space-separated key sequence such as `g g` or `Ctrl+Shift+Z`.
+pub fn parse_sequence(text: &str) -> Option<Vec<KeyboardShortcut>> {
+    let keys: Option<Vec<_>> = text.split_whitespace().map(parse_shortcut).collect();
+    keys.filter(|keys| !keys.is_empty())
+}
+
+/// Parses shortcuts written like `Ctrl+Shift+Z` or `F1`.
+fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = Key::from_name(parts.pop()?)?;
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        modifiers |= match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "cmd" | "command" => Modifiers::COMMAND,
+            "shift" => Modifiers::SHIFT,
+            "alt" | "option" => Modifiers::ALT,
+            _ => return None,
+        };
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+fn is_letter(key: Key) -> bool {
+    let name = key.name();
+    name.len() == 1 && name.chars().all(|c| c.is_ascii_alphabetic())
+}
@@ -0,0 +1,615 @@
+//! Runs the app as a wlr layer-shell overlay instead of an eframe window,
+//! for wlroots-based Wayland compositors. Wayland ignores always-on-top and
+//! lets clients neither position their windows nor raise them over a
+//! fullscreen app; an overlay layer surface is placed by the compositor
+//! above everything, centred on the output.
+//!
+//! eframe can't create layer surfaces, so this is a small runner of its
+//! own: smithay-client-toolkit for the surface and input, EGL through
+//! glutin and egui_glow to paint. The surface exists only while the app
+//! shows something and takes the keyboard while it does, as launchers do.
+
+use eframe::egui;
+use egui_glow::glow;
+use glutin::api::egl::context::PossiblyCurrentContext;
+use glutin::api::egl::display::Display;
+use glutin::api::egl::surface::Surface;
+use glutin::config::{ConfigTemplateBuilder, GlConfig};
+use glutin::context::{ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentGlContext};
+use glutin::display::GlDisplay;
+use glutin::surface::{GlSurface, SurfaceAttributesBuilder, WindowSurface};
+use raw_window_handle::{RawDisplayHandle, RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle};
+use smithay_client_toolkit::compositor::{CompositorHandler, CompositorState};
+use smithay_client_toolkit::output::{OutputHandler, OutputState};
+use smithay_client_toolkit::reexports::calloop::{EventLoop, LoopHandle};
+use smithay_client_toolkit::reexports::calloop_wayland_source::WaylandSource;
+use smithay_client_toolkit::reexports::calloop;
+use smithay_client_toolkit::reexports::client::globals::{registry_queue_init, BindError, GlobalError};
+use smithay_client_toolkit::reexports::client::protocol::{wl_keyboard, wl_output, wl_pointer, wl_seat, wl_surface};
+use smithay_client_toolkit::reexports::client::{ConnectError, Connection, Proxy, QueueHandle};
+use smithay_client_toolkit::registry::{ProvidesRegistryState, RegistryState};
+use smithay_client_toolkit::seat::keyboard::{KeyEvent, KeyboardHandler, Keysym, Modifiers};
+use smithay_client_toolkit::seat::pointer::{PointerEvent, PointerEventKind, PointerHandler};
+use smithay_client_toolkit::seat::{Capability, SeatHandler, SeatState};
+use smithay_client_toolkit::shell::wlr_layer::{
+    KeyboardInteractivity, Layer, LayerShell, LayerShellHandler, LayerSurface, LayerSurfaceConfigure,
+};
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::{
+    delegate_compositor, delegate_keyboard, delegate_layer, delegate_output, delegate_pointer, delegate_registry,
+    delegate_seat, registry_handlers,
+};
+use std::ffi::c_void;
+use std::num::NonZeroU32;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::app::TrinketApp;
+
+const NAMESPACE: &str = "trinket";
+/// The size asked for, in points; the same as the eframe window's.
+const SIZE: (u32, u32) = (600, 400);
+/// How often the app runs while nothing is shown, to pick up hotkeys and
+/// background events.
+const HIDDEN_INTERVAL: Duration = Duration::from_millis(50);
+/// Paint anyway when the compositor holds back frame callbacks this long,
+/// as it may for a surface it considers hidden.
+const FRAME_TIMEOUT: Duration = Duration::from_millis(500);
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+
+/// True when running under Wayland with a compositor that offers the wlr
+/// layer shell.
+pub fn is_available() -> bool {
+    let Ok(connection) = Connection::connect_to_env() else {
+        return false;
+    };
+    // The queue is never dispatched; the globals come with the first
+    // roundtrip.
+    let Ok((globals, _queue)) = registry_queue_init::<Runner>(&connection) else {
+        return false;
+    };
+    globals.contents().with_list(|list| list.iter().any(|global| global.interface == "zwlr_layer_shell_v1"))
+}
+
+/// Why the overlay couldn't be set up or drawn.
+#[derive(Debug, Error)]
+pub enum LayerShellError {
+    #[error("can't connect to Wayland: {0}")]
+    Connect(#[from] ConnectError),
+    #[error("Wayland registry: {0}")]
+    Registry(#[from] GlobalError),
+    #[error("the compositor lacks a global: {0}")]
+    Bind(#[from] BindError),
+    #[error("event loop: {0}")]
+    EventLoop(#[from] calloop::Error),
+    #[error("EGL: {0}")]
+    Egl(#[from] glutin::error::Error),
+    #[error("painter: {0}")]
+    Painter(#[from] egui_glow::PainterError),
+    #[error("{0}")]
+    Surface(&'static str),
+}
+
+/// Runs the app until it asks to close. `make_app` gets the egui context
+/// the app is drawn with.
+pub fn run(make_app: impl FnOnce(&egui::Context) -> TrinketApp) -> Result<(), LayerShellError> {
+    let connection = Connection::connect_to_env()?;
+    let (globals, queue) = registry_queue_init(&connection)?;
+    let qh = queue.handle();
+    let mut event_loop: EventLoop<'static, Runner> = EventLoop::try_new()?;
+    WaylandSource::new(connection.clone(), queue)
+        .insert(event_loop.handle())
+        .map_err(|e| e.error)?;
+
+    let display_ptr = NonNull::new(connection.backend().display_ptr().cast::<c_void>())
+        .ok_or(LayerShellError::Surface("the Wayland connection has no display"))?;
+    // SAFETY: the display outlives the runner, which holds the connection.
+    let egl = unsafe { Display::new(RawDisplayHandle::Wayland(WaylandDisplayHandle::new(display_ptr)))? };
+
+    let ctx = egui::Context::default();
+    let app = make_app(&ctx);
+    let mut runner = Runner {
+        registry_state: RegistryState::new(&globals),
+        seat_state: SeatState::new(&globals, &qh),
+        output_state: OutputState::new(&globals, &qh),
+        compositor: CompositorState::bind(&globals, &qh)?,
+        layer_shell: LayerShell::bind(&globals, &qh)?,
+        loop_handle: event_loop.handle(),
+        egl,
+        gl: None,
+        window: None,
+        keyboard: None,
+        pointer: None,
+        ctx,
+        app,
+        start: Instant::now(),
+        events: Vec::new(),
+        modifiers: egui::Modifiers::NONE,
+        pointer_pos: egui::Pos2::ZERO,
+        textures: egui::TexturesDelta::default(),
+        last_run: Instant::now(),
+        exit: false,
+    };
+
+    while !runner.exit {
+        let timeout = match &runner.window {
+            Some(window) if window.ready() => Duration::ZERO,
+            Some(_) => FRAME_TIMEOUT,
+            None => HIDDEN_INTERVAL,
+        };
+        event_loop.dispatch(Some(timeout), &mut runner)?;
+        runner.tick(&qh)?;
+    }
+    runner.shut_down();
+    Ok(())
+}
+
+/// The layer surface while the app shows something.
+struct Window {
+    layer: LayerSurface,
+    /// Created once the compositor has told the surface its size.
+    surface: Option<Surface<WindowSurface>>,
+    size: (u32, u32),
+    scale: i32,
+    /// A frame callback is outstanding since this frame was painted.
+    frame_pending: Option<Instant>,
+}
+
+impl Window {
+    fn ready(&self) -> bool {
+        self.surface.is_some() && self.frame_pending.is_none_or(|painted| painted.elapsed() >= FRAME_TIMEOUT)
+    }
+
+    fn physical_size(&self) -> [u32; 2] {
+        let scale = self.scale.max(1).unsigned_abs();
+        [self.size.0 * scale, self.size.1 * scale]
+    }
+}
+
+/// The GL context and painter, made with the first surface and kept while
+/// the surface comes and goes.
+struct Gl {
+    context: PossiblyCurrentContext,
+    painter: egui_glow::Painter,
+}
+
+struct Runner {
+    registry_state: RegistryState,
+    seat_state: SeatState,
+    output_state: OutputState,
+    compositor: CompositorState,
+    layer_shell: LayerShell,
+    loop_handle: LoopHandle<'static, Runner>,
+    egl: Display,
+    gl: Option<Gl>,
+    window: Option<Window>,
+    keyboard: Option<wl_keyboard::WlKeyboard>,
+    pointer: Option<wl_pointer::WlPointer>,
+    ctx: egui::Context,
+    app: TrinketApp,
+    start: Instant,
+    /// Input since the last frame.
+    events: Vec<egui::Event>,
+    modifiers: egui::Modifiers,
+    pointer_pos: egui::Pos2,
+    /// Texture changes not yet uploaded, from frames run while hidden.
+    textures: egui::TexturesDelta,
+    last_run: Instant,
+    exit: bool,
+}
+
+impl Runner {
+    /// Runs a frame when one is due, then maps or unmaps the surface to
+    /// match what the app wants to show.
+    fn tick(&mut self, qh: &QueueHandle<Self>) -> Result<(), LayerShellError> {
+        match &self.window {
+            Some(window) if window.ready() => self.frame(qh)?,
+            None if self.last_run.elapsed() >= HIDDEN_INTERVAL => self.frame(qh)?,
+            _ => {}
+        }
+        match (self.app.wants_window(), self.window.is_some()) {
+            (true, false) => self.map(qh),
+            (false, true) => self.unmap()?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn map(&mut self, qh: &QueueHandle<Self>) {
+        let surface = self.compositor.create_surface(qh);
+        let layer = self.layer_shell.create_layer_surface(qh, surface, Layer::Overlay, Some(NAMESPACE), None);
+        layer.set_keyboard_interactivity(KeyboardInteractivity::Exclusive);
+        layer.set_size(SIZE.0, SIZE.1);
+        // The first commit has no buffer; the compositor answers with the
+        // configure that sizes the surface.
+        layer.commit();
+        self.window = Some(Window { layer, surface: None, size: SIZE, scale: 1, frame_pending: None });
+    }
+
+    fn unmap(&mut self) -> Result<(), LayerShellError> {
+        if let Some(gl) = &self.gl {
+            gl.context.make_not_current_in_place()?;
+        }
+        self.window = None;
+        self.events.push(egui::Event::PointerGone);
+        self.events.push(egui::Event::WindowFocused(false));
+        Ok(())
+    }
+
+    fn frame(&mut self, qh: &QueueHandle<Self>) -> Result<(), LayerShellError> {
+        self.last_run = Instant::now();
+        let raw_input = self.raw_input();
+        let app = &mut self.app;
+        let output = self.ctx.run(raw_input, |ctx| app.ui(ctx));
+        self.handle_platform_output(output.platform_output);
+        if let Some(viewport) = output.viewport_output.get(&egui::ViewportId::ROOT) {
+            if viewport.commands.iter().any(|command| matches!(command, egui::ViewportCommand::Close)) {
+                self.exit = true;
+            }
+        }
+        self.textures.append(output.textures_delta);
+
+        let Some(window) = &mut self.window else {
+            return Ok(());
+        };
+        let Some(surface) = &window.surface else {
+            return Ok(());
+        };
+        let Some(gl) = &mut self.gl else {
+            return Ok(());
+        };
+        let size = window.physical_size();
+        let primitives = self.ctx.tessellate(output.shapes, output.pixels_per_point);
+        gl.painter.clear(size, [0.0, 0.0, 0.0, 0.0]);
+        gl.painter.paint_and_update_textures(size, output.pixels_per_point, &primitives, &std::mem::take(&mut self.textures));
+        let wl_surface = window.layer.wl_surface();
+        wl_surface.frame(qh, wl_surface.clone());
+        surface.swap_buffers(&gl.context)?;
+        window.frame_pending = Some(Instant::now());
+        Ok(())
+    }
+
+    fn raw_input(&mut self) -> egui::RawInput {
+        let mut raw_input = egui::RawInput {
+            time: Some(self.start.elapsed().as_secs_f64()),
+            modifiers: self.modifiers,
+            focused: self.window.is_some(),
+            events: std::mem::take(&mut self.events),
+            ..Default::default()
+        };
+        if let Some(window) = &self.window {
+            let (width, height) = window.size;
+            raw_input.screen_rect = Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width as f32, height as f32)));
+            if let Some(viewport) = raw_input.viewports.get_mut(&egui::ViewportId::ROOT) {
+                viewport.native_pixels_per_point = Some(window.scale.max(1) as f32);
+                viewport.focused = Some(true);
+            }
+        }
+        raw_input
+    }
+
+    fn handle_platform_output(&mut self, output: egui::PlatformOutput) {
+        for command in output.commands {
+            if let egui::OutputCommand::CopyText(text) = command {
+                if let Err(e) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+                    log::warn!("Failed to copy to the clipboard: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Creates the EGL surface once the compositor has sized the layer
+    /// surface, and the GL context with the first one.
+    fn attach_surface(&mut self) -> Result<(), LayerShellError> {
+        let Some(window) = &mut self.window else {
+            return Ok(());
+        };
+        let [width, height] = window.physical_size();
+        let (Some(width), Some(height)) = (NonZeroU32::new(width), NonZeroU32::new(height)) else {
+            return Ok(());
+        };
+        if let (Some(surface), Some(gl)) = (&window.surface, &self.gl) {
+            surface.resize(&gl.context, width, height);
+            return Ok(());
+        }
+
+        let surface_ptr = NonNull::new(window.layer.wl_surface().id().as_ptr().cast::<c_void>())
+            .ok_or(LayerShellError::Surface("the layer surface is gone"))?;
+        let raw_window = RawWindowHandle::Wayland(WaylandWindowHandle::new(surface_ptr));
+        let template = ConfigTemplateBuilder::new().with_alpha_size(8).with_transparency(true).build();
+        // SAFETY: the config and surface are made for this runner's display
+        // and the wl_surface, which outlives the EGL surface made on it.
+        let config = unsafe { self.egl.find_configs(template)? }
+            .find(|config| config.supports_transparency().unwrap_or(false))
+            .ok_or(LayerShellError::Surface("no EGL config with an alpha channel"))?;
+        let attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(raw_window, width, height);
+        let surface = unsafe { self.egl.create_window_surface(&config, &attributes)? };
+
+        match &self.gl {
+            Some(gl) => gl.context.make_current(&surface)?,
+            None => {
+                let attributes = ContextAttributesBuilder::new().build(Some(raw_window));
+                let context = unsafe { self.egl.create_context(&config, &attributes)? }.make_current(&surface)?;
+                // SAFETY: the context was just made current on this thread.
+                let gl = unsafe { glow::Context::from_loader_function_cstr(|name| self.egl.get_proc_address(name)) };
+                let painter = egui_glow::Painter::new(Arc::new(gl), "", None, true)?;
+                self.gl = Some(Gl { context, painter });
+            }
+        }
+        window.surface = Some(surface);
+        Ok(())
+    }
+
+    fn shut_down(&mut self) {
+        if let Some(gl) = &mut self.gl {
+            // The painter can only free its textures with a surface current.
+            if self.window.as_ref().is_some_and(|window| window.surface.is_some()) {
+                gl.painter.destroy();
+            }
+        }
+    }
+
+    fn key(&mut self, event: &KeyEvent, pressed: bool, repeat: bool) {
+        if let Some(key) = egui_key(event.keysym) {
+            if pressed && self.modifiers.command {
+                match key {
+                    egui::Key::C => self.events.push(egui::Event::Copy),
+                    egui::Key::X => self.events.push(egui::Event::Cut),
+                    egui::Key::V => match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+                        Ok(text) => self.events.push(egui::Event::Paste(text)),
+                        Err(e) => log::warn!("Failed to paste from the clipboard: {}", e),
+                    },
+                    _ => {}
+                }
+            }
+            self.events.push(egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat,
+                modifiers: self.modifiers,
+            });
+        }
+        if !pressed || self.modifiers.ctrl || self.modifiers.alt {
+            return;
+        }
+        if let Some(text) = event.utf8.as_deref().filter(|text| !text.chars().any(char::is_control)) {
+            self.events.push(egui::Event::Text(text.to_string()));
+        }
+    }
+
+    fn is_ours(&self, surface: &wl_surface::WlSurface) -> bool {
+        self.window.as_ref().is_some_and(|window| window.layer.wl_surface() == surface)
+    }
+}
+
+/// The egui key for a keysym, from its name or the character it types.
+fn egui_key(keysym: Keysym) -> Option<egui::Key> {
+    Some(match keysym {
+        Keysym::Escape => egui::Key::Escape,
+        Keysym::Return | Keysym::KP_Enter => egui::Key::Enter,
+        Keysym::Tab | Keysym::ISO_Left_Tab => egui::Key::Tab,
+        Keysym::BackSpace => egui::Key::Backspace,
+        Keysym::Delete | Keysym::KP_Delete => egui::Key::Delete,
+        Keysym::Insert => egui::Key::Insert,
+        Keysym::Home | Keysym::KP_Home => egui::Key::Home,
+        Keysym::End | Keysym::KP_End => egui::Key::End,
+        Keysym::Page_Up | Keysym::KP_Page_Up => egui::Key::PageUp,
+        Keysym::Page_Down | Keysym::KP_Page_Down => egui::Key::PageDown,
+        Keysym::Left | Keysym::KP_Left => egui::Key::ArrowLeft,
+        Keysym::Right | Keysym::KP_Right => egui::Key::ArrowRight,
+        Keysym::Up | Keysym::KP_Up => egui::Key::ArrowUp,
+        Keysym::Down | Keysym::KP_Down => egui::Key::ArrowDown,
+        Keysym::space => egui::Key::Space,
+        Keysym::F1 => egui::Key::F1,
+        Keysym::F2 => egui::Key::F2,
+        Keysym::F3 => egui::Key::F3,
+        Keysym::F4 => egui::Key::F4,
+        Keysym::F5 => egui::Key::F5,
+        Keysym::F6 => egui::Key::F6,
+        Keysym::F7 => egui::Key::F7,
+        Keysym::F8 => egui::Key::F8,
+        Keysym::F9 => egui::Key::F9,
+        Keysym::F10 => egui::Key::F10,
+        Keysym::F11 => egui::Key::F11,
+        Keysym::F12 => egui::Key::F12,
+        _ => return egui::Key::from_name(&keysym.key_char()?.to_string()),
+    })
+}
+
+impl CompositorHandler for Runner {
+    fn scale_factor_changed(&mut self, _: &Connection, _: &QueueHandle<Self>, surface: &wl_surface::WlSurface, factor: i32) {
+        if !self.is_ours(surface) {
+            return;
+        }
+        surface.set_buffer_scale(factor);
+        if let Some(window) = &mut self.window {
+            window.scale = factor;
+        }
+        if let Err(e) = self.attach_surface() {
+            log::error!("Failed to resize the layer surface: {}", e);
+        }
+    }
+
+    fn transform_changed(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_surface::WlSurface, _: wl_output::Transform) {}
+
+    fn frame(&mut self, _: &Connection, _: &QueueHandle<Self>, surface: &wl_surface::WlSurface, _: u32) {
+        if let Some(window) = self.window.as_mut().filter(|window| window.layer.wl_surface() == surface) {
+            window.frame_pending = None;
+        }
+    }
+
+    fn surface_enter(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_surface::WlSurface, _: &wl_output::WlOutput) {}
+
+    fn surface_leave(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_surface::WlSurface, _: &wl_output::WlOutput) {}
+}
+
+impl LayerShellHandler for Runner {
+    fn closed(&mut self, _: &Connection, _: &QueueHandle<Self>, layer: &LayerSurface) {
+        // Its output went away; it is made again on another while the app
+        // still wants to show something.
+        if self.window.as_ref().is_some_and(|window| &window.layer == layer) {
+            if let Err(e) = self.unmap() {
+                log::error!("Failed to release the layer surface: {}", e);
+            }
+        }
+    }
+
+    fn configure(&mut self, _: &Connection, _: &QueueHandle<Self>, layer: &LayerSurface, configure: LayerSurfaceConfigure, _: u32) {
+        let Some(window) = self.window.as_mut().filter(|window| &window.layer == layer) else {
+            return;
+        };
+        let (width, height) = configure.new_size;
+        if width > 0 && height > 0 {
+            window.size = (width, height);
+        }
+        if let Err(e) = self.attach_surface() {
+            log::error!("Failed to set up drawing on the layer surface: {}", e);
+            self.exit = true;
+        }
+    }
+}
+
+impl OutputHandler for Runner {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_output::WlOutput) {}
+
+    fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_output::WlOutput) {}
+
+    fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_output::WlOutput) {}
+}
+
+impl SeatHandler for Runner {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.seat_state
+    }
+
+    fn new_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_seat::WlSeat) {}
+
+    fn new_capability(&mut self, _: &Connection, qh: &QueueHandle<Self>, seat: wl_seat::WlSeat, capability: Capability) {
+        if capability == Capability::Keyboard && self.keyboard.is_none() {
+            let repeat = Box::new(|runner: &mut Runner, _: &wl_keyboard::WlKeyboard, event: KeyEvent| {
+                runner.key(&event, true, true);
+            });
+            match self.seat_state.get_keyboard_with_repeat(qh, &seat, None, self.loop_handle.clone(), repeat) {
+                Ok(keyboard) => self.keyboard = Some(keyboard),
+                Err(e) => log::error!("Failed to use the keyboard: {}", e),
+            }
+        }
+        if capability == Capability::Pointer && self.pointer.is_none() {
+            match self.seat_state.get_pointer(qh, &seat) {
+                Ok(pointer) => self.pointer = Some(pointer),
+                Err(e) => log::error!("Failed to use the pointer: {}", e),
+            }
+        }
+    }
+
+    fn remove_capability(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_seat::WlSeat, capability: Capability) {
+        if capability == Capability::Keyboard {
+            if let Some(keyboard) = self.keyboard.take() {
+                keyboard.release();
+            }
+        }
+        if capability == Capability::Pointer {
+            if let Some(pointer) = self.pointer.take() {
+                pointer.release();
+            }
+        }
+    }
+
+    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_seat::WlSeat) {}
+}
+
+impl KeyboardHandler for Runner {
+    fn enter(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_keyboard::WlKeyboard, surface: &wl_surface::WlSurface, _: u32, _: &[u32], _: &[Keysym]) {
+        if self.is_ours(surface) {
+            self.events.push(egui::Event::WindowFocused(true));
+        }
+    }
+
+    fn leave(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_keyboard::WlKeyboard, surface: &wl_surface::WlSurface, _: u32) {
+        if self.is_ours(surface) {
+            self.events.push(egui::Event::WindowFocused(false));
+        }
+    }
+
+    fn press_key(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_keyboard::WlKeyboard, _: u32, event: KeyEvent) {
+        self.key(&event, true, false);
+    }
+
+    fn release_key(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_keyboard::WlKeyboard, _: u32, event: KeyEvent) {
+        self.key(&event, false, false);
+    }
+
+    fn update_modifiers(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_keyboard::WlKeyboard, _: u32, modifiers: Modifiers, _: u32) {
+        self.modifiers = egui::Modifiers {
+            alt: modifiers.alt,
+            ctrl: modifiers.ctrl,
+            shift: modifiers.shift,
+            mac_cmd: false,
+            command: modifiers.ctrl,
+        };
+    }
+}
+
+impl PointerHandler for Runner {
+    fn pointer_frame(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_pointer::WlPointer, events: &[PointerEvent]) {
+        for event in events {
+            if !self.is_ours(&event.surface) {
+                continue;
+            }
+            let pos = egui::pos2(event.position.0 as f32, event.position.1 as f32);
+            match event.kind {
+                PointerEventKind::Enter { .. } | PointerEventKind::Motion { .. } => {
+                    self.pointer_pos = pos;
+                    self.events.push(egui::Event::PointerMoved(pos));
+                }
+                PointerEventKind::Leave { .. } => self.events.push(egui::Event::PointerGone),
+                PointerEventKind::Press { button, .. } | PointerEventKind::Release { button, .. } => {
+                    let button = match button {
+                        BTN_LEFT => egui::PointerButton::Primary,
+                        BTN_RIGHT => egui::PointerButton::Secondary,
+                        BTN_MIDDLE => egui::PointerButton::Middle,
+                        _ => continue,
+                    };
+                    self.events.push(egui::Event::PointerButton {
+                        pos: self.pointer_pos,
+                        button,
+                        pressed: matches!(event.kind, PointerEventKind::Press { .. }),
+                        modifiers: self.modifiers,
+                    });
+                }
+                PointerEventKind::Axis { horizontal, vertical, .. } => {
+                    self.events.push(egui::Event::MouseWheel {
+                        unit: egui::MouseWheelUnit::Point,
+                        delta: egui::vec2(-horizontal.absolute as f32, -vertical.absolute as f32),
+                        modifiers: self.modifiers,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl ProvidesRegistryState for Runner {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    registry_handlers![OutputState, SeatState];
+}
+
+delegate_compositor!(Runner);
+delegate_output!(Runner);
+delegate_seat!(Runner);
+delegate_keyboard!(Runner);
+delegate_pointer!(Runner);
+delegate_layer!(Runner);
+delegate_registry!(Runner);
@@ -0,0 +1,89 @@
+//! Logs go to stderr as before and to a file per day in `logs` under the
+//! data dir, so a report like "my hotkey didn't save" can come with what
+//! happened. `log` records from the rest of the app are forwarded, inside
+//! whatever storage or sync span was running.
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{Builder, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+const LOG_PREFIX: &str = "trinket";
+const LOG_SUFFIX: &str = "log";
+/// Days of logs kept; older files are deleted as new ones start.
+const KEPT_FILES: usize = 7;
+/// The log viewer reads at most this much of the end of today's file.
+const TAIL_BYTES: u64 = 512 * 1024;
+
+pub fn log_dir() -> PathBuf {
+    trinket_core::data_dir().join("logs")
+}
+
+/// Starts logging. `RUST_LOG` sets what reaches stderr, errors only by
+/// default; the files get info and up unless `RUST_LOG` asks for more.
+/// Keep the guard until exit, or the last lines may not reach the file.
+pub fn init() -> Option<WorkerGuard> {
+    let stderr = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("error")));
+
+    let appender = Builder::new()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_PREFIX)
+        .filename_suffix(LOG_SUFFIX)
+        .max_log_files(KEPT_FILES)
+        .build(log_dir());
+    let (file, guard) = match appender {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")));
+            (Some(layer), Some(guard))
+        }
+        Err(e) => {
+            eprintln!("Logging to stderr only, the log folder is unusable: {}", e);
+            (None, None)
+        }
+    };
+
+    if let Err(e) = tracing_subscriber::registry().with(stderr).with(file).try_init() {
+        eprintln!("Failed to start logging: {}", e);
+    }
+    guard
+}
+
+/// The last lines of the newest log file, oldest first.
+pub fn recent_lines() -> Result<Vec<String>, std::io::Error> {
+    let newest = fs::read_dir(log_dir())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(LOG_PREFIX) && name.ends_with(LOG_SUFFIX))
+        })
+        // Dated names sort by day.
+        .max();
+    let Some(path) = newest else {
+        return Ok(Vec::new());
+    };
+
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    // Reading from the middle of the file starts partway through a line.
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    Ok(lines)
+}
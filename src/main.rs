@@ -1,58 +1,112 @@
 use eframe::egui;
-use global_hotkey::{GlobalHotKeyManager, hotkey::{HotKey, Modifiers, Code}};
+#[cfg(feature = "hotkeys")]
+use global_hotkey::{GlobalHotKeyManager, hotkey::{HotKey, Modifiers}};
 use std::sync::mpsc;
+#[cfg(all(not(target_os = "linux"), feature = "tray"))]
 use tray_icon::TrayIconBuilder;
 use image::ImageFormat;
+use thiserror::Error;
 
 mod app;
 mod clipboard;
+mod config;
+mod diagnostics;
 mod hotkeys;
-mod storage;
+mod keymap;
+#[cfg(all(target_os = "macos", feature = "tray"))]
+mod menu_bar;
+#[cfg(all(target_os = "linux", feature = "layer-shell"))]
+mod layer_shell;
+mod logging;
+#[cfg(feature = "ocr")]
+mod ocr;
+mod page_title;
+mod platform;
+mod platform_auth;
+mod redaction;
+mod session;
+#[cfg(feature = "spellcheck")]
+mod spellcheck;
 mod ui;
+mod undo;
+
+// The logic shared with other frontends, under the paths it had when it
+// lived in this crate.
+#[cfg(feature = "vault")]
+use trinket_core::crypto;
+#[cfg(feature = "sync")]
+use trinket_core::sync;
+use trinket_core::{bidi, events, import, picker, storage, transform};
 
 use app::TrinketApp;
+#[cfg(any(feature = "hotkeys", feature = "tray"))]
 use hotkeys::HotkeyEvent;
+use platform::Tray;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
-
-    let (hotkey_tx, hotkey_rx) = mpsc::channel();
+/// Why trinket couldn't start.
+#[derive(Debug, Error)]
+enum StartupError {
+    #[cfg(feature = "hotkeys")]
+    #[error("failed to register global hotkeys: {0}")]
+    Hotkeys(#[from] global_hotkey::Error),
+    #[error("failed to load the icon: {0}")]
+    Icon(#[from] image::ImageError),
+    #[error(transparent)]
+    Window(#[from] eframe::Error),
+    #[cfg(all(target_os = "linux", feature = "layer-shell"))]
+    #[error(transparent)]
+    LayerShell(#[from] layer_shell::LayerShellError),
+}
 
-    let manager = GlobalHotKeyManager::new()?;
-    let add_hotkey = HotKey::new(Some(Modifiers::SUPER | Modifiers::CONTROL), Code::PageUp);
-    let get_hotkey = HotKey::new(Some(Modifiers::SUPER | Modifiers::CONTROL), Code::PageDown);
+fn main() -> Result<(), StartupError> {
+    let _log_guard = logging::init();
 
-    manager.register(add_hotkey)?;
-    manager.register(get_hotkey)?;
+    let (hotkey_tx, hotkey_rx) = mpsc::channel();
 
-    let _hotkey_handler = std::thread::spawn(move || {
-        loop {
-            if let Ok(event) = global_hotkey::GlobalHotKeyEvent::receiver().try_recv() {
-                if event.id == add_hotkey.id() {
-                    let _ = hotkey_tx.send(HotkeyEvent::Add);
-                } else if event.id == get_hotkey.id() {
-                    let _ = hotkey_tx.send(HotkeyEvent::Get);
-                }
-            }
-            std::thread::sleep(std::time::Duration::from_millis(10));
+    // A jump list entry starts a new process; pass the task to the one
+    // already running, or handle it here once started.
+    #[cfg(windows)]
+    if let Some((task, event)) = platform::windows::launch_task() {
+        if platform::windows::forward_task(&task) {
+            return Ok(());
         }
-    });
+        let _ = hotkey_tx.send(event);
+    }
+    #[cfg(windows)]
+    platform::windows::spawn_task_receiver(hotkey_tx.clone());
 
-    let icon_bytes = include_bytes!("../assets/trinket.ico");
-    let img = image::load_from_memory_with_format(icon_bytes, ImageFormat::Ico)
-        .map_err(|e| format!("Failed to load tray icon: {}", e))?;
-    let rgba_img = img.to_rgba8();
-    let (width, height) = rgba_img.dimensions();
-    let icon = tray_icon::Icon::from_rgba(rgba_img.into_raw(), width, height)?;
+    #[cfg(feature = "hotkeys")]
+    let manager = GlobalHotKeyManager::new()?;
+    #[cfg(feature = "hotkeys")]
+    let bindings = register_hotkeys(&manager)?;
 
-    let _tray_icon = TrayIconBuilder::new()
-        .with_tooltip("Trinket - Text Snippets")
-        .with_icon(icon)
-        .build()?;
+    #[cfg(feature = "tray")]
+    let tray = {
+        let icon_bytes = include_bytes!("../assets/trinket.ico");
+        let img = image::load_from_memory_with_format(icon_bytes, ImageFormat::Ico)?;
+        let rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+        Some(build_tray(rgba_img.into_raw(), width, height, hotkey_tx.clone()))
+    };
+    #[cfg(not(feature = "tray"))]
+    let tray: Option<Result<Tray, String>> = None;
+    // With no hotkey or tray icon to bring it back, the window stays open.
+    let resident = cfg!(feature = "hotkeys") || tray.as_ref().is_some_and(Result::is_ok);
+
+    #[cfg(target_os = "macos")]
+    let services_tx = hotkey_tx.clone();
+    #[cfg(feature = "hotkeys")]
+    let hotkey_health = hotkeys::spawn_listener(bindings, hotkey_tx);
+    #[cfg(not(feature = "hotkeys"))]
+    let hotkey_health = {
+        // Only the jump list or the Services menu send events then, each
+        // through its own clone.
+        drop(hotkey_tx);
+        std::sync::Arc::default()
+    };
 
     let egui_icon_bytes = include_bytes!("../assets/trinket.ico");
-    let img = image::load_from_memory_with_format(egui_icon_bytes, ImageFormat::Ico)
-        .map_err(|e| format!("Failed to load icon: {}", e))?;
+    let img = image::load_from_memory_with_format(egui_icon_bytes, ImageFormat::Ico)?;
     let rgba_img = img.to_rgba8();
     let (width, height) = rgba_img.dimensions();
     let egui_icon = egui::IconData {
@@ -65,19 +119,81 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         viewport: egui::ViewportBuilder::default()
             .with_decorations(false)
             .with_transparent(true)
-            .with_always_on_top()
-            .with_visible(false)
+            .with_visible(!resident)
             .with_resizable(true)
             .with_inner_size([600.0, 400.0])
             .with_icon(egui_icon),
+        // A menu bar extra lives in the status item alone, with no Dock icon.
+        #[cfg(target_os = "macos")]
+        event_loop_builder: Some(Box::new(|builder| {
+            use winit::platform::macos::{ActivationPolicy, EventLoopBuilderExtMacOS};
+            builder.with_activation_policy(ActivationPolicy::Accessory);
+        })),
         ..Default::default()
     };
 
+    #[cfg(all(target_os = "linux", feature = "layer-shell"))]
+    if config::Config::load_checked(&config::Config::default_path()).0.layer_shell && layer_shell::is_available() {
+        return Ok(layer_shell::run(|ctx| TrinketApp::new(ctx, hotkey_rx, hotkey_health, tray))?);
+    }
+
     eframe::run_native(
         "Trinket",
         options,
-        Box::new(|cc| Ok(Box::new(TrinketApp::new(cc, hotkey_rx)))),
+        Box::new(|cc| {
+            // NSApp exists only once the event loop is built.
+            #[cfg(target_os = "macos")]
+            platform::services::register(services_tx);
+            Ok(Box::new(TrinketApp::new(&cc.egui_ctx, hotkey_rx, hotkey_health, tray)))
+        }),
     )?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Registers the global hotkeys, returning which event each one sends.
+#[cfg(feature = "hotkeys")]
+fn register_hotkeys(manager: &GlobalHotKeyManager) -> Result<Vec<(u32, HotkeyEvent)>, global_hotkey::Error> {
+    let add_hotkey = HotKey::new(Some(Modifiers::SUPER | Modifiers::CONTROL), hotkeys::ADD);
+    let get_hotkey = HotKey::new(Some(Modifiers::SUPER | Modifiers::CONTROL), hotkeys::GET);
+    let selection_hotkey = HotKey::new(Some(Modifiers::SUPER | Modifiers::CONTROL), hotkeys::SELECTION);
+    let snooze_hotkey = HotKey::new(Some(Modifiers::SUPER | Modifiers::CONTROL), hotkeys::SNOOZE);
+
+    manager.register(add_hotkey)?;
+    manager.register(get_hotkey)?;
+    manager.register(selection_hotkey)?;
+    manager.register(snooze_hotkey)?;
+
+    #[allow(unused_mut)]
+    let mut bindings = vec![
+        (add_hotkey.id(), HotkeyEvent::Add),
+        (get_hotkey.id(), HotkeyEvent::Get),
+        (selection_hotkey.id(), HotkeyEvent::CaptureSelection),
+        (snooze_hotkey.id(), HotkeyEvent::ToggleSnooze),
+    ];
+    #[cfg(feature = "ocr")]
+    {
+        let capture_hotkey = HotKey::new(Some(Modifiers::SUPER | Modifiers::CONTROL), hotkeys::CAPTURE);
+        manager.register(capture_hotkey)?;
+        bindings.push((capture_hotkey.id(), HotkeyEvent::Capture));
+    }
+    Ok(bindings)
+}
+
+/// The tray icon, or why there is none; trinket runs on without one.
+#[cfg(all(not(target_os = "linux"), feature = "tray"))]
+fn build_tray(rgba: Vec<u8>, width: u32, height: u32, _events: mpsc::Sender<HotkeyEvent>) -> Result<Tray, String> {
+    let icon = tray_icon::Icon::from_rgba(rgba, width, height).map_err(|e| e.to_string())?;
+    TrayIconBuilder::new()
+        .with_tooltip(app::TRAY_TOOLTIP)
+        .with_icon(icon)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// The tray icon, or why there is none; trinket runs on without one.
+/// Clicks on it arrive with the hotkey events.
+#[cfg(all(target_os = "linux", feature = "tray"))]
+fn build_tray(rgba: Vec<u8>, width: u32, height: u32, events: mpsc::Sender<HotkeyEvent>) -> Result<Tray, String> {
+    platform::status_notifier::StatusNotifier::start(&rgba, width, height, app::TRAY_TOOLTIP, events)
+}
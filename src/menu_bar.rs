@@ -0,0 +1,140 @@
+use chrono::{DateTime, Utc};
+use std::time::{Duration, Instant};
+use tray_icon::menu::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem};
+use tray_icon::TrayIcon;
+
+use crate::bidi;
+use crate::storage::{Snippet, UsageLog};
+
+const RECENT_COUNT: usize = 8;
+/// The menu can't say when it opens, so the recent snippets are checked
+/// this often instead.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+/// Longer titles are cut so the menu stays narrow.
+const TITLE_CHARS: usize = 40;
+
+pub enum MenuBarAction {
+    Add,
+    Get,
+    /// Copy the snippet with this id.
+    Copy(String),
+    /// Snooze the hotkeys, or resume them while snoozed.
+    ToggleSnooze,
+    Settings,
+    Quit,
+}
+
+/// The dropdown under the menu bar icon on macOS, where a status item is
+/// expected to open a menu: add, find, copy one of the recent snippets or
+/// quit without bringing up a window first.
+#[derive(Default)]
+pub struct MenuBar {
+    add: MenuId,
+    get: MenuId,
+    snooze: MenuId,
+    settings: MenuId,
+    quit: MenuId,
+    /// Menu ids of the recent items and the snippets they copy.
+    recent: Vec<(MenuId, String)>,
+    /// Snippet ids and titles last listed, so an unchanged menu isn't rebuilt.
+    listed: Vec<(String, String)>,
+    /// Whether the snooze item last read "Resume Hotkeys".
+    snoozed: bool,
+    refreshed: Option<Instant>,
+}
+
+impl MenuBar {
+    /// Rebuilds the tray menu when the recent snippets or the snooze
+    /// changed.
+    pub fn refresh(&mut self, tray: &TrayIcon, snippets: &[Snippet], usage: &UsageLog, snoozed: bool) -> tray_icon::menu::Result<()> {
+        if self.refreshed.is_some_and(|at| at.elapsed() < REFRESH_INTERVAL) {
+            return Ok(());
+        }
+        let listed = recent(snippets, usage);
+        if self.refreshed.is_some() && listed == self.listed && snoozed == self.snoozed {
+            self.refreshed = Some(Instant::now());
+            return Ok(());
+        }
+        self.refreshed = Some(Instant::now());
+
+        let menu = Menu::new();
+        let add = MenuItem::new("Add Snippet…", true, None);
+        let get = MenuItem::new("Find Snippet…", true, None);
+        menu.append_items(&[&add, &get, &PredefinedMenuItem::separator()])?;
+
+        self.recent.clear();
+        if listed.is_empty() {
+            menu.append(&MenuItem::new("No Recent Snippets", false, None))?;
+        } else {
+            menu.append(&MenuItem::new("Recent", false, None))?;
+            for (id, title) in &listed {
+                let item = MenuItem::new(title, true, None);
+                self.recent.push((item.id().clone(), id.clone()));
+                menu.append(&item)?;
+            }
+        }
+
+        let snooze = MenuItem::new(if snoozed { "Resume Hotkeys" } else { "Snooze Hotkeys" }, true, None);
+        let settings = MenuItem::new("Settings…", true, None);
+        let quit = MenuItem::new("Quit Trinket", true, None);
+        menu.append_items(&[&PredefinedMenuItem::separator(), &snooze, &settings, &quit])?;
+
+        self.add = add.id().clone();
+        self.get = get.id().clone();
+        self.snooze = snooze.id().clone();
+        self.settings = settings.id().clone();
+        self.quit = quit.id().clone();
+        self.listed = listed;
+        self.snoozed = snoozed;
+        tray.set_menu(Some(Box::new(menu)));
+        Ok(())
+    }
+
+    /// The next menu item the user picked, if any.
+    pub fn poll(&self) -> Option<MenuBarAction> {
+        while let Ok(event) = MenuEvent::receiver().try_recv() {
+            let action = if event.id == self.add {
+                MenuBarAction::Add
+            } else if event.id == self.get {
+                MenuBarAction::Get
+            } else if event.id == self.snooze {
+                MenuBarAction::ToggleSnooze
+            } else if event.id == self.settings {
+                MenuBarAction::Settings
+            } else if event.id == self.quit {
+                MenuBarAction::Quit
+            } else if let Some((_, id)) = self.recent.iter().find(|(item, _)| *item == event.id) {
+                MenuBarAction::Copy(id.clone())
+            } else {
+                // An item from a menu that has since been rebuilt.
+                continue;
+            };
+            return Some(action);
+        }
+        None
+    }
+}
+
+/// The most recently copied snippets, topped up with the newest ones, as
+/// ids and menu titles.
+fn recent(snippets: &[Snippet], usage: &UsageLog) -> Vec<(String, String)> {
+    let mut ranked: Vec<(bool, DateTime<Utc>, &Snippet)> = snippets
+        .iter()
+        .filter(|snippet| !snippet.meta.archived)
+        .map(|snippet| match usage.get(&snippet.id) {
+            Some(used) => (true, used.last_used, snippet),
+            None => (false, DateTime::<Utc>::from(snippet.created), snippet),
+        })
+        .collect();
+    ranked.sort_by(|a, b| (b.0, b.1).cmp(&(a.0, a.1)));
+    ranked
+        .into_iter()
+        .take(RECENT_COUNT)
+        .map(|(_, _, snippet)| (snippet.id.clone(), menu_title(snippet.safe_title())))
+        .collect()
+}
+
+fn menu_title(title: &str) -> String {
+    // A single & marks a mnemonic in menu item text.
+    bidi::clip(title, TITLE_CHARS).replace('&', "&&")
+}
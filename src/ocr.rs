@@ -0,0 +1,44 @@
+//! Screen capture and text recognition for the OCR capture mode. Only built
+//! with the `ocr` feature, which needs Tesseract and its English data installed.
+
+use image::RgbaImage;
+use std::sync::mpsc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum OcrError {
+    #[error("no monitor found")]
+    NoMonitor,
+    #[error("screen capture failed: {0}")]
+    Capture(#[from] xcap::XCapError),
+    #[error("text recognition failed: {0}")]
+    Recognize(#[from] tesseract::TesseractError),
+}
+
+/// Takes a screenshot of the primary monitor.
+pub fn capture_screen() -> Result<RgbaImage, OcrError> {
+    let monitors = xcap::Monitor::all()?;
+    let primary = monitors.iter()
+        .find(|monitor| monitor.is_primary().unwrap_or(false))
+        .or_else(|| monitors.first())
+        .ok_or(OcrError::NoMonitor)?;
+    Ok(primary.capture_image()?)
+}
+
+/// Runs OCR on `image` in the background and sends the recognized text, or
+/// an error message, once done.
+pub fn recognize_in_background(image: RgbaImage, sender: mpsc::Sender<Result<String, String>>) {
+    std::thread::spawn(move || {
+        let result = recognize(&image).map_err(|e| OcrError::from(e).to_string());
+        let _ = sender.send(result);
+    });
+}
+
+fn recognize(image: &RgbaImage) -> Result<String, tesseract::TesseractError> {
+    let (width, height) = image.dimensions();
+    let text = tesseract::Tesseract::new(None, Some("eng"))?
+        .set_frame(image.as_raw(), width as i32, height as i32, 4, 4 * width as i32)?
+        .recognize()?
+        .get_text()?;
+    Ok(text.trim().to_string())
+}
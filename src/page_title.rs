@@ -0,0 +1,66 @@
+use std::io::Read;
+use std::sync::mpsc;
+use std::time::Duration;
+use thiserror::Error;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// `<title>` lives in the head, so there is no need to download whole pages.
+const MAX_HEAD_BYTES: u64 = 64 * 1024;
+
+/// Fetches the `<title>` of `url` in the background and sends
+/// `(snippet id, title)` once it is known. Failures are only logged; the
+/// snippet keeps its derived title.
+pub fn fetch_page_title(id: String, url: String, sender: mpsc::Sender<(String, String)>) {
+    std::thread::spawn(move || match fetch(&url) {
+        Ok(Some(title)) => {
+            let _ = sender.send((id, title));
+        }
+        Ok(None) => log::info!("No title found at {}", url),
+        Err(e) => log::info!("Could not fetch title of {}: {}", url, e),
+    });
+}
+
+#[derive(Debug, Error)]
+enum FetchError {
+    #[error(transparent)]
+    Request(#[from] attohttpc::Error),
+    #[error("HTTP {0}")]
+    Status(attohttpc::StatusCode),
+    #[error(transparent)]
+    Read(#[from] std::io::Error),
+}
+
+fn fetch(url: &str) -> Result<Option<String>, FetchError> {
+    let response = attohttpc::get(url)
+        .timeout(FETCH_TIMEOUT)
+        .header("User-Agent", concat!("trinket/", env!("CARGO_PKG_VERSION")))
+        .send()?;
+    if !response.is_success() {
+        return Err(FetchError::Status(response.status()));
+    }
+
+    let mut head = Vec::new();
+    response.take(MAX_HEAD_BYTES).read_to_end(&mut head)?;
+    Ok(extract_title(&String::from_utf8_lossy(&head)))
+}
+
+fn extract_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+
+    let title = decode_entities(&html[start..end]);
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,102 @@
+//! Which application has the focus: the foreground window's process on
+//! Windows, the frontmost application on macOS and the active X11 window
+//! through `xdotool` elsewhere. Wayland has no common way to ask, so there
+//! this finds nothing unless the app runs under XWayland.
+
+/// The focused application. `name` is the process or application name,
+/// `title` the window title where the platform tells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveApp {
+    pub name: String,
+    pub title: String,
+}
+
+/// The application focused right now, if it can be told.
+pub fn active_app() -> Option<ActiveApp> {
+    imp::active_app()
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::ActiveApp;
+    use std::path::Path;
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION};
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId};
+
+    pub fn active_app() -> Option<ActiveApp> {
+        unsafe {
+            let window = GetForegroundWindow();
+            if window.is_invalid() {
+                return None;
+            }
+            let mut title = [0u16; 512];
+            let title_len = GetWindowTextW(window, &mut title).max(0) as usize;
+            let mut pid = 0;
+            GetWindowThreadProcessId(window, Some(&mut pid));
+
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+            let mut path = [0u16; 1024];
+            let mut path_len = path.len() as u32;
+            let queried = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(path.as_mut_ptr()), &mut path_len);
+            let _ = CloseHandle(process);
+            queried.ok()?;
+
+            let path = String::from_utf16_lossy(&path[..path_len as usize]);
+            Some(ActiveApp {
+                name: Path::new(&path).file_stem()?.to_string_lossy().into_owned(),
+                title: String::from_utf16_lossy(&title[..title_len]),
+            })
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::ActiveApp;
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+
+    #[link(name = "AppKit", kind = "framework")]
+    extern "C" {}
+
+    /// Window titles need the accessibility permission, so only the
+    /// application name is read.
+    pub fn active_app() -> Option<ActiveApp> {
+        unsafe {
+            let workspace: Retained<AnyObject> = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let app: Option<Retained<AnyObject>> = msg_send![&*workspace, frontmostApplication];
+            let name: Option<Retained<NSString>> = msg_send![&*app?, localizedName];
+            Some(ActiveApp {
+                name: name?.to_string(),
+                title: String::new(),
+            })
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use super::ActiveApp;
+    use std::process::Command;
+
+    pub fn active_app() -> Option<ActiveApp> {
+        let pid = xdotool(&["getactivewindow", "getwindowpid"])?;
+        let name = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+        Some(ActiveApp {
+            name: name.trim().to_string(),
+            title: xdotool(&["getactivewindow", "getwindowname"]).unwrap_or_default(),
+        })
+    }
+
+    fn xdotool(args: &[&str]) -> Option<String> {
+        let output = Command::new("xdotool").args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
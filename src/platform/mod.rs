@@ -0,0 +1,24 @@
+//! Hooks into the desktop shell beyond the hotkeys.
+
+pub mod active_window;
+#[cfg(feature = "hotkeys")]
+pub mod selection;
+#[cfg(target_os = "macos")]
+pub mod services;
+#[cfg(all(target_os = "linux", feature = "tray"))]
+pub mod status_notifier;
+#[cfg(any(windows, target_os = "macos"))]
+pub mod window_effects;
+#[cfg(windows)]
+pub mod windows;
+
+/// The tray icon. tray-icon needs a GTK main loop on Linux, which trinket
+/// doesn't run, so there the icon is a StatusNotifierItem of our own.
+#[cfg(all(target_os = "linux", feature = "tray"))]
+pub use status_notifier::StatusNotifier as Tray;
+#[cfg(all(not(target_os = "linux"), feature = "tray"))]
+pub use tray_icon::TrayIcon as Tray;
+
+/// Built without the tray feature there is never a tray icon.
+#[cfg(not(feature = "tray"))]
+pub enum Tray {}
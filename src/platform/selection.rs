@@ -0,0 +1,222 @@
+//! Copies what's selected in the focused app by sending it the copy
+//! shortcut, then puts back what the clipboard held before. The shortcut
+//! goes through `SendInput` on Windows, System Events on macOS (which needs
+//! the Accessibility permission), and `xdotool` on X11 or `wtype` on
+//! Wayland elsewhere.
+//!
+//! Copies the app marks as private, with the hints password managers set
+//! to keep clipboard history tools away, are dropped.
+
+use arboard::{Clipboard, ImageData};
+use std::time::{Duration, Instant};
+use zeroize::{Zeroize, Zeroizing};
+
+/// How long the app gets to answer the copy.
+const COPY_TIMEOUT: Duration = Duration::from_millis(600);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// What the clipboard held before the copy.
+enum Saved {
+    Text(Zeroizing<String>),
+    Image(ImageData<'static>),
+    Nothing,
+}
+
+/// What copying the selection brought back.
+pub enum Captured {
+    Text(String),
+    /// The app marked the copy to be left out of clipboard history.
+    Concealed,
+}
+
+/// The text selected in the focused app. Blocks for up to a second, so
+/// it's run off the UI thread. Fails when nothing was selected, or when
+/// the copy couldn't be sent.
+pub fn capture_selection() -> Result<Captured, String> {
+    let mut clipboard = Clipboard::new().map_err(|e| format!("No clipboard: {}", e))?;
+    let saved = match clipboard.get_text() {
+        Ok(text) => Saved::Text(Zeroizing::new(text)),
+        Err(_) => clipboard.get_image().map_or(Saved::Nothing, Saved::Image),
+    };
+    // Cleared first, so an app that copies nothing isn't mistaken for one
+    // that copied what was already there.
+    let _ = clipboard.clear();
+
+    let captured = imp::send_copy().and_then(|()| {
+        let started = Instant::now();
+        loop {
+            match clipboard.get_text() {
+                Ok(mut text) if !text.is_empty() => {
+                    if imp::is_concealed() {
+                        text.zeroize();
+                        return Ok(Captured::Concealed);
+                    }
+                    return Ok(Captured::Text(text));
+                }
+                _ if started.elapsed() >= COPY_TIMEOUT => return Err("Nothing was selected".to_string()),
+                _ => std::thread::sleep(POLL_INTERVAL),
+            }
+        }
+    });
+
+    let restored = match saved {
+        Saved::Text(text) => clipboard.set_text(text.as_str()),
+        Saved::Image(image) => clipboard.set_image(image),
+        Saved::Nothing => clipboard.clear(),
+    };
+    if let Err(e) = restored {
+        log::warn!("Could not put the clipboard back after capturing the selection: {}", e);
+    }
+    captured
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::time::{Duration, Instant};
+    use windows::core::w;
+    use windows::Win32::Foundation::HGLOBAL;
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard, RegisterClipboardFormatW,
+    };
+    use windows::Win32::System::Memory::{GlobalLock, GlobalUnlock};
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS,
+        KEYEVENTF_KEYUP, VIRTUAL_KEY, VK_C, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT,
+    };
+
+    /// The hotkey's own keys are let go of first, or the app would see
+    /// Win+Ctrl+C.
+    const RELEASE_TIMEOUT: Duration = Duration::from_secs(1);
+
+    pub fn send_copy() -> Result<(), String> {
+        let started = Instant::now();
+        while [VK_LWIN, VK_RWIN, VK_SHIFT, VK_MENU].iter().any(|&key| is_down(key)) {
+            if started.elapsed() >= RELEASE_TIMEOUT {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let inputs = [key(VK_CONTROL, false), key(VK_C, false), key(VK_C, true), key(VK_CONTROL, true)];
+        let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            return Err("Could not send the copy shortcut".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether the clipboard carries the formats Windows' own clipboard
+    /// history checks: the first excludes the copy outright, the second
+    /// does when it holds zero.
+    pub fn is_concealed() -> bool {
+        unsafe {
+            let excluded = RegisterClipboardFormatW(w!("ExcludeClipboardContentFromMonitorProcessing"));
+            if excluded != 0 && IsClipboardFormatAvailable(excluded).is_ok() {
+                return true;
+            }
+            let history = RegisterClipboardFormatW(w!("CanIncludeInClipboardHistory"));
+            if history == 0 || IsClipboardFormatAvailable(history).is_err() || OpenClipboard(None).is_err() {
+                return false;
+            }
+            let allowed = GetClipboardData(history).ok().and_then(|data| {
+                let memory = HGLOBAL(data.0);
+                let value = GlobalLock(memory) as *const u32;
+                let allowed = (!value.is_null()).then(|| *value != 0);
+                let _ = GlobalUnlock(memory);
+                allowed
+            });
+            let _ = CloseClipboard();
+            allowed == Some(false)
+        }
+    }
+
+    fn is_down(key: VIRTUAL_KEY) -> bool {
+        unsafe { GetAsyncKeyState(key.0 as i32) < 0 }
+    }
+
+    fn key(key: VIRTUAL_KEY, up: bool) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: key,
+                    wScan: 0,
+                    dwFlags: if up { KEYEVENTF_KEYUP } else { KEYBD_EVENT_FLAGS(0) },
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use std::process::Command;
+    use std::time::Duration;
+
+    /// Time to let go of ⌃⌘, which System Events would otherwise add to
+    /// the ⌘C it sends.
+    const RELEASE_DELAY: Duration = Duration::from_millis(250);
+
+    pub fn send_copy() -> Result<(), String> {
+        std::thread::sleep(RELEASE_DELAY);
+        let output = Command::new("osascript")
+            .args(["-e", r#"tell application "System Events" to keystroke "c" using command down"#])
+            .output()
+            .map_err(|e| format!("Could not run osascript: {}", e))?;
+        if !output.status.success() {
+            return Err("Could not send ⌘C; allow trinket under Privacy & Security → Accessibility".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn is_concealed() -> bool {
+        let pasteboard: Retained<AnyObject> = unsafe { msg_send![class!(NSPasteboard), generalPasteboard] };
+        crate::platform::services::is_concealed(&pasteboard)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use std::process::Command;
+
+    const PASSWORD_MANAGER_HINT: &str = "x-kde-passwordManagerHint";
+
+    pub fn send_copy() -> Result<(), String> {
+        // xdotool's --clearmodifiers lifts the hotkey's keys while it types.
+        let (program, args): (&str, &[&str]) = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            ("wtype", &["-M", "ctrl", "c", "-m", "ctrl"])
+        } else {
+            ("xdotool", &["key", "--clearmodifiers", "ctrl+c"])
+        };
+        let status = Command::new(program)
+            .args(args)
+            .status()
+            .map_err(|e| format!("Could not run {} to copy the selection: {}", program, e))?;
+        if !status.success() {
+            return Err(format!("{} could not send the copy shortcut", program));
+        }
+        Ok(())
+    }
+
+    /// Whether the clipboard offers KDE's password manager hint, which
+    /// Klipper and the like leave out of their history.
+    pub fn is_concealed() -> bool {
+        let (program, args): (&str, &[&str]) = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            ("wl-paste", &["--list-types"])
+        } else {
+            ("xclip", &["-selection", "clipboard", "-target", "TARGETS", "-out"])
+        };
+        match Command::new(program).args(args).output() {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).lines().any(|kind| kind.trim() == PASSWORD_MANAGER_HINT),
+            Err(e) => {
+                log::debug!("Could not run {} to read the clipboard's formats: {}", program, e);
+                false
+            }
+        }
+    }
+}
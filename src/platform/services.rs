@@ -0,0 +1,79 @@
+//! "Save Selection to Trinket" in the macOS Services menu, so text selected
+//! in any app can be saved without opening the add window. The menu item is
+//! declared under `NSServices` in the app bundle's Info.plist
+//! (`assets/Info.plist`); here the running app answers it.
+
+use objc2::rc::Retained;
+use objc2::runtime::{AnyObject, NSObject};
+use objc2::{define_class, msg_send, AllocAnyThread, DefinedClass};
+use objc2_foundation::NSString;
+use std::sync::mpsc;
+
+use crate::hotkeys::HotkeyEvent;
+
+/// `NSPasteboardTypeString`.
+const STRING_TYPE: &str = "public.utf8-plain-text";
+/// Types apps put on the pasteboard to keep a copy out of clipboard
+/// history, as set out at nspasteboard.org.
+const CONCEALED_TYPES: [&str; 2] = ["org.nspasteboard.ConcealedType", "org.nspasteboard.TransientType"];
+
+define_class!(
+    #[unsafe(super(NSObject))]
+    #[name = "TrinketServiceProvider"]
+    #[ivars = mpsc::Sender<HotkeyEvent>]
+    struct ServiceProvider;
+
+    impl ServiceProvider {
+        /// The `NSMessage` named in Info.plist.
+        #[unsafe(method(saveSelection:userData:error:))]
+        fn save_selection(&self, pasteboard: &AnyObject, _user_data: Option<&NSString>, _error: *mut *mut NSString) {
+            if is_concealed(pasteboard) {
+                log::info!("Not saving a selection its app marked as private");
+                return;
+            }
+            let text: Option<Retained<NSString>> = unsafe { msg_send![pasteboard, stringForType: &*NSString::from_str(STRING_TYPE)] };
+            match text.map(|text| text.to_string()).filter(|text| !text.trim().is_empty()) {
+                Some(text) => {
+                    let _ = self.ivars().send(HotkeyEvent::SaveSelection(text));
+                }
+                None => log::info!("The selection sent to the service holds no text"),
+            }
+        }
+    }
+);
+
+impl ServiceProvider {
+    fn new(events: mpsc::Sender<HotkeyEvent>) -> Retained<Self> {
+        let this = Self::alloc().set_ivars(events);
+        unsafe { msg_send![super(this), init] }
+    }
+}
+
+/// Whether the app that wrote `pasteboard` marked it to be left out of
+/// clipboard history.
+pub fn is_concealed(pasteboard: &AnyObject) -> bool {
+    CONCEALED_TYPES.iter().any(|kind| {
+        let data: Option<Retained<AnyObject>> = unsafe { msg_send![pasteboard, dataForType: &*NSString::from_str(kind)] };
+        data.is_some()
+    })
+}
+
+/// Answers the service from now on; saved selections arrive with the hotkey
+/// events. Call on the main thread once the application is running.
+pub fn register(events: mpsc::Sender<HotkeyEvent>) {
+    let provider = ServiceProvider::new(events);
+    unsafe {
+        let app: Retained<AnyObject> = msg_send![objc2::class!(NSApplication), sharedApplication];
+        let _: () = msg_send![&*app, setServicesProvider: &*provider];
+        NSUpdateDynamicServices();
+    }
+    // The application holds the provider weakly; it serves for as long as
+    // the app runs.
+    std::mem::forget(provider);
+}
+
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {
+    /// Picks up Info.plist changes without logging out.
+    fn NSUpdateDynamicServices();
+}
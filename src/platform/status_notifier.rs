@@ -0,0 +1,127 @@
+//! The tray icon on Linux, served as a StatusNotifierItem over D-Bus. The
+//! panels of KDE, Xfce, Cinnamon and most wlroots bars host these directly;
+//! stock GNOME hosts none without the AppIndicator extension, which is
+//! detected up front so the user can be told instead of the icon silently
+//! missing.
+
+use std::sync::mpsc;
+use zbus::blocking::{connection, Connection, Proxy};
+use zbus::interface;
+use zbus::names::BusName;
+
+use crate::hotkeys::HotkeyEvent;
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+
+/// An icon as the spec wants it: width, height and ARGB32 pixels in network
+/// byte order.
+type Pixmap = (i32, i32, Vec<u8>);
+
+struct Item {
+    icon: Vec<Pixmap>,
+    tooltip: String,
+    events: mpsc::Sender<HotkeyEvent>,
+}
+
+#[interface(name = "org.kde.StatusNotifierItem")]
+impl Item {
+    #[zbus(property)]
+    fn category(&self) -> &str {
+        "ApplicationStatus"
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        "trinket"
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> &str {
+        "Trinket"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "Active"
+    }
+
+    #[zbus(property)]
+    fn icon_pixmap(&self) -> Vec<Pixmap> {
+        self.icon.clone()
+    }
+
+    /// Icon name, icon, title and text.
+    #[zbus(property)]
+    fn tool_tip(&self) -> (String, Vec<Pixmap>, String, String) {
+        let (title, text) = self.tooltip.split_once('\n').unwrap_or((&self.tooltip, ""));
+        (String::new(), Vec::new(), title.to_string(), text.to_string())
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        false
+    }
+
+    /// A click on the icon opens the get window.
+    fn activate(&self, _x: i32, _y: i32) {
+        let _ = self.events.send(HotkeyEvent::Get);
+    }
+
+    /// A middle click opens the add window.
+    fn secondary_activate(&self, _x: i32, _y: i32) {
+        let _ = self.events.send(HotkeyEvent::Add);
+    }
+
+    /// There is no menu to show, so a right click snoozes the hotkeys or
+    /// resumes them.
+    fn context_menu(&self, _x: i32, _y: i32) {
+        let _ = self.events.send(HotkeyEvent::ToggleSnooze);
+    }
+}
+
+pub struct StatusNotifier {
+    /// Serves the item for as long as it is kept.
+    connection: Connection,
+}
+
+impl StatusNotifier {
+    /// Puts the icon in the panel; clicks on it arrive as hotkey events.
+    /// Fails when no panel is hosting status items.
+    pub fn start(rgba: &[u8], width: u32, height: u32, tooltip: &str, events: mpsc::Sender<HotkeyEvent>) -> Result<Self, String> {
+        let argb = rgba.chunks_exact(4).flat_map(|pixel| [pixel[3], pixel[0], pixel[1], pixel[2]]).collect();
+        let item = Item {
+            icon: vec![(width as i32, height as i32, argb)],
+            tooltip: tooltip.to_string(),
+            events,
+        };
+        let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+        let connection = connection::Builder::session()
+            .and_then(|builder| builder.name(name.as_str()))
+            .and_then(|builder| builder.serve_at(ITEM_PATH, item))
+            .and_then(|builder| builder.build())
+            .map_err(|e| e.to_string())?;
+        if !host_registered(&connection) {
+            return Err("no panel is showing tray icons (on GNOME, the AppIndicator extension adds one)".to_string());
+        }
+        connection
+            .call_method(Some(WATCHER), WATCHER_PATH, Some(WATCHER), "RegisterStatusNotifierItem", &(name.as_str(),))
+            .map_err(|e| e.to_string())?;
+        Ok(Self { connection })
+    }
+
+    pub fn set_tooltip<S: AsRef<str>>(&self, tooltip: Option<S>) -> zbus::Result<()> {
+        let item = self.connection.object_server().interface::<_, Item>(ITEM_PATH)?;
+        item.get_mut().tooltip = tooltip.map(|text| text.as_ref().to_string()).unwrap_or_default();
+        self.connection.emit_signal(None::<BusName>, ITEM_PATH, ITEM_INTERFACE, "NewToolTip", &())
+    }
+}
+
+/// Whether a panel has told the watcher it shows status items.
+fn host_registered(connection: &Connection) -> bool {
+    Proxy::new(connection, WATCHER, WATCHER_PATH, WATCHER)
+        .and_then(|watcher| watcher.get_property::<bool>("IsStatusNotifierHostRegistered"))
+        .unwrap_or(false)
+}
@@ -0,0 +1,113 @@
+//! A blurred backdrop behind the frameless window so it reads as a native
+//! quick switcher rather than a grey rectangle: the acrylic backdrop on
+//! Windows 11 and a visual effect view on macOS. The frame follows the
+//! light or dark style egui draws with.
+
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+/// Turns the backdrop on or off and styles the frame for `dark`. Fails on
+/// systems without the backdrop, such as Windows 10.
+pub fn apply(window: &impl HasWindowHandle, backdrop: bool, dark: bool) -> Result<(), String> {
+    let handle = window.window_handle().map_err(|e| e.to_string())?;
+    imp::apply(handle.as_raw(), backdrop, dark)
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::RawWindowHandle;
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Dwm::{
+        DwmExtendFrameIntoClientArea, DwmSetWindowAttribute, DWMSBT_AUTO, DWMSBT_TRANSIENTWINDOW, DWMWA_SYSTEMBACKDROP_TYPE,
+        DWMWA_USE_IMMERSIVE_DARK_MODE, DWMWA_WINDOW_CORNER_PREFERENCE, DWMWCP_ROUND, DWMWINDOWATTRIBUTE,
+    };
+    use windows::Win32::UI::Controls::MARGINS;
+
+    pub fn apply(handle: RawWindowHandle, backdrop: bool, dark: bool) -> Result<(), String> {
+        let RawWindowHandle::Win32(handle) = handle else {
+            return Err("not a Win32 window".to_string());
+        };
+        let window = HWND(handle.hwnd.get() as *mut c_void);
+        unsafe {
+            set_attribute(window, DWMWA_USE_IMMERSIVE_DARK_MODE, &i32::from(dark))?;
+            // Windows 10 has no rounded corners to ask for; it keeps square ones.
+            let _ = set_attribute(window, DWMWA_WINDOW_CORNER_PREFERENCE, &DWMWCP_ROUND);
+            // The backdrop shows through wherever the client area is
+            // transparent, which is all of it once the frame covers it.
+            let inset = if backdrop { -1 } else { 0 };
+            let margins = MARGINS {
+                cxLeftWidth: inset,
+                cxRightWidth: inset,
+                cyTopHeight: inset,
+                cyBottomHeight: inset,
+            };
+            DwmExtendFrameIntoClientArea(window, &margins).map_err(|e| e.message())?;
+            let kind = if backdrop { DWMSBT_TRANSIENTWINDOW } else { DWMSBT_AUTO };
+            set_attribute(window, DWMWA_SYSTEMBACKDROP_TYPE, &kind)
+                .map_err(|e| format!("{} (the backdrop needs Windows 11)", e))
+        }
+    }
+
+    unsafe fn set_attribute<T>(window: HWND, attribute: DWMWINDOWATTRIBUTE, value: &T) -> Result<(), String> {
+        DwmSetWindowAttribute(window, attribute, value as *const T as *const c_void, size_of::<T>() as u32)
+            .map_err(|e| e.message())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::RawWindowHandle;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, Bool};
+    use objc2::{class, msg_send};
+    use objc2_foundation::{NSRect, NSString};
+
+    /// `NSVisualEffectMaterialPopover`, the material of Spotlight-like panels.
+    const MATERIAL_POPOVER: isize = 6;
+    /// `NSVisualEffectBlendingModeBehindWindow`.
+    const BEHIND_WINDOW: isize = 0;
+    /// `NSVisualEffectStateActive`, so the blur stays when the window isn't key.
+    const ALWAYS_ACTIVE: isize = 1;
+    /// `NSViewWidthSizable | NSViewHeightSizable`.
+    const RESIZE_WITH_PARENT: usize = 2 | 16;
+    /// `NSWindowBelow`.
+    const BELOW: isize = -1;
+
+    pub fn apply(handle: RawWindowHandle, backdrop: bool, dark: bool) -> Result<(), String> {
+        let RawWindowHandle::AppKit(handle) = handle else {
+            return Err("not an AppKit window".to_string());
+        };
+        unsafe {
+            let view: &AnyObject = handle.ns_view.cast::<AnyObject>().as_ref();
+            let window: Option<Retained<AnyObject>> = msg_send![view, window];
+            let window = window.ok_or("the view has no window")?;
+            let name = NSString::from_str(if dark { "NSAppearanceNameDarkAqua" } else { "NSAppearanceNameAqua" });
+            let appearance: Option<Retained<AnyObject>> = msg_send![class!(NSAppearance), appearanceNamed: &*name];
+            let _: () = msg_send![&*window, setAppearance: appearance.as_deref()];
+
+            // Drop the effect view added last time before adding a new one.
+            let subviews: Retained<AnyObject> = msg_send![view, subviews];
+            let count: usize = msg_send![&*subviews, count];
+            for index in 0..count {
+                let subview: Retained<AnyObject> = msg_send![&*subviews, objectAtIndex: index];
+                let is_effect: Bool = msg_send![&*subview, isKindOfClass: class!(NSVisualEffectView)];
+                if is_effect.as_bool() {
+                    let _: () = msg_send![&*subview, removeFromSuperview];
+                }
+            }
+            if !backdrop {
+                return Ok(());
+            }
+
+            let bounds: NSRect = msg_send![view, bounds];
+            let effect: Retained<AnyObject> = msg_send![class!(NSVisualEffectView), new];
+            let _: () = msg_send![&*effect, setFrame: bounds];
+            let _: () = msg_send![&*effect, setMaterial: MATERIAL_POPOVER];
+            let _: () = msg_send![&*effect, setBlendingMode: BEHIND_WINDOW];
+            let _: () = msg_send![&*effect, setState: ALWAYS_ACTIVE];
+            let _: () = msg_send![&*effect, setAutoresizingMask: RESIZE_WITH_PARENT];
+            let _: () = msg_send![view, addSubview: &*effect, positioned: BELOW, relativeTo: None::<&AnyObject>];
+        }
+        Ok(())
+    }
+}
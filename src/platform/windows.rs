@@ -0,0 +1,211 @@
+//! The taskbar jump list: "Add snippet" and "Find snippet" tasks and the
+//! first few pinned snippets. Each entry starts trinket with an argument;
+//! that process hands the argument to the running one through a
+//! message-only window and exits, and the running one sends it on as a
+//! hotkey event.
+
+use ::windows::core::{w, Interface, HSTRING, PCWSTR};
+use ::windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use ::windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+use ::windows::Win32::System::Com::StructuredStorage::PROPVARIANT;
+use ::windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+use ::windows::Win32::System::DataExchange::COPYDATASTRUCT;
+use ::windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use ::windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+use ::windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+use ::windows::Win32::UI::Shell::{DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, ShellLink};
+use ::windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, FindWindowExW, GetMessageW, RegisterClassW, SendMessageW,
+    HWND_MESSAGE, MSG, WINDOW_EX_STYLE, WINDOW_STYLE, WM_COPYDATA, WNDCLASSW,
+};
+use std::cell::RefCell;
+use std::path::Path;
+use std::sync::mpsc;
+
+use crate::hotkeys::HotkeyEvent;
+
+/// Pinned snippets beyond this many are left out of the jump list.
+pub const PINNED_TASKS: usize = 5;
+
+const ADD_ARG: &str = "--add";
+const FIND_ARG: &str = "--find";
+const COPY_ARG: &str = "--copy";
+const CLASS_NAME: PCWSTR = w!("TrinketJumpList");
+/// Tells our `WM_COPYDATA` messages apart from anything else sent there.
+const TASK_MESSAGE: usize = 0x5452_4e4b;
+
+thread_local! {
+    static SENDER: RefCell<Option<mpsc::Sender<HotkeyEvent>>> = const { RefCell::new(None) };
+}
+
+/// The jump list task this process was started for, as passed on the
+/// command line and as the event it stands for.
+pub fn launch_task() -> Option<(String, HotkeyEvent)> {
+    let task = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    parse_task(&task).map(|event| (task, event))
+}
+
+fn parse_task(task: &str) -> Option<HotkeyEvent> {
+    match task.split_once(' ') {
+        Some((COPY_ARG, id)) if !id.is_empty() => Some(HotkeyEvent::CopySnippet(id.to_string())),
+        None if task == ADD_ARG => Some(HotkeyEvent::Add),
+        None if task == FIND_ARG => Some(HotkeyEvent::Get),
+        _ => None,
+    }
+}
+
+/// Hands `task` to the trinket that is already running. False when there
+/// is none, so this process should start up and handle it itself.
+pub fn forward_task(task: &str) -> bool {
+    let Ok(window) = (unsafe { FindWindowExW(Some(HWND_MESSAGE), None, CLASS_NAME, PCWSTR::null()) }) else {
+        return false;
+    };
+    let data = COPYDATASTRUCT {
+        dwData: TASK_MESSAGE,
+        cbData: task.len() as u32,
+        lpData: task.as_ptr() as *mut _,
+    };
+    unsafe { SendMessageW(window, WM_COPYDATA, None, Some(LPARAM(&data as *const COPYDATASTRUCT as isize))) };
+    true
+}
+
+/// Starts receiving tasks forwarded by later launches and sends them on to
+/// the app with the hotkey events.
+pub fn spawn_task_receiver(sender: mpsc::Sender<HotkeyEvent>) {
+    std::thread::spawn(move || {
+        SENDER.set(Some(sender));
+        if let Err(e) = unsafe { create_window() } {
+            log::warn!("Jump list tasks won't reach this instance: {}", e);
+            return;
+        }
+        let mut message = MSG::default();
+        while unsafe { GetMessageW(&mut message, None, 0, 0) }.as_bool() {
+            unsafe { DispatchMessageW(&message) };
+        }
+    });
+}
+
+unsafe fn create_window() -> ::windows::core::Result<HWND> {
+    let instance = GetModuleHandleW(PCWSTR::null())?;
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(window_proc),
+        hInstance: instance.into(),
+        lpszClassName: CLASS_NAME,
+        ..Default::default()
+    };
+    if RegisterClassW(&class) == 0 {
+        return Err(::windows::core::Error::from_win32());
+    }
+    CreateWindowExW(
+        WINDOW_EX_STYLE::default(),
+        CLASS_NAME,
+        PCWSTR::null(),
+        WINDOW_STYLE::default(),
+        0,
+        0,
+        0,
+        0,
+        Some(HWND_MESSAGE),
+        None,
+        Some(instance.into()),
+        None,
+    )
+}
+
+unsafe extern "system" fn window_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if message == WM_COPYDATA {
+        let data = &*(lparam.0 as *const COPYDATASTRUCT);
+        if data.dwData != TASK_MESSAGE {
+            return LRESULT(0);
+        }
+        let bytes = std::slice::from_raw_parts(data.lpData as *const u8, data.cbData as usize);
+        if let Some(event) = std::str::from_utf8(bytes).ok().and_then(parse_task) {
+            SENDER.with_borrow(|sender| {
+                if let Some(sender) = sender {
+                    let _ = sender.send(event);
+                }
+            });
+        }
+        return LRESULT(1);
+    }
+    DefWindowProcW(window, message, wparam, lparam)
+}
+
+/// Replaces the jump list with the two tasks and `pinned`, given as
+/// snippet ids and titles. COM calls can block, so this runs on its own
+/// thread.
+pub fn set_jump_list(pinned: Vec<(String, String)>) {
+    std::thread::spawn(move || {
+        let exe = match std::env::current_exe() {
+            Ok(exe) => exe,
+            Err(e) => {
+                log::warn!("Could not update the jump list: {}", e);
+                return;
+            }
+        };
+        unsafe {
+            if let Err(e) = CoInitializeEx(None, COINIT_APARTMENTTHREADED).ok() {
+                log::warn!("Could not update the jump list: {}", e);
+                return;
+            }
+            if let Err(e) = build_jump_list(&exe, &pinned) {
+                log::warn!("Could not update the jump list: {}", e);
+            }
+            CoUninitialize();
+        }
+    });
+}
+
+unsafe fn build_jump_list(exe: &Path, pinned: &[(String, String)]) -> ::windows::core::Result<()> {
+    let exe = HSTRING::from(exe);
+    let list: ICustomDestinationList = CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+    let mut slots = 0;
+    let removed: IObjectArray = list.BeginList(&mut slots)?;
+
+    let tasks: IObjectCollection = CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+    tasks.AddObject(&shell_link(&exe, ADD_ARG, "Add snippet")?)?;
+    tasks.AddObject(&shell_link(&exe, FIND_ARG, "Find snippet")?)?;
+    list.AddUserTasks(&tasks.cast::<IObjectArray>()?)?;
+
+    // The shell refuses a category holding an entry the user removed from
+    // the list, so those stay out until they're unpinned and pinned again.
+    let removed = removed_arguments(&removed)?;
+    let items: IObjectCollection = CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+    let mut any = false;
+    for (id, title) in pinned {
+        let arguments = format!("{} {}", COPY_ARG, id);
+        if !removed.contains(&arguments) {
+            items.AddObject(&shell_link(&exe, &arguments, title)?)?;
+            any = true;
+        }
+    }
+    if any {
+        list.AppendCategory(w!("Pinned"), &items.cast::<IObjectArray>()?)?;
+    }
+    list.CommitList()
+}
+
+unsafe fn shell_link(exe: &HSTRING, arguments: &str, title: &str) -> ::windows::core::Result<IShellLinkW> {
+    let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+    link.SetPath(exe)?;
+    link.SetArguments(&HSTRING::from(arguments))?;
+    link.SetIconLocation(exe, 0)?;
+    let store: IPropertyStore = link.cast()?;
+    store.SetValue(&PKEY_Title, &PROPVARIANT::from(title))?;
+    store.Commit()?;
+    Ok(link)
+}
+
+unsafe fn removed_arguments(removed: &IObjectArray) -> ::windows::core::Result<Vec<String>> {
+    let mut arguments = Vec::new();
+    for index in 0..removed.GetCount()? {
+        let Ok(link) = removed.GetAt::<IShellLinkW>(index) else {
+            continue;
+        };
+        let mut buffer = [0u16; 128];
+        link.GetArguments(&mut buffer)?;
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        arguments.push(String::from_utf16_lossy(&buffer[..len]));
+    }
+    Ok(arguments)
+}
@@ -0,0 +1,106 @@
+//! Asks the operating system to confirm the user is present before a secret
+//! snippet is shown or copied: Windows Hello, Touch ID (or the login
+//! password) on macOS, and a polkit prompt on Linux.
+
+use std::sync::mpsc;
+
+/// Shows the platform's verification prompt with `reason` on a background
+/// thread and sends whether the user confirmed. Errors mean no prompt could
+/// be shown at all, for example because no Hello PIN or polkit agent is set
+/// up.
+pub fn request_verification(reason: String, sender: mpsc::Sender<Result<bool, String>>) {
+    std::thread::spawn(move || {
+        let _ = sender.send(imp::verify(&reason));
+    });
+}
+
+#[cfg(windows)]
+mod imp {
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{UserConsentVerificationResult, UserConsentVerifier};
+
+    pub fn verify(reason: &str) -> Result<bool, String> {
+        let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+            .and_then(|operation| operation.get())
+            .map_err(|e| e.message())?;
+        match result {
+            UserConsentVerificationResult::Verified => Ok(true),
+            UserConsentVerificationResult::Canceled | UserConsentVerificationResult::RetriesExhausted => Ok(false),
+            UserConsentVerificationResult::DeviceNotPresent | UserConsentVerificationResult::NotConfiguredForUser => {
+                Err("Windows Hello is not set up".to_string())
+            }
+            UserConsentVerificationResult::DisabledByPolicy => Err("Windows Hello is disabled by policy".to_string()),
+            _ => Err("Windows Hello is busy".to_string()),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, Bool};
+    use objc2::{class, msg_send};
+    use objc2_foundation::{NSError, NSString};
+    use std::sync::mpsc;
+
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {}
+
+    /// `LAPolicyDeviceOwnerAuthentication`: Touch ID or Apple Watch, with the
+    /// login password as fallback.
+    const POLICY: isize = 2;
+    /// `LAErrorAuthenticationFailed`, `LAErrorUserCancel`,
+    /// `LAErrorUserFallback` and `LAErrorSystemCancel`.
+    const DECLINED: [isize; 4] = [-1, -2, -3, -4];
+
+    pub fn verify(reason: &str) -> Result<bool, String> {
+        let (sender, receiver) = mpsc::channel();
+        let reply = RcBlock::new(move |success: Bool, error: *mut NSError| {
+            let result = match unsafe { error.as_ref() } {
+                _ if success.as_bool() => Ok(true),
+                Some(error) if !DECLINED.contains(&error.code()) => Err(error.localizedDescription().to_string()),
+                _ => Ok(false),
+            };
+            let _ = sender.send(result);
+        });
+
+        let context: Retained<AnyObject> = unsafe { msg_send![class!(LAContext), new] };
+        let reason = NSString::from_str(reason);
+        unsafe {
+            let _: () = msg_send![&*context, evaluatePolicy: POLICY, localizedReason: &*reason, reply: &*reply];
+        }
+        receiver.recv().map_err(|e| e.to_string())?
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use std::process::Command;
+
+    /// Registered by `assets/io.github.claudehenchoz.trinket.policy`, which
+    /// has to be installed in `/usr/share/polkit-1/actions`.
+    const ACTION: &str = "io.github.claudehenchoz.trinket.reveal-secret";
+
+    pub fn verify(_reason: &str) -> Result<bool, String> {
+        // polkit shows the message from the policy file, not `reason`.
+        let output = Command::new("pkcheck")
+            .args(["--action-id", ACTION, "--allow-user-interaction", "--process"])
+            .arg(std::process::id().to_string())
+            .output()
+            .map_err(|e| format!("could not run pkcheck: {}", e))?;
+        match output.status.code() {
+            Some(0) => Ok(true),
+            // Not authorized, or the dialog was dismissed.
+            Some(1..=3) => Ok(false),
+            _ => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        }
+    }
+}
+
+#[cfg(not(any(windows, unix)))]
+mod imp {
+    pub fn verify(_reason: &str) -> Result<bool, String> {
+        Err("this platform has no verification prompt".to_string())
+    }
+}
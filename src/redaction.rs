@@ -0,0 +1,165 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Built-in patterns as `(name, regex, Luhn-checked)`. Where a pattern has a
+/// capture group only that part is masked, so `key = value` keeps its key.
+const BUILT_IN: [(&str, &str, bool); 4] = [
+    ("card number", r"\b(?:\d[ -]?){12,18}\d\b", true),
+    ("AWS access key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b", false),
+    ("AWS secret key", r#"(?i)aws_secret_access_key\s*[:=]\s*["']?([A-Za-z0-9/+=]{40})"#, false),
+    ("JWT", r"\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+", false),
+];
+
+/// Characters left visible at the end of a masked match.
+const KEEP_CHARS: usize = 4;
+const MASK: &str = "••••";
+
+/// What happens when a snippet about to be saved looks like it holds a secret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionMode {
+    Off,
+    /// Ask whether to save as typed or masked.
+    #[default]
+    Warn,
+    /// Save masked unless the snippet is marked secret.
+    Mask,
+}
+
+impl RedactionMode {
+    pub const ALL: [RedactionMode; 3] = [RedactionMode::Off, RedactionMode::Warn, RedactionMode::Mask];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RedactionMode::Off => "Off",
+            RedactionMode::Warn => "Warn before saving",
+            RedactionMode::Mask => "Mask automatically",
+        }
+    }
+}
+
+struct Rule {
+    name: String,
+    pattern: Regex,
+    luhn: bool,
+}
+
+/// Finds card numbers, cloud keys, tokens and user-defined patterns in text.
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    /// The built-in rules plus `extra` patterns by name. Invalid patterns are
+    /// logged and skipped.
+    pub fn new(extra: &BTreeMap<String, String>) -> Self {
+        let built_in = BUILT_IN.iter().map(|&(name, pattern, luhn)| (name.to_string(), pattern, luhn));
+        let extra = extra.iter().map(|(name, pattern)| (name.clone(), pattern.as_str(), false));
+        let rules = built_in.chain(extra)
+            .filter_map(|(name, pattern, luhn)| match Regex::new(pattern) {
+                Ok(pattern) => Some(Rule { name, pattern, luhn }),
+                Err(e) => {
+                    log::warn!("Ignoring redaction pattern {}: {}", name, e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Names of the rules that match somewhere in `text`.
+    pub fn detect(&self, text: &str) -> Vec<String> {
+        self.rules.iter()
+            .filter(|rule| rule.matches(text).next().is_some())
+            .map(|rule| rule.name.clone())
+            .collect()
+    }
+
+    /// `text` with every match replaced by a mask that keeps its last few
+    /// characters, so masked values can still be told apart.
+    pub fn mask(&self, text: &str) -> String {
+        let mut ranges: Vec<Range<usize>> = self.rules.iter()
+            .flat_map(|rule| rule.matches(text))
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+
+        let mut masked = String::with_capacity(text.len());
+        let mut end = 0;
+        for range in ranges {
+            if range.start < end {
+                continue;
+            }
+            let value = &text[range.clone()];
+            let keep = value.char_indices().rev().nth(KEEP_CHARS - 1).map_or(value.len(), |(i, _)| i);
+            masked.push_str(&text[end..range.start]);
+            masked.push_str(MASK);
+            masked.push_str(&value[keep..]);
+            end = range.end;
+        }
+        masked.push_str(&text[end..]);
+        masked
+    }
+}
+
+/// Keeps selections captured from excluded apps, or matching excluded
+/// patterns, out of the library.
+#[cfg(any(target_os = "macos", feature = "hotkeys"))]
+#[derive(Default)]
+pub struct CaptureFilter {
+    apps: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+#[cfg(any(target_os = "macos", feature = "hotkeys"))]
+impl CaptureFilter {
+    /// Invalid patterns are logged and skipped.
+    pub fn new(apps: &[String], patterns: &[String]) -> Self {
+        let patterns = patterns.iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    log::warn!("Ignoring capture exclusion {}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self { apps: apps.iter().map(|app| app.trim().to_string()).collect(), patterns }
+    }
+
+    /// Whether nothing is to be captured from the app named `app`.
+    pub fn excludes_app(&self, app: &str) -> bool {
+        self.apps.iter().any(|excluded| excluded.eq_ignore_ascii_case(app))
+    }
+
+    /// Whether `text` matches one of the excluded patterns.
+    pub fn excludes_text(&self, text: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(text))
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new())
+    }
+}
+
+impl Rule {
+    fn matches<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.pattern.captures_iter(text)
+            .filter_map(|captures| captures.get(1).or_else(|| captures.get(0)))
+            .filter(|found| !self.luhn || luhn_valid(found.as_str()))
+            .map(|found| found.range())
+    }
+}
+
+/// Card numbers end in a Luhn check digit; most other digit runs don't pass.
+fn luhn_valid(number: &str) -> bool {
+    let mut sum = 0;
+    for (i, digit) in number.chars().rev().filter_map(|c| c.to_digit(10)).enumerate() {
+        let value = if i % 2 == 1 { digit * 2 } else { digit };
+        sum += if value > 9 { value - 9 } else { value };
+    }
+    sum % 10 == 0
+}
@@ -0,0 +1,174 @@
+//! Watches for the OS session being locked and unlocked: WTS session
+//! notifications on Windows, logind's `LockedHint` on Linux and the screen
+//! lock notifications on macOS.
+
+use std::sync::mpsc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    Locked,
+    Unlocked,
+}
+
+/// Starts sending lock and unlock events. Must be called on the main thread
+/// on macOS. Where the events aren't available this only logs why.
+pub fn spawn_session_watcher(sender: mpsc::Sender<SessionEvent>) {
+    imp::watch(sender);
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::SessionEvent;
+    use std::cell::RefCell;
+    use std::sync::mpsc;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::RemoteDesktop::{WTSRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW, HWND_MESSAGE, MSG,
+        WINDOW_EX_STYLE, WINDOW_STYLE, WM_WTSSESSION_CHANGE, WNDCLASSW, WTS_SESSION_LOCK, WTS_SESSION_UNLOCK,
+    };
+
+    const CLASS_NAME: PCWSTR = w!("TrinketSessionWatcher");
+
+    thread_local! {
+        static SENDER: RefCell<Option<mpsc::Sender<SessionEvent>>> = const { RefCell::new(None) };
+    }
+
+    pub fn watch(sender: mpsc::Sender<SessionEvent>) {
+        std::thread::spawn(move || {
+            SENDER.set(Some(sender));
+            if let Err(e) = unsafe { create_window() } {
+                log::warn!("Not watching for session locks: {}", e);
+                return;
+            }
+            // Session changes are sent straight to `window_proc`; the loop
+            // only has to keep the thread pumping messages.
+            let mut message = MSG::default();
+            while unsafe { GetMessageW(&mut message, None, 0, 0) }.as_bool() {
+                unsafe { DispatchMessageW(&message) };
+            }
+        });
+    }
+
+    /// A message-only window registered for this session's lock changes.
+    unsafe fn create_window() -> windows::core::Result<HWND> {
+        let instance = GetModuleHandleW(PCWSTR::null())?;
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance.into(),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        if RegisterClassW(&class) == 0 {
+            return Err(windows::core::Error::from_win32());
+        }
+        let window = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            CLASS_NAME,
+            PCWSTR::null(),
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            Some(HWND_MESSAGE),
+            None,
+            Some(instance.into()),
+            None,
+        )?;
+        WTSRegisterSessionNotification(window, NOTIFY_FOR_THIS_SESSION)?;
+        Ok(window)
+    }
+
+    unsafe extern "system" fn window_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if message == WM_WTSSESSION_CHANGE {
+            let event = match wparam.0 as u32 {
+                WTS_SESSION_LOCK => Some(SessionEvent::Locked),
+                WTS_SESSION_UNLOCK => Some(SessionEvent::Unlocked),
+                _ => None,
+            };
+            if let Some(event) = event {
+                SENDER.with_borrow(|sender| {
+                    if let Some(sender) = sender {
+                        let _ = sender.send(event);
+                    }
+                });
+            }
+            return LRESULT(0);
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::SessionEvent;
+    use std::sync::mpsc;
+
+    pub fn watch(sender: mpsc::Sender<SessionEvent>) {
+        std::thread::spawn(move || {
+            if let Err(e) = watch_logind(&sender) {
+                log::warn!("Not watching for session locks: {}", e);
+            }
+        });
+    }
+
+    /// Screen lockers set the session's `LockedHint`, which also covers
+    /// `loginctl lock-session`.
+    fn watch_logind(sender: &mpsc::Sender<SessionEvent>) -> zbus::Result<()> {
+        let connection = zbus::blocking::Connection::system()?;
+        let session = zbus::blocking::Proxy::new(
+            &connection,
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1/session/auto",
+            "org.freedesktop.login1.Session",
+        )?;
+        for change in session.receive_property_changed::<bool>("LockedHint") {
+            let event = if change.get()? { SessionEvent::Locked } else { SessionEvent::Unlocked };
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::SessionEvent;
+    use block2::RcBlock;
+    use objc2_foundation::{NSDistributedNotificationCenter, NSNotification, NSString};
+    use std::ptr::NonNull;
+    use std::sync::mpsc;
+
+    pub fn watch(sender: mpsc::Sender<SessionEvent>) {
+        let center = NSDistributedNotificationCenter::defaultCenter();
+        let names = [
+            ("com.apple.screenIsLocked", SessionEvent::Locked),
+            ("com.apple.screenIsUnlocked", SessionEvent::Unlocked),
+        ];
+        for (name, event) in names {
+            let sender = sender.clone();
+            let block = RcBlock::new(move |_: NonNull<NSNotification>| {
+                let _ = sender.send(event);
+            });
+            let observer = unsafe {
+                center.addObserverForName_object_queue_usingBlock(Some(&NSString::from_str(name)), None, None, &block)
+            };
+            // Observed for as long as the app runs.
+            std::mem::forget(observer);
+        }
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+mod imp {
+    use super::SessionEvent;
+    use std::sync::mpsc;
+
+    pub fn watch(_sender: mpsc::Sender<SessionEvent>) {
+        log::info!("Session lock events are not supported on this platform");
+    }
+}
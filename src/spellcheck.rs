@@ -0,0 +1,127 @@
+//! Spelling for the add window's editor, checked against Hunspell
+//! dictionaries. Only built with the `spellcheck` feature.
+
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use thiserror::Error;
+
+/// Suggestions offered for a misspelled word, best first.
+const MAX_SUGGESTIONS: usize = 6;
+
+/// Apostrophes allowed inside a word, as in "don't" and "don’t".
+const APOSTROPHES: &[char] = &['\'', '’'];
+
+#[derive(Debug, Error)]
+pub enum SpellcheckError {
+    #[error("no {0} dictionary found")]
+    NotFound(String),
+    #[error("could not read the dictionary: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse the dictionary: {0}")]
+    Parse(#[from] spellbook::ParseDictionaryError),
+}
+
+pub struct Spellchecker {
+    dictionary: spellbook::Dictionary,
+}
+
+impl Spellchecker {
+    /// Loads the `<language>.aff` and `<language>.dic` pair, e.g. `en_US`,
+    /// from the first of [`dictionary_dirs`] that has both.
+    pub fn load(language: &str) -> Result<Self, SpellcheckError> {
+        let dir = dictionary_dirs()
+            .into_iter()
+            .find(|dir| dir.join(format!("{}.aff", language)).is_file() && dir.join(format!("{}.dic", language)).is_file())
+            .ok_or_else(|| SpellcheckError::NotFound(language.to_string()))?;
+        let aff = std::fs::read_to_string(dir.join(format!("{}.aff", language)))?;
+        let dic = std::fs::read_to_string(dir.join(format!("{}.dic", language)))?;
+        Ok(Self { dictionary: spellbook::Dictionary::new(&aff, &dic)? })
+    }
+
+    /// Byte ranges of the words in `text` the dictionary doesn't know.
+    pub fn misspelled(&self, text: &str) -> Vec<Range<usize>> {
+        words(text).into_iter().filter(|range| !self.dictionary.check(&text[range.clone()])).collect()
+    }
+
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        self.dictionary.suggest(word, &mut suggestions);
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+}
+
+/// Parsing a dictionary takes a moment, so it's done away from the UI
+/// thread; the result arrives on the returned receiver.
+pub fn load_in_background(language: String) -> mpsc::Receiver<Result<Spellchecker, SpellcheckError>> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(Spellchecker::load(&language));
+    });
+    receiver
+}
+
+/// Where dictionaries are looked for: `dictionaries` in the data dir, then
+/// where Hunspell keeps them on Linux and macOS.
+fn dictionary_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![trinket_core::data_dir().join("dictionaries")];
+    if cfg!(target_os = "macos") {
+        dirs.extend(dirs::home_dir().map(|home| home.join("Library").join("Spelling")));
+        dirs.push(PathBuf::from("/Library/Spelling"));
+    } else if cfg!(unix) {
+        dirs.extend(["/usr/share/hunspell", "/usr/share/myspell", "/usr/share/myspell/dicts"].map(PathBuf::from));
+    }
+    dirs
+}
+
+/// Byte ranges of the words worth checking. Tokens that look like code,
+/// paths, addresses or numbers are passed over, as are words with capitals
+/// past the first letter, such as acronyms and camelCase names.
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut token_start = 0;
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        if c.is_whitespace() {
+            if token_start < i && !looks_like_code(&text[token_start..i]) {
+                token_words(text, token_start..i, &mut words);
+            }
+            token_start = i + c.len_utf8();
+        }
+    }
+    words
+}
+
+fn looks_like_code(token: &str) -> bool {
+    token.contains(|c: char| c.is_numeric() || matches!(c, '_' | '/' | '\\' | '@' | '<' | '>' | '=' | '{' | '}'))
+}
+
+fn token_words(text: &str, token: Range<usize>, words: &mut Vec<Range<usize>>) {
+    let mut start = None;
+    for (i, c) in text[token.clone()].char_indices().chain([(token.len(), ' ')]) {
+        let in_word = c.is_alphabetic() || (start.is_some() && APOSTROPHES.contains(&c));
+        match start {
+            None if in_word => start = Some(token.start + i),
+            Some(word_start) if !in_word => {
+                let word = text[word_start..token.start + i].trim_end_matches(APOSTROPHES);
+                if word.chars().count() > 1 && !word.chars().skip(1).any(char::is_uppercase) {
+                    words.push(word_start..word_start + word.len());
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_and_acronyms_are_not_checked() {
+        let text = "Don't run cargo_build on v2 (see https://example.com), ask HR or getUser.";
+        let found: Vec<&str> = words(text).into_iter().map(|range| &text[range]).collect();
+        assert_eq!(found, ["Don't", "run", "on", "see", "ask", "or"]);
+    }
+}
@@ -1,5 +0,0 @@
-pub mod file_ops;
-pub mod indexer;
-
-pub use file_ops::{FileStorage, Snippet};
-pub use indexer::SearchIndex;
\ No newline at end of file
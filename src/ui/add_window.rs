@@ -1,62 +1,725 @@
+use chrono::Utc;
 use egui;
+#[cfg(feature = "spellcheck")]
+use egui::text::{LayoutJob, LayoutSection};
+use egui_extras::syntax_highlighting::{highlight, CodeTheme};
+#[cfg(feature = "spellcheck")]
+use std::ops::Range;
+use zeroize::Zeroize;
+
+use crate::bidi;
+use crate::keymap::{format_keys, Command, Keymap, Scope};
+use crate::redaction::{RedactionMode, Redactor};
+#[cfg(feature = "spellcheck")]
+use crate::spellcheck::Spellchecker;
+use crate::storage::checklist;
+use crate::storage::{Language, Snippet, SnippetMeta, SnippetSource};
+use crate::ui::shortcut_overlay::show_shortcut_overlay;
+
+/// Content and caller-chosen metadata for a snippet about to be saved.
+pub struct NewSnippet {
+    /// Id of the snippet being edited, `None` when creating a new one.
+    pub editing: Option<String>,
+    pub content: String,
+    pub folder: String,
+    pub meta: SnippetMeta,
+    /// Save it even if it's over the library's size limit; set once the
+    /// user confirmed that.
+    pub over_limit: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Expiry {
+    #[default]
+    Never,
+    OneHour,
+    OneDay,
+    OneWeek,
+    OneMonth,
+}
+
+impl Expiry {
+    const ALL: [Expiry; 5] = [Expiry::Never, Expiry::OneHour, Expiry::OneDay, Expiry::OneWeek, Expiry::OneMonth];
+    
+    fn label(self) -> &'static str {
+        match self {
+            Expiry::Never => "Keep forever",
+            Expiry::OneHour => "Delete after 1 hour",
+            Expiry::OneDay => "Delete after 1 day",
+            Expiry::OneWeek => "Delete after 7 days",
+            Expiry::OneMonth => "Delete after 30 days",
+        }
+    }
+    
+    fn duration(self) -> Option<chrono::Duration> {
+        match self {
+            Expiry::Never => None,
+            Expiry::OneHour => Some(chrono::Duration::hours(1)),
+            Expiry::OneDay => Some(chrono::Duration::days(1)),
+            Expiry::OneWeek => Some(chrono::Duration::days(7)),
+            Expiry::OneMonth => Some(chrono::Duration::days(30)),
+        }
+    }
+}
 
 #[derive(Default)]
 pub struct AddWindowState {
     text_buffer: String,
+    /// The size and limit of a snippet the library refused as too large,
+    /// while asking whether to save it anyway.
+    oversize: Option<(usize, usize)>,
+    /// Set by "Save anyway" until the snippet is saved.
+    over_limit: bool,
+    redaction: RedactionMode,
+    redactor: Redactor,
+    /// Names of the redaction rules matching the current text.
+    detected: Vec<String>,
+    redaction_confirm: bool,
+    language: Option<Language>,
+    expiry: Expiry,
+    /// The expiry of the last snippet saved, to put back if it comes back
+    /// oversized.
+    last_expiry: Expiry,
+    folder: String,
+    secret: bool,
+    title: String,
+    notes: String,
+    /// Comma-separated app names the snippet is limited to.
+    apps: String,
+    /// The app focused when the add hotkey was pressed; kept with new snippets.
+    source: Option<SnippetSource>,
+    /// Title of the snippet a new one is appended to, when there is one.
+    append_to: Option<String>,
+    checklist: bool,
+    /// Whether to check spelling, once chosen; unset checks prose and
+    /// leaves code alone.
+    spellcheck: Option<bool>,
+    #[cfg(feature = "spellcheck")]
+    speller: Option<Spellchecker>,
+    /// Byte ranges of the misspelled words in the editor, underlined.
+    #[cfg(feature = "spellcheck")]
+    misspelled: Vec<Range<usize>>,
+    /// The word right-clicked for suggestions, and those suggestions.
+    #[cfg(feature = "spellcheck")]
+    suggesting: Option<(Range<usize>, Vec<String>)>,
+    editing: Option<String>,
+    keymap: Keymap,
+    shortcuts_open: bool,
+    /// Whether the editor wraps long lines; follows `word_wrap` in the config.
+    wrap: bool,
 }
 
 impl AddWindowState {
-    pub fn new() -> Self {
+    pub fn new(keymap: Keymap) -> Self {
         Self {
             text_buffer: String::new(),
+            oversize: None,
+            over_limit: false,
+            redaction: RedactionMode::Off,
+            redactor: Redactor::default(),
+            detected: Vec::new(),
+            redaction_confirm: false,
+            language: None,
+            expiry: Expiry::Never,
+            last_expiry: Expiry::Never,
+            folder: String::new(),
+            secret: false,
+            title: String::new(),
+            notes: String::new(),
+            apps: String::new(),
+            source: None,
+            append_to: None,
+            checklist: false,
+            spellcheck: None,
+            #[cfg(feature = "spellcheck")]
+            speller: None,
+            #[cfg(feature = "spellcheck")]
+            misspelled: Vec::new(),
+            #[cfg(feature = "spellcheck")]
+            suggesting: None,
+            editing: None,
+            keymap,
+            shortcuts_open: false,
+            wrap: true,
         }
     }
     
-    pub fn show(&mut self, ctx: &egui::Context) -> Option<String> {
+    /// Starts a new snippet with `text` already in the editor, for review
+    /// before saving.
+    pub fn prefill(&mut self, text: String) {
+        self.language = Language::detect(&text);
+        self.text_buffer = text;
+        self.detect_redactions();
+        self.spellcheck = None;
+        #[cfg(feature = "spellcheck")]
+        self.check_spelling();
+        self.source = None;
+        self.editing = None;
+    }
+    
+    /// Notes where the next new snippet was captured from.
+    pub fn set_source(&mut self, source: Option<SnippetSource>) {
+        self.source = source;
+    }
+    
+    pub fn set_append_target(&mut self, title: Option<String>) {
+        self.append_to = title;
+    }
+    
+    pub fn configure(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+    
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+    
+    /// Whether the editor wraps, after the toggle above it.
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+    
+    pub fn set_redaction(&mut self, redaction: RedactionMode, redactor: Redactor) {
+        self.redaction = redaction;
+        self.redactor = redactor;
+        self.detect_redactions();
+    }
+    
+    fn detect_redactions(&mut self) {
+        self.detected = match self.redaction {
+            RedactionMode::Off => Vec::new(),
+            _ => self.redactor.detect(&self.text_buffer),
+        };
+    }
+    
+    /// Starts checking spelling with `speller`, or stops with `None`.
+    #[cfg(feature = "spellcheck")]
+    pub fn set_spellchecker(&mut self, speller: Option<Spellchecker>) {
+        self.speller = speller;
+        self.check_spelling();
+    }
+    
+    #[cfg(feature = "spellcheck")]
+    fn checks_spelling(&self) -> bool {
+        self.spellcheck.unwrap_or(self.language.is_none())
+    }
+    
+    #[cfg(feature = "spellcheck")]
+    fn check_spelling(&mut self) {
+        self.suggesting = None;
+        self.misspelled = match &self.speller {
+            Some(speller) if self.checks_spelling() => speller.misspelled(&self.text_buffer),
+            _ => Vec::new(),
+        };
+    }
+    
+    pub fn is_editing(&self) -> bool {
+        self.editing.is_some()
+    }
+    
+    /// Loads an existing snippet into the editor; saving replaces its content.
+    pub fn edit(&mut self, snippet: &Snippet) {
+        self.text_buffer = snippet.content.clone();
+        self.language = snippet.meta.language;
+        self.folder = snippet.folder.clone();
+        self.secret = snippet.meta.secret;
+        self.title = snippet.meta.title.clone().unwrap_or_default();
+        self.notes = snippet.meta.notes.clone();
+        self.apps = snippet.meta.apps.join(", ");
+        self.source = None;
+        self.checklist = snippet.meta.checklist;
+        self.spellcheck = snippet.meta.spellcheck;
+        self.expiry = Expiry::Never;
+        self.oversize = None;
+        self.over_limit = false;
+        self.redaction_confirm = false;
+        self.editing = Some(snippet.id.clone());
+        self.detect_redactions();
+        #[cfg(feature = "spellcheck")]
+        self.check_spelling();
+    }
+    
+    /// Puts `snippet` back in the editor after the library refused it as
+    /// `size` bytes, over the `limit`, and asks whether to save it anyway.
+    pub fn confirm_oversize(&mut self, snippet: NewSnippet, size: usize, limit: usize) {
+        self.language = Language::detect(&snippet.content);
+        self.text_buffer = snippet.content;
+        self.folder = snippet.folder;
+        self.editing = snippet.editing;
+        self.expiry = self.last_expiry;
+        self.secret = snippet.meta.secret;
+        self.title = snippet.meta.title.unwrap_or_default();
+        self.notes = snippet.meta.notes;
+        self.apps = snippet.meta.apps.join(", ");
+        self.source = snippet.meta.source;
+        self.checklist = snippet.meta.checklist;
+        self.spellcheck = snippet.meta.spellcheck;
+        self.redaction_confirm = false;
+        self.oversize = Some((size, limit));
+        self.detect_redactions();
+        #[cfg(feature = "spellcheck")]
+        self.check_spelling();
+    }
+    
+    pub fn show(&mut self, ctx: &egui::Context, folders: &[String]) -> Option<NewSnippet> {
         let mut save_triggered = false;
         let mut close_triggered = false;
+        // Chosen in the redaction prompt: save masked, or as typed.
+        let mut masking = None;
         
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Add New Snippet");
+            ui.horizontal(|ui| {
+                ui.heading(if self.editing.is_some() { "Edit Snippet" } else { "Add New Snippet" });
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.toggle_value(&mut self.wrap, "Wrap").on_hover_text("Wrap long lines instead of scrolling sideways");
+                });
+            });
             ui.add_space(10.0);
             
+            let language = self.language;
+            let wrap = self.wrap;
+            #[cfg(feature = "spellcheck")]
+            let misspelled = self.misspelled.clone();
+            let theme = CodeTheme::from_style(ui.style());
+            let mut layouter = |ui: &egui::Ui, buffer: &dyn egui::TextBuffer, wrap_width: f32| {
+                let mut job = match language {
+                    Some(language) => highlight(ui.ctx(), ui.style(), &theme, buffer.as_str(), language.extension()),
+                    None => egui::text::LayoutJob::simple(
+                        buffer.as_str().to_owned(),
+                        egui::TextStyle::Body.resolve(ui.style()),
+                        ui.visuals().text_color(),
+                        wrap_width,
+                    ),
+                };
+                job.wrap.max_width = if wrap { wrap_width } else { f32::INFINITY };
+                #[cfg(feature = "spellcheck")]
+                underline(&mut job, &misspelled, buffer.as_str(), ui.visuals().error_fg_color);
+                // Characters stay in typing order so the cursor lines up;
+                // RTL text is only aligned to the right.
+                if bidi::is_rtl(buffer.as_str()) {
+                    job.halign = egui::Align::RIGHT;
+                }
+                ui.painter().layout_job(job)
+            };
+            
             egui::ScrollArea::vertical()
+                .hscroll(!wrap)
                 .max_height(300.0)
                 .show(ui, |ui| {
-                    ui.text_edit_multiline(&mut self.text_buffer)
-                        .request_focus();
+                    let output = egui::TextEdit::multiline(&mut self.text_buffer)
+                        .code_editor()
+                        .layouter(&mut layouter)
+                        .show(ui);
+                    if output.response.changed() {
+                        self.language = Language::detect(&self.text_buffer);
+                        self.detect_redactions();
+                        #[cfg(feature = "spellcheck")]
+                        self.check_spelling();
+                    }
+                    // Keep focus on the content unless another field took it.
+                    if ui.memory(|m| m.focused().is_none()) {
+                        output.response.request_focus();
+                    }
+                    #[cfg(feature = "spellcheck")]
+                    self.show_suggestions(&output);
+                });
+            
+            ui.add(
+                egui::TextEdit::singleline(&mut self.title)
+                    .hint_text("Title (optional, derived from the content)")
+                    .desired_width(f32::INFINITY),
+            );
+            ui.add(
+                egui::TextEdit::multiline(&mut self.notes)
+                    .hint_text("Notes (optional, not copied)")
+                    .desired_rows(2)
+                    .desired_width(f32::INFINITY),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut self.apps)
+                    .hint_text("Only in apps (optional, e.g. terminal, code)")
+                    .desired_width(f32::INFINITY),
+            )
+            .on_hover_text("List the snippet only when one of these apps had the focus");
+            let mut forget_source = false;
+            if let Some(source) = self.source.as_ref().filter(|_| self.editing.is_none()) {
+                ui.horizontal(|ui| {
+                    let label = ui.weak(format!("Captured from {}", source.app));
+                    if !source.window.is_empty() {
+                        label.on_hover_text(&source.window);
+                    }
+                    if ui.small_button("✕").on_hover_text("Don't keep where this came from").clicked() {
+                        forget_source = true;
+                    }
                 });
+            }
+            if forget_source {
+                self.source = None;
+            }
+            if let Some(title) = self.append_to.as_ref().filter(|_| self.editing.is_none()) {
+                ui.weak(format!("📝 Saving adds this to the end of “{}”", title))
+                    .on_hover_text("Stop appending from the snippet's menu in the list");
+            }
+            
+            // Folder and expiry only apply when creating a snippet.
+            let creating = self.editing.is_none();
+            ui.horizontal(|ui| {
+                if creating {
+                    self.show_creation_options(ui, folders);
+                }
+                ui.checkbox(&mut self.secret, "Secret");
+                ui.checkbox(&mut self.checklist, "Checklist")
+                    .on_hover_text("Each line becomes a checkbox in the preview");
+                #[cfg(feature = "spellcheck")]
+                if self.speller.is_some() {
+                    let mut checking = self.checks_spelling();
+                    if ui.checkbox(&mut checking, "Check spelling")
+                        .on_hover_text("Right-click an underlined word for suggestions")
+                        .changed()
+                    {
+                        self.spellcheck = Some(checking);
+                        self.check_spelling();
+                    }
+                }
+                if let Some(language) = self.language {
+                    ui.weak(format!("Detected language: {}", language.name()));
+                }
+            });
+            
+            if !self.secret && !self.detected.is_empty() {
+                let found = self.detected.join(", ");
+                ui.colored_label(ui.visuals().warn_fg_color, match self.redaction {
+                    RedactionMode::Mask => format!("Will be masked on save: {}. Mark it secret to keep it as typed.", found),
+                    _ => format!("Looks like it contains: {}.", found),
+                });
+            }
             
             ui.add_space(10.0);
             ui.separator();
             ui.add_space(10.0);
             
+            if self.redaction_confirm {
+                ui.colored_label(ui.visuals().warn_fg_color, "Save it in plain text?");
+                ui.horizontal(|ui| {
+                    if ui.button("Save masked").clicked() {
+                        save_triggered = true;
+                        masking = Some(true);
+                    }
+                    if ui.button("Save as typed").clicked() {
+                        save_triggered = true;
+                        masking = Some(false);
+                    }
+                    if ui.button("Keep editing").clicked() {
+                        self.redaction_confirm = false;
+                    }
+                });
+                ui.add_space(10.0);
+            }
+            
+            if let Some((size, limit)) = self.oversize {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("This snippet is {}, over the {} limit.", format_size(size), format_size(limit)),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Save anyway").clicked() {
+                        save_triggered = true;
+                        self.oversize = None;
+                        self.over_limit = true;
+                    }
+                    if ui.button("Keep editing").clicked() {
+                        self.oversize = None;
+                    }
+                });
+                ui.add_space(10.0);
+            }
+            
             ui.horizontal(|ui| {
-                if ui.button("Save and Close (Ctrl+Enter)").clicked() {
+                if ui.button(button_label(ctx, &self.keymap, "Save and Close", Command::Save)).clicked() {
                     save_triggered = true;
                 }
-                if ui.button("Cancel (Esc)").clicked() {
+                if ui.button(button_label(ctx, &self.keymap, "Cancel", Command::Cancel)).clicked() {
                     close_triggered = true;
                 }
             });
         });
         
-        ctx.input_mut(|i| {
-            if i.consume_key(egui::Modifiers::CTRL, egui::Key::Enter) {
-                save_triggered = true;
+        let typing = ctx.wants_keyboard_input();
+        for command in ctx.input(|i| self.keymap.triggered(i, Scope::Add, typing)) {
+            match command {
+                Command::Save => save_triggered = true,
+                Command::Cancel if self.shortcuts_open => self.shortcuts_open = false,
+                Command::Cancel => close_triggered = true,
+                Command::ShowShortcuts => self.shortcuts_open = !self.shortcuts_open,
+                _ => {}
             }
-            if i.consume_key(egui::Modifiers::NONE, egui::Key::Escape) {
-                close_triggered = true;
-            }
-        });
+        }
+        if self.shortcuts_open {
+            show_shortcut_overlay(ctx, &self.keymap, Scope::Add, &mut self.shortcuts_open);
+        }
+        
+        let flagged = !self.secret && !self.detected.is_empty();
+        if save_triggered && flagged && masking.is_none() && self.redaction == RedactionMode::Warn {
+            self.redaction_confirm = true;
+            save_triggered = false;
+        }
+        let mask = flagged && masking.unwrap_or(self.redaction == RedactionMode::Mask);
         
         if save_triggered && !self.text_buffer.is_empty() {
-            Some(std::mem::take(&mut self.text_buffer))
+            self.oversize = None;
+            self.redaction_confirm = false;
+            self.detected.clear();
+            self.language = None;
+            self.last_expiry = std::mem::take(&mut self.expiry);
+            let meta = SnippetMeta {
+                expires: self.last_expiry.duration().map(|ttl| Utc::now() + ttl),
+                secret: std::mem::take(&mut self.secret),
+                title: Some(std::mem::take(&mut self.title).trim().to_string()).filter(|t| !t.is_empty()),
+                notes: std::mem::take(&mut self.notes).trim().to_string(),
+                apps: parse_apps(&std::mem::take(&mut self.apps)),
+                source: self.source.take(),
+                checklist: self.checklist,
+                spellcheck: self.spellcheck.take(),
+                ..SnippetMeta::default()
+            };
+            let mut content = std::mem::take(&mut self.text_buffer);
+            if mask {
+                let masked = self.redactor.mask(&content);
+                content.zeroize();
+                content = masked;
+            }
+            Some(NewSnippet {
+                editing: self.editing.take(),
+                content: if std::mem::take(&mut self.checklist) { checklist::to_markdown(&content) } else { content },
+                // Kept so consecutive captures land in the same folder.
+                folder: self.folder.clone(),
+                meta,
+                over_limit: std::mem::take(&mut self.over_limit),
+            })
         } else if close_triggered {
             self.text_buffer.clear();
+            self.oversize = None;
+            self.over_limit = false;
+            self.redaction_confirm = false;
+            self.detected.clear();
+            self.language = None;
+            self.expiry = Expiry::Never;
+            self.secret = false;
+            self.title.clear();
+            self.notes.clear();
+            self.apps.clear();
+            self.source = None;
+            self.checklist = false;
+            self.spellcheck = None;
+            self.editing = None;
             None
         } else {
             None
         }
     }
-}
\ No newline at end of file
+    
+    /// Offers replacements for the misspelled word right-clicked in the
+    /// editor.
+    #[cfg(feature = "spellcheck")]
+    fn show_suggestions(&mut self, output: &egui::text_edit::TextEditOutput) {
+        let Some(speller) = &self.speller else {
+            return;
+        };
+        if output.response.secondary_clicked() {
+            self.suggesting = output.response.interact_pointer_pos()
+                .map(|pos| output.galley.cursor_from_pos(pos - output.galley_pos).index)
+                .and_then(|index| {
+                    let byte = self.text_buffer.char_indices().nth(index).map_or(self.text_buffer.len(), |(byte, _)| byte);
+                    self.misspelled.iter().find(|range| range.contains(&byte)).cloned()
+                })
+                .map(|range| {
+                    let suggestions = speller.suggest(&self.text_buffer[range.clone()]);
+                    (range, suggestions)
+                });
+        }
+        let Some((range, suggestions)) = self.suggesting.clone() else {
+            return;
+        };
+        
+        let mut chosen = None;
+        let menu = output.response.context_menu(|ui| {
+            if suggestions.is_empty() {
+                ui.weak("No suggestions");
+            }
+            for suggestion in &suggestions {
+                if ui.button(suggestion).clicked() {
+                    chosen = Some(suggestion);
+                    ui.close();
+                }
+            }
+        });
+        if menu.is_none() {
+            self.suggesting = None;
+        }
+        // The text may have changed since the menu opened.
+        if let Some(word) = chosen.filter(|_| self.misspelled.contains(&range)) {
+            self.text_buffer.replace_range(range, word);
+            self.language = Language::detect(&self.text_buffer);
+            self.detect_redactions();
+            self.check_spelling();
+        }
+    }
+    
+    fn show_creation_options(&mut self, ui: &mut egui::Ui, folders: &[String]) {
+        ui.label("Folder:");
+        ui.add(
+            egui::TextEdit::singleline(&mut self.folder)
+                .hint_text("(root) or e.g. work/clients")
+                .desired_width(180.0),
+        );
+        ui.menu_button("▾", |ui| {
+            if ui.button("(root)").clicked() {
+                self.folder.clear();
+                ui.close();
+            }
+            for folder in folders {
+                if ui.button(folder).clicked() {
+                    self.folder = folder.clone();
+                    ui.close();
+                }
+            }
+        });
+        
+        egui::ComboBox::from_id_salt("expiry")
+            .selected_text(self.expiry.label())
+            .show_ui(ui, |ui| {
+                for expiry in Expiry::ALL {
+                    ui.selectable_value(&mut self.expiry, expiry, expiry.label());
+                }
+            });
+    }
+}
+
+fn parse_apps(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn format_size(bytes: usize) -> String {
+    const KIB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= KIB * KIB {
+        format!("{:.1} MB", bytes / (KIB * KIB))
+    } else if bytes >= KIB {
+        format!("{:.1} KB", bytes / KIB)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+fn button_label(ctx: &egui::Context, keymap: &Keymap, label: &str, command: Command) -> String {
+    match keymap.keys(command) {
+        Some(keys) => format!("{} ({})", label, format_keys(ctx, keys)),
+        None => label.to_string(),
+    }
+}
+
+/// Underlines the `misspelled` byte ranges of `job`, splitting its sections
+/// where a word starts or ends.
+#[cfg(feature = "spellcheck")]
+fn underline(job: &mut LayoutJob, misspelled: &[Range<usize>], text: &str, color: egui::Color32) {
+    // Ranges found before this frame's edit may no longer fit the text.
+    let misspelled: Vec<&Range<usize>> = misspelled.iter()
+        .filter(|range| text.get((*range).clone()).is_some())
+        .collect();
+    if misspelled.is_empty() {
+        return;
+    }
+    let mut sections = Vec::with_capacity(job.sections.len() + misspelled.len() * 2);
+    for section in std::mem::take(&mut job.sections) {
+        let end = section.byte_range.end;
+        let mut start = section.byte_range.start;
+        let mut leading_space = section.leading_space;
+        while start < end {
+            let (piece_end, marked) = match misspelled.iter().find(|range| range.end > start) {
+                Some(range) if range.start <= start => (range.end.min(end), true),
+                Some(range) => (range.start.min(end), false),
+                None => (end, false),
+            };
+            let mut format = section.format.clone();
+            if marked {
+                format.underline = egui::Stroke::new(1.0, color);
+            }
+            sections.push(LayoutSection { leading_space, byte_range: start..piece_end, format });
+            leading_space = 0.0;
+            start = piece_end;
+        }
+    }
+    job.sections = sections;
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{Key, Modifiers};
+
+    use super::*;
+    use crate::ui::harness::{snippet, Harness};
+
+    fn open() -> (AddWindowState, Harness) {
+        let mut window = AddWindowState::new(Keymap::default());
+        let mut harness = Harness::new();
+        // The editor takes the focus in the first frame.
+        assert!(harness.run(|ctx| window.show(ctx, &[])).is_none());
+        (window, harness)
+    }
+
+    fn save(window: &mut AddWindowState, harness: &mut Harness) -> Option<NewSnippet> {
+        harness.press(Key::Enter, Modifiers::CTRL);
+        harness.run(|ctx| window.show(ctx, &[]))
+    }
+
+    #[test]
+    fn ctrl_enter_saves_the_typed_text() {
+        let (mut window, mut harness) = open();
+        harness.type_text("ssh deploy@example.org");
+        assert!(harness.run(|ctx| window.show(ctx, &[])).is_none());
+
+        let saved = save(&mut window, &mut harness).expect("saved");
+        assert_eq!(saved.content, "ssh deploy@example.org");
+        assert!(saved.editing.is_none());
+        assert!(window.text_buffer.is_empty());
+    }
+
+    #[test]
+    fn escape_discards_the_text() {
+        let (mut window, mut harness) = open();
+        harness.type_text("draft");
+        harness.run(|ctx| window.show(ctx, &[]));
+
+        harness.press(Key::Escape, Modifiers::NONE);
+        assert!(harness.run(|ctx| window.show(ctx, &[])).is_none());
+        assert!(window.text_buffer.is_empty());
+        assert!(save(&mut window, &mut harness).is_none());
+    }
+
+    #[test]
+    fn refused_oversized_text_comes_back_to_confirm() {
+        let (mut window, mut harness) = open();
+        harness.type_text("a long log");
+        harness.run(|ctx| window.show(ctx, &[]));
+        let saved = save(&mut window, &mut harness).expect("saved");
+        assert!(!saved.over_limit);
+
+        window.confirm_oversize(saved, 10, 8);
+        assert_eq!(window.text_buffer, "a long log");
+        assert_eq!(window.oversize, Some((10, 8)));
+    }
+
+    #[test]
+    fn editing_keeps_the_id() {
+        let (mut window, mut harness) = open();
+        window.edit(&snippet("a", "git checkout main", 1));
+
+        let saved = save(&mut window, &mut harness).expect("saved");
+        assert_eq!(saved.editing.as_deref(), Some("a"));
+        assert_eq!(saved.content, "git checkout main");
+        assert!(!window.is_editing());
+    }
+}
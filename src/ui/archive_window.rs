@@ -0,0 +1,118 @@
+use egui;
+use std::path::PathBuf;
+
+pub enum ArchiveAction {
+    /// Write every snippet to the file, encrypted with the passphrase, and
+    /// the secrets too when the flag is set.
+    Export(PathBuf, String, bool),
+    Import(PathBuf, String),
+    Back,
+}
+
+/// Asks where a passphrase-protected archive goes or comes from, and for
+/// its passphrase.
+pub struct ArchiveWindowState {
+    exporting: bool,
+    path: String,
+    passphrase: String,
+    confirmation: String,
+    include_secrets: bool,
+}
+
+impl ArchiveWindowState {
+    pub fn export(path: PathBuf) -> Self {
+        Self {
+            exporting: true,
+            path: path.display().to_string(),
+            passphrase: String::new(),
+            confirmation: String::new(),
+            include_secrets: false,
+        }
+    }
+
+    pub fn import() -> Self {
+        Self {
+            exporting: false,
+            path: String::new(),
+            passphrase: String::new(),
+            confirmation: String::new(),
+            include_secrets: false,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        if self.exporting {
+            "Export archive"
+        } else {
+            "Import archive"
+        }
+    }
+
+    /// `secret_count` of the `snippet_count` snippets are secrets, which are
+    /// only exported when asked for.
+    pub fn show(&mut self, ctx: &egui::Context, snippet_count: usize, secret_count: usize) -> Option<ArchiveAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(ArchiveAction::Back);
+                }
+                ui.heading(self.title());
+                if self.exporting {
+                    let count = if self.include_secrets { snippet_count } else { snippet_count - secret_count };
+                    ui.weak(format!("{} snippets", count));
+                }
+            });
+            if self.exporting {
+                ui.weak("Snippets and their metadata are saved to one file, encrypted with a passphrase.");
+            } else {
+                ui.weak("Snippets already in the library are skipped.");
+            }
+            ui.separator();
+
+            egui::Grid::new("archive_grid").num_columns(2).spacing([12.0, 8.0]).show(ui, |ui| {
+                ui.label("File:");
+                ui.add(egui::TextEdit::singleline(&mut self.path).hint_text("Path to the archive").desired_width(320.0));
+                ui.end_row();
+
+                ui.label("Passphrase:");
+                ui.add(egui::TextEdit::singleline(&mut self.passphrase).password(true).desired_width(200.0));
+                ui.end_row();
+
+                if self.exporting {
+                    ui.label("Repeat:");
+                    ui.add(egui::TextEdit::singleline(&mut self.confirmation).password(true).desired_width(200.0));
+                    ui.end_row();
+
+                    if secret_count > 0 {
+                        ui.label("");
+                        ui.checkbox(&mut self.include_secrets, format!("Include the {} secret snippets", secret_count))
+                            .on_hover_text("Each exported secret is noted in the access log");
+                        ui.end_row();
+                    }
+                }
+            });
+            ui.add_space(8.0);
+
+            let mismatch = self.exporting && self.passphrase != self.confirmation;
+            if mismatch && !self.confirmation.is_empty() {
+                ui.colored_label(ui.visuals().warn_fg_color, "The passphrases don't match.");
+            }
+            let ready = !self.path.trim().is_empty() && !self.passphrase.is_empty() && !mismatch;
+            let label = if self.exporting { "Export" } else { "Import" };
+            if ui.add_enabled(ready, egui::Button::new(label)).clicked() {
+                let path = PathBuf::from(self.path.trim());
+                let passphrase = std::mem::take(&mut self.passphrase);
+                self.confirmation.clear();
+                action = Some(if self.exporting {
+                    ArchiveAction::Export(path, passphrase, self.include_secrets)
+                } else {
+                    ArchiveAction::Import(path, passphrase)
+                });
+            }
+        });
+
+        action
+    }
+}
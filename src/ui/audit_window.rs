@@ -0,0 +1,75 @@
+use chrono::Local;
+use egui;
+
+use crate::storage::audit::AccessRecord;
+use crate::storage::{Snippet, SECRET_MASK};
+
+pub enum AuditAction {
+    /// Save the log as CSV.
+    Export,
+    Back,
+}
+
+/// Lists every reveal and copy of a secret snippet, most recent first.
+pub struct AuditWindowState {
+    records: Vec<AccessRecord>,
+}
+
+impl AuditWindowState {
+    pub fn new(records: Vec<AccessRecord>) -> Self {
+        Self { records }
+    }
+    
+    pub fn records(&self) -> &[AccessRecord] {
+        &self.records
+    }
+    
+    pub fn show(&mut self, ctx: &egui::Context, snippets: &[Snippet]) -> Option<AuditAction> {
+        let mut action = None;
+        
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(AuditAction::Back);
+                }
+                ui.heading("Access log");
+                ui.weak(format!("{} entries", self.records.len()));
+                if !self.records.is_empty() && ui.button("Export CSV…").clicked() {
+                    action = Some(AuditAction::Export);
+                }
+            });
+            ui.weak("Every reveal and copy of a secret snippet is recorded here.");
+            ui.separator();
+            
+            if self.records.is_empty() {
+                ui.weak("No secret snippet has been revealed or copied yet.");
+                return;
+            }
+            
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                egui::Grid::new("audit_grid").num_columns(4).striped(true).spacing([12.0, 6.0]).show(ui, |ui| {
+                    ui.strong("When");
+                    ui.strong("Action");
+                    ui.strong("Snippet");
+                    ui.strong("Id");
+                    ui.end_row();
+                    
+                    for record in &self.records {
+                        ui.label(record.at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string());
+                        ui.label(record.kind.label());
+                        // Secret titles are masked unless one was typed in.
+                        let title = match snippets.iter().find(|snippet| snippet.id == record.id) {
+                            Some(snippet) => snippet.meta.title.as_deref().unwrap_or(SECRET_MASK),
+                            None => "(deleted)",
+                        };
+                        ui.add(egui::Label::new(title).truncate());
+                        ui.weak(&record.id);
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+        
+        action
+    }
+}
@@ -0,0 +1,113 @@
+use egui;
+
+use crate::storage::Snippet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Separator {
+    #[default]
+    Newline,
+    BlankLine,
+    Space,
+    Nothing,
+}
+
+impl Separator {
+    const ALL: [Separator; 4] = [Separator::Newline, Separator::BlankLine, Separator::Space, Separator::Nothing];
+
+    fn label(self) -> &'static str {
+        match self {
+            Separator::Newline => "New line",
+            Separator::BlankLine => "Blank line",
+            Separator::Space => "Space",
+            Separator::Nothing => "Nothing",
+        }
+    }
+
+    fn text(self) -> &'static str {
+        match self {
+            Separator::Newline => "\n",
+            Separator::BlankLine => "\n\n",
+            Separator::Space => " ",
+            Separator::Nothing => "",
+        }
+    }
+}
+
+/// Snippets queued in "build" mode, copied together in order. Ids rather than
+/// indices are kept so the queue survives the snippet list changing.
+#[derive(Default)]
+pub struct BuildList {
+    ids: Vec<String>,
+    separator: Separator,
+}
+
+impl BuildList {
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn push(&mut self, id: &str) {
+        self.ids.push(id.to_string());
+    }
+
+    /// Draws the staging list. Returns the snippet indices to copy and the
+    /// joined text once "Copy all" is clicked.
+    pub fn show(&mut self, ui: &mut egui::Ui, snippets: &[Snippet]) -> Option<(Vec<usize>, String)> {
+        // Drop entries whose snippet was deleted or expired meanwhile.
+        self.ids.retain(|id| snippets.iter().any(|s| &s.id == id));
+
+        ui.strong(format!("Build ({})", self.ids.len()));
+
+        let mut move_up = None;
+        let mut remove = None;
+        egui::ScrollArea::vertical().id_salt("build_list").max_height(200.0).show(ui, |ui| {
+            for (position, id) in self.ids.iter().enumerate() {
+                let Some(snippet) = snippets.iter().find(|s| &s.id == id) else {
+                    continue;
+                };
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(position > 0, egui::Button::new("↑").small()).clicked() {
+                        move_up = Some(position);
+                    }
+                    if ui.small_button("✕").clicked() {
+                        remove = Some(position);
+                    }
+                    ui.add(egui::Label::new(snippet.safe_title()).truncate());
+                });
+            }
+        });
+        if let Some(position) = move_up {
+            self.ids.swap(position - 1, position);
+        }
+        if let Some(position) = remove {
+            self.ids.remove(position);
+        }
+
+        egui::ComboBox::from_label("Separator")
+            .selected_text(self.separator.label())
+            .show_ui(ui, |ui| {
+                for separator in Separator::ALL {
+                    ui.selectable_value(&mut self.separator, separator, separator.label());
+                }
+            });
+
+        let mut copy = None;
+        ui.horizontal(|ui| {
+            if ui.button("Copy all").clicked() {
+                copy = Some(self.combine(snippets));
+            }
+            if ui.button("Clear").clicked() {
+                self.ids.clear();
+            }
+        });
+        copy
+    }
+
+    fn combine(&self, snippets: &[Snippet]) -> (Vec<usize>, String) {
+        let indices: Vec<usize> = self.ids.iter()
+            .filter_map(|id| snippets.iter().position(|s| &s.id == id))
+            .collect();
+        let parts: Vec<&str> = indices.iter().map(|&i| snippets[i].content.as_str()).collect();
+        (indices, parts.join(self.separator.text()))
+    }
+}
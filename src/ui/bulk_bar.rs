@@ -0,0 +1,73 @@
+use egui;
+
+/// An operation applied to every selected snippet at once.
+pub enum BulkOp {
+    AddTag(String),
+    RemoveTag(String),
+    Move(String),
+    SetPinned(bool),
+    Delete,
+}
+
+pub enum BulkBarAction {
+    Apply(BulkOp),
+    SelectAll,
+    ClearSelection,
+}
+
+/// Toolbar shown above the results while snippets are multi-selected.
+pub fn show_bulk_bar(
+    ui: &mut egui::Ui,
+    selected: usize,
+    folders: &[String],
+    tag_input: &mut String,
+) -> Option<BulkBarAction> {
+    let mut action = None;
+    
+    ui.horizontal_wrapped(|ui| {
+        ui.strong(format!("{} selected", selected));
+        if ui.small_button("Select all").clicked() {
+            action = Some(BulkBarAction::SelectAll);
+        }
+        if ui.small_button("Clear").clicked() {
+            action = Some(BulkBarAction::ClearSelection);
+        }
+        ui.separator();
+        
+        ui.add(egui::TextEdit::singleline(tag_input).hint_text("tag").desired_width(80.0));
+        let tag = tag_input.trim().trim_start_matches('#').to_lowercase();
+        if ui.add_enabled(!tag.is_empty(), egui::Button::new("+ Tag")).clicked() {
+            action = Some(BulkBarAction::Apply(BulkOp::AddTag(tag.clone())));
+            tag_input.clear();
+        }
+        if ui.add_enabled(!tag.is_empty(), egui::Button::new("− Tag")).clicked() {
+            action = Some(BulkBarAction::Apply(BulkOp::RemoveTag(tag)));
+            tag_input.clear();
+        }
+        ui.separator();
+        
+        ui.menu_button("Move to ▾", |ui| {
+            if ui.button("(root)").clicked() {
+                action = Some(BulkBarAction::Apply(BulkOp::Move(String::new())));
+                ui.close();
+            }
+            for folder in folders {
+                if ui.button(folder).clicked() {
+                    action = Some(BulkBarAction::Apply(BulkOp::Move(folder.clone())));
+                    ui.close();
+                }
+            }
+        });
+        if ui.button("📌 Pin").clicked() {
+            action = Some(BulkBarAction::Apply(BulkOp::SetPinned(true)));
+        }
+        if ui.button("Unpin").clicked() {
+            action = Some(BulkBarAction::Apply(BulkOp::SetPinned(false)));
+        }
+        if ui.button("🗑 Delete").clicked() {
+            action = Some(BulkBarAction::Apply(BulkOp::Delete));
+        }
+    });
+    
+    action
+}
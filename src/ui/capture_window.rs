@@ -0,0 +1,94 @@
+use egui;
+use image::RgbaImage;
+
+pub enum CaptureAction {
+    /// The dragged region, cropped from the screenshot.
+    Selected(RgbaImage),
+    Cancel,
+}
+
+/// Shows a screenshot and lets the user drag a rectangle over the text to
+/// recognize. Esc cancels.
+pub struct CaptureWindowState {
+    screenshot: RgbaImage,
+    texture: Option<egui::TextureHandle>,
+    drag_start: Option<egui::Pos2>,
+}
+
+impl CaptureWindowState {
+    pub fn new(screenshot: RgbaImage) -> Self {
+        Self {
+            screenshot,
+            texture: None,
+            drag_start: None,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<CaptureAction> {
+        let mut action = None;
+        let (width, height) = self.screenshot.dimensions();
+        let texture = self.texture.get_or_insert_with(|| {
+            let image = egui::ColorImage::from_rgba_unmultiplied([width as usize, height as usize], self.screenshot.as_raw());
+            ctx.load_texture("ocr_screenshot", image, egui::TextureOptions::LINEAR)
+        });
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::NONE)
+            .show(ctx, |ui| {
+                let available = ui.available_rect_before_wrap();
+                let scale = (available.width() / width as f32).min(available.height() / height as f32);
+                let rect = egui::Rect::from_min_size(available.min, egui::vec2(width as f32, height as f32) * scale);
+
+                let response = ui.allocate_rect(rect, egui::Sense::drag());
+                ui.painter().image(
+                    texture.id(),
+                    rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+
+                if response.drag_started() {
+                    self.drag_start = response.interact_pointer_pos();
+                }
+                let selection = self.drag_start
+                    .zip(ui.ctx().pointer_latest_pos())
+                    .map(|(start, end)| egui::Rect::from_two_pos(start, end).intersect(rect));
+
+                if let Some(selection) = selection {
+                    ui.painter().rect_stroke(
+                        selection,
+                        0.0,
+                        egui::Stroke::new(2.0, ui.visuals().selection.stroke.color),
+                        egui::StrokeKind::Outside,
+                    );
+                }
+
+                if response.drag_stopped() {
+                    self.drag_start = None;
+                    if let Some(selection) = selection.filter(|s| s.width() >= 4.0 && s.height() >= 4.0) {
+                        // Map from screen points back to screenshot pixels.
+                        let x = ((selection.min.x - rect.min.x) / scale) as u32;
+                        let y = ((selection.min.y - rect.min.y) / scale) as u32;
+                        let w = ((selection.width() / scale) as u32).min(width - x);
+                        let h = ((selection.height() / scale) as u32).min(height - y);
+                        let cropped = image::imageops::crop_imm(&self.screenshot, x, y, w, h).to_image();
+                        action = Some(CaptureAction::Selected(cropped));
+                    }
+                }
+
+                ui.painter().text(
+                    rect.left_top() + egui::vec2(12.0, 12.0),
+                    egui::Align2::LEFT_TOP,
+                    "Drag over the text to capture · Esc to cancel",
+                    egui::TextStyle::Body.resolve(ui.style()),
+                    ui.visuals().strong_text_color(),
+                );
+            });
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            action = Some(CaptureAction::Cancel);
+        }
+
+        action
+    }
+}
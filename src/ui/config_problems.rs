@@ -0,0 +1,61 @@
+use egui;
+use std::path::Path;
+
+use crate::config::ConfigProblem;
+
+const MODAL_WIDTH: f32 = 480.0;
+const LIST_HEIGHT: f32 = 220.0;
+
+pub enum ConfigProblemsAction {
+    /// Put the file aside and start over from the default settings.
+    ResetToDefaults,
+    /// Carry on with the settings that were valid.
+    Dismiss,
+}
+
+/// What was wrong in the config file when trinket started.
+pub fn show_config_problems(ctx: &egui::Context, path: &Path, problems: &[ConfigProblem]) -> Option<ConfigProblemsAction> {
+    let mut action = None;
+
+    let response = egui::Modal::new(egui::Id::new("config_problems")).show(ctx, |ui| {
+        ui.set_width(MODAL_WIDTH);
+        ui.heading("Problems in the config file");
+        ui.horizontal_wrapped(|ui| {
+            ui.weak("In");
+            ui.add(egui::Label::new(egui::RichText::new(path.display().to_string()).weak()).selectable(true));
+        });
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .max_height(LIST_HEIGHT)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                egui::Grid::new("config_problems_grid").num_columns(2).spacing([12.0, 4.0]).show(ui, |ui| {
+                    for problem in problems {
+                        ui.monospace(problem.key.as_deref().unwrap_or("(file)"));
+                        ui.label(&problem.message);
+                        ui.end_row();
+                    }
+                });
+            });
+        ui.separator();
+        ui.label("Settings that could be read are in use; the others have their defaults until the file is fixed or saved from Settings.");
+
+        ui.horizontal(|ui| {
+            if ui.button("Continue").clicked() {
+                action = Some(ConfigProblemsAction::Dismiss);
+            }
+            if ui.button("Reset to defaults")
+                .on_hover_text("The current file is kept next to it, ending in .bak")
+                .clicked()
+            {
+                action = Some(ConfigProblemsAction::ResetToDefaults);
+            }
+        });
+    });
+
+    if response.should_close() && action.is_none() {
+        action = Some(ConfigProblemsAction::Dismiss);
+    }
+    action
+}
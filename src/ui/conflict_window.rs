@@ -0,0 +1,135 @@
+use chrono::{DateTime, Local};
+use egui;
+
+use crate::storage::title::display_title;
+use crate::storage::{Snippet, SECRET_MASK};
+use crate::sync::{Conflict, Resolution};
+use crate::ui::history_window::{show_inline, show_side_by_side};
+
+pub enum ConflictAction {
+    /// Settle the conflict at this index.
+    Resolve(usize, Resolution),
+    Back,
+}
+
+/// Shows snippets edited both here and on another device, one at a time,
+/// with a diff from this device's version to the other's.
+#[derive(Default)]
+pub struct ConflictWindowState {
+    selected: usize,
+    side_by_side: bool,
+}
+
+impl ConflictWindowState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, conflicts: &[Conflict], snippets: &[Snippet]) -> Option<ConflictAction> {
+        let mut action = None;
+        self.selected = self.selected.min(conflicts.len().saturating_sub(1));
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(ConflictAction::Back);
+                }
+                ui.heading("Sync conflicts");
+                ui.weak(format!("{} left", conflicts.len()));
+            });
+            ui.weak("These snippets were edited here and on another device since they last synced.");
+            ui.separator();
+
+            let Some(conflict) = conflicts.get(self.selected) else {
+                ui.weak("No conflicts left.");
+                return;
+            };
+            let mine = snippets.iter().find(|snippet| snippet.id == conflict.id());
+            let theirs = &conflict.theirs;
+            let secret = theirs.meta.secret || mine.is_some_and(|mine| mine.meta.secret);
+
+            egui::ComboBox::from_id_salt("conflict")
+                .width(360.0)
+                .selected_text(conflict_label(conflict))
+                .show_ui(ui, |ui| {
+                    for (index, conflict) in conflicts.iter().enumerate() {
+                        ui.selectable_value(&mut self.selected, index, conflict_label(conflict));
+                    }
+                });
+            ui.add_space(4.0);
+
+            egui::Grid::new("conflict_versions").num_columns(3).spacing([16.0, 4.0]).show(ui, |ui| {
+                ui.label("");
+                ui.strong("This device");
+                ui.strong(&conflict.peer_name);
+                ui.end_row();
+
+                ui.label("Folder:");
+                ui.label(mine.map_or("—", |mine| folder_label(&mine.folder)));
+                ui.label(folder_label(&theirs.folder));
+                ui.end_row();
+
+                ui.label("Modified:");
+                ui.label(mine.map_or_else(|| "—".to_string(), |mine| format_time(mine.modified.into())));
+                ui.label(format_time(theirs.modified.into()));
+                ui.end_row();
+            });
+            ui.add_space(4.0);
+
+            ui.horizontal(|ui| {
+                if ui.button("Keep mine").on_hover_text("The other device takes this version on the next sync").clicked() {
+                    action = Some(ConflictAction::Resolve(self.selected, Resolution::Mine));
+                }
+                if ui.button("Keep theirs").clicked() {
+                    action = Some(ConflictAction::Resolve(self.selected, Resolution::Theirs));
+                }
+                if ui.button("Keep both").on_hover_text("This device's version is saved as a new snippet").clicked() {
+                    action = Some(ConflictAction::Resolve(self.selected, Resolution::Both));
+                }
+                if !secret {
+                    ui.checkbox(&mut self.side_by_side, "Side by side");
+                }
+            });
+            ui.separator();
+
+            let mine_content = mine.map_or("", |mine| mine.content.as_str());
+            if secret {
+                ui.weak("Secret snippets are compared without showing their content.");
+            } else if mine_content == theirs.content {
+                ui.weak("The content is the same; only the folder, tags, notes or other details differ.");
+            } else {
+                egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+                    if self.side_by_side {
+                        show_side_by_side(ui, mine_content, &theirs.content);
+                    } else {
+                        show_inline(ui, mine_content, &theirs.content);
+                    }
+                });
+            }
+        });
+
+        action
+    }
+}
+
+fn conflict_label(conflict: &Conflict) -> String {
+    let theirs = &conflict.theirs;
+    let title = if theirs.meta.secret {
+        SECRET_MASK.to_string()
+    } else {
+        display_title(&theirs.content, &theirs.meta)
+    };
+    format!("{} · {}", title, conflict.peer_name)
+}
+
+fn folder_label(folder: &str) -> &str {
+    if folder.is_empty() {
+        "(root)"
+    } else {
+        folder
+    }
+}
+
+fn format_time(time: DateTime<Local>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
@@ -0,0 +1,45 @@
+use chrono::{Local, NaiveDate};
+use egui;
+use egui_extras::DatePickerButton;
+
+use crate::storage::indexer::{replace_filter, DATE_FORMAT};
+use crate::storage::SearchQuery;
+
+/// A row of From/To calendar pickers that edit the `after:` and `before:`
+/// filters in the search text, so typed and picked ranges stay the same thing.
+pub fn show_date_filter(ui: &mut egui::Ui, search_query: &mut String) {
+    let query = SearchQuery::parse(search_query);
+    let today = Local::now().date_naive();
+
+    ui.horizontal(|ui| {
+        if let Some(date) = date_bound(ui, "From", "date_after", query.after, today) {
+            *search_query = replace_filter(search_query, "after", format_date(date).as_deref());
+        }
+        if let Some(date) = date_bound(ui, "To", "date_before", query.before, today) {
+            *search_query = replace_filter(search_query, "before", format_date(date).as_deref());
+        }
+        if (query.after.is_some() || query.before.is_some()) && ui.small_button("Clear").clicked() {
+            *search_query = replace_filter(search_query, "after", None);
+            *search_query = replace_filter(search_query, "before", None);
+        }
+    });
+}
+
+/// Returns the new bound when it changed; `Some(None)` clears it.
+fn date_bound(
+    ui: &mut egui::Ui,
+    label: &str,
+    id: &str,
+    current: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Option<Option<NaiveDate>> {
+    let mut enabled = current.is_some();
+    let mut date = current.unwrap_or(today);
+    let toggled = ui.checkbox(&mut enabled, label).changed();
+    let picked = ui.add_enabled(enabled, DatePickerButton::new(&mut date).id_salt(id)).changed();
+    (toggled || picked).then(|| enabled.then_some(date))
+}
+
+fn format_date(date: Option<NaiveDate>) -> Option<String> {
+    date.map(|date| date.format(DATE_FORMAT).to_string())
+}
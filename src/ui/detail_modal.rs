@@ -0,0 +1,143 @@
+use chrono::{DateTime, Local};
+use egui;
+use std::time::SystemTime;
+
+use crate::bidi;
+use crate::storage::usage::SnippetUsage;
+use crate::storage::{Snippet, SECRET_MASK};
+
+const MODAL_WIDTH: f32 = 480.0;
+const CONTENT_HEIGHT: f32 = 260.0;
+
+pub enum DetailAction {
+    Copy,
+    Edit,
+    Delete,
+    ShowHistory,
+    Close,
+}
+
+/// Everything known about one snippet, over the get window.
+pub fn show_detail_modal(
+    ctx: &egui::Context,
+    snippet: &Snippet,
+    usage: Option<SnippetUsage>,
+    revealed: &mut bool,
+) -> Option<DetailAction> {
+    let mut action = None;
+
+    let response = egui::Modal::new(egui::Id::new("snippet_detail")).show(ctx, |ui| {
+        ui.set_width(MODAL_WIDTH);
+        ui.heading(bidi::visual(snippet.safe_title()).as_ref());
+        ui.separator();
+
+        let hidden = snippet.meta.secret && !*revealed;
+        egui::ScrollArea::vertical()
+            .max_height(CONTENT_HEIGHT)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                if hidden {
+                    ui.weak(SECRET_MASK);
+                } else {
+                    ui.add(egui::Label::new(egui::RichText::new(bidi::visual(&snippet.content)).monospace()).selectable(true));
+                }
+            });
+        if snippet.meta.secret {
+            ui.toggle_value(revealed, if *revealed { "🙈 Hide" } else { "👁 Reveal" });
+        }
+        ui.separator();
+
+        egui::Grid::new("detail_grid").num_columns(2).spacing([12.0, 4.0]).show(ui, |ui| {
+            let meta = &snippet.meta;
+            row(ui, "Folder", if snippet.folder.is_empty() { "(root)" } else { &snippet.folder });
+            if let Some(language) = meta.language {
+                row(ui, "Language", language.name());
+            }
+            if !meta.tags.is_empty() {
+                let tags: Vec<String> = meta.tags.iter().map(|tag| format!("#{}", tag)).collect();
+                row(ui, "Tags", &tags.join(" "));
+            }
+            if let Some(source) = &meta.source {
+                let from = if source.window.is_empty() {
+                    source.app.clone()
+                } else {
+                    format!("{} — {}", source.app, source.window)
+                };
+                row(ui, "Captured from", &from);
+            }
+            if !meta.apps.is_empty() {
+                row(ui, "Only in", &meta.apps.join(", "));
+            }
+            row(ui, "Created", &format_time(snippet.created));
+            row(ui, "Modified", &format_time(snippet.modified));
+            if let Some(expires) = meta.expires {
+                row(ui, "Expires", &DateTime::<Local>::from(expires).format("%Y-%m-%d %H:%M").to_string());
+            }
+            let used = match usage {
+                Some(usage) => format!("{} times, last {}", usage.count, format_time(usage.last_used.into())),
+                None => "Never".to_string(),
+            };
+            row(ui, "Used", &used);
+            let flags: Vec<&str> = [
+                (meta.pinned, "pinned"),
+                (meta.locked, "locked"),
+                (meta.secret, "secret"),
+                (meta.checklist, "checklist"),
+            ]
+            .into_iter()
+            .filter_map(|(set, name)| set.then_some(name))
+            .collect();
+            if !flags.is_empty() {
+                row(ui, "Flags", &flags.join(", "));
+            }
+            row(ui, "File", &snippet.file_path.display().to_string());
+            row(ui, "Size", &format!("{} bytes", snippet.content.len()));
+        });
+
+        if !snippet.meta.notes.is_empty() {
+            ui.add_space(4.0);
+            ui.label(egui::RichText::new(bidi::visual(&snippet.meta.notes)).italics());
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            let locked = snippet.meta.locked;
+            if ui.button("📋 Copy").clicked() {
+                action = Some(DetailAction::Copy);
+            }
+            if ui.add_enabled(!locked, egui::Button::new("Edit"))
+                .on_disabled_hover_text("Unlock the snippet to edit it")
+                .clicked()
+            {
+                action = Some(DetailAction::Edit);
+            }
+            if ui.button("History…").clicked() {
+                action = Some(DetailAction::ShowHistory);
+            }
+            if ui.add_enabled(!locked, egui::Button::new("Delete"))
+                .on_disabled_hover_text("Unlock the snippet to delete it")
+                .clicked()
+            {
+                action = Some(DetailAction::Delete);
+            }
+            if ui.button("Close").clicked() {
+                action = Some(DetailAction::Close);
+            }
+        });
+    });
+
+    if action.is_none() && response.should_close() {
+        action = Some(DetailAction::Close);
+    }
+    action
+}
+
+fn row(ui: &mut egui::Ui, label: &str, value: &str) {
+    ui.weak(label);
+    ui.add(egui::Label::new(value).selectable(true).wrap());
+    ui.end_row();
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M").to_string()
+}
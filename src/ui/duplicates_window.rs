@@ -0,0 +1,136 @@
+use chrono::{DateTime, Local};
+use egui;
+
+use crate::storage::duplicates::{find_duplicates, DuplicateGroup};
+use crate::storage::{Snippet, UsageLog};
+
+/// One group's resolution: keep `keep`, drop the rest. Indices refer to the
+/// snippet list passed to `show`.
+pub struct DuplicatePlan {
+    pub keep: usize,
+    pub remove: Vec<usize>,
+}
+
+pub enum DuplicatesAction {
+    /// Delete the extra copies after folding their usage and notes into the kept one.
+    Merge(Vec<DuplicatePlan>),
+    /// Delete the extra copies as they are.
+    Delete(Vec<DuplicatePlan>),
+    Back,
+}
+
+struct GroupState {
+    group: DuplicateGroup,
+    /// Position in `group.indices` of the snippet to keep.
+    keep: usize,
+    selected: bool,
+}
+
+pub struct DuplicatesWindowState {
+    groups: Vec<GroupState>,
+}
+
+impl DuplicatesWindowState {
+    pub fn new(snippets: &[Snippet], usage: &UsageLog) -> Self {
+        let groups = find_duplicates(snippets)
+            .into_iter()
+            .map(|group| {
+                // Default to keeping the most used copy, then the oldest.
+                let keep = (0..group.indices.len())
+                    .max_by_key(|&position| {
+                        let snippet = &snippets[group.indices[position]];
+                        let count = usage.get(&snippet.id).map_or(0, |u| u.count);
+                        (count, std::cmp::Reverse(snippet.created))
+                    })
+                    .unwrap_or(0);
+                GroupState { group, keep, selected: true }
+            })
+            .collect();
+        Self { groups }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, snippets: &[Snippet], usage: &UsageLog) -> Option<DuplicatesAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(DuplicatesAction::Back);
+                }
+                ui.heading("Duplicates");
+            });
+            ui.separator();
+
+            if self.groups.is_empty() {
+                ui.weak("No duplicate snippets found.");
+                return;
+            }
+
+            let selected = self.groups.iter().filter(|g| g.selected).count();
+            ui.horizontal(|ui| {
+                ui.label(format!("{} groups, {} selected", self.groups.len(), selected));
+                if ui.add_enabled(selected > 0, egui::Button::new("Merge selected"))
+                    .on_hover_text("Keep the chosen copy with the usage and notes of all copies")
+                    .clicked()
+                {
+                    action = Some(DuplicatesAction::Merge(self.plans()));
+                }
+                if ui.add_enabled(selected > 0, egui::Button::new("Delete extras"))
+                    .on_hover_text("Keep the chosen copy and move the others to the trash")
+                    .clicked()
+                {
+                    action = Some(DuplicatesAction::Delete(self.plans()));
+                }
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                for (group_index, state) in self.groups.iter_mut().enumerate() {
+                    ui.push_id(group_index, |ui| {
+                        show_group(ui, state, snippets, usage);
+                    });
+                    ui.separator();
+                }
+            });
+        });
+
+        action
+    }
+
+    fn plans(&self) -> Vec<DuplicatePlan> {
+        self.groups.iter()
+            .filter(|state| state.selected)
+            .map(|state| DuplicatePlan {
+                keep: state.group.indices[state.keep],
+                remove: state.group.indices.iter()
+                    .enumerate()
+                    .filter(|&(position, _)| position != state.keep)
+                    .map(|(_, &index)| index)
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+fn show_group(ui: &mut egui::Ui, state: &mut GroupState, snippets: &[Snippet], usage: &UsageLog) {
+    let kind = if state.group.exact { "Identical" } else { "Similar" };
+    ui.checkbox(&mut state.selected, format!("{} ({} copies)", kind, state.group.indices.len()));
+
+    for (position, &index) in state.group.indices.iter().enumerate() {
+        let snippet = &snippets[index];
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut state.keep, position, "Keep");
+            let created: DateTime<Local> = snippet.created.into();
+            ui.weak(created.format("%Y-%m-%d").to_string());
+            let uses = usage.get(&snippet.id).map_or(0, |u| u.count);
+            ui.weak(format!("{} uses", uses));
+            if !snippet.folder.is_empty() {
+                ui.weak(format!("📁 {}", snippet.folder));
+            }
+            if snippet.meta.locked {
+                ui.label("🔒").on_hover_text("Locked snippets can't be removed");
+            }
+            ui.add(egui::Label::new(snippet.safe_title()).truncate());
+        });
+    }
+}
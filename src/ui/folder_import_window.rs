@@ -0,0 +1,121 @@
+use egui;
+use std::path::PathBuf;
+
+use crate::import::folder::{FolderPlan, Subfolders};
+
+pub enum FolderImportAction {
+    /// Work out what importing the folder would do, without saving.
+    Preview(PathBuf, Subfolders),
+    Import(FolderPlan),
+    Back,
+}
+
+/// Imports a folder of text files in two steps: a preview of the snippets
+/// it would make and the files it would leave out, then the import itself.
+pub struct FolderImportWindowState {
+    path: String,
+    subfolders: Subfolders,
+    /// The last preview, dropped when the path or mapping changes.
+    plan: Option<FolderPlan>,
+    /// Set while a preview or import runs.
+    busy: bool,
+}
+
+impl FolderImportWindowState {
+    pub fn new() -> Self {
+        Self { path: String::new(), subfolders: Subfolders::Folders, plan: None, busy: false }
+    }
+
+    pub fn set_plan(&mut self, plan: Option<FolderPlan>) {
+        self.plan = plan;
+        self.busy = false;
+    }
+
+    pub fn set_busy(&mut self, busy: bool) {
+        self.busy = busy;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<FolderImportAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(FolderImportAction::Back);
+                }
+                ui.heading("Import folder");
+            });
+            ui.weak("Each .txt or .md file becomes a snippet titled by its file name. Nothing is saved until you import.");
+            ui.separator();
+
+            let mut changed = false;
+            egui::Grid::new("folder_import_grid").num_columns(2).spacing([12.0, 8.0]).show(ui, |ui| {
+                ui.label("Folder:");
+                changed |= ui.add(egui::TextEdit::singleline(&mut self.path).hint_text("Folder to read").desired_width(320.0)).changed();
+                ui.end_row();
+
+                ui.label("Folders inside:");
+                ui.horizontal(|ui| {
+                    changed |= ui.radio_value(&mut self.subfolders, Subfolders::Folders, "become folders").changed();
+                    changed |= ui.radio_value(&mut self.subfolders, Subfolders::Tags, "become tags").changed();
+                });
+                ui.end_row();
+            });
+            if changed {
+                self.plan = None;
+            }
+            ui.add_space(8.0);
+
+            ui.horizontal(|ui| {
+                let ready = !self.busy && !self.path.trim().is_empty();
+                if ui.add_enabled(ready, egui::Button::new("Preview")).clicked() {
+                    action = Some(FolderImportAction::Preview(PathBuf::from(self.path.trim()), self.subfolders));
+                }
+                let importable = self.plan.as_ref().map_or(0, |plan| plan.items.len());
+                if ui.add_enabled(!self.busy && importable > 0, egui::Button::new(format!("Import {} snippets", importable))).clicked() {
+                    if let Some(plan) = self.plan.take() {
+                        self.busy = true;
+                        action = Some(FolderImportAction::Import(plan));
+                    }
+                }
+                if self.busy {
+                    ui.spinner();
+                }
+            });
+
+            let Some(plan) = &self.plan else {
+                return;
+            };
+            ui.separator();
+            ui.label(format!("{} snippets into the {} folder", plan.items.len(), plan.folder));
+            if plan.duplicates > 0 {
+                ui.weak(format!("{} files are already in the library and are left out.", plan.duplicates));
+            }
+            if !plan.skipped.is_empty() {
+                egui::CollapsingHeader::new(format!("{} files left out", plan.skipped.len()))
+                    .id_salt("folder_import_skipped")
+                    .show(ui, |ui| {
+                        for (path, reason) in &plan.skipped {
+                            ui.weak(format!("{}: {}", path.display(), reason));
+                        }
+                    });
+            }
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                egui::Grid::new("folder_import_plan").num_columns(3).striped(true).show(ui, |ui| {
+                    ui.strong("Title");
+                    ui.strong("Folder");
+                    ui.strong("Tags");
+                    ui.end_row();
+                    for item in &plan.items {
+                        ui.add(egui::Label::new(item.title.as_deref().unwrap_or_default()).truncate());
+                        ui.weak(if item.folder.is_empty() { plan.folder.clone() } else { format!("{}/{}", plan.folder, item.folder) });
+                        ui.weak(item.tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" "));
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+
+        action
+    }
+}
@@ -0,0 +1,49 @@
+use egui;
+use std::collections::BTreeMap;
+
+#[derive(Default)]
+struct FolderNode {
+    children: BTreeMap<String, FolderNode>,
+}
+
+/// Sidebar tree of folders. `selected` is `None` for "all snippets" or the
+/// `/`-separated path of the chosen folder. Returns true when it changed.
+/// Folders in `locked` are encrypted and not unlocked yet.
+pub fn show_folder_tree(ui: &mut egui::Ui, folders: &[String], locked: &[String], selected: &mut Option<String>) -> bool {
+    let mut root = FolderNode::default();
+    for folder in folders {
+        let mut node = &mut root;
+        for part in folder.split('/') {
+            node = node.children.entry(part.to_string()).or_default();
+        }
+    }
+    
+    let before = selected.clone();
+    ui.selectable_value(selected, None, "All snippets");
+    egui::ScrollArea::vertical().id_salt("folder_tree").show(ui, |ui| {
+        show_children(ui, &root, "", locked, selected);
+    });
+    *selected != before
+}
+
+fn show_children(ui: &mut egui::Ui, node: &FolderNode, prefix: &str, locked: &[String], selected: &mut Option<String>) {
+    for (name, child) in &node.children {
+        let path = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        
+        let icon = if locked.contains(&path) { "🔒" } else { "📁" };
+        if child.children.is_empty() {
+            ui.selectable_value(selected, Some(path.clone()), format!("{} {}", icon, name));
+        } else {
+            let id = ui.make_persistent_id(("folder", &path));
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+                .show_header(ui, |ui| {
+                    ui.selectable_value(selected, Some(path.clone()), format!("{} {}", icon, name));
+                })
+                .body(|ui| show_children(ui, child, &path, locked, selected));
+        }
+    }
+}
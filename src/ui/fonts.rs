@@ -0,0 +1,32 @@
+use egui::epaint::text::{FontInsert, FontPriority, InsertFontFamily};
+use egui::{FontData, FontFamily};
+
+/// System fonts with Hebrew and Arabic letters, including the presentation
+/// forms `bidi` shapes Arabic into. egui's own fonts have neither. The
+/// first one found is used.
+#[cfg(windows)]
+const RTL_FONTS: &[&str] = &[r"C:\Windows\Fonts\segoeui.ttf", r"C:\Windows\Fonts\arial.ttf"];
+#[cfg(target_os = "macos")]
+const RTL_FONTS: &[&str] = &["/System/Library/Fonts/Supplemental/Arial.ttf", "/Library/Fonts/Arial Unicode.ttf"];
+#[cfg(not(any(windows, target_os = "macos")))]
+const RTL_FONTS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu-sans-fonts/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+];
+
+/// Adds a system font as the last fallback, so Hebrew and Arabic snippets
+/// show letters rather than boxes.
+pub fn add_rtl_fallback(ctx: &egui::Context) {
+    let Some((path, bytes)) = RTL_FONTS.iter().find_map(|path| Some((path, std::fs::read(path).ok()?))) else {
+        log::info!("No system font with Hebrew and Arabic letters found");
+        return;
+    };
+    log::debug!("Using {} for Hebrew and Arabic", path);
+    let families = [FontFamily::Proportional, FontFamily::Monospace]
+        .into_iter()
+        .map(|family| InsertFontFamily { family, priority: FontPriority::Lowest })
+        .collect();
+    ctx.add_font(FontInsert::new("rtl-fallback", FontData::from_owned(bytes), families));
+}
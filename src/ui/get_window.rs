@@ -1,7 +1,110 @@
 use egui;
+use egui::text::{LayoutJob, TextFormat};
 use egui_extras::{Column, TableBuilder};
-use crate::storage::Snippet;
-use chrono::{DateTime, Local};
+use crate::bidi::{self, clip};
+#[cfg(feature = "hotkeys")]
+use crate::hotkeys::{ADD_HINT, SNOOZE_HINT};
+use crate::platform::active_window::ActiveApp;
+use crate::keymap::{Command, Keymap, Profile, Scope};
+use crate::storage::indexer::replace_filter;
+use crate::storage::{resolve_link, SearchProvider, SearchQuery, Snippet, UsageLog, SECRET_MASK};
+#[cfg(feature = "sync")]
+use crate::sync::lan::SyncStatus;
+use crate::transform::{Pipeline, Transform};
+use crate::ui::build_list::BuildList;
+use crate::ui::date_filter::show_date_filter;
+use crate::ui::detail_modal::{show_detail_modal, DetailAction};
+use crate::ui::bulk_bar::{show_bulk_bar, BulkBarAction, BulkOp};
+use crate::ui::folder_tree::show_folder_tree;
+use crate::ui::preview_pane::{show_preview, MatchCursor, PreviewAction};
+use crate::ui::qr_popup::QrPopup;
+use crate::ui::shortcut_overlay::show_shortcut_overlay;
+use crate::ui::status_bar::{show_status_bar, FilterChip};
+#[cfg(feature = "sync")]
+use crate::ui::sync_status::{show_sync_error, show_sync_indicator, SyncStatusAction};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
+/// Time slice the filter may use per frame before yielding to rendering.
+const FILTER_BUDGET: Duration = Duration::from_millis(4);
+/// How many snippets are checked between clock reads.
+const FILTER_CHUNK: usize = 256;
+/// Lengths offered under ☰ → Snooze hotkeys, in minutes.
+#[cfg(any(feature = "hotkeys", feature = "tray"))]
+const SNOOZE_CHOICES: &[(&str, u32)] = &[("15 minutes", 15), ("1 hour", 60), ("4 hours", 4 * 60), ("8 hours", 8 * 60)];
+const APPEND_HINT: &str = "New snippets and captures are added to the end of this one";
+/// How long a copied row says so in a sticky session.
+const COPIED_FLASH: Duration = Duration::from_millis(1500);
+
+/// How the get window presents results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListLayout {
+    /// Columns, folder tree, preview pane and build list.
+    #[default]
+    Table,
+    /// A search box over a flat list of titles, nothing else.
+    Launcher,
+}
+
+impl ListLayout {
+    pub const ALL: [ListLayout; 2] = [ListLayout::Table, ListLayout::Launcher];
+    
+    pub fn label(self) -> &'static str {
+        match self {
+            ListLayout::Table => "Table",
+            ListLayout::Launcher => "Compact launcher",
+        }
+    }
+}
+
+/// A column the results table can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortColumn {
+    Date,
+    Lang,
+    Title,
+}
+
+/// How the results table was last left, kept in the config so it looks the
+/// same after a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableLayout {
+    /// Column widths in points, once resized; the title column takes the rest.
+    pub date_width: Option<f32>,
+    pub lang_width: Option<f32>,
+    pub show_date: bool,
+    pub show_lang: bool,
+    /// `None` keeps the search's order: best match first, then pinned and newest.
+    pub sort: Option<SortColumn>,
+    pub descending: bool,
+}
+
+impl Default for TableLayout {
+    fn default() -> Self {
+        Self { date_width: None, lang_width: None, show_date: true, show_lang: true, sort: None, descending: false }
+    }
+}
+
+/// How much of the list fits on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListDensity {
+    pub row_height: f32,
+    pub preview_chars: usize,
+}
+
+impl Default for ListDensity {
+    fn default() -> Self {
+        Self {
+            row_height: 25.0,
+            preview_chars: 80,
+        }
+    }
+}
 
 #[derive(Default)]
 pub struct GetWindowState {
@@ -9,6 +112,156 @@ pub struct GetWindowState {
     filtered_indices: Vec<usize>,
     selected_index: usize,
     first_frame: bool,
+    filter: FilterProgress,
+    /// Time slice the filter may use per frame; `None` finishes each pass
+    /// in the frame it starts, so tests see the same results every run.
+    filter_budget: Option<Duration>,
+    preview_cache: HashMap<usize, LayoutJob>,
+    scroll_to_selected: bool,
+    /// Snippet index to select once the running filter pass reaches it.
+    pending_jump: Option<usize>,
+    selected_folder: Option<String>,
+    /// Encrypted folders whose key isn't loaded yet.
+    locked_folders: Vec<String>,
+    passphrase: String,
+    /// Snippets edited here and on a synced device, waiting to be settled.
+    #[cfg(feature = "sync")]
+    sync_conflicts: usize,
+    /// `None` while LAN sync is off.
+    #[cfg(feature = "sync")]
+    sync_status: Option<SyncStatus>,
+    #[cfg(feature = "sync")]
+    sync_error_open: bool,
+    /// Id of the secret snippet whose content is currently revealed.
+    revealed_id: Option<String>,
+    transforms: Pipeline,
+    /// Whether the preview wraps long lines; follows `word_wrap` in the config.
+    wrap: bool,
+    /// Snippets queued to be copied together; kept while the window is hidden.
+    build: BuildList,
+    qr: Option<QrPopup>,
+    /// Snippet indices picked with Ctrl/Shift-click for bulk operations.
+    selection: BTreeSet<usize>,
+    tag_input: String,
+    keymap: Keymap,
+    shortcuts_open: bool,
+    focus_search: bool,
+    /// Whether the search box had focus at the end of the last frame.
+    search_focused: bool,
+    /// Position in the search history of the recalled query being shown.
+    history_cursor: Option<usize>,
+    date_filter_open: bool,
+    /// Id of the snippet shown in the detail modal.
+    detail_id: Option<String>,
+    /// List index of the row under the pointer last frame, which shows quick actions.
+    hovered_row: Option<usize>,
+    density: ListDensity,
+    layout: ListLayout,
+    table: TableLayout,
+    /// Scroll position of the launcher list last frame.
+    launcher_offset: f32,
+    matches: MatchCursor,
+    /// The app that had the focus when the window opened.
+    active_app: Option<ActiveApp>,
+    /// Lists snippets meant for other apps too.
+    all_apps: bool,
+    /// The snippets changed since the search provider last indexed them.
+    index_stale: bool,
+    /// The snippet captures are appended to, marked in the list.
+    append_target: Option<String>,
+    /// Copying leaves the window open, for copying one snippet after
+    /// another; Escape ends the session.
+    sticky: bool,
+    /// When each snippet was last copied in a sticky session, by id.
+    copied: HashMap<String, Instant>,
+    /// With an empty search box, how many of the results are the pinned
+    /// ones heading the default view; the recently used follow them.
+    /// `None` while the list is a plain one.
+    sections: Option<usize>,
+}
+
+/// A filter pass that may span several frames. When the new query only
+/// narrows the previous one, the pass scans the previous results instead of
+/// the whole library.
+#[derive(Default)]
+struct FilterProgress {
+    query: SearchQuery,
+    candidates: Option<Vec<usize>>,
+    cursor: usize,
+    complete: bool,
+    /// The provider's score for each result so far, to rank them once the
+    /// pass completes.
+    scores: Vec<f32>,
+}
+
+pub enum GetWindowAction {
+    /// Copy the snippet at this index of the slice passed to `show`.
+    Copy(usize),
+    Edit(usize),
+    /// Save a copy of the snippet and open it in the editor.
+    Duplicate(usize),
+    Delete(usize),
+    ToggleSecret(usize),
+    ToggleLock(usize),
+    /// Archive the snippet, or bring it back into the results.
+    ToggleArchived(usize),
+    ShowHistory(usize),
+    /// Wrap long lines in the preview and editor, or scroll them sideways.
+    SetWordWrap(bool),
+    /// Reveal this secret snippet; the app may confirm the user and
+    /// records the access first.
+    RevealSecret(String),
+    /// Copy a checklist as plain text instead of markdown.
+    CopyPlain(usize),
+    CopyTransformed(usize, Pipeline),
+    /// Copy the joined text of several snippets, listed by index.
+    CopyCombined(Vec<usize>, String),
+    ToggleChecklistItem(usize, usize),
+    ResetChecklist(usize),
+    Bulk(Vec<usize>, BulkOp),
+    Undo,
+    OpenStatistics,
+    OpenDuplicates,
+    OpenReview,
+    /// Write the snippets listed, by index, to one Markdown file.
+    ExportMarkdown(Vec<usize>),
+    /// Swap a pinned snippet with the pinned one above (`true`) or below it.
+    MovePinned(usize, bool),
+    OpenSettings,
+    /// Ignore the hotkeys and tray clicks and mute notifications for this
+    /// many minutes.
+    #[cfg(any(feature = "hotkeys", feature = "tray"))]
+    Snooze(u32),
+    OpenTags,
+    OpenTrash,
+    OpenAudit,
+    OpenRestore,
+    OpenImport,
+    OpenFolderImport,
+    OpenLog,
+    /// Type a new snippet; there is no add hotkey to do it with.
+    #[cfg(not(feature = "hotkeys"))]
+    NewSnippet,
+    #[cfg(feature = "vault")]
+    ExportArchive,
+    #[cfg(feature = "vault")]
+    ImportArchive,
+    #[cfg(feature = "sync")]
+    OpenPairing,
+    #[cfg(feature = "sync")]
+    OpenConflicts,
+    #[cfg(feature = "sync")]
+    SyncNow,
+    #[cfg(feature = "sync")]
+    DismissSyncError,
+    /// Unlock this encrypted folder with the typed passphrase.
+    UnlockFolder(String, String),
+    /// Open the add window with this text already in the editor.
+    CreateFromQuery(String),
+    /// Append later captures to the snippet at this index instead of
+    /// saving them as new ones; `None` goes back to new snippets.
+    SetAppendTarget(Option<usize>),
+    Close,
 }
 
 pub struct SnippetView {
@@ -18,49 +271,452 @@ pub struct SnippetView {
 }
 
 impl GetWindowState {
-    pub fn new() -> Self {
+    pub fn new(keymap: Keymap, density: ListDensity, layout: ListLayout) -> Self {
         Self {
             search_query: String::new(),
             filtered_indices: Vec::new(),
             selected_index: 0,
             first_frame: true,
+            filter: FilterProgress::default(),
+            filter_budget: Some(FILTER_BUDGET),
+            preview_cache: HashMap::new(),
+            scroll_to_selected: false,
+            pending_jump: None,
+            selected_folder: None,
+            locked_folders: Vec::new(),
+            passphrase: String::new(),
+            #[cfg(feature = "sync")]
+            sync_conflicts: 0,
+            #[cfg(feature = "sync")]
+            sync_status: None,
+            #[cfg(feature = "sync")]
+            sync_error_open: false,
+            revealed_id: None,
+            transforms: Pipeline::default(),
+            wrap: true,
+            build: BuildList::default(),
+            qr: None,
+            selection: BTreeSet::new(),
+            tag_input: String::new(),
+            keymap,
+            shortcuts_open: false,
+            focus_search: false,
+            search_focused: false,
+            history_cursor: None,
+            date_filter_open: false,
+            detail_id: None,
+            hovered_row: None,
+            density,
+            layout,
+            table: TableLayout::default(),
+            launcher_offset: 0.0,
+            matches: MatchCursor::default(),
+            active_app: None,
+            all_apps: false,
+            index_stale: true,
+            append_target: None,
+            sticky: false,
+            copied: HashMap::new(),
+            sections: None,
+        }
+    }
+    
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+    
+    pub fn set_density(&mut self, density: ListDensity) {
+        if density != self.density {
+            self.density = density;
+            self.preview_cache.clear();
+        }
+    }
+    
+    pub fn set_layout(&mut self, layout: ListLayout) {
+        if layout != self.layout {
+            self.layout = layout;
+            // Only the table is sorted by column.
+            self.resort();
+        }
+    }
+    
+    pub fn set_table_layout(&mut self, table: TableLayout) {
+        if table.sort != self.table.sort || table.descending != self.table.descending {
+            self.resort();
         }
+        self.table = table;
+    }
+    
+    /// The table's column widths, columns and sort order as the user left
+    /// them, for the app to keep.
+    pub fn table_layout(&self) -> &TableLayout {
+        &self.table
+    }
+    
+    /// Runs the filter again, so the results come out in the new order.
+    fn resort(&mut self) {
+        self.filter = FilterProgress::default();
+        self.filtered_indices.clear();
+        self.preview_cache.clear();
+    }
+    
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+    
+    pub fn set_append_target(&mut self, id: Option<String>) {
+        self.append_target = id;
+    }
+    
+    /// Whether copying should leave the window open.
+    pub fn is_sticky(&self) -> bool {
+        self.sticky
+    }
+    
+    /// Flashes "copied ✓" on the snippet's row.
+    pub fn mark_copied(&mut self, id: String) {
+        self.copied.insert(id, Instant::now());
+    }
+    
+    /// Lists the snippets for `app` and for any app, until the user asks
+    /// for all of them.
+    pub fn set_active_app(&mut self, app: Option<ActiveApp>) {
+        self.active_app = app;
+        self.all_apps = false;
+    }
+    
+    pub fn search_query(&self) -> &str {
+        &self.search_query
     }
     
-    pub fn show(&mut self, ctx: &egui::Context, snippets: &[Snippet]) -> Option<String> {
-        let mut selected_content = None;
+    /// `undo_label` names the last action that can be undone, if any.
+    /// `recent_searches` is the search history, newest first.
+    #[allow(clippy::too_many_arguments)]
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        snippets: &[Snippet],
+        search: &mut dyn SearchProvider,
+        folders: &[String],
+        undo_label: Option<&str>,
+        recent_searches: &[String],
+        usage: &UsageLog,
+    ) -> Option<GetWindowAction> {
+        // Escape has already taken focus away by now, so remember who had it.
+        let was_searching = self.search_focused;
+        
+        // Added first so it spans the full width below the side panels.
+        #[cfg(feature = "sync")]
+        let mut sync_action = None;
+        let removed = show_status_bar(
+            ctx,
+            self.filtered_indices.len(),
+            snippets.len(),
+            &self.filter.query,
+            !self.filter.complete,
+            |#[cfg_attr(not(feature = "sync"), allow(unused_variables))] ui| {
+                #[cfg(feature = "sync")]
+                if let Some(status) = &self.sync_status {
+                    sync_action = show_sync_indicator(ui, status);
+                }
+            },
+        );
+        match removed {
+            Some(FilterChip::Folder) => self.selected_folder = None,
+            Some(chip) => {
+                if let Some(key) = chip.key() {
+                    self.search_query = replace_filter(&self.search_query, key, None);
+                }
+            }
+            None => {}
+        }
+        
+        let mut action = match self.layout {
+            ListLayout::Table => self.show_table(ctx, snippets, search, folders, undo_label, recent_searches, usage),
+            ListLayout::Launcher => self.show_launcher(ctx, snippets, search, undo_label, recent_searches, usage),
+        };
+        
+        if self.qr.as_ref().is_some_and(|qr| !qr.show(ctx)) {
+            self.qr = None;
+        }
+        #[cfg(feature = "sync")]
+        if let Some(error) = self.sync_status.as_ref().and_then(|status| status.error.as_ref()).filter(|_| self.sync_error_open) {
+            sync_action = sync_action.or(show_sync_error(ctx, error, &mut self.sync_error_open));
+        }
+        #[cfg(feature = "sync")]
+        match sync_action {
+            Some(SyncStatusAction::SyncNow) => action = action.or(Some(GetWindowAction::SyncNow)),
+            Some(SyncStatusAction::ShowError) => self.sync_error_open = true,
+            Some(SyncStatusAction::Dismiss) => action = action.or(Some(GetWindowAction::DismissSyncError)),
+            None => {}
+        }
+        
+        let typing = ctx.wants_keyboard_input();
+        let commands = ctx.input(|i| self.keymap.triggered(i, Scope::Get, typing));
+        let selected_snippet = self.filtered_indices.get(self.selected_index)
+            .copied()
+            .filter(|&idx| idx < snippets.len());
+        // Up in an empty search box walks back through earlier searches.
+        let recalling = self.search_focused && (self.search_query.is_empty() || self.history_cursor.is_some());
+        for command in commands {
+            match command {
+                Command::SelectPrevious if recalling => {
+                    let older = self.history_cursor.map_or(0, |cursor| cursor + 1);
+                    if let Some(query) = recent_searches.get(older) {
+                        self.search_query = query.clone();
+                        self.history_cursor = Some(older);
+                    }
+                }
+                Command::SelectNext if recalling => {
+                    match self.history_cursor {
+                        Some(0) | None => {
+                            self.search_query.clear();
+                            self.history_cursor = None;
+                        }
+                        Some(cursor) => {
+                            self.search_query = recent_searches[cursor - 1].clone();
+                            self.history_cursor = Some(cursor - 1);
+                        }
+                    }
+                }
+                Command::SelectPrevious if self.selected_index > 0 => {
+                    self.selected_index -= 1;
+                    self.scroll_to_selected = true;
+                }
+                Command::SelectNext if self.selected_index < self.filtered_indices.len().saturating_sub(1) => {
+                    self.selected_index += 1;
+                    self.scroll_to_selected = true;
+                }
+                Command::JumpFirst => {
+                    self.selected_index = 0;
+                    self.scroll_to_selected = true;
+                }
+                Command::JumpLast => {
+                    self.selected_index = self.filtered_indices.len().saturating_sub(1);
+                    self.scroll_to_selected = true;
+                }
+                Command::MovePinnedUp | Command::MovePinnedDown => {
+                    if let Some(snippet_index) = selected_snippet.filter(|&i| snippets[i].meta.pinned) {
+                        action = Some(GetWindowAction::MovePinned(snippet_index, command == Command::MovePinnedUp));
+                    }
+                }
+                Command::NextMatch => self.matches.next(),
+                Command::PreviousMatch => self.matches.previous(),
+                Command::FocusSearch => self.focus_search = true,
+                Command::ShowDetails => {
+                    self.detail_id = selected_snippet.map(|i| snippets[i].id.clone());
+                }
+                Command::Copy => {
+                    if let Some(snippet_index) = selected_snippet {
+                        action = Some(GetWindowAction::Copy(snippet_index));
+                    }
+                }
+                Command::AddToBuild => {
+                    if let Some(snippet_index) = selected_snippet {
+                        self.build.push(&snippets[snippet_index].id);
+                    }
+                }
+                Command::Delete => {
+                    if let Some(snippet_index) = selected_snippet {
+                        action = Some(GetWindowAction::Delete(snippet_index));
+                    }
+                }
+                // With text in the search box, Ctrl+Z belongs to the text field.
+                Command::Undo if self.search_query.is_empty() && undo_label.is_some() => {
+                    action = Some(GetWindowAction::Undo);
+                }
+                Command::ShowShortcuts => self.shortcuts_open = !self.shortcuts_open,
+                Command::Close => {
+                    if self.shortcuts_open {
+                        self.shortcuts_open = false;
+                    } else if self.detail_id.is_some() {
+                        self.detail_id = None;
+                    } else if was_searching && self.keymap.profile() == Profile::Vim {
+                        // Escape only leaves the search box, like leaving insert mode.
+                    } else if self.selection.is_empty() {
+                        action = Some(GetWindowAction::Close);
+                    } else {
+                        self.selection.clear();
+                    }
+                }
+                _ => {}
+            }
+        }
+        
+        if self.shortcuts_open {
+            show_shortcut_overlay(ctx, &self.keymap, Scope::Get, &mut self.shortcuts_open);
+        }
+        
+        let detail = self.detail_id.as_ref()
+            .and_then(|id| snippets.iter().position(|snippet| &snippet.id == id));
+        if let Some(snippet_index) = detail {
+            let snippet = &snippets[snippet_index];
+            let mut revealed = self.revealed_id.as_deref() == Some(snippet.id.as_str());
+            let detail_action = show_detail_modal(ctx, snippet, usage.get(&snippet.id), &mut revealed);
+            action = self.set_revealed(&snippet.id, revealed).or(action);
+            if detail_action.is_some() {
+                self.detail_id = None;
+            }
+            match detail_action {
+                Some(DetailAction::Copy) => action = Some(GetWindowAction::Copy(snippet_index)),
+                Some(DetailAction::Edit) => action = Some(GetWindowAction::Edit(snippet_index)),
+                Some(DetailAction::Delete) => action = Some(GetWindowAction::Delete(snippet_index)),
+                Some(DetailAction::ShowHistory) => action = Some(GetWindowAction::ShowHistory(snippet_index)),
+                Some(DetailAction::Close) | None => {}
+            }
+        } else {
+            self.detail_id = None;
+        }
+        
+        action
+    }
+    
+    #[allow(clippy::too_many_arguments)]
+    fn show_table(
+        &mut self,
+        ctx: &egui::Context,
+        snippets: &[Snippet],
+        search: &mut dyn SearchProvider,
+        folders: &[String],
+        undo_label: Option<&str>,
+        recent_searches: &[String],
+        usage: &UsageLog,
+    ) -> Option<GetWindowAction> {
+        let mut action = None;
+        
+        if !folders.is_empty() {
+            egui::SidePanel::left("folder_tree")
+                .resizable(true)
+                .default_width(140.0)
+                .show(ctx, |ui| {
+                    show_folder_tree(ui, folders, &self.locked_folders, &mut self.selected_folder);
+                });
+        }
+        
+        if !self.build.is_empty() {
+            egui::SidePanel::right("build_list")
+                .resizable(true)
+                .default_width(180.0)
+                .show(ctx, |ui| {
+                    if let Some((indices, text)) = self.build.show(ui, snippets) {
+                        action = Some(GetWindowAction::CopyCombined(indices, text));
+                    }
+                });
+        }
+        
+        let selected = self.filtered_indices.get(self.selected_index).copied();
+        if let Some((snippet_index, snippet)) = selected.and_then(|i| snippets.get(i).map(|s| (i, s))) {
+            let mut revealed = self.revealed_id.as_deref() == Some(snippet.id.as_str());
+            let mut preview_action = None;
+            egui::TopBottomPanel::bottom("preview_pane")
+                .resizable(true)
+                .default_height(120.0)
+                .show(ctx, |ui| {
+                    preview_action = show_preview(
+                        ui,
+                        snippet,
+                        &self.filter.query,
+                        &*search,
+                        &mut self.matches,
+                        &mut revealed,
+                        &mut self.transforms,
+                        self.wrap,
+                    );
+                });
+            action = self.set_revealed(&snippet.id, revealed).or(action);
+            
+            match preview_action {
+                Some(PreviewAction::FollowLink(target)) => self.jump_to_link(&target, snippets),
+                Some(PreviewAction::Copy) => action = Some(GetWindowAction::Copy(snippet_index)),
+                Some(PreviewAction::ToggleSecret) => action = Some(GetWindowAction::ToggleSecret(snippet_index)),
+                Some(PreviewAction::ToggleChecklistItem(line)) => {
+                    action = Some(GetWindowAction::ToggleChecklistItem(snippet_index, line));
+                }
+                Some(PreviewAction::ResetChecklist) => action = Some(GetWindowAction::ResetChecklist(snippet_index)),
+                Some(PreviewAction::CopyPlain) => action = Some(GetWindowAction::CopyPlain(snippet_index)),
+                Some(PreviewAction::ShowQr) => self.qr = Some(QrPopup::new(ctx, snippet.safe_title(), &snippet.content)),
+                Some(PreviewAction::CopyTransformed) => {
+                    action = Some(GetWindowAction::CopyTransformed(snippet_index, self.transforms.clone()));
+                }
+                Some(PreviewAction::ToggleWrap) => action = Some(GetWindowAction::SetWordWrap(!self.wrap)),
+                None => {}
+            }
+        }
         
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.label("Search:");
-                let search_response = ui.text_edit_singleline(&mut self.search_query);
-                
-                if self.first_frame {
-                    search_response.request_focus();
-                    self.first_frame = false;
+            if let Some(chosen) = self.search_row(ui, undo_label, recent_searches) {
+                action = Some(chosen);
+            }
+            
+            if !self.selection.is_empty() {
+                match show_bulk_bar(ui, self.selection.len(), folders, &mut self.tag_input) {
+                    Some(BulkBarAction::Apply(op)) => {
+                        let indices = std::mem::take(&mut self.selection).into_iter().collect();
+                        action = Some(GetWindowAction::Bulk(indices, op));
+                    }
+                    Some(BulkBarAction::SelectAll) => self.selection.extend(self.filtered_indices.iter().copied()),
+                    Some(BulkBarAction::ClearSelection) => self.selection.clear(),
+                    None => {}
                 }
-            });
+            }
             
             ui.separator();
             
-            self.update_filtered_results(snippets);
+            self.update_filtered_results(snippets, search, usage);
+            if !self.filter.complete {
+                ctx.request_repaint();
+            }
+            self.apply_pending_jump();
+            if self.filter.complete && self.filtered_indices.is_empty() {
+                if let Some(chosen) = self.empty_state(ui, snippets.is_empty()) {
+                    action = Some(chosen);
+                }
+                return;
+            }
             
-            let table = TableBuilder::new(ui)
+            let mut popup = None;
+            let hovered_row = self.hovered_row.take();
+            let sections = self.sections;
+            let (show_date, show_lang) = (self.table.show_date, self.table.show_lang);
+            let mut table = TableBuilder::new(ui)
                 .striped(true)
                 .resizable(true)
-                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-                .column(Column::auto().at_least(120.0))
-                .column(Column::remainder())
-                .min_scrolled_height(300.0);
+                .sense(egui::Sense::click())
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center));
+            if show_date {
+                table = table.column(sized_column(self.table.date_width, 120.0));
+            }
+            if show_lang {
+                table = table.column(sized_column(self.table.lang_width, 60.0));
+            }
+            table = table.column(Column::remainder()).min_scrolled_height(300.0);
             
+            if std::mem::take(&mut self.scroll_to_selected) {
+                table = table.scroll_to_row(self.selected_index, None);
+            }
+            
+            let mut widths = (None, None);
+            let mut resort = false;
+            let layout = &mut self.table;
             table
                 .header(20.0, |mut header| {
-                    header.col(|ui| { ui.strong("Date"); });
-                    header.col(|ui| { ui.strong("Preview"); });
+                    if show_date {
+                        header.col(|ui| {
+                            widths.0 = Some(ui.max_rect().width());
+                            resort |= sort_header(ui, "Date", SortColumn::Date, layout);
+                        });
+                    }
+                    if show_lang {
+                        header.col(|ui| {
+                            widths.1 = Some(ui.max_rect().width());
+                            resort |= sort_header(ui, "Lang", SortColumn::Lang, layout);
+                        });
+                    }
+                    header.col(|ui| { resort |= sort_header(ui, "Title", SortColumn::Title, layout); });
                 })
                 .body(|body| {
                     body.rows(
-                        25.0, 
+                        self.density.row_height,
                         self.filtered_indices.len(),
                         |mut row| {
                             let list_index = row.index();
@@ -68,80 +724,894 @@ impl GetWindowState {
                                 let snippet_index = self.filtered_indices[list_index];
                                 if snippet_index < snippets.len() {
                                     let snippet = &snippets[snippet_index];
-                                    let is_selected = list_index == self.selected_index;
+                                    let is_selected = list_index == self.selected_index
+                                        || self.selection.contains(&snippet_index);
                                     
                                     row.set_selected(is_selected);
+                                    let appending = self.append_target.as_ref() == Some(&snippet.id);
+                                    
+                                    if show_date {
+                                        row.col(|ui| {
+                                            ui.label(format_timestamp(snippet.created));
+                                            row_badges(ui, snippet, appending);
+                                        });
+                                    }
+                                    
+                                    if show_lang {
+                                        row.col(|ui| {
+                                            if let Some(language) = snippet.meta.language {
+                                                ui.weak(language.name());
+                                            }
+                                        });
+                                    }
                                     
                                     row.col(|ui| {
-                                        ui.label(format_timestamp(snippet.created));
+                                        // Buttons claim the right edge first; the title truncates into what's left.
+                                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                            if hovered_row == Some(list_index) {
+                                                if let Some(chosen) = quick_actions(ui, snippet, snippet_index) {
+                                                    action = Some(chosen);
+                                                }
+                                            }
+                                            if let Some(label) = section_label(sections, list_index) {
+                                                ui.weak(label);
+                                            }
+                                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                                                if !show_date {
+                                                    row_badges(ui, snippet, appending);
+                                                }
+                                                show_copied(ui, self.copied.get(&snippet.id));
+                                                for tag in &snippet.meta.tags {
+                                                    ui.small(format!("#{}", tag));
+                                                }
+                                                if snippet.meta.secret {
+                                                    ui.weak(format!("🔒 {}", SECRET_MASK));
+                                                    return;
+                                                }
+                                                let query = &self.filter.query;
+                                                let preview_chars = self.density.preview_chars;
+                                                let job = self.preview_cache
+                                                    .entry(snippet_index)
+                                                    .or_insert_with(|| {
+                                                        highlight_matches(&clip(&snippet.title, preview_chars), query, &*search, ui.style())
+                                                    })
+                                                    .clone();
+                                                ui.add(egui::Label::new(job).truncate());
+                                            });
+                                        });
                                     });
                                     
-                                    row.col(|ui| {
-                                        let highlighted = highlight_matches(&snippet.preview, &self.search_query);
-                                        ui.label(highlighted);
+                                    if row.response().contains_pointer() {
+                                        self.hovered_row = Some(list_index);
+                                    }
+                                    
+                                    row.response().context_menu(|ui| {
+                                        if let Some(chosen) = row_context_menu(ui, snippet, snippet_index, appending, &mut self.build, &mut action) {
+                                            popup = Some((chosen, snippet_index));
+                                        }
                                     });
                                     
+                                    // A plain click on other rows copies and hides the window
+                                    // before a second click can arrive.
+                                    if row.response().double_clicked() {
+                                        popup = Some((RowPopup::Details, snippet_index));
+                                    }
+                                    
                                     if row.response().clicked() {
+                                        let modifiers = ctx.input(|i| i.modifiers);
+                                        if modifiers.command {
+                                            if !self.selection.remove(&snippet_index) {
+                                                self.selection.insert(snippet_index);
+                                            }
+                                        } else if modifiers.shift {
+                                            let (from, to) = if self.selected_index <= list_index {
+                                                (self.selected_index, list_index)
+                                            } else {
+                                                (list_index, self.selected_index)
+                                            };
+                                            let range = self.filtered_indices.get(from..=to).unwrap_or_default();
+                                            self.selection.extend(range.iter().copied());
+                                        } else if !snippet.meta.secret {
+                                            // Secrets only copy through an explicit action.
+                                            action = Some(GetWindowAction::Copy(snippet_index));
+                                        }
                                         self.selected_index = list_index;
-                                        selected_content = Some(snippet.content.clone());
                                     }
                                 }
                             }
                         }
                     );
                 });
+            
+            if let Some((chosen, snippet_index)) = popup {
+                self.open_popup(ctx, chosen, &snippets[snippet_index]);
+            }
+            // Widths are taken once a resize is let go of, not on every
+            // frame of the drag.
+            if ctx.input(|i| i.pointer.any_released()) {
+                let rounded = |width: Option<f32>| width.map(f32::round);
+                self.table.date_width = rounded(widths.0).or(self.table.date_width);
+                self.table.lang_width = rounded(widths.1).or(self.table.lang_width);
+            }
+            if resort {
+                self.resort();
+            }
         });
         
-        ctx.input_mut(|i| {
-            if i.key_pressed(egui::Key::ArrowUp) && self.selected_index > 0 {
-                self.selected_index -= 1;
+        action
+    }
+    
+    /// The search box with its history, date filter and window menu.
+    fn search_row(&mut self, ui: &mut egui::Ui, undo_label: Option<&str>, recent_searches: &[String]) -> Option<GetWindowAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            let search_response = ui.text_edit_singleline(&mut self.search_query);
+            if search_response.changed() {
+                self.history_cursor = None;
             }
-            if i.key_pressed(egui::Key::ArrowDown) && self.selected_index < self.filtered_indices.len().saturating_sub(1) {
-                self.selected_index += 1;
+            ui.add_enabled_ui(!recent_searches.is_empty(), |ui| {
+                ui.menu_button("🕘", |ui| {
+                    for query in recent_searches {
+                        if ui.button(query).clicked() {
+                            self.search_query = query.clone();
+                            self.history_cursor = None;
+                            self.focus_search = true;
+                            ui.close();
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("Recent searches");
+            });
+            let dated = self.search_query.contains("after:") || self.search_query.contains("before:");
+            if ui.selectable_label(self.date_filter_open || dated, "📅")
+                .on_hover_text("Filter by date created")
+                .clicked()
+            {
+                self.date_filter_open = !self.date_filter_open;
             }
-            if i.key_pressed(egui::Key::Enter) && !self.filtered_indices.is_empty() && self.selected_index < self.filtered_indices.len() {
-                let snippet_index = self.filtered_indices[self.selected_index];
-                if snippet_index < snippets.len() {
-                    selected_content = Some(snippets[snippet_index].content.clone());
+            if let Some(app) = &self.active_app {
+                let hint = if self.all_apps {
+                    "Showing snippets for every app; click to hide those meant for other apps"
+                } else {
+                    "Showing snippets for this app and for any app; click to show all"
+                };
+                if ui.selectable_label(!self.all_apps, format!("🗔 {}", app.name)).on_hover_text(hint).clicked() {
+                    self.all_apps = !self.all_apps;
                 }
             }
-            if i.key_pressed(egui::Key::Escape) {
-                selected_content = Some(String::new());
+            if ui.selectable_label(self.sticky, "Keep open")
+                .on_hover_text("Copy one snippet after another without the window closing; Escape closes it")
+                .clicked()
+            {
+                self.sticky = !self.sticky;
+            }
+            let text = SearchQuery::free_text(&self.search_query);
+            if !text.is_empty()
+                && ui.button("➕").on_hover_text("Create a snippet with this text").clicked()
+            {
+                action = Some(GetWindowAction::CreateFromQuery(text));
+            }
+            
+            // Vim starts out navigating; `/` moves into the search box.
+            if std::mem::take(&mut self.first_frame) && self.keymap.profile() == Profile::Standard {
+                self.focus_search = true;
+            }
+            if std::mem::take(&mut self.focus_search) {
+                search_response.request_focus();
             }
+            self.search_focused = search_response.has_focus();
+            
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if let Some(label) = undo_label {
+                    if ui.button("↶ Undo").on_hover_text(format!("Undo {}", label)).clicked() {
+                        action = Some(GetWindowAction::Undo);
+                    }
+                }
+                ui.menu_button("☰", |ui| {
+                    #[cfg(not(feature = "hotkeys"))]
+                    {
+                        if ui.button("New snippet…").clicked() {
+                            action = Some(GetWindowAction::NewSnippet);
+                            ui.close();
+                        }
+                        ui.separator();
+                    }
+                    if ui.button("Statistics").clicked() {
+                        action = Some(GetWindowAction::OpenStatistics);
+                        ui.close();
+                    }
+                    if ui.button("Find duplicates…").clicked() {
+                        action = Some(GetWindowAction::OpenDuplicates);
+                        ui.close();
+                    }
+                    if ui.button("Review unused…").clicked() {
+                        action = Some(GetWindowAction::OpenReview);
+                        ui.close();
+                    }
+                    if ui.button("Tags…").clicked() {
+                        action = Some(GetWindowAction::OpenTags);
+                        ui.close();
+                    }
+                    let exportable = self.filter.complete && !self.filtered_indices.is_empty();
+                    if ui.add_enabled(exportable, egui::Button::new("Export results as Markdown"))
+                        .on_hover_text("Save the snippets listed now to one Markdown file in Downloads")
+                        .clicked()
+                    {
+                        action = Some(GetWindowAction::ExportMarkdown(self.filtered_indices.clone()));
+                        ui.close();
+                    }
+                    if ui.button("Trash…").clicked() {
+                        action = Some(GetWindowAction::OpenTrash);
+                        ui.close();
+                    }
+                    if ui.button("Access log…").clicked() {
+                        action = Some(GetWindowAction::OpenAudit);
+                        ui.close();
+                    }
+                    if ui.button("Restore from a backup…").clicked() {
+                        action = Some(GetWindowAction::OpenRestore);
+                        ui.close();
+                    }
+                    if ui.button("Import from another app…").clicked() {
+                        action = Some(GetWindowAction::OpenImport);
+                        ui.close();
+                    }
+                    if ui.button("Import folder…").clicked() {
+                        action = Some(GetWindowAction::OpenFolderImport);
+                        ui.close();
+                    }
+                    if ui.button("Log…").clicked() {
+                        action = Some(GetWindowAction::OpenLog);
+                        ui.close();
+                    }
+                    #[cfg(feature = "vault")]
+                    {
+                        ui.separator();
+                        if ui.button("Export encrypted archive…").clicked() {
+                            action = Some(GetWindowAction::ExportArchive);
+                            ui.close();
+                        }
+                        if ui.button("Import archive…").clicked() {
+                            action = Some(GetWindowAction::ImportArchive);
+                            ui.close();
+                        }
+                        ui.separator();
+                    }
+                    #[cfg(feature = "sync")]
+                    if ui.button("Pair devices…").clicked() {
+                        action = Some(GetWindowAction::OpenPairing);
+                        ui.close();
+                    }
+                    #[cfg(feature = "sync")]
+                    if self.sync_status.is_some() && ui.button("Sync now").clicked() {
+                        action = Some(GetWindowAction::SyncNow);
+                        ui.close();
+                    }
+                    #[cfg(feature = "sync")]
+                    if self.sync_conflicts > 0 && ui.button(format!("Sync conflicts ({})…", self.sync_conflicts)).clicked() {
+                        action = Some(GetWindowAction::OpenConflicts);
+                        ui.close();
+                    }
+                    #[cfg(any(feature = "hotkeys", feature = "tray"))]
+                    {
+                        #[cfg_attr(not(feature = "hotkeys"), allow(unused_variables))]
+                        let snooze = ui.menu_button("Snooze hotkeys", |ui| {
+                            for &(label, minutes) in SNOOZE_CHOICES {
+                                if ui.button(label).clicked() {
+                                    action = Some(GetWindowAction::Snooze(minutes));
+                                    ui.close();
+                                }
+                            }
+                        });
+                        #[cfg(feature = "hotkeys")]
+                        snooze.response.on_hover_text(format!("{} snoozes for as long as set in the settings, or resumes early", SNOOZE_HINT));
+                    }
+                    if ui.button("Settings…").clicked() {
+                        action = Some(GetWindowAction::OpenSettings);
+                        ui.close();
+                    }
+                });
+            });
         });
         
-        selected_content
+        if self.date_filter_open {
+            show_date_filter(ui, &mut self.search_query);
+        }
+        action
     }
     
-    fn update_filtered_results(&mut self, snippets: &[Snippet]) {
-        if self.search_query.is_empty() {
-            self.filtered_indices = (0..snippets.len()).collect();
-        } else {
-            let query_lower = self.search_query.to_lowercase();
-            self.filtered_indices = snippets.iter()
-                .enumerate()
-                .filter_map(|(idx, snippet)| {
-                    let content_lower = snippet.content.to_lowercase();
-                    if content_lower.contains(&query_lower) {
-                        Some(idx)
-                    } else {
-                        None
+    /// Spotlight-style: the search box over a flat list of titles.
+    #[allow(clippy::too_many_arguments)]
+    fn show_launcher(
+        &mut self,
+        ctx: &egui::Context,
+        snippets: &[Snippet],
+        search: &mut dyn SearchProvider,
+        undo_label: Option<&str>,
+        recent_searches: &[String],
+        usage: &UsageLog,
+    ) -> Option<GetWindowAction> {
+        let mut action = None;
+        
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(chosen) = self.search_row(ui, undo_label, recent_searches) {
+                action = Some(chosen);
+            }
+            ui.separator();
+            
+            self.update_filtered_results(snippets, search, usage);
+            if !self.filter.complete {
+                ctx.request_repaint();
+            }
+            self.apply_pending_jump();
+            if self.filter.complete && self.filtered_indices.is_empty() {
+                if let Some(chosen) = self.empty_state(ui, snippets.is_empty()) {
+                    action = Some(chosen);
+                }
+                return;
+            }
+            
+            let row_height = self.density.row_height;
+            let step = row_height + ui.spacing().item_spacing.y;
+            let mut scroll = egui::ScrollArea::vertical().auto_shrink([false, false]);
+            if std::mem::take(&mut self.scroll_to_selected) {
+                // Scroll just far enough to bring the selected row into view.
+                let top = self.selected_index as f32 * step;
+                let visible = ui.available_height();
+                let offset = self.launcher_offset.max(top + step - visible).min(top);
+                scroll = scroll.vertical_scroll_offset(offset.max(0.0));
+            }
+            
+            let mut popup = None;
+            let sections = self.sections;
+            let output = scroll.show_rows(ui, row_height, self.filtered_indices.len(), |ui, rows| {
+                for list_index in rows {
+                    let Some(&snippet_index) = self.filtered_indices.get(list_index) else {
+                        continue;
+                    };
+                    let Some(snippet) = snippets.get(snippet_index) else {
+                        continue;
+                    };
+                    let (rect, response) = ui.allocate_exact_size(
+                        egui::vec2(ui.available_width(), row_height),
+                        egui::Sense::click(),
+                    );
+                    let visuals = ui.visuals();
+                    if list_index == self.selected_index {
+                        ui.painter().rect_filled(rect, 4.0, visuals.selection.bg_fill);
+                    } else if response.hovered() {
+                        ui.painter().rect_filled(rect, 4.0, visuals.widgets.hovered.bg_fill);
                     }
-                })
-                .collect();
+                    
+                    // The section label claims the right edge first, like the table's quick actions.
+                    let inner = egui::UiBuilder::new()
+                        .max_rect(rect.shrink2(egui::vec2(8.0, 0.0)))
+                        .layout(egui::Layout::right_to_left(egui::Align::Center));
+                    ui.scope_builder(inner, |ui| {
+                        if let Some(label) = section_label(sections, list_index) {
+                            ui.weak(label);
+                        }
+                        ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                            if snippet.meta.pinned {
+                                ui.label("📌");
+                            }
+                            if self.append_target.as_ref() == Some(&snippet.id) {
+                                ui.label("📝").on_hover_text(APPEND_HINT);
+                            }
+                            show_copied(ui, self.copied.get(&snippet.id));
+                            if snippet.meta.secret {
+                                ui.weak(format!("🔒 {}", SECRET_MASK));
+                            } else {
+                                let query = &self.filter.query;
+                                let preview_chars = self.density.preview_chars;
+                                let job = self.preview_cache
+                                    .entry(snippet_index)
+                                    .or_insert_with(|| {
+                                        highlight_matches(&clip(&snippet.title, preview_chars), query, &*search, ui.style())
+                                    })
+                                    .clone();
+                                ui.add(egui::Label::new(job).selectable(false).truncate());
+                            }
+                        });
+                    });
+                    
+                    response.context_menu(|ui| {
+                        let appending = self.append_target.as_ref() == Some(&snippet.id);
+                        if let Some(chosen) = row_context_menu(ui, snippet, snippet_index, appending, &mut self.build, &mut action) {
+                            popup = Some((chosen, snippet_index));
+                        }
+                    });
+                    if response.clicked() {
+                        self.selected_index = list_index;
+                        if !snippet.meta.secret {
+                            action = Some(GetWindowAction::Copy(snippet_index));
+                        }
+                    }
+                }
+            });
+            self.launcher_offset = output.state.offset.y;
+            
+            if let Some((chosen, snippet_index)) = popup {
+                self.open_popup(ctx, chosen, &snippets[snippet_index]);
+            }
+        });
+        
+        action
+    }
+    
+    /// Guidance in place of the list when there is nothing to show.
+    fn empty_state(&mut self, ui: &mut egui::Ui, library_empty: bool) -> Option<GetWindowAction> {
+        if let Some(folder) = self.selected_locked_folder() {
+            return self.unlock_prompt(ui, folder);
+        }
+        let mut action = None;
+        let text = SearchQuery::free_text(&self.search_query);
+        ui.vertical_centered(|ui| {
+            ui.add_space(24.0);
+            if library_empty {
+                ui.heading("No snippets yet");
+                // The add hotkey registered in main.rs.
+                #[cfg(feature = "hotkeys")]
+                ui.label(format!("Press {} anywhere to add selected text or type a new snippet.", ADD_HINT));
+                #[cfg(not(feature = "hotkeys"))]
+                ui.label("Choose New snippet… from the ☰ menu to add one.");
+            } else {
+                ui.heading("No matches");
+                if self.search_query.trim().is_empty() {
+                    ui.label("This folder has no snippets.");
+                } else {
+                    ui.label(format!("Nothing matches “{}”.", self.search_query.trim()));
+                }
+            }
+            ui.add_space(8.0);
+            if !text.is_empty() && ui.button(format!("Create a snippet with “{}”", clip(&text, 40))).clicked() {
+                action = Some(GetWindowAction::CreateFromQuery(text));
+            }
+            if !library_empty {
+                if self.selected_folder.is_some() && ui.button("Search all folders").clicked() {
+                    self.selected_folder = None;
+                }
+                if !self.search_query.is_empty() && ui.button("Clear search").clicked() {
+                    self.search_query.clear();
+                    self.focus_search = true;
+                }
+            }
+        });
+        action
+    }
+    
+    /// The locked folder holding the selected folder, if any.
+    fn selected_locked_folder(&self) -> Option<String> {
+        let selected = self.selected_folder.as_deref()?;
+        self.locked_folders.iter()
+            .filter(|locked| selected == locked.as_str() || selected.starts_with(&format!("{}/", locked)))
+            .max_by_key(|locked| locked.len())
+            .cloned()
+    }
+    
+    fn unlock_prompt(&mut self, ui: &mut egui::Ui, folder: String) -> Option<GetWindowAction> {
+        let mut unlock = false;
+        ui.vertical_centered(|ui| {
+            ui.add_space(24.0);
+            ui.heading("🔒 Encrypted folder");
+            ui.label(format!("Enter the passphrase for “{}” to show its snippets.", folder));
+            ui.add_space(8.0);
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.passphrase)
+                    .password(true)
+                    .hint_text("Passphrase")
+                    .desired_width(200.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            unlock = (ui.button("Unlock").clicked() || submitted) && !self.passphrase.is_empty();
+        });
+        unlock.then(|| GetWindowAction::UnlockFolder(folder, std::mem::take(&mut self.passphrase)))
+    }
+    
+    fn open_popup(&mut self, ctx: &egui::Context, popup: RowPopup, snippet: &Snippet) {
+        match popup {
+            RowPopup::Qr => self.qr = Some(QrPopup::new(ctx, snippet.safe_title(), &snippet.content)),
+            RowPopup::Details => self.detail_id = Some(snippet.id.clone()),
+        }
+    }
+    
+    fn update_filtered_results(&mut self, snippets: &[Snippet], search: &mut dyn SearchProvider, usage: &UsageLog) {
+        if self.index_stale {
+            search.index(snippets);
+            self.index_stale = false;
+        }
+
+        let mut query = SearchQuery::parse(&self.search_query);
+        query.folder = self.selected_folder.clone();
+        query.app = self.active_app.as_ref()
+            .filter(|_| !self.all_apps)
+            .map(|app| app.name.to_lowercase());
+        if query != self.filter.query {
+            // The default view leaves snippets out, so a search can't start from it.
+            let narrows = self.filter.complete && self.sections.is_none() && query.narrows(&self.filter.query);
+            let candidates = narrows.then(|| std::mem::take(&mut self.filtered_indices));
+            
+            self.filter = FilterProgress {
+                query,
+                candidates,
+                cursor: 0,
+                complete: false,
+                scores: Vec::new(),
+            };
+            self.filtered_indices.clear();
+            self.preview_cache.clear();
+            self.sections = None;
         }
         
-        if self.selected_index >= self.filtered_indices.len() {
+        if self.filter.complete {
+            return;
+        }
+        
+        let started = Instant::now();
+        let total = self.filter.candidates.as_ref().map_or(snippets.len(), Vec::len);
+        while self.filter.cursor < total {
+            let idx = match &self.filter.candidates {
+                Some(candidates) => candidates[self.filter.cursor],
+                None => self.filter.cursor,
+            };
+            self.filter.cursor += 1;
+            
+            if let Some(snippet) = snippets.get(idx) {
+                let score = if self.filter.query.is_empty() {
+                    // Only archived snippets are left out.
+                    self.filter.query.admits(snippet).then_some(0.0)
+                } else {
+                    search.score(snippet, &self.filter.query)
+                };
+                if let Some(score) = score {
+                    self.filtered_indices.push(idx);
+                    self.filter.scores.push(score);
+                }
+            }
+            
+            if self.filter.cursor.is_multiple_of(FILTER_CHUNK)
+                && self.filter_budget.is_some_and(|budget| started.elapsed() >= budget)
+            {
+                break;
+            }
+        }
+        
+        if self.filter.cursor >= total {
+            self.filter.complete = true;
+            self.filter.candidates = None;
+            // A stable sort, so snippets the provider scores alike keep
+            // their pinned-then-newest order.
+            let scores = std::mem::take(&mut self.filter.scores);
+            let mut ranked: Vec<(usize, f32)> = self.filtered_indices.iter().copied().zip(scores).collect();
+            ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+            self.filtered_indices = ranked.into_iter().map(|(idx, _)| idx).collect();
+            
+            if let Some(column) = self.table.sort.filter(|_| self.layout == ListLayout::Table) {
+                sort_by_column(&mut self.filtered_indices, snippets, column, self.table.descending);
+            } else if self.search_query.trim().is_empty() && self.selected_folder.is_none() {
+                if let Some((view, pinned)) = default_view(&self.filtered_indices, snippets, usage) {
+                    self.filtered_indices = view;
+                    self.sections = Some(pinned);
+                }
+            }
+        }
+        
+        if self.selected_index >= self.filtered_indices.len() && self.filter.complete {
             self.selected_index = self.filtered_indices.len().saturating_sub(1);
         }
     }
     
+    pub fn set_locked_folders(&mut self, folders: Vec<String>) {
+        self.locked_folders = folders;
+    }
+    
+    #[cfg(feature = "sync")]
+    pub fn set_sync_conflicts(&mut self, count: usize) {
+        self.sync_conflicts = count;
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn set_sync_status(&mut self, status: Option<SyncStatus>) {
+        self.sync_status = status;
+    }
+    
+    pub fn reveal(&mut self, id: String) {
+        self.revealed_id = Some(id);
+    }
+    
+    pub fn hide_revealed(&mut self) {
+        self.revealed_id = None;
+    }
+    
+    /// Records the Reveal toggle of the shown snippet. Revealing is left to
+    /// the app, which may ask the OS to confirm the user first.
+    fn set_revealed(&mut self, id: &str, revealed: bool) -> Option<GetWindowAction> {
+        if revealed && self.revealed_id.as_deref() != Some(id) {
+            return Some(GetWindowAction::RevealSecret(id.to_string()));
+        }
+        self.revealed_id = revealed.then(|| id.to_string());
+        None
+    }
+    
+    /// Selects a snippet by its index in the list passed to `show`, once
+    /// the filter pass reaches it.
+    pub fn select_snippet(&mut self, snippet_index: usize) {
+        self.pending_jump = Some(snippet_index);
+    }
+    
+    fn jump_to_link(&mut self, target: &str, snippets: &[Snippet]) {
+        match resolve_link(target, snippets) {
+            Some(snippet_index) => {
+                // The target may be filtered out; show everything and select
+                // it once the filter pass gets there.
+                if !self.filtered_indices.contains(&snippet_index) {
+                    self.search_query.clear();
+                }
+                self.pending_jump = Some(snippet_index);
+            }
+            None => log::info!("No snippet matches link [[{}]]", target),
+        }
+    }
+    
+    fn apply_pending_jump(&mut self) {
+        let Some(snippet_index) = self.pending_jump else {
+            return;
+        };
+        if let Some(position) = self.filtered_indices.iter().position(|&i| i == snippet_index) {
+            self.selected_index = position;
+            self.scroll_to_selected = true;
+            self.pending_jump = None;
+        } else if self.filter.complete {
+            self.pending_jump = None;
+        }
+    }
+    
+    /// Drops cached results after the snippet list changed underneath us.
+    pub fn invalidate(&mut self) {
+        self.filter = FilterProgress::default();
+        self.index_stale = true;
+        self.selection.clear();
+        self.filtered_indices.clear();
+        self.preview_cache.clear();
+    }
+    
     pub fn reset(&mut self) {
         self.first_frame = true;
         self.search_query.clear();
+        self.history_cursor = None;
         self.selected_index = 0;
-        self.filtered_indices.clear();
+        self.qr = None;
+        self.detail_id = None;
+        self.keymap.reset();
+        self.sticky = false;
+        self.copied.clear();
+        self.invalidate();
+    }
+}
+
+/// How many recently used snippets the default view lists.
+const RECENT_ROWS: usize = 15;
+
+/// The default view for an empty search box: the pinned snippets among
+/// `indices` in their order, then the most recently used others. Returns
+/// the list and how many of it are pinned, or `None` when nothing is
+/// pinned or used yet, which leaves the plain list.
+fn default_view(indices: &[usize], snippets: &[Snippet], usage: &UsageLog) -> Option<(Vec<usize>, usize)> {
+    let mut view: Vec<usize> = indices.iter().copied().filter(|&i| snippets[i].meta.pinned).collect();
+    let pinned = view.len();
+    let mut recent: Vec<(usize, DateTime<Utc>)> = indices.iter()
+        .copied()
+        .filter(|&i| !snippets[i].meta.pinned)
+        .filter_map(|i| usage.get(&snippets[i].id).map(|used| (i, used.last_used)))
+        .collect();
+    recent.sort_by_key(|&(_, used)| std::cmp::Reverse(used));
+    view.extend(recent.into_iter().take(RECENT_ROWS).map(|(i, _)| i));
+    (!view.is_empty()).then_some((view, pinned))
+}
+
+/// Orders `indices` by `column`, keeping the search's order among equals.
+fn sort_by_column(indices: &mut [usize], snippets: &[Snippet], column: SortColumn, descending: bool) {
+    let compare = |a: &Snippet, b: &Snippet| match column {
+        SortColumn::Date => a.created.cmp(&b.created),
+        SortColumn::Lang => a.meta.language.map(|language| language.name()).cmp(&b.meta.language.map(|language| language.name())),
+        SortColumn::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+    };
+    indices.sort_by(|&a, &b| {
+        let ordering = compare(&snippets[a], &snippets[b]);
+        if descending { ordering.reverse() } else { ordering }
+    });
+}
+
+/// A column sized to its content, or as the user last resized it.
+fn sized_column(width: Option<f32>, at_least: f32) -> Column {
+    width.map_or_else(Column::auto, Column::initial).at_least(at_least)
+}
+
+/// A column heading that sorts by the column when clicked, and the other
+/// way round when clicked again. Its context menu picks the columns shown
+/// and goes back to the search's order. True when the order changed.
+fn sort_header(ui: &mut egui::Ui, label: &str, column: SortColumn, layout: &mut TableLayout) -> bool {
+    let arrow = match layout.sort {
+        Some(sorted) if sorted == column && layout.descending => " ⏷",
+        Some(sorted) if sorted == column => " ⏶",
+        _ => "",
+    };
+    let response = ui.add(egui::Label::new(egui::RichText::new(format!("{}{}", label, arrow)).strong()).sense(egui::Sense::click()))
+        .on_hover_text("Click to sort, right-click for columns");
+    let mut resort = false;
+    if response.clicked() {
+        if layout.sort == Some(column) {
+            layout.descending = !layout.descending;
+        } else {
+            layout.sort = Some(column);
+            // Newest first is the useful way round for dates.
+            layout.descending = column == SortColumn::Date;
+        }
+        resort = true;
+    }
+    response.context_menu(|ui| {
+        ui.checkbox(&mut layout.show_date, "Date column");
+        ui.checkbox(&mut layout.show_lang, "Lang column");
+        ui.separator();
+        if ui.add_enabled(layout.sort.is_some(), egui::Button::new("Search order")).clicked() {
+            layout.sort = None;
+            resort = true;
+            ui.close();
+        }
+    });
+    resort
+}
+
+/// The pinned, locked and appended-to marks of a row.
+fn row_badges(ui: &mut egui::Ui, snippet: &Snippet, appending: bool) {
+    if snippet.meta.pinned {
+        ui.label("📌").on_hover_text("Pinned");
+    }
+    if snippet.meta.locked {
+        ui.label("🔒").on_hover_text("Locked");
+    }
+    if appending {
+        ui.label("📝").on_hover_text(APPEND_HINT);
+    }
+}
+
+/// The heading of the default view's section starting at this row.
+fn section_label(sections: Option<usize>, list_index: usize) -> Option<&'static str> {
+    let pinned = sections?;
+    match list_index {
+        0 if pinned > 0 => Some("Pinned"),
+        i if i == pinned => Some("Recently used"),
+        _ => None,
+    }
+}
+
+/// "copied ✓" on a row copied in the last moments of a sticky session.
+fn show_copied(ui: &mut egui::Ui, copied: Option<&Instant>) {
+    let Some(left) = copied.map(|at| COPIED_FLASH.saturating_sub(at.elapsed())).filter(|left| !left.is_zero()) else {
+        return;
+    };
+    ui.label(egui::RichText::new("copied ✓").small().strong().color(egui::Color32::from_rgb(0x3c, 0xa0, 0x50)));
+    ui.ctx().request_repaint_after(left);
+}
+
+/// Small icon buttons shown on the hovered row, laid out right to left.
+fn quick_actions(ui: &mut egui::Ui, snippet: &Snippet, snippet_index: usize) -> Option<GetWindowAction> {
+    let locked = snippet.meta.locked;
+    let mut chosen = None;
+    
+    if ui.add_enabled(!locked, egui::Button::new("🗑").small())
+        .on_hover_text("Delete")
+        .on_disabled_hover_text("Unlock the snippet to delete it")
+        .clicked()
+    {
+        chosen = Some(GetWindowAction::Delete(snippet_index));
+    }
+    let pin_hint = if snippet.meta.pinned { "Unpin" } else { "Pin" };
+    if ui.small_button("📌").on_hover_text(pin_hint).clicked() {
+        chosen = Some(GetWindowAction::Bulk(vec![snippet_index], BulkOp::SetPinned(!snippet.meta.pinned)));
+    }
+    if ui.add_enabled(!locked, egui::Button::new("✏").small())
+        .on_hover_text("Edit")
+        .on_disabled_hover_text("Unlock the snippet to edit it")
+        .clicked()
+    {
+        chosen = Some(GetWindowAction::Edit(snippet_index));
+    }
+    if ui.small_button("📋").on_hover_text("Copy").clicked() {
+        chosen = Some(GetWindowAction::Copy(snippet_index));
+    }
+    chosen
+}
+
+/// Popups opened from a row that the window shows itself.
+enum RowPopup {
+    Qr,
+    Details,
+}
+
+fn row_context_menu(
+    ui: &mut egui::Ui,
+    snippet: &Snippet,
+    snippet_index: usize,
+    appending: bool,
+    build: &mut BuildList,
+    action: &mut Option<GetWindowAction>,
+) -> Option<RowPopup> {
+    let mut popup = None;
+    let locked = snippet.meta.locked;
+    let mut chosen = None;
+    
+    if ui.button("Copy").clicked() {
+        chosen = Some(GetWindowAction::Copy(snippet_index));
+    }
+    if ui.button("Add to build (Ctrl+B)").clicked() {
+        build.push(&snippet.id);
+        ui.close();
+    }
+    if ui.button("Details…").clicked() {
+        popup = Some(RowPopup::Details);
+        ui.close();
+    }
+    if ui.button("Show as QR").clicked() {
+        popup = Some(RowPopup::Qr);
+        ui.close();
+    }
+    ui.menu_button("Copy as", |ui| {
+        for transform in Transform::ALL {
+            if ui.button(transform.label()).clicked() {
+                chosen = Some(GetWindowAction::CopyTransformed(snippet_index, transform.into()));
+            }
+        }
+    });
+    let edit = ui.add_enabled(!locked, egui::Button::new("Edit"));
+    if edit.on_disabled_hover_text("Unlock the snippet to edit it").clicked() {
+        chosen = Some(GetWindowAction::Edit(snippet_index));
+    }
+    if ui.button("Duplicate").on_hover_text("Save a copy and edit it").clicked() {
+        chosen = Some(GetWindowAction::Duplicate(snippet_index));
+    }
+    let delete = ui.add_enabled(!locked, egui::Button::new("Delete"));
+    if delete.on_disabled_hover_text("Unlock the snippet to delete it").clicked() {
+        chosen = Some(GetWindowAction::Delete(snippet_index));
+    }
+    if ui.button("History…").clicked() {
+        chosen = Some(GetWindowAction::ShowHistory(snippet_index));
     }
+    if appending {
+        if ui.button("Stop appending here").clicked() {
+            chosen = Some(GetWindowAction::SetAppendTarget(None));
+        }
+    } else {
+        let append = ui.add_enabled(!locked, egui::Button::new("Append captures here"));
+        if append.on_hover_text(APPEND_HINT).on_disabled_hover_text("Unlock the snippet to append to it").clicked() {
+            chosen = Some(GetWindowAction::SetAppendTarget(Some(snippet_index)));
+        }
+    }
+    if snippet.meta.pinned {
+        ui.separator();
+        if ui.button("Move up").clicked() {
+            chosen = Some(GetWindowAction::MovePinned(snippet_index, true));
+        }
+        if ui.button("Move down").clicked() {
+            chosen = Some(GetWindowAction::MovePinned(snippet_index, false));
+        }
+    }
+    ui.separator();
+    if ui.button(if locked { "🔓 Unlock" } else { "🔒 Lock" }).clicked() {
+        chosen = Some(GetWindowAction::ToggleLock(snippet_index));
+    }
+    let archive = if snippet.meta.archived { "Unarchive" } else { "🗄 Archive" };
+    if ui.button(archive).on_hover_text("Archived snippets are only found with is:archived").clicked() {
+        chosen = Some(GetWindowAction::ToggleArchived(snippet_index));
+    }
+    
+    if chosen.is_some() {
+        *action = chosen;
+        ui.close();
+    }
+    popup
 }
 
 fn format_timestamp(time: std::time::SystemTime) -> String {
@@ -149,10 +1619,203 @@ fn format_timestamp(time: std::time::SystemTime) -> String {
     datetime.format("%m/%d %H:%M").to_string()
 }
 
-fn highlight_matches(text: &str, query: &str) -> String {
-    if query.is_empty() {
-        return text.to_string();
-    }
+fn highlight_matches(text: &str, query: &SearchQuery, search: &dyn SearchProvider, style: &egui::Style) -> LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(style);
+    let normal = TextFormat::simple(font_id.clone(), style.visuals.text_color());
+    let highlight = TextFormat {
+        background: style.visuals.selection.bg_fill,
+        color: style.visuals.strong_text_color(),
+        ..TextFormat::simple(font_id, style.visuals.text_color())
+    };
     
-    text.to_string()
-}
\ No newline at end of file
+    let mut job = LayoutJob::default();
+    // Reordered RTL text no longer lines up with the match offsets.
+    if bidi::has_rtl(text) {
+        job.append(&bidi::visual(text), 0.0, normal);
+        return job;
+    }
+    let mut last = 0;
+    for span in search.highlight(text, query) {
+        job.append(&text[last..span.start], 0.0, normal.clone());
+        job.append(&text[span.clone()], 0.0, highlight.clone());
+        last = span.end;
+    }
+    job.append(&text[last..], 0.0, normal);
+    job
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{Key, Modifiers};
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::storage::search_provider::{self, SearchProviderKind};
+    use crate::ui::harness::{snippet, Harness};
+
+    struct Fixture {
+        window: GetWindowState,
+        snippets: Vec<Snippet>,
+        search: Box<dyn SearchProvider>,
+        recent: Vec<String>,
+        usage: UsageLog,
+        harness: Harness,
+    }
+
+    impl Fixture {
+        fn new(layout: ListLayout, kinds: &[SearchProviderKind]) -> Self {
+            let mut fixture = Self {
+                window: GetWindowState {
+                    filter_budget: None,
+                    ..GetWindowState::new(Keymap::default(), ListDensity::default(), layout)
+                },
+                snippets: vec![
+                    snippet("a", "git checkout main", 3),
+                    snippet("b", "docker ps --all", 2),
+                    snippet("c", "git status --short", 1),
+                ],
+                search: search_provider::build(kinds),
+                recent: Vec::new(),
+                // Only read here, so the file is never created.
+                usage: UsageLog::empty(PathBuf::from("usage-log")),
+                harness: Harness::new(),
+            };
+            // The search box takes the focus in the first frame.
+            assert!(fixture.frame().is_none());
+            fixture
+        }
+
+        fn frame(&mut self) -> Option<GetWindowAction> {
+            let Self { window, snippets, search, recent, usage, harness } = self;
+            harness.run(|ctx| window.show(ctx, snippets, search.as_mut(), &[], None, recent, usage))
+        }
+
+        fn type_text(&mut self, text: &str) -> Option<GetWindowAction> {
+            self.harness.type_text(text);
+            self.frame()
+        }
+
+        fn press(&mut self, key: Key) -> Option<GetWindowAction> {
+            self.harness.press(key, Modifiers::NONE);
+            self.frame()
+        }
+
+        fn titles(&self) -> Vec<&str> {
+            self.window.filtered_indices.iter().map(|&i| self.snippets[i].title.as_str()).collect()
+        }
+    }
+
+    fn copied(action: Option<GetWindowAction>) -> Option<usize> {
+        match action {
+            Some(GetWindowAction::Copy(index)) => Some(index),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn typing_filters_the_list() {
+        let mut fixture = Fixture::new(ListLayout::Table, &[SearchProviderKind::Substring]);
+        assert_eq!(fixture.titles().len(), 3);
+
+        fixture.type_text("git");
+        assert_eq!(fixture.window.search_query(), "git");
+        assert_eq!(fixture.titles(), ["git checkout main", "git status --short"]);
+    }
+
+    #[test]
+    fn empty_search_shows_pinned_snippets_first() {
+        let mut fixture = Fixture::new(ListLayout::Launcher, &[SearchProviderKind::Substring]);
+        fixture.snippets[2].meta.pinned = true;
+        fixture.window.invalidate();
+        fixture.frame();
+        assert_eq!(fixture.titles(), ["git status --short"]);
+        assert_eq!(fixture.window.sections, Some(1));
+
+        // Searching goes back to the whole library.
+        fixture.type_text("git");
+        assert_eq!(fixture.titles(), ["git checkout main", "git status --short"]);
+        assert_eq!(fixture.window.sections, None);
+    }
+
+    #[test]
+    fn archived_snippets_only_show_up_when_asked_for() {
+        let mut fixture = Fixture::new(ListLayout::Launcher, &[SearchProviderKind::Substring]);
+        fixture.snippets[1].meta.archived = true;
+        fixture.window.invalidate();
+        fixture.frame();
+        assert_eq!(fixture.titles(), ["git checkout main", "git status --short"]);
+
+        fixture.type_text("is:archived");
+        assert_eq!(fixture.titles(), ["docker ps --all"]);
+    }
+
+    #[test]
+    fn table_keeps_its_sort_order_through_searches() {
+        let mut fixture = Fixture::new(ListLayout::Table, &[SearchProviderKind::Substring]);
+        fixture.window.set_table_layout(TableLayout { sort: Some(SortColumn::Title), descending: true, ..TableLayout::default() });
+        fixture.frame();
+        assert_eq!(fixture.titles(), ["git status --short", "git checkout main", "docker ps --all"]);
+
+        fixture.type_text("git");
+        assert_eq!(fixture.titles(), ["git status --short", "git checkout main"]);
+    }
+
+    #[test]
+    fn enter_copies_the_selected_snippet() {
+        let mut fixture = Fixture::new(ListLayout::Table, &[SearchProviderKind::Substring]);
+        fixture.type_text("docker");
+        assert_eq!(copied(fixture.press(Key::Enter)), Some(1));
+    }
+
+    #[test]
+    fn arrows_move_the_selection() {
+        let mut fixture = Fixture::new(ListLayout::Launcher, &[SearchProviderKind::Substring]);
+        fixture.type_text("git");
+        fixture.press(Key::ArrowDown);
+        assert_eq!(copied(fixture.press(Key::Enter)), Some(2));
+
+        // The selection stops at the ends of the list.
+        fixture.press(Key::ArrowDown);
+        fixture.press(Key::ArrowUp);
+        fixture.press(Key::ArrowUp);
+        assert_eq!(copied(fixture.press(Key::Enter)), Some(0));
+    }
+
+    #[test]
+    fn no_match_copies_nothing() {
+        let mut fixture = Fixture::new(ListLayout::Table, &[SearchProviderKind::Substring]);
+        fixture.type_text("kubectl");
+        assert!(fixture.titles().is_empty());
+        assert!(copied(fixture.press(Key::Enter)).is_none());
+    }
+
+    #[test]
+    fn fuzzy_search_matches_initials() {
+        let mut fixture = Fixture::new(ListLayout::Table, &[SearchProviderKind::Substring, SearchProviderKind::Fuzzy]);
+        fixture.type_text("gst");
+        assert_eq!(fixture.titles(), ["git status --short"]);
+        assert_eq!(copied(fixture.press(Key::Enter)), Some(2));
+    }
+
+    #[test]
+    fn up_in_an_empty_search_recalls_earlier_searches() {
+        let mut fixture = Fixture::new(ListLayout::Table, &[SearchProviderKind::Substring]);
+        fixture.recent = vec!["docker".to_string(), "git".to_string()];
+
+        fixture.press(Key::ArrowUp);
+        assert_eq!(fixture.window.search_query(), "docker");
+        fixture.press(Key::ArrowUp);
+        assert_eq!(fixture.window.search_query(), "git");
+        fixture.press(Key::ArrowDown);
+        assert_eq!(fixture.window.search_query(), "docker");
+        // The list catches up with a recalled query on the next frame.
+        fixture.frame();
+        assert_eq!(fixture.titles(), ["docker ps --all"]);
+    }
+
+    #[test]
+    fn escape_closes_the_window() {
+        let mut fixture = Fixture::new(ListLayout::Table, &[SearchProviderKind::Substring]);
+        assert!(matches!(fixture.press(Key::Escape), Some(GetWindowAction::Close)));
+    }
+}
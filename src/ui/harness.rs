@@ -0,0 +1,93 @@
+//! Drives the windows frame by frame with synthetic input, without a
+//! window or GPU, so search, keyboard and copy flows can be tested. Time
+//! advances a fixed step per frame and the screen has a fixed size, so a
+//! run gives the same result every time.
+
+use egui::{Context, Event, Key, Modifiers, Pos2, RawInput, Rect, Vec2};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::storage::title::display_title;
+use crate::storage::{Snippet, SnippetMeta};
+
+const SCREEN: Vec2 = Vec2::new(800.0, 600.0);
+const FRAME_TIME: f64 = 1.0 / 60.0;
+
+pub struct Harness {
+    ctx: Context,
+    events: Vec<Event>,
+    frame: u64,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        Self {
+            ctx: Context::default(),
+            events: Vec::new(),
+            frame: 0,
+        }
+    }
+
+    /// Queues typed text for the next frame.
+    pub fn type_text(&mut self, text: &str) {
+        self.events.push(Event::Text(text.to_string()));
+    }
+
+    /// Queues a press and release of `key` for the next frame. Ctrl counts
+    /// as the command key too, as it does off macOS.
+    pub fn press(&mut self, key: Key, mut modifiers: Modifiers) {
+        if modifiers.ctrl {
+            modifiers.command = true;
+        }
+        for pressed in [true, false] {
+            self.events.push(Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat: false,
+                modifiers,
+            });
+        }
+    }
+
+    /// Runs one frame with the input queued since the last one, returning
+    /// what `show` returned. When egui runs a second pass in the frame, the
+    /// first answer is kept.
+    pub fn run<T>(&mut self, mut show: impl FnMut(&Context) -> Option<T>) -> Option<T> {
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, SCREEN)),
+            time: Some(self.frame as f64 * FRAME_TIME),
+            predicted_dt: FRAME_TIME as f32,
+            focused: true,
+            events: std::mem::take(&mut self.events),
+            ..RawInput::default()
+        };
+        self.frame += 1;
+        let mut output = None;
+        // Nothing is painted, so the shapes and textures are dropped.
+        let _ = self.ctx.run(input, |ctx| {
+            let shown = show(ctx);
+            if output.is_none() {
+                output = shown;
+            }
+        });
+        output
+    }
+}
+
+/// A snippet in the root folder, created `secs` seconds after the epoch so
+/// its place in a sorted list is fixed.
+pub fn snippet(id: &str, content: &str, secs: u64) -> Snippet {
+    let meta = SnippetMeta::default();
+    let created = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+    Snippet {
+        id: id.to_string(),
+        content: content.to_string(),
+        title: display_title(content, &meta),
+        created,
+        modified: created,
+        file_path: PathBuf::from(format!("{}.txt", id)),
+        folder: String::new(),
+        meta,
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Local, Utc};
+use egui;
+use similar::{ChangeTag, TextDiff};
+
+use crate::storage::{Snippet, Version};
+
+pub enum HistoryAction {
+    /// Replace the snippet's content with this earlier version.
+    Restore(String),
+    Back,
+}
+
+struct LoadedVersion {
+    label: String,
+    content: String,
+}
+
+/// Lists a snippet's versions and diffs any two of them. Index 0 is always
+/// the current content.
+pub struct HistoryWindowState {
+    snippet_id: String,
+    title: String,
+    locked: bool,
+    versions: Vec<LoadedVersion>,
+    old: usize,
+    new: usize,
+    side_by_side: bool,
+}
+
+impl HistoryWindowState {
+    /// `versions` are the earlier versions that could be read, with their
+    /// content.
+    pub fn new(snippet: &Snippet, versions: Vec<(Version, String)>) -> Self {
+        let mut loaded = vec![LoadedVersion {
+            label: "Current".to_string(),
+            content: snippet.content.clone(),
+        }];
+        loaded.extend(versions.into_iter().map(|(version, content)| LoadedVersion {
+            label: format_saved(version.saved),
+            content,
+        }));
+
+        Self {
+            snippet_id: snippet.id.clone(),
+            title: snippet.safe_title().to_string(),
+            locked: snippet.meta.locked,
+            old: loaded.len().min(2) - 1,
+            new: 0,
+            versions: loaded,
+            side_by_side: false,
+        }
+    }
+
+    pub fn snippet_id(&self) -> &str {
+        &self.snippet_id
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<HistoryAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(HistoryAction::Back);
+                }
+                ui.heading("History");
+                ui.weak(&self.title);
+            });
+            ui.separator();
+
+            if self.versions.len() < 2 {
+                ui.weak("No earlier versions. A version is kept each time the snippet is edited.");
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                version_combo(ui, "old_version", "From", &self.versions, &mut self.old);
+                version_combo(ui, "new_version", "To", &self.versions, &mut self.new);
+                ui.checkbox(&mut self.side_by_side, "Side by side");
+            });
+            ui.horizontal(|ui| {
+                for index in [self.old, self.new] {
+                    if index == 0 {
+                        continue;
+                    }
+                    let label = format!("Restore {}", self.versions[index].label);
+                    let restore = ui.add_enabled(!self.locked, egui::Button::new(label));
+                    if restore.on_disabled_hover_text("Unlock the snippet to restore a version").clicked() {
+                        action = Some(HistoryAction::Restore(self.versions[index].content.clone()));
+                    }
+                }
+            });
+            ui.separator();
+
+            let old = &self.versions[self.old].content;
+            let new = &self.versions[self.new].content;
+            egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+                if self.side_by_side {
+                    show_side_by_side(ui, old, new);
+                } else {
+                    show_inline(ui, old, new);
+                }
+            });
+        });
+
+        action
+    }
+}
+
+fn version_combo(ui: &mut egui::Ui, id: &str, label: &str, versions: &[LoadedVersion], selected: &mut usize) {
+    ui.label(label);
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(&versions[*selected].label)
+        .show_ui(ui, |ui| {
+            for (index, version) in versions.iter().enumerate() {
+                ui.selectable_value(selected, index, &version.label);
+            }
+        });
+}
+
+pub fn show_inline(ui: &mut egui::Ui, old: &str, new: &str) {
+    let diff = TextDiff::from_lines(old, new);
+    for change in diff.iter_all_changes() {
+        let line = change.value().trim_end_matches('\n');
+        match change.tag() {
+            ChangeTag::Equal => diff_line(ui, "  ", line, None),
+            ChangeTag::Delete => diff_line(ui, "- ", line, Some(removed_color(ui))),
+            ChangeTag::Insert => diff_line(ui, "+ ", line, Some(added_color(ui))),
+        }
+    }
+}
+
+pub fn show_side_by_side(ui: &mut egui::Ui, old: &str, new: &str) {
+    let diff = TextDiff::from_lines(old, new);
+    egui::Grid::new("side_by_side_diff").num_columns(2).striped(false).show(ui, |ui| {
+        for change in diff.iter_all_changes() {
+            let line = change.value().trim_end_matches('\n');
+            match change.tag() {
+                ChangeTag::Equal => {
+                    diff_line(ui, "", line, None);
+                    diff_line(ui, "", line, None);
+                }
+                ChangeTag::Delete => {
+                    diff_line(ui, "", line, Some(removed_color(ui)));
+                    ui.label("");
+                }
+                ChangeTag::Insert => {
+                    ui.label("");
+                    diff_line(ui, "", line, Some(added_color(ui)));
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
+
+fn diff_line(ui: &mut egui::Ui, prefix: &str, line: &str, background: Option<egui::Color32>) {
+    let mut text = egui::RichText::new(format!("{}{}", prefix, line)).monospace();
+    if let Some(background) = background {
+        text = text.background_color(background);
+    }
+    ui.label(text);
+}
+
+fn added_color(ui: &egui::Ui) -> egui::Color32 {
+    if ui.visuals().dark_mode {
+        egui::Color32::from_rgb(0x1f, 0x4d, 0x2a)
+    } else {
+        egui::Color32::from_rgb(0xd4, 0xf5, 0xdc)
+    }
+}
+
+fn removed_color(ui: &egui::Ui) -> egui::Color32 {
+    if ui.visuals().dark_mode {
+        egui::Color32::from_rgb(0x5c, 0x22, 0x22)
+    } else {
+        egui::Color32::from_rgb(0xfa, 0xdb, 0xdb)
+    }
+}
+
+fn format_saved(saved: DateTime<Utc>) -> String {
+    saved.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+}
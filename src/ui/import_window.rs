@@ -0,0 +1,90 @@
+use egui;
+use std::path::PathBuf;
+
+use crate::import::ImportSource;
+
+pub enum ImportAction {
+    Import(ImportSource, PathBuf),
+    Back,
+}
+
+/// Picks another clipboard or snippet manager to bring snippets over from,
+/// and where its data is.
+pub struct ImportWindowState {
+    source: ImportSource,
+    path: String,
+    /// Set while an import runs, so it isn't started twice.
+    importing: bool,
+}
+
+impl ImportWindowState {
+    pub fn new() -> Self {
+        let mut state = Self { source: ImportSource::ALL[0], path: String::new(), importing: false };
+        state.select(state.source);
+        state
+    }
+
+    pub fn set_importing(&mut self, importing: bool) {
+        self.importing = importing;
+    }
+
+    /// Switches to `source`, with the path filled in where it usually is.
+    fn select(&mut self, source: ImportSource) {
+        self.source = source;
+        self.path = source.default_path().map(|path| path.display().to_string()).unwrap_or_default();
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<ImportAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(ImportAction::Back);
+                }
+                ui.heading("Import from another app");
+            });
+            ui.weak("Tags, pins and notes carry over where the app has them. Text already in the library is skipped.");
+            ui.separator();
+
+            egui::Grid::new("import_grid").num_columns(2).spacing([12.0, 8.0]).show(ui, |ui| {
+                ui.label("From:");
+                let mut source = self.source;
+                egui::ComboBox::from_id_salt("import_source")
+                    .selected_text(source.label())
+                    .show_ui(ui, |ui| {
+                        for &option in ImportSource::ALL {
+                            ui.selectable_value(&mut source, option, option.label());
+                        }
+                    });
+                if source != self.source {
+                    self.select(source);
+                }
+                ui.end_row();
+
+                if self.source.reads_path() {
+                    ui.label("Path:");
+                    ui.add(egui::TextEdit::singleline(&mut self.path).hint_text("File or folder to read").desired_width(320.0));
+                    ui.end_row();
+                }
+            });
+            if !self.source.reads_path() {
+                ui.weak("Read from the running CopyQ through its copyq command.");
+            }
+            ui.add_space(8.0);
+
+            let ready = !self.importing && (!self.source.reads_path() || !self.path.trim().is_empty());
+            ui.horizontal(|ui| {
+                if ui.add_enabled(ready, egui::Button::new("Import")).clicked() {
+                    action = Some(ImportAction::Import(self.source, PathBuf::from(self.path.trim())));
+                }
+                if self.importing {
+                    ui.spinner();
+                }
+            });
+            ui.weak(format!("Snippets go into the {} folder.", self.source.folder()));
+        });
+
+        action
+    }
+}
@@ -0,0 +1,126 @@
+use egui;
+use std::str::FromStr;
+use tracing::Level;
+
+use crate::logging::log_dir;
+
+pub enum LogAction {
+    Refresh,
+    /// Copy the lines shown, for a bug report.
+    Copy(String),
+    /// Zip the logs with the config and platform details.
+    CollectDiagnostics,
+    Back,
+}
+
+/// The end of today's log file, for working out why a hotkey or a sync
+/// didn't do what was expected.
+pub struct LogWindowState {
+    lines: Vec<String>,
+    /// The least severe level shown.
+    level: Level,
+    filter: String,
+}
+
+impl LogWindowState {
+    pub fn new(lines: Vec<String>) -> Self {
+        Self {
+            lines,
+            level: Level::INFO,
+            filter: String::new(),
+        }
+    }
+
+    pub fn set_lines(&mut self, lines: Vec<String>) {
+        self.lines = lines;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<LogAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(LogAction::Back);
+                }
+                ui.heading("Log");
+                if ui.button("⟳ Refresh").clicked() {
+                    action = Some(LogAction::Refresh);
+                }
+                if ui.button("📦 Collect diagnostics")
+                    .on_hover_text("Save the logs, settings without secrets, library counts and system details to a zip in Downloads, for a bug report")
+                    .clicked()
+                {
+                    action = Some(LogAction::CollectDiagnostics);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.weak("Files are kept in");
+                ui.add(egui::Label::new(egui::RichText::new(log_dir().display().to_string()).weak()).selectable(true));
+            });
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("log_level")
+                    .selected_text(level_label(self.level))
+                    .show_ui(ui, |ui| {
+                        for level in [Level::ERROR, Level::WARN, Level::INFO, Level::DEBUG, Level::TRACE] {
+                            ui.selectable_value(&mut self.level, level, level_label(level));
+                        }
+                    });
+                ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text("Filter").desired_width(200.0));
+            });
+            ui.separator();
+
+            let filter = self.filter.to_lowercase();
+            let shown: Vec<(&str, Option<Level>)> = self.lines.iter()
+                .map(|line| (line.as_str(), line_level(line)))
+                // Continuation lines of a multi-line message have no level.
+                .filter(|(_, level)| level.is_none_or(|level| level <= self.level))
+                .filter(|(line, _)| filter.is_empty() || line.to_lowercase().contains(&filter))
+                .collect();
+
+            if shown.is_empty() {
+                ui.weak("Nothing logged yet.");
+                return;
+            }
+            if ui.button(format!("📋 Copy {} line(s)", shown.len())).clicked() {
+                let text: Vec<&str> = shown.iter().map(|(line, _)| *line).collect();
+                action = Some(LogAction::Copy(text.join("\n")));
+            }
+
+            let visuals = ui.visuals().clone();
+            let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+            egui::ScrollArea::both()
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show_rows(ui, row_height, shown.len(), |ui, rows| {
+                    for (line, level) in &shown[rows] {
+                        let color = match *level {
+                            Some(Level::ERROR) => visuals.error_fg_color,
+                            Some(Level::WARN) => visuals.warn_fg_color,
+                            Some(Level::DEBUG | Level::TRACE) => visuals.weak_text_color(),
+                            _ => visuals.text_color(),
+                        };
+                        ui.add(egui::Label::new(egui::RichText::new(*line).monospace().color(color)).extend());
+                    }
+                });
+        });
+
+        action
+    }
+}
+
+fn level_label(level: Level) -> &'static str {
+    match level {
+        Level::ERROR => "Errors",
+        Level::WARN => "Warnings and up",
+        Level::INFO => "Info and up",
+        Level::DEBUG => "Debug and up",
+        Level::TRACE => "Everything",
+    }
+}
+
+/// The level of a line as the file layer writes it: a timestamp, then the
+/// level.
+fn line_level(line: &str) -> Option<Level> {
+    Level::from_str(line.split_whitespace().nth(1)?).ok()
+}
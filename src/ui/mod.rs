@@ -1,5 +1,62 @@
 pub mod add_window;
+#[cfg(feature = "vault")]
+pub mod archive_window;
+pub mod audit_window;
+pub mod build_list;
+pub mod bulk_bar;
+pub mod config_problems;
+#[cfg(feature = "ocr")]
+pub mod capture_window;
+#[cfg(feature = "sync")]
+pub mod conflict_window;
+pub mod date_filter;
+pub mod detail_modal;
+pub mod duplicates_window;
+pub mod folder_import_window;
+pub mod folder_tree;
+pub mod fonts;
 pub mod get_window;
+#[cfg(test)]
+pub mod harness;
+pub mod history_window;
+pub mod import_window;
+pub mod log_window;
+#[cfg(feature = "sync")]
+pub mod pairing_window;
+pub mod preview_pane;
+pub mod qr_popup;
+pub mod restore_window;
+pub mod review_window;
+pub mod settings_window;
+pub mod shortcut_overlay;
+pub mod stats_window;
+pub mod status_bar;
+#[cfg(feature = "sync")]
+pub mod sync_status;
+pub mod tags_window;
+pub mod title_bar;
+pub mod toast;
+pub mod trash_window;
 
-pub use add_window::AddWindowState;
-pub use get_window::GetWindowState;
\ No newline at end of file
+pub use add_window::{AddWindowState, NewSnippet};
+#[cfg(feature = "vault")]
+pub use archive_window::{ArchiveAction, ArchiveWindowState};
+pub use audit_window::{AuditAction, AuditWindowState};
+pub use config_problems::{show_config_problems, ConfigProblemsAction};
+#[cfg(feature = "sync")]
+pub use conflict_window::{ConflictAction, ConflictWindowState};
+pub use duplicates_window::{DuplicatesAction, DuplicatesWindowState};
+pub use folder_import_window::{FolderImportAction, FolderImportWindowState};
+pub use get_window::{GetWindowAction, GetWindowState, ListDensity, ListLayout, TableLayout};
+pub use history_window::{HistoryAction, HistoryWindowState};
+pub use import_window::{ImportAction, ImportWindowState};
+pub use log_window::{LogAction, LogWindowState};
+#[cfg(feature = "sync")]
+pub use pairing_window::{PairingAction, PairingWindowState};
+pub use restore_window::{BackupEntry, RestoreAction, RestoreWindowState};
+pub use review_window::{ReviewAction, ReviewWindowState};
+pub use settings_window::{SettingsAction, SettingsWindowState};
+pub use stats_window::{StatsAction, StatsWindowState};
+pub use tags_window::{TagsAction, TagsWindowState};
+pub use toast::Toasts;
+pub use trash_window::{TrashAction, TrashWindowState};
\ No newline at end of file
@@ -0,0 +1,319 @@
+use chrono::{DateTime, Local, Utc};
+use egui;
+use qrcodegen::{QrCode, QrCodeEcc};
+use std::collections::BTreeMap;
+
+use crate::config::{FolderSync, SyncPeer};
+use crate::sync::companion::CompanionInvite;
+use crate::sync::lan::LanSync;
+use crate::ui::qr_popup::render;
+
+pub enum PairingAction {
+    StartPairing,
+    StopPairing,
+    /// Pair with the discovered device with this id, using the code it shows.
+    Pair(String, String),
+    Forget(String),
+    StartCompanionPairing,
+    StopCompanionPairing,
+    ForgetCompanion(String),
+    /// Sets which devices, and whether the bucket, the folder at this path
+    /// syncs with.
+    SetFolderSync(String, FolderSync),
+    SyncNow,
+    Back,
+}
+
+/// Pairs this device with others on the network: one side shows a code,
+/// the other picks it from the devices found and types the code in.
+#[derive(Default)]
+pub struct PairingWindowState {
+    code: Option<String>,
+    selected: Option<String>,
+    typed_code: String,
+    invite: Option<CompanionInvite>,
+    qr: Option<egui::TextureHandle>,
+}
+
+impl PairingWindowState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn show_code(&mut self, code: String) {
+        self.code = Some(code);
+    }
+
+    pub fn show_invite(&mut self, ctx: &egui::Context, invite: CompanionInvite) {
+        self.qr = QrCode::encode_text(&invite.uri, QrCodeEcc::Medium)
+            .ok()
+            .map(|code| ctx.load_texture("companion_qr", render(&code), egui::TextureOptions::NEAREST));
+        self.invite = Some(invite);
+    }
+
+    /// `lan` is `None` while LAN sync is turned off; `bucket` is whether an
+    /// S3 bucket is set up.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        lan: Option<&LanSync>,
+        peers: &[SyncPeer],
+        companions: &[SyncPeer],
+        folders: &[String],
+        folder_sync: &BTreeMap<String, FolderSync>,
+        bucket: bool,
+    ) -> Option<PairingAction> {
+        let mut action = None;
+        // The code is used up by the first device that tries it.
+        if !lan.is_some_and(LanSync::is_pairing) {
+            self.code = None;
+        }
+        if !lan.is_some_and(LanSync::is_companion_pairing) {
+            self.invite = None;
+            self.qr = None;
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(PairingAction::Back);
+                }
+                ui.heading("Pair devices");
+            });
+            ui.weak("Paired devices on the same network keep their snippets in sync, with no cloud service between them.");
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                match lan {
+                    Some(lan) => {
+                        self.pairing_sections(ui, lan, peers, &mut action);
+                        self.companion_section(ui, &mut action);
+                    }
+                    None => {
+                        ui.weak("Turn on LAN sync in Settings to pair devices.");
+                    }
+                }
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    ui.strong("Paired devices");
+                    let can_sync = lan.is_some() && !peers.is_empty();
+                    if ui.add_enabled(can_sync, egui::Button::new("Sync now")).clicked() {
+                        action = Some(PairingAction::SyncNow);
+                    }
+                });
+                if peers.is_empty() {
+                    ui.weak("None yet.");
+                }
+                egui::Grid::new("paired_devices").num_columns(3).spacing([12.0, 4.0]).show(ui, |ui| {
+                    for peer in peers {
+                        ui.label(&peer.name);
+                        ui.weak(&peer.id);
+                        if ui.small_button("Forget").on_hover_text("Stop syncing with this device").clicked() {
+                            action = Some(PairingAction::Forget(peer.id.clone()));
+                        }
+                        ui.end_row();
+                    }
+                });
+                if !peers.is_empty() || bucket {
+                    folder_section(ui, lan, peers, folders, folder_sync, bucket, &mut action);
+                }
+
+                ui.add_space(8.0);
+                ui.strong("Paired phones");
+                if companions.is_empty() {
+                    ui.weak("None yet.");
+                }
+                egui::Grid::new("paired_companions").num_columns(3).spacing([12.0, 4.0]).show(ui, |ui| {
+                    for companion in companions {
+                        ui.label(&companion.name);
+                        ui.weak(&companion.id);
+                        if ui.small_button("Forget").on_hover_text("Stop letting this phone push text and search").clicked() {
+                            action = Some(PairingAction::ForgetCompanion(companion.id.clone()));
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+
+        action
+    }
+
+    fn pairing_sections(&mut self, ui: &mut egui::Ui, lan: &LanSync, peers: &[SyncPeer], action: &mut Option<PairingAction>) {
+        ui.weak(format!("This device's id is {}.", lan.id()));
+        ui.add_space(4.0);
+        ui.strong("Let another device pair with this one");
+        match &self.code {
+            Some(code) => {
+                let (first, second) = code.split_at(code.len() / 2);
+                ui.label(egui::RichText::new(format!("{}-{}", first, second)).monospace().size(28.0));
+                ui.horizontal(|ui| {
+                    ui.weak("Enter this code on the other device.");
+                    if ui.button("Stop").clicked() {
+                        *action = Some(PairingAction::StopPairing);
+                    }
+                });
+            }
+            None => {
+                if ui.button("Show pairing code").clicked() {
+                    *action = Some(PairingAction::StartPairing);
+                }
+            }
+        }
+
+        ui.add_space(8.0);
+        ui.strong("Pair with a device on this network");
+        let found: Vec<_> = lan.discovered()
+            .into_iter()
+            .filter(|device| !peers.iter().any(|peer| peer.id == device.id))
+            .collect();
+        if found.is_empty() {
+            ui.weak("No other devices found yet. LAN sync needs to be on there too.");
+            return;
+        }
+        for device in &found {
+            let selected = self.selected.as_deref() == Some(device.id.as_str());
+            let label = format!("{}  ({})", device.name, device.address.ip());
+            if ui.selectable_label(selected, label).clicked() {
+                self.selected = Some(device.id.clone());
+            }
+        }
+        let selected = self.selected.clone().filter(|id| found.iter().any(|device| device.id == *id));
+        ui.horizontal(|ui| {
+            ui.label("Code:");
+            ui.add(egui::TextEdit::singleline(&mut self.typed_code).hint_text("Shown on the other device").desired_width(160.0));
+            let ready = selected.is_some() && !self.typed_code.trim().is_empty();
+            if ui.add_enabled(ready, egui::Button::new("Pair")).clicked() {
+                if let Some(id) = selected {
+                    *action = Some(PairingAction::Pair(id, std::mem::take(&mut self.typed_code)));
+                }
+            }
+        });
+    }
+
+    fn companion_section(&mut self, ui: &mut egui::Ui, action: &mut Option<PairingAction>) {
+        ui.add_space(8.0);
+        ui.strong("Pair a phone");
+        match (&self.invite, &self.qr) {
+            (Some(invite), Some(qr)) => {
+                ui.image(qr);
+                ui.weak("Scan this with the Trinket companion app to push text here and search snippets from the phone.");
+                ui.horizontal(|ui| {
+                    ui.weak(format!("Or enter {} and code {} by hand.", invite.address, invite.code));
+                    if ui.button("Stop").clicked() {
+                        *action = Some(PairingAction::StopCompanionPairing);
+                    }
+                });
+            }
+            _ => {
+                if ui.button("Show QR code").clicked() {
+                    *action = Some(PairingAction::StartCompanionPairing);
+                }
+            }
+        }
+    }
+}
+
+/// Which folders sync with which devices and the bucket, and when each
+/// last did.
+fn folder_section(
+    ui: &mut egui::Ui,
+    lan: Option<&LanSync>,
+    peers: &[SyncPeer],
+    folders: &[String],
+    folder_sync: &BTreeMap<String, FolderSync>,
+    bucket: bool,
+    action: &mut Option<PairingAction>,
+) {
+    ui.add_space(8.0);
+    ui.strong("Folders");
+    ui.weak("Subfolders follow their parent until changed themselves.");
+    egui::Grid::new("folder_sync").num_columns(4).spacing([12.0, 4.0]).show(ui, |ui| {
+        for folder in std::iter::once("").chain(folders.iter().map(String::as_str)) {
+            let rule = FolderSync::of(folder_sync, folder);
+            if folder.is_empty() {
+                ui.label("(root)");
+            } else {
+                ui.label(folder);
+            }
+
+            let mut enabled = rule.enabled;
+            if ui.checkbox(&mut enabled, "Sync").changed() {
+                *action = Some(PairingAction::SetFolderSync(folder.to_string(), FolderSync { enabled, ..rule.clone() }));
+            }
+
+            ui.add_enabled_ui(rule.enabled, |ui| {
+                egui::ComboBox::from_id_salt(("folder_peers", folder))
+                    .selected_text(peers_label(&rule, peers))
+                    .show_ui(ui, |ui| {
+                        for peer in peers {
+                            let mut on = rule.syncs_with(&peer.id);
+                            if ui.checkbox(&mut on, &peer.name).changed() {
+                                *action = Some(PairingAction::SetFolderSync(folder.to_string(), toggle_peer(&rule, peers, &peer.id, on)));
+                            }
+                        }
+                    });
+                if bucket {
+                    let mut on = rule.syncs_with_bucket();
+                    if ui.checkbox(&mut on, "Bucket").on_hover_text("Sync this folder through the S3 bucket").changed() {
+                        *action = Some(PairingAction::SetFolderSync(folder.to_string(), FolderSync { bucket: Some(on), ..rule.clone() }));
+                    }
+                }
+            });
+
+            ui.weak(folder_status(lan, &rule, peers));
+            ui.end_row();
+        }
+    });
+}
+
+fn peers_label(rule: &FolderSync, peers: &[SyncPeer]) -> String {
+    if rule.peers.is_empty() {
+        return "All devices".to_string();
+    }
+    let names: Vec<&str> = peers.iter()
+        .filter(|peer| rule.syncs_with(&peer.id))
+        .map(|peer| peer.name.as_str())
+        .collect();
+    if names.is_empty() {
+        "No devices".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// The rule with `peer_id` added or taken out. Every device chosen means
+/// all, so devices paired later are included; none chosen turns sync off.
+fn toggle_peer(rule: &FolderSync, peers: &[SyncPeer], peer_id: &str, on: bool) -> FolderSync {
+    let chosen: Vec<String> = peers.iter()
+        .filter(|peer| if peer.id == peer_id { on } else { rule.syncs_with(&peer.id) })
+        .map(|peer| peer.id.clone())
+        .collect();
+    match chosen.len() {
+        0 => FolderSync { enabled: false, peers: Vec::new(), bucket: rule.bucket },
+        n if n == peers.len() => FolderSync { bucket: rule.bucket, ..FolderSync::default() },
+        _ => FolderSync { enabled: true, peers: chosen, bucket: rule.bucket },
+    }
+}
+
+fn folder_status(lan: Option<&LanSync>, rule: &FolderSync, peers: &[SyncPeer]) -> String {
+    if !rule.enabled {
+        return "Kept on this device".to_string();
+    }
+    let Some(lan) = lan else {
+        return "LAN sync is off".to_string();
+    };
+    let last = peers.iter()
+        .filter(|peer| rule.syncs_with(&peer.id))
+        .filter_map(|peer| Some((lan.last_synced(&peer.id)?, &peer.name)))
+        .max();
+    match last {
+        Some((at, name)) => format!("Synced with {} at {}", name, format_time(at)),
+        None => "Not synced yet".to_string(),
+    }
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M").to_string()
+}
@@ -0,0 +1,302 @@
+use egui;
+use egui::text::{CCursor, LayoutJob, LayoutSection, TextFormat};
+use egui_extras::syntax_highlighting::{highlight, CodeTheme};
+use std::ops::Range;
+
+use crate::bidi;
+use crate::storage::{parse_checklist, parse_links, ChecklistLine, SearchProvider, SearchQuery, Snippet, SECRET_MASK};
+use crate::transform::{Pipeline, Transform};
+
+/// The pane renders at most this many bytes; copying still uses the full content.
+const PREVIEW_PANE_BYTES: usize = 20_000;
+
+pub enum PreviewAction {
+    FollowLink(String),
+    Copy,
+    ToggleSecret,
+    /// Flip the checklist item on this line of the content.
+    ToggleChecklistItem(usize),
+    ResetChecklist,
+    /// Copy a checklist without its markdown markers.
+    CopyPlain,
+    /// Copy the content run through the transform pipeline.
+    CopyTransformed,
+    ShowQr,
+    ToggleWrap,
+}
+
+/// Which search match the preview pane is on. Starts over at the first
+/// match whenever the snippet or the query changes.
+#[derive(Default)]
+pub struct MatchCursor {
+    snippet_id: String,
+    query: String,
+    current: usize,
+    /// Steps requested since the last frame, applied once the match count is known.
+    step: isize,
+    scroll: bool,
+}
+
+impl MatchCursor {
+    pub fn next(&mut self) {
+        self.step += 1;
+        self.scroll = true;
+    }
+    
+    pub fn previous(&mut self) {
+        self.step -= 1;
+        self.scroll = true;
+    }
+    
+    fn follow(&mut self, snippet_id: &str, query: &str) {
+        if self.snippet_id != snippet_id || self.query != query {
+            self.snippet_id = snippet_id.to_string();
+            self.query = query.to_string();
+            self.current = 0;
+            self.step = 0;
+            self.scroll = true;
+        }
+    }
+    
+    /// Applies pending steps, wrapping around at either end.
+    fn settle(&mut self, count: usize) -> usize {
+        if count > 0 {
+            self.current = (self.current as isize + self.step).rem_euclid(count as isize) as usize;
+        }
+        self.step = 0;
+        self.current
+    }
+}
+
+/// Renders the full content of the selected snippet with its actions.
+/// `[[links]]` become clickable. Secret content stays masked until
+/// `revealed` is set through the Reveal button. `pipeline` is built up from
+/// the Transform menu and kept across snippets. Plain and code previews
+/// highlight what `search` finds for `query` and scroll to the match
+/// `matches` is on. Without `wrap`, long lines scroll sideways.
+#[allow(clippy::too_many_arguments)]
+pub fn show_preview(
+    ui: &mut egui::Ui,
+    snippet: &Snippet,
+    query: &SearchQuery,
+    search: &dyn SearchProvider,
+    matches: &mut MatchCursor,
+    revealed: &mut bool,
+    pipeline: &mut Pipeline,
+    wrap: bool,
+) -> Option<PreviewAction> {
+    let mut action = None;
+    
+    let hidden = snippet.meta.secret && !*revealed;
+    let content = truncated(&snippet.content);
+    let links = parse_links(content);
+    // Matches can't be marked in reordered RTL text.
+    let found = if hidden || snippet.meta.checklist || !links.is_empty() || bidi::has_rtl(content) {
+        Vec::new()
+    } else {
+        search.highlight(content, query)
+    };
+    matches.follow(&snippet.id, &query.text);
+    let current = matches.settle(found.len());
+    
+    ui.horizontal(|ui| {
+        if snippet.meta.secret {
+            ui.label("🔒 Secret");
+            if ui.button(if *revealed { "Hide" } else { "Reveal" }).clicked() {
+                *revealed = !*revealed;
+            }
+            if ui.button("Copy").clicked() {
+                action = Some(PreviewAction::Copy);
+            }
+            if ui.button("Unmark secret").clicked() {
+                action = Some(PreviewAction::ToggleSecret);
+            }
+        } else if ui.button("Mark secret").clicked() {
+            action = Some(PreviewAction::ToggleSecret);
+        }
+        if snippet.meta.checklist {
+            ui.separator();
+            if ui.button("Uncheck all").clicked() {
+                action = Some(PreviewAction::ResetChecklist);
+            }
+            if ui.button("Copy as plain text").clicked() {
+                action = Some(PreviewAction::CopyPlain);
+            }
+        }
+        if ui.button("QR").on_hover_text("Show as QR code").clicked() {
+            action = Some(PreviewAction::ShowQr);
+        }
+        if ui.selectable_label(wrap, "Wrap").on_hover_text("Wrap long lines instead of scrolling sideways").clicked() {
+            action = Some(PreviewAction::ToggleWrap);
+        }
+        ui.separator();
+        show_transform_controls(ui, pipeline, &mut action);
+        if !found.is_empty() {
+            ui.separator();
+            if ui.small_button("⏶").on_hover_text("Previous match").clicked() {
+                matches.previous();
+            }
+            ui.weak(format!("{}/{}", current + 1, found.len()));
+            if ui.small_button("⏷").on_hover_text("Next match").clicked() {
+                matches.next();
+            }
+        }
+    });
+    
+    if !snippet.meta.notes.is_empty() {
+        ui.label(egui::RichText::new(bidi::visual(&snippet.meta.notes)).italics().weak());
+        ui.separator();
+    }
+    
+    if hidden {
+        ui.label(SECRET_MASK);
+        return action;
+    }
+    
+    egui::ScrollArea::vertical()
+        .hscroll(!wrap)
+        .id_salt("preview_pane")
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            if snippet.meta.checklist {
+                show_checklist(ui, content, &mut action);
+            } else if !links.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    let mut last = 0;
+                    for link in &links {
+                        ui.label(&content[last..link.range.start]);
+                        if ui.link(&content[link.range.clone()]).clicked() {
+                            action = Some(PreviewAction::FollowLink(link.target.clone()));
+                        }
+                        last = link.range.end;
+                    }
+                    ui.label(&content[last..]);
+                });
+            } else {
+                let mut job = match snippet.meta.language {
+                    Some(language) => {
+                        let theme = CodeTheme::from_style(ui.style());
+                        highlight(ui.ctx(), ui.style(), &theme, content, language.extension())
+                    }
+                    None => {
+                        let font_id = egui::TextStyle::Body.resolve(ui.style());
+                        let mut job = LayoutJob::single_section(
+                            bidi::visual(content).into_owned(),
+                            TextFormat::simple(font_id, ui.visuals().text_color()),
+                        );
+                        if bidi::is_rtl(content) {
+                            job.halign = egui::Align::RIGHT;
+                        }
+                        job
+                    }
+                };
+                mark_matches(&mut job, &found, current, ui.visuals());
+                job.wrap.max_width = if wrap { ui.available_width() } else { f32::INFINITY };
+                // Laid out here rather than by the label so the match can be located.
+                let galley = ui.painter().layout_job(job);
+                let response = ui.add(egui::Label::new(galley.clone()).selectable(true));
+                if let Some(range) = found.get(current).filter(|_| matches.scroll) {
+                    let cursor = CCursor::new(content[..range.start].chars().count());
+                    let rect = galley.pos_from_cursor(cursor).translate(response.rect.min.to_vec2());
+                    ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                }
+            }
+            matches.scroll = false;
+            
+            if content.len() < snippet.content.len() {
+                ui.weak(format!("… {} more bytes not shown", snippet.content.len() - content.len()));
+            }
+        });
+    
+    action
+}
+
+fn show_transform_controls(ui: &mut egui::Ui, pipeline: &mut Pipeline, action: &mut Option<PreviewAction>) {
+    ui.menu_button("Transform ▾", |ui| {
+        for transform in Transform::ALL {
+            if ui.button(transform.label()).clicked() {
+                pipeline.push(transform);
+                ui.close();
+            }
+        }
+    });
+    if pipeline.is_empty() {
+        return;
+    }
+    
+    let steps: Vec<&str> = pipeline.steps().iter().map(|step| step.label()).collect();
+    ui.weak(steps.join(" → "));
+    if ui.button("Copy transformed").clicked() {
+        *action = Some(PreviewAction::CopyTransformed);
+    }
+    if ui.small_button("✕").on_hover_text("Clear transforms").clicked() {
+        pipeline.clear();
+    }
+}
+
+fn show_checklist(ui: &mut egui::Ui, content: &str, action: &mut Option<PreviewAction>) {
+    for (line, entry) in parse_checklist(content).into_iter().enumerate() {
+        match entry {
+            ChecklistLine::Item { checked, text } => {
+                let mut checked = checked;
+                if ui.checkbox(&mut checked, text).changed() {
+                    *action = Some(PreviewAction::ToggleChecklistItem(line));
+                }
+            }
+            ChecklistLine::Text(text) if text.trim().is_empty() => ui.add_space(4.0),
+            ChecklistLine::Text(text) => {
+                ui.label(text);
+            }
+        }
+    }
+}
+
+/// Splits the job's sections at match boundaries and gives matches a
+/// background, the current one stronger than the rest.
+fn mark_matches(job: &mut LayoutJob, found: &[Range<usize>], current: usize, visuals: &egui::Visuals) {
+    if found.is_empty() {
+        return;
+    }
+    let mut sections = Vec::with_capacity(job.sections.len() + found.len() * 2);
+    for section in std::mem::take(&mut job.sections) {
+        let end = section.byte_range.end;
+        let mut start = section.byte_range.start;
+        let mut leading_space = section.leading_space;
+        while start < end {
+            let next = found.iter().enumerate().find(|(_, range)| range.end > start);
+            let (piece_end, background) = match next {
+                Some((i, range)) if range.start <= start => {
+                    let fill = if i == current { visuals.warn_fg_color } else { visuals.selection.bg_fill };
+                    (range.end.min(end), Some(fill))
+                }
+                Some((_, range)) => (range.start.min(end), None),
+                None => (end, None),
+            };
+            let mut format = section.format.clone();
+            if let Some(fill) = background {
+                format.background = fill;
+                format.color = visuals.strong_text_color();
+            }
+            sections.push(LayoutSection {
+                leading_space,
+                byte_range: start..piece_end,
+                format,
+            });
+            leading_space = 0.0;
+            start = piece_end;
+        }
+    }
+    job.sections = sections;
+}
+
+fn truncated(content: &str) -> &str {
+    if content.len() <= PREVIEW_PANE_BYTES {
+        return content;
+    }
+    let mut end = PREVIEW_PANE_BYTES;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    &content[..end]
+}
@@ -0,0 +1,66 @@
+use egui;
+use qrcodegen::{QrCode, QrCodeEcc};
+
+/// Pixels per QR module; large enough for phone cameras at arm's length.
+const MODULE_PIXELS: usize = 6;
+/// Light border in modules, as required by the QR spec for reliable scanning.
+const QUIET_ZONE: usize = 4;
+
+/// A popup showing one snippet's content as a QR code.
+pub struct QrPopup {
+    title: String,
+    image: Result<egui::TextureHandle, String>,
+}
+
+impl QrPopup {
+    pub fn new(ctx: &egui::Context, title: &str, content: &str) -> Self {
+        let image = QrCode::encode_text(content, QrCodeEcc::Medium)
+            .map(|code| ctx.load_texture("snippet_qr", render(&code), egui::TextureOptions::NEAREST))
+            .map_err(|e| format!("This snippet can't be shown as a QR code: {}", e));
+        Self {
+            title: title.to_string(),
+            image,
+        }
+    }
+
+    /// Draws the popup; returns false once it was closed.
+    pub fn show(&self, ctx: &egui::Context) -> bool {
+        let mut open = true;
+        egui::Window::new("QR code")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.weak(&self.title);
+                match &self.image {
+                    Ok(texture) => {
+                        ui.image(texture);
+                    }
+                    Err(message) => {
+                        ui.colored_label(ui.visuals().warn_fg_color, message);
+                    }
+                }
+            });
+        open
+    }
+}
+
+pub fn render(code: &QrCode) -> egui::ColorImage {
+    let modules = code.size() as usize + 2 * QUIET_ZONE;
+    let side = modules * MODULE_PIXELS;
+    let mut pixels = vec![egui::Color32::WHITE; side * side];
+
+    for y in 0..side {
+        for x in 0..side {
+            let mx = (x / MODULE_PIXELS) as i32 - QUIET_ZONE as i32;
+            let my = (y / MODULE_PIXELS) as i32 - QUIET_ZONE as i32;
+            // get_module is false outside the symbol, which covers the quiet zone.
+            if code.get_module(mx, my) {
+                pixels[y * side + x] = egui::Color32::BLACK;
+            }
+        }
+    }
+
+    egui::ColorImage::new([side, side], pixels)
+}
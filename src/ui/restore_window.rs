@@ -0,0 +1,178 @@
+use egui;
+use std::path::PathBuf;
+
+use crate::storage::backup::{format_created, Backup, BackupDiff};
+use crate::storage::Snippet;
+
+pub enum RestoreAction {
+    /// Compare this backup with the library.
+    Preview(Backup),
+    /// Replace the library with this backup; already confirmed.
+    Restore(PathBuf),
+    BackUpNow,
+    Back,
+}
+
+/// A backup and how many snippets it holds, `None` when it can't be read.
+pub struct BackupEntry {
+    pub backup: Backup,
+    pub snippets: Option<usize>,
+}
+
+/// Lists the backups and shows what restoring one would change before it
+/// replaces the library, to recover from a bad sync or a bulk delete.
+pub struct RestoreWindowState {
+    backups: Vec<BackupEntry>,
+    preview: Option<(Backup, BackupDiff)>,
+    confirm_restore: bool,
+}
+
+impl RestoreWindowState {
+    pub fn new(backups: Vec<BackupEntry>) -> Self {
+        Self {
+            backups,
+            preview: None,
+            confirm_restore: false,
+        }
+    }
+
+    /// Swaps in a fresh list, dropping the preview if its backup is gone.
+    pub fn set_backups(&mut self, backups: Vec<BackupEntry>) {
+        self.backups = backups;
+        if let Some((previewed, _)) = &self.preview {
+            if !self.backups.iter().any(|entry| entry.backup.path == previewed.path) {
+                self.preview = None;
+            }
+        }
+    }
+
+    pub fn set_preview(&mut self, backup: Backup, diff: BackupDiff) {
+        self.preview = Some((backup, diff));
+        self.confirm_restore = false;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<RestoreAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(RestoreAction::Back);
+                }
+                ui.heading("Restore");
+                ui.weak(format!("{} backups", self.backups.len()));
+                if ui.button("Back up now").clicked() {
+                    action = Some(RestoreAction::BackUpNow);
+                }
+            });
+            ui.weak("The library is backed up before a restore, so a restore can be undone too.");
+            ui.separator();
+
+            if self.backups.is_empty() {
+                ui.weak("No backups yet.");
+                return;
+            }
+
+            egui::ScrollArea::vertical().id_salt("backups").max_height(180.0).show(ui, |ui| {
+                egui::Grid::new("backups_grid").num_columns(4).striped(true).spacing([12.0, 6.0]).show(ui, |ui| {
+                    ui.strong("Taken");
+                    ui.strong("Snippets");
+                    ui.strong("Size");
+                    ui.end_row();
+
+                    for entry in &self.backups {
+                        ui.label(format_created(&entry.backup));
+                        match entry.snippets {
+                            Some(count) => ui.label(count.to_string()),
+                            None => ui.colored_label(ui.visuals().warn_fg_color, "Unreadable"),
+                        };
+                        ui.label(format!("{} KiB", entry.backup.size.div_ceil(1024)));
+                        let previewed = self.preview.as_ref().is_some_and(|(backup, _)| backup.path == entry.backup.path);
+                        if ui.selectable_label(previewed, "Preview").clicked() && !previewed {
+                            action = Some(RestoreAction::Preview(entry.backup.clone()));
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+
+            let Some((backup, diff)) = &self.preview else {
+                ui.separator();
+                ui.weak("Preview a backup to see what restoring it would change.");
+                return;
+            };
+            ui.separator();
+            ui.strong(format!("Restoring the backup from {} would:", format_created(backup)));
+            ui.label(format!(
+                "bring back {}, remove {} and revert {} snippets; {} stay as they are.",
+                diff.returning.len(),
+                diff.removed.len(),
+                diff.changed.len(),
+                diff.unchanged
+            ));
+            ui.horizontal(|ui| {
+                if self.confirm_restore {
+                    ui.colored_label(ui.visuals().warn_fg_color, "Replace the library with this backup?");
+                    if ui.button("Restore").clicked() {
+                        action = Some(RestoreAction::Restore(backup.path.clone()));
+                        self.confirm_restore = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_restore = false;
+                    }
+                } else if ui.button("Restore…").clicked() {
+                    self.confirm_restore = true;
+                }
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical().id_salt("backup_diff").auto_shrink([false, false]).show(ui, |ui| {
+                snippet_section(ui, "Brought back", &diff.returning);
+                snippet_section(ui, "Removed", &diff.removed);
+                egui::CollapsingHeader::new(format!("Reverted ({})", diff.changed.len()))
+                    .id_salt("reverted")
+                    .default_open(!diff.changed.is_empty())
+                    .show(ui, |ui| {
+                        for (now, then) in &diff.changed {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::Label::new(then.safe_title()).truncate());
+                                ui.weak(changes(now, then));
+                            });
+                        }
+                    });
+            });
+        });
+
+        action
+    }
+}
+
+fn snippet_section(ui: &mut egui::Ui, heading: &str, snippets: &[Snippet]) {
+    egui::CollapsingHeader::new(format!("{} ({})", heading, snippets.len()))
+        .id_salt(heading)
+        .default_open(!snippets.is_empty())
+        .show(ui, |ui| {
+            for snippet in snippets {
+                ui.horizontal(|ui| {
+                    ui.weak(if snippet.folder.is_empty() { "(root)" } else { &snippet.folder });
+                    ui.add(egui::Label::new(snippet.safe_title()).truncate());
+                });
+            }
+        });
+}
+
+/// What differs between a snippet now and in the backup, in a few words.
+fn changes(now: &Snippet, then: &Snippet) -> String {
+    let mut changes = Vec::new();
+    if now.content != then.content {
+        changes.push("content".to_string());
+    }
+    if now.folder != then.folder {
+        let folder = if then.folder.is_empty() { "(root)" } else { &then.folder };
+        changes.push(format!("back to {}", folder));
+    }
+    if now.meta.tags != then.meta.tags {
+        changes.push("tags".to_string());
+    }
+    changes.join(", ")
+}
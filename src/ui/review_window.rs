@@ -0,0 +1,92 @@
+use chrono::{DateTime, Local, Utc};
+use egui;
+
+use crate::storage::review::{last_touched, stale_snippets};
+use crate::storage::{Snippet, UsageLog};
+
+pub enum ReviewAction {
+    /// Still useful; leave it off the list for another review period.
+    Keep(usize),
+    Edit(usize),
+    /// Out of the results and off the list, without deleting it.
+    Archive(usize),
+    Delete(usize),
+    Back,
+}
+
+/// Snippets nobody has copied, edited or kept in a while, one at a time
+/// for a keep, edit or delete decision.
+pub struct ReviewWindowState {
+    months: u32,
+    /// Indices into the snippets passed to `show`, longest untouched first.
+    stale: Vec<usize>,
+}
+
+impl ReviewWindowState {
+    pub fn new(snippets: &[Snippet], usage: &UsageLog, months: u32) -> Self {
+        let mut state = Self { months, stale: Vec::new() };
+        state.refresh(snippets, usage);
+        state
+    }
+
+    /// Lists the stale snippets again; call it when the snippets changed.
+    pub fn refresh(&mut self, snippets: &[Snippet], usage: &UsageLog) {
+        self.stale = stale_snippets(snippets, usage, self.months, Utc::now());
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, snippets: &[Snippet], usage: &UsageLog) -> Option<ReviewAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(ReviewAction::Back);
+                }
+                ui.heading("Review");
+            });
+            ui.separator();
+
+            if self.stale.is_empty() {
+                ui.weak(format!("Every snippet was used in the last {} months.", self.months));
+                return;
+            }
+            ui.label(format!("{} snippets haven't been copied or changed in {} months.", self.stale.len(), self.months));
+            ui.separator();
+
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                for &index in &self.stale {
+                    let Some(snippet) = snippets.get(index) else {
+                        continue;
+                    };
+                    ui.horizontal(|ui| {
+                        if ui.button("Keep").on_hover_text(format!("Don't ask again for {} months", self.months)).clicked() {
+                            action = Some(ReviewAction::Keep(index));
+                        }
+                        let locked = snippet.meta.locked;
+                        if ui.add_enabled(!locked, egui::Button::new("Edit")).clicked() {
+                            action = Some(ReviewAction::Edit(index));
+                        }
+                        if ui.button("Archive").on_hover_text("Keep it, but only find it with is:archived").clicked() {
+                            action = Some(ReviewAction::Archive(index));
+                        }
+                        if ui.add_enabled(!locked, egui::Button::new("Delete"))
+                            .on_disabled_hover_text("Unlock the snippet to change it")
+                            .clicked()
+                        {
+                            action = Some(ReviewAction::Delete(index));
+                        }
+                        let touched: DateTime<Local> = last_touched(snippet, usage).into();
+                        ui.weak(touched.format("%Y-%m-%d").to_string())
+                            .on_hover_text("Last copied, changed or kept");
+                        if !snippet.folder.is_empty() {
+                            ui.weak(format!("📁 {}", snippet.folder));
+                        }
+                        ui.add(egui::Label::new(snippet.safe_title()).truncate());
+                    });
+                }
+            });
+        });
+
+        action
+    }
+}
@@ -0,0 +1,360 @@
+use egui;
+
+use crate::config::{Config, ROW_HEIGHT_RANGE, UI_SCALE_RANGE};
+use crate::keymap::Profile;
+use crate::redaction::RedactionMode;
+use crate::picker::SnippetPicker;
+use crate::ui::ListLayout;
+use crate::storage::search_provider::{self, SearchProviderKind};
+use crate::storage::starters::{STARTER_FOLDER, STARTER_SETS};
+use crate::storage::title::TITLE_CHARS;
+use crate::storage::{SearchProvider, Snippet};
+#[cfg(feature = "s3")]
+use crate::config::S3Target;
+#[cfg(feature = "s3")]
+use crate::sync::s3::S3Credentials;
+#[cfg(feature = "s3")]
+use zeroize::Zeroize;
+
+const TRY_LIST_HEIGHT: f32 = 160.0;
+
+pub enum SettingsAction {
+    Save(Box<Config>),
+    /// Keep these in the keychain; they take effect right away.
+    #[cfg(feature = "s3")]
+    SaveS3Credentials(S3Credentials),
+    /// Use this key from another device for the bucket.
+    #[cfg(feature = "s3")]
+    SetS3Key(String),
+    #[cfg(feature = "s3")]
+    CopyS3Key,
+    BackUpNow,
+    OpenRestore,
+    /// Import the starter set at this index of `STARTER_SETS`.
+    ImportStarters(usize),
+    Back,
+}
+
+/// Edits a copy of the config; nothing changes until it's saved.
+pub struct SettingsWindowState {
+    draft: Config,
+    /// Tries the drafted search providers on the library before saving.
+    picker: SnippetPicker,
+    search: Box<dyn SearchProvider>,
+    /// The title of the snippet last picked in the picker.
+    tried: Option<String>,
+    /// Typed here and handed to the keychain, never kept in the config.
+    #[cfg(feature = "s3")]
+    s3_access_key_id: String,
+    #[cfg(feature = "s3")]
+    s3_secret: String,
+    #[cfg(feature = "s3")]
+    s3_key: String,
+}
+
+#[cfg(feature = "s3")]
+impl Drop for SettingsWindowState {
+    fn drop(&mut self) {
+        self.s3_secret.zeroize();
+        self.s3_key.zeroize();
+    }
+}
+
+impl SettingsWindowState {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            draft: config.clone(),
+            picker: SnippetPicker::new("settings_picker").list_height(TRY_LIST_HEIGHT),
+            search: search_provider::build(&config.search_providers),
+            tried: None,
+            #[cfg(feature = "s3")]
+            s3_access_key_id: String::new(),
+            #[cfg(feature = "s3")]
+            s3_secret: String::new(),
+            #[cfg(feature = "s3")]
+            s3_key: String::new(),
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, snippets: &[Snippet]) -> Option<SettingsAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(SettingsAction::Back);
+                }
+                ui.heading("Settings");
+            });
+            ui.separator();
+
+            egui::Grid::new("settings_grid").num_columns(2).spacing([12.0, 8.0]).show(ui, |ui| {
+                ui.label("Keybindings:");
+                egui::ComboBox::from_id_salt("keymap_profile")
+                    .selected_text(self.draft.keymap_profile.label())
+                    .show_ui(ui, |ui| {
+                        for profile in Profile::ALL {
+                            ui.selectable_value(&mut self.draft.keymap_profile, profile, profile.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Snippet list:");
+                egui::ComboBox::from_id_salt("list_layout")
+                    .selected_text(self.draft.list_layout.label())
+                    .show_ui(ui, |ui| {
+                        for layout in ListLayout::ALL {
+                            ui.selectable_value(&mut self.draft.list_layout, layout, layout.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Row height:");
+                ui.add(egui::DragValue::new(&mut self.draft.row_height).range(ROW_HEIGHT_RANGE).suffix(" pt"));
+                ui.end_row();
+
+                ui.label("Preview length:");
+                ui.add(egui::DragValue::new(&mut self.draft.preview_chars).range(10..=TITLE_CHARS).suffix(" chars"));
+                ui.end_row();
+
+                ui.label("Interface scale:");
+                ui.add(egui::Slider::new(&mut self.draft.ui_scale, UI_SCALE_RANGE).step_by(0.05));
+                ui.end_row();
+
+                ui.label("Window:");
+                ui.checkbox(&mut self.draft.always_on_top, "Keep on top of other windows");
+                ui.end_row();
+
+                #[cfg(all(target_os = "linux", feature = "layer-shell"))]
+                {
+                    ui.label("");
+                    ui.checkbox(&mut self.draft.layer_shell, "Show as a Wayland overlay")
+                        .on_hover_text("Above fullscreen apps on wlroots-based compositors; applies after a restart");
+                    ui.end_row();
+                }
+
+                #[cfg(any(windows, target_os = "macos"))]
+                {
+                    ui.label("");
+                    ui.checkbox(&mut self.draft.window_effects, "Blurred window background")
+                        .on_hover_text("Needs Windows 11 on Windows");
+                    ui.end_row();
+                }
+
+                ui.label("Search:");
+                ui.horizontal(|ui| {
+                    let mut changed = false;
+                    for kind in SearchProviderKind::ALL {
+                        let mut on = self.draft.search_providers.contains(&kind);
+                        if ui.checkbox(&mut on, kind.label()).changed() {
+                            changed = true;
+                            if on {
+                                self.draft.search_providers.push(kind);
+                            } else {
+                                self.draft.search_providers.retain(|&k| k != kind);
+                            }
+                        }
+                    }
+                    if changed {
+                        self.search = search_provider::build(&self.draft.search_providers);
+                        self.picker.invalidate();
+                    }
+                })
+                .response
+                .on_hover_text("With none ticked, text search is used");
+                ui.end_row();
+
+                ui.label("Link titles:");
+                ui.checkbox(&mut self.draft.fetch_link_titles, "Look up page titles for links");
+                ui.end_row();
+
+                ui.label("Metrics:");
+                ui.checkbox(&mut self.draft.local_metrics, "Keep usage metrics on this device")
+                    .on_hover_text("Captures, searches and time to copy per day, shown in Statistics; never sent anywhere");
+                ui.end_row();
+
+                ui.label("Clear copied secrets:");
+                ui.add(egui::DragValue::new(&mut self.draft.secret_clipboard_clear_secs)
+                    .range(0..=3600)
+                    .suffix(" s"))
+                    .on_hover_text("0 leaves secrets on the clipboard");
+                ui.end_row();
+
+                ui.label("Secrets:");
+                ui.checkbox(&mut self.draft.confirm_secrets, "Confirm it's you before showing or copying")
+                    .on_hover_text("Uses Windows Hello, Touch ID or a polkit password prompt");
+                ui.end_row();
+
+                ui.label("");
+                ui.checkbox(&mut self.draft.secure_delete, "Erase deleted secrets instead of trashing them")
+                    .on_hover_text("Deleted secrets can't be restored or undone");
+                ui.end_row();
+
+                ui.label("Card numbers and keys:");
+                egui::ComboBox::from_id_salt("redaction")
+                    .selected_text(self.draft.redaction.label())
+                    .show_ui(ui, |ui| {
+                        for mode in RedactionMode::ALL {
+                            ui.selectable_value(&mut self.draft.redaction, mode, mode.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Checked when saving snippets that aren't marked secret");
+                ui.end_row();
+
+                #[cfg(feature = "sync")]
+                {
+                    ui.label("LAN sync:");
+                    ui.checkbox(&mut self.draft.lan_sync, "Sync with paired devices on this network")
+                        .on_hover_text("Pair devices from the ☰ menu");
+                    ui.end_row();
+                }
+
+                #[cfg(feature = "s3")]
+                if let Some(s3_action) = self.s3_rows(ui) {
+                    action = Some(s3_action);
+                }
+
+                ui.label("Empty trash after:");
+                ui.add(egui::DragValue::new(&mut self.draft.trash_retention_days)
+                    .range(0..=3650)
+                    .suffix(" days"))
+                    .on_hover_text("0 keeps deleted snippets until the trash is emptied");
+                ui.end_row();
+
+                ui.label("Suggest a review after:");
+                ui.add(egui::DragValue::new(&mut self.draft.review_after_months)
+                    .range(0..=120)
+                    .suffix(" months unused"))
+                    .on_hover_text("0 never suggests one; ☰ → Review unused… works either way");
+                ui.end_row();
+
+                ui.label("Snooze for:");
+                ui.add(egui::DragValue::new(&mut self.draft.snooze_minutes)
+                    .range(1..=24 * 60)
+                    .suffix(" min"))
+                    .on_hover_text("How long the snooze hotkey and tray icon pause the hotkeys");
+                ui.end_row();
+
+                ui.label("Back up every:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut self.draft.backup_interval_hours)
+                        .range(0..=24 * 30)
+                        .suffix(" h"))
+                        .on_hover_text("0 turns scheduled backups off");
+                    if ui.button("Back up now").clicked() {
+                        action = Some(SettingsAction::BackUpNow);
+                    }
+                    if ui.button("Restore…").on_hover_text(self.draft.backup_dir().display().to_string()).clicked() {
+                        action = Some(SettingsAction::OpenRestore);
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Backups kept:");
+                ui.add(egui::DragValue::new(&mut self.draft.backups_kept).range(1..=1000))
+                    .on_hover_text("Older backups are deleted after each new one");
+                ui.end_row();
+
+                ui.label("Starter snippets:");
+                ui.horizontal_wrapped(|ui| {
+                    for (index, set) in STARTER_SETS.iter().enumerate() {
+                        let hint = format!("{}; {} snippets into {}/{}", set.description, set.len(), STARTER_FOLDER, set.folder);
+                        if ui.button(format!("Import {}", set.name)).on_hover_text(hint).clicked() {
+                            action = Some(SettingsAction::ImportStarters(index));
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Size warning:");
+                let mut kib = self.draft.max_snippet_bytes / 1024;
+                if ui.add(egui::DragValue::new(&mut kib).range(1..=1024 * 1024).suffix(" KiB")).changed() {
+                    self.draft.max_snippet_bytes = kib * 1024;
+                }
+                ui.end_row();
+            });
+
+            if !self.draft.keybindings.is_empty() {
+                ui.add_space(4.0);
+                ui.weak(format!(
+                    "{} shortcut overrides from the config file apply on top of this profile.",
+                    self.draft.keybindings.len()
+                ));
+            }
+
+            ui.add_space(4.0);
+            egui::CollapsingHeader::new("Try the search").show(ui, |ui| {
+                if let Some(idx) = self.picker.show(ui, snippets, self.search.as_mut()) {
+                    self.tried = snippets.get(idx).map(|snippet| snippet.safe_title().to_string());
+                }
+                if let Some(title) = &self.tried {
+                    ui.weak(format!("Enter in the window would copy “{}”", title));
+                }
+            });
+
+            ui.add_space(8.0);
+            if ui.button("Save").clicked() {
+                action = Some(SettingsAction::Save(Box::new(self.draft.clone())));
+            }
+        });
+
+        action
+    }
+
+    /// Grid rows for the bucket, its credentials and the sync key.
+    #[cfg(feature = "s3")]
+    fn s3_rows(&mut self, ui: &mut egui::Ui) -> Option<SettingsAction> {
+        let mut action = None;
+        ui.label("S3 sync:");
+        let mut enabled = self.draft.s3.is_some();
+        if ui.checkbox(&mut enabled, "Sync and back up through an S3-compatible bucket").changed() {
+            self.draft.s3 = enabled.then(S3Target::default);
+        }
+        ui.end_row();
+        let Some(target) = &mut self.draft.s3 else {
+            return None;
+        };
+
+        ui.label("Endpoint:");
+        ui.add(egui::TextEdit::singleline(&mut target.endpoint).hint_text("https://s3.us-east-1.amazonaws.com"));
+        ui.end_row();
+        ui.label("Region:");
+        ui.text_edit_singleline(&mut target.region);
+        ui.end_row();
+        ui.label("Bucket:");
+        ui.text_edit_singleline(&mut target.bucket);
+        ui.end_row();
+        ui.label("Prefix:");
+        ui.text_edit_singleline(&mut target.prefix)
+            .on_hover_text("Folder in the bucket everything is kept under");
+        ui.end_row();
+
+        ui.label("Credentials:");
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.s3_access_key_id).hint_text("Access key ID").desired_width(140.0));
+            ui.add(egui::TextEdit::singleline(&mut self.s3_secret).password(true).hint_text("Secret key").desired_width(140.0));
+            let complete = !self.s3_access_key_id.trim().is_empty() && !self.s3_secret.is_empty();
+            if ui.add_enabled(complete, egui::Button::new("Save to keychain")).clicked() {
+                action = Some(SettingsAction::SaveS3Credentials(S3Credentials {
+                    access_key_id: std::mem::take(&mut self.s3_access_key_id).trim().to_string(),
+                    secret_access_key: std::mem::take(&mut self.s3_secret),
+                }));
+            }
+        });
+        ui.end_row();
+
+        ui.label("Sync key:");
+        ui.horizontal(|ui| {
+            if ui.button("Copy").on_hover_text("Paste it on the other devices that sync through the bucket").clicked() {
+                action = Some(SettingsAction::CopyS3Key);
+            }
+            ui.add(egui::TextEdit::singleline(&mut self.s3_key).password(true).hint_text("Key from another device").desired_width(180.0));
+            if ui.add_enabled(!self.s3_key.trim().is_empty(), egui::Button::new("Use")).clicked() {
+                action = Some(SettingsAction::SetS3Key(std::mem::take(&mut self.s3_key)));
+            }
+        });
+        ui.end_row();
+        action
+    }
+}
@@ -0,0 +1,34 @@
+use egui;
+
+use crate::keymap::{format_keys, Keymap, Scope, MOUSE_GESTURES};
+
+/// Lists every shortcut active in `scope`, including the user's overrides.
+pub fn show_shortcut_overlay(ctx: &egui::Context, keymap: &Keymap, scope: Scope, open: &mut bool) {
+    egui::Window::new("Keyboard shortcuts")
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            egui::Grid::new("shortcut_grid").num_columns(2).striped(true).show(ui, |ui| {
+                let mut bindings = keymap.bindings(scope).peekable();
+                while let Some(binding) = bindings.next() {
+                    // Bindings arrive grouped by command; list alternatives on one row.
+                    let mut keys = vec![format_keys(ctx, &binding.keys)];
+                    while let Some(next) = bindings.next_if(|next| next.command == binding.command) {
+                        keys.push(format_keys(ctx, &next.keys));
+                    }
+                    ui.monospace(keys.join(" or "));
+                    ui.label(binding.command.label());
+                    ui.end_row();
+                }
+                if scope == Scope::Get {
+                    for (gesture, description) in MOUSE_GESTURES {
+                        ui.monospace(gesture);
+                        ui.label(description);
+                        ui.end_row();
+                    }
+                }
+            });
+        });
+}
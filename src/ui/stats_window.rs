@@ -0,0 +1,195 @@
+use chrono::{DateTime, Datelike, Local};
+use egui;
+use std::collections::BTreeMap;
+
+use crate::storage::metrics::DayMetrics;
+use crate::storage::{Metrics, Snippet, UsageLog};
+
+const TOP_COUNT: usize = 10;
+/// Most recent days listed under "On this device".
+const METRIC_DAYS: usize = 14;
+const GROWTH_BAR_HEIGHT: f32 = 80.0;
+
+pub enum StatsAction {
+    ClearMetrics,
+    Back,
+    Close,
+}
+
+#[derive(Default)]
+pub struct StatsWindowState;
+
+impl StatsWindowState {
+    /// `metrics` is `None` while local metrics are off.
+    pub fn show(&mut self, ctx: &egui::Context, snippets: &[Snippet], usage: &UsageLog, metrics: Option<&Metrics>) -> Option<StatsAction> {
+        let mut action = None;
+        
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(StatsAction::Back);
+                }
+                ui.heading("Statistics");
+            });
+            ui.separator();
+            
+            let mut used: Vec<_> = snippets.iter()
+                .filter_map(|snippet| usage.get(&snippet.id).map(|u| (snippet, u)))
+                .collect();
+            used.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.count));
+            let never_used: Vec<_> = snippets.iter()
+                .filter(|snippet| usage.get(&snippet.id).is_none())
+                .collect();
+            
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.label(format!(
+                    "{} snippets, {} used at least once, {} never used",
+                    snippets.len(),
+                    used.len(),
+                    never_used.len(),
+                ));
+                ui.add_space(8.0);
+                
+                ui.strong("Most used");
+                if used.is_empty() {
+                    ui.weak("Nothing copied yet.");
+                }
+                egui::Grid::new("most_used").striped(true).show(ui, |ui| {
+                    for (snippet, stats) in used.iter().take(TOP_COUNT) {
+                        ui.label(format!("{}×", stats.count));
+                        ui.label(format_date(stats.last_used.into()));
+                        ui.add(egui::Label::new(snippet.safe_title()).truncate());
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(8.0);
+                
+                ui.collapsing(format!("Never used ({})", never_used.len()), |ui| {
+                    for snippet in &never_used {
+                        ui.horizontal(|ui| {
+                            ui.weak(format_date(snippet.created.into()));
+                            ui.add(egui::Label::new(snippet.safe_title()).truncate());
+                        });
+                    }
+                });
+                ui.add_space(8.0);
+                
+                ui.strong("Library growth");
+                show_growth(ui, snippets);
+                ui.add_space(8.0);
+                
+                ui.strong("On this device");
+                match metrics {
+                    Some(metrics) => {
+                        if show_metrics(ui, metrics) {
+                            action = Some(StatsAction::ClearMetrics);
+                        }
+                    }
+                    None => {
+                        ui.weak("Turn on usage metrics in Settings to count captures, searches and time to copy here.");
+                    }
+                }
+            });
+        });
+        
+        ctx.input(|i| {
+            if i.key_pressed(egui::Key::Escape) {
+                action = Some(StatsAction::Close);
+            }
+        });
+        
+        action
+    }
+}
+
+/// Cumulative snippet count per month, drawn as a simple bar chart.
+fn show_growth(ui: &mut egui::Ui, snippets: &[Snippet]) {
+    let mut per_month: BTreeMap<(i32, u32), usize> = BTreeMap::new();
+    for snippet in snippets {
+        let created: DateTime<Local> = snippet.created.into();
+        *per_month.entry((created.year(), created.month())).or_default() += 1;
+    }
+    if per_month.is_empty() {
+        ui.weak("No snippets yet.");
+        return;
+    }
+    
+    let mut total = 0;
+    let cumulative: Vec<_> = per_month.into_iter()
+        .map(|(month, count)| {
+            total += count;
+            (month, count, total)
+        })
+        .collect();
+    
+    let bar_width = (ui.available_width() / cumulative.len() as f32).clamp(4.0, 40.0);
+    let (rect, _) = ui.allocate_exact_size(
+        egui::vec2(bar_width * cumulative.len() as f32, GROWTH_BAR_HEIGHT),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    let color = ui.visuals().selection.bg_fill;
+    for (i, ((year, month), added, running)) in cumulative.iter().enumerate() {
+        let height = GROWTH_BAR_HEIGHT * *running as f32 / total as f32;
+        let left = rect.left() + i as f32 * bar_width;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left + 1.0, rect.bottom() - height),
+            egui::pos2(left + bar_width - 1.0, rect.bottom()),
+        );
+        painter.rect_filled(bar, 1.0, color);
+        ui.interact(bar, ui.id().with(("growth_bar", i)), egui::Sense::hover())
+            .on_hover_text(format!("{}-{:02}: +{} ({} total)", year, month, added, running));
+    }
+}
+
+/// The recent days' counters with their totals. Returns true when Clear
+/// was clicked.
+fn show_metrics(ui: &mut egui::Ui, metrics: &Metrics) -> bool {
+    let days = metrics.days();
+    if days.is_empty() {
+        ui.weak("Nothing counted yet.");
+        return false;
+    }
+    
+    let mut total = DayMetrics::default();
+    for day in days.values() {
+        total.add(day);
+    }
+    ui.label(format!(
+        "{} captures, {} searches and {} copies over {} day(s)",
+        total.captures,
+        total.searches,
+        total.copies,
+        days.len(),
+    ));
+    egui::Grid::new("metrics_grid").num_columns(5).striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+        ui.strong("Day");
+        ui.strong("Captures");
+        ui.strong("Searches");
+        ui.strong("Copies");
+        ui.strong("Time to copy");
+        ui.end_row();
+        for (date, day) in days.iter().rev().take(METRIC_DAYS) {
+            ui.label(date.format("%Y-%m-%d").to_string());
+            ui.label(day.captures.to_string());
+            ui.label(day.searches.to_string());
+            ui.label(day.copies.to_string());
+            ui.label(format_latency(day));
+            ui.end_row();
+        }
+    });
+    ui.label(format!("Average time to copy: {}", format_latency(&total)));
+    ui.weak("Counted on this device only and never sent anywhere.");
+    ui.button("Clear metrics").clicked()
+}
+
+fn format_latency(day: &DayMetrics) -> String {
+    match day.average_latency() {
+        Some(latency) => format!("{:.1} s", latency.as_secs_f32()),
+        None => "—".to_string(),
+    }
+}
+
+fn format_date(time: DateTime<Local>) -> String {
+    time.format("%Y-%m-%d").to_string()
+}
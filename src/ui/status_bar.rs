@@ -0,0 +1,92 @@
+use egui;
+
+use crate::storage::indexer::DATE_FORMAT;
+use crate::storage::SearchQuery;
+
+/// A filter shown as a chip; clicking the chip removes it.
+pub enum FilterChip {
+    Language,
+    Tag,
+    Source,
+    After,
+    Before,
+    Archived,
+    Folder,
+}
+
+impl FilterChip {
+    /// The `key:` the filter is typed with, `None` for the folder sidebar.
+    pub fn key(&self) -> Option<&'static str> {
+        match self {
+            FilterChip::Language => Some("lang"),
+            FilterChip::Tag => Some("tag"),
+            FilterChip::Source => Some("from"),
+            FilterChip::After => Some("after"),
+            FilterChip::Before => Some("before"),
+            FilterChip::Archived => Some("is"),
+            FilterChip::Folder => None,
+        }
+    }
+}
+
+/// Result counts, active filters and the sort order along the bottom of
+/// the get window, with whatever `trailing` adds at the right-hand end.
+/// Returns the filter the user asked to remove.
+pub fn show_status_bar(
+    ctx: &egui::Context,
+    shown: usize,
+    total: usize,
+    query: &SearchQuery,
+    searching: bool,
+    trailing: impl FnOnce(&mut egui::Ui),
+) -> Option<FilterChip> {
+    let mut removed = None;
+
+    egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            let counts = if searching {
+                format!("{} of {} snippets, searching…", shown, total)
+            } else {
+                format!("{} of {} snippets", shown, total)
+            };
+            ui.small(counts);
+
+            let mut chips: Vec<(FilterChip, String)> = Vec::new();
+            if let Some(language) = query.language {
+                chips.push((FilterChip::Language, format!("lang: {}", language.name())));
+            }
+            if let Some(tag) = &query.tag {
+                chips.push((FilterChip::Tag, format!("#{}", tag)));
+            }
+            if let Some(source) = &query.source {
+                chips.push((FilterChip::Source, format!("from {}", source)));
+            }
+            if let Some(after) = query.after {
+                chips.push((FilterChip::After, format!("after {}", after.format(DATE_FORMAT))));
+            }
+            if let Some(before) = query.before {
+                chips.push((FilterChip::Before, format!("before {}", before.format(DATE_FORMAT))));
+            }
+            if query.archived {
+                chips.push((FilterChip::Archived, "🗄 archived".to_string()));
+            }
+            if let Some(folder) = &query.folder {
+                chips.push((FilterChip::Folder, format!("📁 {}", folder)));
+            }
+            for (chip, label) in chips {
+                let button = egui::Button::new(egui::RichText::new(format!("{} ✕", label)).small())
+                    .corner_radius(8.0);
+                if ui.add(button).on_hover_text("Remove filter").clicked() {
+                    removed = Some(chip);
+                }
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                trailing(ui);
+                ui.small("Pinned, then newest first");
+            });
+        });
+    });
+
+    removed
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Local, Utc};
+use egui;
+
+use crate::sync::lan::{SyncError, SyncStatus};
+
+pub enum SyncStatusAction {
+    SyncNow,
+    ShowError,
+    Dismiss,
+}
+
+/// The sync state as a small label for the status bar; clicking it shows
+/// what went wrong, if anything did.
+pub fn show_sync_indicator(ui: &mut egui::Ui, status: &SyncStatus) -> Option<SyncStatusAction> {
+    let label = egui::RichText::new(summary(status)).small();
+    let label = match &status.error {
+        Some(_) if !status.syncing => label.color(ui.visuals().warn_fg_color),
+        _ => label,
+    };
+    let response = ui.add(egui::Label::new(label).sense(egui::Sense::click()));
+    if status.error.is_some() {
+        response.on_hover_text("Show what went wrong").clicked().then_some(SyncStatusAction::ShowError)
+    } else {
+        response.on_hover_text("Sync now").clicked().then_some(SyncStatusAction::SyncNow)
+    }
+}
+
+/// Details of the last failed sync; `open` turns false once it is closed.
+pub fn show_sync_error(ctx: &egui::Context, error: &SyncError, open: &mut bool) -> Option<SyncStatusAction> {
+    let mut action = None;
+    egui::Window::new("Sync failed")
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(format!("Syncing with {} failed at {}:", error.peer, format_time(error.at)));
+            ui.colored_label(ui.visuals().warn_fg_color, &error.message);
+            ui.weak("Both devices need LAN sync turned on and to be on the same network.");
+            ui.horizontal(|ui| {
+                if ui.button("Sync now").clicked() {
+                    action = Some(SyncStatusAction::SyncNow);
+                }
+                if ui.button("Dismiss").clicked() {
+                    action = Some(SyncStatusAction::Dismiss);
+                }
+            });
+        });
+    if action.is_some() {
+        *open = false;
+    }
+    action
+}
+
+/// One line for the status bar and the tray tooltip.
+pub fn summary(status: &SyncStatus) -> String {
+    if status.syncing {
+        return "⟳ Syncing…".to_string();
+    }
+    match (&status.error, status.last_synced) {
+        (Some(error), _) => format!("⚠ Sync with {} failed", error.peer),
+        (None, Some(at)) => format!("✓ Synced {}", format_time(at)),
+        (None, None) => "Not synced yet".to_string(),
+    }
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    let time = DateTime::<Local>::from(time);
+    if time.date_naive() == Local::now().date_naive() {
+        time.format("%H:%M").to_string()
+    } else {
+        time.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
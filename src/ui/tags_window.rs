@@ -0,0 +1,126 @@
+use egui;
+use std::collections::BTreeSet;
+
+use crate::storage::tags::{count_tags, normalize_tag};
+use crate::storage::Snippet;
+
+pub enum TagsAction {
+    Rename { from: String, to: String },
+    /// Replace every tag in `from` with `into`.
+    Merge { from: Vec<String>, into: String },
+    Delete(String),
+    Back,
+}
+
+/// Lists every tag with its snippet count for renaming, merging and deleting.
+#[derive(Default)]
+pub struct TagsWindowState {
+    /// Tag being renamed and the new name typed so far.
+    renaming: Option<(String, String)>,
+    /// Tags ticked for merging.
+    selected: BTreeSet<String>,
+    merge_into: String,
+    confirm_delete: Option<String>,
+}
+
+impl TagsWindowState {
+    pub fn show(&mut self, ctx: &egui::Context, snippets: &[Snippet]) -> Option<TagsAction> {
+        let mut action = None;
+        let counts = count_tags(snippets);
+        self.selected.retain(|tag| counts.contains_key(tag));
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(TagsAction::Back);
+                }
+                ui.heading("Tags");
+                ui.weak(format!("{} tags", counts.len()));
+            });
+            ui.separator();
+
+            if counts.is_empty() {
+                ui.weak("No tags yet. Add them in the editor or with the bulk bar.");
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                if self.selected.len() < 2 {
+                    ui.weak("Tick two or more tags to merge them.");
+                    return;
+                }
+                if !self.selected.contains(&self.merge_into) {
+                    self.merge_into = self.selected.iter().next().cloned().unwrap_or_default();
+                }
+                ui.label(format!("Merge {} tags into", self.selected.len()));
+                egui::ComboBox::from_id_salt("merge_into")
+                    .selected_text(format!("#{}", self.merge_into))
+                    .show_ui(ui, |ui| {
+                        for tag in &self.selected {
+                            ui.selectable_value(&mut self.merge_into, tag.clone(), format!("#{}", tag));
+                        }
+                    });
+                if ui.button("Merge").clicked() {
+                    let from = std::mem::take(&mut self.selected).into_iter().collect();
+                    action = Some(TagsAction::Merge { from, into: self.merge_into.clone() });
+                }
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                egui::Grid::new("tags_grid").num_columns(3).striped(true).spacing([12.0, 6.0]).show(ui, |ui| {
+                    for (tag, count) in &counts {
+                        let mut ticked = self.selected.contains(tag);
+                        if ui.checkbox(&mut ticked, format!("#{}", tag)).changed() {
+                            if ticked {
+                                self.selected.insert(tag.clone());
+                            } else {
+                                self.selected.remove(tag);
+                            }
+                        }
+                        ui.weak(format!("{}×", count));
+
+                        ui.horizontal(|ui| {
+                            if let Some((renaming, new_name)) = &mut self.renaming {
+                                if renaming == tag {
+                                    let response = ui.text_edit_singleline(new_name);
+                                    let normalized = normalize_tag(new_name);
+                                    let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                    let rename = ui.add_enabled(normalized.is_some(), egui::Button::new("Rename"))
+                                        .on_hover_text("Renaming to an existing tag merges the two");
+                                    if let Some(to) = normalized.filter(|_| submitted || rename.clicked()) {
+                                        action = Some(TagsAction::Rename { from: tag.clone(), to });
+                                        self.renaming = None;
+                                    } else if ui.button("Cancel").clicked() {
+                                        self.renaming = None;
+                                    }
+                                    return;
+                                }
+                            }
+                            if self.confirm_delete.as_ref() == Some(tag) {
+                                ui.label(format!("Remove from {} snippets?", count));
+                                if ui.button("Delete").clicked() {
+                                    action = Some(TagsAction::Delete(tag.clone()));
+                                    self.confirm_delete = None;
+                                }
+                                if ui.button("Cancel").clicked() {
+                                    self.confirm_delete = None;
+                                }
+                                return;
+                            }
+                            if ui.button("Rename…").clicked() {
+                                self.renaming = Some((tag.clone(), tag.clone()));
+                            }
+                            if ui.button("Delete…").clicked() {
+                                self.confirm_delete = Some(tag.clone());
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+
+        action
+    }
+}
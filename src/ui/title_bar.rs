@@ -0,0 +1,50 @@
+use egui;
+
+const TITLE_BAR_HEIGHT: f32 = 24.0;
+
+pub enum TitleBarAction {
+    ToggleAlwaysOnTop,
+    Minimize,
+    Close,
+}
+
+/// A slim stand-in for the window decorations, which are turned off: drag
+/// the bar to move the window, double-click it to maximize.
+pub fn show_title_bar(ctx: &egui::Context, title: &str, always_on_top: bool) -> Option<TitleBarAction> {
+    let mut action = None;
+
+    egui::TopBottomPanel::top("title_bar")
+        .exact_height(TITLE_BAR_HEIGHT)
+        .show(ctx, |ui| {
+            let bar = ui.max_rect();
+            let drag = ui.interact(bar, egui::Id::new("title_bar_drag"), egui::Sense::click_and_drag());
+            if drag.drag_started_by(egui::PointerButton::Primary) {
+                ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+            }
+            if drag.double_clicked() {
+                let maximized = ctx.input(|i| i.viewport().maximized.unwrap_or(false));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(!maximized));
+            }
+
+            ui.horizontal_centered(|ui| {
+                ui.label(egui::RichText::new(title).strong());
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.add(egui::Button::new("✕").frame(false)).on_hover_text("Close").clicked() {
+                        action = Some(TitleBarAction::Close);
+                    }
+                    if ui.add(egui::Button::new("🗕").frame(false)).on_hover_text("Minimize").clicked() {
+                        action = Some(TitleBarAction::Minimize);
+                    }
+                    let pin_hint = if always_on_top { "Stop keeping on top" } else { "Keep on top" };
+                    if ui.add(egui::Button::selectable(always_on_top, "📌"))
+                        .on_hover_text(pin_hint)
+                        .clicked()
+                    {
+                        action = Some(TitleBarAction::ToggleAlwaysOnTop);
+                    }
+                });
+            });
+        });
+
+    action
+}
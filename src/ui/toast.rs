@@ -0,0 +1,67 @@
+use egui;
+use std::time::{Duration, Instant};
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+struct Toast {
+    message: String,
+    expires: Instant,
+    error: bool,
+}
+
+/// Short-lived messages drawn on top of whichever window is open.
+#[derive(Default)]
+pub struct Toasts {
+    items: Vec<Toast>,
+    /// Drops info messages while snoozed; errors still show.
+    muted: bool,
+}
+
+impl Toasts {
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(message.into(), true);
+    }
+    
+    pub fn info(&mut self, message: impl Into<String>) {
+        if !self.muted {
+            self.push(message.into(), false);
+        }
+    }
+    
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+    
+    fn push(&mut self, message: String, error: bool) {
+        self.items.push(Toast {
+            message,
+            expires: Instant::now() + TOAST_DURATION,
+            error,
+        });
+    }
+    
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        self.items.retain(|toast| toast.expires > now);
+        if self.items.is_empty() {
+            return;
+        }
+        
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -12.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for toast in &self.items {
+                    egui::Frame::popup(ui.style())
+                        .fill(ui.visuals().extreme_bg_color)
+                        .show(ui, |ui| {
+                            if toast.error {
+                                ui.colored_label(ui.visuals().error_fg_color, &toast.message);
+                            } else {
+                                ui.label(&toast.message);
+                            }
+                        });
+                }
+            });
+    }
+}
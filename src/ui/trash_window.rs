@@ -0,0 +1,107 @@
+use chrono::{DateTime, Local, Utc};
+use egui;
+
+use crate::storage::Snippet;
+
+pub enum TrashAction {
+    Restore(String),
+    /// Delete this snippet for good.
+    Delete(String),
+    Empty,
+    Back,
+}
+
+/// Lists deleted snippets so they can be restored or removed for good.
+pub struct TrashWindowState {
+    snippets: Vec<Snippet>,
+    retention_days: u32,
+    confirm_empty: bool,
+}
+
+impl TrashWindowState {
+    pub fn new(snippets: Vec<Snippet>, retention_days: u32) -> Self {
+        Self {
+            snippets,
+            retention_days,
+            confirm_empty: false,
+        }
+    }
+
+    /// Drops a snippet from the list once it was restored or deleted.
+    pub fn remove(&mut self, id: &str) {
+        self.snippets.retain(|snippet| snippet.id != id);
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<TrashAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("← Back").clicked() {
+                    action = Some(TrashAction::Back);
+                }
+                ui.heading("Trash");
+                ui.weak(format!("{} snippets", self.snippets.len()));
+            });
+            if self.retention_days > 0 {
+                ui.weak(format!(
+                    "Snippets are deleted for good {} days after they were trashed.",
+                    self.retention_days
+                ));
+            }
+            ui.separator();
+
+            if self.snippets.is_empty() {
+                ui.weak("The trash is empty.");
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                if self.confirm_empty {
+                    ui.label(format!("Delete {} snippets for good?", self.snippets.len()));
+                    if ui.button("Empty trash").clicked() {
+                        action = Some(TrashAction::Empty);
+                        self.confirm_empty = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_empty = false;
+                    }
+                } else if ui.button("Empty trash…").clicked() {
+                    self.confirm_empty = true;
+                }
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                egui::Grid::new("trash_grid").num_columns(4).striped(true).spacing([12.0, 6.0]).show(ui, |ui| {
+                    ui.strong("Deleted");
+                    ui.strong("From");
+                    ui.strong("Snippet");
+                    ui.end_row();
+
+                    for snippet in &self.snippets {
+                        ui.label(snippet.meta.deleted.map(format_deleted).unwrap_or_default());
+                        let folder = snippet.meta.trashed_from.as_deref().unwrap_or_default();
+                        ui.label(if folder.is_empty() { "(root)" } else { folder });
+                        ui.add(egui::Label::new(snippet.safe_title()).truncate());
+                        ui.horizontal(|ui| {
+                            if ui.button("Restore").clicked() {
+                                action = Some(TrashAction::Restore(snippet.id.clone()));
+                            }
+                            if ui.button("Delete").on_hover_text("Delete for good").clicked() {
+                                action = Some(TrashAction::Delete(snippet.id.clone()));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+
+        action
+    }
+}
+
+fn format_deleted(deleted: DateTime<Utc>) -> String {
+    deleted.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+}
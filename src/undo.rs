@@ -0,0 +1,44 @@
+use crate::storage::SnippetMeta;
+
+/// Older entries are dropped beyond this.
+const MAX_ENTRIES: usize = 20;
+
+/// How to reverse one change to one snippet.
+pub enum UndoChange {
+    /// The full metadata before tags, pins or similar were changed.
+    Meta { id: String, before: Box<SnippetMeta> },
+    Moved { id: String, from: String },
+    Trashed { id: String },
+}
+
+/// Everything one user action changed, undone as a unit.
+pub struct UndoEntry {
+    pub label: String,
+    pub changes: Vec<UndoChange>,
+}
+
+#[derive(Default)]
+pub struct UndoStack {
+    entries: Vec<UndoEntry>,
+}
+
+impl UndoStack {
+    pub fn push(&mut self, entry: UndoEntry) {
+        if entry.changes.is_empty() {
+            return;
+        }
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.entries.push(entry);
+    }
+    
+    pub fn pop(&mut self) -> Option<UndoEntry> {
+        self.entries.pop()
+    }
+    
+    /// Label of the action the next `pop` would undo.
+    pub fn peek_label(&self) -> Option<&str> {
+        self.entries.last().map(|entry| entry.label.as_str())
+    }
+}
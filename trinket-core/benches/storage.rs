@@ -0,0 +1,109 @@
+//! Load, search and update times over synthetic libraries of 1k, 10k and
+//! 100k snippets. Before a release, compare against the last one:
+//!
+//! ```sh
+//! cargo bench -p trinket-core -- --baseline release
+//! ```
+//!
+//! and after it, save the new numbers with `--save-baseline release`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+use tempfile::TempDir;
+use trinket_core::storage::search_provider::{self, SearchProviderKind};
+use trinket_core::storage::{sort_snippets, FileStorage, SearchQuery, Snippet, SnippetMeta};
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+const WORDS: [&str; 16] = [
+    "git", "push", "deploy", "docker", "compose", "select", "from", "where",
+    "kubectl", "logs", "curl", "header", "token", "ssh", "tunnel", "regards",
+];
+const FOLDERS: [&str; 4] = ["", "work", "work/clients", "personal"];
+
+/// A library of `count` snippets on disk, the same every run.
+struct Library {
+    // Removed with the library when dropped.
+    _dir: TempDir,
+    storage: FileStorage,
+    snippets: Vec<Snippet>,
+}
+
+impl Library {
+    fn generate(count: usize) -> Self {
+        let dir = TempDir::new().expect("temp dir");
+        let storage = FileStorage::new(dir.path().join("snippets")).expect("storage");
+        // A fixed linear congruential sequence picks the words.
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 33) as usize
+        };
+        for i in 0..count {
+            let lines = 1 + next() % 6;
+            let content = (0..lines)
+                .map(|_| (0..2 + next() % 8).map(|_| WORDS[next() % WORDS.len()]).collect::<Vec<_>>().join(" "))
+                .collect::<Vec<_>>()
+                .join("\n");
+            storage
+                .save_snippet(&content, FOLDERS[i % FOLDERS.len()], SnippetMeta::default())
+                .expect("save snippet");
+        }
+        let mut snippets = storage.load_all_snippets().expect("load snippets");
+        sort_snippets(&mut snippets);
+        Self { _dir: dir, storage, snippets }
+    }
+}
+
+fn benches(c: &mut Criterion) {
+    for count in SIZES {
+        let mut library = Library::generate(count);
+
+        let mut load = c.benchmark_group("load");
+        load.sample_size(10);
+        load.bench_function(BenchmarkId::from_parameter(count), |b| {
+            b.iter(|| black_box(library.storage.load_all_snippets().expect("load snippets")))
+        });
+        load.finish();
+
+        let mut search = c.benchmark_group("search");
+        for (name, kind, text) in [
+            ("substring", SearchProviderKind::Substring, "docker compose"),
+            ("fuzzy", SearchProviderKind::Fuzzy, "dcp"),
+        ] {
+            let mut provider = search_provider::build(&[kind]);
+            provider.index(&library.snippets);
+            let query = SearchQuery::parse(text);
+            search.bench_with_input(BenchmarkId::new(name, count), &query, |b, query| {
+                b.iter(|| black_box(provider.query(query, &library.snippets)))
+            });
+        }
+        let provider = search_provider::build(&[SearchProviderKind::Substring]);
+        let query = SearchQuery::parse("folder:work tunnel");
+        search.bench_with_input(BenchmarkId::new("filtered", count), &query, |b, query| {
+            b.iter(|| black_box(provider.query(query, &library.snippets)))
+        });
+        search.finish();
+
+        // What an edit costs: writing it, then re-sorting and re-indexing
+        // the list the window shows.
+        let mut update = c.benchmark_group("update");
+        update.sample_size(20);
+        let mut provider = search_provider::build(&[SearchProviderKind::Substring, SearchProviderKind::Fuzzy]);
+        let mut edits = 0u64;
+        update.bench_function(BenchmarkId::from_parameter(count), |b| {
+            b.iter(|| {
+                edits += 1;
+                let snippet = &mut library.snippets[0];
+                let content = format!("{}\nedit {}", WORDS[edits as usize % WORDS.len()], edits);
+                library.storage.update_snippet(snippet, &content).expect("update snippet");
+                sort_snippets(&mut library.snippets);
+                provider.index(&library.snippets);
+            })
+        });
+        update.finish();
+    }
+}
+
+criterion_group!(storage, benches);
+criterion_main!(storage);
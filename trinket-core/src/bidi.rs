@@ -0,0 +1,304 @@
+//! Right-to-left text for a renderer that only lays glyphs out left to
+//! right. egui has neither the Unicode bidi algorithm nor Arabic shaping, so
+//! read-only text is turned into its visual form first: each line's RTL
+//! runs are reversed with numbers kept in reading order, brackets mirrored
+//! and Arabic letters swapped for their joined presentation forms. This is
+//! a simplification of the algorithm that gets plain Hebrew and Arabic,
+//! mixed with Latin words and numbers, right.
+
+use std::borrow::Cow;
+
+/// Explicit embeddings, overrides and isolates. egui can't honour them and a
+/// cut title may leave one unterminated, so they're dropped for display.
+const CONTROLS: [char; 9] = [
+    '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}', '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Left,
+    Right,
+    Number,
+    Neutral,
+}
+
+fn class(c: char) -> Class {
+    match c {
+        // Some Arabic marks count as alphabetic, so they're sorted out first.
+        c if is_mark(c) => Class::Neutral,
+        '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}' => {
+            if ('\u{0660}'..='\u{0669}').contains(&c) || ('\u{06F0}'..='\u{06F9}').contains(&c) {
+                Class::Number
+            } else {
+                Class::Right
+            }
+        }
+        c if c.is_ascii_digit() => Class::Number,
+        c if c.is_alphabetic() => Class::Left,
+        _ => Class::Neutral,
+    }
+}
+
+/// Hebrew points and Arabic vowel marks, which sit on the letter before
+/// them rather than taking a place of their own.
+fn is_mark(c: char) -> bool {
+    matches!(c, '\u{0591}'..='\u{05BD}' | '\u{05BF}' | '\u{05C1}'..='\u{05C2}' | '\u{05C4}'..='\u{05C5}' | '\u{05C7}'
+        | '\u{0610}'..='\u{061A}' | '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06ED}')
+}
+
+/// Whether `text` holds any right-to-left letters.
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| class(c) == Class::Right)
+}
+
+/// Whether `text` reads right to left, judged by its first letter.
+pub fn is_rtl(text: &str) -> bool {
+    text.chars().map(class).find(|class| matches!(class, Class::Left | Class::Right)) == Some(Class::Right)
+}
+
+/// Where to cut `text` to keep at most `max_chars` characters, or `None` when
+/// it is short enough. Marks don't count, so the cut never parts a letter
+/// from its vowels.
+pub fn clip_at(text: &str, max_chars: usize) -> Option<usize> {
+    let mut letters = text.char_indices().filter(|&(_, c)| !is_mark(c) && c != '\u{200D}');
+    letters.nth(max_chars).map(|(end, _)| end)
+}
+
+/// At most `max_chars` characters of `text`, with an ellipsis where it was
+/// cut. In RTL text the ellipsis lands on the left once laid out.
+pub fn clip(text: &str, max_chars: usize) -> Cow<'_, str> {
+    match clip_at(text, max_chars) {
+        Some(end) => Cow::Owned(format!("{}…", &text[..end])),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// `text` in the order it should be drawn left to right, line by line.
+/// Text without RTL letters comes back as it is.
+pub fn visual(text: &str) -> Cow<'_, str> {
+    if !has_rtl(text) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.split('\n').map(visual_line).collect::<Vec<_>>().join("\n"))
+}
+
+fn visual_line(line: &str) -> String {
+    let chars: Vec<char> = shape_arabic(line).into_iter().filter(|c| !CONTROLS.contains(c)).collect();
+    let rtl = is_rtl(line);
+    let base: u8 = if rtl { 1 } else { 0 };
+    let classes: Vec<Class> = chars.iter().map(|&c| class(c)).collect();
+
+    // Letters take their own direction; numbers read left to right but sit
+    // in the run of RTL text around them.
+    let mut levels = vec![base; chars.len()];
+    let mut last_strong = if rtl { Class::Right } else { Class::Left };
+    for (i, class) in classes.iter().enumerate() {
+        levels[i] = match class {
+            Class::Left => {
+                last_strong = Class::Left;
+                if rtl { 2 } else { 0 }
+            }
+            Class::Right => {
+                last_strong = Class::Right;
+                1
+            }
+            Class::Number if rtl || last_strong == Class::Right => 2,
+            Class::Number => 0,
+            Class::Neutral => base,
+        };
+    }
+    // Marks belong to the letter before them.
+    for i in 1..chars.len() {
+        if is_mark(chars[i]) {
+            levels[i] = levels[i - 1];
+        }
+    }
+    // Spaces and punctuation between two runs of one direction join them;
+    // anywhere else they follow the line. Numbers count as RTL here.
+    let direction = |level: u8, class: Class| if (class == Class::Number && level == 2) || level % 2 == 1 { 1 } else { 0 };
+    let mut i = 0;
+    while i < chars.len() {
+        if classes[i] != Class::Neutral || is_mark(chars[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && classes[i] == Class::Neutral && !is_mark(chars[i]) {
+            i += 1;
+        }
+        let before = start.checked_sub(1).map_or(base, |j| direction(levels[j], classes[j]));
+        let after = if i < chars.len() { direction(levels[i], classes[i]) } else { base };
+        let level = match (before == after, before) {
+            (true, 1) => 1,
+            (true, _) if rtl => 2,
+            (true, _) => 0,
+            (false, _) => base,
+        };
+        levels[start..i].fill(level);
+    }
+
+    let mut order: Vec<usize> = (0..chars.len()).collect();
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    for level in (1..=highest).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < order.len() && levels[order[i]] >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+    }
+    // A reversed letter must still be followed by its marks.
+    let mut i = 0;
+    while i < order.len() {
+        let start = i;
+        while i + 1 < order.len() && is_mark(chars[order[i]]) && order[i + 1] + 1 == order[i] {
+            i += 1;
+        }
+        if i > start {
+            order[start..=i].reverse();
+        }
+        i += 1;
+    }
+    order.into_iter()
+        .map(|i| if levels[i] % 2 == 1 { mirrored(chars[i]) } else { chars[i] })
+        .collect()
+}
+
+fn mirrored(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => c,
+    }
+}
+
+/// Arabic letters with their isolated presentation form and whether they
+/// join on both sides; the others join only to the letter before them.
+/// Forms follow the isolated one: final, then initial and medial.
+const ARABIC: [(char, u32, bool); 36] = [
+    ('\u{0621}', 0xFE80, false),
+    ('\u{0622}', 0xFE81, false),
+    ('\u{0623}', 0xFE83, false),
+    ('\u{0624}', 0xFE85, false),
+    ('\u{0625}', 0xFE87, false),
+    ('\u{0626}', 0xFE89, true),
+    ('\u{0627}', 0xFE8D, false),
+    ('\u{0628}', 0xFE8F, true),
+    ('\u{0629}', 0xFE93, false),
+    ('\u{062A}', 0xFE95, true),
+    ('\u{062B}', 0xFE99, true),
+    ('\u{062C}', 0xFE9D, true),
+    ('\u{062D}', 0xFEA1, true),
+    ('\u{062E}', 0xFEA5, true),
+    ('\u{062F}', 0xFEA9, false),
+    ('\u{0630}', 0xFEAB, false),
+    ('\u{0631}', 0xFEAD, false),
+    ('\u{0632}', 0xFEAF, false),
+    ('\u{0633}', 0xFEB1, true),
+    ('\u{0634}', 0xFEB5, true),
+    ('\u{0635}', 0xFEB9, true),
+    ('\u{0636}', 0xFEBD, true),
+    ('\u{0637}', 0xFEC1, true),
+    ('\u{0638}', 0xFEC5, true),
+    ('\u{0639}', 0xFEC9, true),
+    ('\u{063A}', 0xFECD, true),
+    ('\u{0641}', 0xFED1, true),
+    ('\u{0642}', 0xFED5, true),
+    ('\u{0643}', 0xFED9, true),
+    ('\u{0644}', 0xFEDD, true),
+    ('\u{0645}', 0xFEE1, true),
+    ('\u{0646}', 0xFEE5, true),
+    ('\u{0647}', 0xFEE9, true),
+    ('\u{0648}', 0xFEED, false),
+    ('\u{0649}', 0xFEEF, false),
+    ('\u{064A}', 0xFEF1, true),
+];
+
+const TATWEEL: char = '\u{0640}';
+const LAM: char = '\u{0644}';
+
+fn arabic_forms(c: char) -> Option<(u32, bool)> {
+    ARABIC.iter().find(|(letter, _, _)| *letter == c).map(|&(_, isolated, dual)| (isolated, dual))
+}
+
+/// Whether `c` connects to the letter after it.
+fn joins_forward(c: char) -> bool {
+    c == TATWEEL || arabic_forms(c).is_some_and(|(_, dual)| dual)
+}
+
+/// Whether `c` connects to the letter before it. Hamza stands alone.
+fn joins_backward(c: char) -> bool {
+    c == TATWEEL || arabic_forms(c).is_some_and(|_| c != '\u{0621}')
+}
+
+/// Lam followed by an alef, as a ligature: isolated form, final after it.
+fn lam_alef(alef: char) -> Option<u32> {
+    match alef {
+        '\u{0622}' => Some(0xFEF5),
+        '\u{0623}' => Some(0xFEF7),
+        '\u{0625}' => Some(0xFEF9),
+        '\u{0627}' => Some(0xFEFB),
+        _ => None,
+    }
+}
+
+/// Arabic letters replaced by the form their neighbours call for.
+fn shape_arabic(line: &str) -> Vec<char> {
+    let chars: Vec<char> = line.chars().collect();
+    // Neighbouring letters, looking past vowel marks.
+    let neighbour = |from: usize, forward: bool| -> Option<(usize, char)> {
+        let mut i = from;
+        loop {
+            i = if forward { i + 1 } else { i.checked_sub(1)? };
+            let c = *chars.get(i)?;
+            if !is_mark(c) {
+                return Some((i, c));
+            }
+        }
+    };
+    let mut shaped = Vec::with_capacity(chars.len());
+    let mut skip = None;
+    for (i, &c) in chars.iter().enumerate() {
+        if skip == Some(i) {
+            continue;
+        }
+        let Some((isolated, dual)) = arabic_forms(c) else {
+            shaped.push(c);
+            continue;
+        };
+        let after_joiner = joins_backward(c) && neighbour(i, false).is_some_and(|(_, before)| joins_forward(before));
+        let next = neighbour(i, true);
+        if c == LAM {
+            if let Some((j, ligature)) = next.and_then(|(j, alef)| Some((j, lam_alef(alef)?))) {
+                shaped.push(char::from_u32(ligature + u32::from(after_joiner)).unwrap_or(c));
+                // Marks between lam and alef stay on the ligature.
+                shaped.extend(&chars[i + 1..j]);
+                skip = Some(j);
+                continue;
+            }
+        }
+        let before_joiner = dual && next.is_some_and(|(_, after)| joins_backward(after));
+        let offset = match (after_joiner, before_joiner) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        shaped.push(char::from_u32(isolated + offset).unwrap_or(c));
+    }
+    shaped
+}
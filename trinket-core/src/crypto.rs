@@ -0,0 +1,307 @@
+//! Keys for encrypting snippet content at rest with age. Only built with
+//! the `vault` feature.
+//!
+//! A vault is readable by the identities in its identity file and written
+//! for their public keys plus any extra recipients, such as a teammate's
+//! X25519 key, an SSH key or a hardware key through an age plugin. Sharing
+//! the storage folder through a sync tool then shares the vault only with
+//! those recipients.
+//!
+//! Folders can have vaults of their own. Their identity is either a file
+//! like the main vault's or kept inside the folder, encrypted with a
+//! passphrase.
+
+use age::secrecy::{ExposeSecret, SecretString};
+use chrono::Local;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Why a vault couldn't be opened, or content couldn't be encrypted or
+/// decrypted with it.
+#[derive(Debug, Error)]
+pub enum VaultError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("the identity file holds no age identities")]
+    NoIdentities,
+    #[error("invalid age identity: {0}")]
+    BadIdentity(&'static str),
+    #[error("no recipients: list the hardware key's recipient in the recipients file")]
+    NoRecipients,
+    #[error("not an age recipient: {0}")]
+    BadRecipient(String),
+    #[error("not encrypted with a passphrase")]
+    NotPassphraseEncrypted,
+    #[error("wrong passphrase")]
+    WrongPassphrase,
+    #[error("the key file is not an identity file")]
+    NotText,
+    #[error(transparent)]
+    Encrypt(#[from] age::EncryptError),
+    #[error(transparent)]
+    Decrypt(#[from] age::DecryptError),
+}
+
+impl From<VaultError> for std::io::Error {
+    fn from(error: VaultError) -> Self {
+        let kind = match &error {
+            VaultError::Io(e) => e.kind(),
+            VaultError::WrongPassphrase => std::io::ErrorKind::PermissionDenied,
+            VaultError::Encrypt(_) => std::io::ErrorKind::Other,
+            _ => std::io::ErrorKind::InvalidData,
+        };
+        match error {
+            VaultError::Io(e) => e,
+            error => std::io::Error::new(kind, error),
+        }
+    }
+}
+
+/// Identities and recipients are `Send`, so storage holding a vault can
+/// run on a background thread.
+pub struct Vault {
+    identities: Vec<Box<dyn age::Identity + Send + Sync>>,
+    recipients: Vec<Box<dyn age::Recipient + Send + Sync>>,
+}
+
+impl Vault {
+    /// Opens the vault with the identities in `identity_file`, creating a
+    /// new X25519 identity there on first use. `recipients_file` lists extra
+    /// recipients one per line; `#` starts a comment.
+    pub fn open(identity_file: &Path, recipients_file: Option<&Path>) -> Result<Self, VaultError> {
+        let identities = if identity_file.exists() {
+            fs::read_to_string(identity_file)?
+        } else {
+            log::info!("Creating vault identity {}", identity_file.display());
+            create_identity_file(identity_file)?
+        };
+        Self::from_keys(&identities, &read_recipients(recipients_file)?)
+    }
+
+    /// Opens a vault whose identity is stored in `key_file`, encrypted with
+    /// `passphrase`. The first call creates the identity and so sets the
+    /// passphrase. Only the key file pays for the slow passphrase
+    /// derivation; content is encrypted to the identity inside it.
+    pub fn open_with_passphrase(key_file: &Path, passphrase: SecretString, recipients_file: Option<&Path>) -> Result<Self, VaultError> {
+        let identities = if key_file.exists() {
+            let plaintext = decrypt_with_passphrase(&fs::read(key_file)?, passphrase)?;
+            String::from_utf8(plaintext).map_err(|_| VaultError::NotText)?
+        } else {
+            log::info!("Creating folder key {}", key_file.display());
+            let identities = new_identity();
+            write_private(key_file, &encrypt_with_passphrase(identities.as_bytes(), passphrase)?)?;
+            identities
+        };
+        Self::from_keys(&identities, &read_recipients(recipients_file)?)
+    }
+
+    /// Opens the vault with the identities kept in the OS keychain. On first
+    /// use they are copied from `identity_file` when it exists, or a new
+    /// X25519 identity is created.
+    #[cfg(feature = "keychain")]
+    pub fn open_keychain(identity_file: Option<&Path>, recipients_file: Option<&Path>) -> Result<Self, VaultError> {
+        use crate::keychain;
+
+        let identities = match keychain::load(keychain::VAULT_IDENTITY)? {
+            Some(identities) => identities,
+            None => {
+                let identities = match identity_file.filter(|path| path.exists()) {
+                    Some(path) => {
+                        log::info!("Copying vault identity {} into the keychain; the file can now be deleted", path.display());
+                        fs::read_to_string(path)?
+                    }
+                    None => {
+                        log::info!("Creating vault identity in the keychain");
+                        new_identity()
+                    }
+                };
+                // Check the identities before they replace the file.
+                Self::from_keys(&identities, &read_recipients(recipients_file)?)?;
+                keychain::store(keychain::VAULT_IDENTITY, &identities)?;
+                identities
+            }
+        };
+        Self::from_keys(&identities, &read_recipients(recipients_file)?)
+    }
+
+    /// Builds a vault from the text of an identity file and a recipients
+    /// file, wherever those are kept.
+    pub fn from_keys(identities: &str, recipients: &str) -> Result<Self, VaultError> {
+        let mut own = Vec::new();
+        for line in identities.lines().map(str::trim) {
+            if line.starts_with("AGE-SECRET-KEY-") {
+                let identity = age::x25519::Identity::from_str(line).map_err(VaultError::BadIdentity)?;
+                own.push(identity.to_public().to_string());
+            }
+        }
+        let identities = parse_identities(identities)?;
+        if identities.is_empty() {
+            return Err(VaultError::NoIdentities);
+        }
+
+        let lines = own.iter().map(String::as_str).chain(recipients.lines());
+        let recipients = parse_recipients(lines)?;
+        if recipients.is_empty() {
+            // Plugin identities don't reveal their recipient.
+            return Err(VaultError::NoRecipients);
+        }
+        Ok(Self { identities, recipients })
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, VaultError> {
+        let recipients = self.recipients.iter().map(|recipient| recipient.as_ref() as &dyn age::Recipient);
+        let encryptor = age::Encryptor::with_recipients(recipients)?;
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + 512);
+        let mut writer = encryptor.wrap_output(&mut ciphertext)?;
+        writer.write_all(plaintext)?;
+        writer.finish()?;
+        Ok(ciphertext)
+    }
+}
+
+/// Decrypts content written by any of `vaults`, so files keep their key
+/// wherever they are moved, such as into the trash.
+pub fn decrypt<'a>(vaults: impl IntoIterator<Item = &'a Vault>, ciphertext: &[u8]) -> Result<Vec<u8>, VaultError> {
+    let decryptor = age::Decryptor::new(ciphertext)?;
+    let identities = vaults.into_iter()
+        .flat_map(|vault| vault.identities.iter())
+        .map(|identity| identity.as_ref() as &dyn age::Identity);
+    let mut reader = decryptor.decrypt(identities)?;
+    // Plaintext is never longer than the ciphertext, so the buffer isn't
+    // reallocated and no stray copies are left in freed memory.
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    reader.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// Encrypts with a passphrase alone, for files that leave the vault, such
+/// as export archives and the keys of passphrase folders.
+pub fn encrypt_with_passphrase(plaintext: &[u8], passphrase: SecretString) -> Result<Vec<u8>, VaultError> {
+    let encryptor = age::Encryptor::with_user_passphrase(passphrase);
+    let mut ciphertext = Vec::with_capacity(plaintext.len() + 512);
+    let mut writer = encryptor.wrap_output(&mut ciphertext)?;
+    writer.write_all(plaintext)?;
+    writer.finish()?;
+    Ok(ciphertext)
+}
+
+pub fn decrypt_with_passphrase(ciphertext: &[u8], passphrase: SecretString) -> Result<Vec<u8>, VaultError> {
+    let decryptor = age::Decryptor::new(ciphertext)?;
+    if !decryptor.is_scrypt() {
+        return Err(VaultError::NotPassphraseEncrypted);
+    }
+    let identity = age::scrypt::Identity::new(passphrase);
+    let mut reader = decryptor.decrypt(std::iter::once(&identity as &dyn age::Identity)).map_err(|e| match e {
+        age::DecryptError::DecryptionFailed | age::DecryptError::NoMatchingKeys => VaultError::WrongPassphrase,
+        e => VaultError::Decrypt(e),
+    })?;
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    reader.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// Accepts X25519 (`AGE-SECRET-KEY-1…`) and plugin (`AGE-PLUGIN-…`)
+/// identities, as an age identity file holds. Each plugin is started once
+/// for all of its identities.
+fn parse_identities(text: &str) -> Result<Vec<Box<dyn age::Identity + Send + Sync>>, VaultError> {
+    let mut identities: Vec<Box<dyn age::Identity + Send + Sync>> = Vec::new();
+    let mut plugin_identities: Vec<age::plugin::Identity> = Vec::new();
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        if let Ok(identity) = age::x25519::Identity::from_str(line) {
+            identities.push(Box::new(identity));
+        } else if let Ok(identity) = age::plugin::Identity::from_str(line) {
+            plugin_identities.push(identity);
+        } else {
+            // The line is a secret, so it isn't repeated in the error.
+            return Err(VaultError::BadIdentity("unrecognized line in the identity file"));
+        }
+    }
+
+    let mut plugins: Vec<String> = plugin_identities.iter().map(|i| i.plugin().to_string()).collect();
+    plugins.sort();
+    plugins.dedup();
+    for plugin in plugins {
+        let identity = age::plugin::IdentityPluginV1::new(&plugin, &plugin_identities, age::NoCallbacks)?;
+        identities.push(Box::new(identity));
+    }
+
+    Ok(identities)
+}
+
+/// Accepts X25519 (`age1…`), SSH and plugin (`age1yubikey1…`) recipients.
+/// Each plugin is started once for all of its recipients.
+fn parse_recipients<'a>(lines: impl Iterator<Item = &'a str>) -> Result<Vec<Box<dyn age::Recipient + Send + Sync>>, VaultError> {
+    let mut recipients: Vec<Box<dyn age::Recipient + Send + Sync>> = Vec::new();
+    let mut plugin_recipients: Vec<age::plugin::Recipient> = Vec::new();
+
+    for line in lines.map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        if let Ok(recipient) = age::x25519::Recipient::from_str(line) {
+            recipients.push(Box::new(recipient));
+        } else if let Ok(recipient) = age::ssh::Recipient::from_str(line) {
+            recipients.push(Box::new(recipient));
+        } else if let Ok(recipient) = age::plugin::Recipient::from_str(line) {
+            plugin_recipients.push(recipient);
+        } else {
+            return Err(VaultError::BadRecipient(line.to_string()));
+        }
+    }
+
+    let mut plugins: Vec<String> = plugin_recipients.iter().map(|r| r.plugin().to_string()).collect();
+    plugins.sort();
+    plugins.dedup();
+    for plugin in plugins {
+        let targets: Vec<age::plugin::Recipient> = plugin_recipients.iter()
+            .filter(|recipient| recipient.plugin() == plugin)
+            .cloned()
+            .collect();
+        let recipient = age::plugin::RecipientPluginV1::new(&plugin, &targets, &[], age::NoCallbacks)?;
+        recipients.push(Box::new(recipient));
+    }
+
+    Ok(recipients)
+}
+
+fn read_recipients(recipients_file: Option<&Path>) -> Result<String, std::io::Error> {
+    match recipients_file {
+        Some(path) => fs::read_to_string(path),
+        None => Ok(String::new()),
+    }
+}
+
+/// A fresh X25519 identity in identity file format.
+fn new_identity() -> String {
+    let identity = age::x25519::Identity::generate();
+    format!(
+        "# created: {}\n# public key: {}\n{}\n",
+        Local::now().to_rfc3339(),
+        identity.to_public(),
+        identity.to_string().expose_secret(),
+    )
+}
+
+/// Writes a fresh X25519 identity, readable only by the current user.
+fn create_identity_file(path: &Path) -> Result<String, std::io::Error> {
+    let text = new_identity();
+    write_private(path, text.as_bytes())?;
+    Ok(text)
+}
+
+/// Creates `path` readable only by the current user; never overwrites.
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), std::io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
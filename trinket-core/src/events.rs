@@ -0,0 +1,60 @@
+//! Snippet lifecycle events. Whoever changes the library publishes what
+//! happened once, and every part that keeps something derived from it (the
+//! list a window shows, a search index, usage counts, notifications)
+//! subscribes instead of being updated by hand at each call site.
+
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::storage::Snippet;
+
+#[derive(Debug, Clone)]
+pub enum SnippetEvent {
+    /// Saved for the first time, or restored from the trash.
+    Created(Snippet),
+    /// Saved again with new content or metadata; replaces the snippet with
+    /// the same id.
+    Updated(Snippet),
+    /// Trashed, erased or expired.
+    Deleted { id: String },
+    /// Put on the clipboard.
+    Copied { id: String },
+}
+
+impl SnippetEvent {
+    pub fn id(&self) -> &str {
+        match self {
+            SnippetEvent::Created(snippet) | SnippetEvent::Updated(snippet) => &snippet.id,
+            SnippetEvent::Deleted { id } | SnippetEvent::Copied { id } => id,
+        }
+    }
+}
+
+/// Hands every published event to each subscriber, in the order published.
+/// Clones share the same subscribers, so a background thread can publish
+/// too.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<mpsc::Sender<SnippetEvent>>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events published from now on. Dropping the receiver unsubscribes.
+    pub fn subscribe(&self) -> mpsc::Receiver<SnippetEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap_or_else(PoisonError::into_inner).push(sender);
+        receiver
+    }
+
+    pub fn publish(&self, event: SnippetEvent) {
+        log::debug!("Snippet event for {}", event.id());
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
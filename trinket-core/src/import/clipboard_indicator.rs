@@ -0,0 +1,34 @@
+//! GNOME Clipboard Indicator keeps its history as a JSON array in
+//! `registry.txt`: objects with the text, its MIME type and whether it was
+//! starred, or bare strings in versions before images were kept.
+
+use serde::Deserialize;
+use std::path::Path;
+
+use super::{ImportError, Imported};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Text(String),
+    Item {
+        contents: String,
+        #[serde(default)]
+        favorite: bool,
+        #[serde(default)]
+        mimetype: Option<String>,
+    },
+}
+
+/// Starred entries come in pinned; images, stored as file names, are left out.
+pub fn read(path: &Path) -> Result<Vec<Imported>, ImportError> {
+    let entries: Vec<Entry> = serde_json::from_str(&std::fs::read_to_string(path)?)
+        .map_err(|e| ImportError::Format(format!("{} isn't a Clipboard Indicator history: {}", path.display(), e)))?;
+    Ok(entries.into_iter()
+        .filter_map(|entry| match entry {
+            Entry::Text(content) => Some(Imported { content, ..Imported::default() }),
+            Entry::Item { mimetype: Some(mime), .. } if !mime.starts_with("text/") => None,
+            Entry::Item { contents, favorite, .. } => Some(Imported { content: contents, pinned: favorite, ..Imported::default() }),
+        })
+        .collect())
+}
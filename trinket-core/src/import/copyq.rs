@@ -0,0 +1,65 @@
+//! CopyQ's tab files are a Qt binary format, so the items are asked of the
+//! running CopyQ through its `copyq` command instead, as JSON.
+
+use serde::Deserialize;
+use std::process::Command;
+
+use super::{ImportError, Imported};
+
+/// Walks every tab and prints one JSON array of their text items.
+const SCRIPT: &str = r#"
+var items = [];
+var tabs = tab();
+for (var t = 0; t < tabs.length; ++t) {
+    tab(tabs[t]);
+    for (var i = 0; i < size(); ++i) {
+        items.push({
+            tab: tabs[t],
+            text: str(read(i)),
+            notes: str(read(mimeItemNotes, i)),
+            tags: str(read("application/x-copyq-tags", i)),
+        });
+    }
+}
+print(JSON.stringify(items));
+"#;
+
+#[derive(Deserialize)]
+struct Item {
+    tab: String,
+    text: String,
+    notes: String,
+    tags: String,
+}
+
+/// Each tab becomes a tag, next to the item's own tags; item notes become
+/// the snippet's notes. Items without text, such as images, are left out.
+pub fn read() -> Result<Vec<Imported>, ImportError> {
+    let output = Command::new("copyq")
+        .args(["eval", "--", SCRIPT])
+        .output()
+        .map_err(|e| ImportError::Format(format!("Could not run copyq; is CopyQ installed? ({})", e)))?;
+    if !output.status.success() {
+        return Err(ImportError::Format(format!(
+            "copyq failed; is CopyQ running? {}",
+            String::from_utf8_lossy(&output.stderr).trim(),
+        )));
+    }
+    let items: Vec<Item> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| ImportError::Format(format!("Could not read what copyq printed: {}", e)))?;
+
+    Ok(items.into_iter()
+        .filter(|item| !item.text.is_empty())
+        .map(|item| {
+            // `&` marks the tab's keyboard shortcut, as in "&clipboard".
+            let tab = item.tab.replace('&', "");
+            let tags = std::iter::once(tab.as_str())
+                .chain(item.tags.split([',', '\n']))
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+            Imported { content: item.text, notes: item.notes, tags, ..Imported::default() }
+        })
+        .collect())
+}
@@ -0,0 +1,102 @@
+//! Ditto keeps its clips in an SQLite database, `Ditto.db`: one `Main` row
+//! per clip or group, and the clip's clipboard formats in `Data`.
+
+use rusqlite::{Connection, OpenFlags};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{ImportError, Imported};
+
+impl From<rusqlite::Error> for ImportError {
+    fn from(error: rusqlite::Error) -> Self {
+        ImportError::Format(format!("Could not read the Ditto database: {}", error))
+    }
+}
+
+/// The group a clip is in becomes a tag, as does its quick paste text. A
+/// description edited in Ditto becomes the title. Clips without text, such
+/// as images and files, are left out.
+pub fn read(path: &Path) -> Result<Vec<Imported>, ImportError> {
+    // Read-only, so a running Ditto keeps the database to itself.
+    let db = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let groups: HashMap<i64, String> = db
+        .prepare("SELECT lID, mText FROM Main WHERE bIsGroup = 1")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let mut clips = db.prepare(
+        "SELECT Main.mText, Main.lParentID, Main.QuickPasteText, Data.ooData
+         FROM Main LEFT JOIN Data ON Data.lParentID = Main.lID AND Data.strClipBoardFormat = 'CF_UNICODETEXT'
+         WHERE Main.bIsGroup = 0
+         ORDER BY Main.lDate DESC",
+    )?;
+    let rows = clips.query_map([], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, Option<i64>>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<Vec<u8>>>(3)?,
+        ))
+    })?;
+
+    let mut imported = Vec::new();
+    for row in rows {
+        let (description, group, quick_paste, data) = row?;
+        let description = description.unwrap_or_default();
+        let text = data.map(|bytes| utf16_text(&bytes));
+        let Some(content) = text.clone().or((!description.is_empty()).then(|| description.clone())) else {
+            continue;
+        };
+        let edited = text.is_some() && description != content && !description.contains('\n');
+        let tags = group.and_then(|id| groups.get(&id).cloned())
+            .into_iter()
+            .chain(quick_paste)
+            .filter(|tag| !tag.trim().is_empty())
+            .collect();
+        imported.push(Imported {
+            content,
+            title: edited.then_some(description),
+            tags,
+            ..Imported::default()
+        });
+    }
+    Ok(imported)
+}
+
+/// CF_UNICODETEXT is UTF-16LE with a trailing NUL.
+fn utf16_text(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+    String::from_utf16_lossy(&units).trim_end_matches('\0').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn clips_take_their_group_as_a_tag() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("Ditto.db");
+        let db = Connection::open(&path).unwrap();
+        db.execute_batch(
+            "CREATE TABLE Main (lID INTEGER PRIMARY KEY, lDate INTEGER, mText TEXT, bIsGroup INTEGER, lParentID INTEGER, QuickPasteText TEXT);
+             CREATE TABLE Data (lID INTEGER PRIMARY KEY, lParentID INTEGER, strClipBoardFormat TEXT, ooData BLOB);
+             INSERT INTO Main VALUES (1, 0, 'Work', 1, -1, NULL);
+             INSERT INTO Main VALUES (2, 2, 'Standup notes', 0, 1, 'su');
+             INSERT INTO Main VALUES (3, 1, 'cargo test', 0, -1, NULL);",
+        ).unwrap();
+        let text: Vec<u8> = "Yesterday:\nToday:\0".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        db.execute("INSERT INTO Data VALUES (1, 2, 'CF_UNICODETEXT', ?1)", [text]).unwrap();
+        drop(db);
+
+        let clips = read(&path).unwrap();
+        assert_eq!(clips.len(), 2);
+        assert_eq!(clips[0].content, "Yesterday:\nToday:");
+        assert_eq!(clips[0].title.as_deref(), Some("Standup notes"));
+        assert_eq!(clips[0].tags, ["Work", "su"]);
+        assert_eq!(clips[1].content, "cargo test");
+        assert!(clips[1].title.is_none() && clips[1].tags.is_empty());
+    }
+}
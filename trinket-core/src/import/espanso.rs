@@ -0,0 +1,76 @@
+//! Espanso keeps its matches in YAML files under `match/`, each with a
+//! `matches` list of triggers and what they expand to.
+
+use serde::Deserialize;
+use std::path::Path;
+
+use super::{ImportError, Imported};
+
+#[derive(Deserialize)]
+struct MatchFile {
+    #[serde(default)]
+    matches: Vec<Match>,
+}
+
+#[derive(Deserialize)]
+struct Match {
+    trigger: Option<String>,
+    #[serde(default)]
+    triggers: Vec<String>,
+    regex: Option<String>,
+    label: Option<String>,
+    replace: Option<String>,
+    markdown: Option<String>,
+    html: Option<String>,
+}
+
+/// Reads a match file, or every `.yml` file in a folder and the folders in
+/// it. The label, or else the triggers, becomes the title, and the name of
+/// any file but `base.yml` a tag. `{{variables}}` are kept as they are;
+/// image matches are left out.
+pub fn read(path: &Path) -> Result<Vec<Imported>, ImportError> {
+    let mut files = Vec::new();
+    collect_files(path, &mut files)?;
+    files.sort();
+
+    let mut imported = Vec::new();
+    for file in files {
+        let parsed: MatchFile = serde_yaml::from_str(&std::fs::read_to_string(&file)?)
+            .map_err(|e| ImportError::Format(format!("{} isn't an Espanso match file: {}", file.display(), e)))?;
+        let tag = file.file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(|stem| stem.trim_start_matches('_'))
+            .filter(|&stem| stem != "base" && !stem.is_empty())
+            .map(str::to_string);
+        for m in parsed.matches {
+            let Some(content) = m.replace.or(m.markdown).or(m.html) else {
+                continue;
+            };
+            let triggers: Vec<String> = m.trigger.into_iter().chain(m.triggers).chain(m.regex).collect();
+            imported.push(Imported {
+                content,
+                title: m.label.or_else(|| (!triggers.is_empty()).then(|| triggers.join(", "))),
+                tags: tag.clone().into_iter().collect(),
+                ..Imported::default()
+            });
+        }
+    }
+    Ok(imported)
+}
+
+fn collect_files(path: &Path, files: &mut Vec<std::path::PathBuf>) -> Result<(), ImportError> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        let yaml = path.extension().is_some_and(|ext| ext == "yml" || ext == "yaml");
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if yaml {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
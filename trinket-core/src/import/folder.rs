@@ -0,0 +1,97 @@
+//! A folder of text and Markdown files, one snippet per file, titled by
+//! the file's name.
+
+use std::path::{Path, PathBuf};
+
+use super::{tag_from_name, ImportError, Imported};
+use crate::storage::folders::normalize_folder;
+
+/// Files with these extensions are read; others are listed as skipped.
+const TEXT_EXTENSIONS: &[&str] = &["txt", "text", "md", "markdown"];
+
+/// What the folders inside the imported one become.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subfolders {
+    /// Folders of the same names in the library.
+    Folders,
+    /// Tags on the snippets inside them, one per level.
+    Tags,
+}
+
+/// What importing a folder would do, for a look before anything is saved.
+#[derive(Debug, Default)]
+pub struct FolderPlan {
+    /// The library folder the snippets go into, named after the imported one.
+    pub folder: String,
+    pub items: Vec<Imported>,
+    /// Files left out because their content is already in the library.
+    pub duplicates: usize,
+    /// Other files left out, with why.
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+/// Walks `root` and plans a snippet for each text file in it. Hidden files
+/// and folders are passed over; files that aren't UTF-8, are empty or are
+/// over `max_bytes` are listed as skipped.
+pub fn plan(root: &Path, subfolders: Subfolders, max_bytes: usize) -> Result<FolderPlan, ImportError> {
+    let name = root.file_name().and_then(|name| name.to_str()).unwrap_or("imported");
+    let mut plan = FolderPlan {
+        folder: normalize_folder(name).unwrap_or_else(|_| "imported".to_string()),
+        ..FolderPlan::default()
+    };
+    walk(root, &[], subfolders, max_bytes, &mut plan)?;
+    Ok(plan)
+}
+
+fn walk(dir: &Path, parents: &[String], subfolders: Subfolders, max_bytes: usize, plan: &mut FolderPlan) -> Result<(), ImportError> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+
+    for path in entries {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()).map(str::to_string) else {
+            plan.skipped.push((path, "the name isn't UTF-8".to_string()));
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            let parents: Vec<String> = parents.iter().cloned().chain([name]).collect();
+            walk(&path, &parents, subfolders, max_bytes, plan)?;
+            continue;
+        }
+
+        let text = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| TEXT_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        if !text {
+            plan.skipped.push((path, "not a text or Markdown file".to_string()));
+            continue;
+        }
+        if std::fs::metadata(&path)?.len() > max_bytes as u64 {
+            plan.skipped.push((path, format!("larger than {} bytes", max_bytes)));
+            continue;
+        }
+        let Ok(content) = String::from_utf8(std::fs::read(&path)?) else {
+            plan.skipped.push((path, "not UTF-8 text".to_string()));
+            continue;
+        };
+        if content.trim().is_empty() {
+            plan.skipped.push((path, "empty".to_string()));
+            continue;
+        }
+
+        let title = path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string);
+        let (folder, tags) = match subfolders {
+            Subfolders::Folders => (parents.join("/"), Vec::new()),
+            Subfolders::Tags => (String::new(), parents.iter().filter_map(|name| tag_from_name(name)).collect()),
+        };
+        match normalize_folder(&folder) {
+            Ok(folder) => plan.items.push(Imported { content, title, tags, folder, ..Imported::default() }),
+            Err(e) => plan.skipped.push((path, e.to_string())),
+        }
+    }
+    Ok(())
+}
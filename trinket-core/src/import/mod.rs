@@ -0,0 +1,226 @@
+//! Reads the snippets and clipboard history of other apps, so switching to
+//! trinket doesn't mean starting over. Each source maps what it has onto a
+//! snippet's content, title, notes, tags and pin.
+
+mod clipboard_indicator;
+mod copyq;
+#[cfg(feature = "ditto")]
+mod ditto;
+mod espanso;
+pub mod folder;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::storage::tags::normalize_tag;
+use crate::storage::{FileStorage, Snippet, SnippetMeta, StorageError};
+use folder::{FolderPlan, Subfolders};
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("{0}")]
+    Format(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    CopyQ,
+    #[cfg(feature = "ditto")]
+    Ditto,
+    ClipboardIndicator,
+    Espanso,
+}
+
+impl ImportSource {
+    pub const ALL: &[ImportSource] = &[
+        ImportSource::CopyQ,
+        #[cfg(feature = "ditto")]
+        ImportSource::Ditto,
+        ImportSource::ClipboardIndicator,
+        ImportSource::Espanso,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportSource::CopyQ => "CopyQ",
+            #[cfg(feature = "ditto")]
+            ImportSource::Ditto => "Ditto",
+            ImportSource::ClipboardIndicator => "GNOME Clipboard Indicator",
+            ImportSource::Espanso => "Espanso",
+        }
+    }
+
+    /// The folder the snippets are imported into.
+    pub fn folder(self) -> &'static str {
+        match self {
+            ImportSource::CopyQ => "copyq",
+            #[cfg(feature = "ditto")]
+            ImportSource::Ditto => "ditto",
+            ImportSource::ClipboardIndicator => "clipboard-indicator",
+            ImportSource::Espanso => "espanso",
+        }
+    }
+
+    /// Whether the source is read from a file or folder; CopyQ is asked
+    /// through its command line instead.
+    pub fn reads_path(self) -> bool {
+        self != ImportSource::CopyQ
+    }
+
+    /// Where the app keeps its data when installed the usual way.
+    pub fn default_path(self) -> Option<PathBuf> {
+        match self {
+            ImportSource::CopyQ => None,
+            #[cfg(feature = "ditto")]
+            ImportSource::Ditto => dirs::config_dir().map(|dir| dir.join("Ditto").join("Ditto.db")),
+            ImportSource::ClipboardIndicator => {
+                dirs::cache_dir().map(|dir| dir.join("clipboard-indicator@tudmotu.com").join("registry.txt"))
+            }
+            ImportSource::Espanso => dirs::config_dir().map(|dir| dir.join("espanso").join("match")),
+        }
+    }
+
+    /// Reads every snippet the source holds. `path` is ignored for CopyQ.
+    pub fn read(self, path: &Path) -> Result<Vec<Imported>, ImportError> {
+        match self {
+            ImportSource::CopyQ => copyq::read(),
+            #[cfg(feature = "ditto")]
+            ImportSource::Ditto => ditto::read(path),
+            ImportSource::ClipboardIndicator => clipboard_indicator::read(path),
+            ImportSource::Espanso => espanso::read(path),
+        }
+    }
+}
+
+/// A snippet read from another app, not saved yet.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub content: String,
+    pub title: Option<String>,
+    pub notes: String,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    /// Below the folder the import goes into; empty for that folder itself.
+    pub folder: String,
+}
+
+impl FileStorage {
+    /// Saves what `source` holds into its folder. Clipboard histories repeat
+    /// themselves, so content already in the library or met earlier in the
+    /// import is skipped, which also makes importing again safe. Returns
+    /// the snippets saved.
+    pub fn import_from(&self, source: ImportSource, path: &Path) -> Result<Vec<Snippet>, ImportError> {
+        let (items, _) = self.new_items(source.read(path)?)?;
+        self.save_imported(items, source.folder())
+    }
+
+    /// Plans importing the text files under `root`, without saving
+    /// anything. Files whose content is already in the library are counted
+    /// in `duplicates` and left out of the plan.
+    pub fn plan_folder_import(&self, root: &Path, subfolders: Subfolders, max_bytes: usize) -> Result<FolderPlan, ImportError> {
+        let mut plan = folder::plan(root, subfolders, max_bytes)?;
+        (plan.items, plan.duplicates) = self.new_items(std::mem::take(&mut plan.items))?;
+        Ok(plan)
+    }
+
+    /// Saves a plan from [`FileStorage::plan_folder_import`], leaving out
+    /// what was added to the library since it was made.
+    pub fn import_folder(&self, plan: FolderPlan) -> Result<Vec<Snippet>, ImportError> {
+        let (items, _) = self.new_items(plan.items)?;
+        self.save_imported(items, &plan.folder)
+    }
+
+    /// Drops the items that are blank, already in the library or repeat an
+    /// earlier one. Returns the rest and how many were dropped.
+    fn new_items(&self, items: Vec<Imported>) -> Result<(Vec<Imported>, usize), StorageError> {
+        let existing = self.load_all_snippets()?;
+        let keep: Vec<bool> = {
+            let mut seen: HashSet<&str> = existing.iter().map(|snippet| snippet.content.as_str()).collect();
+            items.iter().map(|item| !item.content.trim().is_empty() && seen.insert(&item.content)).collect()
+        };
+        let dropped = keep.iter().filter(|&&keep| !keep).count();
+        let items = items.into_iter().zip(keep).filter_map(|(item, keep)| keep.then_some(item)).collect();
+        Ok((items, dropped))
+    }
+
+    /// Saves `items` under `folder`, with their tags made searchable.
+    fn save_imported(&self, items: Vec<Imported>, folder: &str) -> Result<Vec<Snippet>, ImportError> {
+        let mut saved = Vec::new();
+        for item in items {
+            let mut tags: Vec<String> = Vec::new();
+            for tag in item.tags.iter().filter_map(|tag| tag_from_name(tag)) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            let meta = SnippetMeta {
+                title: item.title.filter(|title| !title.trim().is_empty()),
+                notes: item.notes,
+                tags,
+                pinned: item.pinned,
+                ..SnippetMeta::default()
+            };
+            let folder = if item.folder.is_empty() { folder.to_string() } else { format!("{}/{}", folder, item.folder) };
+            saved.push(self.save_snippet(&item.content, &folder, meta)?);
+        }
+        Ok(saved)
+    }
+}
+
+/// A tab, group or folder name as a tag `tag:` can search for.
+fn tag_from_name(name: &str) -> Option<String> {
+    normalize_tag(&name.trim().replace(char::is_whitespace, "-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::temp_storage;
+
+    #[test]
+    fn importing_again_skips_what_is_already_there() {
+        let (dir, storage) = temp_storage();
+        let registry = dir.path().join("registry.txt");
+        std::fs::write(&registry, r#"[
+            {"contents": "ssh deploy@build", "favorite": true, "mimetype": "text/plain;charset=utf-8"},
+            {"contents": "ssh deploy@build", "favorite": false, "mimetype": "text/plain;charset=utf-8"},
+            {"contents": "b1946ac92492d2347c6235b4d2611184", "favorite": false, "mimetype": "image/png"},
+            "make release"
+        ]"#).unwrap();
+
+        let saved = storage.import_from(ImportSource::ClipboardIndicator, &registry).unwrap();
+        let contents: Vec<&str> = saved.iter().map(|snippet| snippet.content.as_str()).collect();
+        assert_eq!(contents, ["ssh deploy@build", "make release"]);
+        assert!(saved[0].meta.pinned && saved.iter().all(|snippet| snippet.folder == "clipboard-indicator"));
+
+        assert!(storage.import_from(ImportSource::ClipboardIndicator, &registry).unwrap().is_empty());
+    }
+
+    #[test]
+    fn folder_import_previews_before_saving() {
+        let (dir, storage) = temp_storage();
+        let notes = dir.path().join("notes");
+        std::fs::create_dir_all(notes.join("SQL Server")).unwrap();
+        std::fs::write(notes.join("greeting.txt"), "Hi there").unwrap();
+        std::fs::write(notes.join("SQL Server").join("Who is active.md"), "EXEC sp_WhoIsActive;").unwrap();
+        std::fs::write(notes.join("logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        storage.save_snippet("Hi there", "", SnippetMeta::default()).unwrap();
+
+        let plan = storage.plan_folder_import(&notes, Subfolders::Tags, 1024).unwrap();
+        assert_eq!(plan.folder, "notes");
+        assert_eq!(plan.items.len(), 1);
+        assert_eq!(plan.duplicates, 1);
+        assert_eq!(plan.skipped.len(), 1);
+        assert_eq!(storage.load_all_snippets().unwrap().len(), 1);
+
+        let saved = storage.import_folder(plan).unwrap();
+        assert_eq!(saved[0].meta.title.as_deref(), Some("Who is active"));
+        assert_eq!(saved[0].meta.tags, ["sql-server"]);
+        assert_eq!(saved[0].folder, "notes");
+    }
+}
@@ -0,0 +1,36 @@
+//! Secrets kept in the platform keychain: Windows Credential Manager, the
+//! macOS Keychain or a Secret Service provider such as GNOME Keyring. Only
+//! built with the `keychain` feature.
+
+const SERVICE: &str = "trinket";
+
+/// Keychain entry holding the vault's age identities.
+pub const VAULT_IDENTITY: &str = "vault-identity";
+
+/// Keychain entries for S3 sync: the bucket's access keys, and the age key
+/// its contents are encrypted with.
+#[cfg(feature = "s3")]
+pub const S3_CREDENTIALS: &str = "s3-credentials";
+#[cfg(feature = "s3")]
+pub const S3_KEY: &str = "s3-key";
+
+/// The secret stored under `name`, or `None` when there is none yet.
+pub fn load(name: &str) -> Result<Option<String>, std::io::Error> {
+    match entry(name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(to_io(e)),
+    }
+}
+
+pub fn store(name: &str, secret: &str) -> Result<(), std::io::Error> {
+    entry(name)?.set_password(secret).map_err(to_io)
+}
+
+fn entry(name: &str) -> Result<keyring::Entry, std::io::Error> {
+    keyring::Entry::new(SERVICE, name).map_err(to_io)
+}
+
+fn to_io(error: keyring::Error) -> std::io::Error {
+    std::io::Error::other(format!("keychain: {}", error))
+}
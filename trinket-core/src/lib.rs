@@ -0,0 +1,34 @@
+//! Trinket without its windows: snippet storage and search, clipboard
+//! transforms, encryption at rest and sync. Nothing here depends on the
+//! desktop shell, and only the optional picker widget on egui, so other
+//! frontends can build on it.
+
+use std::path::PathBuf;
+
+pub mod bidi;
+#[cfg(feature = "vault")]
+pub mod crypto;
+pub mod events;
+pub mod import;
+pub mod library_settings;
+#[cfg(feature = "keychain")]
+pub mod keychain;
+#[cfg(feature = "picker")]
+pub mod picker;
+pub mod storage;
+#[cfg(feature = "sync")]
+pub mod sync;
+pub mod sync_settings;
+pub mod transform;
+
+/// Where trinket keeps its config, keys and, by default, the library.
+pub fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("trinket")
+}
+
+/// The app config, which other frontends read the library settings from.
+pub fn config_path() -> PathBuf {
+    data_dir().join("config.json")
+}
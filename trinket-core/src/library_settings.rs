@@ -0,0 +1,79 @@
+//! The library settings kept in the app config: the vault, folders with
+//! keys of their own, the size limit and where backups go. Read here too so
+//! frontends other than the app, such as the C interface, open the library
+//! the way the app does.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::storage::folders::normalize_folder;
+use crate::storage::DEFAULT_MAX_SNIPPET_BYTES;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FolderVault {
+    /// age identity file for the folder, created on first use. Unset keeps
+    /// the identity inside the folder, encrypted with a passphrase that is
+    /// asked for when the folder is opened.
+    pub identity_file: Option<PathBuf>,
+    /// Further age recipients who can read the folder, one per line.
+    pub recipients_file: Option<PathBuf>,
+}
+
+/// The config fields that decide how the library is opened. Everything
+/// else in the file is ignored.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LibrarySettings {
+    pub max_snippet_bytes: usize,
+    pub backup_dir: Option<PathBuf>,
+    pub vault_identity_file: Option<PathBuf>,
+    pub vault_identity_in_keychain: bool,
+    pub folder_vaults: BTreeMap<String, FolderVault>,
+}
+
+impl Default for LibrarySettings {
+    fn default() -> Self {
+        Self {
+            max_snippet_bytes: DEFAULT_MAX_SNIPPET_BYTES,
+            backup_dir: None,
+            vault_identity_file: None,
+            vault_identity_in_keychain: false,
+            folder_vaults: BTreeMap::new(),
+        }
+    }
+}
+
+impl LibrarySettings {
+    /// Reads the settings from the config at `path`; a missing file gives
+    /// the defaults.
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether the whole library is meant to be encrypted at rest.
+    pub fn has_vault(&self) -> bool {
+        self.vault_identity_file.is_some() || self.vault_identity_in_keychain
+    }
+
+    pub fn backup_dir(&self) -> PathBuf {
+        self.backup_dir.clone().unwrap_or_else(|| crate::config_path().with_file_name("backups"))
+    }
+
+    /// Folders with keys of their own, by normalized folder path.
+    pub fn folder_vaults(&self) -> impl Iterator<Item = (String, &FolderVault)> {
+        self.folder_vaults.iter().filter_map(|(folder, folder_vault)| match normalize_folder(folder) {
+            Ok(normalized) if !normalized.is_empty() => Some((normalized, folder_vault)),
+            _ => {
+                log::warn!("Ignoring folder vault for '{}': not a folder", folder);
+                None
+            }
+        })
+    }
+}
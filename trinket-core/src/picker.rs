@@ -0,0 +1,217 @@
+//! The search box, result list and preview of trinket's window as a widget,
+//! for embedding a snippet picker in other egui apps. The library and the
+//! search provider stay with the caller; the picker only keeps the query
+//! and where the selection is.
+
+use egui::{Key, ScrollArea, TextEdit, Ui};
+
+use crate::bidi::clip;
+use crate::storage::{SearchProvider, SearchQuery, Snippet, SECRET_MASK};
+
+const ROW_HEIGHT: f32 = 20.0;
+const LIST_HEIGHT: f32 = 240.0;
+const PREVIEW_HEIGHT: f32 = 120.0;
+/// Longer titles are cut so the folder beside them stays in view.
+const TITLE_CHARS: usize = 80;
+
+/// A search box over a list of snippets. Typing narrows the list, Up and
+/// Down move through it, and Enter or a double click picks the selected
+/// snippet.
+pub struct SnippetPicker {
+    id_salt: egui::Id,
+    query: String,
+    /// Indices into the snippets last shown, best match first.
+    results: Vec<usize>,
+    selected: usize,
+    /// What `results` was found for: the query and how many snippets there
+    /// were. `None` after `invalidate` until the next search.
+    searched: Option<(SearchQuery, usize)>,
+    preview: bool,
+    list_height: f32,
+    focus: bool,
+}
+
+impl SnippetPicker {
+    /// `id_salt` keeps the ids apart when more than one picker is shown.
+    pub fn new(id_salt: impl std::hash::Hash) -> Self {
+        Self {
+            id_salt: egui::Id::new(id_salt),
+            query: String::new(),
+            results: Vec::new(),
+            selected: 0,
+            searched: None,
+            preview: true,
+            list_height: LIST_HEIGHT,
+            focus: false,
+        }
+    }
+
+    /// Leaves out the preview of the selected snippet.
+    pub fn without_preview(mut self) -> Self {
+        self.preview = false;
+        self
+    }
+
+    /// How tall the result list may grow before it scrolls.
+    pub fn list_height(mut self, height: f32) -> Self {
+        self.list_height = height;
+        self
+    }
+
+    /// Puts the cursor in the search box the next time it's shown.
+    pub fn focus(&mut self) {
+        self.focus = true;
+    }
+
+    /// Searches again the next time it's shown. Call it when the snippets
+    /// or the provider changed without the number of snippets changing.
+    pub fn invalidate(&mut self) {
+        self.searched = None;
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Clears the search box and goes back to the top of the list.
+    pub fn reset(&mut self) {
+        self.query.clear();
+        self.selected = 0;
+        self.searched = None;
+    }
+
+    /// Shows the picker over `snippets`, returning the index of the one
+    /// picked this frame.
+    pub fn show(&mut self, ui: &mut Ui, snippets: &[Snippet], search: &mut dyn SearchProvider) -> Option<usize> {
+        let mut picked = None;
+
+        let search_box = ui.add(
+            TextEdit::singleline(&mut self.query)
+                .id(self.id_salt.with("query"))
+                .hint_text("Search snippets…")
+                .desired_width(f32::INFINITY),
+        );
+        if std::mem::take(&mut self.focus) {
+            search_box.request_focus();
+        }
+        self.search(snippets, search);
+
+        let mut scroll_to_selected = false;
+        if search_box.has_focus() || search_box.lost_focus() {
+            ui.input_mut(|i| {
+                if i.consume_key(egui::Modifiers::NONE, Key::ArrowDown) {
+                    self.selected = (self.selected + 1).min(self.results.len().saturating_sub(1));
+                    scroll_to_selected = true;
+                }
+                if i.consume_key(egui::Modifiers::NONE, Key::ArrowUp) {
+                    self.selected = self.selected.saturating_sub(1);
+                    scroll_to_selected = true;
+                }
+            });
+            // A single-line edit gives up focus on Enter.
+            if search_box.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                picked = self.results.get(self.selected).copied();
+                search_box.request_focus();
+            }
+        }
+
+        if self.results.is_empty() {
+            ui.weak(if snippets.is_empty() { "No snippets yet" } else { "No matches" });
+            return picked;
+        }
+
+        ScrollArea::vertical()
+            .id_salt(self.id_salt.with("results"))
+            .max_height(self.list_height)
+            .auto_shrink([false, true])
+            .show_rows(ui, ROW_HEIGHT, self.results.len(), |ui, rows| {
+                for row in rows {
+                    let Some(snippet) = snippets.get(self.results[row]) else {
+                        continue;
+                    };
+                    ui.horizontal(|ui| {
+                        let response = ui.selectable_label(row == self.selected, clip(snippet.safe_title(), TITLE_CHARS).as_ref());
+                        if !snippet.folder.is_empty() {
+                            ui.weak(&snippet.folder);
+                        }
+                        if scroll_to_selected && row == self.selected {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            self.selected = row;
+                        }
+                        if response.double_clicked() {
+                            picked = Some(self.results[row]);
+                        }
+                    });
+                }
+            });
+
+        if self.preview {
+            if let Some(snippet) = self.results.get(self.selected).and_then(|&idx| snippets.get(idx)) {
+                ui.separator();
+                ScrollArea::vertical()
+                    .id_salt(self.id_salt.with("preview"))
+                    .max_height(PREVIEW_HEIGHT)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        let content = if snippet.meta.secret { SECRET_MASK } else { &snippet.content };
+                        ui.add(egui::Label::new(egui::RichText::new(content).monospace()).wrap());
+                    });
+            }
+        }
+
+        picked
+    }
+
+    /// Runs the search when the query or the snippets changed since the
+    /// last one. Libraries an embedding app shows are small enough to
+    /// search in one go, unlike the window's, which spreads it over frames.
+    fn search(&mut self, snippets: &[Snippet], search: &mut dyn SearchProvider) {
+        let query = SearchQuery::parse(&self.query);
+        if let Some((searched, count)) = &self.searched {
+            if *searched == query && *count == snippets.len() {
+                return;
+            }
+        }
+        if self.searched.as_ref().is_none_or(|(_, count)| *count != snippets.len()) {
+            search.index(snippets);
+        }
+        self.results = search.query(&query, snippets);
+        // A new query starts from its best match; the same one keeps the
+        // selection where it was.
+        if self.searched.as_ref().is_some_and(|(searched, _)| *searched != query) {
+            self.selected = 0;
+        }
+        self.selected = self.selected.min(self.results.len().saturating_sub(1));
+        self.searched = Some((query, snippets.len()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::search_provider::{self, SearchProviderKind};
+    use crate::storage::{temp_storage, SnippetMeta};
+
+    #[test]
+    fn new_query_starts_from_the_best_match() {
+        let (_dir, storage) = temp_storage();
+        for content in ["git push", "docker compose up", "git checkout"] {
+            storage.save_snippet(content, "", SnippetMeta::default()).unwrap();
+        }
+        let snippets = storage.load_all_snippets().unwrap();
+        let mut search = search_provider::build(&[SearchProviderKind::Substring]);
+        let mut picker = SnippetPicker::new("test");
+
+        picker.search(&snippets, search.as_mut());
+        assert_eq!(picker.results.len(), 3);
+        picker.selected = 2;
+
+        picker.query = "git".to_string();
+        picker.search(&snippets, search.as_mut());
+        assert_eq!(picker.results.len(), 2);
+        assert_eq!(picker.selected, 0);
+        assert!(picker.results.iter().all(|&idx| snippets[idx].content.starts_with("git")));
+    }
+}
@@ -0,0 +1,129 @@
+use age::secrecy::SecretString;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::error::StorageError;
+use super::file_ops::{write_atomic, FileStorage, Snippet};
+use super::folders::normalize_folder;
+use super::metadata::{sidecar_path, SnippetMeta};
+use crate::crypto::{decrypt_with_passphrase, encrypt_with_passphrase};
+
+/// Archives are plain age files, so `age -d` turns one back into JSON.
+pub const ARCHIVE_EXTENSION: &str = "json.age";
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Snippets with their folders and metadata, as one passphrase-encrypted
+/// document that is safe to send by email or keep on a cloud drive.
+#[derive(Serialize, Deserialize)]
+struct Archive<'a> {
+    version: u32,
+    exported: DateTime<Utc>,
+    snippets: Vec<ArchivedSnippet<'a>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArchivedSnippet<'a> {
+    id: Cow<'a, str>,
+    folder: Cow<'a, str>,
+    content: Cow<'a, str>,
+    meta: Cow<'a, SnippetMeta>,
+}
+
+/// Imported secrets are scrubbed like those of a [`Snippet`].
+impl Drop for ArchivedSnippet<'_> {
+    fn drop(&mut self) {
+        if let (true, Cow::Owned(content)) = (self.meta.secret, &mut self.content) {
+            content.zeroize();
+        }
+    }
+}
+
+/// Writes `snippets` to `path`, encrypted with `passphrase`. Secrets are
+/// left out unless `include_secrets`. Returns the snippets written.
+pub fn export_archive<'a>(
+    snippets: impl IntoIterator<Item = &'a Snippet>,
+    path: &Path,
+    passphrase: SecretString,
+    include_secrets: bool,
+) -> Result<Vec<&'a Snippet>, std::io::Error> {
+    let snippets: Vec<&Snippet> = snippets.into_iter()
+        .filter(|snippet| include_secrets || !snippet.meta.secret)
+        .collect();
+    let archive = Archive {
+        version: ARCHIVE_VERSION,
+        exported: Utc::now(),
+        snippets: snippets.iter()
+            .map(|snippet| ArchivedSnippet {
+                id: Cow::Borrowed(&snippet.id),
+                folder: Cow::Borrowed(&snippet.folder),
+                content: Cow::Borrowed(&snippet.content),
+                meta: Cow::Borrowed(&snippet.meta),
+            })
+            .collect(),
+    };
+    let json = Zeroizing::new(serde_json::to_vec(&archive)?);
+    write_atomic(path, &encrypt_with_passphrase(&json, passphrase)?)?;
+    Ok(snippets)
+}
+
+impl FileStorage {
+    /// Saves the snippets of an archive with their ids, folders and
+    /// metadata, skipping any whose id is already in the library. Returns
+    /// how many were imported and how many skipped.
+    pub fn import_archive(&self, path: &Path, passphrase: SecretString, existing: &[Snippet]) -> Result<(usize, usize), StorageError> {
+        let json = Zeroizing::new(decrypt_with_passphrase(&fs::read(path)?, passphrase)?);
+        let archive: Archive = serde_json::from_slice(&json).map_err(std::io::Error::from)?;
+        if archive.version > ARCHIVE_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "the archive was written by a newer version of Trinket",
+            ).into());
+        }
+
+        let known: HashSet<&str> = existing.iter().map(|snippet| snippet.id.as_str()).collect();
+        let (mut imported, mut skipped) = (0, 0);
+        for snippet in &archive.snippets {
+            // Ids become file names, so anything odd is not trusted.
+            let id_ok = !snippet.id.is_empty() && snippet.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+            let folder = normalize_folder(&snippet.folder).unwrap_or_default();
+            if !id_ok || known.contains(&*snippet.id) || sidecar_path(&self.folder_path(&folder).join(&*snippet.id)).exists() {
+                skipped += 1;
+                continue;
+            }
+            let mut meta = SnippetMeta::clone(&snippet.meta);
+            meta.deleted = None;
+            meta.trashed_from = None;
+            self.write_snippet(snippet.id.to_string(), &snippet.content, &folder, meta)?;
+            imported += 1;
+        }
+        Ok((imported, skipped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::temp_storage;
+
+    #[test]
+    fn secrets_stay_out_unless_asked_for() {
+        let (dir, storage) = temp_storage();
+        storage.save_snippet("Kind regards", "", SnippetMeta::default()).unwrap();
+        storage.save_snippet("hunter2", "", SnippetMeta { secret: true, ..SnippetMeta::default() }).unwrap();
+        let snippets = storage.load_all_snippets().unwrap();
+        let path = dir.path().join("export.json.age");
+
+        let written = export_archive(&snippets, &path, SecretString::from("passphrase"), false).unwrap();
+        assert_eq!(written.iter().map(|snippet| snippet.content.as_str()).collect::<Vec<_>>(), ["Kind regards"]);
+        let (_other_dir, other) = temp_storage();
+        assert_eq!(other.import_archive(&path, SecretString::from("passphrase"), &[]).unwrap(), (1, 0));
+
+        let written = export_archive(&snippets, &path, SecretString::from("passphrase"), true).unwrap();
+        assert_eq!(written.len(), 2);
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+pub const AUDIT_FILE: &str = ".audit-log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessKind {
+    Reveal,
+    Copy,
+    /// Written to an export archive.
+    Export,
+}
+
+impl AccessKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            AccessKind::Reveal => "Revealed",
+            AccessKind::Copy => "Copied",
+            AccessKind::Export => "Exported",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessRecord {
+    pub at: DateTime<Utc>,
+    pub kind: AccessKind,
+    pub id: String,
+}
+
+/// Append-only log of every reveal, copy and export of a secret snippet. Entries are
+/// only ever added, one JSON line each, so the file doubles as an audit trail.
+#[derive(Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+    
+    pub fn record(&self, id: &str, kind: AccessKind) -> Result<(), std::io::Error> {
+        let record = AccessRecord { at: Utc::now(), kind, id: id.to_string() };
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+    
+    /// Every entry, most recent first. Unreadable lines are skipped.
+    pub fn records(&self) -> Result<Vec<AccessRecord>, std::io::Error> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Ok(record) = serde_json::from_str::<AccessRecord>(&line?) {
+                records.push(record);
+            }
+        }
+        records.reverse();
+        Ok(records)
+    }
+}
+
+/// Writes `records` as CSV with `at,action,id` columns.
+pub fn export_csv(records: &[AccessRecord], path: &Path) -> Result<(), std::io::Error> {
+    let mut csv = String::from("at,action,id\n");
+    for record in records {
+        let kind = match record.kind {
+            AccessKind::Reveal => "reveal",
+            AccessKind::Copy => "copy",
+            AccessKind::Export => "export",
+        };
+        csv.push_str(&format!("{},{},{}\n", record.at.to_rfc3339(), kind, record.id));
+    }
+    std::fs::write(path, csv)
+}
@@ -0,0 +1,121 @@
+use std::sync::mpsc;
+use std::time::Duration;
+
+use super::changes::{Change, ChangeLog, CHANGES_FILE};
+use super::error::StorageError;
+use super::file_ops::{content_files_in, FileStorage, Snippet};
+use super::metadata::SnippetMeta;
+
+/// How often `FileStorage::watch` looks at the change log.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a backend offers beyond saving, updating, deleting and listing, so
+/// a frontend can leave out what the library it opened can't do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+    /// Deleted snippets go to a trash they can be restored from.
+    pub trash: bool,
+    /// Replaced content is kept as versions.
+    pub history: bool,
+    /// Snippets can be sorted into folders.
+    pub folders: bool,
+    /// Content can be encrypted at rest.
+    pub encryption: bool,
+    /// `watch` reports changes, including those made by other processes.
+    pub watch: bool,
+}
+
+/// Where snippets are kept. The app works on `FileStorage` directly for
+/// the extras only it has; a database, an encrypted container or a remote
+/// store can stand in for it through these calls alone.
+pub trait StorageBackend {
+    fn capabilities(&self) -> Capabilities;
+
+    /// Saves new content into `folder`; the backend assigns the id.
+    fn save(&self, content: &str, folder: &str, meta: SnippetMeta) -> Result<Snippet, StorageError>;
+
+    /// Replaces a snippet's content, updating `snippet` to match.
+    fn update(&self, snippet: &mut Snippet, content: &str) -> Result<(), StorageError>;
+
+    /// Deletes a snippet. Returns whether it can be restored.
+    fn delete(&self, snippet: &Snippet) -> Result<bool, StorageError>;
+
+    /// Every readable snippet, newest first.
+    fn list(&self) -> Result<Vec<Snippet>, StorageError>;
+
+    /// Every snippet one at a time, in no particular order, for work that
+    /// shouldn't hold the whole library in memory.
+    fn stream(&self) -> Result<Box<dyn Iterator<Item = Result<Snippet, StorageError>> + '_>, StorageError>;
+
+    /// Changes from now on, until the receiver is dropped. Backends without
+    /// the `watch` capability never send any.
+    fn watch(&self) -> mpsc::Receiver<Change>;
+}
+
+impl StorageBackend for FileStorage {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            trash: true,
+            history: true,
+            folders: true,
+            encryption: cfg!(feature = "vault"),
+            watch: true,
+        }
+    }
+
+    fn save(&self, content: &str, folder: &str, meta: SnippetMeta) -> Result<Snippet, StorageError> {
+        self.save_snippet(content, folder, meta)
+    }
+
+    fn update(&self, snippet: &mut Snippet, content: &str) -> Result<(), StorageError> {
+        self.update_snippet(snippet, content)
+    }
+
+    fn delete(&self, snippet: &Snippet) -> Result<bool, StorageError> {
+        self.delete_snippet(snippet)
+    }
+
+    fn list(&self) -> Result<Vec<Snippet>, StorageError> {
+        self.load_all_snippets()
+    }
+
+    fn stream(&self) -> Result<Box<dyn Iterator<Item = Result<Snippet, StorageError>> + '_>, StorageError> {
+        let paths = content_files_in(&self.base_path)?;
+        Ok(Box::new(
+            paths.into_iter()
+                .filter(|path| !self.is_folder_locked(&self.folder_of(path)))
+                .map(|path| {
+                    let metadata = std::fs::metadata(&path)?;
+                    self.load_snippet(path, metadata)
+                }),
+        ))
+    }
+
+    /// Every storage handle on the library, in this process or another,
+    /// appends to the change log, so following it sees them all.
+    fn watch(&self) -> mpsc::Receiver<Change> {
+        let (sender, receiver) = mpsc::channel();
+        let path = self.base_path.join(CHANGES_FILE);
+        std::thread::spawn(move || {
+            let log = ChangeLog::open(path);
+            let mut seen = log.latest();
+            loop {
+                std::thread::sleep(WATCH_INTERVAL);
+                let changes = match log.since(seen) {
+                    Ok(changes) => changes,
+                    Err(e) => {
+                        log::warn!("Failed to read the change log: {}", e);
+                        continue;
+                    }
+                };
+                for change in changes {
+                    seen = seen.max(change.rev);
+                    if sender.send(change).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        receiver
+    }
+}
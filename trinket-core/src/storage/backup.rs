@@ -0,0 +1,419 @@
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+use tempfile::NamedTempFile;
+
+use super::changes::{ChangeKind, CHANGES_FILE};
+use super::file_ops::{content_files_in, is_content_file, FileStorage, Snippet};
+use super::folders::is_internal_dir;
+
+const BACKUP_PREFIX: &str = "trinket-";
+const BACKUP_EXTENSION: &str = ".tar.gz";
+const NAME_FORMAT: &str = "%Y%m%d-%H%M%S";
+/// How often the scheduler checks whether a backup is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Sync bookkeeping belongs to this device and what it agreed with its
+/// peers, not to the library; restoring it would make a sync take the
+/// restored snippets for ones already sent and undo the restore.
+const DEVICE_STATE: [&str; 2] = [".sync", CHANGES_FILE];
+const BLOCK: usize = 512;
+
+/// A backup archive: the snippet folder as a gzipped tarball, with
+/// encrypted files left as they are.
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// How the library would change if a backup were restored.
+#[derive(Debug, Default)]
+pub struct BackupDiff {
+    /// In the backup but not the library, so restoring brings them back.
+    pub returning: Vec<Snippet>,
+    /// In the library but not the backup, so restoring removes them.
+    pub removed: Vec<Snippet>,
+    /// In both but different, as they are now and as they were.
+    pub changed: Vec<(Snippet, Snippet)>,
+    pub unchanged: usize,
+}
+
+/// What the backup scheduler works with; changed from the settings while
+/// it runs.
+#[derive(Debug, Clone)]
+pub struct BackupSettings {
+    pub dir: PathBuf,
+    /// 0 turns scheduled backups off.
+    pub interval_hours: u32,
+    /// 0 keeps every backup.
+    pub kept: usize,
+}
+
+/// Starts a background thread that backs up `base_path` whenever the newest
+/// backup is older than the configured interval, then prunes the oldest.
+/// Failures are sent back for the UI to show.
+pub fn spawn_backups(base_path: PathBuf, settings: Arc<Mutex<BackupSettings>>) -> mpsc::Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || loop {
+        let BackupSettings { dir, interval_hours, kept } = settings.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        if interval_hours > 0 {
+            let newest = list_backups(&dir).ok().and_then(|backups| backups.first().map(|backup| backup.created));
+            let due = newest.is_none_or(|created| Utc::now() - created >= chrono::Duration::hours(interval_hours.into()));
+            if due {
+                let result = write_backup(&base_path, &dir).and_then(|backup| {
+                    log::info!("Backed up the snippets to {}", backup.path.display());
+                    prune_backups(&dir, kept)
+                });
+                if let Err(e) = result {
+                    log::error!("Scheduled backup failed: {}", e);
+                    if sender.send(e.to_string()).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+
+    receiver
+}
+
+/// Writes a backup of everything under `base_path` into `dir`, named by the
+/// time it was taken.
+pub fn write_backup(base_path: &Path, dir: &Path) -> Result<Backup, std::io::Error> {
+    fs::create_dir_all(dir)?;
+    let mut created = Utc::now();
+    let mut path = dir.join(backup_name(created));
+    // Two backups within a second, as around a restore, get distinct names.
+    while path.exists() {
+        created += chrono::Duration::seconds(1);
+        path = dir.join(backup_name(created));
+    }
+
+    let temp_file = NamedTempFile::new_in(dir)?;
+    let mut archive = GzEncoder::new(BufWriter::new(temp_file.as_file()), Compression::default());
+    let mut pending = vec![base_path.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            let relative = relative_name(base_path, &path)?;
+            if DEVICE_STATE.contains(&relative.as_str()) || path == dir {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                write_header(&mut archive, &format!("{}/", relative), 0, mtime(&metadata), b'5')?;
+                pending.push(path);
+            } else if metadata.is_file() {
+                let contents = fs::read(&path)?;
+                write_header(&mut archive, &relative, contents.len() as u64, mtime(&metadata), b'0')?;
+                archive.write_all(&contents)?;
+                archive.write_all(&[0; BLOCK][..padding(contents.len())])?;
+            }
+        }
+    }
+    // Two empty blocks end a tarball.
+    archive.write_all(&[0; BLOCK * 2])?;
+    archive.finish()?.flush()?;
+    temp_file.persist(&path)?;
+
+    let size = fs::metadata(&path)?.len();
+    Ok(Backup { path, created, size })
+}
+
+/// Backups in `dir`, newest first. Files that aren't backups are ignored.
+pub fn list_backups(dir: &Path) -> Result<Vec<Backup>, std::io::Error> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(stamp) = name.to_str()
+            .and_then(|name| name.strip_prefix(BACKUP_PREFIX))
+            .and_then(|name| name.strip_suffix(BACKUP_EXTENSION)) else {
+            continue;
+        };
+        let Ok(created) = NaiveDateTime::parse_from_str(stamp, NAME_FORMAT) else {
+            continue;
+        };
+        backups.push(Backup { path: entry.path(), created: created.and_utc(), size: entry.metadata()?.len() });
+    }
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created));
+    Ok(backups)
+}
+
+/// Deletes all but the newest `kept` backups; 0 keeps them all. Returns how
+/// many were deleted.
+pub fn prune_backups(dir: &Path, kept: usize) -> Result<usize, std::io::Error> {
+    if kept == 0 {
+        return Ok(0);
+    }
+    let mut pruned = 0;
+    for backup in list_backups(dir)?.into_iter().skip(kept) {
+        fs::remove_file(&backup.path)?;
+        pruned += 1;
+    }
+    Ok(pruned)
+}
+
+/// How many snippets a backup holds, outside the trash, from the names of
+/// its files alone.
+pub fn count_snippets(backup: &Path) -> Result<usize, std::io::Error> {
+    let mut count = 0;
+    read_entries(backup, |name, kind, _, _| {
+        let in_library = name.ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .all(|dir| !is_internal_dir(dir));
+        if kind != b'5' && in_library && is_content_file(&name) {
+            count += 1;
+        }
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+/// Compares the library as it is with the snippets of a backup, by id.
+pub fn diff_backup(current: &[Snippet], backup: Vec<Snippet>) -> BackupDiff {
+    let mut diff = BackupDiff::default();
+    let mut unmatched: HashMap<&str, &Snippet> = current.iter().map(|snippet| (snippet.id.as_str(), snippet)).collect();
+    for old in backup {
+        match unmatched.remove(old.id.as_str()) {
+            None => diff.returning.push(old),
+            Some(now) if now.content != old.content || now.folder != old.folder || now.meta.tags != old.meta.tags => {
+                diff.changed.push((now.clone(), old));
+            }
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    diff.removed = current.iter().filter(|snippet| unmatched.contains_key(snippet.id.as_str())).cloned().collect();
+    diff
+}
+
+/// When a backup was taken, in local time, for lists.
+pub fn format_created(backup: &Backup) -> String {
+    backup.created.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+}
+
+impl FileStorage {
+    /// The snippets in `backup` as they were, read with this library's keys
+    /// and with the paths they would be restored to. Snippets in folders
+    /// locked here are left out, as they are from the library.
+    pub fn backup_snippets(&self, backup: &Path) -> Result<Vec<Snippet>, std::io::Error> {
+        // Unpacked next to the library rather than in the shared temp
+        // folder, since snippets that aren't encrypted are plain text.
+        let parent = self.base_path.parent().unwrap_or_else(|| Path::new("."));
+        let staging = tempfile::Builder::new().prefix(".trinket-preview").tempdir_in(parent)?;
+        unpack(backup, staging.path())?;
+
+        let mut snippets = Vec::new();
+        for path in content_files_in(staging.path())? {
+            let relative = path.strip_prefix(staging.path()).map_err(|_| invalid("a file is outside the backup"))?;
+            let restored_path = self.base_path.join(relative);
+            let folder = self.folder_of(&restored_path);
+            if self.is_folder_locked(&folder) {
+                continue;
+            }
+            let metadata = fs::metadata(&path)?;
+            match self.load_snippet(path.clone(), metadata) {
+                Ok(mut snippet) => {
+                    snippet.folder = folder;
+                    snippet.file_path = restored_path;
+                    snippets.push(snippet);
+                }
+                Err(e) => log::warn!("Skipping unreadable snippet {} in {}: {}", relative.display(), backup.display(), e),
+            }
+        }
+        Ok(snippets)
+    }
+
+    /// Replaces the library with the contents of `backup`, after backing up
+    /// the library as it is into `dir` first, which is returned. Every
+    /// restored snippet is logged as changed so syncs send it on.
+    pub fn restore_backup(&self, backup: &Path, dir: &Path) -> Result<Backup, std::io::Error> {
+        self.ensure_writable()?;
+        let base_name = self.base_path.file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "the snippet folder has no name"))?;
+        // Unpacked next to the library first, so a damaged backup changes
+        // nothing and the files only need renaming into place.
+        let staging = self.base_path.with_file_name(format!(".{}-restoring", base_name));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        if let Err(e) = unpack(backup, &staging) {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+        let before = write_backup(&self.base_path, dir)?;
+
+        for entry in fs::read_dir(&self.base_path)? {
+            let entry = entry?;
+            if DEVICE_STATE.contains(&entry.file_name().to_str().unwrap_or_default()) || entry.path() == dir {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            } else {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        for entry in fs::read_dir(&staging)? {
+            let entry = entry?;
+            if DEVICE_STATE.contains(&entry.file_name().to_str().unwrap_or_default()) {
+                continue;
+            }
+            fs::rename(entry.path(), self.base_path.join(entry.file_name()))?;
+        }
+        fs::remove_dir_all(&staging)?;
+
+        for snippet in self.load_all_snippets()? {
+            self.record_change(&snippet.id, ChangeKind::Update);
+        }
+        Ok(before)
+    }
+}
+
+/// Extracts the files and folders of a backup into `target`, refusing any
+/// path that would land outside it.
+fn unpack(backup: &Path, target: &Path) -> Result<(), std::io::Error> {
+    fs::create_dir_all(target)?;
+    read_entries(backup, |name, kind, modified, contents| {
+        let path = target.join(name);
+        match kind {
+            b'5' => fs::create_dir_all(&path),
+            b'0' | 0 => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let mut file = File::create(&path)?;
+                std::io::copy(contents, &mut file)?;
+                // Snippet dates come from file times, so they are kept.
+                file.set_modified(modified)
+            }
+            _ => Err(invalid("the backup holds something other than files and folders")),
+        }
+    })
+}
+
+/// Calls `visit` with the path, type, modification time and contents of
+/// each entry of a backup in turn. Contents it leaves unread are skipped.
+fn read_entries(
+    backup: &Path,
+    mut visit: impl FnMut(PathBuf, u8, SystemTime, &mut dyn Read) -> Result<(), std::io::Error>,
+) -> Result<(), std::io::Error> {
+    let mut archive = GzDecoder::new(BufReader::new(File::open(backup)?));
+    let mut header = [0u8; BLOCK];
+    loop {
+        archive.read_exact(&mut header)?;
+        if header.iter().all(|&byte| byte == 0) {
+            return Ok(());
+        }
+        let name = header_name(&header)?;
+        let size = octal(&header[124..136])?;
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(octal(&header[136..148])?);
+        let mut contents = (&mut archive).take(size);
+        visit(name, header[156], modified, &mut contents)?;
+        std::io::copy(&mut contents, &mut std::io::sink())?;
+        let size = usize::try_from(size).map_err(|_| invalid("an entry in the backup is too large"))?;
+        std::io::copy(&mut (&mut archive).take(padding(size) as u64), &mut std::io::sink())?;
+    }
+}
+
+fn backup_name(created: DateTime<Utc>) -> String {
+    format!("{}{}{}", BACKUP_PREFIX, created.format(NAME_FORMAT), BACKUP_EXTENSION)
+}
+
+/// `path` relative to `base_path`, with `/` between parts as in tarballs.
+fn relative_name(base_path: &Path, path: &Path) -> Result<String, std::io::Error> {
+    let relative = path.strip_prefix(base_path).map_err(|_| invalid("a file is outside the snippet folder"))?;
+    let parts: Option<Vec<&str>> = relative.components().map(|c| c.as_os_str().to_str()).collect();
+    parts.map(|parts| parts.join("/")).ok_or_else(|| invalid("a file name isn't valid UTF-8"))
+}
+
+fn mtime(metadata: &fs::Metadata) -> u64 {
+    metadata.modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_secs())
+}
+
+fn padding(size: usize) -> usize {
+    (BLOCK - size % BLOCK) % BLOCK
+}
+
+/// Writes a ustar header. Names longer than 100 bytes are split into the
+/// prefix field at a `/`.
+fn write_header(out: &mut impl Write, name: &str, size: u64, mtime: u64, kind: u8) -> Result<(), std::io::Error> {
+    let (prefix, name) = match name.len() {
+        0..=100 => ("", name),
+        _ => name.char_indices()
+            .filter(|&(i, c)| c == '/' && i <= 155 && name.len() - i - 1 <= 100 && i + 1 < name.len())
+            .map(|(i, _)| (&name[..i], &name[i + 1..]))
+            .next()
+            .ok_or_else(|| invalid(format!("{} is too long a path for a backup", name)))?,
+    };
+    let mut header = [0u8; BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    put_octal(&mut header[100..108], if kind == b'5' { 0o755 } else { 0o644 });
+    put_octal(&mut header[108..116], 0);
+    put_octal(&mut header[116..124], 0);
+    put_octal(&mut header[124..136], size);
+    put_octal(&mut header[136..148], mtime);
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+    // The checksum is taken with its own field as spaces.
+    header[148..156].fill(b' ');
+    let checksum: u64 = header.iter().map(|&byte| u64::from(byte)).sum();
+    header[148..155].copy_from_slice(format!("{:06o}\0", checksum).as_bytes());
+    out.write_all(&header)
+}
+
+fn put_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}\0", value, width = field.len() - 1);
+    field.copy_from_slice(digits.as_bytes());
+}
+
+fn octal(field: &[u8]) -> Result<u64, std::io::Error> {
+    let text = std::str::from_utf8(field).map_err(|_| invalid("the backup is damaged"))?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| invalid("the backup is damaged"))
+}
+
+/// The entry's path, which must stay inside the folder it is unpacked to.
+fn header_name(header: &[u8; BLOCK]) -> Result<PathBuf, std::io::Error> {
+    let field = |range: std::ops::Range<usize>| {
+        let bytes = &header[range];
+        let end = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+        std::str::from_utf8(&bytes[..end]).map_err(|_| invalid("the backup is damaged"))
+    };
+    let (prefix, name) = (field(345..500)?, field(0..100)?);
+    let name = if prefix.is_empty() { PathBuf::from(name) } else { Path::new(prefix).join(name) };
+    if name.as_os_str().is_empty() || !name.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(invalid(format!("the backup holds an unsafe path: {}", name.display())));
+    }
+    Ok(name)
+}
+
+fn invalid(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
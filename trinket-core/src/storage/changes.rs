@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::file_ops::write_atomic;
+
+pub const CHANGES_FILE: &str = ".changes";
+/// Past this size the log is rewritten on open with only the last change
+/// to each snippet.
+const COMPACT_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub rev: u64,
+    pub id: String,
+    pub kind: ChangeKind,
+    pub at: DateTime<Utc>,
+}
+
+/// Append-only log of every snippet created, changed or deleted, so a sync
+/// can ask what changed since the revision it last saw instead of
+/// rescanning the library. Revisions are microseconds since the epoch,
+/// bumped where needed to keep increasing, so the janitor's storage handle
+/// and the app's can both append to the same log.
+pub struct ChangeLog {
+    path: PathBuf,
+    last: AtomicU64,
+}
+
+/// The revision of a change made at `at`, before any bump to keep the
+/// revisions increasing.
+pub fn rev_at(at: DateTime<Utc>) -> u64 {
+    u64::try_from(at.timestamp_micros()).unwrap_or_default()
+}
+
+impl ChangeLog {
+    pub fn open(path: PathBuf) -> Self {
+        let log = Self { path, last: AtomicU64::new(0) };
+        let changes = log.read().unwrap_or_else(|e| {
+            log::warn!("Failed to read the change log: {}", e);
+            Vec::new()
+        });
+        log.last.store(changes.iter().map(|change| change.rev).max().unwrap_or(0), Ordering::Relaxed);
+        let size = fs::metadata(&log.path).map_or(0, |metadata| metadata.len());
+        if size > COMPACT_BYTES {
+            if let Err(e) = log.compact(changes) {
+                log::warn!("Failed to compact the change log: {}", e);
+            }
+        }
+        log
+    }
+
+    pub fn record(&self, id: &str, kind: ChangeKind) -> Result<u64, std::io::Error> {
+        let at = Utc::now();
+        let now = rev_at(at);
+        let rev = match self.last.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| Some(now.max(last + 1))) {
+            Ok(last) | Err(last) => now.max(last + 1),
+        };
+        let mut line = serde_json::to_string(&Change { rev, id: id.to_string(), kind, at })?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+        Ok(rev)
+    }
+
+    /// The newest revision this handle has seen or recorded.
+    pub fn latest(&self) -> u64 {
+        self.last.load(Ordering::Relaxed)
+    }
+
+    /// Changes after `rev`, oldest first. A compacted log keeps only the
+    /// last change to each snippet, so treat the kinds as what a snippet
+    /// ended up as rather than a full history.
+    pub fn since(&self, rev: u64) -> Result<Vec<Change>, std::io::Error> {
+        let mut changes: Vec<Change> = self.read()?.into_iter().filter(|change| change.rev > rev).collect();
+        changes.sort_by_key(|change| change.rev);
+        Ok(changes)
+    }
+
+    fn read(&self) -> Result<Vec<Change>, std::io::Error> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut changes = Vec::new();
+        for line in BufReader::new(file).lines() {
+            // A torn last line from a crash mid-append is skipped.
+            if let Ok(change) = serde_json::from_str(&line?) {
+                changes.push(change);
+            }
+        }
+        Ok(changes)
+    }
+
+    fn compact(&self, changes: Vec<Change>) -> Result<(), std::io::Error> {
+        let mut latest: HashMap<String, Change> = HashMap::new();
+        for change in changes {
+            if latest.get(&change.id).is_none_or(|known| known.rev < change.rev) {
+                latest.insert(change.id.clone(), change);
+            }
+        }
+        let mut kept: Vec<Change> = latest.into_values().collect();
+        kept.sort_by_key(|change| change.rev);
+        let mut text = String::new();
+        for change in &kept {
+            text.push_str(&serde_json::to_string(change)?);
+            text.push('\n');
+        }
+        write_atomic(&self.path, text.as_bytes())
+    }
+}
@@ -0,0 +1,81 @@
+/// Checklist snippets are stored as markdown task lists (`- [ ] item`), so the
+/// file stays readable on its own and copying the content yields markdown.
+const UNCHECKED: &str = "- [ ] ";
+const CHECKED: &str = "- [x] ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecklistLine<'a> {
+    Item { checked: bool, text: &'a str },
+    /// Headings, notes or blank lines between items.
+    Text(&'a str),
+}
+
+pub fn parse_checklist(content: &str) -> Vec<ChecklistLine<'_>> {
+    content.lines().map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> ChecklistLine<'_> {
+    let trimmed = line.trim_start();
+    let Some(rest) = trimmed.strip_prefix("- [").or_else(|| trimmed.strip_prefix("* [")) else {
+        return ChecklistLine::Text(line);
+    };
+    match rest.get(..2) {
+        Some(" ]") => ChecklistLine::Item { checked: false, text: rest[2..].trim_start() },
+        Some("x]" | "X]") => ChecklistLine::Item { checked: true, text: rest[2..].trim_start() },
+        _ => ChecklistLine::Text(line),
+    }
+}
+
+/// Turns every non-blank line that is not already an item into an unchecked one.
+pub fn to_markdown(content: &str) -> String {
+    map_lines(content, |line| match parse_line(line) {
+        ChecklistLine::Text(text) if !text.trim().is_empty() => format!("{}{}", UNCHECKED, text.trim()),
+        _ => line.to_string(),
+    })
+}
+
+/// Renders items as `[ ] item` / `[x] item` for places that don't speak markdown.
+pub fn to_plain(content: &str) -> String {
+    map_lines(content, |line| match parse_line(line) {
+        ChecklistLine::Item { checked, text } => format!("[{}] {}", if checked { 'x' } else { ' ' }, text),
+        ChecklistLine::Text(text) => text.to_string(),
+    })
+}
+
+/// Flips the item on line `line`; other lines are returned unchanged.
+pub fn toggle_item(content: &str, line: usize) -> String {
+    let mut index = 0;
+    map_lines(content, |text| {
+        let current = index;
+        index += 1;
+        match parse_line(text) {
+            ChecklistLine::Item { checked, text: item } if current == line => {
+                format!("{}{}{}", indent(text), if checked { UNCHECKED } else { CHECKED }, item)
+            }
+            _ => text.to_string(),
+        }
+    })
+}
+
+/// Unchecks every item, ready for the next run through the list.
+pub fn reset(content: &str) -> String {
+    map_lines(content, |line| match parse_line(line) {
+        ChecklistLine::Item { text, .. } => format!("{}{}{}", indent(line), UNCHECKED, text),
+        ChecklistLine::Text(text) => text.to_string(),
+    })
+}
+
+fn indent(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Rewrites each line while keeping the original line endings.
+fn map_lines(content: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\r', '\n']);
+        out.push_str(&f(body));
+        out.push_str(&line[body.len()..]);
+    }
+    out
+}
@@ -0,0 +1,87 @@
+use similar::TextDiff;
+use std::collections::HashMap;
+
+use super::Snippet;
+
+/// Snippets at least this similar (0.0 to 1.0) count as near-duplicates.
+const NEAR_THRESHOLD: f32 = 0.9;
+/// Character diffs get expensive quickly; longer snippets are only checked
+/// for exact duplicates.
+const NEAR_MAX_BYTES: usize = 2048;
+
+/// Snippets that look like copies of each other, as indices into the list
+/// that was scanned.
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub indices: Vec<usize>,
+    /// Every member has the same content, ignoring surrounding whitespace.
+    pub exact: bool,
+}
+
+pub fn find_duplicates(snippets: &[Snippet]) -> Vec<DuplicateGroup> {
+    let mut by_content: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, snippet) in snippets.iter().enumerate() {
+        let content = snippet.content.trim();
+        if !content.is_empty() {
+            by_content.entry(content).or_default().push(index);
+        }
+    }
+
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    // One representative per distinct content takes part in the near check.
+    let mut distinct: Vec<usize> = Vec::new();
+    for indices in by_content.into_values() {
+        distinct.push(indices[0]);
+        if indices.len() > 1 {
+            groups.push(DuplicateGroup { indices, exact: true });
+        }
+    }
+    groups.extend(near_duplicates(snippets, distinct));
+
+    for group in &mut groups {
+        group.indices.sort_unstable();
+    }
+    groups.sort_by_key(|group| group.indices[0]);
+    groups
+}
+
+/// Groups similar snippets. Sorting by length means each one is only
+/// compared with neighbours short enough to reach the threshold.
+fn near_duplicates(snippets: &[Snippet], mut candidates: Vec<usize>) -> Vec<DuplicateGroup> {
+    candidates.retain(|&i| snippets[i].content.trim().len() <= NEAR_MAX_BYTES);
+    candidates.sort_by_key(|&i| snippets[i].content.trim().len());
+
+    let mut parent: Vec<usize> = (0..candidates.len()).collect();
+    for a in 0..candidates.len() {
+        let text_a = snippets[candidates[a]].content.trim();
+        for b in a + 1..candidates.len() {
+            let text_b = snippets[candidates[b]].content.trim();
+            // Past this length ratio the similarity can't reach the threshold.
+            if (text_a.len() as f32) < text_b.len() as f32 * NEAR_THRESHOLD {
+                break;
+            }
+            if TextDiff::from_chars(text_a, text_b).ratio() >= NEAR_THRESHOLD {
+                let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+                parent[root_b] = root_a;
+            }
+        }
+    }
+
+    let mut grouped: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (position, &index) in candidates.iter().enumerate() {
+        let root = find(&mut parent, position);
+        grouped.entry(root).or_default().push(index);
+    }
+    grouped.into_values()
+        .filter(|indices| indices.len() > 1)
+        .map(|indices| DuplicateGroup { indices, exact: false })
+        .collect()
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Why a snippet or the library couldn't be read or written.
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "vault")]
+    #[error(transparent)]
+    Vault(#[from] crate::crypto::VaultError),
+    #[error("content is encrypted and no vault key is configured")]
+    VaultLocked,
+    #[error("folder '{0}' is encrypted and locked")]
+    FolderLocked(String),
+    #[error("snippet is locked; unlock it first")]
+    SnippetLocked,
+    #[error("invalid folder name '{0}'")]
+    InvalidFolder(String),
+    #[error("snippet is not in the trash")]
+    NotInTrash,
+    #[error("content is not valid UTF-8")]
+    NotText(#[source] std::str::Utf8Error),
+    /// The library opened read-only because its folder can't be written.
+    #[error("the library is read-only")]
+    ReadOnly,
+    #[error("{} can't be written: {source}", path.display())]
+    NotWritable { path: PathBuf, source: std::io::Error },
+    /// Over the limit set with `set_max_snippet_bytes`; saving it anyway
+    /// takes the user's say-so.
+    #[error("the snippet is {size} bytes, over the {limit} bytes allowed")]
+    TooLarge { size: usize, limit: usize },
+    #[error("the library is in layout {found}, newer than this version of trinket knows ({known}); update trinket")]
+    NewerLayout { found: u32, known: u32 },
+}
+
+/// For code that only deals in I/O errors, such as sync.
+impl From<StorageError> for std::io::Error {
+    fn from(error: StorageError) -> Self {
+        use std::io::ErrorKind;
+
+        let kind = match &error {
+            StorageError::Io(e) => e.kind(),
+            #[cfg(feature = "vault")]
+            StorageError::Vault(_) => ErrorKind::InvalidData,
+            StorageError::VaultLocked
+            | StorageError::FolderLocked(_)
+            | StorageError::SnippetLocked
+            | StorageError::ReadOnly => ErrorKind::PermissionDenied,
+            StorageError::NotWritable { source, .. } => source.kind(),
+            StorageError::InvalidFolder(_) | StorageError::TooLarge { .. } => ErrorKind::InvalidInput,
+            StorageError::NotInTrash => ErrorKind::NotFound,
+            StorageError::NotText(_) | StorageError::NewerLayout { .. } => ErrorKind::InvalidData,
+        };
+        match error {
+            StorageError::Io(e) => e,
+            #[cfg(feature = "vault")]
+            StorageError::Vault(e) => e.into(),
+            error => std::io::Error::new(kind, error),
+        }
+    }
+}
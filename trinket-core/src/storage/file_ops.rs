@@ -0,0 +1,838 @@
+use std::borrow::Cow;
+#[cfg(feature = "vault")]
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use super::changes::{ChangeKind, ChangeLog, CHANGES_FILE};
+use super::error::StorageError;
+use super::folders::{is_internal_dir, normalize_folder};
+use super::journal::CaptureJournal;
+use super::language::Language;
+use super::metadata::{is_sidecar, sidecar_path, SnippetMeta};
+use super::shred::{scrub_file, shred_file};
+use super::title::display_title;
+
+const JOURNAL_FILE: &str = ".capture-journal";
+/// The size limit until [`FileStorage::set_max_snippet_bytes`] sets another.
+pub const DEFAULT_MAX_SNIPPET_BYTES: usize = 1024 * 1024;
+/// Start of every age file; marks content written by a vault.
+pub(crate) const AGE_MAGIC: &[u8] = b"age-encryption.org/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: String,
+    pub content: String,
+    /// Display title, see [`display_title`].
+    pub title: String,
+    pub created: SystemTime,
+    pub modified: SystemTime,
+    pub file_path: PathBuf,
+    /// `/`-separated folder relative to the storage root, `""` for the root.
+    pub folder: String,
+    pub meta: SnippetMeta,
+}
+
+/// A secret snippet's content is scrubbed from memory once no copy of the
+/// snippet needs it any more.
+impl Drop for Snippet {
+    fn drop(&mut self) {
+        if self.meta.secret {
+            self.content.zeroize();
+        }
+    }
+}
+
+pub const SECRET_MASK: &str = "••••••••";
+
+impl Snippet {
+    /// The title to show in lists, masked for secret snippets.
+    pub fn safe_title(&self) -> &str {
+        if self.meta.secret {
+            SECRET_MASK
+        } else {
+            &self.title
+        }
+    }
+    
+    /// Recomputes the title after the content or metadata changed.
+    pub fn refresh_title(&mut self) {
+        self.title = display_title(&self.content, &self.meta);
+    }
+}
+
+pub struct FileStorage {
+    pub base_path: PathBuf,
+    journal: CaptureJournal,
+    changes: ChangeLog,
+    /// Encrypts content and versions as they are written. Files written
+    /// without it stay readable and are encrypted on their next save.
+    #[cfg(feature = "vault")]
+    vault: Option<crate::crypto::Vault>,
+    /// Set when a vault is configured, so content is never written in
+    /// plain text because the vault failed to open.
+    vault_required: bool,
+    /// Folders with a key of their own. Their content is never written with
+    /// the main vault or in plain text.
+    encrypted_folders: Vec<String>,
+    /// Keys of the encrypted folders that are unlocked.
+    #[cfg(feature = "vault")]
+    folder_vaults: BTreeMap<String, crate::crypto::Vault>,
+    /// Deleting a secret snippet erases it instead of trashing it.
+    secure_delete: bool,
+    /// Content larger than this is refused with [`StorageError::TooLarge`]
+    /// unless saved with one of the `_anyway` methods.
+    max_snippet_bytes: usize,
+    /// Set when the library folder can't be written; every change fails
+    /// with [`StorageError::ReadOnly`].
+    read_only: bool,
+}
+
+impl FileStorage {
+    /// Opens the library at `base_path`, creating the folder if needed.
+    /// Fails when the folder can't be written.
+    pub fn new(base_path: PathBuf) -> Result<Self, StorageError> {
+        let not_writable = |source| StorageError::NotWritable { path: base_path.clone(), source };
+        fs::create_dir_all(&base_path).map_err(not_writable)?;
+        tempfile::tempfile_in(&base_path).map_err(not_writable)?;
+        Ok(Self::open(base_path, false))
+    }
+    
+    /// Opens the library at `base_path` for reading only, such as when
+    /// [`new`](Self::new) failed, so what can be read is still usable.
+    pub fn read_only(base_path: PathBuf) -> Self {
+        Self::open(base_path, true)
+    }
+    
+    fn open(base_path: PathBuf, read_only: bool) -> Self {
+        let journal = CaptureJournal::new(base_path.join(JOURNAL_FILE));
+        let changes = ChangeLog::open(base_path.join(CHANGES_FILE));
+        Self {
+            base_path,
+            journal,
+            changes,
+            #[cfg(feature = "vault")]
+            vault: None,
+            vault_required: false,
+            encrypted_folders: Vec::new(),
+            #[cfg(feature = "vault")]
+            folder_vaults: BTreeMap::new(),
+            secure_delete: false,
+            max_snippet_bytes: DEFAULT_MAX_SNIPPET_BYTES,
+            read_only,
+        }
+    }
+    
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+    
+    /// Fails when the library is read-only; called before any change.
+    pub(crate) fn ensure_writable(&self) -> Result<(), StorageError> {
+        if self.read_only {
+            return Err(StorageError::ReadOnly);
+        }
+        Ok(())
+    }
+    
+    #[cfg(feature = "vault")]
+    pub fn with_vault(mut self, vault: crate::crypto::Vault) -> Self {
+        self.vault = Some(vault);
+        self.vault_required = true;
+        self
+    }
+    
+    /// Forgets the vault's keys and those of every encrypted folder; content
+    /// can't be read or written until [`unlock_vault`](Self::unlock_vault)
+    /// and [`unlock_folder`](Self::unlock_folder).
+    #[cfg(feature = "vault")]
+    pub fn lock_vault(&mut self) {
+        self.vault = None;
+        self.folder_vaults.clear();
+    }
+    
+    #[cfg(feature = "vault")]
+    pub fn unlock_vault(&mut self, vault: crate::crypto::Vault) {
+        self.vault = Some(vault);
+    }
+    
+    /// True when a vault is configured but its keys aren't loaded.
+    #[cfg(feature = "vault")]
+    pub fn is_vault_locked(&self) -> bool {
+        self.vault_required && self.vault.is_none()
+    }
+    
+    /// Encrypts `folder` and its subfolders with a key of their own, given
+    /// later with [`unlock_folder`](Self::unlock_folder).
+    pub fn add_encrypted_folder(&mut self, folder: String) {
+        self.encrypted_folders.push(folder);
+    }
+    
+    #[cfg(feature = "vault")]
+    pub fn unlock_folder(&mut self, folder: &str, vault: crate::crypto::Vault) {
+        self.folder_vaults.insert(folder.to_string(), vault);
+    }
+    
+    /// The encrypted folder whose key covers `folder`, the innermost one
+    /// when they are nested.
+    pub fn encrypted_folder(&self, folder: &str) -> Option<&str> {
+        self.encrypted_folders.iter()
+            .filter(|encrypted| {
+                folder == encrypted.as_str() || folder.strip_prefix(encrypted.as_str()).is_some_and(|rest| rest.starts_with('/'))
+            })
+            .max_by_key(|encrypted| encrypted.len())
+            .map(String::as_str)
+    }
+    
+    /// Encrypted folders whose key isn't loaded; their snippets aren't listed.
+    pub fn locked_folders(&self) -> Vec<String> {
+        self.encrypted_folders.iter()
+            .filter(|folder| !self.has_folder_key(folder))
+            .cloned()
+            .collect()
+    }
+    
+    pub(crate) fn is_folder_locked(&self, folder: &str) -> bool {
+        self.encrypted_folder(folder).is_some_and(|encrypted| !self.has_folder_key(encrypted))
+    }
+    
+    #[cfg(feature = "vault")]
+    fn has_folder_key(&self, encrypted: &str) -> bool {
+        self.folder_vaults.contains_key(encrypted)
+    }
+    
+    #[cfg(not(feature = "vault"))]
+    fn has_folder_key(&self, _encrypted: &str) -> bool {
+        false
+    }
+    
+    /// Refuses to write content until a vault is attached.
+    pub fn require_vault(mut self) -> Self {
+        self.vault_required = true;
+        self
+    }
+    
+    pub fn set_secure_delete(&mut self, secure_delete: bool) {
+        self.secure_delete = secure_delete;
+    }
+    
+    pub fn set_max_snippet_bytes(&mut self, max_snippet_bytes: usize) {
+        self.max_snippet_bytes = max_snippet_bytes;
+    }
+    
+    pub(crate) fn check_size(&self, content: &str) -> Result<(), StorageError> {
+        if content.len() > self.max_snippet_bytes {
+            return Err(StorageError::TooLarge { size: content.len(), limit: self.max_snippet_bytes });
+        }
+        Ok(())
+    }
+    
+    /// Reads a content or version file, decrypting it if a vault wrote it.
+    pub(crate) fn read_text(&self, path: &Path) -> Result<String, StorageError> {
+        self.open_text(fs::read(path)?)
+    }
+    
+    /// Content as stored, decrypted if a vault wrote it.
+    fn open_text(&self, bytes: Vec<u8>) -> Result<String, StorageError> {
+        let bytes = if bytes.starts_with(AGE_MAGIC) {
+            self.decrypt(&bytes)?
+        } else {
+            bytes
+        };
+        String::from_utf8(bytes).map_err(|e| {
+            let error = e.utf8_error();
+            e.into_bytes().zeroize();
+            StorageError::NotText(error)
+        })
+    }
+    
+    /// Writes a content or version file of a snippet in `folder`, encrypted
+    /// with the folder's key if it has one or else when a vault is open.
+    pub(crate) fn write_text(&self, folder: &str, path: &Path, text: &str) -> Result<(), StorageError> {
+        self.ensure_writable()?;
+        Ok(write_atomic(path, &self.seal(folder, text)?)?)
+    }
+    
+    /// [`write_text`](Self::write_text) for a file whose old content must
+    /// not outlive it, such as a secret's: that is overwritten in place
+    /// before the new file replaces it.
+    pub(crate) fn overwrite_text(&self, folder: &str, path: &Path, text: &str) -> Result<(), StorageError> {
+        self.ensure_writable()?;
+        Ok(overwrite_atomic(path, &self.seal(folder, text)?)?)
+    }
+    
+    /// `text` as it's stored in `folder`: encrypted with the folder's key if
+    /// it has one or else when a vault is open, plain otherwise. Fails when
+    /// the key it needs isn't loaded.
+    fn seal<'a>(&self, folder: &str, text: &'a str) -> Result<Cow<'a, [u8]>, StorageError> {
+        if let Some(encrypted) = self.encrypted_folder(folder) {
+            #[cfg(feature = "vault")]
+            if let Some(vault) = self.folder_vaults.get(encrypted) {
+                return Ok(Cow::Owned(vault.encrypt(text.as_bytes())?));
+            }
+            return Err(StorageError::FolderLocked(encrypted.to_string()));
+        }
+        #[cfg(feature = "vault")]
+        if let Some(vault) = &self.vault {
+            return Ok(Cow::Owned(vault.encrypt(text.as_bytes())?));
+        }
+        if self.vault_required {
+            return Err(StorageError::VaultLocked);
+        }
+        Ok(Cow::Borrowed(text.as_bytes()))
+    }
+    
+    /// Whether `write_text` would encrypt content in `folder`.
+    pub(crate) fn encrypts(&self, folder: &str) -> bool {
+        #[cfg(feature = "vault")]
+        {
+            match self.encrypted_folder(folder) {
+                Some(encrypted) => self.folder_vaults.contains_key(encrypted),
+                None => self.vault.is_some(),
+            }
+        }
+        #[cfg(not(feature = "vault"))]
+        {
+            let _ = folder;
+            false
+        }
+    }
+    
+    #[cfg(feature = "vault")]
+    fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>, StorageError> {
+        if self.vault.is_none() && self.folder_vaults.is_empty() {
+            return Err(StorageError::VaultLocked);
+        }
+        Ok(crate::crypto::decrypt(self.vault.iter().chain(self.folder_vaults.values()), bytes)?)
+    }
+    
+    #[cfg(not(feature = "vault"))]
+    fn decrypt(&self, _bytes: &[u8]) -> Result<Vec<u8>, StorageError> {
+        Err(StorageError::VaultLocked)
+    }
+    
+    /// Saves new content into `folder`. `meta` carries caller choices such as
+    /// an expiry; the creation time and language are filled in here.
+    #[tracing::instrument(skip_all, fields(folder = %folder))]
+    pub fn save_snippet(&self, content: &str, folder: &str, meta: SnippetMeta) -> Result<Snippet, StorageError> {
+        self.check_size(content)?;
+        self.save_snippet_anyway(content, folder, meta)
+    }
+    
+    /// [`save_snippet`](Self::save_snippet) past the size limit, once the
+    /// user confirmed it.
+    pub fn save_snippet_anyway(&self, content: &str, folder: &str, meta: SnippetMeta) -> Result<Snippet, StorageError> {
+        self.ensure_writable()?;
+        let id = Uuid::new_v4().to_string();
+        // A bad folder name must not cost the user their capture.
+        let folder = normalize_folder(folder).unwrap_or_else(|e| {
+            log::warn!("Saving to root instead: {}", e);
+            String::new()
+        });
+        // Journaled as it will be stored, so a capture for a locked vault or
+        // folder fails before anything is written and an encrypted one never
+        // lands on disk in plain text.
+        self.journal.begin(&id, &self.seal(&folder, content)?, &folder, &meta)?;
+        let snippet = self.write_snippet(id, content, &folder, meta)?;
+        if let Err(e) = self.journal.finish(&snippet.id) {
+            log::warn!("Failed to mark capture {} as saved: {}", snippet.id, e);
+        }
+        Ok(snippet)
+    }
+    
+    /// Replaces a snippet's content in place, renaming the file when the
+    /// detected language (and so the extension) changes. The old content is
+    /// kept as a version.
+    #[tracing::instrument(skip_all, fields(id = %snippet.id))]
+    pub fn update_snippet(&self, snippet: &mut Snippet, content: &str) -> Result<(), StorageError> {
+        self.check_size(content)?;
+        self.update_snippet_anyway(snippet, content)
+    }
+    
+    /// [`update_snippet`](Self::update_snippet) past the size limit, once
+    /// the user confirmed it.
+    pub fn update_snippet_anyway(&self, snippet: &mut Snippet, content: &str) -> Result<(), StorageError> {
+        ensure_unlocked(snippet)?;
+        if snippet.content != content {
+            self.record_version(snippet)?;
+        }
+        self.write_content(snippet, content)
+    }
+    
+    /// Adds `text` as a new line at the end of a snippet. The snippet is
+    /// read from disk first, so appends and other changes queued before the
+    /// copy in memory caught up still all land.
+    #[tracing::instrument(skip_all, fields(id = %snippet.id))]
+    pub fn append_to_snippet(&self, snippet: &mut Snippet, text: &str) -> Result<(), StorageError> {
+        self.reread(snippet)?;
+        ensure_unlocked(snippet)?;
+        let separator = if snippet.content.is_empty() || snippet.content.ends_with('\n') { "" } else { "\n" };
+        let content = format!("{}{}{}", snippet.content, separator, text);
+        self.update_snippet(snippet, &content)
+    }
+    
+    /// Stores new checklist state. Ticking items is using a checklist rather
+    /// than editing it, so this is allowed on locked snippets.
+    pub fn update_checklist(&self, snippet: &mut Snippet, content: &str) -> Result<(), StorageError> {
+        self.write_content(snippet, content)
+    }
+    
+    fn write_content(&self, snippet: &mut Snippet, content: &str) -> Result<(), StorageError> {
+        let language = Language::detect(content);
+        let new_path = snippet.file_path.with_extension(language.map_or("txt", Language::extension));
+        // The sidecar on disk still says whether the old content was secret,
+        // even when the caller has just changed that.
+        let was_secret = snippet.meta.secret || SnippetMeta::load(&snippet.file_path).is_some_and(|meta| meta.secret);
+        if was_secret {
+            self.overwrite_text(&snippet.folder, &new_path, content)?;
+        } else {
+            self.write_text(&snippet.folder, &new_path, content)?;
+        }
+        if new_path != snippet.file_path {
+            if was_secret {
+                shred_file(&snippet.file_path)?;
+            } else {
+                fs::remove_file(&snippet.file_path)?;
+            }
+        }
+        
+        if snippet.meta.secret {
+            snippet.content.zeroize();
+        }
+        snippet.content = content.to_string();
+        snippet.modified = SystemTime::now();
+        snippet.file_path = new_path;
+        snippet.meta.language = language;
+        snippet.refresh_title();
+        self.save_meta(snippet)
+    }
+    
+    /// Takes a snippet's folder, content and metadata from another device.
+    /// The local content is kept as a version; the lock isn't checked since
+    /// the change was made elsewhere, but the size limit is.
+    #[cfg(feature = "sync")]
+    pub fn overwrite_snippet(&self, snippet: &mut Snippet, folder: &str, content: &str, meta: SnippetMeta) -> Result<(), StorageError> {
+        self.check_size(content)?;
+        self.move_snippet(snippet, folder)?;
+        if snippet.content != content {
+            self.record_version(snippet)?;
+        }
+        if snippet.meta.secret {
+            snippet.content.zeroize();
+        }
+        snippet.meta = meta;
+        self.write_content(snippet, content)
+    }
+    
+    /// Moves a snippet to the trash, or erases a secret one for good when
+    /// secure deletion is on. Returns whether it can be restored.
+    #[tracing::instrument(skip_all, fields(id = %snippet.id))]
+    pub fn delete_snippet(&self, snippet: &Snippet) -> Result<bool, StorageError> {
+        self.ensure_writable()?;
+        ensure_unlocked(snippet)?;
+        if self.secure_delete && snippet.meta.secret {
+            self.purge(&snippet.file_path)?;
+            return Ok(false);
+        }
+        self.move_to_trash(&snippet.file_path)?;
+        Ok(true)
+    }
+    
+    /// Applies `change` to a snippet's metadata and saves it. The snippet
+    /// is read from disk first, so changes queued before the copy in memory
+    /// caught up still all land.
+    #[tracing::instrument(skip_all, fields(id = %snippet.id))]
+    pub fn update_meta(&self, snippet: &mut Snippet, change: impl FnOnce(&mut SnippetMeta)) -> Result<(), StorageError> {
+        self.ensure_writable()?;
+        self.reread(snippet)?;
+        change(&mut snippet.meta);
+        snippet.refresh_title();
+        self.save_meta(snippet)
+    }
+
+    /// Brings a copy of a snippet up to date with its content and sidecar
+    /// on disk.
+    pub(super) fn reread(&self, snippet: &mut Snippet) -> Result<(), StorageError> {
+        snippet.content = self.read_text(&snippet.file_path)?;
+        if let Some(meta) = SnippetMeta::load(&snippet.file_path) {
+            snippet.meta = meta;
+        }
+        snippet.refresh_title();
+        Ok(())
+    }
+
+    /// Rewrites the sidecar after the snippet's metadata was changed in memory.
+    pub fn save_meta(&self, snippet: &Snippet) -> Result<(), StorageError> {
+        self.ensure_writable()?;
+        snippet.meta.save(&snippet.file_path)?;
+        self.record_change(&snippet.id, ChangeKind::Update);
+        Ok(())
+    }
+    
+    /// Notes a change in the log. The snippet is already saved by then, so
+    /// failing to log it is only worth a warning.
+    pub(super) fn record_change(&self, id: &str, kind: ChangeKind) {
+        if let Err(e) = self.changes.record(id, kind) {
+            log::warn!("Failed to log a change to snippet {}: {}", id, e);
+        }
+    }
+    
+    /// Persists captures that were journaled but never written, e.g. because
+    /// the process died mid-save. Returns how many snippets were recovered;
+    /// a read-only library keeps its journal for later. A capture that can't
+    /// be written, such as one for a locked folder, stays journaled for the
+    /// next start without holding up the others.
+    #[tracing::instrument(skip_all)]
+    pub fn replay_journal(&self) -> Result<usize, StorageError> {
+        if self.read_only {
+            return Ok(0);
+        }
+        let pending = self.journal.pending()?;
+        let mut recovered = 0;
+        
+        for capture in pending {
+            let id = capture.id.clone();
+            // The sidecar is written last, so its presence means the save completed.
+            let folder = normalize_folder(&capture.folder).unwrap_or_default();
+            if !sidecar_path(&self.folder_path(&folder).join(&capture.id)).exists() {
+                let written = self.open_text(capture.content)
+                    .and_then(|content| self.write_snippet(capture.id, &content, &folder, capture.meta));
+                if let Err(e) = written {
+                    log::error!("Failed to recover capture {}: {}", id, e);
+                    continue;
+                }
+                recovered += 1;
+            }
+            self.journal.finish(&id)?;
+        }
+        
+        Ok(recovered)
+    }
+    
+    /// Writes a new snippet file and its sidecar. `meta.created` is kept when
+    /// set, as for imported snippets, and otherwise stamped now.
+    pub(crate) fn write_snippet(&self, id: String, content: &str, folder: &str, mut meta: SnippetMeta) -> Result<Snippet, StorageError> {
+        let language = Language::detect(content);
+        let extension = language.map_or("txt", Language::extension);
+        let dir = self.folder_path(folder);
+        fs::create_dir_all(&dir)?;
+        let file_path = dir.join(format!("{}.{}", id, extension));
+        
+        self.write_text(folder, &file_path, content)?;
+        
+        let created = meta.created.map_or_else(SystemTime::now, SystemTime::from);
+        meta.created = Some(DateTime::<Utc>::from(created));
+        meta.language = language;
+        meta.save(&file_path)?;
+        self.record_change(&id, ChangeKind::Create);
+        
+        let metadata = fs::metadata(&file_path)?;
+        let modified = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+        
+        Ok(Snippet {
+            id,
+            content: content.to_string(),
+            title: display_title(content, &meta),
+            created,
+            modified,
+            file_path,
+            folder: folder.to_string(),
+            meta,
+        })
+    }
+    
+    #[tracing::instrument(skip_all)]
+    pub fn load_all_snippets(&self) -> Result<Vec<Snippet>, StorageError> {
+        let mut snippets = Vec::new();
+        
+        for path in self.content_files()? {
+            if self.is_folder_locked(&self.folder_of(&path)) {
+                continue;
+            }
+            let metadata = fs::metadata(&path)?;
+            // One unreadable file, such as vault content without the key,
+            // shouldn't hide the rest of the library.
+            match self.load_snippet(path.clone(), metadata) {
+                Ok(snippet) => snippets.push(snippet),
+                Err(e) => log::error!("Skipping unreadable snippet {}: {}", path.display(), e),
+            }
+        }
+        
+        sort_snippets(&mut snippets);
+        
+        Ok(snippets)
+    }
+    
+    /// Lists snippets whose expiry has passed as `(id, content path)` pairs,
+    /// reading only sidecars so the scan stays cheap.
+    pub fn expired_snippets(&self, now: DateTime<Utc>) -> Result<Vec<(String, PathBuf)>, StorageError> {
+        let mut expired = Vec::new();
+        
+        for path in self.content_files()? {
+            let is_expired = SnippetMeta::load(&path)
+                .and_then(|meta| meta.expires)
+                .is_some_and(|expires| expires <= now);
+            if is_expired {
+                let id = path.file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default()
+                    .to_string();
+                expired.push((id, path));
+            }
+        }
+        
+        Ok(expired)
+    }
+    
+    /// Ids of the snippets in locked encrypted folders, from file names alone.
+    #[cfg(feature = "sync")]
+    pub fn locked_ids(&self) -> Result<Vec<String>, StorageError> {
+        Ok(self.content_files()?
+            .into_iter()
+            .filter(|path| self.is_folder_locked(&self.folder_of(path)))
+            .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+            .collect())
+    }
+    
+    /// Every snippet content file in the root and its folders.
+    fn content_files(&self) -> Result<Vec<PathBuf>, std::io::Error> {
+        content_files_in(&self.base_path)
+    }
+    
+    pub(crate) fn load_snippet(&self, path: PathBuf, metadata: fs::Metadata) -> Result<Snippet, StorageError> {
+        let content = self.read_text(&path)?;
+        
+        let id = path.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+        
+        let mut meta = SnippetMeta::load(&path).unwrap_or_default();
+        let created = match meta.created {
+            Some(created) => created.into(),
+            None => {
+                // Legacy snippet without a sidecar: pin down the best
+                // time we have now, before a restore or sync resets it.
+                let created = metadata.created()
+                    .or_else(|_| metadata.modified())
+                    .unwrap_or_else(|_| SystemTime::now());
+                meta.created = Some(DateTime::<Utc>::from(created));
+                if let Err(e) = meta.save(&path) {
+                    log::warn!("Failed to write metadata for {}: {}", path.display(), e);
+                }
+                created
+            }
+        };
+        if meta.language.is_none() {
+            meta.language = path.extension()
+                .and_then(|s| s.to_str())
+                .and_then(Language::from_extension);
+        }
+        
+        Ok(Snippet {
+            id,
+            content: content.clone(),
+            title: display_title(&content, &meta),
+            created,
+            modified: metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+            folder: self.folder_of(&path),
+            file_path: path,
+            meta,
+        })
+    }
+}
+
+/// Pinned snippets first in their chosen order, then the rest newest first.
+pub fn sort_snippets(snippets: &mut [Snippet]) {
+    snippets.sort_by_key(|snippet| {
+        let meta = &snippet.meta;
+        (!meta.pinned, if meta.pinned { meta.pin_order } else { 0 }, std::cmp::Reverse(snippet.created))
+    });
+}
+
+/// The `pin_order` that puts a newly pinned snippet after the others.
+pub fn next_pin_order(snippets: &[Snippet]) -> u32 {
+    snippets.iter()
+        .filter(|snippet| snippet.meta.pinned)
+        .map(|snippet| snippet.meta.pin_order + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+fn ensure_unlocked(snippet: &Snippet) -> Result<(), StorageError> {
+    if snippet.meta.locked {
+        return Err(StorageError::SnippetLocked);
+    }
+    Ok(())
+}
+
+/// Every snippet content file under `root` laid out as a library, such as
+/// a backup unpacked elsewhere.
+pub(crate) fn content_files_in(root: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if !is_internal_dir(&path) {
+                    pending.push(path);
+                }
+            } else if is_content_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+    
+    Ok(files)
+}
+
+/// Snippet content is stored as `<id>.txt` or `<id>.<language extension>`;
+/// sidecars, the journal and temp files are skipped.
+pub(crate) fn is_content_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+        return false;
+    };
+    if name.starts_with('.') || is_sidecar(path) {
+        return false;
+    }
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("txt") => true,
+        Some(extension) => Language::from_extension(extension).is_some(),
+        None => false,
+    }
+}
+
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), std::io::Error> {
+    use tempfile::NamedTempFile;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let temp_file = NamedTempFile::new_in(dir)?;
+    fs::write(&temp_file, contents)?;
+    temp_file.persist(path)?;
+    Ok(())
+}
+
+/// [`write_atomic`], overwriting the file at `path` with zeros before the
+/// new one is renamed over it, so the old content isn't left in the freed
+/// blocks. The new content reaches the disk first.
+pub(crate) fn overwrite_atomic(path: &Path, contents: &[u8]) -> Result<(), std::io::Error> {
+    use tempfile::NamedTempFile;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let temp_file = NamedTempFile::new_in(dir)?;
+    fs::write(&temp_file, contents)?;
+    temp_file.as_file().sync_all()?;
+    if path.exists() {
+        scrub_file(path)?;
+    }
+    temp_file.persist(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::temp_storage;
+
+    #[test]
+    fn appends_from_a_stale_copy_all_land() {
+        let (_dir, storage) = temp_storage();
+        let snippet = storage.save_snippet("log", "", SnippetMeta::default()).unwrap();
+
+        storage.append_to_snippet(&mut snippet.clone(), "first").unwrap();
+        let mut stale = snippet.clone();
+        storage.append_to_snippet(&mut stale, "second").unwrap();
+
+        assert_eq!(stale.content, "log\nfirst\nsecond");
+    }
+
+    #[test]
+    fn meta_changes_from_a_stale_copy_all_land() {
+        let (_dir, storage) = temp_storage();
+        let snippet = storage.save_snippet("notes", "", SnippetMeta::default()).unwrap();
+
+        storage.update_meta(&mut snippet.clone(), |meta| meta.archived = true).unwrap();
+        storage.append_to_snippet(&mut snippet.clone(), "more").unwrap();
+        let mut stale = snippet.clone();
+        storage.update_meta(&mut stale, |meta| meta.locked = true).unwrap();
+
+        assert!(stale.meta.archived && stale.meta.locked);
+        assert_eq!(stale.content, "notes\nmore");
+        assert!(SnippetMeta::load(&stale.file_path).is_some_and(|meta| meta.archived && meta.locked));
+    }
+
+    #[test]
+    fn replay_keeps_only_the_captures_it_could_not_write() {
+        let (_dir, mut storage) = temp_storage();
+        storage.add_encrypted_folder("private".to_string());
+        storage.journal.begin("locked", b"hunter2", "private", &SnippetMeta::default()).unwrap();
+        storage.journal.begin("lost", b"meeting notes", "", &SnippetMeta::default()).unwrap();
+
+        assert_eq!(storage.replay_journal().unwrap(), 1);
+        let pending = storage.journal.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, "locked");
+        assert_eq!(storage.load_all_snippets().unwrap()[0].content, "meeting notes");
+    }
+
+    #[test]
+    fn captures_for_a_locked_folder_are_refused_before_the_journal() {
+        let (_dir, mut storage) = temp_storage();
+        storage.add_encrypted_folder("private".to_string());
+
+        let refused = storage.save_snippet("hunter2", "private", SnippetMeta::default());
+        assert!(matches!(refused, Err(StorageError::FolderLocked(_))));
+        assert!(!storage.base_path.join(JOURNAL_FILE).exists());
+    }
+
+    #[cfg(feature = "vault")]
+    #[test]
+    fn vault_captures_are_journaled_encrypted() {
+        use age::secrecy::ExposeSecret;
+
+        let identity = age::x25519::Identity::generate();
+        let vault = crate::crypto::Vault::from_keys(identity.to_string().expose_secret(), "").unwrap();
+        let (_dir, storage) = temp_storage();
+        let storage = storage.with_vault(vault);
+        let sealed = storage.seal("", "hunter2").unwrap();
+        storage.journal.begin("lost", &sealed, "", &SnippetMeta::default()).unwrap();
+
+        let journal = fs::read_to_string(storage.base_path.join(JOURNAL_FILE)).unwrap();
+        assert!(!journal.contains("hunter2"));
+        assert_eq!(storage.replay_journal().unwrap(), 1);
+        assert_eq!(storage.load_all_snippets().unwrap()[0].content, "hunter2");
+        assert!(!storage.base_path.join(JOURNAL_FILE).exists());
+    }
+
+    #[test]
+    fn editing_a_secret_overwrites_the_old_file() {
+        let (dir, storage) = temp_storage();
+        let mut snippet = storage.save_snippet("hunter2", "", SnippetMeta { secret: true, ..SnippetMeta::default() }).unwrap();
+        // A second name for the old file shows what its blocks hold after the edit.
+        let old = dir.path().join("old");
+        fs::hard_link(&snippet.file_path, &old).unwrap();
+
+        storage.update_snippet(&mut snippet, "correct horse").unwrap();
+        assert_eq!(fs::read(&old).unwrap(), vec![0; "hunter2".len()]);
+        assert_eq!(storage.read_text(&snippet.file_path).unwrap(), "correct horse");
+    }
+
+    #[test]
+    fn oversized_content_needs_saving_anyway() {
+        let (_dir, mut storage) = temp_storage();
+        storage.set_max_snippet_bytes(4);
+
+        let refused = storage.save_snippet("too long", "", SnippetMeta::default());
+        assert!(matches!(refused, Err(StorageError::TooLarge { size: 8, limit: 4 })));
+        assert!(storage.journal.pending().unwrap().is_empty());
+
+        let mut snippet = storage.save_snippet_anyway("too long", "", SnippetMeta::default()).unwrap();
+        assert!(matches!(storage.update_snippet(&mut snippet, "longer still"), Err(StorageError::TooLarge { .. })));
+        assert_eq!(snippet.content, "too long");
+    }
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::changes::ChangeKind;
+use super::error::StorageError;
+use super::file_ops::{FileStorage, Snippet};
+use super::metadata::sidecar_path;
+use super::shred::shred_file;
+
+/// Holds the passphrase-encrypted identity of a folder with its own vault.
+#[cfg(feature = "vault")]
+pub const FOLDER_KEY_FILE: &str = ".vault-key";
+
+/// Folders are plain subdirectories of the storage directory, addressed by
+/// `/`-separated paths relative to it (`""` is the root). Directories whose
+/// name starts with a dot (trash, history) are internal and never listed.
+impl FileStorage {
+    pub fn folder_path(&self, folder: &str) -> PathBuf {
+        folder.split('/')
+            .filter(|part| !part.is_empty())
+            .fold(self.base_path.clone(), |path, part| path.join(part))
+    }
+    
+    /// The folder a content file lives in, relative to the storage root.
+    pub fn folder_of(&self, content_path: &Path) -> String {
+        content_path.parent()
+            .map(|dir| self.relative_folder(dir))
+            .unwrap_or_default()
+    }
+    
+    fn relative_folder(&self, dir: &Path) -> String {
+        dir.strip_prefix(&self.base_path)
+            .map(|relative| {
+                relative.components()
+                    .filter_map(|c| c.as_os_str().to_str())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .unwrap_or_default()
+    }
+    
+    /// Moves a snippet and its sidecar into `folder`, creating it if needed.
+    /// Content moving in or out of an encrypted folder is rewritten with the
+    /// new folder's key; earlier versions keep the key they were saved with.
+    #[tracing::instrument(skip_all, fields(id = %snippet.id, folder = %folder))]
+    pub fn move_snippet(&self, snippet: &mut Snippet, folder: &str) -> Result<(), StorageError> {
+        self.ensure_writable()?;
+        let folder = normalize_folder(folder)?;
+        if folder == snippet.folder {
+            return Ok(());
+        }
+        let dir = self.folder_path(&folder);
+        fs::create_dir_all(&dir)?;
+        
+        let file_name = snippet.file_path.file_name()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "snippet path has no file name"))?;
+        let new_path = dir.join(file_name);
+        let sidecar = sidecar_path(&snippet.file_path);
+        if self.encrypted_folder(&folder) != self.encrypted_folder(&snippet.folder) {
+            self.write_text(&folder, &new_path, &snippet.content)?;
+            if snippet.meta.secret {
+                shred_file(&snippet.file_path)?;
+            } else {
+                fs::remove_file(&snippet.file_path)?;
+            }
+        } else {
+            fs::rename(&snippet.file_path, &new_path)?;
+        }
+        if sidecar.exists() {
+            fs::rename(&sidecar, sidecar_path(&new_path))?;
+        }
+        
+        snippet.file_path = new_path;
+        snippet.folder = folder;
+        self.record_change(&snippet.id, ChangeKind::Update);
+        Ok(())
+    }
+    
+    /// All folders below the root, sorted, including empty ones.
+    pub fn list_folders(&self) -> Result<Vec<String>, StorageError> {
+        let mut folders = Vec::new();
+        let mut pending = vec![self.base_path.clone()];
+        
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() && !is_internal_dir(&entry.path()) {
+                    folders.push(self.relative_folder(&entry.path()));
+                    pending.push(entry.path());
+                }
+            }
+        }
+        
+        folders.sort();
+        Ok(folders)
+    }
+}
+
+pub(crate) fn is_internal_dir(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .is_none_or(|name| name.starts_with('.'))
+}
+
+/// Cleans up a user-typed folder path, accepting either slash style and
+/// rejecting anything that could escape the storage directory.
+pub fn normalize_folder(folder: &str) -> Result<String, StorageError> {
+    let mut parts = Vec::new();
+    for part in folder.split(['/', '\\']).map(str::trim).filter(|part| !part.is_empty()) {
+        if part.starts_with('.') || part.contains(':') {
+            return Err(StorageError::InvalidFolder(part.to_string()));
+        }
+        parts.push(part);
+    }
+    Ok(parts.join("/"))
+}
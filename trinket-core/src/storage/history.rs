@@ -0,0 +1,57 @@
+use chrono::{DateTime, TimeZone, Utc};
+use std::fs;
+use std::path::PathBuf;
+
+use super::error::StorageError;
+use super::file_ops::{FileStorage, Snippet};
+
+pub const HISTORY_DIR: &str = ".history";
+
+/// An earlier content of a snippet, kept as
+/// `.history/<id>/<unix millis>.txt` whenever the snippet is edited.
+#[derive(Debug, Clone)]
+pub struct Version {
+    pub saved: DateTime<Utc>,
+    pub path: PathBuf,
+}
+
+impl FileStorage {
+    fn history_path(&self, id: &str) -> PathBuf {
+        self.base_path.join(HISTORY_DIR).join(id)
+    }
+
+    /// Keeps the snippet's current content as a version before it is replaced.
+    pub(crate) fn record_version(&self, snippet: &Snippet) -> Result<(), StorageError> {
+        let dir = self.history_path(&snippet.id);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.txt", Utc::now().timestamp_millis()));
+        self.write_text(&snippet.folder, &path, &snippet.content)
+    }
+
+    pub fn read_version(&self, version: &Version) -> Result<String, StorageError> {
+        self.read_text(&version.path)
+    }
+
+    /// Earlier versions of a snippet, newest first.
+    pub fn versions(&self, id: &str) -> Result<Vec<Version>, StorageError> {
+        let dir = self.history_path(id);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut versions = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let saved = path.file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<i64>().ok())
+                .and_then(|millis| Utc.timestamp_millis_opt(millis).single());
+            if let Some(saved) = saved {
+                versions.push(Version { saved, path });
+            }
+        }
+
+        versions.sort_by_key(|version| std::cmp::Reverse(version.saved));
+        Ok(versions)
+    }
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, Local, NaiveDate};
+use std::ops::Range;
+
+use super::{Language, Snippet};
+
+/// Date format for `after:` and `before:` filters.
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Non-ASCII queries are lowercased in slices of this many bytes so a huge
+/// snippet never needs a full lowercased copy.
+const SEARCH_CHUNK: usize = 64 * 1024;
+
+#[derive(Default)]
+pub struct SearchIndex {
+    // Simple implementation for now - could be expanded with proper indexing
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn search(&self, query: &str, snippets: &[Snippet]) -> Vec<usize> {
+        let query = SearchQuery::parse(query);
+        snippets.iter()
+            .enumerate()
+            .filter_map(|(idx, snippet)| {
+                if self.matches(snippet, &query) {
+                    Some(idx)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Checks a single snippet against a parsed query, so callers can spread a
+    /// full scan over several frames.
+    pub fn matches(&self, snippet: &Snippet, query: &SearchQuery) -> bool {
+        query.admits(snippet)
+            && (contains_ignore_case(&snippet.content, &query.text)
+                || contains_ignore_case(&snippet.meta.notes, &query.text))
+    }
+}
+
+/// A search box query split into `key:value` filters and lowercased free text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub language: Option<Language>,
+    pub tag: Option<String>,
+    /// Lowercased text to find in the app or window a snippet was captured from.
+    pub source: Option<String>,
+    /// Created on or after this local date.
+    pub after: Option<NaiveDate>,
+    /// Created on or before this local date.
+    pub before: Option<NaiveDate>,
+    /// Restricts results to a folder and its subfolders; set from the
+    /// sidebar rather than typed.
+    pub folder: Option<String>,
+    /// Lowercased name of the app that had the focus; hides snippets meant
+    /// for other apps. Set by the get window rather than typed.
+    pub app: Option<String>,
+    /// `is:archived`: only archived snippets, which are otherwise left out.
+    pub archived: bool,
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Self {
+        let mut query = SearchQuery::default();
+        let words: Vec<&str> = input.split_whitespace()
+            .filter(|word| !query.apply_filter(word))
+            .collect();
+        query.text = words.join(" ").to_lowercase();
+        query
+    }
+    
+    /// The search box text without its `key:value` filters, case kept.
+    pub fn free_text(input: &str) -> String {
+        let mut query = SearchQuery::default();
+        let words: Vec<&str> = input.split_whitespace()
+            .filter(|word| !query.apply_filter(word))
+            .collect();
+        words.join(" ")
+    }
+    
+    /// Takes `word` as a filter if it is one; other words are search text.
+    fn apply_filter(&mut self, word: &str) -> bool {
+        match word.split_once(':') {
+            // Unknown languages are ignored rather than matching nothing,
+            // so results don't vanish while `lang:` is still being typed.
+            Some(("lang", value)) => self.language = Language::parse(value),
+            Some(("tag", value)) if !value.is_empty() => self.tag = Some(value.to_lowercase()),
+            Some(("from", value)) if !value.is_empty() => self.source = Some(value.to_lowercase()),
+            Some(("after", value)) => self.after = NaiveDate::parse_from_str(value, DATE_FORMAT).ok(),
+            Some(("before", value)) => self.before = NaiveDate::parse_from_str(value, DATE_FORMAT).ok(),
+            Some(("is", "archived")) => self.archived = true,
+            _ => return false,
+        }
+        true
+    }
+    
+    /// Whether `snippet` passes every filter, leaving the free text to the
+    /// search provider.
+    pub fn admits(&self, snippet: &Snippet) -> bool {
+        if snippet.meta.archived != self.archived {
+            return false;
+        }
+        if self.language.is_some() && snippet.meta.language != self.language {
+            return false;
+        }
+        if self.tag.as_ref().is_some_and(|tag| !snippet.meta.tags.contains(tag)) {
+            return false;
+        }
+        if let Some(from) = &self.source {
+            let found = snippet.meta.source.as_ref().is_some_and(|source| {
+                contains_ignore_case(&source.app, from) || contains_ignore_case(&source.window, from)
+            });
+            if !found {
+                return false;
+            }
+        }
+        if self.after.is_some() || self.before.is_some() {
+            let created = DateTime::<Local>::from(snippet.created).date_naive();
+            if self.after.is_some_and(|after| created < after)
+                || self.before.is_some_and(|before| created > before)
+            {
+                return false;
+            }
+        }
+        if let Some(folder) = &self.folder {
+            let in_folder = snippet.folder == *folder
+                || snippet.folder.strip_prefix(folder.as_str()).is_some_and(|rest| rest.starts_with('/'));
+            if !in_folder {
+                return false;
+            }
+        }
+        if let Some(app) = &self.app {
+            let apps = &snippet.meta.apps;
+            if !apps.is_empty() && !apps.iter().any(|name| app.contains(name.as_str())) {
+                return false;
+            }
+        }
+        true
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+            && self.language.is_none()
+            && self.tag.is_none()
+            && self.source.is_none()
+            && self.after.is_none()
+            && self.before.is_none()
+            && self.folder.is_none()
+            && self.app.is_none()
+            && !self.archived
+    }
+    
+    /// True when every snippet matching `self` also matches `previous`, so a
+    /// new pass only needs to re-check the previous results.
+    pub fn narrows(&self, previous: &SearchQuery) -> bool {
+        self.language == previous.language
+            && self.tag == previous.tag
+            && self.source == previous.source
+            && self.after == previous.after
+            && self.before == previous.before
+            && self.folder == previous.folder
+            && self.app == previous.app
+            && self.archived == previous.archived
+            && self.text.contains(&previous.text)
+    }
+}
+
+/// Swaps the `key:` filter in a search box query for `value`, or removes it,
+/// keeping the rest of the text.
+pub fn replace_filter(input: &str, key: &str, value: Option<&str>) -> String {
+    let prefix = format!("{}:", key);
+    let mut words: Vec<&str> = input.split_whitespace()
+        .filter(|word| !word.starts_with(&prefix))
+        .collect();
+    let filter = value.map(|value| format!("{}{}", prefix, value));
+    words.extend(filter.as_deref());
+    words.join(" ")
+}
+
+/// Byte ranges of `needle_lower` in `haystack`, ignoring case. Gives up
+/// when lowercasing changes the text's length, since the offsets would no
+/// longer line up.
+pub fn find_ignore_case(haystack: &str, needle_lower: &str) -> Vec<Range<usize>> {
+    let haystack_lower = haystack.to_lowercase();
+    if needle_lower.is_empty() || haystack_lower.len() != haystack.len() {
+        return Vec::new();
+    }
+    haystack_lower.match_indices(needle_lower)
+        .map(|(start, matched)| start..start + matched.len())
+        .filter(|range| haystack.is_char_boundary(range.start) && haystack.is_char_boundary(range.end))
+        .collect()
+}
+
+pub fn contains_ignore_case(haystack: &str, needle_lower: &str) -> bool {
+    if needle_lower.is_empty() {
+        return true;
+    }
+    
+    if needle_lower.is_ascii() {
+        let needle = needle_lower.as_bytes();
+        return haystack.as_bytes()
+            .windows(needle.len())
+            .any(|window| window.eq_ignore_ascii_case(needle));
+    }
+    
+    // Each slice is extended by the needle's length in chars so matches that
+    // straddle a slice boundary are still found.
+    let overlap = needle_lower.chars().count();
+    let mut start = 0;
+    while start < haystack.len() {
+        let mut end = (start + SEARCH_CHUNK).min(haystack.len());
+        while !haystack.is_char_boundary(end) {
+            end += 1;
+        }
+        let extended = haystack[end..]
+            .char_indices()
+            .nth(overlap)
+            .map_or(haystack.len(), |(offset, _)| end + offset);
+        
+        if haystack[start..extended].to_lowercase().contains(needle_lower) {
+            return true;
+        }
+        start = end;
+    }
+    
+    false
+}
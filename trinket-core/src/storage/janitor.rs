@@ -0,0 +1,62 @@
+use chrono::Utc;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use super::file_ops::FileStorage;
+
+/// Starts a background thread that moves expired snippets to the trash,
+/// first right away and then every `interval`. The ids of trashed snippets
+/// are sent back so the UI can drop them from its list. Snippets that have
+/// been in the trash longer than `trash_retention_days` are deleted for
+/// good; 0 keeps them. The value is read on every pass so it can change
+/// while the thread runs.
+pub fn spawn_janitor(
+    base_path: PathBuf,
+    interval: Duration,
+    trash_retention_days: Arc<AtomicU32>,
+) -> mpsc::Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    
+    std::thread::spawn(move || {
+        let storage = match FileStorage::new(base_path) {
+            Ok(storage) => storage,
+            Err(e) => {
+                log::error!("Janitor could not open storage: {}", e);
+                return;
+            }
+        };
+        
+        loop {
+            match storage.expired_snippets(Utc::now()) {
+                Ok(expired) => {
+                    for (id, path) in expired {
+                        match storage.move_to_trash(&path) {
+                            Ok(()) => {
+                                log::info!("Moved expired snippet {} to trash", id);
+                                if sender.send(id).is_err() {
+                                    return;
+                                }
+                            }
+                            Err(e) => log::error!("Failed to trash expired snippet {}: {}", id, e),
+                        }
+                    }
+                }
+                Err(e) => log::error!("Failed to scan for expired snippets: {}", e),
+            }
+            let days = trash_retention_days.load(Ordering::Relaxed);
+            if days > 0 {
+                let cutoff = Utc::now() - chrono::Duration::days(days.into());
+                match storage.purge_trash(Some(cutoff)) {
+                    Ok(0) => {}
+                    Ok(count) => log::info!("Purged {} snippet(s) trashed more than {} days ago", count, days),
+                    Err(e) => log::error!("Failed to purge old trash: {}", e),
+                }
+            }
+            std::thread::sleep(interval);
+        }
+    });
+    
+    receiver
+}
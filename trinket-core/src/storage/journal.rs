@@ -0,0 +1,139 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use super::file_ops::AGE_MAGIC;
+use super::metadata::SnippetMeta;
+use super::shred::shred_file;
+
+/// Append-only log of captures that have not reached their snippet file yet.
+/// Every save writes a `Capture` record first and a `Done` record once the
+/// snippet is persisted; anything left unmatched is replayed on startup.
+/// Content is logged the way its snippet file will hold it, so what a vault
+/// encrypts is encrypted here too.
+pub struct CaptureJournal {
+    path: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalRecord {
+    Capture {
+        id: String,
+        #[serde(default)]
+        content: String,
+        /// Base64 of content a vault encrypted, in place of `content`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sealed: Option<String>,
+        #[serde(default)]
+        folder: String,
+        #[serde(default)]
+        meta: Box<SnippetMeta>,
+    },
+    Done { id: String },
+}
+
+#[derive(Debug)]
+pub struct PendingCapture {
+    pub id: String,
+    /// Plain text, or an age file when a vault encrypted it.
+    pub content: Vec<u8>,
+    pub folder: String,
+    pub meta: SnippetMeta,
+}
+
+impl CaptureJournal {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+    
+    /// Logs a capture about to be saved. `content` is what its snippet file
+    /// will hold: UTF-8 text, or an age file for an encrypted target.
+    pub fn begin(&self, id: &str, content: &[u8], folder: &str, meta: &SnippetMeta) -> Result<(), std::io::Error> {
+        let (content, sealed) = if content.starts_with(AGE_MAGIC) {
+            (String::new(), Some(base64::engine::general_purpose::STANDARD.encode(content)))
+        } else {
+            let text = std::str::from_utf8(content).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            (text.to_string(), None)
+        };
+        self.append(&JournalRecord::Capture {
+            id: id.to_string(),
+            content,
+            sealed,
+            folder: folder.to_string(),
+            meta: Box::new(meta.clone()),
+        })
+    }
+    
+    pub fn finish(&self, id: &str) -> Result<(), std::io::Error> {
+        self.append(&JournalRecord::Done { id: id.to_string() })?;
+        if self.pending()?.is_empty() {
+            self.clear()?;
+        }
+        Ok(())
+    }
+    
+    pub fn pending(&self) -> Result<Vec<PendingCapture>, std::io::Error> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        
+        let mut order = Vec::new();
+        let mut captures = HashMap::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            // A torn final line means we crashed mid-append; the capture it
+            // belonged to was never acknowledged to the user either.
+            let Ok(record) = serde_json::from_str::<JournalRecord>(&line) else {
+                log::warn!("Skipping unreadable journal record");
+                continue;
+            };
+            match record {
+                JournalRecord::Capture { id, content, sealed, folder, meta } => {
+                    let content = match sealed {
+                        Some(sealed) => match base64::engine::general_purpose::STANDARD.decode(sealed) {
+                            Ok(bytes) => bytes,
+                            Err(_) => {
+                                log::warn!("Skipping journal record {} with unreadable content", id);
+                                continue;
+                            }
+                        },
+                        None => content.into_bytes(),
+                    };
+                    order.push(id.clone());
+                    captures.insert(id, (content, folder, meta));
+                }
+                JournalRecord::Done { id } => {
+                    captures.remove(&id);
+                }
+            }
+        }
+        
+        Ok(order.into_iter()
+            .filter_map(|id| {
+                captures.remove(&id).map(|(content, folder, meta)| PendingCapture { id, content, folder, meta: *meta })
+            })
+            .collect())
+    }
+    
+    /// Removes the journal, overwriting it first since it may hold secrets.
+    pub fn clear(&self) -> Result<(), std::io::Error> {
+        match shred_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+    
+    fn append(&self, record: &JournalRecord) -> Result<(), std::io::Error> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        file.sync_data()
+    }
+}
@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+
+/// Programming languages trinket recognises in pasted code. The detected
+/// language picks the file extension, drives highlighting and `lang:` filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Json,
+    Shell,
+    PowerShell,
+    Sql,
+    Html,
+    Css,
+    Yaml,
+    Toml,
+    C,
+    Cpp,
+    Go,
+    Java,
+}
+
+impl Language {
+    pub const ALL: [Language; 16] = [
+        Language::Rust,
+        Language::Python,
+        Language::JavaScript,
+        Language::TypeScript,
+        Language::Json,
+        Language::Shell,
+        Language::PowerShell,
+        Language::Sql,
+        Language::Html,
+        Language::Css,
+        Language::Yaml,
+        Language::Toml,
+        Language::C,
+        Language::Cpp,
+        Language::Go,
+        Language::Java,
+    ];
+    
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Json => "json",
+            Language::Shell => "shell",
+            Language::PowerShell => "powershell",
+            Language::Sql => "sql",
+            Language::Html => "html",
+            Language::Css => "css",
+            Language::Yaml => "yaml",
+            Language::Toml => "toml",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Go => "go",
+            Language::Java => "java",
+        }
+    }
+    
+    pub fn extension(self) -> &'static str {
+        match self {
+            Language::Rust => "rs",
+            Language::Python => "py",
+            Language::JavaScript => "js",
+            Language::TypeScript => "ts",
+            Language::Json => "json",
+            Language::Shell => "sh",
+            Language::PowerShell => "ps1",
+            Language::Sql => "sql",
+            Language::Html => "html",
+            Language::Css => "css",
+            Language::Yaml => "yaml",
+            Language::Toml => "toml",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Go => "go",
+            Language::Java => "java",
+        }
+    }
+    
+    /// Accepts names, extensions and a few common aliases, as typed in `lang:`.
+    pub fn parse(name: &str) -> Option<Language> {
+        let name = name.to_lowercase();
+        match name.as_str() {
+            "js" | "node" => Some(Language::JavaScript),
+            "ts" => Some(Language::TypeScript),
+            "bash" | "sh" | "zsh" => Some(Language::Shell),
+            "ps" | "pwsh" | "ps1" => Some(Language::PowerShell),
+            "py" => Some(Language::Python),
+            "rs" => Some(Language::Rust),
+            "yml" => Some(Language::Yaml),
+            "c++" => Some(Language::Cpp),
+            "golang" => Some(Language::Go),
+            _ => Language::ALL.into_iter()
+                .find(|lang| lang.name() == name || lang.extension() == name),
+        }
+    }
+    
+    pub fn from_extension(extension: &str) -> Option<Language> {
+        Language::ALL.into_iter().find(|lang| lang.extension() == extension)
+    }
+    
+    /// Guesses the language from content using cheap keyword heuristics.
+    /// Only looks at the first few kilobytes, and returns `None` for prose.
+    pub fn detect(content: &str) -> Option<Language> {
+        let sample = sample(content, DETECT_SAMPLE_BYTES);
+        let trimmed = sample.trim_start();
+        if trimmed.is_empty() {
+            return None;
+        }
+        
+        if let Some(first_line) = trimmed.lines().next() {
+            if first_line.starts_with("#!") {
+                return detect_shebang(first_line);
+            }
+        }
+        
+        if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && content.len() <= JSON_PARSE_LIMIT
+            && serde_json::from_str::<serde_json::Value>(content.trim()).is_ok()
+        {
+            return Some(Language::Json);
+        }
+        
+        let lower = trimmed.to_lowercase();
+        if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+            return Some(Language::Html);
+        }
+        
+        // Keywords turn up in sentences too, so prose needs more evidence.
+        let min_score = if is_prose(sample) { PROSE_MIN_SCORE } else { MIN_SCORE };
+        Language::ALL.into_iter()
+            .map(|lang| (lang, score(lang, sample)))
+            .filter(|(_, score)| *score >= min_score)
+            .max_by_key(|(_, score)| *score)
+            .map(|(lang, _)| lang)
+    }
+}
+
+const DETECT_SAMPLE_BYTES: usize = 4096;
+const MIN_SCORE: usize = 2;
+const PROSE_MIN_SCORE: usize = 4;
+/// Larger documents are scored on their sample instead of fully parsed.
+const JSON_PARSE_LIMIT: usize = 256 * 1024;
+
+fn sample(content: &str, max_bytes: usize) -> &str {
+    let mut end = content.len().min(max_bytes);
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    &content[..end]
+}
+
+fn detect_shebang(line: &str) -> Option<Language> {
+    if line.contains("python") {
+        Some(Language::Python)
+    } else if line.contains("node") {
+        Some(Language::JavaScript)
+    } else if line.contains("pwsh") {
+        Some(Language::PowerShell)
+    } else if line.contains("sh") {
+        Some(Language::Shell)
+    } else {
+        None
+    }
+}
+
+/// Whether most lines of `sample` read like sentences rather than code.
+fn is_prose(sample: &str) -> bool {
+    let lines: Vec<&str> = sample.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    let sentences = lines.iter()
+        .filter(|line| {
+            line.split_whitespace().count() >= 6
+                && line.ends_with(['.', '!', '?', ',', ':'])
+                && !line.contains([';', '{', '}', '=', '<', '>'])
+        })
+        .count();
+    sentences * 2 > lines.len()
+}
+
+/// Something in a sample that points to a language.
+enum Marker {
+    /// The text anywhere.
+    Text(&'static str),
+    /// A line starting with the first text, after its indentation, and
+    /// containing the second further on.
+    Line(&'static str, &'static str),
+}
+
+impl Marker {
+    fn found_in(&self, sample: &str) -> bool {
+        match self {
+            Marker::Text(text) => sample.contains(text),
+            Marker::Line(start, then) => sample.lines().any(|line| {
+                line.trim_start().strip_prefix(start).is_some_and(|rest| rest.contains(then))
+            }),
+        }
+    }
+}
+
+fn score(lang: Language, sample: &str) -> usize {
+    use Marker::{Line, Text};
+    
+    let markers: &[Marker] = match lang {
+        Language::Rust => &[Line("fn ", "("), Text("let mut "), Line("impl ", "{"), Text("pub fn"), Text("use std::"), Text("::new("), Text("-> Result<"), Text("#[derive"), Line("match ", "{")],
+        Language::Python => &[Line("def ", "("), Line("import ", ""), Line("from ", " import "), Text("self."), Line("elif ", ":"), Text("print("), Text("__init__"), Line("class ", ":"), Text("):\n")],
+        Language::JavaScript => &[Text("function "), Line("const ", "="), Text("=> {"), Text("console.log"), Text("require("), Text("document."), Line("let ", "="), Text("export default")],
+        Language::TypeScript => &[Text("interface "), Text(": string"), Text(": number"), Text("export type "), Text("implements "), Text(": boolean"), Text("<T>")],
+        // Valid JSON is caught before scoring; this only catches fragments.
+        Language::Json => &[Text("\": \""), Text("\": {"), Text("\": ["), Text("\": true"), Text("\": null")],
+        Language::Shell => &[Text("echo "), Text("sudo "), Text("export "), Text("fi\n"), Text("then\n"), Text("$("), Text("| grep"), Text("apt "), Line("cd ", ""), Text("&& ")],
+        Language::PowerShell => &[Text("Get-"), Text("Set-"), Text("$_"), Text("Write-Host"), Text("-ErrorAction"), Text("New-Object"), Text("$env:")],
+        Language::Sql => &[Text("SELECT "), Text("FROM "), Text("WHERE "), Text("INSERT INTO"), Text("UPDATE "), Text("JOIN "), Text("CREATE TABLE"), Text("GROUP BY")],
+        Language::Html => &[Text("<div"), Text("</div>"), Text("<span"), Text("<a href"), Text("<p>"), Text("<body"), Text("<head"), Text("class=\"")],
+        Language::Css => &[Text("{\n"), Text("color:"), Text("margin:"), Text("padding:"), Text("display:"), Text("font-"), Text("px;"), Text("@media")],
+        Language::Yaml => &[Text("---\n"), Text(":\n  "), Text("\n- "), Text("apiVersion:"), Text("name: "), Text("version: ")],
+        Language::Toml => &[Text("[package]"), Text("[dependencies]"), Text("\n["), Text(" = \""), Text("version = ")],
+        Language::C => &[Text("#include <"), Text("int main("), Text("printf("), Text("malloc("), Text("->"), Text("NULL"), Text("void ")],
+        Language::Cpp => &[Text("#include <"), Text("std::"), Text("cout <<"), Text("template<"), Text("nullptr"), Text("namespace "), Text("::")],
+        Language::Go => &[Line("package ", ""), Line("func ", "("), Text(":= "), Text("fmt."), Text("go func"), Text("err != nil"), Text("import (")],
+        Language::Java => &[Text("public class "), Text("public static void"), Text("System.out"), Text("private "), Text("import java."), Text("new "), Text("@Override")],
+    };
+    markers.iter().filter(|marker| marker.found_in(sample)).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prose_with_python_words_stays_plain_text() {
+        let paragraph = "Before the call, import the figures from the March report into the shared sheet.\n\
+            None of the regional numbers are final yet, so flag anything that looks off.\n\
+            We can class the outliers separately once the totals from finance are in.";
+        assert_eq!(Language::detect(paragraph), None);
+    }
+
+    #[test]
+    fn python_is_recognised_by_its_lines() {
+        let code = "from pathlib import Path\n\ndef newest(folder):\n    return max(Path(folder).iterdir())\n";
+        assert_eq!(Language::detect(code), Some(Language::Python));
+    }
+}
@@ -0,0 +1,61 @@
+use std::ops::Range;
+
+use super::Snippet;
+
+/// Shortest id prefix accepted as a reference, to avoid accidental matches.
+const MIN_ID_PREFIX: usize = 8;
+
+/// A `[[target]]` reference inside snippet content. `range` covers the whole
+/// link including brackets, `target` is the trimmed text between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetLink {
+    pub range: Range<usize>,
+    pub target: String,
+}
+
+pub fn parse_links(content: &str) -> Vec<SnippetLink> {
+    let mut links = Vec::new();
+    let mut offset = 0;
+    
+    while let Some(start) = content[offset..].find("[[") {
+        let start = offset + start;
+        let Some(len) = content[start + 2..].find("]]") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        let target = content[start + 2..end - 2].trim();
+        if !target.is_empty() && !target.contains('\n') {
+            links.push(SnippetLink {
+                range: start..end,
+                target: target.to_string(),
+            });
+        }
+        offset = end;
+    }
+    
+    links
+}
+
+/// Finds the snippet a link points at: exact id, then an unambiguous id
+/// prefix, then a case-insensitive match on the first line.
+pub fn resolve_link(target: &str, snippets: &[Snippet]) -> Option<usize> {
+    if let Some(index) = snippets.iter().position(|s| s.id == target) {
+        return Some(index);
+    }
+    
+    if target.len() >= MIN_ID_PREFIX {
+        let mut matches = snippets.iter()
+            .enumerate()
+            .filter(|(_, s)| s.id.starts_with(target));
+        if let (Some((index, _)), None) = (matches.next(), matches.next()) {
+            return Some(index);
+        }
+    }
+    
+    let target_lower = target.to_lowercase();
+    snippets.iter().position(|s| {
+        s.content.lines()
+            .next()
+            .is_some_and(|line| line.trim().to_lowercase() == target_lower)
+    })
+}
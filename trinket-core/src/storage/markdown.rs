@@ -0,0 +1,64 @@
+use chrono::{DateTime, Local};
+use std::path::Path;
+
+use super::file_ops::Snippet;
+
+/// `snippets` as one Markdown document: a heading per snippet with its date
+/// and tags under it, then the content in a code fence. Secrets are left
+/// out. Returns the document and how many snippets are in it.
+pub fn to_markdown<'a>(snippets: impl IntoIterator<Item = &'a Snippet>) -> (String, usize) {
+    let mut doc = String::from("# Snippets\n");
+    let mut count = 0;
+    for snippet in snippets.into_iter().filter(|snippet| !snippet.meta.secret) {
+        let created: DateTime<Local> = snippet.created.into();
+        doc.push_str(&format!("\n## {}\n\n", snippet.title));
+        let mut line = created.format("%Y-%m-%d").to_string();
+        for tag in &snippet.meta.tags {
+            line.push_str(&format!(" `#{}`", tag));
+        }
+        doc.push_str(&format!("{}\n\n", line));
+
+        // A fence longer than any run of backticks inside keeps the content whole.
+        let longest_run = snippet.content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat((longest_run + 1).max(3));
+        let language = snippet.meta.language.map_or("", |language| language.name());
+        doc.push_str(&format!("{}{}\n{}\n{}\n", fence, language, snippet.content.trim_end_matches('\n'), fence));
+        count += 1;
+    }
+    (doc, count)
+}
+
+/// Writes [`to_markdown`] of `snippets` to `path`, returning how many
+/// snippets it holds.
+pub fn export_markdown<'a>(snippets: impl IntoIterator<Item = &'a Snippet>, path: &Path) -> Result<usize, std::io::Error> {
+    let (doc, count) = to_markdown(snippets);
+    std::fs::write(path, doc)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{temp_storage, SnippetMeta};
+
+    #[test]
+    fn fences_outlast_backticks_and_secrets_stay_out() {
+        let (_dir, storage) = temp_storage();
+        let readme = SnippetMeta {
+            title: Some("Readme block".to_string()),
+            tags: vec!["docs".to_string()],
+            ..SnippetMeta::default()
+        };
+        storage.save_snippet("```sh\nls\n```", "", readme).unwrap();
+        let secret = SnippetMeta { secret: true, ..SnippetMeta::default() };
+        storage.save_snippet("hunter2", "", secret).unwrap();
+        let snippets = storage.load_all_snippets().unwrap();
+
+        let (doc, count) = to_markdown(&snippets);
+        assert_eq!(count, 1);
+        assert!(doc.contains("## Readme block\n"));
+        assert!(doc.contains(" `#docs`\n"));
+        assert!(doc.contains("\n````\n```sh\nls\n```\n````\n"));
+        assert!(!doc.contains("hunter2"));
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::file_ops::write_atomic;
+use super::language::Language;
+
+const SIDECAR_SUFFIX: &str = ".meta.json";
+
+/// Per-snippet metadata kept in a `<id>.meta.json` sidecar next to the content
+/// file, so it survives copies, restores and sync tools that reset file times.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnippetMeta {
+    pub created: Option<DateTime<Utc>>,
+    pub language: Option<Language>,
+    pub expires: Option<DateTime<Utc>>,
+    pub deleted: Option<DateTime<Utc>>,
+    pub trashed_from: Option<String>,
+    /// Masked in the UI, copied only on explicit request, and cleared from
+    /// the clipboard after a delay.
+    pub secret: bool,
+    /// Locked snippets refuse edits and deletion until unlocked.
+    pub locked: bool,
+    /// Free-form context about the snippet; searchable but never copied.
+    pub notes: String,
+    /// Content is a markdown task list shown as checkboxes.
+    pub checklist: bool,
+    /// Whether the editor checks the spelling. Unset checks prose and
+    /// leaves snippets with a detected language alone.
+    pub spellcheck: Option<bool>,
+    /// User-chosen title, replacing the one derived from the content.
+    pub title: Option<String>,
+    /// Title of the web page for snippets that are a single link.
+    pub page_title: Option<String>,
+    /// Lowercase labels, matched with `tag:` in the search box.
+    pub tags: Vec<String>,
+    /// Lowercase app names. When set, the snippet is only listed while one
+    /// of them had the focus as the get window opened; each is matched
+    /// against part of the focused app's name, so `terminal` covers
+    /// `gnome-terminal` and `WindowsTerminal`.
+    pub apps: Vec<String>,
+    /// The app that had the focus when the snippet was captured.
+    pub source: Option<SnippetSource>,
+    pub pinned: bool,
+    /// Left out of results unless searched for with `is:archived`. Unlike
+    /// deleting, it never goes to the trash or expires.
+    pub archived: bool,
+    /// Position among pinned snippets, lowest first; ignored when unpinned.
+    pub pin_order: u32,
+    /// When the snippet was last kept in a review of unused snippets.
+    pub reviewed: Option<DateTime<Utc>>,
+}
+
+/// Where a captured snippet came from, searched with `from:`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnippetSource {
+    pub app: String,
+    /// Title of the focused window; empty where the platform doesn't tell.
+    pub window: String,
+}
+
+impl SnippetMeta {
+    pub fn load(content_path: &Path) -> Option<Self> {
+        let text = fs::read_to_string(sidecar_path(content_path)).ok()?;
+        match serde_json::from_str(&text) {
+            Ok(meta) => Some(meta),
+            Err(e) => {
+                log::warn!("Ignoring unreadable metadata for {}: {}", content_path.display(), e);
+                None
+            }
+        }
+    }
+    
+    pub fn save(&self, content_path: &Path) -> Result<(), std::io::Error> {
+        let json = serde_json::to_vec_pretty(self)?;
+        write_atomic(&sidecar_path(content_path), &json)
+    }
+}
+
+pub fn sidecar_path(content_path: &Path) -> PathBuf {
+    let stem = content_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    content_path.with_file_name(format!("{}{}", stem, SIDECAR_SUFFIX))
+}
+
+pub fn is_sidecar(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .is_some_and(|name| name.ends_with(SIDECAR_SUFFIX))
+}
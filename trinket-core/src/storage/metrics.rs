@@ -0,0 +1,115 @@
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::file_ops::write_atomic;
+
+/// Kept in the data dir rather than the library, so it is never synced or
+/// backed up along with the snippets.
+pub const METRICS_FILE: &str = "metrics.json";
+
+/// Days kept; older ones are dropped on the next write.
+const KEPT_DAYS: usize = 90;
+
+/// One day's counters on this device.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DayMetrics {
+    /// Snippets saved, whether typed, captured or sent from another app.
+    pub captures: u32,
+    /// Searches that ended in a copy.
+    pub searches: u32,
+    pub copies: u32,
+    /// Copies made from the get window, which are timed.
+    pub timed_copies: u32,
+    /// Milliseconds from the get window opening to each timed copy, summed.
+    pub copy_latency_ms: u64,
+}
+
+impl DayMetrics {
+    /// Average time from opening the get window to copying.
+    pub fn average_latency(&self) -> Option<Duration> {
+        (self.timed_copies > 0).then(|| Duration::from_millis(self.copy_latency_ms / u64::from(self.timed_copies)))
+    }
+
+    pub fn add(&mut self, other: &DayMetrics) {
+        self.captures += other.captures;
+        self.searches += other.searches;
+        self.copies += other.copies;
+        self.timed_copies += other.timed_copies;
+        self.copy_latency_ms += other.copy_latency_ms;
+    }
+}
+
+/// Counters of how the app is used, by local date. Only ever written to
+/// disk here and shown in the statistics view; nothing is sent anywhere.
+pub struct Metrics {
+    path: PathBuf,
+    days: BTreeMap<NaiveDate, DayMetrics>,
+}
+
+impl Metrics {
+    pub fn open(path: PathBuf) -> Self {
+        let days = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                log::warn!("Starting metrics afresh, the file is unreadable: {}", e);
+                BTreeMap::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                log::warn!("Failed to read metrics: {}", e);
+                BTreeMap::new()
+            }
+        };
+        Self { path, days }
+    }
+
+    pub fn days(&self) -> &BTreeMap<NaiveDate, DayMetrics> {
+        &self.days
+    }
+
+    pub fn record_capture(&mut self) -> Result<(), std::io::Error> {
+        self.today().captures += 1;
+        self.save()
+    }
+
+    pub fn record_search(&mut self) -> Result<(), std::io::Error> {
+        self.today().searches += 1;
+        self.save()
+    }
+
+    /// `latency` is the time since the get window opened, when the copy was
+    /// made from it.
+    pub fn record_copy(&mut self, latency: Option<Duration>) -> Result<(), std::io::Error> {
+        let today = self.today();
+        today.copies += 1;
+        if let Some(latency) = latency {
+            today.timed_copies += 1;
+            today.copy_latency_ms += u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        }
+        self.save()
+    }
+
+    /// Forgets every day recorded, removing the file.
+    pub fn clear(&mut self) -> Result<(), std::io::Error> {
+        self.days.clear();
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn today(&mut self) -> &mut DayMetrics {
+        self.days.entry(Local::now().date_naive()).or_default()
+    }
+
+    fn save(&mut self) -> Result<(), std::io::Error> {
+        while self.days.len() > KEPT_DAYS {
+            self.days.pop_first();
+        }
+        write_atomic(&self.path, &serde_json::to_vec(&self.days)?)
+    }
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use super::backup::write_backup;
+use super::error::StorageError;
+use super::file_ops::{content_files_in, write_atomic, FileStorage, AGE_MAGIC};
+use super::history::HISTORY_DIR;
+use super::metadata::{sidecar_path, SnippetMeta};
+
+/// Records which layout the library is in. Kept with the snippets, so a
+/// restored backup carries the layout it was taken in.
+pub const MANIFEST_FILE: &str = ".manifest.json";
+
+/// A step from one on-disk layout to the next.
+struct Migration {
+    /// The layout the library is in once the step has run.
+    version: u32,
+    description: &'static str,
+    /// Whether the step can run now. One that can't, such as encrypting
+    /// without a vault, holds back the ones after it until it can.
+    applies: fn(&FileStorage) -> bool,
+    /// Upgrades the library, returning how many files were changed.
+    run: fn(&FileStorage) -> Result<usize, StorageError>,
+}
+
+/// In order; a library with no manifest is at layout 0, plain `.txt`
+/// files only.
+const MIGRATIONS: [Migration; 2] = [
+    Migration {
+        version: 1,
+        description: "Add metadata sidecars to plain snippets",
+        applies: |_| true,
+        run: add_sidecars,
+    },
+    Migration {
+        version: 2,
+        description: "Encrypt plain snippets with the vault",
+        applies: |storage| storage.encrypts(""),
+        run: encrypt_plain,
+    },
+];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+}
+
+impl Manifest {
+    fn load(base_path: &Path) -> Result<Self, std::io::Error> {
+        match fs::read(base_path.join(MANIFEST_FILE)) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, base_path: &Path) -> Result<(), std::io::Error> {
+        write_atomic(&base_path.join(MANIFEST_FILE), &serde_json::to_vec_pretty(self)?)
+    }
+}
+
+/// The newest layout this build knows.
+pub fn current_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+impl FileStorage {
+    /// Brings the library up to the newest layout that can be reached now,
+    /// writing a backup into `backup_dir` before the first change. Returns
+    /// the steps run, which is none when the library is already up to date.
+    /// A library written by a newer trinket, or a read-only one, is left
+    /// alone.
+    pub fn migrate(&self, backup_dir: &Path) -> Result<Vec<&'static str>, StorageError> {
+        let mut manifest = Manifest::load(&self.base_path)?;
+        if manifest.version > current_version() {
+            return Err(StorageError::NewerLayout { found: manifest.version, known: current_version() });
+        }
+        if self.is_read_only() {
+            return Ok(Vec::new());
+        }
+
+        let pending: Vec<&Migration> = MIGRATIONS.iter()
+            .filter(|migration| migration.version > manifest.version)
+            .take_while(|migration| (migration.applies)(self))
+            .collect();
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+        // A new library has nothing worth a backup.
+        if !content_files_in(&self.base_path)?.is_empty() {
+            let backup = write_backup(&self.base_path, backup_dir)?;
+            log::info!("Backed up the library to {} before migrating", backup.path.display());
+        }
+
+        let mut applied = Vec::new();
+        for migration in pending {
+            let changed = (migration.run)(self)?;
+            log::info!("Migrated to layout {} ({}): {} file(s) changed", migration.version, migration.description, changed);
+            // Saved after each step, so an interrupted run resumes where
+            // it stopped.
+            manifest.version = migration.version;
+            manifest.save(&self.base_path)?;
+            applied.push(migration.description);
+        }
+        Ok(applied)
+    }
+}
+
+/// Dates every snippet without a sidecar, as loading it would.
+fn add_sidecars(storage: &FileStorage) -> Result<usize, StorageError> {
+    let mut changed = 0;
+    for path in content_files_in(&storage.base_path)? {
+        if sidecar_path(&path).exists() || storage.is_folder_locked(&storage.folder_of(&path)) {
+            continue;
+        }
+        let metadata = fs::metadata(&path)?;
+        let created = metadata.created()
+            .or_else(|_| metadata.modified())?;
+        let meta = SnippetMeta {
+            created: Some(created.into()),
+            ..SnippetMeta::default()
+        };
+        meta.save(&path)?;
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+/// Rewrites plain content, and the versions kept of it, with the key its
+/// folder is written with. Snippets in locked folders wait for their key.
+fn encrypt_plain(storage: &FileStorage) -> Result<usize, StorageError> {
+    let mut changed = 0;
+    let mut folders = HashMap::new();
+    for path in content_files_in(&storage.base_path)? {
+        let folder = storage.folder_of(&path);
+        if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+            folders.insert(id.to_string(), folder.clone());
+        }
+        changed += encrypt_file(storage, &folder, &path)?;
+    }
+
+    let history = storage.base_path.join(HISTORY_DIR);
+    if !history.exists() {
+        return Ok(changed);
+    }
+    for entry in fs::read_dir(history)? {
+        let dir = entry?.path();
+        let Some(folder) = dir.file_name().and_then(|s| s.to_str()).and_then(|id| folders.get(id)) else {
+            continue;
+        };
+        for version in fs::read_dir(&dir)? {
+            changed += encrypt_file(storage, folder, &version?.path())?;
+        }
+    }
+    Ok(changed)
+}
+
+fn encrypt_file(storage: &FileStorage, folder: &str, path: &Path) -> Result<usize, StorageError> {
+    if !storage.encrypts(folder) || fs::read(path)?.starts_with(AGE_MAGIC) {
+        return Ok(0);
+    }
+    let text = storage.read_text(path)?;
+    // The plain text being replaced is what encryption is meant to hide.
+    storage.overwrite_text(folder, path, &text)?;
+    Ok(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::temp_storage;
+
+    #[test]
+    fn plain_library_gets_sidecars_and_a_backup() {
+        let (dir, storage) = temp_storage();
+        let path = storage.base_path.join("legacy.txt");
+        fs::write(&path, "written before sidecars").unwrap();
+        let backups = dir.path().join("backups");
+
+        let applied = storage.migrate(&backups).unwrap();
+
+        assert_eq!(applied, [MIGRATIONS[0].description]);
+        assert!(SnippetMeta::load(&path).and_then(|meta| meta.created).is_some());
+        assert_eq!(fs::read_dir(&backups).unwrap().count(), 1);
+        // Encrypting waits for a vault; until then there's nothing to do.
+        assert_eq!(Manifest::load(&storage.base_path).unwrap().version, 1);
+        assert!(storage.migrate(&backups).unwrap().is_empty());
+    }
+
+    #[test]
+    fn newer_layout_is_left_alone() {
+        let (dir, storage) = temp_storage();
+        Manifest { version: current_version() + 1 }.save(&storage.base_path).unwrap();
+
+        assert!(storage.migrate(&dir.path().join("backups")).is_err());
+    }
+}
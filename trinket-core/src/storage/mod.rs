@@ -0,0 +1,57 @@
+#[cfg(feature = "vault")]
+pub mod archive;
+pub mod audit;
+pub mod backend;
+pub mod backup;
+pub mod changes;
+pub mod checklist;
+pub mod duplicates;
+pub mod error;
+pub mod file_ops;
+pub mod folders;
+pub mod history;
+pub mod indexer;
+pub mod janitor;
+pub mod journal;
+pub mod language;
+pub mod links;
+pub mod markdown;
+pub mod metrics;
+pub mod metadata;
+pub mod migrations;
+pub mod review;
+pub mod search_history;
+pub mod search_provider;
+pub mod shred;
+pub mod starters;
+pub mod stats;
+pub mod tags;
+pub mod title;
+pub mod trash;
+pub mod usage;
+pub mod worker;
+
+pub use backend::{Capabilities, StorageBackend};
+pub use checklist::{parse_checklist, ChecklistLine};
+pub use error::StorageError;
+pub use file_ops::{next_pin_order, sort_snippets, FileStorage, Snippet, DEFAULT_MAX_SNIPPET_BYTES, SECRET_MASK};
+pub use history::Version;
+pub use indexer::{SearchIndex, SearchQuery};
+pub use language::Language;
+pub use links::{parse_links, resolve_link};
+pub use metrics::Metrics;
+pub use metadata::{SnippetMeta, SnippetSource};
+pub use search_history::SearchHistory;
+pub use search_provider::{SearchProvider, SearchProviderKind};
+pub use stats::StorageStats;
+pub use usage::UsageLog;
+pub use worker::StorageWorker;
+
+/// A library in a fresh temporary folder, kept until the `TempDir` drops.
+/// It sits in `library` under it, leaving room beside it for other files.
+#[cfg(test)]
+pub(crate) fn temp_storage() -> (tempfile::TempDir, FileStorage) {
+    let dir = tempfile::TempDir::new().unwrap();
+    let storage = FileStorage::new(dir.path().join("library")).unwrap();
+    (dir, storage)
+}
\ No newline at end of file
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Months, Utc};
+
+use super::file_ops::Snippet;
+use super::usage::UsageLog;
+
+/// When the snippet last proved useful: copied, edited or kept in a review.
+pub fn last_touched(snippet: &Snippet, usage: &UsageLog) -> DateTime<Utc> {
+    let modified: DateTime<Utc> = snippet.modified.into();
+    [usage.get(&snippet.id).map(|u| u.last_used), snippet.meta.reviewed]
+        .into_iter()
+        .flatten()
+        .fold(modified, DateTime::max)
+}
+
+/// Indices of the snippets untouched for `months` months, the longest
+/// untouched first. Pinned and archived snippets are kept on purpose and
+/// never listed.
+pub fn stale_snippets(snippets: &[Snippet], usage: &UsageLog, months: u32, now: DateTime<Utc>) -> Vec<usize> {
+    let Some(cutoff) = now.checked_sub_months(Months::new(months)) else {
+        return Vec::new();
+    };
+    let mut stale: Vec<(usize, DateTime<Utc>)> = snippets.iter()
+        .enumerate()
+        .filter(|(_, snippet)| !snippet.meta.pinned && !snippet.meta.archived)
+        .map(|(index, snippet)| (index, last_touched(snippet, usage)))
+        .filter(|&(_, touched)| touched < cutoff)
+        .collect();
+    stale.sort_by_key(|&(_, touched)| touched);
+    stale.into_iter().map(|(index, _)| index).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SnippetMeta;
+    use chrono::Duration;
+    use std::path::PathBuf;
+
+    fn snippet(id: &str, modified: DateTime<Utc>, meta: SnippetMeta) -> Snippet {
+        Snippet {
+            id: id.to_string(),
+            content: id.to_string(),
+            title: id.to_string(),
+            created: modified.into(),
+            modified: modified.into(),
+            file_path: PathBuf::from(format!("{}.txt", id)),
+            folder: String::new(),
+            meta,
+        }
+    }
+
+    #[test]
+    fn review_pin_and_archive_keep_snippets_off_the_list() {
+        let now = Utc::now();
+        let old = now - Duration::days(400);
+        let usage = UsageLog::empty(PathBuf::from("usage.jsonl"));
+        let snippets = [
+            snippet("old", old, SnippetMeta::default()),
+            snippet("recent", now, SnippetMeta::default()),
+            snippet("reviewed", old, SnippetMeta { reviewed: Some(now), ..SnippetMeta::default() }),
+            snippet("pinned", old, SnippetMeta { pinned: true, ..SnippetMeta::default() }),
+            snippet("archived", old, SnippetMeta { archived: true, ..SnippetMeta::default() }),
+        ];
+
+        assert_eq!(stale_snippets(&snippets, &usage, 6, now), [0]);
+    }
+}
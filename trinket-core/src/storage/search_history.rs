@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::PathBuf;
+
+pub const SEARCH_HISTORY_FILE: &str = ".search-history";
+const MAX_QUERIES: usize = 20;
+
+/// Recently used search queries, newest first, one per line on disk.
+#[derive(Clone)]
+pub struct SearchHistory {
+    path: PathBuf,
+    queries: Vec<String>,
+}
+
+impl SearchHistory {
+    pub fn empty(path: PathBuf) -> Self {
+        Self {
+            path,
+            queries: Vec::new(),
+        }
+    }
+
+    pub fn open(path: PathBuf) -> Result<Self, std::io::Error> {
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::empty(path)),
+            Err(e) => return Err(e),
+        };
+        let queries = text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(MAX_QUERIES)
+            .map(str::to_string)
+            .collect();
+        Ok(Self { path, queries })
+    }
+
+    /// Moves `query` to the front, dropping the oldest entry when full.
+    /// Returns whether that changed anything, to [`save`](Self::save).
+    pub fn record(&mut self, query: &str) -> bool {
+        let query = query.trim();
+        if query.is_empty() || self.queries.first().is_some_and(|first| first == query) {
+            return false;
+        }
+        self.queries.retain(|existing| existing != query);
+        self.queries.insert(0, query.to_string());
+        self.queries.truncate(MAX_QUERIES);
+        true
+    }
+
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        fs::write(&self.path, self.queries.join("\n"))
+    }
+
+    pub fn queries(&self) -> &[String] {
+        &self.queries
+    }
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use super::indexer::{find_ignore_case, SearchIndex, SearchQuery};
+use super::Snippet;
+
+/// Fuzzy hits are scaled below 1.0, so when both providers run, snippets
+/// containing the exact text list before ones that only match fuzzily.
+const FUZZY_WEIGHT: f32 = 0.5;
+
+/// How the get window finds snippets for the free text of a query. The
+/// `key:value` filters are applied the same way for every provider, through
+/// `SearchQuery::admits`.
+pub trait SearchProvider: Send {
+    /// Rebuilds whatever the provider keeps about the library. Called when
+    /// the snippets change, before the next search; providers that keep
+    /// nothing leave it alone.
+    fn index(&mut self, _snippets: &[Snippet]) {}
+
+    /// How well `snippet` matches, higher is better, or `None` when it
+    /// doesn't. Asked one snippet at a time so a search can be spread over
+    /// several frames.
+    fn score(&self, snippet: &Snippet, query: &SearchQuery) -> Option<f32>;
+
+    /// Byte ranges of `text` to mark as matching, in order and not
+    /// overlapping.
+    fn highlight(&self, text: &str, query: &SearchQuery) -> Vec<Range<usize>>;
+
+    /// Indices of the matching snippets, best first; equal scores keep the
+    /// order of `snippets`.
+    fn query(&self, query: &SearchQuery, snippets: &[Snippet]) -> Vec<usize> {
+        if query.is_empty() {
+            // Archived snippets stay out even without a query.
+            return snippets.iter()
+                .enumerate()
+                .filter_map(|(idx, snippet)| query.admits(snippet).then_some(idx))
+                .collect();
+        }
+        let mut hits: Vec<(usize, f32)> = snippets.iter()
+            .enumerate()
+            .filter_map(|(idx, snippet)| Some((idx, self.score(snippet, query)?)))
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+        hits.into_iter().map(|(idx, _)| idx).collect()
+    }
+}
+
+/// The providers that can be named in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchProviderKind {
+    /// Content and notes containing the text, ignoring case.
+    Substring,
+    /// Titles holding the letters of each word in order, so `gco` finds
+    /// "git checkout".
+    Fuzzy,
+}
+
+impl SearchProviderKind {
+    pub const ALL: [SearchProviderKind; 2] = [SearchProviderKind::Substring, SearchProviderKind::Fuzzy];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchProviderKind::Substring => "Text",
+            SearchProviderKind::Fuzzy => "Fuzzy titles",
+        }
+    }
+}
+
+/// The provider for `kinds`: the one named, or every one named combined.
+/// An empty list means the substring scan.
+pub fn build(kinds: &[SearchProviderKind]) -> Box<dyn SearchProvider> {
+    let mut providers: Vec<Box<dyn SearchProvider>> = kinds.iter()
+        .map(|kind| -> Box<dyn SearchProvider> {
+            match kind {
+                SearchProviderKind::Substring => Box::new(SearchIndex::new()),
+                SearchProviderKind::Fuzzy => Box::new(FuzzySearch),
+            }
+        })
+        .collect();
+    match providers.len() {
+        0 => Box::new(SearchIndex::new()),
+        1 => providers.remove(0),
+        _ => Box::new(CombinedSearch { providers }),
+    }
+}
+
+impl SearchProvider for SearchIndex {
+    fn score(&self, snippet: &Snippet, query: &SearchQuery) -> Option<f32> {
+        self.matches(snippet, query).then_some(1.0)
+    }
+
+    fn highlight(&self, text: &str, query: &SearchQuery) -> Vec<Range<usize>> {
+        find_ignore_case(text, &query.text)
+    }
+}
+
+/// Matches each word of the query as a subsequence of the title, scoring
+/// runs of adjacent letters and letters starting a word higher.
+pub struct FuzzySearch;
+
+impl SearchProvider for FuzzySearch {
+    fn score(&self, snippet: &Snippet, query: &SearchQuery) -> Option<f32> {
+        if !query.admits(snippet) {
+            return None;
+        }
+        let words: Vec<&str> = query.text.split_whitespace().collect();
+        if words.is_empty() {
+            return Some(FUZZY_WEIGHT);
+        }
+        let mut total = 0.0;
+        for word in &words {
+            total += subsequence(&snippet.title, word)?.0;
+        }
+        Some(FUZZY_WEIGHT * total / words.len() as f32)
+    }
+
+    /// Only the first line is marked, where the title comes from; letters
+    /// scattered through a whole snippet would mark nearly everything.
+    fn highlight(&self, text: &str, query: &SearchQuery) -> Vec<Range<usize>> {
+        let first_line = text.lines().next().unwrap_or_default();
+        let mut spans: Vec<Range<usize>> = query.text.split_whitespace()
+            .filter_map(|word| subsequence(first_line, word))
+            .flat_map(|(_, spans)| spans)
+            .collect();
+        merge(&mut spans);
+        spans
+    }
+}
+
+/// Where the letters of `word` first appear in order in `text`, as byte
+/// ranges, with a score between 0 and 1.
+fn subsequence(text: &str, word: &str) -> Option<(f32, Vec<Range<usize>>)> {
+    let mut wanted = word.chars().peekable();
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    let mut bonus = 0;
+    let mut previous: Option<char> = None;
+    for (at, c) in text.char_indices() {
+        let Some(&next) = wanted.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(std::iter::once(next)) {
+            wanted.next();
+            if previous.is_none_or(|previous| !previous.is_alphanumeric()) {
+                bonus += 1;
+            }
+            match spans.last_mut() {
+                Some(span) if span.end == at => {
+                    span.end = at + c.len_utf8();
+                    bonus += 1;
+                }
+                _ => spans.push(at..at + c.len_utf8()),
+            }
+        }
+        previous = Some(c);
+    }
+    if wanted.peek().is_some() {
+        return None;
+    }
+    let letters = word.chars().count() as f32;
+    Some(((letters + bonus as f32) / (3.0 * letters), spans))
+}
+
+/// Sorts `spans` and joins the ones that touch or overlap.
+fn merge(spans: &mut Vec<Range<usize>>) {
+    spans.sort_by_key(|span| span.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(spans.len());
+    for span in spans.drain(..) {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    *spans = merged;
+}
+
+/// Several providers at once: a snippet matches when any of them matches
+/// it, with the best of their scores.
+pub struct CombinedSearch {
+    providers: Vec<Box<dyn SearchProvider>>,
+}
+
+impl SearchProvider for CombinedSearch {
+    fn index(&mut self, snippets: &[Snippet]) {
+        for provider in &mut self.providers {
+            provider.index(snippets);
+        }
+    }
+
+    fn score(&self, snippet: &Snippet, query: &SearchQuery) -> Option<f32> {
+        self.providers.iter()
+            .filter_map(|provider| provider.score(snippet, query))
+            .reduce(f32::max)
+    }
+
+    fn highlight(&self, text: &str, query: &SearchQuery) -> Vec<Range<usize>> {
+        let mut spans: Vec<Range<usize>> = self.providers.iter()
+            .flat_map(|provider| provider.highlight(text, query))
+            .collect();
+        merge(&mut spans);
+        spans
+    }
+}
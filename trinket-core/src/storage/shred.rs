@@ -0,0 +1,35 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Overwrites `path` with zeros, flushes that to disk and removes it, so a
+/// deleted secret can't be read back from the freed blocks. Journaling and
+/// copy-on-write filesystems or SSD wear levelling may still keep older
+/// copies elsewhere.
+pub fn shred_file(path: &Path) -> Result<(), std::io::Error> {
+    scrub_file(path)?;
+    fs::remove_file(path)
+}
+
+/// Overwrites `path` with zeros in place and flushes that to disk, keeping
+/// the file, such as before something is renamed over it.
+pub fn scrub_file(path: &Path) -> Result<(), std::io::Error> {
+    let len = fs::metadata(path)?.len();
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    std::io::copy(&mut std::io::repeat(0).take(len), &mut file)?;
+    file.flush()?;
+    file.sync_all()
+}
+
+/// Shreds every file below `dir`, then removes the directory.
+pub fn shred_dir(dir: &Path) -> Result<(), std::io::Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            shred_dir(&path)?;
+        } else {
+            shred_file(&path)?;
+        }
+    }
+    fs::remove_dir(dir)
+}
@@ -0,0 +1,133 @@
+use super::error::StorageError;
+use super::file_ops::{FileStorage, Snippet};
+use super::metadata::SnippetMeta;
+
+/// Starter sets are imported into folders under this one.
+pub const STARTER_FOLDER: &str = "starters";
+
+/// A themed handful of snippets a new library can start from.
+pub struct StarterSet {
+    pub name: &'static str,
+    /// The folder under [`STARTER_FOLDER`] it's imported into.
+    pub folder: &'static str,
+    pub description: &'static str,
+    snippets: &'static [Starter],
+}
+
+struct Starter {
+    title: &'static str,
+    content: &'static str,
+    tags: &'static [&'static str],
+}
+
+impl StarterSet {
+    pub fn len(&self) -> usize {
+        self.snippets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snippets.is_empty()
+    }
+}
+
+pub const STARTER_SETS: [StarterSet; 3] = [
+    StarterSet {
+        name: "Git commands",
+        folder: "git",
+        description: "Undoing, stashing, rebasing and cleaning up branches",
+        snippets: &[
+            Starter { title: "Undo the last commit, keep the changes", content: "git reset --soft HEAD~1", tags: &["git"] },
+            Starter { title: "Amend the last commit without editing the message", content: "git commit --amend --no-edit", tags: &["git"] },
+            Starter { title: "Stash including untracked files", content: "git stash push --include-untracked -m \"wip\"", tags: &["git"] },
+            Starter { title: "Interactive rebase onto main", content: "git fetch origin\ngit rebase -i origin/main", tags: &["git"] },
+            Starter { title: "Delete merged local branches", content: "git branch --merged main | grep -v '^[ *]*main$' | xargs git branch -d", tags: &["git"] },
+            Starter { title: "Compact log graph", content: "git log --oneline --graph --decorate --all", tags: &["git"] },
+            Starter { title: "Show who changed each line", content: "git blame -w -C -L 1,40 path/to/file", tags: &["git"] },
+            Starter { title: "Discard local changes to a file", content: "git restore path/to/file", tags: &["git"] },
+            Starter { title: "Push a new branch and track it", content: "git push -u origin HEAD", tags: &["git"] },
+        ],
+    },
+    StarterSet {
+        name: "HTTP status codes",
+        folder: "http",
+        description: "The codes APIs return most, with what each means",
+        snippets: &[
+            Starter { title: "200 OK", content: "200 OK\nThe request succeeded.", tags: &["http"] },
+            Starter { title: "201 Created", content: "201 Created\nA resource was created; its URL is in the Location header.", tags: &["http"] },
+            Starter { title: "204 No Content", content: "204 No Content\nThe request succeeded and there is no body.", tags: &["http"] },
+            Starter { title: "301 Moved Permanently", content: "301 Moved Permanently\nThe resource lives at the URL in the Location header from now on.", tags: &["http"] },
+            Starter { title: "304 Not Modified", content: "304 Not Modified\nThe cached copy is still current.", tags: &["http"] },
+            Starter { title: "400 Bad Request", content: "400 Bad Request\nThe request was malformed or failed validation.", tags: &["http"] },
+            Starter { title: "401 Unauthorized", content: "401 Unauthorized\nCredentials are missing or invalid.", tags: &["http"] },
+            Starter { title: "403 Forbidden", content: "403 Forbidden\nThe credentials are valid but not allowed to do this.", tags: &["http"] },
+            Starter { title: "404 Not Found", content: "404 Not Found\nThere is nothing at this URL.", tags: &["http"] },
+            Starter { title: "409 Conflict", content: "409 Conflict\nThe request clashes with the resource's current state.", tags: &["http"] },
+            Starter { title: "429 Too Many Requests", content: "429 Too Many Requests\nRate limited; wait as long as the Retry-After header says.", tags: &["http"] },
+            Starter { title: "500 Internal Server Error", content: "500 Internal Server Error\nThe server failed while handling the request.", tags: &["http"] },
+            Starter { title: "503 Service Unavailable", content: "503 Service Unavailable\nThe server is overloaded or down for maintenance.", tags: &["http"] },
+        ],
+    },
+    StarterSet {
+        name: "Regex recipes",
+        folder: "regex",
+        description: "Patterns for emails, dates, addresses and whitespace",
+        snippets: &[
+            Starter { title: "Email address (loose)", content: r"[^@\s]+@[^@\s]+\.[^@\s]+", tags: &["regex"] },
+            Starter { title: "ISO 8601 date", content: r"\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12]\d|3[01])", tags: &["regex"] },
+            Starter { title: "IPv4 address", content: r"\b((25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(25[0-5]|2[0-4]\d|1?\d?\d)\b", tags: &["regex"] },
+            Starter { title: "UUID", content: r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}", tags: &["regex"] },
+            Starter { title: "URL (http or https)", content: r"https?://[^\s/$.?#].[^\s]*", tags: &["regex"] },
+            Starter { title: "Hex color", content: r"#(?:[0-9a-fA-F]{3}){1,2}\b", tags: &["regex"] },
+            Starter { title: "Trailing whitespace", content: r"[ \t]+$", tags: &["regex"] },
+            Starter { title: "Blank lines", content: r"^\s*$\n", tags: &["regex"] },
+        ],
+    },
+];
+
+impl FileStorage {
+    /// Saves the snippets of `set` into its folder, leaving out any whose
+    /// content is already there, so importing again only brings back the
+    /// ones deleted since. Returns the snippets saved.
+    pub fn import_starters(&self, set: &StarterSet) -> Result<Vec<Snippet>, StorageError> {
+        let folder = format!("{}/{}", STARTER_FOLDER, set.folder);
+        let existing: Vec<Snippet> = self.load_all_snippets()?
+            .into_iter()
+            .filter(|snippet| snippet.folder == folder)
+            .collect();
+
+        let mut saved = Vec::new();
+        for starter in set.snippets {
+            if existing.iter().any(|snippet| snippet.content == starter.content) {
+                continue;
+            }
+            let meta = SnippetMeta {
+                title: Some(starter.title.to_string()),
+                tags: starter.tags.iter().map(|tag| tag.to_string()).collect(),
+                ..SnippetMeta::default()
+            };
+            saved.push(self.save_snippet(starter.content, &folder, meta)?);
+        }
+        Ok(saved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::temp_storage;
+
+    #[test]
+    fn importing_again_adds_only_what_was_deleted() {
+        let (_dir, storage) = temp_storage();
+        let set = &STARTER_SETS[0];
+
+        let first = storage.import_starters(set).unwrap();
+        assert_eq!(first.len(), set.len());
+        assert!(first.iter().all(|snippet| snippet.folder == "starters/git" && snippet.meta.title.is_some()));
+
+        storage.delete_snippet(&first[0]).unwrap();
+        let second = storage.import_starters(set).unwrap();
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].content, first[0].content);
+    }
+}
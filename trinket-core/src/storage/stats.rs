@@ -0,0 +1,90 @@
+//! Counts describing the library, for a diagnostics bundle attached to a
+//! bug report. Nothing in them says what the snippets hold or are called.
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use super::error::StorageError;
+use super::file_ops::FileStorage;
+use super::metadata::SnippetMeta;
+
+#[derive(Debug, Default, Serialize)]
+pub struct StorageStats {
+    pub snippets: usize,
+    pub secret: usize,
+    pub locked: usize,
+    pub pinned: usize,
+    pub archived: usize,
+    pub folders: usize,
+    pub locked_folders: usize,
+    pub trashed: usize,
+    /// Files under the library folder, trash, history and journal included.
+    pub files: usize,
+    pub bytes: u64,
+    pub read_only: bool,
+    #[cfg(feature = "vault")]
+    pub vault_locked: bool,
+}
+
+impl FileStorage {
+    pub fn statistics(&self) -> Result<StorageStats, StorageError> {
+        let snippets = self.load_all_snippets()?;
+        let count = |wanted: fn(&SnippetMeta) -> bool| snippets.iter().filter(|snippet| wanted(&snippet.meta)).count();
+        let (files, bytes) = disk_usage(&self.base_path)?;
+        Ok(StorageStats {
+            snippets: snippets.len(),
+            secret: count(|meta| meta.secret),
+            locked: count(|meta| meta.locked),
+            pinned: count(|meta| meta.pinned),
+            archived: count(|meta| meta.archived),
+            folders: self.list_folders()?.len(),
+            locked_folders: self.locked_folders().len(),
+            trashed: self.trash_files()?.len(),
+            files,
+            bytes,
+            read_only: self.is_read_only(),
+            #[cfg(feature = "vault")]
+            vault_locked: self.is_vault_locked(),
+        })
+    }
+}
+
+/// Files under `dir` and their total size in bytes.
+fn disk_usage(dir: &Path) -> Result<(usize, u64), std::io::Error> {
+    let (mut files, mut bytes) = (0, 0);
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            let (inner_files, inner_bytes) = disk_usage(&entry.path())?;
+            files += inner_files;
+            bytes += inner_bytes;
+        } else {
+            files += 1;
+            bytes += metadata.len();
+        }
+    }
+    Ok((files, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::temp_storage;
+
+    #[test]
+    fn statistics_count_without_reading_out_content() {
+        let (_dir, storage) = temp_storage();
+        storage.save_snippet("hunter2", "work", SnippetMeta { secret: true, ..SnippetMeta::default() }).unwrap();
+        storage.save_snippet("Kind regards", "", SnippetMeta { pinned: true, ..SnippetMeta::default() }).unwrap();
+        let gone = storage.save_snippet("old", "", SnippetMeta::default()).unwrap();
+        storage.delete_snippet(&gone).unwrap();
+
+        let stats = storage.statistics().unwrap();
+        assert_eq!((stats.snippets, stats.secret, stats.pinned, stats.trashed), (2, 1, 1, 1));
+        assert_eq!(stats.folders, 1);
+        assert!(stats.files >= 3 && stats.bytes > 0);
+        assert!(!serde_json::to_string(&stats).unwrap().contains("hunter2"));
+    }
+}
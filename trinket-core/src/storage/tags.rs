@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use super::error::StorageError;
+use super::file_ops::{FileStorage, Snippet};
+use super::metadata::SnippetMeta;
+
+/// Every tag in use with the number of snippets carrying it, by name.
+pub fn count_tags(snippets: &[Snippet]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for snippet in snippets {
+        for tag in &snippet.meta.tags {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Lowercases a typed tag and drops a leading `#`; `None` when nothing is
+/// left or it contains whitespace, which `tag:` searches can't match.
+pub fn normalize_tag(input: &str) -> Option<String> {
+    let tag = input.trim().trim_start_matches('#').to_lowercase();
+    if tag.is_empty() || tag.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(tag)
+}
+
+/// Replaces any of `from` in `tags` with `to`, or removes them when `to` is
+/// `None`, keeping the first position and no duplicates.
+fn replace_tags(tags: &[String], from: &[String], to: Option<&str>) -> Vec<String> {
+    let mut replaced: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = match to {
+            Some(to) if from.contains(tag) => to,
+            None if from.contains(tag) => continue,
+            _ => tag.as_str(),
+        };
+        if !replaced.iter().any(|existing| existing == tag) {
+            replaced.push(tag.to_string());
+        }
+    }
+    replaced
+}
+
+impl FileStorage {
+    /// Renames, merges or deletes tags across the library: every tag in
+    /// `from` becomes `to`, or is removed when `to` is `None`. Either all
+    /// affected snippets are rewritten or, after a failed write, the ones
+    /// already written are put back. Returns each changed snippet's id and
+    /// previous metadata.
+    pub fn rewrite_tags(
+        &self,
+        snippets: &mut [Snippet],
+        from: &[String],
+        to: Option<&str>,
+    ) -> Result<Vec<(String, SnippetMeta)>, StorageError> {
+        let mut written: Vec<(usize, SnippetMeta)> = Vec::new();
+
+        for (index, snippet) in snippets.iter_mut().enumerate() {
+            // The copy may be behind changes queued before it was made.
+            let rewritten = self.reread(snippet).and_then(|()| {
+                if !snippet.meta.tags.iter().any(|tag| from.contains(tag)) {
+                    return Ok(None);
+                }
+                let before = snippet.meta.clone();
+                snippet.meta.tags = replace_tags(&before.tags, from, to);
+                match self.save_meta(snippet) {
+                    Ok(()) => Ok(Some(before)),
+                    Err(e) => {
+                        snippet.meta = before;
+                        Err(e)
+                    }
+                }
+            });
+            match rewritten {
+                Ok(Some(before)) => written.push((index, before)),
+                Ok(None) => {}
+                Err(e) => {
+                    for (index, before) in written.into_iter().rev() {
+                        let snippet = &mut snippets[index];
+                        snippet.meta = before;
+                        if let Err(e) = self.save_meta(snippet) {
+                            log::error!("Failed to roll back tags of {}: {}", snippet.id, e);
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(written.into_iter()
+            .map(|(index, before)| (snippets[index].id.clone(), before))
+            .collect())
+    }
+}
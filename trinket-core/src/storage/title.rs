@@ -0,0 +1,87 @@
+use super::language::Language;
+use crate::bidi;
+use super::metadata::SnippetMeta;
+
+/// Derived titles are cut here; lists may show fewer characters.
+pub const TITLE_CHARS: usize = 200;
+
+/// The title shown in lists: the user's override, then a fetched page title
+/// for link snippets, then one derived from the content.
+pub fn display_title(content: &str, meta: &SnippetMeta) -> String {
+    meta.title.as_deref()
+        .or(meta.page_title.as_deref())
+        .map(|title| truncate(title.trim()))
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| derive_title(content, meta.language))
+}
+
+/// The URL when the whole snippet is a single http(s) link.
+pub fn link_url(content: &str) -> Option<&str> {
+    let content = content.trim();
+    let is_link = (content.starts_with("http://") || content.starts_with("https://"))
+        && !content.contains(char::is_whitespace);
+    is_link.then_some(content)
+}
+
+fn derive_title(content: &str, language: Option<Language>) -> String {
+    if let Some(url) = link_url(content) {
+        return truncate(url.split_once("://").map_or(url, |(_, rest)| rest));
+    }
+    if language == Some(Language::Json) {
+        return json_title(content);
+    }
+
+    let shell = matches!(language, Some(Language::Shell | Language::PowerShell));
+    let mut lines = content.lines().map(str::trim).filter(|line| is_meaningful(line));
+    let Some(first) = lines.next() else {
+        return String::new();
+    };
+
+    // A leading comment usually says what the snippet is for.
+    if let Some(comment) = strip_comment(first) {
+        return truncate(comment);
+    }
+    if shell {
+        // Lead with the command so `docker ...` and `git ...` snippets sort
+        // themselves out at a glance.
+        let mut words = first.split_whitespace();
+        if let Some(command) = words.next() {
+            let name = command.rsplit(['/', '\\']).next().unwrap_or(command);
+            let rest: Vec<&str> = words.collect();
+            return truncate(format!("{} {}", name, rest.join(" ")).trim_end());
+        }
+    }
+    truncate(first)
+}
+
+/// Skips blank lines, shebangs and lines that are only brackets.
+fn is_meaningful(line: &str) -> bool {
+    !line.is_empty()
+        && !line.starts_with("#!")
+        && line.chars().any(|c| c.is_alphanumeric())
+}
+
+fn strip_comment(line: &str) -> Option<&str> {
+    // `# ` rather than `#` so `#include` and `#[derive]` aren't taken for comments.
+    ["///", "//", "--", "# ", "/*", "<!--", "*"]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .map(|rest| rest.trim().trim_end_matches("*/").trim_end_matches("-->").trim())
+        .filter(|rest| rest.chars().any(|c| c.is_alphanumeric()))
+}
+
+/// Names the top-level keys so JSON snippets are told apart by shape.
+fn json_title(content: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(content) {
+        Ok(serde_json::Value::Object(map)) => {
+            let keys: Vec<&str> = map.keys().map(String::as_str).collect();
+            truncate(&format!("JSON {{ {} }}", keys.join(", ")))
+        }
+        Ok(serde_json::Value::Array(items)) => format!("JSON array of {}", items.len()),
+        _ => truncate(content.lines().next().unwrap_or_default().trim()),
+    }
+}
+
+fn truncate(text: &str) -> String {
+    bidi::clip(text, TITLE_CHARS).into_owned()
+}
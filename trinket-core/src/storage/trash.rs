@@ -0,0 +1,178 @@
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::changes::ChangeKind;
+use super::error::StorageError;
+use super::file_ops::{FileStorage, Snippet};
+use super::folders::normalize_folder;
+use super::history::HISTORY_DIR;
+use super::metadata::{is_sidecar, sidecar_path, SnippetMeta};
+use super::shred::{shred_dir, shred_file};
+
+pub const TRASH_DIR: &str = ".trash";
+
+impl FileStorage {
+    pub fn trash_path(&self) -> PathBuf {
+        self.base_path.join(TRASH_DIR)
+    }
+    
+    /// Moves a snippet and its sidecar into the trash directory, stamping the
+    /// deletion time and original folder into the metadata so the trash can be
+    /// purged by age and entries restored where they came from.
+    pub fn move_to_trash(&self, content_path: &Path) -> Result<(), StorageError> {
+        self.ensure_writable()?;
+        let trash = self.trash_path();
+        fs::create_dir_all(&trash)?;
+        
+        let mut meta = SnippetMeta::load(content_path).unwrap_or_default();
+        meta.deleted = Some(Utc::now());
+        meta.trashed_from = Some(self.folder_of(content_path));
+        meta.save(content_path)?;
+        
+        let file_name = content_path.file_name()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "snippet path has no file name"))?;
+        let sidecar = sidecar_path(content_path);
+        fs::rename(content_path, trash.join(file_name))?;
+        if let Some(sidecar_name) = sidecar.file_name() {
+            fs::rename(&sidecar, trash.join(sidecar_name))?;
+        }
+        if let Some(id) = content_path.file_stem().and_then(|s| s.to_str()) {
+            self.record_change(id, ChangeKind::Delete);
+        }
+        Ok(())
+    }
+    
+    /// Moves a trashed snippet back to the folder it was deleted from, or
+    /// the root if that folder name is no longer valid.
+    #[tracing::instrument(skip_all, fields(id = %id))]
+    pub fn restore_from_trash(&self, id: &str) -> Result<Snippet, StorageError> {
+        self.ensure_writable()?;
+        let trash = self.trash_path();
+        let content_path = fs::read_dir(&trash)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .find(|path| {
+                path.file_stem().and_then(|s| s.to_str()) == Some(id) && !is_sidecar(path)
+            })
+            .ok_or(StorageError::NotInTrash)?;
+        
+        let mut meta = SnippetMeta::load(&content_path).unwrap_or_default();
+        let folder = normalize_folder(meta.trashed_from.as_deref().unwrap_or_default()).unwrap_or_default();
+        meta.deleted = None;
+        meta.trashed_from = None;
+        
+        let dir = self.folder_path(&folder);
+        fs::create_dir_all(&dir)?;
+        let file_name = content_path.file_name()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "snippet path has no file name"))?;
+        let restored = dir.join(file_name);
+        let sidecar = sidecar_path(&content_path);
+        fs::rename(&content_path, &restored)?;
+        if sidecar.exists() {
+            fs::remove_file(&sidecar)?;
+        }
+        meta.save(&restored)?;
+        self.record_change(id, ChangeKind::Create);
+        
+        let metadata = fs::metadata(&restored)?;
+        self.load_snippet(restored, metadata)
+    }
+    
+    /// Snippets in the trash, most recently deleted first. Their `folder` is
+    /// the trash itself; `meta.trashed_from` holds where they came from.
+    pub fn trashed_snippets(&self) -> Result<Vec<Snippet>, StorageError> {
+        let mut snippets = Vec::new();
+        for path in self.trash_files()? {
+            // Left out until the encrypted folder they came from is unlocked.
+            let from = SnippetMeta::load(&path).and_then(|meta| meta.trashed_from).unwrap_or_default();
+            if self.is_folder_locked(&from) {
+                continue;
+            }
+            let metadata = fs::metadata(&path)?;
+            snippets.push(self.load_snippet(path, metadata)?);
+        }
+        snippets.sort_by_key(|snippet| std::cmp::Reverse(snippet.meta.deleted));
+        Ok(snippets)
+    }
+    
+    /// Ids of trashed snippets with when they were deleted and the folder
+    /// they came from, read from their sidecars only.
+    #[cfg(feature = "sync")]
+    pub fn trashed_ids(&self) -> Result<Vec<(String, DateTime<Utc>, String)>, StorageError> {
+        let mut ids = Vec::new();
+        for path in self.trash_files()? {
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let meta = SnippetMeta::load(&path).unwrap_or_default();
+            let deleted = meta.deleted.unwrap_or_else(Utc::now);
+            ids.push((id.to_string(), deleted, meta.trashed_from.unwrap_or_default()));
+        }
+        Ok(ids)
+    }
+    
+    /// Deletes a trashed snippet for good, along with its saved versions.
+    pub fn delete_from_trash(&self, id: &str) -> Result<(), StorageError> {
+        let content_path = self.trash_files()?
+            .into_iter()
+            .find(|path| path.file_stem().and_then(|s| s.to_str()) == Some(id))
+            .ok_or(StorageError::NotInTrash)?;
+        self.purge(&content_path)
+    }
+    
+    /// Deletes everything trashed before `cutoff`, or the whole trash when
+    /// there is no cutoff. Returns how many snippets were removed.
+    pub fn purge_trash(&self, cutoff: Option<DateTime<Utc>>) -> Result<usize, StorageError> {
+        let mut purged = 0;
+        for path in self.trash_files()? {
+            // Entries without a deletion time are dated by the trashing itself.
+            let deleted = SnippetMeta::load(&path)
+                .and_then(|meta| meta.deleted)
+                .or_else(|| fs::metadata(&path).and_then(|m| m.modified()).ok().map(DateTime::<Utc>::from));
+            if cutoff.is_none_or(|cutoff| deleted.is_some_and(|deleted| deleted < cutoff)) {
+                self.purge(&path)?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+    
+    /// Removes a snippet's files and saved versions for good. A secret
+    /// snippet's files are overwritten first.
+    pub(super) fn purge(&self, content_path: &Path) -> Result<(), StorageError> {
+        self.ensure_writable()?;
+        let secret = SnippetMeta::load(content_path).is_some_and(|meta| meta.secret);
+        let remove_file = |path: &Path| if secret { shred_file(path) } else { fs::remove_file(path) };
+        let remove_dir = |path: &Path| if secret { shred_dir(path) } else { fs::remove_dir_all(path) };
+
+        let id = content_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let history = self.base_path.join(HISTORY_DIR).join(id);
+        if !id.is_empty() && history.exists() {
+            remove_dir(&history)?;
+        }
+        let sidecar = sidecar_path(content_path);
+        if sidecar.exists() {
+            remove_file(&sidecar)?;
+        }
+        remove_file(content_path)?;
+        if !id.is_empty() {
+            self.record_change(id, ChangeKind::Delete);
+        }
+        Ok(())
+    }
+    
+    pub(crate) fn trash_files(&self) -> Result<Vec<PathBuf>, StorageError> {
+        let trash = self.trash_path();
+        if !trash.exists() {
+            return Ok(Vec::new());
+        }
+        let mut files = Vec::new();
+        for entry in fs::read_dir(trash)? {
+            let path = entry?.path();
+            if path.is_file() && !is_sidecar(&path) {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+}
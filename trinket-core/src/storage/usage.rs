@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+pub const USAGE_FILE: &str = ".usage-log";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UsageRecord {
+    id: String,
+    at: DateTime<Utc>,
+}
+
+/// Folds the stats of a merged-away duplicate into the snippet that was kept.
+#[derive(Debug, Serialize, Deserialize)]
+struct MergeRecord {
+    merged: String,
+    into: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum LogLine {
+    Copy(UsageRecord),
+    Merge(MergeRecord),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SnippetUsage {
+    pub count: u32,
+    pub last_used: DateTime<Utc>,
+}
+
+/// Append-only log of copy events, aggregated into per-snippet counts when
+/// opened. One JSON line per copy keeps writes cheap and crash-safe.
+pub struct UsageLog {
+    path: PathBuf,
+    stats: HashMap<String, SnippetUsage>,
+    /// Copies made on synced devices, added to this device's own.
+    #[cfg(feature = "sync")]
+    synced: HashMap<String, SnippetUsage>,
+}
+
+impl UsageLog {
+    pub fn empty(path: PathBuf) -> Self {
+        Self {
+            path,
+            stats: HashMap::new(),
+            #[cfg(feature = "sync")]
+            synced: HashMap::new(),
+        }
+    }
+    
+    pub fn open(path: PathBuf) -> Result<Self, std::io::Error> {
+        let mut log = Self::empty(path);
+        
+        let file = match File::open(&log.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(log),
+            Err(e) => return Err(e),
+        };
+        for line in BufReader::new(file).lines() {
+            match serde_json::from_str::<LogLine>(&line?) {
+                Ok(LogLine::Copy(record)) => log.apply(record.id, record.at),
+                Ok(LogLine::Merge(record)) => log.apply_merge(&record.merged, record.into),
+                Err(_) => {}
+            }
+        }
+        
+        Ok(log)
+    }
+    
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    pub fn record(&mut self, id: &str) -> Result<(), std::io::Error> {
+        let at = write_copy(&self.path, id)?;
+        self.counted(id.to_string(), at);
+        Ok(())
+    }
+    
+    /// Counts a copy already logged with [`write_copy`].
+    pub fn counted(&mut self, id: String, at: DateTime<Utc>) {
+        self.apply(id, at);
+    }
+    
+    /// Adds the counts of `merged` to `into`, e.g. when duplicates are merged.
+    pub fn merge(&mut self, merged: &str, into: &str) -> Result<(), std::io::Error> {
+        if self.stats.contains_key(merged) {
+            write_merge(&self.path, merged, into)?;
+            self.merged(merged, into.to_string());
+        }
+        Ok(())
+    }
+    
+    /// Applies a merge already logged with [`write_merge`].
+    pub fn merged(&mut self, merged: &str, into: String) {
+        self.apply_merge(merged, into);
+    }
+    
+    #[cfg(not(feature = "sync"))]
+    pub fn get(&self, id: &str) -> Option<SnippetUsage> {
+        self.local(id)
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn get(&self, id: &str) -> Option<SnippetUsage> {
+        match (self.local(id), self.synced.get(id).copied()) {
+            (Some(local), Some(synced)) => Some(SnippetUsage {
+                count: local.count + synced.count,
+                last_used: local.last_used.max(synced.last_used),
+            }),
+            (local, synced) => local.or(synced),
+        }
+    }
+
+    /// Copies made on this device only.
+    pub fn local(&self, id: &str) -> Option<SnippetUsage> {
+        self.stats.get(id).copied()
+    }
+
+    /// Copies made on this device, by snippet id.
+    pub fn local_uses(&self) -> &HashMap<String, SnippetUsage> {
+        &self.stats
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn set_synced(&mut self, synced: HashMap<String, SnippetUsage>) {
+        self.synced = synced;
+    }
+    
+    fn apply(&mut self, id: String, at: DateTime<Utc>) {
+        let usage = self.stats.entry(id).or_insert(SnippetUsage { count: 0, last_used: at });
+        usage.count += 1;
+        usage.last_used = usage.last_used.max(at);
+    }
+    
+    fn apply_merge(&mut self, merged: &str, into: String) {
+        let Some(from) = self.stats.remove(merged) else {
+            return;
+        };
+        let usage = self.stats.entry(into).or_insert(SnippetUsage { count: 0, last_used: from.last_used });
+        usage.count += from.count;
+        usage.last_used = usage.last_used.max(from.last_used);
+    }
+}
+
+/// Logs a copy of `id` to the usage log at `path` and returns when it was
+/// made, for [`UsageLog::counted`]. Split from [`UsageLog::record`] so the
+/// write can happen off the thread that holds the log.
+pub fn write_copy(path: &Path, id: &str) -> Result<DateTime<Utc>, std::io::Error> {
+    let at = Utc::now();
+    append(path, &LogLine::Copy(UsageRecord { id: id.to_string(), at }))?;
+    Ok(at)
+}
+
+/// Logs that `merged` was folded into `into`, for [`UsageLog::merged`].
+pub fn write_merge(path: &Path, merged: &str, into: &str) -> Result<(), std::io::Error> {
+    append(path, &LogLine::Merge(MergeRecord {
+        merged: merged.to_string(),
+        into: into.to_string(),
+    }))
+}
+
+fn append(path: &Path, entry: &LogLine) -> Result<(), std::io::Error> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
@@ -0,0 +1,77 @@
+use std::sync::mpsc;
+
+use super::file_ops::FileStorage;
+
+type Job<R> = Box<dyn FnOnce(&mut FileStorage) -> R + Send>;
+
+/// Owns the library on a background thread, so reading and writing it
+/// never holds up the thread that asked. Jobs run one at a time in the
+/// order they were sent, and their results come back in that order.
+pub struct StorageWorker<R> {
+    jobs: mpsc::Sender<Job<R>>,
+    replies: mpsc::Receiver<R>,
+}
+
+impl<R: Send + 'static> StorageWorker<R> {
+    /// Starts the thread, which first calls `open` for the storage; what
+    /// `open` returns alongside it is the first reply. `wake` is called after
+    /// each reply, so a UI waiting for input can be told one is ready. The
+    /// thread stops once the worker is dropped and the jobs sent are done.
+    pub fn spawn(open: impl FnOnce() -> (FileStorage, R) + Send + 'static, wake: impl Fn() + Send + 'static) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job<R>>();
+        let (reply_sender, replies) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (mut storage, opened) = open();
+            let _ = reply_sender.send(opened);
+            wake();
+            for job in job_receiver {
+                // The receiving end going away doesn't stop the jobs
+                // already sent; their writes still belong on disk.
+                let _ = reply_sender.send(job(&mut storage));
+                wake();
+            }
+        });
+
+        Self { jobs, replies }
+    }
+
+    /// Queues `job` behind the ones already sent.
+    pub fn run(&self, job: impl FnOnce(&mut FileStorage) -> R + Send + 'static) {
+        if self.jobs.send(Box::new(job)).is_err() {
+            log::error!("Storage thread has stopped; dropping a job");
+        }
+    }
+
+    /// The result of the next finished job, if there is one.
+    pub fn try_reply(&self) -> Option<R> {
+        self.replies.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{temp_storage, SnippetMeta};
+    use std::time::Duration;
+
+    #[test]
+    fn jobs_run_in_order() {
+        let (_dir, storage) = temp_storage();
+        let worker = StorageWorker::spawn(move || (storage, "opened".to_string()), || {});
+
+        worker.run(|storage| storage.save_snippet("first", "", SnippetMeta::default()).map(|s| s.id.clone()).unwrap());
+        worker.run(|storage| storage.load_all_snippets().unwrap().len().to_string());
+
+        let mut replies = Vec::new();
+        while replies.len() < 3 {
+            match worker.try_reply() {
+                Some(reply) => replies.push(reply),
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        assert_eq!(replies[0], "opened");
+        assert!(!replies[1].is_empty());
+        assert_eq!(replies[2], "1");
+    }
+}
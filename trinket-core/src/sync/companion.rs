@@ -0,0 +1,130 @@
+//! Lets a phone on the same network push text to this device and search its
+//! snippets, from a companion app or a PWA. The phone pairs by scanning a
+//! QR code holding a `trinket://pair` link with this device's address, id,
+//! key and a one-time code, so it knows which key to expect without
+//! discovery.
+//!
+//! Companions connect to the LAN sync port and use the same handshake and
+//! sealed frames as paired devices, with `companion` set in their hello.
+//! After the `ready` exchange the phone sends requests and gets one reply
+//! each, until it closes the connection. Companions never see the library
+//! as a whole, only search results, and secret snippets come without their
+//! content.
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::mpsc;
+
+use super::lan::{closed, Channel, LanEvent, APP_TIMEOUT, MDNS_GROUP};
+use crate::storage::indexer::SearchQuery;
+use crate::storage::search_provider::SearchProvider;
+use crate::storage::title::display_title;
+use crate::storage::{Snippet, SECRET_MASK};
+
+/// Search results sent in one reply, whatever the phone asks for.
+const MAX_RESULTS: usize = 50;
+
+/// What the phone scans to pair with this device.
+pub struct CompanionInvite {
+    pub address: SocketAddr,
+    pub code: String,
+    pub uri: String,
+}
+
+impl CompanionInvite {
+    pub(super) fn new(id: &str, public_key: &str, port: u16, code: String) -> Result<Self, std::io::Error> {
+        let address = SocketAddr::new(local_address()?, port);
+        let uri = format!("trinket://pair?v=1&addr={}&id={}&key={}&code={}", address, id, public_key, code);
+        Ok(Self { address, code, uri })
+    }
+}
+
+/// This device's address on the network it is announced on.
+fn local_address() -> Result<IpAddr, std::io::Error> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    // Connecting a UDP socket sends nothing; it only picks the interface.
+    socket.connect(MDNS_GROUP)?;
+    let address = socket.local_addr()?.ip();
+    if address.is_unspecified() || address.is_loopback() {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotConnected, "this device isn't on a network"));
+    }
+    Ok(address)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "snake_case")]
+enum Request {
+    /// Save the text as a new snippet.
+    Push(String),
+    Search { query: String, limit: usize },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "snake_case")]
+enum Reply {
+    Saved,
+    Results(Vec<CompanionHit>),
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompanionHit {
+    pub id: String,
+    pub title: String,
+    pub folder: String,
+    /// `None` for secret snippets.
+    pub content: Option<String>,
+}
+
+/// The first `limit` snippets matching `query`, best first.
+pub fn search(search: &dyn SearchProvider, snippets: &[Snippet], query: &str, limit: usize) -> Vec<CompanionHit> {
+    search.query(&SearchQuery::parse(query), snippets)
+        .into_iter()
+        .take(limit)
+        .map(|i| {
+            let snippet = &snippets[i];
+            let secret = snippet.meta.secret;
+            CompanionHit {
+                id: snippet.id.clone(),
+                title: if secret { SECRET_MASK.to_string() } else { display_title(&snippet.content, &snippet.meta) },
+                folder: snippet.folder.clone(),
+                content: (!secret).then(|| snippet.content.clone()),
+            }
+        })
+        .collect()
+}
+
+/// Answers the requests of the companion called `name` until it hangs up.
+pub(super) fn serve(channel: &mut Channel, name: &str, events: &mpsc::Sender<LanEvent>) -> Result<(), std::io::Error> {
+    loop {
+        let request = match channel.receive() {
+            Ok(request) => request,
+            // Phones connect for a request or two and then go quiet.
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let reply = match request {
+            Request::Push(text) => {
+                let (done, saved) = mpsc::channel();
+                events.send(LanEvent::Pushed { from: name.to_string(), text, done }).map_err(|_| closed())?;
+                match saved.recv_timeout(APP_TIMEOUT) {
+                    Ok(Ok(())) => Reply::Saved,
+                    Ok(Err(message)) => Reply::Failed(message),
+                    Err(_) => Reply::Failed("The text could not be saved".to_string()),
+                }
+            }
+            Request::Search { query, limit } => {
+                let (reply, hits) = mpsc::channel();
+                let limit = limit.min(MAX_RESULTS);
+                events.send(LanEvent::SearchNeeded { query, limit, reply }).map_err(|_| closed())?;
+                match hits.recv_timeout(APP_TIMEOUT) {
+                    Ok(hits) => Reply::Results(hits),
+                    Err(_) => Reply::Failed("The snippets could not be searched".to_string()),
+                }
+            }
+        };
+        channel.send(&reply)?;
+    }
+}
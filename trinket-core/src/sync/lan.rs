@@ -0,0 +1,979 @@
+//! Syncs with paired devices on the same network, with no server between
+//! them. Devices announce themselves over mDNS as a DNS-SD `_trinket._tcp`
+//! service and sync over TCP.
+//!
+//! Each device has a long-term X25519 key. A session opens with a hello from
+//! both sides carrying that key and a fresh one, and the session keys come
+//! from all three key agreements between them, so only a device holding the
+//! key we paired with can derive them. While pairing there is no key to
+//! check yet; the code shown on one device and typed on the other goes
+//! through a PAKE (see `pake`) whose key is mixed in instead, so the
+//! session can't be used to test guesses at the code offline. The device
+//! showing the code keeps it until another proves it knows it, and drops
+//! it after a few wrong guesses. Everything after the hello is sealed with
+//! ChaCha20-Poly1305. Companion phones connect the same way (see
+//! [`super::companion`]); they know this device's key from the QR code,
+//! so their code is mixed in as it is.
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chrono::{DateTime, Utc};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use rand_core::{OsRng, RngCore};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use x25519_dalek::{PublicKey, SharedSecret, StaticSecret};
+use zeroize::Zeroizing;
+
+use super::companion::{serve, CompanionHit, CompanionInvite};
+use super::pake::Pake;
+use super::{base_saved, load_base, outcome, plan, save_base, to_hex, Changes, Conflict, Hashes, Manifest, Outcome, Snapshot, SyncedSnippet};
+use crate::sync_settings::{FolderSync, SyncPeer};
+use crate::storage::changes::{rev_at, ChangeLog, CHANGES_FILE};
+
+const PROTOCOL_VERSION: u32 = 1;
+/// The DNS-SD service devices announce, with this device's id as the
+/// instance name.
+const SERVICE_TYPE: &str = "_trinket._tcp.local.";
+/// Where mDNS queries go, to pick the interface devices are found on.
+pub(super) const MDNS_GROUP: (Ipv4Addr, u16) = (Ipv4Addr::new(224, 0, 0, 251), 5353);
+const SYNC_INTERVAL: Duration = Duration::from_secs(120);
+/// Peers are synced this often even when nothing changed here, to pick up
+/// copy counts and edits made to the files outside the app.
+const FULL_SYNC_INTERVAL: Duration = Duration::from_secs(30 * 60);
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a session waits for the app to read or save the library.
+pub(super) const APP_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_HELLO: usize = 4 * 1024;
+const MAX_FRAME: usize = 64 * 1024 * 1024;
+const KEY_INFO: &[u8] = b"trinket lan sync v1";
+/// Crockford's base32: no I, L, O or U to misread.
+const CODE_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+/// 50 bits, enough that the code can't be guessed while pairing is open.
+const CODE_LENGTH: usize = 10;
+/// Wrong guesses at a pairing code before it is dropped.
+const MAX_PAIRING_FAILURES: u32 = 3;
+
+/// This device's long-term key, which paired devices know it by.
+pub struct DeviceKey {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl DeviceKey {
+    pub fn default_path() -> PathBuf {
+        crate::data_dir().join("device-key")
+    }
+
+    /// Reads the key from `path`, creating it on first use.
+    pub fn load_or_create(path: &Path) -> Result<Self, std::io::Error> {
+        let secret = match fs::read(path) {
+            Ok(bytes) => {
+                let bytes = Zeroizing::new(bytes);
+                let mut key = Zeroizing::new([0u8; 32]);
+                if bytes.len() != key.len() {
+                    return Err(invalid(format!("{} is not a device key", path.display())));
+                }
+                key.copy_from_slice(&bytes);
+                StaticSecret::from(*key)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let secret = StaticSecret::random_from_rng(OsRng);
+                write_private(path, secret.as_bytes())?;
+                secret
+            }
+            Err(e) => return Err(e),
+        };
+        let public = PublicKey::from(&secret);
+        Ok(Self { secret, public })
+    }
+
+    pub fn id(&self) -> String {
+        device_id(&self.public)
+    }
+}
+
+/// Short id derived from a device's public key, so it can't be claimed by
+/// another device.
+fn device_id(public: &PublicKey) -> String {
+    to_hex(&Sha256::digest(public.as_bytes())[..8])
+}
+
+fn parse_key(text: &str) -> Result<PublicKey, std::io::Error> {
+    let bytes: Option<Vec<u8>> = (0..text.len())
+        .step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect();
+    let bytes: [u8; 32] = bytes
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid("malformed device key"))?;
+    Ok(PublicKey::from(bytes))
+}
+
+/// The name other devices see, from the host name.
+fn device_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "Trinket".to_string())
+}
+
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), std::io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+fn new_code() -> String {
+    (0..CODE_LENGTH)
+        .map(|_| CODE_ALPHABET[OsRng.next_u32() as usize % CODE_ALPHABET.len()] as char)
+        .collect()
+}
+
+/// Upper case without separators, with the letters Crockford's alphabet
+/// leaves out read as the digits they look like.
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscoveredPeer {
+    pub id: String,
+    pub name: String,
+    pub address: SocketAddr,
+}
+
+/// What a session needs from the app, which owns the library.
+pub enum LanEvent {
+    /// Reply with the library as it is now; dropping the sender fails the
+    /// session.
+    SnapshotNeeded(mpsc::Sender<Snapshot>),
+    /// Save these changes from `peer` and reply whether that worked.
+    Received { peer: String, changes: Changes, done: mpsc::Sender<bool> },
+    /// Snippets edited on both sides, for the user to settle.
+    Conflicts(Vec<Conflict>),
+    /// Pairing succeeded; the peer should be saved as trusted.
+    Paired(SyncPeer),
+    /// A phone paired as a companion and should be saved as one.
+    CompanionPaired(SyncPeer),
+    /// A companion sent `text` to save as a snippet; reply with the
+    /// outcome.
+    Pushed { from: String, text: String, done: mpsc::Sender<Result<(), String>> },
+    /// A companion searches for `query`; reply with at most `limit` hits.
+    SearchNeeded { query: String, limit: usize, reply: mpsc::Sender<Vec<CompanionHit>> },
+    Failed(String),
+}
+
+/// How syncing with paired devices is going, for the status bar and tray.
+#[derive(Debug, Clone, Default)]
+pub struct SyncStatus {
+    pub syncing: bool,
+    /// When the last sync with any device finished.
+    pub last_synced: Option<DateTime<Utc>>,
+    /// The last failure, until a sync succeeds or it is dismissed.
+    pub error: Option<SyncError>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncError {
+    pub peer: String,
+    pub message: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Hello {
+    version: u32,
+    id: String,
+    name: String,
+    public_key: String,
+    ephemeral: String,
+    pairing: bool,
+    /// Sent by phones, which only push text and search.
+    #[serde(default)]
+    companion: bool,
+    /// This side's PAKE share while pairing devices, hex-encoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pake: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "snake_case")]
+enum Message {
+    /// Proves both sides derived the same keys.
+    Ready,
+    Manifest(Manifest),
+    Request(Vec<String>),
+    Snippets(Vec<SyncedSnippet>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Initiator,
+    Responder,
+}
+
+#[derive(Default)]
+struct State {
+    enabled: bool,
+    trusted: Vec<SyncPeer>,
+    companions: Vec<SyncPeer>,
+    /// Set while this device accepts a new pairing; used at most once.
+    pairing_code: Option<PairingCode>,
+    /// The same for a companion, from the QR code it scanned.
+    companion_code: Option<PairingCode>,
+    folder_sync: BTreeMap<String, FolderSync>,
+    /// When each peer last finished a sync with this device, by id.
+    last_synced: BTreeMap<String, DateTime<Utc>>,
+    /// The change log revision each peer's last sync started at.
+    synced_rev: BTreeMap<String, u64>,
+    /// Sessions with peers running now.
+    sessions: usize,
+    error: Option<SyncError>,
+    discovered: BTreeMap<String, DiscoveredPeer>,
+}
+
+/// A code one other device may pair with.
+struct PairingCode {
+    code: String,
+    failures: u32,
+}
+
+impl PairingCode {
+    fn new(code: String) -> Self {
+        Self { code, failures: 0 }
+    }
+}
+
+struct Inner {
+    key: DeviceKey,
+    id: String,
+    name: String,
+    port: u16,
+    storage_path: PathBuf,
+    changes: ChangeLog,
+    state: Mutex<State>,
+    /// Held for the length of a session; one at a time keeps the agreed
+    /// state with each peer consistent.
+    session: Mutex<()>,
+}
+
+impl Inner {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether anything was saved or deleted here since the last sync with
+    /// `peer_id`, or it hasn't been synced with since starting.
+    fn changed_since_sync(&self, peer_id: &str) -> bool {
+        let Some(rev) = self.state().synced_rev.get(peer_id).copied() else {
+            return true;
+        };
+        self.changes.since(rev).map_or(true, |changes| !changes.is_empty())
+    }
+
+    fn fail(&self, peer: &str, error: &std::io::Error) {
+        self.state().error = Some(SyncError { peer: peer.to_string(), message: error.to_string(), at: Utc::now() });
+    }
+
+    /// Whether `hello` comes from a paired device or companion, with the
+    /// key it was paired with.
+    fn is_trusted(&self, hello: &Hello) -> bool {
+        let state = self.state();
+        let known = if hello.companion { &state.companions } else { &state.trusted };
+        known.iter().any(|peer| peer.id == hello.id && peer.public_key == hello.public_key)
+    }
+
+    fn hello(&self, ephemeral: &StaticSecret, pairing: bool, pake: Option<&Pake>) -> Hello {
+        Hello {
+            version: PROTOCOL_VERSION,
+            id: self.id.clone(),
+            name: self.name.clone(),
+            public_key: to_hex(self.key.public.as_bytes()),
+            ephemeral: to_hex(PublicKey::from(ephemeral).as_bytes()),
+            pairing,
+            companion: false,
+            pake: pake.map(|pake| to_hex(&pake.share)),
+        }
+    }
+
+    fn pairing_code(&self, companion: bool) -> Option<String> {
+        let state = self.state();
+        let code = if companion { &state.companion_code } else { &state.pairing_code };
+        code.as_ref().map(|code| code.code.clone())
+    }
+
+    /// Uses up `code` once a device proved it knows it. False when it was
+    /// used or withdrawn in the meantime.
+    fn use_pairing_code(&self, companion: bool, code: &str) -> bool {
+        let mut state = self.state();
+        let slot = if companion { &mut state.companion_code } else { &mut state.pairing_code };
+        slot.take_if(|pairing| pairing.code == code).is_some()
+    }
+
+    /// Counts a wrong guess at `code`. Returns whether that was one too
+    /// many and the code is dropped.
+    fn pairing_failed(&self, companion: bool, code: &str) -> bool {
+        let mut state = self.state();
+        let slot = if companion { &mut state.companion_code } else { &mut state.pairing_code };
+        let failures = match slot {
+            Some(pairing) if pairing.code == code => {
+                pairing.failures += 1;
+                pairing.failures
+            }
+            _ => return false,
+        };
+        if failures >= MAX_PAIRING_FAILURES {
+            *slot = None;
+            return true;
+        }
+        false
+    }
+}
+
+/// Runs discovery and sync in the background for as long as it is kept.
+pub struct LanSync {
+    inner: Arc<Inner>,
+    events: mpsc::Receiver<LanEvent>,
+    sender: mpsc::Sender<LanEvent>,
+    wake: mpsc::Sender<()>,
+    mdns: ServiceDaemon,
+    service: ServiceInfo,
+}
+
+impl LanSync {
+    /// Starts listening on `port` and announcing this device. Snippets are
+    /// synced with the `trusted` peers found on the network, and
+    /// `companions` may connect to push text and search.
+    pub fn start(key: DeviceKey, port: u16, trusted: Vec<SyncPeer>, companions: Vec<SyncPeer>, storage_path: PathBuf) -> Result<Self, std::io::Error> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        let mdns = ServiceDaemon::new().map_err(std::io::Error::other)?;
+
+        let last_synced = trusted.iter()
+            .filter_map(|peer| Some((peer.id.clone(), base_saved(&storage_path, &peer.id)?)))
+            .collect();
+        let inner = Arc::new(Inner {
+            id: key.id(),
+            key,
+            name: device_name(),
+            port: listener.local_addr()?.port(),
+            changes: ChangeLog::open(storage_path.join(CHANGES_FILE)),
+            storage_path,
+            state: Mutex::new(State { enabled: true, trusted, companions, last_synced, ..State::default() }),
+            session: Mutex::new(()),
+        });
+        let (sender, events) = mpsc::channel();
+        let (wake, wakeups) = mpsc::channel();
+
+        let (listen_inner, listen_sender) = (inner.clone(), sender.clone());
+        std::thread::spawn(move || listen(listen_inner, listener, listen_sender));
+        let found = mdns.browse(SERVICE_TYPE).map_err(std::io::Error::other)?;
+        let discover_inner = inner.clone();
+        std::thread::spawn(move || discover(discover_inner, found));
+        let (schedule_inner, schedule_sender) = (inner.clone(), sender.clone());
+        std::thread::spawn(move || schedule(schedule_inner, wakeups, schedule_sender));
+
+        let version = PROTOCOL_VERSION.to_string();
+        let properties = [("id", inner.id.as_str()), ("name", inner.name.as_str()), ("v", version.as_str())];
+        let host = format!("{}.local.", inner.id);
+        let service = ServiceInfo::new(SERVICE_TYPE, &inner.id, &host, "", inner.port, &properties[..])
+            .map_err(std::io::Error::other)?
+            .enable_addr_auto();
+        mdns.register(service.clone()).map_err(std::io::Error::other)?;
+
+        Ok(Self { inner, events, sender, wake, mdns, service })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// Turned off, the device stops announcing itself and refuses sessions.
+    pub fn set_enabled(&self, enabled: bool) {
+        let was_enabled = std::mem::replace(&mut self.inner.state().enabled, enabled);
+        let announced = match (was_enabled, enabled) {
+            (false, true) => self.mdns.register(self.service.clone()),
+            (true, false) => self.mdns.unregister(self.service.get_fullname()).map(drop),
+            _ => Ok(()),
+        };
+        if let Err(e) = announced {
+            log::warn!("Could not update this device's announcement: {}", e);
+        }
+    }
+
+    pub fn set_trusted(&self, trusted: Vec<SyncPeer>) {
+        self.inner.state().trusted = trusted;
+    }
+
+    pub fn set_companions(&self, companions: Vec<SyncPeer>) {
+        self.inner.state().companions = companions;
+    }
+
+    /// Sets which folders sync with which peers, as in the app config's
+    /// `folder_sync`.
+    pub fn set_folder_sync(&self, folder_sync: BTreeMap<String, FolderSync>) {
+        self.inner.state().folder_sync = folder_sync;
+    }
+
+    pub fn last_synced(&self, peer_id: &str) -> Option<DateTime<Utc>> {
+        self.inner.state().last_synced.get(peer_id).copied()
+    }
+
+    pub fn status(&self) -> SyncStatus {
+        let state = self.inner.state();
+        SyncStatus {
+            syncing: state.sessions > 0,
+            last_synced: state.last_synced.values().max().copied(),
+            error: state.error.clone(),
+        }
+    }
+
+    pub fn dismiss_error(&self) {
+        self.inner.state().error = None;
+    }
+
+    /// Lets one other device pair with this one, using the returned code.
+    pub fn start_pairing(&self) -> String {
+        let code = new_code();
+        self.inner.state().pairing_code = Some(PairingCode::new(code.clone()));
+        code
+    }
+
+    /// Lets one phone pair as a companion by scanning the returned invite.
+    pub fn start_companion_pairing(&self) -> Result<CompanionInvite, std::io::Error> {
+        let public_key = to_hex(self.inner.key.public.as_bytes());
+        let invite = CompanionInvite::new(&self.inner.id, &public_key, self.inner.port, new_code())?;
+        self.inner.state().companion_code = Some(PairingCode::new(invite.code.clone()));
+        Ok(invite)
+    }
+
+    pub fn stop_companion_pairing(&self) {
+        self.inner.state().companion_code = None;
+    }
+
+    pub fn is_companion_pairing(&self) -> bool {
+        self.inner.state().companion_code.is_some()
+    }
+
+    pub fn stop_pairing(&self) {
+        self.inner.state().pairing_code = None;
+    }
+
+    pub fn is_pairing(&self) -> bool {
+        self.inner.state().pairing_code.is_some()
+    }
+
+    /// Devices announcing themselves on the network, by name.
+    pub fn discovered(&self) -> Vec<DiscoveredPeer> {
+        let mut peers: Vec<_> = self.inner.state().discovered.values().cloned().collect();
+        peers.sort_by(|a, b| a.name.cmp(&b.name));
+        peers
+    }
+
+    /// Pairs with a discovered device showing `code`, then syncs with it.
+    /// The outcome arrives as an event.
+    pub fn pair_with(&self, peer_id: &str, code: &str) {
+        let Some(peer) = self.inner.state().discovered.get(peer_id).cloned() else {
+            let _ = self.sender.send(LanEvent::Failed("The device is no longer on the network".to_string()));
+            return;
+        };
+        let (inner, sender, code) = (self.inner.clone(), self.sender.clone(), normalize_code(code));
+        std::thread::spawn(move || {
+            if let Err(e) = initiate(&inner, &peer, Some(&code), &sender) {
+                log::warn!("Pairing with {} failed: {}", peer.name, e);
+                let _ = sender.send(LanEvent::Failed(format!("Could not pair with {}: {}", peer.name, e)));
+            }
+        });
+    }
+
+    /// Records `hashes` as the version of snippet `id` agreed with
+    /// `peer_id`, once the user settled a conflict with it. Waits for any
+    /// running session in the background.
+    pub fn settle(&self, peer_id: &str, id: &str, hashes: Hashes) {
+        let (inner, peer_id, id) = (self.inner.clone(), peer_id.to_string(), id.to_string());
+        std::thread::spawn(move || {
+            let _session = inner.session.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut base = load_base(&inner.storage_path, &peer_id);
+            base.insert(id, hashes);
+            if let Err(e) = save_base(&inner.storage_path, &peer_id, &base) {
+                log::error!("Failed to save the sync state with {}: {}", peer_id, e);
+            }
+        });
+    }
+
+    /// Syncs with every paired device on the network right away.
+    pub fn sync_now(&self) {
+        let _ = self.wake.send(());
+    }
+
+    pub fn try_event(&self) -> Option<LanEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+impl Drop for LanSync {
+    /// Says goodbye on the network, so other devices forget this one now
+    /// rather than when its records expire.
+    fn drop(&mut self) {
+        let _ = self.mdns.shutdown();
+    }
+}
+
+fn listen(inner: Arc<Inner>, listener: TcpListener, events: mpsc::Sender<LanEvent>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if !inner.state().enabled {
+            continue;
+        }
+        let (inner, events) = (inner.clone(), events.clone());
+        std::thread::spawn(move || {
+            let address = stream.peer_addr().map(|address| address.to_string()).unwrap_or_default();
+            if let Err(e) = respond(&inner, stream, &events) {
+                log::warn!("LAN sync with {} failed: {}", address, e);
+            }
+        });
+    }
+}
+
+fn discover(inner: Arc<Inner>, found: mdns_sd::Receiver<ServiceEvent>) {
+    // Ends when the daemon shuts down with the LanSync.
+    while let Ok(event) = found.recv() {
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                let Some(id) = info.get_property_val_str("id") else {
+                    continue;
+                };
+                let version = info.get_property_val_str("v").and_then(|version| version.parse().ok());
+                if id == inner.id || version != Some(PROTOCOL_VERSION) {
+                    continue;
+                }
+                // Sessions are only accepted over IPv4.
+                let Some(ip) = info.get_addresses_v4().into_iter().next().copied() else {
+                    continue;
+                };
+                let peer = DiscoveredPeer {
+                    id: id.to_string(),
+                    name: info.get_property_val_str("name").unwrap_or(id).to_string(),
+                    address: SocketAddr::new(ip.into(), info.get_port()),
+                };
+                inner.state().discovered.insert(peer.id.clone(), peer);
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                if let Some(id) = fullname.strip_suffix(SERVICE_TYPE).and_then(|name| name.strip_suffix('.')) {
+                    inner.state().discovered.remove(id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Starts syncs, periodic or asked for, until the [`LanSync`] is dropped.
+fn schedule(inner: Arc<Inner>, wakeups: mpsc::Receiver<()>, events: mpsc::Sender<LanEvent>) {
+    let mut last_sync = Instant::now();
+    let mut last_full_sync = Instant::now();
+    loop {
+        let asked = match wakeups.recv_timeout(SYNC_INTERVAL.saturating_sub(last_sync.elapsed())) {
+            Ok(()) => true,
+            Err(mpsc::RecvTimeoutError::Timeout) => false,
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        };
+        if !inner.state().enabled || !(asked || last_sync.elapsed() >= SYNC_INTERVAL) {
+            continue;
+        }
+        last_sync = Instant::now();
+        let full = last_full_sync.elapsed() >= FULL_SYNC_INTERVAL;
+        if full {
+            last_full_sync = Instant::now();
+        }
+
+        let peers: Vec<DiscoveredPeer> = {
+            let state = inner.state();
+            state.discovered.values()
+                .filter(|peer| state.trusted.iter().any(|trusted| trusted.id == peer.id))
+                .cloned()
+                .collect()
+        };
+        for peer in peers {
+            // Periodic syncs are started by whichever side changed something,
+            // and the full ones by one side of each pair only.
+            let due = asked || inner.changed_since_sync(&peer.id) || (full && peer.id >= inner.id);
+            if !due {
+                continue;
+            }
+            if let Err(e) = initiate(&inner, &peer, None, &events) {
+                log::warn!("LAN sync with {} failed: {}", peer.name, e);
+                inner.fail(&peer.name, &e);
+                if asked {
+                    let _ = events.send(LanEvent::Failed(format!("Could not sync with {}: {}", peer.name, e)));
+                }
+            }
+        }
+    }
+}
+
+/// Connects to `peer` and syncs, pairing first when there is a `code`.
+fn initiate(inner: &Inner, peer: &DiscoveredPeer, code: Option<&str>, events: &mpsc::Sender<LanEvent>) -> Result<(), std::io::Error> {
+    let _session = inner.session.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut stream = TcpStream::connect_timeout(&peer.address, IO_TIMEOUT)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let pake = code.map(|code| Pake::new(code, &[&inner.id, &peer.id]));
+    let mine = serde_json::to_vec(&inner.hello(&ephemeral, code.is_some(), pake.as_ref()))?;
+    write_frame(&mut stream, &mine)?;
+    let their_bytes = read_frame(&mut stream, MAX_HELLO).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => refused("the other device turned the connection down"),
+        _ => e,
+    })?;
+    let theirs = check_hello(&their_bytes)?;
+    if theirs.id != peer.id || theirs.companion {
+        return Err(refused("a different device answered"));
+    }
+    if code.is_none() && !inner.is_trusted(&theirs) {
+        return Err(refused("the device's key has changed; pair with it again"));
+    }
+
+    let pairing_key = match &pake {
+        Some(pake) => Some(pake.finish(&their_share(&theirs)?, true)?),
+        None => None,
+    };
+    let pairing_key = pairing_key.as_ref().map_or(&[][..], |key| &key[..]);
+    let mut channel = Channel::new(inner, stream, Role::Initiator, &ephemeral, &theirs, &mine, &their_bytes, pairing_key)?;
+    channel.send(&Message::Ready)?;
+    match channel.receive() {
+        Ok(Message::Ready) => {}
+        Ok(_) => return Err(invalid("unexpected message")),
+        Err(_) if code.is_some() => return Err(refused("the code didn't match or is no longer valid")),
+        Err(e) => return Err(e),
+    }
+    if code.is_some() {
+        let _ = events.send(LanEvent::Paired(peer_of(&theirs)));
+    }
+    run_session(inner, &mut channel, &theirs, events)
+}
+
+/// Answers a connection from another device.
+fn respond(inner: &Inner, mut stream: TcpStream, events: &mpsc::Sender<LanEvent>) -> Result<(), std::io::Error> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let their_bytes = read_frame(&mut stream, MAX_HELLO)?;
+    let theirs = check_hello(&their_bytes)?;
+    // Companions don't touch the sync state, so they needn't wait for it.
+    let _session = if theirs.companion {
+        None
+    } else {
+        Some(inner.session.try_lock().map_err(|_| refused("another sync is in progress"))?)
+    };
+    // The code stays usable until the other side proves it knows it.
+    let code = if theirs.pairing {
+        inner.pairing_code(theirs.companion).ok_or_else(|| refused("this device isn't pairing"))?
+    } else {
+        if !inner.is_trusted(&theirs) {
+            return Err(refused("the device isn't paired with this one"));
+        }
+        String::new()
+    };
+    let pake = (theirs.pairing && !theirs.companion).then(|| Pake::new(&code, &[&theirs.id, &inner.id]));
+    let pairing_key = match &pake {
+        Some(pake) => Zeroizing::new(pake.finish(&their_share(&theirs)?, false)?.to_vec()),
+        None => Zeroizing::new(code.as_bytes().to_vec()),
+    };
+
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let mine = serde_json::to_vec(&inner.hello(&ephemeral, theirs.pairing, pake.as_ref()))?;
+    write_frame(&mut stream, &mine)?;
+    let mut channel = Channel::new(inner, stream, Role::Responder, &ephemeral, &theirs, &their_bytes, &mine, &pairing_key)?;
+    match channel.receive() {
+        Ok(Message::Ready) => {}
+        Ok(_) => return Err(invalid("unexpected message")),
+        Err(e) => {
+            if theirs.pairing {
+                let message = if inner.pairing_failed(theirs.companion, &code) {
+                    format!("{} could not pair: the code didn't match, and pairing stopped after too many wrong codes", theirs.name)
+                } else {
+                    format!("{} could not pair: the code didn't match", theirs.name)
+                };
+                let _ = events.send(LanEvent::Failed(message));
+            }
+            return Err(e);
+        }
+    }
+    if theirs.pairing && !inner.use_pairing_code(theirs.companion, &code) {
+        return Err(refused("the code is no longer valid"));
+    }
+    channel.send(&Message::Ready)?;
+    if theirs.pairing {
+        let peer = peer_of(&theirs);
+        let _ = events.send(if theirs.companion { LanEvent::CompanionPaired(peer) } else { LanEvent::Paired(peer) });
+    }
+    if theirs.companion {
+        return serve(&mut channel, &theirs.name, events);
+    }
+    run_session(inner, &mut channel, &theirs, events)
+}
+
+fn check_hello(bytes: &[u8]) -> Result<Hello, std::io::Error> {
+    let hello: Hello = serde_json::from_slice(bytes)?;
+    if hello.version != PROTOCOL_VERSION {
+        return Err(refused("the other device runs an incompatible version of Trinket"));
+    }
+    if device_id(&parse_key(&hello.public_key)?) != hello.id {
+        return Err(invalid("the device id doesn't match its key"));
+    }
+    Ok(hello)
+}
+
+/// The PAKE share in a pairing hello.
+fn their_share(hello: &Hello) -> Result<Vec<u8>, std::io::Error> {
+    let share = hello.pake.as_deref().ok_or_else(|| refused("the other device runs an older version of Trinket; update it to pair"))?;
+    (0..share.len())
+        .step_by(2)
+        .map(|i| share.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| invalid("malformed pairing share"))
+}
+
+fn peer_of(hello: &Hello) -> SyncPeer {
+    SyncPeer {
+        id: hello.id.clone(),
+        name: hello.name.clone(),
+        public_key: hello.public_key.clone(),
+    }
+}
+
+/// Syncs with `peer`, keeping the status up to date.
+#[tracing::instrument(name = "lan_sync", skip_all, fields(peer = %peer.name))]
+fn run_session(inner: &Inner, channel: &mut Channel, peer: &Hello, events: &mpsc::Sender<LanEvent>) -> Result<(), std::io::Error> {
+    inner.state().sessions += 1;
+    let started = rev_at(Utc::now());
+    let result = sync_session(inner, channel, peer, events);
+    let mut state = inner.state();
+    state.sessions -= 1;
+    match &result {
+        Ok(()) => {
+            state.last_synced.insert(peer.id.clone(), Utc::now());
+            state.synced_rev.insert(peer.id.clone(), started);
+            state.error = None;
+        }
+        Err(e) => {
+            drop(state);
+            inner.fail(&peer.name, e);
+        }
+    }
+    result
+}
+
+/// Swaps manifests, asks for what the plan says to take, and hands what
+/// arrives to the app. The agreed state is only saved once both sides have
+/// stored their changes.
+fn sync_session(inner: &Inner, channel: &mut Channel, peer: &Hello, events: &mpsc::Sender<LanEvent>) -> Result<(), std::io::Error> {
+    let (reply, snapshot) = mpsc::channel();
+    events.send(LanEvent::SnapshotNeeded(reply)).map_err(|_| closed())?;
+    let mut snapshot = snapshot.recv_timeout(APP_TIMEOUT).map_err(|_| other("the library could not be read"))?;
+    let folder_sync = inner.state().folder_sync.clone();
+    let syncs = |folder: &str| FolderSync::of(&folder_sync, folder).syncs_with(&peer.id);
+    snapshot.exclude(syncs);
+
+    let manifest = snapshot.manifest();
+    let Message::Manifest(theirs) = channel.exchange(Message::Manifest(manifest.clone()))? else {
+        return Err(invalid("expected a manifest"));
+    };
+    let base = load_base(&inner.storage_path, &peer.id);
+    let plan = plan(&manifest, &theirs, &base);
+
+    let wanted: Vec<String> = plan.fetch.iter().chain(&plan.merge).chain(&plan.conflicts).cloned().collect();
+    let Message::Request(requested) = channel.exchange(Message::Request(wanted))? else {
+        return Err(invalid("expected a request"));
+    };
+    let Message::Snippets(received) = channel.exchange(Message::Snippets(snapshot.take(&requested)))? else {
+        return Err(invalid("expected snippets"));
+    };
+
+    let Outcome { agreed, changes, conflicts } = outcome(plan, &base, &snapshot, received, syncs);
+    if !changes.is_empty() {
+        let (done, saved) = mpsc::channel();
+        events.send(LanEvent::Received { peer: peer.name.clone(), changes, done }).map_err(|_| closed())?;
+        if !saved.recv_timeout(APP_TIMEOUT).unwrap_or(false) {
+            return Err(other("the changes could not be saved"));
+        }
+    }
+    let Message::Ready = channel.exchange(Message::Ready)? else {
+        return Err(invalid("expected the session to end"));
+    };
+    save_base(&inner.storage_path, &peer.id, &agreed)?;
+
+    if !conflicts.is_empty() {
+        let conflicts = conflicts.into_iter()
+            .map(|theirs| Conflict { peer_id: peer.id.clone(), peer_name: peer.name.clone(), theirs })
+            .collect();
+        events.send(LanEvent::Conflicts(conflicts)).map_err(|_| closed())?;
+    }
+    Ok(())
+}
+
+/// An authenticated, encrypted connection to another device.
+pub(super) struct Channel {
+    stream: TcpStream,
+    role: Role,
+    sealer: ChaCha20Poly1305,
+    opener: ChaCha20Poly1305,
+    sent: u64,
+    received: u64,
+}
+
+impl Channel {
+    /// Derives the session keys. `initiator_hello` and `responder_hello`
+    /// are the hellos as sent, so tampering with either breaks the keys.
+    /// `pairing` is the PAKE key while pairing devices, a companion's code
+    /// while pairing one, and empty otherwise.
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        inner: &Inner,
+        stream: TcpStream,
+        role: Role,
+        ephemeral: &StaticSecret,
+        theirs: &Hello,
+        initiator_hello: &[u8],
+        responder_hello: &[u8],
+        pairing: &[u8],
+    ) -> Result<Self, std::io::Error> {
+        let their_static = parse_key(&theirs.public_key)?;
+        let their_ephemeral = parse_key(&theirs.ephemeral)?;
+        let ee = ephemeral.diffie_hellman(&their_ephemeral);
+        let static_ephemeral = inner.key.secret.diffie_hellman(&their_ephemeral);
+        let ephemeral_static = ephemeral.diffie_hellman(&their_static);
+        // Named from the initiator's side: its ephemeral key with the
+        // responder's static one, and the other way round.
+        let (es, se) = match role {
+            Role::Initiator => (ephemeral_static, static_ephemeral),
+            Role::Responder => (static_ephemeral, ephemeral_static),
+        };
+        if ![&ee, &es, &se].iter().all(|secret: &&SharedSecret| secret.was_contributory()) {
+            return Err(invalid("the other device sent a weak key"));
+        }
+
+        let mut ikm = Zeroizing::new(Vec::with_capacity(96 + pairing.len()));
+        for part in [ee.as_bytes(), es.as_bytes(), se.as_bytes(), pairing] {
+            ikm.extend_from_slice(part);
+        }
+        let salt = Sha256::new().chain_update(initiator_hello).chain_update(responder_hello).finalize();
+        let mut keys = Zeroizing::new([0u8; 64]);
+        Hkdf::<Sha256>::new(Some(&salt), &ikm)
+            .expand(KEY_INFO, keys.as_mut())
+            .map_err(|_| invalid("could not derive the session keys"))?;
+        let to_responder = ChaCha20Poly1305::new(Key::from_slice(&keys[..32]));
+        let to_initiator = ChaCha20Poly1305::new(Key::from_slice(&keys[32..]));
+        let (sealer, opener) = match role {
+            Role::Initiator => (to_responder, to_initiator),
+            Role::Responder => (to_initiator, to_responder),
+        };
+        Ok(Self { stream, role, sealer, opener, sent: 0, received: 0 })
+    }
+
+    pub(super) fn send(&mut self, message: &impl Serialize) -> Result<(), std::io::Error> {
+        let plain = Zeroizing::new(serde_json::to_vec(message)?);
+        let sealed = self.sealer
+            .encrypt(&nonce(self.sent), plain.as_slice())
+            .map_err(|_| invalid("could not encrypt a message"))?;
+        self.sent += 1;
+        write_frame(&mut self.stream, &sealed)
+    }
+
+    pub(super) fn receive<T: DeserializeOwned>(&mut self) -> Result<T, std::io::Error> {
+        let sealed = read_frame(&mut self.stream, MAX_FRAME)?;
+        let plain = Zeroizing::new(self.opener
+            .decrypt(&nonce(self.received), sealed.as_slice())
+            .map_err(|_| invalid("a message failed authentication"))?);
+        self.received += 1;
+        Ok(serde_json::from_slice(&plain)?)
+    }
+
+    /// Sends `message` and returns the other side's counterpart; the
+    /// initiator speaks first.
+    fn exchange(&mut self, message: Message) -> Result<Message, std::io::Error> {
+        match self.role {
+            Role::Initiator => {
+                self.send(&message)?;
+                self.receive()
+            }
+            Role::Responder => {
+                let reply = self.receive()?;
+                self.send(&message)?;
+                Ok(reply)
+            }
+        }
+    }
+}
+
+/// Each direction has its own key, so a message counter is a safe nonce.
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[..8].copy_from_slice(&counter.to_le_bytes());
+    *Nonce::from_slice(&nonce)
+}
+
+fn write_frame(stream: &mut TcpStream, bytes: &[u8]) -> Result<(), std::io::Error> {
+    let len = u32::try_from(bytes.len()).map_err(|_| invalid("message too large"))?;
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(bytes)
+}
+
+fn read_frame(stream: &mut TcpStream, limit: usize) -> Result<Vec<u8>, std::io::Error> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > limit {
+        return Err(invalid("message too large"));
+    }
+    let mut bytes = vec![0u8; len];
+    stream.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+fn refused(error: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::PermissionDenied, error)
+}
+
+fn other(error: &str) -> std::io::Error {
+    std::io::Error::other(error)
+}
+
+pub(super) fn closed() -> std::io::Error {
+    other("Trinket is closing")
+}
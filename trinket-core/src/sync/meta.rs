@@ -0,0 +1,250 @@
+//! Merges a snippet changed on two devices field by field, so that a tag
+//! added on one and a pin set on the other both survive. Each metadata field
+//! and the folder is a last-writer-wins register, stamped with when it last
+//! changed and on which device; tags are a last-writer-wins set, so adding
+//! and removing different tags on two devices merges cleanly; and copy
+//! counts are kept per device and added up, a grow-only counter. Changes
+//! are found by comparing each snippet with how it looked at the last sync,
+//! and stamped with the time its sidecar was saved. Every merge is
+//! symmetric, so both devices reach the same result on their own.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{SyncedSnippet, SYNC_DIR};
+use crate::storage::file_ops::write_atomic;
+use crate::storage::metadata::sidecar_path;
+use crate::storage::usage::SnippetUsage;
+use crate::storage::{Snippet, SnippetMeta};
+
+const CLOCKS_FILE: &str = "clocks.json";
+/// Metadata that follows the content or stays on this device rather than
+/// being merged as a register.
+const UNMERGED_FIELDS: [&str; 5] = ["tags", "created", "language", "deleted", "trashed_from"];
+const FOLDER_FIELD: &str = "folder";
+
+/// When something changed and on which device; the device id breaks ties.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Stamp {
+    pub at: DateTime<Utc>,
+    pub device: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TagStamp {
+    pub stamp: Stamp,
+    /// False once the tag was removed.
+    pub present: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Uses {
+    pub count: u32,
+    pub last_used: DateTime<Utc>,
+}
+
+/// When each part of a snippet last changed, and how often each device
+/// copied it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetaClock {
+    /// By metadata field name, plus `folder`.
+    pub fields: BTreeMap<String, Stamp>,
+    pub tags: BTreeMap<String, TagStamp>,
+    /// Copies by device id.
+    pub uses: BTreeMap<String, Uses>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Tracked {
+    clock: MetaClock,
+    /// Field values as of the last sync.
+    seen: Map<String, Value>,
+    seen_tags: Vec<String>,
+}
+
+/// The clocks of every synced snippet, kept in the sync directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClockStore {
+    #[serde(skip)]
+    path: PathBuf,
+    /// This device's id, so copies made elsewhere can be told apart.
+    device: String,
+    snippets: BTreeMap<String, Tracked>,
+}
+
+impl ClockStore {
+    pub fn load(storage_path: &Path) -> Self {
+        let path = storage_path.join(SYNC_DIR).join(CLOCKS_FILE);
+        let mut store: Self = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        store.path = path;
+        store
+    }
+
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(&self.path, &serde_json::to_vec(self)?)
+    }
+
+    /// Stamps whatever changed in `snippet` since it was last seen as
+    /// changed on `device`, and records `uses` as that device's copies.
+    pub fn observe(&mut self, device: &str, snippet: &Snippet, uses: Option<SnippetUsage>) -> MetaClock {
+        self.device = device.to_string();
+        let stamp = Stamp { at: changed_at(snippet), device: device.to_string() };
+        let tracked = self.snippets.entry(snippet.id.clone()).or_default();
+
+        let current = fields(&snippet.folder, &snippet.meta);
+        for (name, value) in &current {
+            if tracked.seen.get(name) != Some(value) {
+                tracked.clock.fields.insert(name.clone(), stamp.clone());
+            }
+        }
+        for tag in &snippet.meta.tags {
+            if !tracked.seen_tags.contains(tag) || !tracked.clock.tags.contains_key(tag) {
+                tracked.clock.tags.insert(tag.clone(), TagStamp { stamp: stamp.clone(), present: true });
+            }
+        }
+        for tag in &tracked.seen_tags {
+            if !snippet.meta.tags.contains(tag) {
+                tracked.clock.tags.insert(tag.clone(), TagStamp { stamp: stamp.clone(), present: false });
+            }
+        }
+        if let Some(uses) = uses {
+            tracked.clock.uses.insert(device.to_string(), Uses { count: uses.count, last_used: uses.last_used });
+        }
+
+        tracked.seen = current;
+        tracked.seen_tags = snippet.meta.tags.clone();
+        tracked.clock.clone()
+    }
+
+    /// Remembers a snippet written from another device, so its fields
+    /// don't count as changed here.
+    pub fn record(&mut self, snippet: &SyncedSnippet) {
+        self.snippets.insert(snippet.id.clone(), Tracked {
+            clock: snippet.clock.clone(),
+            seen: fields(&snippet.folder, &snippet.meta),
+            seen_tags: snippet.meta.tags.clone(),
+        });
+    }
+
+    /// Forgets snippets that are no longer in the library.
+    pub fn retain(&mut self, ids: &BTreeSet<&str>) {
+        self.snippets.retain(|id, _| ids.contains(id.as_str()));
+    }
+
+    /// Copies made on other devices, by snippet id.
+    pub fn synced_uses(&self) -> HashMap<String, SnippetUsage> {
+        let mut synced = HashMap::new();
+        for (id, tracked) in &self.snippets {
+            let others = tracked.clock.uses.iter().filter(|(device, _)| **device != self.device);
+            for (_, uses) in others {
+                let usage = synced.entry(id.clone()).or_insert(SnippetUsage { count: 0, last_used: uses.last_used });
+                usage.count += uses.count;
+                usage.last_used = usage.last_used.max(uses.last_used);
+            }
+        }
+        synced
+    }
+}
+
+/// When the snippet's metadata was last saved, or now if that's unknown.
+fn changed_at(snippet: &Snippet) -> DateTime<Utc> {
+    fs::metadata(sidecar_path(&snippet.file_path))
+        .and_then(|metadata| metadata.modified())
+        .map(DateTime::from)
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// The registers of a snippet, by name.
+fn fields(folder: &str, meta: &SnippetMeta) -> Map<String, Value> {
+    let mut fields = match serde_json::to_value(meta) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    for name in UNMERGED_FIELDS {
+        fields.remove(name);
+    }
+    fields.insert(FOLDER_FIELD.to_string(), Value::String(folder.to_string()));
+    fields
+}
+
+/// Merges two versions of a snippet that both changed since they last
+/// synced. The content comes from the side that changed it, if either did;
+/// `base_content` is the hash of the content they agreed on.
+pub fn merge(mine: &SyncedSnippet, theirs: &SyncedSnippet, base_content: Option<&str>) -> SyncedSnippet {
+    let content_from_theirs = mine.content != theirs.content && base_content == Some(mine.content_hash().as_str());
+    let content_side = if content_from_theirs { theirs } else { mine };
+
+    let (mine_fields, theirs_fields) = (fields(&mine.folder, &mine.meta), fields(&theirs.folder, &theirs.meta));
+    let mut merged = match serde_json::to_value(&content_side.meta) {
+        Ok(Value::Object(merged)) => merged,
+        _ => Map::new(),
+    };
+    let mut clock = MetaClock::default();
+    let names: BTreeSet<&String> = mine_fields.keys().chain(theirs_fields.keys()).collect();
+    for name in names {
+        let mine_stamp = mine.clock.fields.get(name);
+        let theirs_stamp = theirs.clock.fields.get(name);
+        let (mine_value, theirs_value) = (mine_fields.get(name), theirs_fields.get(name));
+        // Equal stamps only happen for values that never changed; the
+        // larger value wins then so both sides agree.
+        let take_theirs = (theirs_stamp, theirs_value.map(Value::to_string)) > (mine_stamp, mine_value.map(Value::to_string));
+        let (value, stamp) = if take_theirs { (theirs_value, theirs_stamp) } else { (mine_value, mine_stamp) };
+        if let Some(value) = value {
+            merged.insert(name.clone(), value.clone());
+        }
+        if let Some(stamp) = stamp {
+            clock.fields.insert(name.clone(), stamp.clone());
+        }
+    }
+    let folder = match merged.remove(FOLDER_FIELD) {
+        Some(Value::String(folder)) => folder,
+        _ => content_side.folder.clone(),
+    };
+
+    for (tag, stamp) in mine.clock.tags.iter().chain(&theirs.clock.tags) {
+        if clock.tags.get(tag).is_none_or(|known| stamp > known) {
+            clock.tags.insert(tag.clone(), stamp.clone());
+        }
+    }
+    let tags: Vec<String> = clock.tags.iter()
+        .filter(|(_, stamp)| stamp.present)
+        .map(|(tag, _)| tag.clone())
+        .collect();
+    merged.insert("tags".to_string(), serde_json::json!(tags));
+
+    for (device, uses) in mine.clock.uses.iter().chain(&theirs.clock.uses) {
+        let merged = clock.uses.entry(device.clone()).or_insert(*uses);
+        merged.count = merged.count.max(uses.count);
+        merged.last_used = merged.last_used.max(uses.last_used);
+    }
+
+    let created = match (mine.meta.created, theirs.meta.created) {
+        (Some(mine), Some(theirs)) => Some(mine.min(theirs)),
+        (mine, theirs) => mine.or(theirs),
+    };
+    merged.insert("created".to_string(), serde_json::json!(created));
+    merged.remove("deleted");
+    merged.remove("trashed_from");
+
+    SyncedSnippet {
+        id: mine.id.clone(),
+        folder,
+        content: content_side.content.clone(),
+        meta: serde_json::from_value(Value::Object(merged)).unwrap_or_else(|_| content_side.meta.clone()),
+        modified: mine.modified.max(theirs.modified),
+        clock,
+    }
+}
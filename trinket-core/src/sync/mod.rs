@@ -0,0 +1,462 @@
+//! Keeps the library in step with other devices. Each side sends a manifest
+//! of what it holds, and both compare the two against the state they agreed
+//! on at their last sync. That way an edit, move or deletion on either side
+//! carries over. A snippet changed on both sides has its metadata merged
+//! (see [`meta`]); only content edited on both is left for the user to
+//! settle. Phones can also pair as companions (see [`companion`]), and an
+//! S3-compatible bucket can stand in for a device that is always on (see
+//! `s3`, built with the `s3` feature). Only built with the `sync` feature.
+
+pub mod companion;
+pub mod lan;
+pub mod meta;
+mod pake;
+#[cfg(feature = "s3")]
+pub mod s3;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+use crate::storage::file_ops::write_atomic;
+use crate::storage::folders::normalize_folder;
+use crate::storage::usage::SnippetUsage;
+use crate::storage::{FileStorage, Snippet, SnippetMeta};
+use meta::{ClockStore, MetaClock};
+
+/// Holds what was agreed with each peer, as `<peer id>.json`.
+const SYNC_DIR: &str = ".sync";
+
+/// A snippet as it travels between devices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncedSnippet {
+    pub id: String,
+    pub folder: String,
+    pub content: String,
+    pub meta: SnippetMeta,
+    pub modified: DateTime<Utc>,
+    #[serde(default)]
+    pub clock: MetaClock,
+}
+
+/// Copies of secrets are scrubbed like the snippets they came from.
+impl Drop for SyncedSnippet {
+    fn drop(&mut self) {
+        if self.meta.secret {
+            self.content.zeroize();
+        }
+    }
+}
+
+impl SyncedSnippet {
+    pub fn new(snippet: &Snippet) -> Self {
+        Self {
+            id: snippet.id.clone(),
+            folder: snippet.folder.clone(),
+            content: snippet.content.clone(),
+            meta: snippet.meta.clone(),
+            modified: snippet.modified.into(),
+            clock: MetaClock::default(),
+        }
+    }
+
+    /// Fingerprint of everything that syncs: folder, content, metadata and
+    /// copy counts.
+    pub fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.folder.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.content.as_bytes());
+        hasher.update([0]);
+        // Field order is fixed, so equal metadata serializes the same.
+        hasher.update(serde_json::to_vec(&self.meta).unwrap_or_default());
+        hasher.update([0]);
+        hasher.update(serde_json::to_vec(&self.clock.uses).unwrap_or_default());
+        to_hex(&hasher.finalize())
+    }
+
+    pub fn content_hash(&self) -> String {
+        to_hex(&Sha256::digest(self.content.as_bytes()))
+    }
+
+    pub fn hashes(&self) -> Hashes {
+        Hashes { hash: self.hash(), content: self.content_hash() }
+    }
+}
+
+/// Fingerprints of a snippet: of everything that syncs, and of its content
+/// alone, which tells an edit from a metadata change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hashes {
+    pub hash: String,
+    pub content: String,
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum EntryState {
+    Live(Hashes),
+    Deleted,
+    /// In an encrypted folder that is locked, or one not synced with this
+    /// peer; left alone on both sides.
+    Locked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub state: EntryState,
+    pub modified: DateTime<Utc>,
+}
+
+pub type Manifest = BTreeMap<String, ManifestEntry>;
+
+/// Hashes of the snippets both sides held after their last sync, by id.
+pub type SyncBase = BTreeMap<String, Hashes>;
+
+/// The library as a sync session sees it, taken by the app when the
+/// session starts.
+#[derive(Default)]
+pub struct Snapshot {
+    pub snippets: Vec<SyncedSnippet>,
+    /// Trashed snippets with when they were deleted and the folder they
+    /// were in.
+    pub deleted: Vec<(String, DateTime<Utc>, String)>,
+    pub locked: Vec<String>,
+}
+
+impl Snapshot {
+    /// Reads the library, stamping metadata changed since the last sync as
+    /// changed on `device`.
+    /// `local_uses` are the copies made on this device, by snippet id.
+    pub fn new(storage: &FileStorage, snippets: &[Snippet], local_uses: &HashMap<String, SnippetUsage>, device: &str) -> Result<Self, std::io::Error> {
+        let deleted = storage.trashed_ids()?
+            .into_iter()
+            .filter(|(_, _, from)| !storage.is_folder_locked(from))
+            .collect();
+        let mut locked = storage.locked_ids()?;
+        locked.extend(storage.trashed_ids()?
+            .into_iter()
+            .filter(|(_, _, from)| storage.is_folder_locked(from))
+            .map(|(id, _, _)| id));
+
+        let mut clocks = ClockStore::load(&storage.base_path);
+        let synced = snippets.iter()
+            .map(|snippet| {
+                let mut synced = SyncedSnippet::new(snippet);
+                synced.clock = clocks.observe(device, snippet, local_uses.get(&snippet.id).copied());
+                synced
+            })
+            .collect();
+        clocks.retain(&snippets.iter().map(|snippet| snippet.id.as_str()).chain(locked.iter().map(String::as_str)).collect());
+        clocks.save()?;
+        Ok(Self { snippets: synced, deleted, locked })
+    }
+
+    pub fn manifest(&self) -> Manifest {
+        let mut manifest = Manifest::new();
+        for (id, deleted, _) in &self.deleted {
+            manifest.insert(id.clone(), ManifestEntry { state: EntryState::Deleted, modified: *deleted });
+        }
+        for snippet in &self.snippets {
+            let entry = ManifestEntry { state: EntryState::Live(snippet.hashes()), modified: snippet.modified };
+            manifest.insert(snippet.id.clone(), entry);
+        }
+        for id in &self.locked {
+            manifest.insert(id.clone(), ManifestEntry { state: EntryState::Locked, modified: Utc::now() });
+        }
+        manifest
+    }
+
+    /// Leaves out the snippets in folders `syncs` turns down, so the peer
+    /// neither gets them nor deletes its own copies.
+    pub fn exclude(&mut self, syncs: impl Fn(&str) -> bool) {
+        let (snippets, excluded): (Vec<_>, Vec<_>) = std::mem::take(&mut self.snippets)
+            .into_iter()
+            .partition(|snippet| syncs(&snippet.folder));
+        self.snippets = snippets;
+        self.locked.extend(excluded.iter().map(|snippet| snippet.id.clone()));
+        let (deleted, excluded): (Vec<_>, Vec<_>) = std::mem::take(&mut self.deleted)
+            .into_iter()
+            .partition(|(_, _, from)| syncs(from));
+        self.deleted = deleted;
+        self.locked.extend(excluded.into_iter().map(|(id, _, _)| id));
+    }
+
+    pub fn find(&self, id: &str) -> Option<&SyncedSnippet> {
+        self.snippets.iter().find(|snippet| snippet.id == id)
+    }
+
+    /// The snippets among `ids`; ids that aren't live here are left out.
+    pub fn take(&self, ids: &[String]) -> Vec<SyncedSnippet> {
+        let wanted: BTreeSet<&str> = ids.iter().map(String::as_str).collect();
+        self.snippets.iter()
+            .filter(|snippet| wanted.contains(snippet.id.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// What one side takes from the other, and the state both end up with.
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub fetch: Vec<String>,
+    pub delete: Vec<String>,
+    /// Changed on both sides, but the content on one side at most; both
+    /// versions are merged with [`meta::merge`] and the result agreed on.
+    pub merge: Vec<String>,
+    /// Content edited on both sides; the other side's version is fetched
+    /// for the user to compare, and the base is left as it was.
+    pub conflicts: Vec<String>,
+    pub base: SyncBase,
+}
+
+/// Compares `local` with `remote` against their last agreed `base`. A side
+/// that changed a snippet since then wins over one that didn't. When both
+/// did, an edit beats a deletion, and two versions are merged unless both
+/// edited the content. The rules are symmetric, so both peers reach the
+/// same result on their own.
+pub fn plan(local: &Manifest, remote: &Manifest, base: &SyncBase) -> Plan {
+    let mut plan = Plan::default();
+    let ids: BTreeSet<&String> = local.keys().chain(remote.keys()).chain(base.keys()).collect();
+
+    for id in ids {
+        let (mine, theirs) = (local.get(id), remote.get(id));
+        let is_locked = |entry: Option<&ManifestEntry>| entry.is_some_and(|entry| entry.state == EntryState::Locked);
+        if is_locked(mine) || is_locked(theirs) {
+            if let Some(hash) = base.get(id) {
+                plan.base.insert(id.clone(), hash.clone());
+            }
+            continue;
+        }
+
+        let (mine_hashes, theirs_hashes) = (live_hashes(mine), live_hashes(theirs));
+        let agreed = base.get(id);
+        let (mine_hash, theirs_hash) = (mine_hashes.map(|h| &h.hash), theirs_hashes.map(|h| &h.hash));
+        let (mine_changed, theirs_changed) = (mine_hash != agreed.map(|h| &h.hash), theirs_hash != agreed.map(|h| &h.hash));
+        if let (Some(mine), Some(theirs), true, true) = (mine_hashes, theirs_hashes, mine_changed, theirs_changed) {
+            if mine.hash != theirs.hash {
+                let agreed_content = agreed.map(|h| &h.content);
+                let both_edited = mine.content != theirs.content
+                    && Some(&mine.content) != agreed_content
+                    && Some(&theirs.content) != agreed_content;
+                if both_edited {
+                    plan.conflicts.push(id.clone());
+                } else {
+                    plan.merge.push(id.clone());
+                }
+                if let Some(hashes) = agreed {
+                    plan.base.insert(id.clone(), hashes.clone());
+                }
+                continue;
+            }
+        }
+        let take_theirs = mine_hash != theirs_hash && match (mine_changed, theirs_changed) {
+            (true, false) => false,
+            (false, true) => true,
+            // Edited on one side and deleted on the other: the edit wins.
+            _ => mine_hash.is_none(),
+        };
+
+        let result = if take_theirs {
+            match theirs_hash {
+                Some(_) => plan.fetch.push(id.clone()),
+                None if mine_hash.is_some() => plan.delete.push(id.clone()),
+                None => {}
+            }
+            theirs_hashes
+        } else {
+            mine_hashes
+        };
+        if let Some(hashes) = result {
+            plan.base.insert(id.clone(), hashes.clone());
+        }
+    }
+    plan
+}
+
+/// What a session ends with once the other side's snippets are in.
+pub struct Outcome {
+    /// The state to agree on with the other side.
+    pub agreed: SyncBase,
+    /// What to save here: fetched and merged snippets, and deletions.
+    pub changes: Changes,
+    /// The other side's versions of snippets edited on both.
+    pub conflicts: Vec<SyncedSnippet>,
+}
+
+/// Works out what to save and agree on from `plan` and the snippets
+/// `received` for it, merging those changed on both sides. Snippets in
+/// folders `syncs` turns down are left as they were.
+pub fn outcome(plan: Plan, base: &SyncBase, snapshot: &Snapshot, received: Vec<SyncedSnippet>, syncs: impl Fn(&str) -> bool) -> Outcome {
+    let mut received: BTreeMap<String, SyncedSnippet> = received.into_iter()
+        .map(|snippet| (snippet.id.clone(), snippet))
+        .collect();
+    let mut agreed = plan.base;
+    let mut incoming = Vec::new();
+    for id in plan.fetch.iter().chain(&plan.merge) {
+        // Gone from the other side since its manifest, or moved to a
+        // folder this side doesn't sync with them; left as it was.
+        let Some(theirs) = received.remove(id).filter(|theirs| syncs(&theirs.folder)) else {
+            match base.get(id) {
+                Some(hashes) => agreed.insert(id.clone(), hashes.clone()),
+                None => agreed.remove(id),
+            };
+            continue;
+        };
+        let result = match snapshot.find(id) {
+            Some(mine) if plan.merge.contains(id) => {
+                let merged = meta::merge(mine, &theirs, base.get(id).map(|hashes| hashes.content.as_str()));
+                (merged.hash() != mine.hash()).then_some(merged)
+            }
+            _ => Some(theirs),
+        };
+        if let Some(snippet) = result {
+            agreed.insert(id.clone(), snippet.hashes());
+            incoming.push(snippet);
+        } else if let Some(mine) = snapshot.find(id) {
+            agreed.insert(id.clone(), mine.hashes());
+        }
+    }
+    let conflicts = plan.conflicts.iter()
+        .filter_map(|id| received.remove(id))
+        .filter(|theirs| syncs(&theirs.folder))
+        .collect();
+    Outcome { agreed, changes: Changes { snippets: incoming, deleted: plan.delete }, conflicts }
+}
+
+fn live_hashes(entry: Option<&ManifestEntry>) -> Option<&Hashes> {
+    match entry.map(|entry| &entry.state) {
+        Some(EntryState::Live(hashes)) => Some(hashes),
+        _ => None,
+    }
+}
+
+/// Changes received from a peer, to be saved by the app.
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub snippets: Vec<SyncedSnippet>,
+    pub deleted: Vec<String>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.snippets.is_empty() && self.deleted.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.snippets.len() + self.deleted.len()
+    }
+}
+
+/// Saves a peer's changes. Snippets it deleted go to the trash here too,
+/// so a sync never loses anything for good.
+#[tracing::instrument(name = "sync_apply", skip_all)]
+pub fn apply(storage: &FileStorage, snippets: &mut [Snippet], changes: &Changes) -> Result<(), std::io::Error> {
+    let trashed: BTreeSet<String> = storage.trashed_ids()?.into_iter().map(|(id, _, _)| id).collect();
+    let mut clocks = ClockStore::load(&storage.base_path);
+    for incoming in &changes.snippets {
+        // Ids become file names, so anything odd is not trusted.
+        if incoming.id.is_empty() || !incoming.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            log::warn!("Skipping synced snippet with invalid id {:?}", incoming.id);
+            continue;
+        }
+        let folder = normalize_folder(&incoming.folder)?;
+        let mut meta = incoming.meta.clone();
+        meta.deleted = None;
+        meta.trashed_from = None;
+        match snippets.iter_mut().find(|snippet| snippet.id == incoming.id) {
+            Some(snippet) => storage.overwrite_snippet(snippet, &folder, &incoming.content, meta)?,
+            None => {
+                storage.check_size(&incoming.content)?;
+                // Deleted here but edited there: the edit wins.
+                if trashed.contains(&incoming.id) {
+                    storage.delete_from_trash(&incoming.id)?;
+                }
+                storage.write_snippet(incoming.id.clone(), &incoming.content, &folder, meta)?;
+            }
+        }
+        clocks.record(incoming);
+    }
+    clocks.save()?;
+    for id in &changes.deleted {
+        if let Some(snippet) = snippets.iter().find(|snippet| snippet.id == *id) {
+            storage.move_to_trash(&snippet.file_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// A snippet edited both here and on a peer since they last synced.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub peer_id: String,
+    pub peer_name: String,
+    pub theirs: SyncedSnippet,
+}
+
+impl Conflict {
+    pub fn id(&self) -> &str {
+        &self.theirs.id
+    }
+}
+
+/// Which version of a conflicting snippet to keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Mine,
+    Theirs,
+    /// Take the peer's version and keep ours as a new snippet.
+    Both,
+}
+
+/// Saves the outcome the user picked for `conflict`. The base with the peer
+/// must then be settled on the peer's version, so that whatever differs
+/// from it here is sent back on the next sync.
+#[tracing::instrument(name = "sync_resolve", skip_all, fields(peer = %conflict.peer_name))]
+pub fn resolve(storage: &FileStorage, snippets: &mut [Snippet], conflict: &Conflict, resolution: Resolution) -> Result<(), std::io::Error> {
+    if resolution == Resolution::Both {
+        if let Some(mine) = snippets.iter().find(|snippet| snippet.id == conflict.id()) {
+            storage.save_snippet(&mine.content, &mine.folder, mine.meta.clone())?;
+        }
+    }
+    if resolution != Resolution::Mine {
+        let changes = Changes { snippets: vec![conflict.theirs.clone()], deleted: Vec::new() };
+        apply(storage, snippets, &changes)?;
+    }
+    Ok(())
+}
+
+fn base_path(storage_path: &Path, peer_id: &str) -> PathBuf {
+    storage_path.join(SYNC_DIR).join(format!("{}.json", peer_id))
+}
+
+/// What was agreed with `peer_id` last time; empty before the first sync.
+pub fn load_base(storage_path: &Path, peer_id: &str) -> SyncBase {
+    fs::read_to_string(base_path(storage_path, peer_id))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// When the state agreed with `peer_id` was last saved, as every finished
+/// sync does.
+pub fn base_saved(storage_path: &Path, peer_id: &str) -> Option<DateTime<Utc>> {
+    let modified = fs::metadata(base_path(storage_path, peer_id)).and_then(|metadata| metadata.modified());
+    modified.ok().map(DateTime::from)
+}
+
+pub fn save_base(storage_path: &Path, peer_id: &str, base: &SyncBase) -> Result<(), std::io::Error> {
+    let path = base_path(storage_path, peer_id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomic(&path, &serde_json::to_vec(base)?)
+}
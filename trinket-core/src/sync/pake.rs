@@ -0,0 +1,94 @@
+//! CPace over ristretto255, after draft-irtf-cfrg-cpace, so two devices
+//! agree on a key from the pairing code shown on one of them. Neither the
+//! shares nor anything sealed with the key lets someone without the code
+//! test guesses offline: each guess costs them a connection, which the
+//! device showing the code counts.
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha512};
+use zeroize::{Zeroize, Zeroizing};
+
+const DSI: &[u8] = b"CPaceRistretto255";
+const DSI_ISK: &[u8] = b"CPaceRistretto255_ISK";
+
+/// One side's half of the exchange.
+pub(super) struct Pake {
+    secret: Scalar,
+    /// Sent to the other side, as a compressed point.
+    pub(super) share: [u8; 32],
+}
+
+impl Pake {
+    /// `context` names the two devices, the initiator's first, so a share
+    /// is no use between any other pair.
+    pub(super) fn new(code: &str, context: &[&str]) -> Self {
+        let mut wide = Zeroizing::new([0u8; 64]);
+        OsRng.fill_bytes(wide.as_mut());
+        let secret = Scalar::from_bytes_mod_order_wide(&wide);
+        let share = (generator(code, context) * secret).compress().to_bytes();
+        Self { secret, share }
+    }
+
+    /// The key shared with the side that sent `theirs`. Both sides get the
+    /// same one only if they used the same code.
+    pub(super) fn finish(&self, theirs: &[u8], initiator: bool) -> Result<Zeroizing<[u8; 64]>, std::io::Error> {
+        let point = CompressedRistretto::from_slice(theirs)
+            .ok()
+            .and_then(|point| point.decompress())
+            .ok_or_else(|| invalid("malformed pairing share"))?;
+        let shared = point * self.secret;
+        if shared.is_identity() {
+            return Err(invalid("the other device sent a weak pairing share"));
+        }
+        let (first, second) = if initiator { (&self.share[..], theirs) } else { (theirs, &self.share[..]) };
+        let mut hash = Sha512::new();
+        for part in [DSI_ISK, shared.compress().as_bytes(), first, second] {
+            hash.update((part.len() as u64).to_le_bytes());
+            hash.update(part);
+        }
+        let mut key = Zeroizing::new([0u8; 64]);
+        key.copy_from_slice(&hash.finalize());
+        Ok(key)
+    }
+}
+
+impl Drop for Pake {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// A point nobody knows the discrete log of, derived from the code.
+fn generator(code: &str, context: &[&str]) -> RistrettoPoint {
+    let mut hash = Sha512::new();
+    for part in [DSI, code.as_bytes()].into_iter().chain(context.iter().map(|part| part.as_bytes())) {
+        hash.update((part.len() as u64).to_le_bytes());
+        hash.update(part);
+    }
+    let mut uniform = Zeroizing::new([0u8; 64]);
+    uniform.copy_from_slice(&hash.finalize());
+    RistrettoPoint::from_uniform_bytes(&uniform)
+}
+
+fn invalid(error: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_same_code_gives_the_same_key() {
+        let context = ["initiator", "responder"];
+        let (initiator, responder) = (Pake::new("7KQ2M9XV4B", &context), Pake::new("7KQ2M9XV4B", &context));
+        assert_eq!(*initiator.finish(&responder.share, true).unwrap(), *responder.finish(&initiator.share, false).unwrap());
+
+        let guess = Pake::new("7KQ2M9XV4C", &context);
+        assert_ne!(*initiator.finish(&guess.share, true).unwrap(), *guess.finish(&initiator.share, false).unwrap());
+        assert!(initiator.finish(&[0u8; 32], true).is_err());
+    }
+}
@@ -0,0 +1,655 @@
+//! Syncs the library through an S3-compatible bucket (AWS, MinIO, Backblaze
+//! B2 and the like) and keeps encrypted snapshots there as backups. The
+//! bucket is treated as one more peer: its state is compared with the
+//! library against what was agreed at the last sync, as with paired devices.
+//!
+//! Everything in the bucket is encrypted with an age key that never leaves
+//! the devices; it is kept in the keychain along with the bucket's
+//! credentials. Under the configured prefix the bucket holds:
+//!
+//! - `snippets/<id>`: the latest version of each snippet.
+//! - `changes/<revision>-<device>`: what a sync changed, as manifest
+//!   entries. Devices keep their own view of the bucket up to date by
+//!   reading only the change records they haven't seen.
+//! - `snapshots/<time>-<device>`: the whole library once a day, along with
+//!   the bucket's manifest so a new device can start from there.
+//!
+//! Only built with the `s3` feature.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::time::Duration;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::lan::{closed, LanEvent};
+use super::{load_base, outcome, plan, save_base, to_hex, Hashes, Manifest, ManifestEntry, EntryState, Outcome, Snapshot, SyncedSnippet, SYNC_DIR};
+use crate::sync_settings::{FolderSync, S3Target};
+use crate::crypto::{self, Vault};
+use crate::keychain;
+use crate::storage::changes::{rev_at, ChangeLog, CHANGES_FILE};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const SNAPSHOT_INTERVAL: chrono::TimeDelta = chrono::TimeDelta::days(1);
+const SNAPSHOTS_KEPT: usize = 7;
+/// Change records are named by the clock of the device that wrote them, so
+/// records this much older than the newest one seen are read again in case
+/// a device's clock is behind.
+const CLOCK_MARGIN: u64 = 60 * 60 * 1_000_000;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long a sync waits for the app to read or save the library.
+const APP_TIMEOUT: Duration = Duration::from_secs(30);
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbfb4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Access keys for the bucket, kept in the keychain.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl Drop for S3Credentials {
+    fn drop(&mut self) {
+        self.secret_access_key.zeroize();
+    }
+}
+
+impl S3Credentials {
+    pub fn load() -> Result<Option<Self>, std::io::Error> {
+        let Some(mut text) = keychain::load(keychain::S3_CREDENTIALS)? else {
+            return Ok(None);
+        };
+        let credentials = serde_json::from_str(&text);
+        text.zeroize();
+        Ok(Some(credentials?))
+    }
+
+    pub fn store(&self) -> Result<(), std::io::Error> {
+        let mut text = serde_json::to_string(self)?;
+        let result = keychain::store(keychain::S3_CREDENTIALS, &text);
+        text.zeroize();
+        result
+    }
+}
+
+/// The key the bucket's contents are encrypted with. Every device syncing
+/// through the bucket needs the same one, so it is created on the first
+/// and copied to the others.
+pub fn load_key() -> Result<Option<String>, std::io::Error> {
+    keychain::load(keychain::S3_KEY)
+}
+
+/// Checks that `key` is an age identity before keeping it.
+pub fn store_key(key: &str) -> Result<(), std::io::Error> {
+    let key = key.trim();
+    Vault::from_keys(key, "")?;
+    keychain::store(keychain::S3_KEY, key)
+}
+
+fn load_or_create_key() -> Result<String, std::io::Error> {
+    use age::secrecy::ExposeSecret;
+    if let Some(key) = load_key()? {
+        return Ok(key);
+    }
+    log::info!("Creating a sync key for the S3 bucket");
+    let key = age::x25519::Identity::generate().to_string().expose_secret().to_string();
+    keychain::store(keychain::S3_KEY, &key)?;
+    Ok(key)
+}
+
+/// A signed-request client for one bucket, addressed path-style, which
+/// every S3-compatible service accepts.
+struct Bucket {
+    /// `scheme://host[:port]`, without a trailing slash.
+    endpoint: String,
+    host: String,
+    region: String,
+    name: String,
+    credentials: S3Credentials,
+}
+
+impl Bucket {
+    fn new(target: &S3Target, credentials: S3Credentials) -> Result<Self, std::io::Error> {
+        let endpoint = target.endpoint.trim().trim_end_matches('/');
+        let (scheme, authority) = endpoint.split_once("://")
+            .ok_or_else(|| invalid("the endpoint needs to start with https:// or http://"))?;
+        if authority.is_empty() || authority.contains('/') {
+            return Err(invalid("the endpoint is a host name, without a path"));
+        }
+        // The HTTP client leaves out default ports, so the signature must too.
+        let host = match (scheme, authority.rsplit_once(':')) {
+            ("https", Some((host, "443"))) | ("http", Some((host, "80"))) => host,
+            _ => authority,
+        };
+        if target.bucket.trim().is_empty() {
+            return Err(invalid("no bucket name"));
+        }
+        Ok(Self {
+            endpoint: format!("{}://{}", scheme, host),
+            host: host.to_string(),
+            region: if target.region.trim().is_empty() { "us-east-1".to_string() } else { target.region.trim().to_string() },
+            name: target.bucket.trim().to_string(),
+            credentials,
+        })
+    }
+
+    /// The object at `key`, or `None` when there is none.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, std::io::Error> {
+        let response = self.send(attohttpc::Method::GET, key, &[], &[])?;
+        if response.status() == attohttpc::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(checked(response, "GET", key)?.bytes().map_err(std::io::Error::other)?))
+    }
+
+    fn put(&self, key: &str, body: &[u8]) -> Result<(), std::io::Error> {
+        checked(self.send(attohttpc::Method::PUT, key, &[], body)?, "PUT", key)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), std::io::Error> {
+        checked(self.send(attohttpc::Method::DELETE, key, &[], &[])?, "DELETE", key)?;
+        Ok(())
+    }
+
+    /// Keys under `prefix` that sort after `start_after`, in order.
+    fn list(&self, prefix: &str, start_after: &str) -> Result<Vec<String>, std::io::Error> {
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if !start_after.is_empty() {
+                query.push(("start-after", start_after));
+            }
+            if let Some(token) = &token {
+                query.push(("continuation-token", token));
+            }
+            let response = checked(self.send(attohttpc::Method::GET, "", &query, &[])?, "LIST", prefix)?;
+            let xml = response.text().map_err(std::io::Error::other)?;
+            keys.extend(xml_values(&xml, "Key"));
+            token = xml_values(&xml, "NextContinuationToken").into_iter().next();
+            if xml_values(&xml, "IsTruncated").first().map(String::as_str) != Some("true") || token.is_none() {
+                return Ok(keys);
+            }
+        }
+    }
+
+    /// Sends a request signed with AWS Signature Version 4.
+    fn send(&self, method: attohttpc::Method, key: &str, query: &[(&str, &str)], body: &[u8]) -> Result<attohttpc::Response, std::io::Error> {
+        let now = Utc::now();
+        let (date_time, date) = (now.format("%Y%m%dT%H%M%SZ").to_string(), now.format("%Y%m%d").to_string());
+        let payload_hash = if body.is_empty() { EMPTY_SHA256.to_string() } else { to_hex(&Sha256::digest(body)) };
+
+        let mut path = format!("/{}", encode(&self.name));
+        if !key.is_empty() {
+            path.push('/');
+            path.push_str(&key.split('/').map(encode).collect::<Vec<_>>().join("/"));
+        }
+        let mut pairs: Vec<(String, String)> = query.iter().map(|(name, value)| (encode(name), encode(value))).collect();
+        pairs.sort();
+        let query = pairs.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join("&");
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, query, self.host, payload_hash, date_time, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            date_time, scope, to_hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut secret = format!("AWS4{}", self.credentials.secret_access_key);
+        let date_key = hmac(secret.as_bytes(), date.as_bytes());
+        secret.zeroize();
+        let region_key = hmac(&date_key, self.region.as_bytes());
+        let service_key = hmac(&region_key, b"s3");
+        let signing_key = hmac(&service_key, b"aws4_request");
+        let signature = to_hex(&hmac(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.credentials.access_key_id, scope, signed_headers, signature
+        );
+
+        let url = if query.is_empty() { format!("{}{}", self.endpoint, path) } else { format!("{}{}?{}", self.endpoint, path, query) };
+        attohttpc::RequestBuilder::new(method, url)
+            .timeout(REQUEST_TIMEOUT)
+            .header("Authorization", authorization)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", date_time)
+            .bytes(body)
+            .send()
+            .map_err(std::io::Error::other)
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Percent-encodes everything but the characters S3 leaves alone.
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn checked(response: attohttpc::Response, method: &str, key: &str) -> Result<attohttpc::Response, std::io::Error> {
+    if response.is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().unwrap_or_default();
+    let reason = xml_values(&body, "Message").into_iter().next()
+        .or_else(|| xml_values(&body, "Code").into_iter().next())
+        .unwrap_or_else(|| status.to_string());
+    let kind = match status.as_u16() {
+        401 | 403 => std::io::ErrorKind::PermissionDenied,
+        404 => std::io::ErrorKind::NotFound,
+        _ => std::io::ErrorKind::Other,
+    };
+    Err(std::io::Error::new(kind, format!("{} {} failed: {}", method, key, reason)))
+}
+
+/// The text of every `<tag>` element, enough for S3's flat responses.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        values.push(
+            rest[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
+
+/// One entry of a change record.
+#[derive(Serialize, Deserialize)]
+struct RemoteChange {
+    id: String,
+    entry: ManifestEntry,
+}
+
+/// A backup of the library, with the bucket's manifest at the time.
+#[derive(Serialize, Deserialize)]
+struct BucketSnapshot {
+    /// The last change record the manifest includes.
+    last_change: Option<String>,
+    manifest: Manifest,
+    snippets: Vec<SyncedSnippet>,
+}
+
+/// This device's view of the bucket, kept between syncs.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct BucketState {
+    remote: Manifest,
+    /// The newest change record read.
+    last_change: Option<String>,
+    /// Change records read within [`CLOCK_MARGIN`] of the newest.
+    seen: BTreeSet<String>,
+    /// The change log revision the last sync started at.
+    local_rev: u64,
+    last_snapshot: Option<DateTime<Utc>>,
+}
+
+struct Inner {
+    bucket: Bucket,
+    prefix: String,
+    /// Opened with the sync key.
+    vault: Vault,
+    device: String,
+    /// Stands for the bucket wherever a peer id is needed.
+    peer_id: String,
+    storage_path: PathBuf,
+    changes: ChangeLog,
+    /// Which folders sync through the bucket, as in the app config's
+    /// `folder_sync`.
+    folder_sync: Mutex<BTreeMap<String, FolderSync>>,
+    /// Held for the length of a sync, so a settled conflict isn't saved
+    /// over.
+    session: Mutex<()>,
+}
+
+impl Inner {
+    fn key(&self, kind: &str, name: &str) -> String {
+        format!("{}{}/{}", self.prefix, kind, name)
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.storage_path.join(SYNC_DIR).join(format!("{}-state.json", self.peer_id))
+    }
+
+    fn load_state(&self) -> BucketState {
+        fs::read_to_string(self.state_path())
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_state(&self, state: &BucketState) -> Result<(), std::io::Error> {
+        let path = self.state_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        crate::storage::file_ops::write_atomic(&path, &serde_json::to_vec(state)?)
+    }
+
+}
+
+/// Reads and writes the bucket's objects, encrypted with the sync key.
+struct Sealed<'a> {
+    inner: &'a Inner,
+}
+
+impl Sealed<'_> {
+    fn put(&self, key: &str, value: &impl Serialize) -> Result<(), std::io::Error> {
+        let mut plaintext = serde_json::to_vec(value)?;
+        let sealed = self.inner.vault.encrypt(&plaintext);
+        plaintext.zeroize();
+        self.inner.bucket.put(key, &sealed?)
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, std::io::Error> {
+        let Some(sealed) = self.inner.bucket.get(key)? else {
+            return Ok(None);
+        };
+        let mut plaintext = crypto::decrypt([&self.inner.vault], &sealed).map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{} can't be read with this device's sync key; copy the key from the device that set up the bucket ({})", key, e))
+        })?;
+        let value = serde_json::from_slice(&plaintext);
+        plaintext.zeroize();
+        Ok(Some(value?))
+    }
+}
+
+/// Syncs with the bucket in the background for as long as it is kept. It
+/// reports through the same events as LAN sync.
+pub struct S3Sync {
+    inner: Arc<Inner>,
+    events: mpsc::Receiver<LanEvent>,
+    wake: mpsc::Sender<()>,
+}
+
+impl S3Sync {
+    /// Starts syncing the folders `folder_sync` lets through with the
+    /// bucket at `target` as `device`, with the credentials and key from
+    /// the keychain. The key is created when there is none yet.
+    pub fn start(target: &S3Target, device: String, storage_path: PathBuf, folder_sync: BTreeMap<String, FolderSync>) -> Result<Self, std::io::Error> {
+        let credentials = S3Credentials::load()?
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no credentials for the bucket; add them in the settings"))?;
+        let vault = Vault::from_keys(&Zeroizing::new(load_or_create_key()?), "")?;
+        let bucket = Bucket::new(target, credentials)?;
+        let prefix = match target.prefix.trim().trim_matches('/') {
+            "" => String::new(),
+            prefix => format!("{}/", prefix),
+        };
+        let location = format!("{}/{}/{}", bucket.endpoint, bucket.name, prefix);
+        let inner = Arc::new(Inner {
+            peer_id: format!("s3-{}", &to_hex(&Sha256::digest(location.as_bytes()))[..16]),
+            bucket,
+            prefix,
+            vault,
+            device,
+            changes: ChangeLog::open(storage_path.join(CHANGES_FILE)),
+            storage_path,
+            folder_sync: Mutex::new(folder_sync),
+            session: Mutex::new(()),
+        });
+        let (sender, events) = mpsc::channel();
+        let (wake, wakeups) = mpsc::channel();
+        let schedule_inner = inner.clone();
+        std::thread::spawn(move || schedule(schedule_inner, wakeups, sender));
+        Ok(Self { inner, events, wake })
+    }
+
+    /// This device's id, which metadata changes made here are stamped with.
+    pub fn device(&self) -> &str {
+        &self.inner.device
+    }
+
+    /// The id conflicts with the bucket carry as their peer.
+    pub fn peer_id(&self) -> &str {
+        &self.inner.peer_id
+    }
+
+    /// Sets which folders sync through the bucket, as in the app config's
+    /// `folder_sync`.
+    pub fn set_folder_sync(&self, folder_sync: BTreeMap<String, FolderSync>) {
+        *self.inner.folder_sync.lock().unwrap_or_else(PoisonError::into_inner) = folder_sync;
+    }
+
+    /// Records that the user settled a conflict, as [`LanSync::settle`]
+    /// does for paired devices.
+    ///
+    /// [`LanSync::settle`]: super::lan::LanSync::settle
+    pub fn settle(&self, id: &str, hashes: Hashes) {
+        let (inner, id) = (self.inner.clone(), id.to_string());
+        std::thread::spawn(move || {
+            let _session = inner.session.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut base = load_base(&inner.storage_path, &inner.peer_id);
+            base.insert(id, hashes);
+            if let Err(e) = save_base(&inner.storage_path, &inner.peer_id, &base) {
+                log::error!("Failed to save the sync state with the bucket: {}", e);
+            }
+        });
+    }
+
+    pub fn sync_now(&self) {
+        let _ = self.wake.send(());
+    }
+
+    pub fn try_event(&self) -> Option<LanEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+fn schedule(inner: Arc<Inner>, wakeups: mpsc::Receiver<()>, events: mpsc::Sender<LanEvent>) {
+    // The first sync runs right away, if there is anything to do.
+    let mut asked = false;
+    loop {
+        if let Err(e) = sync(&inner, asked, &events) {
+            log::warn!("S3 sync failed: {}", e);
+            if asked {
+                let _ = events.send(LanEvent::Failed(format!("Could not sync with the bucket: {}", e)));
+            }
+        }
+        asked = match wakeups.recv_timeout(SYNC_INTERVAL) {
+            Ok(()) => true,
+            Err(mpsc::RecvTimeoutError::Timeout) => false,
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        };
+    }
+}
+
+/// Catches up on the bucket's change records, then syncs as with a peer:
+/// fetches what changed there, uploads what changed here and records it.
+/// Nothing is asked of the app when neither side changed.
+#[tracing::instrument(name = "s3_sync", skip_all, fields(asked = asked))]
+fn sync(inner: &Inner, asked: bool, events: &mpsc::Sender<LanEvent>) -> Result<(), std::io::Error> {
+    let _session = inner.session.lock().unwrap_or_else(PoisonError::into_inner);
+    let store = Sealed { inner };
+    let mut state = inner.load_state();
+    if state.last_change.is_none() && state.remote.is_empty() {
+        start_from_snapshot(&store, &mut state)?;
+    }
+    let remote_changed = read_changes(&store, &mut state)?;
+    let started = rev_at(Utc::now());
+    let local_changed = inner.changes.since(state.local_rev).map_or(true, |changes| !changes.is_empty());
+    let snapshot_due = state.last_snapshot.is_none_or(|at| Utc::now() - at >= SNAPSHOT_INTERVAL);
+    if !(asked || remote_changed || local_changed || snapshot_due) {
+        return Ok(());
+    }
+
+    let (reply, snapshot) = mpsc::channel();
+    events.send(LanEvent::SnapshotNeeded(reply)).map_err(|_| closed())?;
+    let mut snapshot = snapshot.recv_timeout(APP_TIMEOUT).map_err(|_| std::io::Error::other("the library could not be read"))?;
+    let folder_sync = inner.folder_sync.lock().unwrap_or_else(PoisonError::into_inner).clone();
+    let syncs = |folder: &str| FolderSync::of(&folder_sync, folder).syncs_with_bucket();
+    snapshot.exclude(syncs);
+    let manifest = snapshot.manifest();
+    let base = load_base(&inner.storage_path, &inner.peer_id);
+    let plan = plan(&manifest, &state.remote, &base);
+
+    let mut received = Vec::new();
+    for id in plan.fetch.iter().chain(&plan.merge).chain(&plan.conflicts) {
+        if let Some(snippet) = store.get::<SyncedSnippet>(&inner.key("snippets", id))? {
+            received.push(snippet);
+        }
+    }
+    let Outcome { agreed, changes, conflicts } = outcome(plan, &base, &snapshot, received, syncs);
+
+    // Upload what the bucket doesn't have yet, then record it.
+    let mut uploaded = Vec::new();
+    for (id, hashes) in &agreed {
+        let known = matches!(state.remote.get(id), Some(ManifestEntry { state: EntryState::Live(remote), .. }) if remote.hash == hashes.hash);
+        if known {
+            continue;
+        }
+        // Conflicts keep the old agreed state, which neither side holds;
+        // they are left alone until the user settles them.
+        let snippet = changes.snippets.iter()
+            .chain(&snapshot.snippets)
+            .find(|snippet| snippet.id == *id && snippet.hash() == hashes.hash);
+        if let Some(snippet) = snippet {
+            store.put(&inner.key("snippets", id), snippet)?;
+            let entry = ManifestEntry { state: EntryState::Live(hashes.clone()), modified: snippet.modified };
+            uploaded.push(RemoteChange { id: id.clone(), entry });
+        }
+    }
+    for (id, entry) in &state.remote {
+        let deleted_here = matches!(entry.state, EntryState::Live(_))
+            && !agreed.contains_key(id)
+            && manifest.get(id).is_none_or(|entry| entry.state == EntryState::Deleted);
+        if deleted_here {
+            let modified = manifest.get(id).map_or_else(Utc::now, |entry| entry.modified);
+            uploaded.push(RemoteChange { id: id.clone(), entry: ManifestEntry { state: EntryState::Deleted, modified } });
+        }
+    }
+    if !uploaded.is_empty() {
+        let key = inner.key("changes", &format!("{:020}-{}", rev_at(Utc::now()), inner.device));
+        store.put(&key, &uploaded)?;
+        for change in uploaded {
+            state.remote.insert(change.id, change.entry);
+        }
+        state.seen.insert(key.clone());
+        state.last_change = state.last_change.max(Some(key));
+    }
+
+    if !changes.is_empty() {
+        let (done, saved) = mpsc::channel();
+        events.send(LanEvent::Received { peer: inner.bucket.name.clone(), changes, done }).map_err(|_| closed())?;
+        if !saved.recv_timeout(APP_TIMEOUT).unwrap_or(false) {
+            return Err(std::io::Error::other("the changes could not be saved"));
+        }
+    }
+    save_base(&inner.storage_path, &inner.peer_id, &agreed)?;
+    state.local_rev = started;
+    if snapshot_due {
+        write_snapshot(&store, &state, snapshot)?;
+        state.last_snapshot = Some(Utc::now());
+    }
+    inner.save_state(&state)?;
+
+    if !conflicts.is_empty() {
+        let conflicts = conflicts.into_iter()
+            .map(|theirs| super::Conflict { peer_id: inner.peer_id.clone(), peer_name: inner.bucket.name.clone(), theirs })
+            .collect();
+        events.send(LanEvent::Conflicts(conflicts)).map_err(|_| closed())?;
+    }
+    Ok(())
+}
+
+/// Reads the change records not seen yet into `state`. Returns whether
+/// there were any.
+fn read_changes(store: &Sealed, state: &mut BucketState) -> Result<bool, std::io::Error> {
+    let inner = store.inner;
+    let prefix = inner.key("changes", "");
+    let start_after = match &state.last_change {
+        Some(last) => format!("{}{:020}", prefix, revision(&prefix, last).saturating_sub(CLOCK_MARGIN)),
+        None => String::new(),
+    };
+    let keys: Vec<String> = inner.bucket.list(&prefix, &start_after)?
+        .into_iter()
+        .filter(|key| !state.seen.contains(key))
+        .collect();
+    for key in &keys {
+        let changes: Vec<RemoteChange> = store.get(key)?.unwrap_or_default();
+        for change in changes {
+            state.remote.insert(change.id, change.entry);
+        }
+        state.seen.insert(key.clone());
+        state.last_change = state.last_change.take().max(Some(key.clone()));
+    }
+    if let Some(last) = &state.last_change {
+        let oldest = revision(&prefix, last).saturating_sub(CLOCK_MARGIN);
+        state.seen.retain(|key| revision(&prefix, key) >= oldest);
+    }
+    Ok(!keys.is_empty())
+}
+
+/// The revision a change record is named by.
+fn revision(prefix: &str, key: &str) -> u64 {
+    key.strip_prefix(prefix)
+        .and_then(|name| name.split('-').next())
+        .and_then(|rev| rev.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Takes the bucket's manifest from its newest snapshot, so a new device
+/// doesn't read every change record ever written.
+fn start_from_snapshot(store: &Sealed, state: &mut BucketState) -> Result<(), std::io::Error> {
+    let inner = store.inner;
+    let Some(latest) = inner.bucket.list(&inner.key("snapshots", ""), "")?.pop() else {
+        return Ok(());
+    };
+    if let Some(snapshot) = store.get::<BucketSnapshot>(&latest)? {
+        state.remote = snapshot.manifest;
+        state.last_change = snapshot.last_change;
+    }
+    Ok(())
+}
+
+/// Uploads the library as a snapshot and removes all but the newest
+/// [`SNAPSHOTS_KEPT`].
+fn write_snapshot(store: &Sealed, state: &BucketState, snapshot: Snapshot) -> Result<(), std::io::Error> {
+    let inner = store.inner;
+    let name = format!("{}-{}", Utc::now().format("%Y%m%dT%H%M%SZ"), inner.device);
+    let backup = BucketSnapshot {
+        last_change: state.last_change.clone(),
+        manifest: state.remote.clone(),
+        snippets: snapshot.snippets,
+    };
+    store.put(&inner.key("snapshots", &name), &backup)?;
+    let snapshots = inner.bucket.list(&inner.key("snapshots", ""), "")?;
+    for old in snapshots.iter().rev().skip(SNAPSHOTS_KEPT) {
+        inner.bucket.delete(old)?;
+    }
+    Ok(())
+}
+
+fn invalid(error: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, error)
+}
@@ -0,0 +1,83 @@
+//! The sync settings kept in the app config: paired devices, the S3 bucket
+//! and which folders sync with each. Always built, so a config written by a
+//! build with sync keeps these when read by one without.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "sync")]
+use std::collections::BTreeMap;
+
+/// A device this one syncs with. Only a device holding the key it was
+/// paired with is let in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncPeer {
+    pub id: String,
+    pub name: String,
+    /// X25519 public key, hex-encoded.
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct S3Target {
+    /// `https://host[:port]` of the service, e.g. `https://s3.eu-west-1.amazonaws.com`.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    /// Folder within the bucket everything is kept under.
+    pub prefix: String,
+}
+
+impl Default for S3Target {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            region: "us-east-1".to_string(),
+            bucket: String::new(),
+            prefix: "trinket".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FolderSync {
+    /// Off keeps the folder on this device.
+    pub enabled: bool,
+    /// Ids of the paired devices the folder syncs with; empty means all.
+    pub peers: Vec<String>,
+    /// Whether the folder syncs through the S3 bucket. Unset, it does only
+    /// when it syncs with every device, so a folder kept to some stays out.
+    pub bucket: Option<bool>,
+}
+
+impl Default for FolderSync {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            peers: Vec::new(),
+            bucket: None,
+        }
+    }
+}
+
+#[cfg(feature = "sync")]
+impl FolderSync {
+    /// The rule for `folder` among `rules`: its own or that of the nearest
+    /// folder above it.
+    pub fn of(rules: &BTreeMap<String, FolderSync>, folder: &str) -> FolderSync {
+        std::iter::successors(Some(folder), |folder| {
+            (!folder.is_empty()).then(|| folder.rsplit_once('/').map_or("", |(parent, _)| parent))
+        })
+            .find_map(|folder| rules.get(folder))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn syncs_with(&self, peer_id: &str) -> bool {
+        self.enabled && (self.peers.is_empty() || self.peers.iter().any(|peer| peer == peer_id))
+    }
+
+    pub fn syncs_with_bucket(&self) -> bool {
+        self.enabled && self.bucket.unwrap_or(self.peers.is_empty())
+    }
+}
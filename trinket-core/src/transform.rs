@@ -0,0 +1,119 @@
+use base64::Engine;
+use std::fmt;
+
+/// A single text rewrite applied to snippet content on its way to the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    Uppercase,
+    Lowercase,
+    Trim,
+    JsonPretty,
+    JsonMinify,
+    Base64Encode,
+    Base64Decode,
+    UrlEncode,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Uppercase,
+        Transform::Lowercase,
+        Transform::Trim,
+        Transform::JsonPretty,
+        Transform::JsonMinify,
+        Transform::Base64Encode,
+        Transform::Base64Decode,
+        Transform::UrlEncode,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Transform::Uppercase => "UPPERCASE",
+            Transform::Lowercase => "lowercase",
+            Transform::Trim => "Trim whitespace",
+            Transform::JsonPretty => "JSON pretty-print",
+            Transform::JsonMinify => "JSON minify",
+            Transform::Base64Encode => "Base64 encode",
+            Transform::Base64Decode => "Base64 decode",
+            Transform::UrlEncode => "URL encode",
+        }
+    }
+
+    pub fn apply(self, text: &str) -> Result<String, String> {
+        match self {
+            Transform::Uppercase => Ok(text.to_uppercase()),
+            Transform::Lowercase => Ok(text.to_lowercase()),
+            Transform::Trim => Ok(text.trim().to_string()),
+            Transform::JsonPretty => {
+                let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+                serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+            }
+            Transform::JsonMinify => {
+                let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+                serde_json::to_string(&value).map_err(|e| e.to_string())
+            }
+            Transform::Base64Encode => Ok(base64::engine::general_purpose::STANDARD.encode(text)),
+            Transform::Base64Decode => {
+                // Tolerate the line breaks that wrapped base64 usually carries.
+                let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(compact)
+                    .map_err(|e| e.to_string())?;
+                String::from_utf8(bytes).map_err(|_| "decoded data is not text".to_string())
+            }
+            Transform::UrlEncode => Ok(urlencoding::encode(text).into_owned()),
+        }
+    }
+}
+
+/// Transforms applied left to right, e.g. trim then base64-encode.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline {
+    steps: Vec<Transform>,
+}
+
+impl Pipeline {
+    pub fn push(&mut self, step: Transform) {
+        self.steps.push(step);
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn steps(&self) -> &[Transform] {
+        &self.steps
+    }
+
+    pub fn apply(&self, text: &str) -> Result<String, TransformError> {
+        let mut current = text.to_string();
+        for &step in &self.steps {
+            current = step.apply(&current).map_err(|message| TransformError { step, message })?;
+        }
+        Ok(current)
+    }
+}
+
+impl From<Transform> for Pipeline {
+    fn from(step: Transform) -> Self {
+        Self { steps: vec![step] }
+    }
+}
+
+#[derive(Debug)]
+pub struct TransformError {
+    pub step: Transform,
+    pub message: String,
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed: {}", self.step.label(), self.message)
+    }
+}
+
+impl std::error::Error for TransformError {}
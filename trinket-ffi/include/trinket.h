@@ -0,0 +1,47 @@
+/*
+ * C interface to a trinket snippet library. See trinket-ffi/src/lib.rs for
+ * the details of each call.
+ *
+ * Strings are UTF-8 and NUL-terminated. Strings returned are the caller's
+ * to release with trinket_string_free(); NULL means the call failed and
+ * trinket_last_error() says why.
+ */
+#ifndef TRINKET_H
+#define TRINKET_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define TRINKET_ABI_VERSION 1
+
+typedef struct Trinket Trinket;
+
+/* The version of this interface the library was built with. */
+uint32_t trinket_abi_version(void);
+
+/* Opens the library in path, or trinket's own when path is NULL, with the
+ * settings in trinket's config. No keys are loaded. */
+Trinket *trinket_open(const char *path);
+void trinket_close(Trinket *trinket);
+
+/* One "id\ttitle\n" line per match, best first; limit 0 returns all. */
+char *trinket_search(const Trinket *trinket, const char *query, size_t limit);
+/* The content of a snippet; NULL for unknown ids and secret snippets. */
+char *trinket_get_content(const Trinket *trinket, const char *id);
+/* Saves a new snippet in folder (NULL for the top level); returns its id.
+ * NULL where trinket's config has the content encrypted. */
+char *trinket_add(const Trinket *trinket, const char *content, const char *folder);
+
+void trinket_string_free(char *s);
+/* Owned by the library; valid until the next failing call on the thread. */
+const char *trinket_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TRINKET_H */
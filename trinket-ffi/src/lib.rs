@@ -0,0 +1,340 @@
+//! A C interface to a trinket library, for tools that aren't written in
+//! Rust: AutoHotkey scripts, editor plugins and the like. It opens the
+//! snippet folder directly, next to a running trinket or without one, and
+//! offers search, reading a snippet by id and adding one. The declarations
+//! are in `include/trinket.h`.
+//!
+//! Strings are UTF-8 and NUL-terminated both ways. Strings returned are the
+//! caller's to release with [`trinket_string_free`]; a NULL return means
+//! the call failed, and [`trinket_last_error`] says why. Snippets added
+//! here show up in a running trinket the next time it loads the library.
+//! The library is opened with the settings in trinket's config, but no key
+//! is ever loaded: snippets encrypted at rest can't be read, and adding
+//! fails where trinket would encrypt rather than writing plain text.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::path::PathBuf;
+use std::ptr;
+
+use trinket_core::library_settings::LibrarySettings;
+use trinket_core::storage::search_provider::{self, SearchProviderKind};
+use trinket_core::storage::{FileStorage, SearchQuery, Snippet, SnippetMeta};
+
+/// Bumped whenever a function is removed or changes its signature or
+/// meaning; adding functions leaves it alone.
+pub const TRINKET_ABI_VERSION: u32 = 1;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An open library. Opaque to C; each call re-reads the folder, so edits
+/// made by trinket or another handle are seen right away.
+pub struct Trinket {
+    storage: FileStorage,
+}
+
+fn set_error(message: impl Into<String>) {
+    let message = message.into();
+    log::error!("{}", message);
+    // A message with a NUL in it loses everything from there.
+    let bytes: Vec<u8> = message.into_bytes().into_iter().take_while(|&b| b != 0).collect();
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(bytes).ok());
+}
+
+/// The string at `ptr`, or an error recorded for `what` when it's NULL or
+/// not UTF-8.
+///
+/// # Safety
+/// `ptr` must be NULL or point to a NUL-terminated string.
+unsafe fn str_arg<'a>(ptr: *const c_char, what: &str) -> Option<&'a str> {
+    if ptr.is_null() {
+        set_error(format!("{} is NULL", what));
+        return None;
+    }
+    match CStr::from_ptr(ptr).to_str() {
+        Ok(s) => Some(s),
+        Err(_) => {
+            set_error(format!("{} is not UTF-8", what));
+            None
+        }
+    }
+}
+
+/// Hands `s` to C, or records an error when it holds a NUL.
+fn into_c_string(s: String) -> *mut c_char {
+    match CString::new(s) {
+        Ok(s) => s.into_raw(),
+        Err(_) => {
+            set_error("The text holds a NUL character and can't be returned");
+            ptr::null_mut()
+        }
+    }
+}
+
+fn load(trinket: &Trinket) -> Option<Vec<Snippet>> {
+    match trinket.storage.load_all_snippets() {
+        Ok(snippets) => Some(snippets),
+        Err(e) => {
+            set_error(format!("Could not read the library: {}", e));
+            None
+        }
+    }
+}
+
+/// The version of this interface; see [`TRINKET_ABI_VERSION`].
+#[no_mangle]
+pub extern "C" fn trinket_abi_version() -> u32 {
+    TRINKET_ABI_VERSION
+}
+
+/// Opens the library in `path`, or trinket's own when `path` is NULL, with
+/// the vault, encrypted folders, size limit and migrations set in trinket's
+/// config. Returns NULL on failure. A folder that can't be written opens
+/// read-only, so searching and reading still work but adding fails.
+///
+/// # Safety
+/// `path` must be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn trinket_open(path: *const c_char) -> *mut Trinket {
+    let path = if path.is_null() {
+        trinket_core::data_dir().join("snippets")
+    } else {
+        match str_arg(path, "path") {
+            Some(path) => PathBuf::from(path),
+            None => return ptr::null_mut(),
+        }
+    };
+    let settings = match LibrarySettings::load(&trinket_core::config_path()) {
+        Ok(settings) => settings,
+        Err(e) => {
+            set_error(format!("Could not read trinket's config: {}", e));
+            return ptr::null_mut();
+        }
+    };
+    match open_storage(path, &settings) {
+        Some(storage) => Box::into_raw(Box::new(Trinket { storage })),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Opens the library in `path` as trinket does with `settings`, less the
+/// keys: writes that need one are refused.
+fn open_storage(path: PathBuf, settings: &LibrarySettings) -> Option<FileStorage> {
+    let mut storage = match FileStorage::new(path.clone()) {
+        Ok(storage) => storage,
+        Err(e) if path.is_dir() => {
+            log::warn!("Opening {} read-only: {}", path.display(), e);
+            FileStorage::read_only(path.clone())
+        }
+        Err(e) => {
+            set_error(format!("Could not open {}: {}", path.display(), e));
+            return None;
+        }
+    };
+    if settings.has_vault() {
+        storage = storage.require_vault();
+    }
+    for (folder, _) in settings.folder_vaults() {
+        storage.add_encrypted_folder(folder);
+    }
+    storage.set_max_snippet_bytes(settings.max_snippet_bytes);
+    if let Err(e) = storage.migrate(&settings.backup_dir()) {
+        log::error!("Failed to migrate {}: {}", path.display(), e);
+    }
+    Some(storage)
+}
+
+/// Closes a library opened with [`trinket_open`]. NULL is ignored.
+///
+/// # Safety
+/// `trinket` must be NULL or a handle from [`trinket_open`] not yet closed.
+#[no_mangle]
+pub unsafe extern "C" fn trinket_close(trinket: *mut Trinket) {
+    if !trinket.is_null() {
+        drop(Box::from_raw(trinket));
+    }
+}
+
+/// Searches the library with the same syntax as trinket's search box,
+/// including the `lang:`, `tag:`, `from:`, `after:`, `before:` and
+/// `is:archived` filters. Returns one line per match, best
+/// first and at most `limit` of them (0 for all): the id, a tab, then the
+/// title. Secret snippets are listed with a masked title.
+///
+/// # Safety
+/// `trinket` must be a handle from [`trinket_open`] and `query` a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn trinket_search(trinket: *const Trinket, query: *const c_char, limit: usize) -> *mut c_char {
+    let Some(trinket) = trinket.as_ref() else {
+        set_error("trinket is NULL");
+        return ptr::null_mut();
+    };
+    let Some(query) = str_arg(query, "query") else {
+        return ptr::null_mut();
+    };
+    let Some(snippets) = load(trinket) else {
+        return ptr::null_mut();
+    };
+
+    let search = search_provider::build(&[SearchProviderKind::Substring]);
+    let hits = search.query(&SearchQuery::parse(query), &snippets);
+    let limit = if limit == 0 { hits.len() } else { limit };
+    let lines: String = hits.into_iter()
+        .take(limit)
+        .map(|idx| {
+            let snippet = &snippets[idx];
+            format!("{}\t{}\n", snippet.id, snippet.safe_title().replace(['\t', '\n'], " "))
+        })
+        .collect();
+    into_c_string(lines)
+}
+
+/// The content of the snippet with `id`. Fails for ids that aren't in the
+/// library and for secret snippets, which only trinket itself hands out.
+///
+/// # Safety
+/// `trinket` must be a handle from [`trinket_open`] and `id` a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn trinket_get_content(trinket: *const Trinket, id: *const c_char) -> *mut c_char {
+    let Some(trinket) = trinket.as_ref() else {
+        set_error("trinket is NULL");
+        return ptr::null_mut();
+    };
+    let Some(id) = str_arg(id, "id") else {
+        return ptr::null_mut();
+    };
+    let Some(snippets) = load(trinket) else {
+        return ptr::null_mut();
+    };
+    match snippets.iter().find(|snippet| snippet.id == id) {
+        Some(snippet) if snippet.meta.secret => {
+            set_error(format!("Snippet {} is secret", id));
+            ptr::null_mut()
+        }
+        Some(snippet) => into_c_string(snippet.content.clone()),
+        None => {
+            set_error(format!("No snippet with id {}", id));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Saves `content` as a new snippet in `folder` (NULL or "" for the top
+/// level), returning its id. Fails when trinket's config has the library
+/// or the folder encrypted, since the key isn't loaded here, and for
+/// content over the configured size limit.
+///
+/// # Safety
+/// `trinket` must be a handle from [`trinket_open`], `content` a
+/// NUL-terminated string and `folder` NULL or one.
+#[no_mangle]
+pub unsafe extern "C" fn trinket_add(trinket: *const Trinket, content: *const c_char, folder: *const c_char) -> *mut c_char {
+    let Some(trinket) = trinket.as_ref() else {
+        set_error("trinket is NULL");
+        return ptr::null_mut();
+    };
+    let Some(content) = str_arg(content, "content") else {
+        return ptr::null_mut();
+    };
+    let folder = if folder.is_null() {
+        ""
+    } else {
+        match str_arg(folder, "folder") {
+            Some(folder) => folder,
+            None => return ptr::null_mut(),
+        }
+    };
+    match trinket.storage.save_snippet(content, folder, SnippetMeta::default()) {
+        Ok(snippet) => into_c_string(snippet.id.clone()),
+        Err(e) => {
+            set_error(format!("Could not save the snippet: {}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a string returned by this library. NULL is ignored.
+///
+/// # Safety
+/// `s` must be NULL or a string returned by this library not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn trinket_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Why the last call on this thread failed, or NULL if none has. Owned by
+/// the library and valid until the next failing call on the thread.
+#[no_mangle]
+pub extern "C" fn trinket_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use trinket_core::library_settings::FolderVault;
+
+    fn take(s: *mut c_char) -> String {
+        assert!(!s.is_null());
+        let text = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+        unsafe { trinket_string_free(s) };
+        text
+    }
+
+    #[test]
+    fn add_then_search_and_read() {
+        let dir = TempDir::new().unwrap();
+        let path = CString::new(dir.path().to_str().unwrap()).unwrap();
+        unsafe {
+            let trinket = trinket_open(path.as_ptr());
+            assert!(!trinket.is_null());
+
+            let content = CString::new("docker compose up\n-d").unwrap();
+            let id = take(trinket_add(trinket, content.as_ptr(), ptr::null()));
+
+            let query = CString::new("compose").unwrap();
+            let found = take(trinket_search(trinket, query.as_ptr(), 0));
+            assert_eq!(found, format!("{}\tdocker compose up\n", id));
+
+            let id = CString::new(id).unwrap();
+            assert_eq!(take(trinket_get_content(trinket, id.as_ptr())), "docker compose up\n-d");
+
+            let missing = CString::new("missing").unwrap();
+            assert!(trinket_get_content(trinket, missing.as_ptr()).is_null());
+            assert!(!trinket_last_error().is_null());
+
+            trinket_close(trinket);
+        }
+    }
+
+    #[test]
+    fn adding_where_a_key_is_needed_fails() {
+        let dir = TempDir::new().unwrap();
+        let content = CString::new("hunter2").unwrap();
+        let private = CString::new("private/work").unwrap();
+
+        let mut settings = LibrarySettings::default();
+        settings.folder_vaults.insert("private".to_string(), FolderVault::default());
+        let trinket = Trinket { storage: open_storage(dir.path().to_path_buf(), &settings).unwrap() };
+        unsafe {
+            assert!(trinket_add(&trinket, content.as_ptr(), private.as_ptr()).is_null());
+            take(trinket_add(&trinket, content.as_ptr(), ptr::null()));
+        }
+
+        settings.vault_identity_file = Some(dir.path().join("identity.txt"));
+        let trinket = Trinket { storage: open_storage(dir.path().to_path_buf(), &settings).unwrap() };
+        unsafe {
+            assert!(trinket_add(&trinket, content.as_ptr(), ptr::null()).is_null());
+        }
+        let written = trinket.storage.load_all_snippets().unwrap();
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0].folder, "");
+    }
+}